@@ -0,0 +1,21 @@
+-- KetoBook: Savings interest on BankAccount wallets (2026-02-01)
+
+-- STEP 1: APY configuration and last posting marker on wallets
+ALTER TABLE wallets
+    ADD COLUMN IF NOT EXISTS apy DECIMAL(7, 4),
+    ADD COLUMN IF NOT EXISTS last_interest_posted_at TIMESTAMP WITH TIME ZONE;
+
+DO $$ BEGIN
+    ALTER TABLE wallets
+        ADD CONSTRAINT apy_non_negative CHECK (apy IS NULL OR apy >= 0);
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+COMMENT ON COLUMN wallets.apy IS 'Annual percentage yield (e.g. 4.5 = 4.5%). Only used for BankAccount wallets.';
+COMMENT ON COLUMN wallets.last_interest_posted_at IS 'When the monthly interest job last posted interest for this wallet';
+
+-- STEP 2: Index for the monthly interest job scan
+CREATE INDEX IF NOT EXISTS idx_wallets_interest_bearing
+    ON wallets(last_interest_posted_at)
+    WHERE apy IS NOT NULL AND apy > 0;
@@ -0,0 +1,30 @@
+-- KetoBook: Debt creditor contact info and attachments (2026-02-05)
+
+-- STEP 1: Structured creditor contact fields on debts
+ALTER TABLE debts
+    ADD COLUMN IF NOT EXISTS creditor_phone VARCHAR(50),
+    ADD COLUMN IF NOT EXISTS creditor_email VARCHAR(255),
+    ADD COLUMN IF NOT EXISTS creditor_address TEXT;
+
+COMMENT ON COLUMN debts.creditor_phone IS 'Creditor contact phone number';
+COMMENT ON COLUMN debts.creditor_email IS 'Creditor contact email address';
+COMMENT ON COLUMN debts.creditor_address IS 'Creditor postal address';
+
+-- STEP 2: Attachments metadata (blobs live in the configured storage backend)
+CREATE TABLE IF NOT EXISTS attachments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    entity_type VARCHAR(20) NOT NULL,
+    entity_id UUID NOT NULL,
+    file_name VARCHAR(255) NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    storage_key VARCHAR(500) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT valid_attachment_entity_type CHECK (entity_type IN ('debt')),
+    CONSTRAINT size_bytes_positive CHECK (size_bytes > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_attachments_entity ON attachments(entity_type, entity_id);
+CREATE INDEX IF NOT EXISTS idx_attachments_user_id ON attachments(user_id);
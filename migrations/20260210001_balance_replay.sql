@@ -0,0 +1,37 @@
+-- KetoBook: Balance replay from transaction history (2026-02-10)
+
+-- STEP 1: Opening balance anchors replay (balance = opening_balance + net transactions)
+ALTER TABLE wallets
+    ADD COLUMN IF NOT EXISTS opening_balance DECIMAL(15, 2);
+
+-- Backfill: treat the current balance as correct for existing wallets
+UPDATE wallets w
+SET opening_balance = w.balance - COALESCE((
+    SELECT SUM(CASE WHEN t.transaction_type = 'income' THEN t.amount ELSE -t.amount END)
+    FROM transactions t
+    WHERE t.wallet_id = w.id
+), 0)
+WHERE opening_balance IS NULL;
+
+ALTER TABLE wallets ALTER COLUMN opening_balance SET DEFAULT 0.00;
+ALTER TABLE wallets ALTER COLUMN opening_balance SET NOT NULL;
+
+COMMENT ON COLUMN wallets.opening_balance IS 'Balance before any recorded transaction; shifted by direct balance edits';
+
+-- STEP 2: Shadow table holding replay results per run
+CREATE TABLE IF NOT EXISTS wallet_balance_replay (
+    run_id UUID NOT NULL,
+    wallet_id UUID NOT NULL,
+    user_id VARCHAR(100) NOT NULL,
+    live_balance DECIMAL(15, 2) NOT NULL,
+    replayed_balance DECIMAL(15, 2) NOT NULL,
+    transaction_count BIGINT NOT NULL,
+    total_income DECIMAL(15, 2) NOT NULL,
+    total_expense DECIMAL(15, 2) NOT NULL,
+    applied BOOLEAN NOT NULL DEFAULT FALSE,
+    replayed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (run_id, wallet_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_balance_replay_user_id ON wallet_balance_replay(user_id, replayed_at);
@@ -0,0 +1,13 @@
+-- KetoBook: Long-form Markdown notes on transactions (2026-02-15)
+
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS notes TEXT;
+
+DO $$ BEGIN
+    ALTER TABLE transactions
+        ADD CONSTRAINT notes_max_size CHECK (notes IS NULL OR octet_length(notes) <= 65536);
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+COMMENT ON COLUMN transactions.notes IS 'Optional Markdown notes (warranty details, split context), max 64KB';
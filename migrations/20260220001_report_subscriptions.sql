@@ -0,0 +1,44 @@
+-- KetoBook: Scheduled report email subscriptions (2026-02-20)
+
+-- STEP 1: Subscriptions (one report type/format/schedule per row)
+CREATE TABLE IF NOT EXISTS report_subscriptions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    report_type VARCHAR(30) NOT NULL,
+    format VARCHAR(10) NOT NULL DEFAULT 'csv',
+    frequency VARCHAR(10) NOT NULL,
+    email VARCHAR(255) NOT NULL,
+    wallet_id UUID REFERENCES wallets(id) ON DELETE CASCADE,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    next_run_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    last_sent_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT valid_report_type CHECK (report_type IN ('transactions', 'category_summary')),
+    CONSTRAINT valid_report_format CHECK (format IN ('csv', 'pdf')),
+    CONSTRAINT valid_report_frequency CHECK (frequency IN ('weekly', 'monthly'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_report_subscriptions_user_id ON report_subscriptions(user_id);
+CREATE INDEX IF NOT EXISTS idx_report_subscriptions_due ON report_subscriptions(next_run_at) WHERE active;
+
+COMMENT ON COLUMN report_subscriptions.wallet_id IS 'Optional wallet filter; NULL reports across all wallets';
+COMMENT ON COLUMN report_subscriptions.next_run_at IS 'Next scheduled delivery; advanced by the report job when claimed';
+
+-- STEP 2: Delivery history (one row per attempted send)
+CREATE TABLE IF NOT EXISTS report_deliveries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    subscription_id UUID NOT NULL REFERENCES report_subscriptions(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    status VARCHAR(10) NOT NULL,
+    period_start TIMESTAMP WITH TIME ZONE NOT NULL,
+    period_end TIMESTAMP WITH TIME ZONE NOT NULL,
+    row_count INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT valid_delivery_status CHECK (status IN ('sent', 'failed'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_report_deliveries_subscription ON report_deliveries(subscription_id, created_at DESC);
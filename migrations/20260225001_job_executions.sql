@@ -0,0 +1,16 @@
+-- KetoBook: Exactly-once background job execution records (2026-02-25)
+
+-- One row per executed job occurrence (e.g. interest for a wallet/month).
+-- Jobs insert the record in the same DB transaction as their side effects,
+-- so an occurrence commits at most once across all app instances.
+CREATE TABLE IF NOT EXISTS job_executions (
+    job_name VARCHAR(100) NOT NULL,
+    occurrence_key VARCHAR(255) NOT NULL,
+    executed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (job_name, occurrence_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_executions_executed_at ON job_executions(executed_at);
+
+COMMENT ON TABLE job_executions IS 'Idempotency records for background job occurrences';
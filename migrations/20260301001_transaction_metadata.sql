@@ -0,0 +1,24 @@
+-- KetoBook: User-defined transaction fields (2026-03-01)
+
+-- STEP 1: Custom field values on transactions ({"project": "acme", "reimbursable": true})
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS metadata JSONB NOT NULL DEFAULT '{}'::jsonb;
+
+CREATE INDEX IF NOT EXISTS idx_transactions_metadata ON transactions USING GIN (metadata);
+
+COMMENT ON COLUMN transactions.metadata IS 'Custom field values keyed by transaction_field_definitions.key';
+
+-- STEP 2: Per-user field definitions (metadata keys and their value types)
+CREATE TABLE IF NOT EXISTS transaction_field_definitions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    key VARCHAR(50) NOT NULL,
+    field_type VARCHAR(10) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT unique_field_key_per_user UNIQUE (user_id, key),
+    CONSTRAINT valid_field_type CHECK (field_type IN ('text', 'number', 'boolean')),
+    CONSTRAINT valid_field_key CHECK (key ~ '^[a-z][a-z0-9_]*$')
+);
+
+CREATE INDEX IF NOT EXISTS idx_transaction_field_definitions_user_id ON transaction_field_definitions(user_id);
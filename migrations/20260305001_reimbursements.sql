@@ -0,0 +1,25 @@
+-- KetoBook: Reimbursement tracking (2026-03-05)
+
+-- One row per reimbursable expense. Lifecycle: pending -> submitted -> reimbursed.
+CREATE TABLE IF NOT EXISTS reimbursements (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    payer VARCHAR(255) NOT NULL,
+    amount DECIMAL(15, 2) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    submitted_at TIMESTAMP WITH TIME ZONE,
+    reimbursed_at TIMESTAMP WITH TIME ZONE,
+    settled_by_transaction_id UUID REFERENCES transactions(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT unique_reimbursement_per_transaction UNIQUE (transaction_id),
+    CONSTRAINT valid_reimbursement_status CHECK (status IN ('pending', 'submitted', 'reimbursed')),
+    CONSTRAINT reimbursement_amount_positive CHECK (amount > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_reimbursements_user_status ON reimbursements(user_id, status);
+
+COMMENT ON COLUMN reimbursements.payer IS 'Who owes the reimbursement (employer, client, friend)';
+COMMENT ON COLUMN reimbursements.settled_by_transaction_id IS 'Income transaction that settled this reimbursement';
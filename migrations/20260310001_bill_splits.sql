@@ -0,0 +1,54 @@
+-- KetoBook: Bill splitting and debt settlements (2026-03-10)
+
+-- STEP 1: Debts can now be owed to the user (receivables), not only by them
+ALTER TABLE debts
+    ADD COLUMN IF NOT EXISTS direction VARCHAR(10) NOT NULL DEFAULT 'payable',
+    ADD COLUMN IF NOT EXISTS split_id UUID;
+
+DO $$ BEGIN
+    ALTER TABLE debts
+        ADD CONSTRAINT valid_debt_direction CHECK (direction IN ('payable', 'receivable'));
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+COMMENT ON COLUMN debts.direction IS 'payable: user owes creditor_name; receivable: creditor_name owes user';
+COMMENT ON COLUMN debts.split_id IS 'Bill split that generated this receivable';
+
+-- STEP 2: Bill splits (one per expense transaction; shares are receivable debts)
+CREATE TABLE IF NOT EXISTS bill_splits (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    total_amount DECIMAL(15, 2) NOT NULL,
+    user_share DECIMAL(15, 2) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT unique_split_per_transaction UNIQUE (transaction_id),
+    CONSTRAINT split_user_share_non_negative CHECK (user_share >= 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_bill_splits_user_id ON bill_splits(user_id);
+
+DO $$ BEGIN
+    ALTER TABLE debts
+        ADD CONSTRAINT fk_debts_split_id FOREIGN KEY (split_id) REFERENCES bill_splits(id) ON DELETE CASCADE;
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+CREATE INDEX IF NOT EXISTS idx_debts_split_id ON debts(split_id);
+
+-- STEP 3: Partial/full settlements recorded against a debt
+CREATE TABLE IF NOT EXISTS debt_settlements (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    debt_id UUID NOT NULL REFERENCES debts(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    amount DECIMAL(15, 2) NOT NULL,
+    note VARCHAR(500),
+    settled_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT settlement_amount_positive CHECK (amount > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_debt_settlements_debt_id ON debt_settlements(debt_id);
@@ -0,0 +1,39 @@
+-- KetoBook: Goal-based wallet buckets (2026-03-15)
+
+-- STEP 1: Buckets partition a wallet's balance; their total never exceeds it
+CREATE TABLE IF NOT EXISTS wallet_buckets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    balance DECIMAL(15, 2) NOT NULL DEFAULT 0.00,
+    target_amount DECIMAL(15, 2),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT unique_bucket_name_per_wallet UNIQUE (wallet_id, name),
+    CONSTRAINT bucket_balance_non_negative CHECK (balance >= 0),
+    CONSTRAINT bucket_target_positive CHECK (target_amount IS NULL OR target_amount > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_buckets_wallet_id ON wallet_buckets(wallet_id);
+
+COMMENT ON TABLE wallet_buckets IS 'Virtual sub-balances of a wallet (SUM(balance) <= wallets.balance, enforced by the API)';
+
+-- STEP 2: Expenses may draw from a bucket
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS bucket_id UUID;
+
+DO $$ BEGIN
+    ALTER TABLE transactions
+        ADD CONSTRAINT fk_transactions_bucket_id FOREIGN KEY (bucket_id) REFERENCES wallet_buckets(id) ON DELETE SET NULL;
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+CREATE INDEX IF NOT EXISTS idx_transactions_bucket_id ON transactions(bucket_id);
+
+-- STEP 3: Bucket balances as a scheduled report type
+ALTER TABLE report_subscriptions DROP CONSTRAINT IF EXISTS valid_report_type;
+ALTER TABLE report_subscriptions
+    ADD CONSTRAINT valid_report_type CHECK (report_type IN ('transactions', 'category_summary', 'bucket_balances'));
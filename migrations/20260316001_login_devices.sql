@@ -0,0 +1,28 @@
+-- KetoBook: Login devices (2026-03-16)
+--
+-- Every successful login is recorded against the device it came from (its
+-- user agent), so users can see where their account is signed in, revoke a
+-- device, and be alerted when a device they haven't used before logs in (see
+-- login_devices.rs).
+
+-- STEP 1: One row per user and device; revoking a device retires its row
+CREATE TABLE IF NOT EXISTS login_devices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    user_agent VARCHAR(500) NOT NULL,
+    first_ip VARCHAR(64) NOT NULL,
+    last_ip VARCHAR(64) NOT NULL,
+    login_count INTEGER NOT NULL DEFAULT 1,
+    new_device_alert BOOLEAN NOT NULL DEFAULT FALSE,
+    first_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    revoked_at TIMESTAMP WITH TIME ZONE
+);
+
+-- STEP 2: A device is matched by user agent among the active ones
+CREATE UNIQUE INDEX IF NOT EXISTS idx_login_devices_active
+    ON login_devices(user_id, user_agent) WHERE revoked_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_login_devices_user ON login_devices(user_id, last_seen_at DESC);
+
+COMMENT ON TABLE login_devices IS 'Devices an account has logged in from';
+COMMENT ON COLUMN login_devices.new_device_alert IS 'First login from this device after the account had others; raised as a new device alert';
@@ -0,0 +1,16 @@
+-- KetoBook: Budget alert settings (2026-03-17)
+--
+-- Each user picks the percentages of a budget at which they want to be
+-- alerted; users without a row get the defaults (80% and 100%). See
+-- budget_alerts.rs.
+
+-- STEP 1: One row per user
+CREATE TABLE IF NOT EXISTS budget_alert_settings (
+    user_id VARCHAR(100) PRIMARY KEY,
+    thresholds SMALLINT[] NOT NULL DEFAULT '{80,100}',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+COMMENT ON TABLE budget_alert_settings IS 'Per-user budget alert thresholds';
+COMMENT ON COLUMN budget_alert_settings.thresholds IS 'Percentages of a budget (ascending) at which an alert is raised';
@@ -0,0 +1,25 @@
+-- KetoBook: Sandbox wallets (2026-03-18)
+--
+-- Integrators test the API against a sandbox wallet. Every transaction on a
+-- sandbox wallet is tagged `sandbox` here, whatever wrote it, and reports
+-- leave sandbox wallets and their transactions out (see reports.rs).
+
+-- STEP 1: The flags
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS sandbox BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS sandbox BOOLEAN NOT NULL DEFAULT FALSE;
+
+COMMENT ON COLUMN wallets.sandbox IS 'Test wallet; left out of reports and totals';
+COMMENT ON COLUMN transactions.sandbox IS 'On a sandbox wallet; set by trg_transactions_sandbox';
+
+-- STEP 2: A transaction takes its wallet's flag
+CREATE OR REPLACE FUNCTION set_transaction_sandbox() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.sandbox := COALESCE((SELECT sandbox FROM wallets WHERE id = NEW.wallet_id), FALSE);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_transactions_sandbox ON transactions;
+CREATE TRIGGER trg_transactions_sandbox
+    BEFORE INSERT OR UPDATE OF wallet_id ON transactions
+    FOR EACH ROW EXECUTE FUNCTION set_transaction_sandbox();
@@ -0,0 +1,41 @@
+-- KetoBook: Inbound provider webhooks (2026-03-19)
+--
+-- Payment and bank providers (Stripe, Plaid, MoMo) post events to
+-- `/api/provider-webhooks/{provider}`. Deliveries that pass the signature,
+-- timestamp and replay checks are queued here for the provider's
+-- integration; rejected ones are kept as dead letters for debugging (see
+-- provider_webhooks.rs).
+
+-- STEP 1: Verified deliveries, oldest unprocessed first
+CREATE TABLE IF NOT EXISTS provider_webhook_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    provider VARCHAR(20) NOT NULL,
+    nonce VARCHAR(200) NOT NULL,
+    payload TEXT NOT NULL,
+    received_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    processed_at TIMESTAMP WITH TIME ZONE,
+    UNIQUE (provider, nonce)
+);
+
+CREATE INDEX IF NOT EXISTS idx_provider_webhook_events_pending
+    ON provider_webhook_events(provider, received_at) WHERE processed_at IS NULL;
+
+-- STEP 2: Rejected deliveries, as received
+CREATE TABLE IF NOT EXISTS provider_webhook_dead_letters (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    provider VARCHAR(20) NOT NULL,
+    reason VARCHAR(50) NOT NULL,
+    remote_addr VARCHAR(64),
+    headers JSONB NOT NULL DEFAULT '{}'::jsonb,
+    body TEXT NOT NULL,
+    body_truncated BOOLEAN NOT NULL DEFAULT FALSE,
+    received_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_provider_webhook_dead_letters_received
+    ON provider_webhook_dead_letters(received_at DESC);
+
+COMMENT ON TABLE provider_webhook_events IS 'Verified provider deliveries awaiting their integration';
+COMMENT ON COLUMN provider_webhook_events.nonce IS 'Delivery id the replay check keyed on';
+COMMENT ON TABLE provider_webhook_dead_letters IS 'Rejected provider deliveries, kept for debugging';
+COMMENT ON COLUMN provider_webhook_dead_letters.reason IS 'missing_signature, bad_signature, stale_timestamp, replayed, too_large';
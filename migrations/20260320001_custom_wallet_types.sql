@@ -0,0 +1,55 @@
+-- KetoBook: User-defined wallet types (2026-03-20)
+
+-- STEP 1: Per-user wallet types and the behavior template they follow
+CREATE TABLE IF NOT EXISTS custom_wallet_types (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    behavior VARCHAR(10) NOT NULL,
+    supports_credit_limit BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT unique_wallet_type_name_per_user UNIQUE (user_id, name),
+    CONSTRAINT valid_wallet_behavior CHECK (behavior IN ('asset', 'liability')),
+    CONSTRAINT credit_limit_requires_liability CHECK (NOT supports_credit_limit OR behavior = 'liability')
+);
+
+CREATE INDEX IF NOT EXISTS idx_custom_wallet_types_user_id ON custom_wallet_types(user_id);
+
+-- STEP 2: Wallets of a custom type (their wallet_type is 'Other')
+ALTER TABLE wallets
+    ADD COLUMN IF NOT EXISTS custom_type_id UUID;
+
+DO $$ BEGIN
+    ALTER TABLE wallets
+        ADD CONSTRAINT fk_wallets_custom_type_id FOREIGN KEY (custom_type_id) REFERENCES custom_wallet_types(id) ON DELETE RESTRICT;
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+CREATE INDEX IF NOT EXISTS idx_wallets_custom_type_id ON wallets(custom_type_id);
+
+COMMENT ON COLUMN wallets.custom_type_id IS 'User-defined type; its template replaces the built-in wallet_type rules';
+
+-- STEP 3: Available balance follows the template, not just CreditCard
+CREATE OR REPLACE VIEW v_wallet_summary AS
+SELECT
+    w.id,
+    w.user_id,
+    w.name,
+    w.wallet_type,
+    w.balance,
+    w.credit_limit,
+    CASE
+        WHEN (w.wallet_type::text = 'CreditCard' OR ct.supports_credit_limit) AND w.credit_limit > 0
+        THEN (w.credit_limit - w.balance)
+        ELSE w.balance
+    END as available_balance,
+    COUNT(t.id) as transaction_count,
+    MAX(t.created_at) as last_transaction_date,
+    w.created_at,
+    w.updated_at
+FROM wallets w
+LEFT JOIN custom_wallet_types ct ON ct.id = w.custom_type_id
+LEFT JOIN transactions t ON w.id = t.wallet_id
+GROUP BY w.id, w.user_id, w.name, w.wallet_type, w.balance, w.credit_limit, ct.supports_credit_limit, w.created_at, w.updated_at;
@@ -0,0 +1,25 @@
+-- KetoBook: Database backups to S3-compatible storage (2026-03-25)
+
+-- One row per backup attempt; the object lives in the backup bucket under object_key
+CREATE TABLE IF NOT EXISTS backup_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    triggered_by VARCHAR(10) NOT NULL,
+    status VARCHAR(10) NOT NULL DEFAULT 'running',
+    object_key VARCHAR(500),
+    size_bytes BIGINT,
+    sha256 CHAR(64),
+    error TEXT,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_at TIMESTAMP WITH TIME ZONE,
+    verify_status VARCHAR(10),
+    verify_error TEXT,
+    verified_at TIMESTAMP WITH TIME ZONE,
+
+    CONSTRAINT valid_backup_trigger CHECK (triggered_by IN ('manual', 'scheduled')),
+    CONSTRAINT valid_backup_status CHECK (status IN ('running', 'succeeded', 'failed', 'expired')),
+    CONSTRAINT valid_backup_verify_status CHECK (verify_status IS NULL OR verify_status IN ('passed', 'failed'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_backup_runs_started_at ON backup_runs(started_at DESC);
+
+COMMENT ON TABLE backup_runs IS 'pg_dump backups uploaded to S3; expired rows had their object removed by retention';
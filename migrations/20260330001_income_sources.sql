@@ -0,0 +1,41 @@
+-- KetoBook: Income sources (2026-03-30)
+
+-- STEP 1: Where a user's income comes from (employer, side gig, passive)
+CREATE TABLE IF NOT EXISTS income_sources (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    kind VARCHAR(20) NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT unique_income_source_name_per_user UNIQUE (user_id, name),
+    CONSTRAINT valid_income_source_kind CHECK (kind IN ('employer', 'side_gig', 'passive', 'other'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_income_sources_user_id ON income_sources(user_id);
+
+-- STEP 2: Income transactions may name their source
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS income_source_id UUID;
+
+DO $$ BEGIN
+    ALTER TABLE transactions
+        ADD CONSTRAINT fk_transactions_income_source_id FOREIGN KEY (income_source_id) REFERENCES income_sources(id) ON DELETE SET NULL;
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+DO $$ BEGIN
+    ALTER TABLE transactions
+        ADD CONSTRAINT income_source_requires_income CHECK (income_source_id IS NULL OR transaction_type = 'income');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+-- Income report: a user's income by source and month
+CREATE INDEX IF NOT EXISTS idx_transactions_user_income_source ON transactions(user_id, income_source_id, created_at)
+    WHERE transaction_type = 'income';
+
+COMMENT ON COLUMN transactions.income_source_id IS 'Income transactions only; unlinked (NULL) when the source is deleted';
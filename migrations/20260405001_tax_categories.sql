@@ -0,0 +1,20 @@
+-- KetoBook: Tax classification of categories (2026-04-05)
+
+-- A user's tax class per transaction category; the annual tax report sums by class
+CREATE TABLE IF NOT EXISTS tax_category_mappings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    category VARCHAR(100) NOT NULL,
+    tax_class VARCHAR(30) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT unique_tax_category_per_user UNIQUE (user_id, category),
+    CONSTRAINT valid_tax_class CHECK (tax_class IN (
+        'taxable_income', 'exempt_income', 'deductible', 'business_expense', 'charitable'
+    ))
+);
+
+CREATE INDEX IF NOT EXISTS idx_tax_category_mappings_user_id ON tax_category_mappings(user_id);
+
+COMMENT ON TABLE tax_category_mappings IS 'Income classes apply to income transactions of the category, the others to expenses';
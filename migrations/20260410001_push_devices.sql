@@ -0,0 +1,44 @@
+-- KetoBook: Push notification devices and alerts (2026-04-10)
+
+-- STEP 1: Registered devices and their alert preferences
+CREATE TABLE IF NOT EXISTS push_devices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    platform VARCHAR(10) NOT NULL,
+    token VARCHAR(4096) NOT NULL,
+    name VARCHAR(100),
+    low_balance_below NUMERIC(15, 2),
+    due_date_days_before SMALLINT,
+    budget_alerts BOOLEAN NOT NULL DEFAULT TRUE,
+    quiet_start_minute SMALLINT,
+    quiet_end_minute SMALLINT,
+    utc_offset_minutes SMALLINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT unique_push_token UNIQUE (platform, token),
+    CONSTRAINT valid_push_platform CHECK (platform IN ('fcm', 'apns')),
+    CONSTRAINT valid_due_date_days_before CHECK (due_date_days_before IS NULL OR due_date_days_before BETWEEN 0 AND 30),
+    CONSTRAINT valid_quiet_hours CHECK (
+        (quiet_start_minute IS NULL AND quiet_end_minute IS NULL)
+        OR (quiet_start_minute BETWEEN 0 AND 1439 AND quiet_end_minute BETWEEN 0 AND 1439)
+    ),
+    CONSTRAINT valid_utc_offset CHECK (utc_offset_minutes BETWEEN -720 AND 840)
+);
+
+CREATE INDEX IF NOT EXISTS idx_push_devices_user_id ON push_devices(user_id);
+
+-- STEP 2: Alerts already sent to a device (at most once per alert key)
+CREATE TABLE IF NOT EXISTS push_alert_log (
+    device_id UUID NOT NULL,
+    alert_key VARCHAR(200) NOT NULL,
+    sent_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (device_id, alert_key),
+    CONSTRAINT fk_push_alert_log_device_id FOREIGN KEY (device_id) REFERENCES push_devices(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_push_alert_log_sent_at ON push_alert_log(sent_at);
+
+COMMENT ON COLUMN push_devices.utc_offset_minutes IS 'Device clock offset; quiet hours are in device-local minutes of the day';
+COMMENT ON TABLE push_alert_log IS 'Alert keys look like low_balance:{wallet_id}:{date} or due_date:{debt_id}:{due_date}';
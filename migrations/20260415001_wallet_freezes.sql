@@ -0,0 +1,31 @@
+-- KetoBook: Wallet freeze (2026-04-15)
+
+-- STEP 1: Active freezes; a row blocks balance changes on its wallet
+CREATE TABLE IF NOT EXISTS wallet_freezes (
+    wallet_id UUID PRIMARY KEY REFERENCES wallets(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    reason VARCHAR(200),
+    frozen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    unfreeze_at TIMESTAMP WITH TIME ZONE,
+
+    CONSTRAINT unfreeze_after_freeze CHECK (unfreeze_at IS NULL OR unfreeze_at > frozen_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_freezes_unfreeze_at ON wallet_freezes(unfreeze_at) WHERE unfreeze_at IS NOT NULL;
+
+COMMENT ON COLUMN wallet_freezes.unfreeze_at IS 'Automatic unfreeze time; the freeze stops applying at this time even before the job removes the row';
+
+-- STEP 2: Audit trail of freezes and unfreezes
+CREATE TABLE IF NOT EXISTS wallet_freeze_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    action VARCHAR(20) NOT NULL,
+    reason VARCHAR(200),
+    unfreeze_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT valid_freeze_action CHECK (action IN ('freeze', 'unfreeze', 'auto_unfreeze'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_freeze_events_wallet_id ON wallet_freeze_events(wallet_id, created_at);
@@ -0,0 +1,50 @@
+-- KetoBook: Standing transfer orders between wallets (2026-04-20)
+
+-- STEP 1: Standing orders (a fixed amount, or a sweep of everything above a floor)
+CREATE TABLE IF NOT EXISTS standing_orders (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    from_wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    to_wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    mode VARCHAR(10) NOT NULL,
+    amount DECIMAL(15, 2),
+    sweep_above DECIMAL(15, 2),
+    frequency VARCHAR(10) NOT NULL,
+    start_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    next_run_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    last_run_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT distinct_standing_order_wallets CHECK (from_wallet_id <> to_wallet_id),
+    CONSTRAINT valid_standing_order_mode CHECK (
+        (mode = 'fixed' AND amount > 0 AND sweep_above IS NULL)
+        OR (mode = 'sweep' AND amount IS NULL AND sweep_above >= 0)
+    ),
+    CONSTRAINT valid_standing_order_frequency CHECK (frequency IN ('daily', 'weekly', 'monthly'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_standing_orders_user_id ON standing_orders(user_id);
+CREATE INDEX IF NOT EXISTS idx_standing_orders_due ON standing_orders(next_run_at) WHERE active;
+
+COMMENT ON COLUMN standing_orders.start_at IS 'First run; later runs are whole days/weeks/months after it';
+COMMENT ON COLUMN standing_orders.sweep_above IS 'Sweep mode: unallocated source balance kept; everything above it is moved';
+
+-- STEP 2: Run history (one row per scheduled run)
+CREATE TABLE IF NOT EXISTS standing_order_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL REFERENCES standing_orders(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    status VARCHAR(10) NOT NULL,
+    scheduled_for TIMESTAMP WITH TIME ZONE NOT NULL,
+    amount DECIMAL(15, 2),
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT valid_standing_order_run_status CHECK (status IN ('completed', 'skipped', 'failed'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_standing_order_runs_order ON standing_order_runs(order_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_standing_order_runs_failed ON standing_order_runs(created_at) WHERE status = 'failed';
@@ -0,0 +1,26 @@
+-- KetoBook: Transaction templates / favorites (2026-04-25)
+
+-- STEP 1: Templates for frequent, identical entries (parking, lunch, ...)
+CREATE TABLE IF NOT EXISTS transaction_templates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    transaction_type VARCHAR(10) NOT NULL,
+    amount DECIMAL(15, 2),
+    category VARCHAR(100) NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    use_count INTEGER NOT NULL DEFAULT 0,
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT unique_template_name UNIQUE (user_id, name),
+    CONSTRAINT valid_template_type CHECK (transaction_type IN ('income', 'expense')),
+    CONSTRAINT positive_template_amount CHECK (amount IS NULL OR amount > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_transaction_templates_user_id ON transaction_templates(user_id);
+
+COMMENT ON COLUMN transaction_templates.amount IS 'NULL: the amount is given each time the template is used';
+COMMENT ON COLUMN transaction_templates.use_count IS 'Transactions created from the template; favorites are listed most used first';
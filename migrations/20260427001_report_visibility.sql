@@ -0,0 +1,35 @@
+-- KetoBook: Report visibility (2026-04-27)
+--
+-- A report subscription or a debt statement is private to its owner unless
+-- it's shared, and only a report of one wallet can be: `members` shows it to
+-- the listed users. Reports over all of a user's wallets stay private, so
+-- personal wallets never show up for anyone else.
+
+-- STEP 1: Report subscriptions
+ALTER TABLE report_subscriptions
+    ADD COLUMN IF NOT EXISTS visibility VARCHAR(20) NOT NULL DEFAULT 'private'
+        CONSTRAINT valid_report_visibility CHECK (visibility IN ('private', 'members')),
+    ADD COLUMN IF NOT EXISTS shared_with TEXT[] NOT NULL DEFAULT '{}';
+
+COMMENT ON COLUMN report_subscriptions.visibility IS 'private or members (shared_with)';
+COMMENT ON COLUMN report_subscriptions.shared_with IS 'Users who see the subscription when visibility is members';
+
+-- STEP 2: Debt statements
+ALTER TABLE debts
+    ADD COLUMN IF NOT EXISTS statement_visibility VARCHAR(20) NOT NULL DEFAULT 'private'
+        CONSTRAINT valid_statement_visibility CHECK (statement_visibility IN ('private', 'members')),
+    ADD COLUMN IF NOT EXISTS statement_shared_with TEXT[] NOT NULL DEFAULT '{}';
+
+COMMENT ON COLUMN debts.statement_visibility IS 'Who may download the statement: private or members';
+COMMENT ON COLUMN debts.statement_shared_with IS 'Users who may download the statement when statement_visibility is members';
+
+-- STEP 3: Visibility check usable in any query
+CREATE OR REPLACE FUNCTION report_visible_to(
+    owner VARCHAR, wallet UUID, visibility VARCHAR, shared_with TEXT[], viewer VARCHAR
+)
+RETURNS BOOLEAN AS $$
+    SELECT viewer = owner
+        OR (wallet IS NOT NULL AND visibility = 'members' AND viewer = ANY(shared_with))
+$$ LANGUAGE sql STABLE;
+
+COMMENT ON FUNCTION report_visible_to(VARCHAR, UUID, VARCHAR, TEXT[], VARCHAR) IS 'Whether viewer may see a report of owner on wallet with the given visibility';
@@ -0,0 +1,32 @@
+-- KetoBook: Admin impersonation sessions with audit trail (2026-04-30)
+
+-- STEP 1: Time-limited impersonation sessions minted by support
+CREATE TABLE IF NOT EXISTS impersonation_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    requested_by VARCHAR(100) NOT NULL,
+    reason VARCHAR(500) NOT NULL,
+    write_access BOOLEAN NOT NULL DEFAULT FALSE,
+    token_hash CHAR(64) NOT NULL UNIQUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    revoked_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_impersonation_sessions_user_id ON impersonation_sessions(user_id, created_at DESC);
+
+COMMENT ON COLUMN impersonation_sessions.token_hash IS 'SHA-256 (hex) of the bearer token; the token itself is only returned when minted';
+COMMENT ON COLUMN impersonation_sessions.requested_by IS 'Support agent who requested the session (free text; admin auth is a shared token)';
+
+-- STEP 2: Every request made with a session, including rejected ones
+CREATE TABLE IF NOT EXISTS impersonation_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    session_id UUID NOT NULL REFERENCES impersonation_sessions(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    method VARCHAR(10) NOT NULL,
+    path VARCHAR(500) NOT NULL,
+    status SMALLINT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_impersonation_events_session ON impersonation_events(session_id, created_at);
@@ -0,0 +1,24 @@
+-- KetoBook: Configurable transaction validation rules (2026-05-05)
+
+-- STEP 1: Deployment-wide policy, a single row
+CREATE TABLE IF NOT EXISTS transaction_policy (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE,
+    allow_zero_amount BOOLEAN NOT NULL DEFAULT FALSE,
+    max_amount DECIMAL(15, 2),
+    description_required_above DECIMAL(15, 2),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT single_transaction_policy CHECK (id),
+    CONSTRAINT max_amount_positive CHECK (max_amount IS NULL OR max_amount > 0),
+    CONSTRAINT description_threshold_not_negative CHECK (description_required_above IS NULL OR description_required_above >= 0)
+);
+
+INSERT INTO transaction_policy (id) VALUES (TRUE) ON CONFLICT (id) DO NOTHING;
+
+COMMENT ON COLUMN transaction_policy.max_amount IS 'Largest amount of a single transaction; NULL for no limit';
+COMMENT ON COLUMN transaction_policy.description_required_above IS 'Amounts above this need a non-blank description; NULL to never require one';
+
+-- STEP 2: Zero amounts are allowed or not by the policy, not the schema
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS amount_positive;
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS amount_not_negative;
+ALTER TABLE transactions ADD CONSTRAINT amount_not_negative CHECK (amount >= 0);
@@ -0,0 +1,42 @@
+-- KetoBook: Low-balance auto top-up rules (2026-05-10)
+
+-- STEP 1: Rules (at most one per wallet)
+CREATE TABLE IF NOT EXISTS wallet_top_up_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    funding_wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    threshold DECIMAL(15, 2) NOT NULL,
+    amount DECIMAL(15, 2) NOT NULL,
+    daily_cap DECIMAL(15, 2) NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT unique_top_up_rule_per_wallet UNIQUE (wallet_id),
+    CONSTRAINT distinct_top_up_wallets CHECK (wallet_id <> funding_wallet_id),
+    CONSTRAINT top_up_amount_positive CHECK (amount > 0),
+    CONSTRAINT top_up_daily_cap_positive CHECK (daily_cap > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_top_up_rules_user_id ON wallet_top_up_rules(user_id);
+
+COMMENT ON COLUMN wallet_top_up_rules.threshold IS 'A top-up runs when the wallet balance drops below this';
+COMMENT ON COLUMN wallet_top_up_rules.daily_cap IS 'Most moved by the rule per UTC day; the last top-up of a day is cut to fit';
+
+-- STEP 2: Top-up history (completed transfers, and attempts that could not move money)
+CREATE TABLE IF NOT EXISTS wallet_top_ups (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    rule_id UUID NOT NULL REFERENCES wallet_top_up_rules(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    status VARCHAR(10) NOT NULL,
+    amount DECIMAL(15, 2),
+    balance_before DECIMAL(15, 2) NOT NULL,
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT valid_top_up_status CHECK (status IN ('completed', 'capped', 'failed'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_top_ups_rule ON wallet_top_ups(rule_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_wallet_top_ups_created_at ON wallet_top_ups(created_at);
@@ -0,0 +1,29 @@
+-- KetoBook: Two-phase transfers that take days to clear (2026-05-15)
+
+-- STEP 1: Transfers debited from the source and not yet credited to the destination
+CREATE TABLE IF NOT EXISTS pending_transfers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    from_wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    to_wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    amount DECIMAL(15, 2) NOT NULL,
+    reference VARCHAR(200) NOT NULL DEFAULT '',
+    status VARCHAR(12) NOT NULL DEFAULT 'in_transit',
+    expected_by TIMESTAMP WITH TIME ZONE NOT NULL,
+    debit_transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    resolution_transaction_id UUID REFERENCES transactions(id) ON DELETE SET NULL,
+    initiated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved_at TIMESTAMP WITH TIME ZONE,
+
+    CONSTRAINT distinct_pending_transfer_wallets CHECK (from_wallet_id <> to_wallet_id),
+    CONSTRAINT pending_transfer_amount_positive CHECK (amount > 0),
+    CONSTRAINT valid_pending_transfer_status CHECK (status IN ('in_transit', 'settled', 'cancelled')),
+    CONSTRAINT pending_transfer_resolved CHECK ((status = 'in_transit') = (resolved_at IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_pending_transfers_user_id ON pending_transfers(user_id, initiated_at DESC);
+CREATE INDEX IF NOT EXISTS idx_pending_transfers_in_transit ON pending_transfers(expected_by) WHERE status = 'in_transit';
+
+COMMENT ON COLUMN pending_transfers.expected_by IS 'When the transfer should have cleared; reminders start after it';
+COMMENT ON COLUMN pending_transfers.debit_transaction_id IS 'Expense on the source; deleting it refunds the source and drops the transfer';
+COMMENT ON COLUMN pending_transfers.resolution_transaction_id IS 'Income on the destination (settled) or refund on the source (cancelled)';
@@ -0,0 +1,25 @@
+-- KetoBook: Scoped API keys (2026-05-20)
+
+-- STEP 1: Keys a user hands to an integration (dashboard, bot), limited to scopes
+CREATE TABLE IF NOT EXISTS api_keys (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    key_prefix VARCHAR(12) NOT NULL,
+    token_hash CHAR(64) NOT NULL,
+    scopes TEXT[] NOT NULL,
+    sandbox BOOLEAN NOT NULL DEFAULT FALSE,
+    expires_at TIMESTAMP WITH TIME ZONE,
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT unique_api_key_hash UNIQUE (token_hash),
+    CONSTRAINT api_key_has_scopes CHECK (cardinality(scopes) > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_user_id ON api_keys(user_id);
+
+COMMENT ON COLUMN api_keys.key_prefix IS 'First characters of the key, to tell keys apart; the key itself is never stored';
+COMMENT ON COLUMN api_keys.scopes IS 'Granted scopes, e.g. read:wallets, create:transactions, write:debts';
+COMMENT ON COLUMN api_keys.sandbox IS 'Writes only to sandbox wallets and creates wallets as sandbox';
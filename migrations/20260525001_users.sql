@@ -0,0 +1,16 @@
+-- KetoBook: User accounts (2026-05-25)
+
+-- STEP 1: Accounts that log in to get a bearer token; `id` is the user_id of their data
+CREATE TABLE IF NOT EXISTS users (
+    id VARCHAR(100) PRIMARY KEY,
+    email VARCHAR(255) NOT NULL,
+    password_hash TEXT NOT NULL,
+    last_login_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT unique_user_email UNIQUE (email)
+);
+
+COMMENT ON COLUMN users.email IS 'Stored lowercased';
+COMMENT ON COLUMN users.password_hash IS 'Argon2id PHC string';
@@ -0,0 +1,22 @@
+-- KetoBook: Immediate transfers between two of a user's wallets (2026-05-30)
+
+-- STEP 1: Transfers posted in one database transaction, linking their two legs
+CREATE TABLE IF NOT EXISTS wallet_transfers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    from_wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    to_wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    amount DECIMAL(15, 2) NOT NULL,
+    description VARCHAR(200) NOT NULL DEFAULT '',
+    out_transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    in_transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT distinct_wallet_transfer_wallets CHECK (from_wallet_id <> to_wallet_id),
+    CONSTRAINT wallet_transfer_amount_positive CHECK (amount > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_transfers_user_id ON wallet_transfers(user_id, created_at DESC);
+
+COMMENT ON COLUMN wallet_transfers.out_transaction_id IS 'Expense on the source (category transfer); deleting it drops the link';
+COMMENT ON COLUMN wallet_transfers.in_transaction_id IS 'Income on the destination (category transfer); deleting it drops the link';
@@ -0,0 +1,45 @@
+-- KetoBook: Recurring transactions posted on a schedule (2026-06-01)
+
+-- STEP 1: Recurring rules (salary, rent, subscriptions)
+CREATE TABLE IF NOT EXISTS recurring_transactions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    transaction_type VARCHAR(50) NOT NULL,
+    amount DECIMAL(15, 2) NOT NULL,
+    category VARCHAR(100) NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    frequency VARCHAR(10) NOT NULL,
+    start_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    next_run_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    last_run_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT valid_recurring_transaction_type CHECK (transaction_type IN ('income', 'expense')),
+    CONSTRAINT recurring_transaction_amount_positive CHECK (amount > 0),
+    CONSTRAINT valid_recurring_transaction_frequency CHECK (frequency IN ('daily', 'weekly', 'monthly'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_recurring_transactions_user_id ON recurring_transactions(user_id);
+CREATE INDEX IF NOT EXISTS idx_recurring_transactions_due ON recurring_transactions(next_run_at) WHERE active;
+
+COMMENT ON COLUMN recurring_transactions.start_at IS 'First run; later runs are whole days/weeks/months after it';
+
+-- STEP 2: Run history (one row per scheduled run)
+CREATE TABLE IF NOT EXISTS recurring_transaction_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    rule_id UUID NOT NULL REFERENCES recurring_transactions(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    status VARCHAR(10) NOT NULL,
+    scheduled_for TIMESTAMP WITH TIME ZONE NOT NULL,
+    transaction_id UUID REFERENCES transactions(id) ON DELETE SET NULL,
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT valid_recurring_transaction_run_status CHECK (status IN ('completed', 'failed'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_recurring_transaction_runs_rule ON recurring_transaction_runs(rule_id, created_at DESC);
@@ -0,0 +1,67 @@
+-- KetoBook: Online schema changes and transactions.occurred_at (2026-06-05)
+--
+-- Expand step of an expand/contract change. Everything here takes only brief
+-- locks; the slow parts (backfill, index build, constraint validation) run
+-- afterwards in the background, one step at a time (see online_migrations.rs).
+
+-- Give up instead of queueing behind long transactions (and blocking every query behind us)
+SET LOCAL lock_timeout = '5s';
+
+-- STEP 1: Progress of background schema steps, one row per step once started
+CREATE TABLE IF NOT EXISTS online_migrations (
+    name VARCHAR(100) PRIMARY KEY,
+    kind VARCHAR(30) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'running',
+    rows_done BIGINT NOT NULL DEFAULT 0,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_at TIMESTAMP WITH TIME ZONE,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT online_migration_kind_valid CHECK (kind IN ('backfill', 'concurrent_index', 'validate_constraint')),
+    CONSTRAINT online_migration_status_valid CHECK (status IN ('running', 'failed', 'completed'))
+);
+
+COMMENT ON COLUMN online_migrations.rows_done IS 'Rows updated so far by a backfill';
+COMMENT ON COLUMN online_migrations.error IS 'Error of the last failed attempt; the step is retried on the next run';
+
+-- STEP 2: When a transaction happened, as opposed to when it was recorded.
+-- Nullable without a default, so adding it doesn't rewrite the table.
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS occurred_at TIMESTAMP WITH TIME ZONE;
+
+COMMENT ON COLUMN transactions.occurred_at IS 'When the transaction happened; defaults to created_at';
+
+-- Rows written from now on get a value (updates too, since the NOT VALID check
+-- below applies to them); untouched rows are backfilled in chunks
+CREATE OR REPLACE FUNCTION set_transactions_occurred_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.occurred_at = COALESCE(NEW.occurred_at, NEW.created_at, CURRENT_TIMESTAMP);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_transactions_occurred_at ON transactions;
+CREATE TRIGGER trigger_transactions_occurred_at
+    BEFORE INSERT OR UPDATE ON transactions
+    FOR EACH ROW
+    EXECUTE FUNCTION set_transactions_occurred_at();
+
+-- Enforced for new rows right away; checked against old rows once the backfill is done
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS occurred_at_not_null;
+ALTER TABLE transactions ADD CONSTRAINT occurred_at_not_null CHECK (occurred_at IS NOT NULL) NOT VALID;
+
+-- STEP 3: Backfills are not edits; chunks run with `ketobook.backfill` set so
+-- they leave updated_at alone
+CREATE OR REPLACE FUNCTION update_transactions_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF current_setting('ketobook.backfill', TRUE) = 'on' THEN
+        NEW.updated_at = OLD.updated_at;
+    ELSE
+        NEW.updated_at = CURRENT_TIMESTAMP;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
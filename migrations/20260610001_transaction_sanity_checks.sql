@@ -0,0 +1,15 @@
+-- KetoBook: Sanity limits on unusually large transactions (2026-06-10)
+
+-- STEP 1: Thresholds past which a transaction needs an explicit confirmation
+ALTER TABLE transaction_policy ADD COLUMN IF NOT EXISTS outlier_multiplier DECIMAL(8, 2);
+ALTER TABLE transaction_policy ADD COLUMN IF NOT EXISTS max_net_worth_change_percent DECIMAL(6, 2);
+
+ALTER TABLE transaction_policy DROP CONSTRAINT IF EXISTS outlier_multiplier_above_one;
+ALTER TABLE transaction_policy ADD CONSTRAINT outlier_multiplier_above_one
+    CHECK (outlier_multiplier IS NULL OR outlier_multiplier > 1);
+ALTER TABLE transaction_policy DROP CONSTRAINT IF EXISTS net_worth_change_percent_positive;
+ALTER TABLE transaction_policy ADD CONSTRAINT net_worth_change_percent_positive
+    CHECK (max_net_worth_change_percent IS NULL OR max_net_worth_change_percent > 0);
+
+COMMENT ON COLUMN transaction_policy.outlier_multiplier IS 'Amounts above this many times the wallet''s largest transaction need confirm; NULL to never ask';
+COMMENT ON COLUMN transaction_policy.max_net_worth_change_percent IS 'Transactions moving net worth (sum of wallet balances) by more than this percent need confirm; NULL to never ask';
@@ -0,0 +1,45 @@
+-- KetoBook: Spending budgets per category and period (2026-06-15)
+
+-- STEP 1: Weekly or monthly spending limits, per category or overall
+CREATE TABLE IF NOT EXISTS budgets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    category VARCHAR(100),
+    period VARCHAR(10) NOT NULL,
+    amount DECIMAL(15, 2) NOT NULL,
+    alert_thresholds SMALLINT[],
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT valid_budget_period CHECK (period IN ('weekly', 'monthly')),
+    CONSTRAINT budget_amount_positive CHECK (amount > 0)
+);
+
+-- One budget per category (or overall) and period
+CREATE UNIQUE INDEX IF NOT EXISTS idx_budgets_user_category_period
+    ON budgets(user_id, COALESCE(category, ''), period);
+
+COMMENT ON COLUMN budgets.category IS 'Expense category covered; NULL for all expenses except transfers';
+COMMENT ON COLUMN budgets.alert_thresholds IS 'Percentages of amount that raise a budget event when spending crosses them; NULL for the user''s thresholds (budget_alert_settings)';
+
+-- STEP 2: Thresholds crossed, at most once per budget, period and threshold
+CREATE TABLE IF NOT EXISTS budget_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    budget_id UUID NOT NULL REFERENCES budgets(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    period_start TIMESTAMP WITH TIME ZONE NOT NULL,
+    threshold SMALLINT NOT NULL,
+    spent DECIMAL(15, 2) NOT NULL,
+    transaction_id UUID REFERENCES transactions(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT unique_budget_event UNIQUE (budget_id, period_start, threshold)
+);
+
+-- Alert job: recent events per user
+CREATE INDEX IF NOT EXISTS idx_budget_events_user_created ON budget_events(user_id, created_at);
+
+-- Budget status: a user's expenses by category and time
+CREATE INDEX IF NOT EXISTS idx_transactions_user_expense_category ON transactions(user_id, category, created_at)
+    WHERE transaction_type = 'expense';
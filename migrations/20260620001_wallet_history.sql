@@ -0,0 +1,48 @@
+-- KetoBook: Wallet attribute history for as-of reads (2026-06-20)
+--
+-- Balances as of a past moment are rebuilt from the ledger (opening_balance +
+-- transactions); this keeps the other wallet columns as they were before each
+-- change, so the rest of the wallet can be rebuilt too (see history.rs).
+
+-- STEP 1: One row per superseded version of a wallet's attributes
+CREATE TABLE IF NOT EXISTS wallet_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    wallet_type wallet_type NOT NULL,
+    credit_limit DECIMAL(15, 2),
+    custom_type_id UUID,
+    apy DECIMAL(7, 4),
+    opening_balance DECIMAL(15, 2) NOT NULL,
+    changed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_history_wallet_changed ON wallet_history(wallet_id, changed_at);
+CREATE INDEX IF NOT EXISTS idx_wallet_history_user ON wallet_history(user_id);
+
+COMMENT ON TABLE wallet_history IS 'Wallet attributes as they were until changed_at';
+COMMENT ON COLUMN wallet_history.opening_balance IS 'Shifted by direct balance edits, so the old value rebuilds balances before the edit';
+
+-- STEP 2: Record the old version whenever anything but the balance changes
+-- (balance moves with every transaction and is rebuilt from the ledger instead)
+CREATE OR REPLACE FUNCTION record_wallet_history()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO wallet_history (wallet_id, user_id, name, wallet_type, credit_limit, custom_type_id, apy, opening_balance)
+    VALUES (OLD.id, OLD.user_id, OLD.name, OLD.wallet_type, OLD.credit_limit, OLD.custom_type_id, OLD.apy, OLD.opening_balance);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_wallets_history ON wallets;
+CREATE TRIGGER trigger_wallets_history
+    AFTER UPDATE ON wallets
+    FOR EACH ROW
+    WHEN ((OLD.name, OLD.wallet_type, OLD.credit_limit, OLD.custom_type_id, OLD.apy, OLD.opening_balance)
+          IS DISTINCT FROM (NEW.name, NEW.wallet_type, NEW.credit_limit, NEW.custom_type_id, NEW.apy, NEW.opening_balance))
+    EXECUTE FUNCTION record_wallet_history();
+
+-- STEP 3: Balances as of a moment sum a wallet's transactions up to it; the
+-- (wallet_id, created_at) index on transactions is built concurrently in the
+-- background (see online_migrations.rs)
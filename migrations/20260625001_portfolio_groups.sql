@@ -0,0 +1,56 @@
+-- KetoBook: Portfolio groups for wallets and debts (2026-06-25)
+
+-- STEP 1: User-defined groups ("Business", "Personal", "Family", ...)
+CREATE TABLE IF NOT EXISTS portfolio_groups (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_portfolio_groups_user_name ON portfolio_groups(user_id, LOWER(name));
+
+-- STEP 2: At most one group per wallet and per debt; deleting a group ungroups them
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS group_id UUID;
+ALTER TABLE debts ADD COLUMN IF NOT EXISTS group_id UUID;
+
+DO $$ BEGIN
+    ALTER TABLE wallets
+        ADD CONSTRAINT fk_wallets_group_id FOREIGN KEY (group_id) REFERENCES portfolio_groups(id) ON DELETE SET NULL;
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+DO $$ BEGIN
+    ALTER TABLE debts
+        ADD CONSTRAINT fk_debts_group_id FOREIGN KEY (group_id) REFERENCES portfolio_groups(id) ON DELETE SET NULL;
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+CREATE INDEX IF NOT EXISTS idx_wallets_group_id ON wallets(group_id) WHERE group_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_debts_group_id ON debts(group_id) WHERE group_id IS NOT NULL;
+
+COMMENT ON COLUMN wallets.group_id IS 'Portfolio group; NULL when ungrouped';
+COMMENT ON COLUMN debts.group_id IS 'Portfolio group; NULL when ungrouped';
+
+-- STEP 3: Keep the group in the wallet's attribute history (as-of reads)
+ALTER TABLE wallet_history ADD COLUMN IF NOT EXISTS group_id UUID;
+
+CREATE OR REPLACE FUNCTION record_wallet_history()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO wallet_history (wallet_id, user_id, name, wallet_type, credit_limit, custom_type_id, apy, opening_balance, group_id)
+    VALUES (OLD.id, OLD.user_id, OLD.name, OLD.wallet_type, OLD.credit_limit, OLD.custom_type_id, OLD.apy, OLD.opening_balance, OLD.group_id);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_wallets_history ON wallets;
+CREATE TRIGGER trigger_wallets_history
+    AFTER UPDATE ON wallets
+    FOR EACH ROW
+    WHEN ((OLD.name, OLD.wallet_type, OLD.credit_limit, OLD.custom_type_id, OLD.apy, OLD.opening_balance, OLD.group_id)
+          IS DISTINCT FROM (NEW.name, NEW.wallet_type, NEW.credit_limit, NEW.custom_type_id, NEW.apy, NEW.opening_balance, NEW.group_id))
+    EXECUTE FUNCTION record_wallet_history();
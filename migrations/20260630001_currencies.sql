@@ -0,0 +1,127 @@
+-- KetoBook: Wallet currencies and exchange rates (2026-06-30)
+
+-- STEP 1: Supported currencies (ISO 4217 codes)
+CREATE TABLE IF NOT EXISTS currencies (
+    code VARCHAR(3) PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+
+    CONSTRAINT currency_code_format CHECK (code ~ '^[A-Z]{3}$')
+);
+
+INSERT INTO currencies (code, name) VALUES
+    ('VND', 'Vietnamese Dong'),
+    ('USD', 'US Dollar'),
+    ('EUR', 'Euro'),
+    ('GBP', 'Pound Sterling'),
+    ('JPY', 'Japanese Yen'),
+    ('CNY', 'Chinese Yuan'),
+    ('KRW', 'South Korean Won'),
+    ('SGD', 'Singapore Dollar'),
+    ('THB', 'Thai Baht'),
+    ('AUD', 'Australian Dollar'),
+    ('CAD', 'Canadian Dollar'),
+    ('CHF', 'Swiss Franc'),
+    ('HKD', 'Hong Kong Dollar'),
+    ('TWD', 'New Taiwan Dollar'),
+    ('MYR', 'Malaysian Ringgit'),
+    ('IDR', 'Indonesian Rupiah'),
+    ('PHP', 'Philippine Peso'),
+    ('INR', 'Indian Rupee')
+ON CONFLICT (code) DO NOTHING;
+
+-- STEP 2: Exchange rates: 1 unit of `base` is `rate` units of `quote`
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    base VARCHAR(3) NOT NULL REFERENCES currencies(code) ON DELETE CASCADE,
+    quote VARCHAR(3) NOT NULL REFERENCES currencies(code) ON DELETE CASCADE,
+    rate NUMERIC(24, 10) NOT NULL,
+    source VARCHAR(20) NOT NULL DEFAULT 'manual',
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (base, quote),
+    CONSTRAINT distinct_exchange_rate_currencies CHECK (base <> quote),
+    CONSTRAINT exchange_rate_positive CHECK (rate > 0)
+);
+
+COMMENT ON COLUMN exchange_rates.source IS 'manual (admin upsert) or provider (rate refresh job)';
+
+-- Rate from one currency to another: 1 when they are the same, else the
+-- direct rate, the inverse one, or a cross rate through a common base.
+-- NULL when no rate connects them.
+CREATE OR REPLACE FUNCTION exchange_rate(from_currency TEXT, to_currency TEXT)
+RETURNS NUMERIC AS $$
+    SELECT CASE WHEN from_currency = to_currency THEN 1::NUMERIC ELSE COALESCE(
+        (SELECT rate FROM exchange_rates WHERE base = from_currency AND quote = to_currency),
+        (SELECT 1 / rate FROM exchange_rates WHERE base = to_currency AND quote = from_currency),
+        (SELECT b.rate / a.rate
+         FROM exchange_rates a
+         JOIN exchange_rates b ON b.base = a.base
+         WHERE a.quote = from_currency AND b.quote = to_currency
+         ORDER BY LEAST(a.updated_at, b.updated_at) DESC
+         LIMIT 1)
+    ) END
+$$ LANGUAGE sql STABLE;
+
+-- An amount converted at the current rate, rounded to cents; NULL without a rate
+CREATE OR REPLACE FUNCTION convert_amount(amount NUMERIC, from_currency TEXT, to_currency TEXT)
+RETURNS NUMERIC AS $$
+    SELECT ROUND(amount * exchange_rate(from_currency, to_currency), 2)
+$$ LANGUAGE sql STABLE;
+
+-- STEP 3: Every wallet holds one currency, fixed at creation. Existing
+-- amounts were all recorded in dong.
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS currency VARCHAR(3) NOT NULL DEFAULT 'VND';
+
+DO $$ BEGIN
+    ALTER TABLE wallets
+        ADD CONSTRAINT fk_wallets_currency FOREIGN KEY (currency) REFERENCES currencies(code);
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+COMMENT ON COLUMN wallets.currency IS 'ISO 4217 code of balance, credit limit and every transaction of the wallet';
+
+-- STEP 4: Transactions carry their wallet's currency, so aggregates across
+-- wallets can convert without a join. Set on insert; transactions never
+-- change wallet.
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS currency VARCHAR(3) NOT NULL DEFAULT 'VND';
+
+CREATE OR REPLACE FUNCTION set_transaction_currency()
+RETURNS TRIGGER AS $$
+BEGIN
+    SELECT currency INTO NEW.currency FROM wallets WHERE id = NEW.wallet_id;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_transactions_currency ON transactions;
+CREATE TRIGGER trigger_transactions_currency
+    BEFORE INSERT ON transactions
+    FOR EACH ROW
+    EXECUTE FUNCTION set_transaction_currency();
+
+-- STEP 5: The currency a user's reports and net worth are shown in
+CREATE TABLE IF NOT EXISTS user_currency_settings (
+    user_id VARCHAR(100) PRIMARY KEY,
+    base_currency VARCHAR(3) NOT NULL REFERENCES currencies(code),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE OR REPLACE FUNCTION base_currency(for_user TEXT)
+RETURNS VARCHAR AS $$
+    SELECT COALESCE((SELECT base_currency FROM user_currency_settings WHERE user_id = for_user), 'VND')
+$$ LANGUAGE sql STABLE;
+
+-- STEP 6: A transfer between wallets of different currencies credits the
+-- converted amount; record it with the rate used
+ALTER TABLE wallet_transfers ADD COLUMN IF NOT EXISTS to_amount DECIMAL(15, 2);
+ALTER TABLE wallet_transfers ADD COLUMN IF NOT EXISTS exchange_rate NUMERIC(24, 10) NOT NULL DEFAULT 1;
+UPDATE wallet_transfers SET to_amount = amount WHERE to_amount IS NULL;
+ALTER TABLE wallet_transfers ALTER COLUMN to_amount SET NOT NULL;
+
+COMMENT ON COLUMN wallet_transfers.amount IS 'Debited from the source, in its currency';
+COMMENT ON COLUMN wallet_transfers.to_amount IS 'Credited to the destination, in its currency';
+
+ALTER TABLE pending_transfers ADD COLUMN IF NOT EXISTS resolution_amount DECIMAL(15, 2);
+UPDATE pending_transfers SET resolution_amount = amount WHERE resolution_transaction_id IS NOT NULL AND resolution_amount IS NULL;
+
+COMMENT ON COLUMN pending_transfers.resolution_amount IS 'Credited on settlement (converted at that time) or refunded on cancellation';
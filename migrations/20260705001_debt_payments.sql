@@ -0,0 +1,30 @@
+-- KetoBook: Debt payments through a wallet (2026-07-05)
+
+-- STEP 1: A settlement can be paid from (payable) or into (receivable) a wallet
+ALTER TABLE debt_settlements
+    ADD COLUMN IF NOT EXISTS wallet_id UUID REFERENCES wallets(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS transaction_id UUID REFERENCES transactions(id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS idx_debt_settlements_transaction_id
+    ON debt_settlements(transaction_id) WHERE transaction_id IS NOT NULL;
+
+COMMENT ON COLUMN debt_settlements.wallet_id IS 'Wallet the payment moved money through, if any';
+COMMENT ON COLUMN debt_settlements.transaction_id IS 'Expense (payable) or income (receivable) on the wallet; deleting it drops the payment';
+
+-- STEP 2: Reopen a paid debt when one of its settlements goes away
+CREATE OR REPLACE FUNCTION reopen_debt_after_settlement_delete()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE debts SET status = 'active', updated_at = CURRENT_TIMESTAMP
+    WHERE id = OLD.debt_id
+      AND status = 'paid'
+      AND amount > COALESCE((SELECT SUM(amount) FROM debt_settlements WHERE debt_id = OLD.debt_id), 0);
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS reopen_debt_after_settlement_delete ON debt_settlements;
+CREATE TRIGGER reopen_debt_after_settlement_delete
+    AFTER DELETE ON debt_settlements
+    FOR EACH ROW
+    EXECUTE FUNCTION reopen_debt_after_settlement_delete();
@@ -0,0 +1,35 @@
+-- KetoBook: CSV import mapping presets (2026-07-10)
+
+-- STEP 1: Presets, per user or shared with everyone (user_id NULL, admin managed)
+CREATE TABLE IF NOT EXISTS import_presets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100),
+    name VARCHAR(100) NOT NULL,
+    bank VARCHAR(100),
+    delimiter VARCHAR(1) NOT NULL DEFAULT ',',
+    headers TEXT[] NOT NULL,
+    date_column VARCHAR(100) NOT NULL,
+    date_format VARCHAR(50) NOT NULL DEFAULT '%Y-%m-%d',
+    amount_column VARCHAR(100),
+    debit_column VARCHAR(100),
+    credit_column VARCHAR(100),
+    description_column VARCHAR(100),
+    category_column VARCHAR(100),
+    decimal_comma BOOLEAN NOT NULL DEFAULT FALSE,
+    expense_negative BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT import_preset_has_amount CHECK (
+        amount_column IS NOT NULL OR debit_column IS NOT NULL OR credit_column IS NOT NULL
+    )
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_import_presets_user_name
+    ON import_presets(user_id, LOWER(name)) WHERE user_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_import_presets_community_name
+    ON import_presets(LOWER(name)) WHERE user_id IS NULL;
+
+COMMENT ON TABLE import_presets IS 'CSV column mappings per bank; user_id NULL for community presets';
+COMMENT ON COLUMN import_presets.headers IS 'Header row of the statement, used to detect the preset of an uploaded file';
+COMMENT ON COLUMN import_presets.expense_negative IS 'Negative amount_column values are expenses; false for statements listing spending as positive';
@@ -0,0 +1,28 @@
+-- KetoBook: CSV and OFX import of bank statements (2026-07-15)
+
+-- STEP 1: Statement lines already imported, so a re-uploaded statement is not booked twice
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS import_hash VARCHAR(64);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_transactions_wallet_import_hash
+    ON transactions(wallet_id, import_hash) WHERE import_hash IS NOT NULL;
+
+COMMENT ON COLUMN transactions.import_hash IS 'SHA-256 of the statement line (date, signed amount, description, occurrence) it was imported from';
+
+-- STEP 2: One row per uploaded statement, for the activity feed
+CREATE TABLE IF NOT EXISTS transaction_imports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    preset_id UUID REFERENCES import_presets(id) ON DELETE SET NULL,
+    file_name VARCHAR(255) NOT NULL,
+    format VARCHAR(10) NOT NULL CHECK (format IN ('csv', 'ofx')),
+    imported INTEGER NOT NULL DEFAULT 0,
+    skipped INTEGER NOT NULL DEFAULT 0,
+    failed INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_transaction_imports_user_id ON transaction_imports(user_id, created_at DESC);
+
+COMMENT ON TABLE transaction_imports IS 'Bank statements uploaded to POST /api/transactions/{user_id}/import';
@@ -0,0 +1,22 @@
+-- KetoBook: Versioned transactional email templates (2026-07-20)
+
+-- STEP 1: Template versions edited by admins; the highest version of a name/locale is used
+CREATE TABLE IF NOT EXISTS email_templates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(50) NOT NULL,
+    locale VARCHAR(10) NOT NULL,
+    version INTEGER NOT NULL CHECK (version > 0),
+    subject TEXT NOT NULL,
+    body TEXT NOT NULL,
+    note VARCHAR(200),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT email_templates_version_unique UNIQUE (name, locale, version)
+);
+
+COMMENT ON TABLE email_templates IS 'Handlebars email templates; versions override the built-in templates shipped in templates/email';
+COMMENT ON COLUMN email_templates.version IS 'Increasing per name and locale; the built-in template is version 0';
+
+-- STEP 2: Language of scheduled report emails
+ALTER TABLE report_subscriptions
+    ADD COLUMN IF NOT EXISTS locale VARCHAR(10) NOT NULL DEFAULT 'en';
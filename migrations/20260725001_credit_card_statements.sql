@@ -0,0 +1,48 @@
+-- KetoBook: Statement cycles and interest-free periods on credit cards (2026-07-25)
+
+-- STEP 1: Card terms; a card without terms has no statements and accrues no interest
+CREATE TABLE IF NOT EXISTS credit_card_terms (
+    wallet_id UUID PRIMARY KEY REFERENCES wallets(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    statement_day SMALLINT NOT NULL CHECK (statement_day BETWEEN 1 AND 28),
+    payment_due_days SMALLINT NOT NULL CHECK (payment_due_days BETWEEN 1 AND 28),
+    apr DECIMAL(7, 4) NOT NULL CHECK (apr >= 0),
+    tracking_from TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_credit_card_terms_user_id ON credit_card_terms(user_id);
+
+COMMENT ON COLUMN credit_card_terms.statement_day IS 'Day of month (UTC) a statement closes';
+COMMENT ON COLUMN credit_card_terms.payment_due_days IS 'Days after the statement closes by which it must be paid in full to keep the interest-free period';
+COMMENT ON COLUMN credit_card_terms.apr IS 'Annual percentage rate on purchases (e.g. 24.9 = 24.9%)';
+COMMENT ON COLUMN credit_card_terms.tracking_from IS 'Start of the first statement period';
+
+-- STEP 2: Closed statements, one per card per statement date
+CREATE TABLE IF NOT EXISTS credit_card_statements (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    period_start TIMESTAMP WITH TIME ZONE NOT NULL,
+    period_end TIMESTAMP WITH TIME ZONE NOT NULL,
+    due_date TIMESTAMP WITH TIME ZONE NOT NULL,
+    opening_balance DECIMAL(15, 2) NOT NULL,
+    purchases DECIMAL(15, 2) NOT NULL,
+    payments DECIMAL(15, 2) NOT NULL,
+    interest DECIMAL(15, 2) NOT NULL DEFAULT 0,
+    interest_free BOOLEAN NOT NULL,
+    closing_balance DECIMAL(15, 2) NOT NULL,
+    paid_amount DECIMAL(15, 2),
+    paid_in_full BOOLEAN,
+    interest_transaction_id UUID REFERENCES transactions(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (wallet_id, period_end)
+);
+
+CREATE INDEX IF NOT EXISTS idx_credit_card_statements_wallet_id ON credit_card_statements(wallet_id, period_end DESC);
+
+COMMENT ON TABLE credit_card_statements IS 'Statement cycles closed by the interest job; period is (period_start, period_end]';
+COMMENT ON COLUMN credit_card_statements.interest_free IS 'The previous statement was paid in full, so the period was not charged interest when it closed';
+COMMENT ON COLUMN credit_card_statements.paid_amount IS 'Payments between the statement and its due date; NULL until the next statement closes';
+COMMENT ON COLUMN credit_card_statements.paid_in_full IS 'Whether paid_amount covered closing_balance; NULL until the next statement closes';
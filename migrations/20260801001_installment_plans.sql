@@ -0,0 +1,33 @@
+-- KetoBook: Installment plans on credit cards (trả góp) (2026-08-01)
+
+-- STEP 1: A card expense converted into monthly installments
+CREATE TABLE IF NOT EXISTS installment_plans (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    transaction_id UUID UNIQUE REFERENCES transactions(id) ON DELETE SET NULL,
+    description VARCHAR(255),
+    principal DECIMAL(15, 2) NOT NULL CHECK (principal > 0),
+    months SMALLINT NOT NULL CHECK (months BETWEEN 2 AND 60),
+    monthly_rate DECIMAL(7, 4) NOT NULL DEFAULT 0 CHECK (monthly_rate >= 0),
+    fee_rate DECIMAL(7, 4) NOT NULL DEFAULT 0 CHECK (fee_rate >= 0),
+    fee DECIMAL(15, 2) NOT NULL DEFAULT 0,
+    interest DECIMAL(15, 2) NOT NULL DEFAULT 0,
+    total_amount DECIMAL(15, 2) NOT NULL,
+    installment_amount DECIMAL(15, 2) NOT NULL,
+    installments_posted SMALLINT NOT NULL DEFAULT 0,
+    billed DECIMAL(15, 2) NOT NULL DEFAULT 0,
+    next_due_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'active' CHECK (status IN ('active', 'completed')),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_installment_plans_user_id ON installment_plans(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_installment_plans_due ON installment_plans(next_due_at) WHERE status = 'active';
+
+COMMENT ON COLUMN installment_plans.transaction_id IS 'The converted card expense; a transaction is converted at most once';
+COMMENT ON COLUMN installment_plans.monthly_rate IS 'Flat interest per month, in percent of the principal';
+COMMENT ON COLUMN installment_plans.fee_rate IS 'One-off conversion fee, in percent of the principal';
+COMMENT ON COLUMN installment_plans.total_amount IS 'Principal plus interest, billed over the months';
+COMMENT ON COLUMN installment_plans.billed IS 'Sum of the installments posted so far; total_amount - billed is still owed';
@@ -0,0 +1,103 @@
+-- KetoBook: Per-user transaction sequence numbers (2026-08-05)
+--
+-- Every transaction gets the next number of its user (1, 2, 3, ...) from a
+-- counter row updated in the same database transaction as the insert. The
+-- counter row stays locked until commit, so a user's numbers are handed out,
+-- and become visible, in commit order: a client syncing "everything after
+-- #10452" never misses a row committed late with a lower number.
+--
+-- Expand step of an online change (see online_migrations.rs): existing rows
+-- are numbered by a background backfill, then a unique index is built
+-- concurrently.
+
+-- Give up instead of queueing behind long transactions (and blocking every query behind us)
+SET LOCAL lock_timeout = '5s';
+
+-- STEP 1: One counter per user
+CREATE TABLE IF NOT EXISTS user_transaction_counters (
+    user_id VARCHAR(100) PRIMARY KEY,
+    last_sequence BIGINT NOT NULL DEFAULT 0,
+    backfilled BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+COMMENT ON TABLE user_transaction_counters IS 'Last transaction sequence number handed out per user';
+COMMENT ON COLUMN user_transaction_counters.last_sequence IS 'Highest number assigned; starts at the count of transactions recorded before sequence numbers';
+COMMENT ON COLUMN user_transaction_counters.backfilled IS 'Numbers 1..last_sequence handed to those earlier transactions by the backfill';
+
+-- STEP 2: Nullable without a default, so adding it doesn't rewrite the table
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS sequence_number BIGINT;
+
+COMMENT ON COLUMN transactions.sequence_number IS 'Per-user number assigned on insert (#1, #2, ...); never reused or changed';
+
+-- A user's first counter starts after the transactions they recorded before
+-- this migration; those keep 1..N for the backfill
+CREATE OR REPLACE FUNCTION seed_transaction_counter(p_user_id VARCHAR)
+RETURNS VOID AS $$
+BEGIN
+    INSERT INTO user_transaction_counters (user_id, last_sequence)
+    VALUES (p_user_id, (SELECT COUNT(*) FROM transactions WHERE user_id = p_user_id))
+    ON CONFLICT (user_id) DO NOTHING;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Numbers an existing transaction, from the range its user's counter kept
+-- free for transactions recorded before this migration
+CREATE OR REPLACE FUNCTION backfill_transaction_sequence(p_user_id VARCHAR)
+RETURNS BIGINT AS $$
+DECLARE
+    assigned BIGINT;
+BEGIN
+    PERFORM seed_transaction_counter(p_user_id);
+    UPDATE user_transaction_counters
+    SET backfilled = backfilled + 1
+    WHERE user_id = p_user_id
+    RETURNING backfilled INTO assigned;
+    RETURN assigned;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Inserts always take the next number (a value supplied by the caller is
+-- ignored). Updates keep the number; an old row edited before the backfill
+-- reached it is numbered then, since the NOT VALID check below applies to it.
+CREATE OR REPLACE FUNCTION set_transactions_sequence_number()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' THEN
+        IF OLD.sequence_number IS NOT NULL THEN
+            NEW.sequence_number = OLD.sequence_number;
+        ELSIF NEW.sequence_number IS NULL THEN
+            NEW.sequence_number = backfill_transaction_sequence(NEW.user_id);
+        END IF;
+        RETURN NEW;
+    END IF;
+
+    UPDATE user_transaction_counters
+    SET last_sequence = last_sequence + 1, updated_at = CURRENT_TIMESTAMP
+    WHERE user_id = NEW.user_id
+    RETURNING last_sequence INTO NEW.sequence_number;
+
+    IF NOT FOUND THEN
+        PERFORM seed_transaction_counter(NEW.user_id);
+        UPDATE user_transaction_counters
+        SET last_sequence = last_sequence + 1, updated_at = CURRENT_TIMESTAMP
+        WHERE user_id = NEW.user_id
+        RETURNING last_sequence INTO NEW.sequence_number;
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_transactions_sequence_number ON transactions;
+CREATE TRIGGER trigger_transactions_sequence_number
+    BEFORE INSERT OR UPDATE ON transactions
+    FOR EACH ROW
+    EXECUTE FUNCTION set_transactions_sequence_number();
+
+-- STEP 3: Enforced for new rows right away; checked against old rows once the backfill is done
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS sequence_number_not_null;
+ALTER TABLE transactions ADD CONSTRAINT sequence_number_not_null CHECK (sequence_number IS NOT NULL) NOT VALID;
+
+-- STEP 4: The unique (user_id, sequence_number) index is built concurrently
+-- in the background (see online_migrations.rs)
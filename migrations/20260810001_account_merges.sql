@@ -0,0 +1,81 @@
+-- KetoBook: Merging duplicate accounts (2026-08-10)
+
+-- STEP 1: One record per merge; never updated or deleted
+CREATE TABLE IF NOT EXISTS account_merges (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    source_user_id VARCHAR(100) NOT NULL,
+    target_user_id VARCHAR(100) NOT NULL,
+    initiated_by VARCHAR(10) NOT NULL,
+    requested_by VARCHAR(100) NOT NULL,
+    reason VARCHAR(500),
+    moved JSONB NOT NULL DEFAULT '{}'::jsonb,
+    renamed JSONB NOT NULL DEFAULT '{}'::jsonb,
+    dropped JSONB NOT NULL DEFAULT '{}'::jsonb,
+    merged_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT account_merge_initiated_by_valid CHECK (initiated_by IN ('admin', 'user')),
+    CONSTRAINT account_merge_distinct_users CHECK (source_user_id <> target_user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_account_merges_source ON account_merges(source_user_id, merged_at DESC);
+CREATE INDEX IF NOT EXISTS idx_account_merges_target ON account_merges(target_user_id, merged_at DESC);
+
+COMMENT ON TABLE account_merges IS 'Audit record of account merges; append-only';
+COMMENT ON COLUMN account_merges.requested_by IS 'Support agent (admin merges, free text) or the target user (user merges)';
+COMMENT ON COLUMN account_merges.moved IS 'Rows moved to the target, per table';
+COMMENT ON COLUMN account_merges.renamed IS 'Moved rows renamed because the target had one by that name, per table';
+COMMENT ON COLUMN account_merges.dropped IS 'Source rows dropped because the target had its own (settings, budgets), per table';
+
+CREATE OR REPLACE FUNCTION prevent_account_merge_changes()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'account_merges records are append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_account_merges_append_only ON account_merges;
+CREATE TRIGGER trigger_account_merges_append_only
+    BEFORE UPDATE OR DELETE ON account_merges
+    FOR EACH ROW
+    EXECUTE FUNCTION prevent_account_merge_changes();
+
+-- STEP 2: A transaction moved to another user takes a number of that user.
+-- A merge numbers the moved rows itself, in their old order, after reserving
+-- the range on the target's counter; it runs with `ketobook.renumbering` set
+-- (see account_merges.rs). Any other move gets the next number.
+CREATE OR REPLACE FUNCTION set_transactions_sequence_number()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' THEN
+        IF NEW.user_id IS DISTINCT FROM OLD.user_id THEN
+            IF current_setting('ketobook.renumbering', TRUE) IS DISTINCT FROM 'on' THEN
+                PERFORM seed_transaction_counter(NEW.user_id);
+                UPDATE user_transaction_counters
+                SET last_sequence = last_sequence + 1, updated_at = CURRENT_TIMESTAMP
+                WHERE user_id = NEW.user_id
+                RETURNING last_sequence INTO NEW.sequence_number;
+            END IF;
+        ELSIF OLD.sequence_number IS NOT NULL THEN
+            NEW.sequence_number = OLD.sequence_number;
+        ELSIF NEW.sequence_number IS NULL THEN
+            NEW.sequence_number = backfill_transaction_sequence(NEW.user_id);
+        END IF;
+        RETURN NEW;
+    END IF;
+
+    UPDATE user_transaction_counters
+    SET last_sequence = last_sequence + 1, updated_at = CURRENT_TIMESTAMP
+    WHERE user_id = NEW.user_id
+    RETURNING last_sequence INTO NEW.sequence_number;
+
+    IF NOT FOUND THEN
+        PERFORM seed_transaction_counter(NEW.user_id);
+        UPDATE user_transaction_counters
+        SET last_sequence = last_sequence + 1, updated_at = CURRENT_TIMESTAMP
+        WHERE user_id = NEW.user_id
+        RETURNING last_sequence INTO NEW.sequence_number;
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
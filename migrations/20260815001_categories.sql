@@ -0,0 +1,38 @@
+-- KetoBook: Transaction categories (2026-08-15)
+--
+-- Transactions keep their category as text. Each user has a list of
+-- categories that this text is matched against case-insensitively and
+-- normalized to (see categories.rs). Renaming or merging a category rewrites
+-- the text on the user's transactions, templates, recurring transactions,
+-- budgets and tax mappings.
+
+-- STEP 1: A user's categories, optionally nested one level under a parent
+CREATE TABLE IF NOT EXISTS categories (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    parent_id UUID REFERENCES categories(id) ON DELETE SET NULL,
+    icon VARCHAR(50),
+    system BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT category_not_own_parent CHECK (parent_id IS NULL OR parent_id <> id)
+);
+
+-- One category per name, ignoring case ("Groceries" and "groceries" are the same)
+CREATE UNIQUE INDEX IF NOT EXISTS idx_categories_user_name ON categories(user_id, LOWER(name));
+CREATE INDEX IF NOT EXISTS idx_categories_parent_id ON categories(parent_id);
+
+COMMENT ON COLUMN categories.icon IS 'Icon name of the client icon set, or an emoji';
+COMMENT ON COLUMN categories.system IS 'Posted by KetoBook itself (transfer, interest, ...); can''t be renamed, merged or deleted';
+
+-- STEP 2: Users whose default categories were created, so they aren't
+-- recreated after being deleted
+CREATE TABLE IF NOT EXISTS category_seeds (
+    user_id VARCHAR(100) PRIMARY KEY,
+    seeded_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- STEP 3: Renames and merges find a category's transactions by LOWER(category);
+-- that index is built concurrently in the background (see online_migrations.rs)
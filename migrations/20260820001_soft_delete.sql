@@ -0,0 +1,23 @@
+-- KetoBook: Soft delete for wallets and transactions (2026-08-20)
+--
+-- Deleting a wallet or transaction sets `deleted_at` instead of removing the
+-- row, so it can be restored; `?hard=true` still removes it. Every query of
+-- live data filters on `deleted_at IS NULL`.
+--
+-- - A deleted transaction's effect on its wallet (balance, bucket) is
+--   reversed when it is deleted and re-applied when it is restored.
+-- - A deleted wallet keeps its balance; its live transactions are deleted
+--   with it, stamped with the wallet's `deleted_at`, and restored with it.
+
+-- Give up instead of queueing behind long transactions (and blocking every query behind us)
+SET LOCAL lock_timeout = '5s';
+
+-- STEP 1: Nullable without a default, so adding them doesn't rewrite the tables
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE;
+
+COMMENT ON COLUMN wallets.deleted_at IS 'Soft-deleted: hidden everywhere until restored';
+COMMENT ON COLUMN transactions.deleted_at IS 'Soft-deleted: hidden everywhere and not counted in the balance until restored; equals the wallet''s deleted_at when deleted with it';
+
+-- STEP 2: Deleted transactions of a wallet are found by a partial index built
+-- concurrently in the background (see online_migrations.rs)
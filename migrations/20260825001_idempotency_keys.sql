@@ -0,0 +1,25 @@
+-- KetoBook: Idempotency keys for creates (2026-08-25)
+--
+-- Clients may send an `Idempotency-Key` header on POST /api/transactions,
+-- /api/wallets and /api/debts. The first request with a key stores its
+-- response here; a retry with the same key within 24 hours gets that
+-- response back instead of creating a duplicate (see idempotency.rs).
+
+-- STEP 1: One row per (user, route, key), written in the create's DB transaction
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    user_id VARCHAR(100) NOT NULL,
+    route VARCHAR(50) NOT NULL,
+    key VARCHAR(255) NOT NULL,
+    request_hash CHAR(64) NOT NULL,
+    status SMALLINT,
+    response JSONB,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (user_id, route, key)
+);
+
+COMMENT ON COLUMN idempotency_keys.request_hash IS 'SHA-256 of the request body; a retry with another body is rejected';
+COMMENT ON COLUMN idempotency_keys.response IS 'The ApiResponse body sent with status; NULL until the create commits';
+
+-- STEP 2: The purge job deletes keys older than 24 hours
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_created_at ON idempotency_keys(created_at);
@@ -0,0 +1,105 @@
+-- KetoBook: Audit log of financial changes (2026-08-30)
+--
+-- Every insert, update and delete of a wallet, transaction or debt is recorded
+-- by a trigger, in the same DB transaction as the change, with the row before
+-- and after. Handlers name the actor and request through the transaction-local
+-- settings `ketobook.actor` and `ketobook.request_id` (see audit.rs).
+
+-- Give up instead of queueing behind long transactions (and blocking every query behind us)
+SET LOCAL lock_timeout = '5s';
+
+-- STEP 1: One row per change, append-only
+CREATE TABLE IF NOT EXISTS audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    user_id VARCHAR(100) NOT NULL,
+    entity_type VARCHAR(20) NOT NULL CHECK (entity_type IN ('wallet', 'transaction', 'debt')),
+    entity_id TEXT NOT NULL,
+    action VARCHAR(10) NOT NULL CHECK (action IN ('create', 'update', 'delete', 'restore')),
+    actor VARCHAR(150),
+    request_id VARCHAR(100),
+    old_values JSONB,
+    new_values JSONB,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_user_created ON audit_log(user_id, created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log(entity_type, entity_id);
+
+COMMENT ON TABLE audit_log IS 'Changes of wallets, transactions and debts; append-only';
+COMMENT ON COLUMN audit_log.actor IS 'User id, api_key:<id> or impersonation:<session id>; NULL for background jobs';
+COMMENT ON COLUMN audit_log.old_values IS 'The row before the change (to_jsonb); NULL for creates';
+COMMENT ON COLUMN audit_log.new_values IS 'The row after the change (to_jsonb); NULL for hard deletes';
+
+CREATE OR REPLACE FUNCTION prevent_audit_log_changes()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log records are append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_audit_log_append_only ON audit_log;
+CREATE TRIGGER trigger_audit_log_append_only
+    BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW
+    EXECUTE FUNCTION prevent_audit_log_changes();
+
+-- STEP 2: Record changes; the entity type is the trigger argument. Updates
+-- that only touch updated_at, and backfills of new columns, are skipped.
+-- Setting deleted_at is a delete, clearing it a restore.
+CREATE OR REPLACE FUNCTION record_audit_log()
+RETURNS TRIGGER AS $$
+DECLARE
+    before_row JSONB := CASE WHEN TG_OP = 'INSERT' THEN NULL ELSE to_jsonb(OLD) END;
+    after_row JSONB := CASE WHEN TG_OP = 'DELETE' THEN NULL ELSE to_jsonb(NEW) END;
+    change TEXT;
+BEGIN
+    IF current_setting('ketobook.backfill', TRUE) = 'on' THEN
+        RETURN NULL;
+    END IF;
+
+    IF TG_OP = 'INSERT' THEN
+        change := 'create';
+    ELSIF TG_OP = 'DELETE' THEN
+        change := 'delete';
+    ELSIF (before_row - 'updated_at') = (after_row - 'updated_at') THEN
+        RETURN NULL;
+    ELSIF before_row->>'deleted_at' IS NULL AND after_row->>'deleted_at' IS NOT NULL THEN
+        change := 'delete';
+    ELSIF before_row->>'deleted_at' IS NOT NULL AND after_row->>'deleted_at' IS NULL THEN
+        change := 'restore';
+    ELSE
+        change := 'update';
+    END IF;
+
+    INSERT INTO audit_log (user_id, entity_type, entity_id, action, actor, request_id, old_values, new_values)
+    VALUES (
+        COALESCE(after_row->>'user_id', before_row->>'user_id'),
+        TG_ARGV[0],
+        COALESCE(after_row->>'id', before_row->>'id'),
+        change,
+        NULLIF(current_setting('ketobook.actor', TRUE), ''),
+        NULLIF(current_setting('ketobook.request_id', TRUE), ''),
+        before_row,
+        after_row
+    );
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_wallets_audit ON wallets;
+CREATE TRIGGER trigger_wallets_audit
+    AFTER INSERT OR UPDATE OR DELETE ON wallets
+    FOR EACH ROW
+    EXECUTE FUNCTION record_audit_log('wallet');
+
+DROP TRIGGER IF EXISTS trigger_transactions_audit ON transactions;
+CREATE TRIGGER trigger_transactions_audit
+    AFTER INSERT OR UPDATE OR DELETE ON transactions
+    FOR EACH ROW
+    EXECUTE FUNCTION record_audit_log('transaction');
+
+DROP TRIGGER IF EXISTS trigger_debts_audit ON debts;
+CREATE TRIGGER trigger_debts_audit
+    AFTER INSERT OR UPDATE OR DELETE ON debts
+    FOR EACH ROW
+    EXECUTE FUNCTION record_audit_log('debt');
@@ -0,0 +1,60 @@
+-- KetoBook: Daily wallet balance snapshots (2026-09-05)
+--
+-- GET /api/wallets/{user_id}/{wallet_id}/history charts a wallet's balance
+-- over days, weeks or months. Rebuilding each point from the ledger on every
+-- request (as ?as_of= reads do) is too slow for a year of points, so the
+-- closing balance of each UTC day is kept here (see balance_history.rs).
+
+-- STEP 1: One closing balance per wallet and day
+CREATE TABLE IF NOT EXISTS wallet_balance_snapshots (
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    snapshot_date DATE NOT NULL,
+    balance DECIMAL(15, 2) NOT NULL,
+    taken_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (wallet_id, snapshot_date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_balance_snapshots_user_id ON wallet_balance_snapshots(user_id);
+
+COMMENT ON TABLE wallet_balance_snapshots IS 'Wallet balance at the end of each UTC day; days without a row carry the previous one forward';
+COMMENT ON COLUMN wallet_balance_snapshots.balance IS 'Opening balance in force at the end of the day plus every transaction recorded up to it';
+
+-- STEP 2: Seed past days from the ledger
+--
+-- One row per day a wallet had transactions, plus the day it was created;
+-- the days in between carry forward. The snapshot job fills every day from
+-- now on.
+WITH daily AS (
+    SELECT wallet_id, day, SUM(net) AS net
+    FROM (
+        SELECT t.wallet_id, (t.created_at AT TIME ZONE 'UTC')::date AS day,
+               SUM(CASE WHEN t.transaction_type = 'income' THEN t.amount ELSE -t.amount END) AS net
+        FROM transactions t
+        WHERE t.deleted_at IS NULL
+        GROUP BY 1, 2
+        UNION ALL
+        SELECT id, (created_at AT TIME ZONE 'UTC')::date, 0
+        FROM wallets
+        WHERE deleted_at IS NULL
+    ) days
+    GROUP BY wallet_id, day
+),
+running AS (
+    SELECT wallet_id, day, SUM(net) OVER (PARTITION BY wallet_id ORDER BY day) AS net
+    FROM daily
+)
+INSERT INTO wallet_balance_snapshots (wallet_id, user_id, snapshot_date, balance)
+SELECT r.wallet_id, w.user_id, r.day, COALESCE(h.opening_balance, w.opening_balance) + r.net
+FROM running r
+JOIN wallets w ON w.id = r.wallet_id AND w.deleted_at IS NULL
+LEFT JOIN LATERAL (
+    SELECT wh.opening_balance
+    FROM wallet_history wh
+    WHERE wh.wallet_id = w.id AND wh.changed_at >= (r.day + 1)::timestamp AT TIME ZONE 'UTC'
+    ORDER BY wh.changed_at
+    LIMIT 1
+) h ON TRUE
+WHERE r.day < (CURRENT_TIMESTAMP AT TIME ZONE 'UTC')::date
+ON CONFLICT (wallet_id, snapshot_date) DO NOTHING;
@@ -0,0 +1,49 @@
+-- KetoBook: Savings goals (2026-09-10)
+--
+-- A savings goal is the planning counterpart of a debt: an amount to put
+-- aside by a deadline, optionally into a linked wallet. Contributions move
+-- money out of a source wallet (see goals.rs).
+
+-- STEP 1: Goals
+CREATE TABLE IF NOT EXISTS savings_goals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    target_amount DECIMAL(15, 2) NOT NULL,
+    currency VARCHAR(3) NOT NULL REFERENCES currencies(code),
+    deadline DATE NOT NULL,
+    wallet_id UUID REFERENCES wallets(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT savings_goal_target_positive CHECK (target_amount > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_savings_goals_user_id ON savings_goals(user_id);
+CREATE INDEX IF NOT EXISTS idx_savings_goals_wallet_id ON savings_goals(wallet_id);
+
+COMMENT ON COLUMN savings_goals.currency IS 'ISO 4217 code of target_amount and contributions; the linked wallet''s currency when linked';
+COMMENT ON COLUMN savings_goals.wallet_id IS 'Wallet contributions are moved into; NULL moves them out of the tracked wallets';
+
+-- STEP 2: Contributions
+CREATE TABLE IF NOT EXISTS goal_contributions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    goal_id UUID NOT NULL REFERENCES savings_goals(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    from_wallet_id UUID REFERENCES wallets(id) ON DELETE SET NULL,
+    amount DECIMAL(15, 2) NOT NULL,
+    goal_amount DECIMAL(15, 2) NOT NULL,
+    exchange_rate NUMERIC(24, 10) NOT NULL DEFAULT 1,
+    out_transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    in_transaction_id UUID REFERENCES transactions(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT goal_contribution_amount_positive CHECK (amount > 0 AND goal_amount > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_goal_contributions_goal_created ON goal_contributions(goal_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_goal_contributions_user_id ON goal_contributions(user_id);
+
+COMMENT ON COLUMN goal_contributions.amount IS 'Taken from the source wallet, in its currency';
+COMMENT ON COLUMN goal_contributions.goal_amount IS 'Counted toward the goal, in the goal''s currency';
+COMMENT ON COLUMN goal_contributions.out_transaction_id IS 'Expense on the source (category transfer); deleting it no longer counts the contribution';
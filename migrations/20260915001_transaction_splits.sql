@@ -0,0 +1,42 @@
+-- KetoBook: Transaction splits (2026-09-15)
+--
+-- A transaction can be spread over several categories, e.g. one supermarket
+-- receipt covering groceries, household and alcohol. The lines sum to the
+-- transaction's amount; the transaction keeps its own category as the
+-- headline shown in lists (see transaction_splits.rs).
+
+-- STEP 1: Split lines
+CREATE TABLE IF NOT EXISTS transaction_splits (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    category VARCHAR(100) NOT NULL,
+    amount DECIMAL(15, 2) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT transaction_split_amount_positive CHECK (amount > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_transaction_splits_transaction_id ON transaction_splits(transaction_id);
+CREATE INDEX IF NOT EXISTS idx_transaction_splits_user_category ON transaction_splits(user_id, LOWER(category));
+
+COMMENT ON COLUMN transaction_splits.amount IS 'In the transaction''s currency; a transaction''s lines sum to its amount';
+
+-- STEP 2: What category reports aggregate over: a split transaction's lines,
+-- or the whole transaction when it has none. `id` is the transaction's.
+CREATE OR REPLACE VIEW v_transaction_category_lines AS
+SELECT
+    t.id,
+    t.user_id,
+    t.wallet_id,
+    t.income_source_id,
+    t.transaction_type,
+    COALESCE(s.category, t.category) AS category,
+    COALESCE(s.amount, t.amount) AS amount,
+    t.currency,
+    t.metadata,
+    t.created_at,
+    t.deleted_at,
+    t.sandbox
+FROM transactions t
+LEFT JOIN transaction_splits s ON s.transaction_id = t.id;
@@ -0,0 +1,19 @@
+-- KetoBook: Creditor bank details on debts (2026-09-20)
+--
+-- A payable debt can carry the creditor's bank account, from which a VietQR
+-- payment code is generated (see payment_qr.rs).
+
+-- STEP 1: Bank account of the creditor
+ALTER TABLE debts
+    ADD COLUMN IF NOT EXISTS creditor_bank_bin VARCHAR(6),
+    ADD COLUMN IF NOT EXISTS creditor_account_number VARCHAR(19);
+
+ALTER TABLE debts DROP CONSTRAINT IF EXISTS debt_creditor_bank_details;
+ALTER TABLE debts ADD CONSTRAINT debt_creditor_bank_details CHECK (
+    (creditor_bank_bin IS NULL) = (creditor_account_number IS NULL)
+    AND (creditor_bank_bin IS NULL OR creditor_bank_bin ~ '^[0-9]{6}$')
+    AND (creditor_account_number IS NULL OR creditor_account_number ~ '^[0-9A-Za-z]{1,19}$')
+);
+
+COMMENT ON COLUMN debts.creditor_bank_bin IS 'NAPAS bank identification number (6 digits) of the creditor''s bank';
+COMMENT ON COLUMN debts.creditor_account_number IS 'Creditor''s account number at that bank';
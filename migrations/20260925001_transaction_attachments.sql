@@ -0,0 +1,8 @@
+-- KetoBook: Receipts on transactions (2026-09-25)
+--
+-- Attachments (see attachments.rs) can belong to a transaction as well as a
+-- debt: scanned receipts and invoices.
+
+-- STEP 1: Allow the new entity type
+ALTER TABLE attachments DROP CONSTRAINT IF EXISTS valid_attachment_entity_type;
+ALTER TABLE attachments ADD CONSTRAINT valid_attachment_entity_type CHECK (entity_type IN ('debt', 'transaction'));
@@ -0,0 +1,47 @@
+-- KetoBook: In-app notifications with webhook and email delivery (2026-09-30)
+--
+-- A background job writes notifications for debts coming due, overdue debts
+-- and budgets over their limit, then delivers them to the user's webhook
+-- and/or email address (see notifications.rs).
+
+-- STEP 1: Notifications
+CREATE TABLE IF NOT EXISTS notifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    kind VARCHAR(30) NOT NULL,
+    subject_id UUID NOT NULL,
+    notification_key VARCHAR(200) NOT NULL,
+    title VARCHAR(200) NOT NULL,
+    body TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    read_at TIMESTAMP WITH TIME ZONE,
+    delivery_status VARCHAR(10) NOT NULL DEFAULT 'pending',
+    delivery_attempts SMALLINT NOT NULL DEFAULT 0,
+    delivery_error TEXT,
+    delivered_at TIMESTAMP WITH TIME ZONE,
+
+    CONSTRAINT valid_notification_kind CHECK (kind IN ('debt_due', 'debt_overdue', 'budget_exceeded')),
+    CONSTRAINT valid_notification_delivery CHECK (delivery_status IN ('pending', 'sent', 'failed', 'none')),
+    CONSTRAINT unique_notification_key UNIQUE (user_id, notification_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_user_created ON notifications(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_notifications_pending ON notifications(created_at) WHERE delivery_status = 'pending';
+
+COMMENT ON COLUMN notifications.notification_key IS 'What the notification is about, e.g. debt_due:{debt_id}:{due date}; each is raised once';
+COMMENT ON COLUMN notifications.delivery_status IS 'pending until delivered (sent), given up on (failed), or with no channel to go to (none)';
+
+-- STEP 2: Delivery settings per user (defaults apply without a row)
+CREATE TABLE IF NOT EXISTS notification_settings (
+    user_id VARCHAR(100) PRIMARY KEY,
+    due_days_before SMALLINT NOT NULL,
+    webhook_url VARCHAR(500),
+    webhook_secret VARCHAR(64),
+    email VARCHAR(255),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT notification_due_days_range CHECK (due_days_before BETWEEN 0 AND 60),
+    CONSTRAINT notification_webhook_secret CHECK ((webhook_url IS NULL) = (webhook_secret IS NULL))
+);
+
+COMMENT ON COLUMN notification_settings.webhook_secret IS 'Key of the HMAC-SHA256 signature sent with each webhook call';
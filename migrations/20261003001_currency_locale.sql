@@ -0,0 +1,114 @@
+-- KetoBook: Currency symbols and the currency of a user's locale (2026-10-03)
+--
+-- Clients list the supported currencies with their symbol and minor units
+-- for pickers. A user can set their locale instead of a base currency: the
+-- base currency (and so the default currency of a new wallet) is then the
+-- one of the locale's region, or of its language when there's no region.
+-- An explicit base currency still wins; VND when neither says.
+
+-- STEP 1: Display details of each currency
+ALTER TABLE currencies ADD COLUMN IF NOT EXISTS symbol VARCHAR(8) NOT NULL DEFAULT '';
+ALTER TABLE currencies ADD COLUMN IF NOT EXISTS minor_units SMALLINT NOT NULL DEFAULT 2
+    CHECK (minor_units BETWEEN 0 AND 4);
+
+COMMENT ON COLUMN currencies.symbol IS 'Symbol shown next to amounts, e.g. ₫ or US$';
+COMMENT ON COLUMN currencies.minor_units IS 'ISO 4217 decimal places of the currency (0 for VND, JPY, KRW)';
+
+UPDATE currencies c SET symbol = v.symbol, minor_units = v.minor_units
+FROM (VALUES
+    ('VND', '₫', 0),
+    ('USD', '$', 2),
+    ('EUR', '€', 2),
+    ('GBP', '£', 2),
+    ('JPY', '¥', 0),
+    ('CNY', '¥', 2),
+    ('KRW', '₩', 0),
+    ('SGD', 'S$', 2),
+    ('THB', '฿', 2),
+    ('AUD', 'A$', 2),
+    ('CAD', 'CA$', 2),
+    ('CHF', 'CHF', 2),
+    ('HKD', 'HK$', 2),
+    ('TWD', 'NT$', 2),
+    ('MYR', 'RM', 2),
+    ('IDR', 'Rp', 2),
+    ('PHP', '₱', 2),
+    ('INR', '₹', 2)
+) AS v (code, symbol, minor_units)
+WHERE c.code = v.code AND c.symbol = '';
+
+-- STEP 2: The currency of a locale's region, or of its language
+CREATE TABLE IF NOT EXISTS locale_currencies (
+    kind VARCHAR(10) NOT NULL CHECK (kind IN ('region', 'language')),
+    code VARCHAR(2) NOT NULL,
+    currency VARCHAR(3) NOT NULL REFERENCES currencies(code) ON DELETE CASCADE,
+
+    PRIMARY KEY (kind, code)
+);
+
+COMMENT ON TABLE locale_currencies IS 'Currency of an ISO 3166 region (VN) or, for locales without one, an ISO 639 language (vi)';
+
+INSERT INTO locale_currencies (kind, code, currency) VALUES
+    ('region', 'VN', 'VND'),
+    ('region', 'US', 'USD'),
+    ('region', 'GB', 'GBP'),
+    ('region', 'JP', 'JPY'),
+    ('region', 'CN', 'CNY'),
+    ('region', 'KR', 'KRW'),
+    ('region', 'SG', 'SGD'),
+    ('region', 'TH', 'THB'),
+    ('region', 'AU', 'AUD'),
+    ('region', 'CA', 'CAD'),
+    ('region', 'CH', 'CHF'),
+    ('region', 'HK', 'HKD'),
+    ('region', 'TW', 'TWD'),
+    ('region', 'MY', 'MYR'),
+    ('region', 'ID', 'IDR'),
+    ('region', 'PH', 'PHP'),
+    ('region', 'IN', 'INR'),
+    ('region', 'DE', 'EUR'),
+    ('region', 'FR', 'EUR'),
+    ('region', 'IT', 'EUR'),
+    ('region', 'ES', 'EUR'),
+    ('region', 'NL', 'EUR'),
+    ('region', 'BE', 'EUR'),
+    ('region', 'AT', 'EUR'),
+    ('region', 'PT', 'EUR'),
+    ('region', 'IE', 'EUR'),
+    ('region', 'FI', 'EUR'),
+    ('region', 'GR', 'EUR'),
+    ('language', 'vi', 'VND'),
+    ('language', 'ja', 'JPY'),
+    ('language', 'ko', 'KRW'),
+    ('language', 'th', 'THB'),
+    ('language', 'id', 'IDR'),
+    ('language', 'ms', 'MYR'),
+    ('language', 'de', 'EUR'),
+    ('language', 'fr', 'EUR'),
+    ('language', 'it', 'EUR'),
+    ('language', 'es', 'EUR'),
+    ('language', 'nl', 'EUR')
+ON CONFLICT (kind, code) DO NOTHING;
+
+CREATE OR REPLACE FUNCTION locale_currency(locale TEXT)
+RETURNS VARCHAR AS $$
+    SELECT COALESCE(
+        (SELECT currency FROM locale_currencies WHERE kind = 'region' AND code = split_part(locale, '-', 2)),
+        (SELECT currency FROM locale_currencies WHERE kind = 'language' AND code = split_part(locale, '-', 1))
+    )
+$$ LANGUAGE sql STABLE;
+
+-- STEP 3: A user's locale; the base currency becomes optional
+ALTER TABLE user_currency_settings ADD COLUMN IF NOT EXISTS locale VARCHAR(10);
+ALTER TABLE user_currency_settings ALTER COLUMN base_currency DROP NOT NULL;
+
+COMMENT ON COLUMN user_currency_settings.locale IS 'e.g. vi or en-US; gives the base currency when base_currency is NULL';
+COMMENT ON COLUMN user_currency_settings.base_currency IS 'Set explicitly; NULL to follow the locale';
+
+CREATE OR REPLACE FUNCTION base_currency(for_user TEXT)
+RETURNS VARCHAR AS $$
+    SELECT COALESCE(
+        (SELECT COALESCE(base_currency, locale_currency(locale)) FROM user_currency_settings WHERE user_id = for_user),
+        'VND'
+    )
+$$ LANGUAGE sql STABLE;
@@ -0,0 +1,128 @@
+-- KetoBook: Change events and webhook endpoints (2026-10-05)
+--
+-- Changes of wallets, transactions and debts are written to `event_outbox` by
+-- a trigger, in the same DB transaction as the change, for users with an
+-- active webhook endpoint. A background job fans each event out to the
+-- user's endpoints and POSTs it, retrying with backoff (see webhooks.rs).
+
+-- Give up instead of queueing behind long transactions (and blocking every query behind us)
+SET LOCAL lock_timeout = '5s';
+
+-- STEP 1: Endpoints a user registered
+CREATE TABLE IF NOT EXISTS webhook_endpoints (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    url VARCHAR(500) NOT NULL,
+    secret VARCHAR(64) NOT NULL,
+    event_types TEXT[],
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_endpoints_user ON webhook_endpoints(user_id);
+
+COMMENT ON COLUMN webhook_endpoints.secret IS 'HMAC-SHA256 key signing the payloads';
+COMMENT ON COLUMN webhook_endpoints.event_types IS 'Event types sent to the endpoint; NULL for all';
+
+-- STEP 2: Events, written with the change
+CREATE TABLE IF NOT EXISTS event_outbox (
+    id BIGSERIAL PRIMARY KEY,
+    user_id VARCHAR(100) NOT NULL,
+    event_type VARCHAR(30) NOT NULL,
+    entity_id TEXT NOT NULL,
+    data JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    dispatched_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_outbox_undispatched ON event_outbox(id) WHERE dispatched_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_event_outbox_created ON event_outbox(created_at);
+
+COMMENT ON COLUMN event_outbox.data IS 'The row after the change (to_jsonb); the row before for deletes';
+COMMENT ON COLUMN event_outbox.dispatched_at IS 'When the event was queued for the user''s endpoints';
+
+-- STEP 3: One delivery per event and endpoint
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    endpoint_id UUID NOT NULL REFERENCES webhook_endpoints(id) ON DELETE CASCADE,
+    event_id BIGINT NOT NULL REFERENCES event_outbox(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    status VARCHAR(10) NOT NULL DEFAULT 'pending',
+    attempts SMALLINT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    response_status SMALLINT,
+    last_error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    delivered_at TIMESTAMP WITH TIME ZONE,
+
+    CONSTRAINT valid_webhook_delivery_status CHECK (status IN ('pending', 'sent', 'failed')),
+    CONSTRAINT unique_webhook_delivery UNIQUE (endpoint_id, event_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_endpoint ON webhook_deliveries(endpoint_id, created_at DESC);
+
+-- STEP 4: Record events; the entity type is the trigger argument. Like the
+-- audit log, updates that only touch updated_at and backfills are skipped,
+-- and setting or clearing deleted_at is a delete or restore. A debt whose
+-- status becomes 'paid' is a `debt.paid` event.
+CREATE OR REPLACE FUNCTION record_change_event()
+RETURNS TRIGGER AS $$
+DECLARE
+    before_row JSONB := CASE WHEN TG_OP = 'INSERT' THEN NULL ELSE to_jsonb(OLD) END;
+    after_row JSONB := CASE WHEN TG_OP = 'DELETE' THEN NULL ELSE to_jsonb(NEW) END;
+    owner TEXT := COALESCE(after_row->>'user_id', before_row->>'user_id');
+    change TEXT;
+BEGIN
+    IF current_setting('ketobook.backfill', TRUE) = 'on' THEN
+        RETURN NULL;
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM webhook_endpoints WHERE user_id = owner AND active) THEN
+        RETURN NULL;
+    END IF;
+
+    IF TG_OP = 'INSERT' THEN
+        change := 'created';
+    ELSIF TG_OP = 'DELETE' THEN
+        change := 'deleted';
+    ELSIF (before_row - 'updated_at') = (after_row - 'updated_at') THEN
+        RETURN NULL;
+    ELSIF before_row->>'deleted_at' IS NULL AND after_row->>'deleted_at' IS NOT NULL THEN
+        change := 'deleted';
+    ELSIF before_row->>'deleted_at' IS NOT NULL AND after_row->>'deleted_at' IS NULL THEN
+        change := 'restored';
+    ELSIF TG_ARGV[0] = 'debt' AND after_row->>'status' = 'paid' AND before_row->>'status' <> 'paid' THEN
+        change := 'paid';
+    ELSE
+        change := 'updated';
+    END IF;
+
+    INSERT INTO event_outbox (user_id, event_type, entity_id, data)
+    VALUES (
+        owner,
+        TG_ARGV[0] || '.' || change,
+        COALESCE(after_row->>'id', before_row->>'id'),
+        COALESCE(after_row, before_row)
+    );
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_wallets_change_event ON wallets;
+CREATE TRIGGER trigger_wallets_change_event
+    AFTER INSERT OR UPDATE OR DELETE ON wallets
+    FOR EACH ROW
+    EXECUTE FUNCTION record_change_event('wallet');
+
+DROP TRIGGER IF EXISTS trigger_transactions_change_event ON transactions;
+CREATE TRIGGER trigger_transactions_change_event
+    AFTER INSERT OR UPDATE OR DELETE ON transactions
+    FOR EACH ROW
+    EXECUTE FUNCTION record_change_event('transaction');
+
+DROP TRIGGER IF EXISTS trigger_debts_change_event ON debts;
+CREATE TRIGGER trigger_debts_change_event
+    AFTER INSERT OR UPDATE OR DELETE ON debts
+    FOR EACH ROW
+    EXECUTE FUNCTION record_change_event('debt');
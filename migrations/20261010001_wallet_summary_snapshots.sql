@@ -0,0 +1,90 @@
+-- KetoBook: Month-to-date wallet summary snapshots (2026-10-10)
+--
+-- GET /api/wallets/{user_id}/{wallet_id}/summary aggregates the month's
+-- transactions on every read, which is slow for wallets with thousands of
+-- them. A background job keeps the month-to-date totals of busy wallets as of
+-- a point in time here, and the summary adds only the transactions recorded
+-- after it (see summary_snapshots.rs).
+
+-- Give up instead of queueing behind long transactions (and blocking every query behind us)
+SET LOCAL lock_timeout = '5s';
+
+-- STEP 1: Totals of one wallet's month up to as_of
+CREATE TABLE IF NOT EXISTS wallet_summary_snapshots (
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    period_start DATE NOT NULL,
+    as_of TIMESTAMP WITH TIME ZONE NOT NULL,
+    income DECIMAL(15, 2) NOT NULL,
+    expense DECIMAL(15, 2) NOT NULL,
+    transaction_count BIGINT NOT NULL,
+    taken_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (wallet_id, period_start)
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_summary_snapshots_user_id ON wallet_summary_snapshots(user_id);
+
+COMMENT ON TABLE wallet_summary_snapshots IS 'Month-to-date totals of busy wallets; summaries add the transactions created after as_of';
+COMMENT ON COLUMN wallet_summary_snapshots.as_of IS 'Transactions created before this are counted; later ones are the summary''s delta';
+
+-- STEP 2: Category totals of the same snapshot (split lines count in their own category)
+CREATE TABLE IF NOT EXISTS wallet_summary_snapshot_categories (
+    wallet_id UUID NOT NULL,
+    period_start DATE NOT NULL,
+    category VARCHAR(100) NOT NULL,
+    transaction_type VARCHAR(20) NOT NULL,
+    total DECIMAL(15, 2) NOT NULL,
+    transaction_count BIGINT NOT NULL,
+
+    PRIMARY KEY (wallet_id, period_start, transaction_type, category),
+    FOREIGN KEY (wallet_id, period_start)
+        REFERENCES wallet_summary_snapshots(wallet_id, period_start) ON DELETE CASCADE
+);
+
+-- STEP 3: Drop snapshots a change makes stale
+--
+-- Creating, editing, deleting or restoring a transaction created before a
+-- snapshot's as_of changes totals the snapshot already counted; the snapshot
+-- is dropped and summaries aggregate the month until the job takes it again.
+-- New transactions are created after as_of and leave snapshots alone.
+CREATE OR REPLACE FUNCTION invalidate_wallet_summary_snapshots()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM wallet_summary_snapshots
+        WHERE wallet_id = OLD.wallet_id AND as_of > OLD.created_at;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        DELETE FROM wallet_summary_snapshots
+        WHERE wallet_id = NEW.wallet_id AND as_of > NEW.created_at;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_transactions_invalidate_summary_snapshots ON transactions;
+CREATE TRIGGER trigger_transactions_invalidate_summary_snapshots
+    AFTER INSERT OR UPDATE OR DELETE ON transactions
+    FOR EACH ROW
+    EXECUTE FUNCTION invalidate_wallet_summary_snapshots();
+
+-- Split lines are replaced without touching their transaction's wallet or date
+CREATE OR REPLACE FUNCTION invalidate_wallet_summary_snapshots_for_split()
+RETURNS TRIGGER AS $$
+DECLARE
+    split_transaction_id UUID := CASE WHEN TG_OP = 'DELETE' THEN OLD.transaction_id ELSE NEW.transaction_id END;
+BEGIN
+    DELETE FROM wallet_summary_snapshots s
+    USING transactions t
+    WHERE t.id = split_transaction_id
+      AND s.wallet_id = t.wallet_id AND s.as_of > t.created_at;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_transaction_splits_invalidate_summary_snapshots ON transaction_splits;
+CREATE TRIGGER trigger_transaction_splits_invalidate_summary_snapshots
+    AFTER INSERT OR UPDATE OR DELETE ON transaction_splits
+    FOR EACH ROW
+    EXECUTE FUNCTION invalidate_wallet_summary_snapshots_for_split();
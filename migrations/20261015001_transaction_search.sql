@@ -0,0 +1,38 @@
+-- KetoBook: Transaction search (2026-10-15)
+--
+-- GET /api/transactions/user/{user_id}/search?q= finds transactions by words
+-- of their description, category, notes or amount (full-text), and by
+-- misspelled or partial words (trigram similarity). Both are served from
+-- expression indexes, so the rows carry no extra columns (see
+-- transaction_search.rs).
+
+-- Give up instead of queueing behind long transactions (and blocking every query behind us)
+SET LOCAL lock_timeout = '5s';
+
+-- STEP 1: Trigram matching
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- STEP 2: What is searched. The 'simple' configuration neither stems nor drops
+-- stop words, which suits short labels in any language. Description words
+-- rank above category, then notes and amount.
+CREATE OR REPLACE FUNCTION transaction_search_document(
+    description TEXT, category TEXT, notes TEXT, amount DECIMAL
+)
+RETURNS tsvector AS $$
+    SELECT setweight(to_tsvector('simple', COALESCE(description, '')), 'A')
+        || setweight(to_tsvector('simple', COALESCE(category, '')), 'B')
+        || setweight(to_tsvector('simple', COALESCE(notes, '')), 'C')
+        || setweight(to_tsvector('simple', amount::text), 'D')
+$$ LANGUAGE sql IMMUTABLE PARALLEL SAFE;
+
+CREATE OR REPLACE FUNCTION transaction_search_text(description TEXT, category TEXT)
+RETURNS TEXT AS $$
+    SELECT LOWER(COALESCE(description, '') || ' ' || COALESCE(category, ''))
+$$ LANGUAGE sql IMMUTABLE PARALLEL SAFE;
+
+COMMENT ON FUNCTION transaction_search_document(TEXT, TEXT, TEXT, DECIMAL) IS 'Full-text document of a transaction; queries must use the same expression to hit idx_transactions_search_document';
+COMMENT ON FUNCTION transaction_search_text(TEXT, TEXT) IS 'Text matched by trigram similarity; queries must use the same expression to hit idx_transactions_search_trgm';
+
+-- STEP 3: The GIN indexes over both expressions are built concurrently in the
+-- background (see online_migrations.rs); until then searches scan the user's
+-- transactions.
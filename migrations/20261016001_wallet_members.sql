@@ -0,0 +1,102 @@
+-- KetoBook: Shared wallets (2026-10-16)
+--
+-- A wallet can be shared with other users, each with a role:
+--
+--   owner   The wallet's user_id; manages the wallet and its members
+--   editor  Records, edits, deletes and restores its transactions
+--   viewer  Reads the wallet and its transactions
+--
+-- Every wallet has exactly one owner row, kept in step with wallets.user_id
+-- by triggers. Wallet and transaction reads and writes authorize through this
+-- table instead of comparing user_id (see wallet_members.rs). Transactions in
+-- a shared wallet keep the owner's user_id whoever records them; the audit
+-- log tells who did.
+
+-- Give up instead of queueing behind long transactions (and blocking every query behind us)
+SET LOCAL lock_timeout = '5s';
+
+-- STEP 1: Members of each wallet
+CREATE TABLE IF NOT EXISTS wallet_members (
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    role VARCHAR(10) NOT NULL CHECK (role IN ('owner', 'editor', 'viewer')),
+    added_by VARCHAR(100) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (wallet_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_members_user_id ON wallet_members(user_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_wallet_members_one_owner ON wallet_members(wallet_id) WHERE role = 'owner';
+
+COMMENT ON TABLE wallet_members IS 'Who can use a wallet and how; the owner row mirrors wallets.user_id';
+
+-- STEP 2: Every existing wallet is owned by its user
+INSERT INTO wallet_members (wallet_id, user_id, role, added_by, created_at)
+SELECT id, user_id, 'owner', user_id, created_at FROM wallets
+ON CONFLICT (wallet_id, user_id) DO NOTHING;
+
+-- STEP 3: Keep the owner row in step with wallets.user_id (new wallets, and
+-- wallets moved to another account by a merge)
+CREATE OR REPLACE FUNCTION sync_wallet_owner()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' THEN
+        DELETE FROM wallet_members WHERE wallet_id = NEW.id AND user_id = OLD.user_id;
+    END IF;
+    INSERT INTO wallet_members (wallet_id, user_id, role, added_by)
+    VALUES (NEW.id, NEW.user_id, 'owner', NEW.user_id)
+    ON CONFLICT (wallet_id, user_id) DO UPDATE SET role = 'owner', updated_at = CURRENT_TIMESTAMP;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_wallets_insert_owner ON wallets;
+CREATE TRIGGER trigger_wallets_insert_owner
+    AFTER INSERT ON wallets
+    FOR EACH ROW
+    EXECUTE FUNCTION sync_wallet_owner();
+
+DROP TRIGGER IF EXISTS trigger_wallets_update_owner ON wallets;
+CREATE TRIGGER trigger_wallets_update_owner
+    AFTER UPDATE OF user_id ON wallets
+    FOR EACH ROW
+    WHEN (OLD.user_id IS DISTINCT FROM NEW.user_id)
+    EXECUTE FUNCTION sync_wallet_owner();
+
+-- STEP 4: Role check usable in any query
+CREATE OR REPLACE FUNCTION has_wallet_role(wallet UUID, member VARCHAR, needed VARCHAR)
+RETURNS BOOLEAN AS $$
+    SELECT EXISTS (
+        SELECT 1 FROM wallet_members m
+        WHERE m.wallet_id = wallet AND m.user_id = member
+          AND CASE m.role WHEN 'owner' THEN 3 WHEN 'editor' THEN 2 ELSE 1 END
+              >= CASE needed WHEN 'owner' THEN 3 WHEN 'editor' THEN 2 ELSE 1 END
+    )
+$$ LANGUAGE sql STABLE;
+
+COMMENT ON FUNCTION has_wallet_role(UUID, VARCHAR, VARCHAR) IS 'Whether member has at least the needed role (owner > editor > viewer) on wallet';
+
+-- STEP 5: Reports can be shared with a wallet's whole household, and only
+-- with its current members (see 20260427001_report_visibility.sql)
+ALTER TABLE report_subscriptions
+    DROP CONSTRAINT IF EXISTS valid_report_visibility,
+    ADD CONSTRAINT valid_report_visibility CHECK (visibility IN ('private', 'household', 'members'));
+
+ALTER TABLE debts
+    DROP CONSTRAINT IF EXISTS valid_statement_visibility,
+    ADD CONSTRAINT valid_statement_visibility CHECK (statement_visibility IN ('private', 'household', 'members'));
+
+COMMENT ON COLUMN report_subscriptions.visibility IS 'private, household (members of wallet_id) or members (shared_with)';
+COMMENT ON COLUMN debts.statement_visibility IS 'Who may download the statement: private, household (members of wallet_id) or members';
+
+CREATE OR REPLACE FUNCTION report_visible_to(
+    owner VARCHAR, wallet UUID, visibility VARCHAR, shared_with TEXT[], viewer VARCHAR
+)
+RETURNS BOOLEAN AS $$
+    SELECT viewer = owner
+        OR (wallet IS NOT NULL
+            AND (visibility = 'household' OR (visibility = 'members' AND viewer = ANY(shared_with)))
+            AND has_wallet_role(wallet, viewer, 'viewer'))
+$$ LANGUAGE sql STABLE;
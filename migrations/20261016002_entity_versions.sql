@@ -0,0 +1,49 @@
+-- KetoBook: Entity versions (2026-10-16)
+--
+-- Wallets, transactions and debts get a version that goes up by one with
+-- every change to the row, so offline-capable clients can tell whether their
+-- copy is stale (see list_versions.rs). Existing rows start at 1. Backfills
+-- run with `ketobook.backfill` set leave it alone, as they do updated_at.
+
+-- Give up instead of queueing behind long transactions (and blocking every query behind us)
+SET LOCAL lock_timeout = '5s';
+
+-- STEP 1: Version columns (a constant default, so no table rewrite)
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE debts ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 1;
+
+COMMENT ON COLUMN wallets.version IS 'Bumped by every update of the row';
+COMMENT ON COLUMN transactions.version IS 'Bumped by every update of the row';
+COMMENT ON COLUMN debts.version IS 'Bumped by every update of the row';
+
+-- STEP 2: Bump on every update
+CREATE OR REPLACE FUNCTION bump_row_version()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF current_setting('ketobook.backfill', TRUE) = 'on' THEN
+        NEW.version = OLD.version;
+    ELSE
+        NEW.version = OLD.version + 1;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_wallets_version ON wallets;
+CREATE TRIGGER trigger_wallets_version
+    BEFORE UPDATE ON wallets
+    FOR EACH ROW
+    EXECUTE FUNCTION bump_row_version();
+
+DROP TRIGGER IF EXISTS trigger_transactions_version ON transactions;
+CREATE TRIGGER trigger_transactions_version
+    BEFORE UPDATE ON transactions
+    FOR EACH ROW
+    EXECUTE FUNCTION bump_row_version();
+
+DROP TRIGGER IF EXISTS trigger_debts_version ON debts;
+CREATE TRIGGER trigger_debts_version
+    BEFORE UPDATE ON debts
+    FOR EACH ROW
+    EXECUTE FUNCTION bump_row_version();
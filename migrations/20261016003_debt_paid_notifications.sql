@@ -0,0 +1,12 @@
+-- KetoBook: Debt paid notifications (2026-10-16)
+--
+-- A debt marked paid, or paid off by a settlement, raises a `debt_paid`
+-- notification (see the notifications hook in notifications.rs).
+
+-- Give up instead of queueing behind long transactions (and blocking every query behind us)
+SET LOCAL lock_timeout = '5s';
+
+-- STEP 1: Allow the new kind
+ALTER TABLE notifications DROP CONSTRAINT IF EXISTS valid_notification_kind;
+ALTER TABLE notifications ADD CONSTRAINT valid_notification_kind
+    CHECK (kind IN ('debt_due', 'debt_overdue', 'budget_exceeded', 'debt_paid'));
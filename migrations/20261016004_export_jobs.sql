@@ -0,0 +1,37 @@
+-- KetoBook: Background export jobs (2026-10-16)
+--
+-- Transaction and account exports can be queued instead of downloaded within
+-- the request. Workers build the file, store it next to the attachments and
+-- hand out expiring signed links to it (see export_jobs.rs).
+
+-- STEP 1: Export jobs
+CREATE TABLE IF NOT EXISTS export_jobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(100) NOT NULL,
+    kind VARCHAR(20) NOT NULL,
+    format VARCHAR(10) NOT NULL,
+    range_from TIMESTAMP WITH TIME ZONE,
+    range_to TIMESTAMP WITH TIME ZONE,
+    status VARCHAR(10) NOT NULL DEFAULT 'queued',
+    attempts SMALLINT NOT NULL DEFAULT 0,
+    object_key VARCHAR(500),
+    size_bytes BIGINT,
+    download_secret CHAR(64) NOT NULL,
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    started_at TIMESTAMP WITH TIME ZONE,
+    finished_at TIMESTAMP WITH TIME ZONE,
+    expires_at TIMESTAMP WITH TIME ZONE,
+
+    CONSTRAINT valid_export_kind CHECK (kind IN ('transactions', 'account')),
+    CONSTRAINT valid_export_format CHECK (format IN ('csv', 'json')),
+    CONSTRAINT valid_export_status CHECK (status IN ('queued', 'running', 'succeeded', 'failed', 'expired'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_export_jobs_user_created ON export_jobs(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_export_jobs_queued ON export_jobs(created_at) WHERE status = 'queued';
+CREATE INDEX IF NOT EXISTS idx_export_jobs_running ON export_jobs(started_at) WHERE status = 'running';
+CREATE INDEX IF NOT EXISTS idx_export_jobs_expiring ON export_jobs(expires_at) WHERE status = 'succeeded';
+
+COMMENT ON COLUMN export_jobs.download_secret IS 'Signs the download links of this job when storage has no presigned URLs';
+COMMENT ON COLUMN export_jobs.expires_at IS 'When the file is deleted and the job marked expired';
@@ -0,0 +1,29 @@
+-- KetoBook: Localized default categories (2026-10-16)
+--
+-- Default categories are seeded in the user's language and remember which
+-- entry of the seed set they came from, so re-seeding adds back the ones a
+-- user deleted without duplicating renamed ones (see category_seeds.rs).
+
+-- Give up instead of queueing behind long transactions (and blocking every query behind us)
+SET LOCAL lock_timeout = '5s';
+
+-- STEP 1: The seed set entry a category was created from
+ALTER TABLE categories ADD COLUMN IF NOT EXISTS seed_key VARCHAR(50);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_categories_user_seed_key ON categories(user_id, seed_key)
+    WHERE seed_key IS NOT NULL;
+
+COMMENT ON COLUMN categories.seed_key IS 'Key of the default category this was seeded from; kept across renames';
+
+-- STEP 2: The locale a user's defaults were seeded in
+ALTER TABLE category_seeds ADD COLUMN IF NOT EXISTS locale VARCHAR(10);
+
+-- STEP 3: Users seeded before this migration got the English built-in set
+UPDATE categories c
+SET seed_key = LOWER(c.name)
+FROM category_seeds s
+WHERE s.user_id = c.user_id
+  AND c.seed_key IS NULL
+  AND LOWER(c.name) IN ('food', 'groceries', 'dining', 'housing', 'rent', 'utilities',
+                        'transport', 'health', 'entertainment', 'shopping', 'salary', 'uncategorized');
+
+UPDATE category_seeds SET locale = 'en' WHERE locale IS NULL;
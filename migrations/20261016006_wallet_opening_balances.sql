@@ -0,0 +1,27 @@
+-- KetoBook: Dated opening balances (2026-10-16)
+--
+-- Users who start tracking a wallet mid-year set its balance at the start of
+-- a past day instead of editing the current balance. Each setting is kept
+-- here; applying it shifts the opening balance of every recorded version of
+-- the wallet, so as-of reads, balance history and the current balance all
+-- agree with it (see opening_balances.rs).
+
+-- STEP 1: One row per opening balance set; the latest one is in force
+CREATE TABLE IF NOT EXISTS wallet_opening_balances (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    user_id VARCHAR(100) NOT NULL,
+    set_by VARCHAR(100) NOT NULL,
+    as_of DATE NOT NULL,
+    balance DECIMAL(15, 2) NOT NULL,
+    adjustment DECIMAL(15, 2) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_opening_balances_wallet ON wallet_opening_balances(wallet_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_wallet_opening_balances_user ON wallet_opening_balances(user_id);
+
+COMMENT ON TABLE wallet_opening_balances IS 'Balances set at the start of a past UTC day; the latest row is in force';
+COMMENT ON COLUMN wallet_opening_balances.balance IS 'Balance at the start of as_of, before its transactions';
+COMMENT ON COLUMN wallet_opening_balances.adjustment IS 'Added to the wallet''s opening balance in every version, and so to every balance';
+COMMENT ON COLUMN wallet_opening_balances.set_by IS 'User who set it: the owner or a manager of the shared wallet';
@@ -0,0 +1,57 @@
+-- KetoBook: Store wallet types as text (2026-10-16)
+--
+-- The API reads and writes `wallet_type` as a string (`Wallet::wallet_type`),
+-- which sqlx can't map to the `wallet_type` enum of the initial schema:
+-- every wallet read failed to decode against a freshly migrated database.
+-- The column becomes VARCHAR with the same four values; custom types stay
+-- 'Other' with `custom_type_id` (see wallet_types.rs). The view and the
+-- history trigger that use the column are recreated as they were.
+
+-- STEP 1: Drop what depends on the column's type
+DROP VIEW IF EXISTS v_wallet_summary;
+DROP TRIGGER IF EXISTS trigger_wallets_history ON wallets;
+
+-- STEP 2: Text columns limited to the built-in types
+ALTER TABLE wallets ALTER COLUMN wallet_type DROP DEFAULT;
+ALTER TABLE wallets ALTER COLUMN wallet_type TYPE VARCHAR(20) USING wallet_type::text;
+ALTER TABLE wallets ALTER COLUMN wallet_type SET DEFAULT 'Cash';
+ALTER TABLE wallet_history ALTER COLUMN wallet_type TYPE VARCHAR(20) USING wallet_type::text;
+
+DO $$ BEGIN
+    ALTER TABLE wallets ADD CONSTRAINT wallets_wallet_type_valid
+        CHECK (wallet_type IN ('Cash', 'BankAccount', 'CreditCard', 'Other'));
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+DROP TYPE IF EXISTS wallet_type;
+
+-- STEP 3: Recreate the history trigger and the summary view
+CREATE TRIGGER trigger_wallets_history
+    AFTER UPDATE ON wallets
+    FOR EACH ROW
+    WHEN ((OLD.name, OLD.wallet_type, OLD.credit_limit, OLD.custom_type_id, OLD.apy, OLD.opening_balance, OLD.group_id)
+          IS DISTINCT FROM (NEW.name, NEW.wallet_type, NEW.credit_limit, NEW.custom_type_id, NEW.apy, NEW.opening_balance, NEW.group_id))
+    EXECUTE FUNCTION record_wallet_history();
+
+CREATE OR REPLACE VIEW v_wallet_summary AS
+SELECT
+    w.id,
+    w.user_id,
+    w.name,
+    w.wallet_type,
+    w.balance,
+    w.credit_limit,
+    CASE
+        WHEN (w.wallet_type = 'CreditCard' OR ct.supports_credit_limit) AND w.credit_limit > 0
+        THEN w.credit_limit - w.balance
+        ELSE w.balance
+    END AS available_balance,
+    COUNT(t.id) AS transaction_count,
+    MAX(t.created_at) AS last_transaction_date,
+    w.created_at,
+    w.updated_at
+FROM wallets w
+LEFT JOIN custom_wallet_types ct ON ct.id = w.custom_type_id
+LEFT JOIN transactions t ON w.id = t.wallet_id
+GROUP BY w.id, w.user_id, w.name, w.wallet_type, w.balance, w.credit_limit, ct.supports_credit_limit, w.created_at, w.updated_at;
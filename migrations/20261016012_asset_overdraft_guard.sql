@@ -0,0 +1,38 @@
+-- KetoBook: Asset wallets can't be overdrawn (2026-10-16)
+--
+-- Handlers lock a wallet and check its funds before moving its balance (see
+-- `lock_and_check_funds` in transactions.rs). This trigger is the backstop
+-- for any path that misses the check: an asset wallet's balance may not go
+-- below zero. It raises a check violation of
+-- `wallets_asset_balance_not_negative`, which the API answers with 400
+-- "Insufficient balance" instead of a database error. The initial schema's
+-- `balance_non_negative` CHECK still applies to every wallet; the trigger
+-- runs before it, so an asset wallet is reported by name.
+
+-- STEP 1: Whether a wallet's type holds money owed
+CREATE OR REPLACE FUNCTION wallet_is_liability(wallet_type VARCHAR, custom_type_id UUID)
+RETURNS BOOLEAN AS $$
+    SELECT CASE
+        WHEN custom_type_id IS NOT NULL THEN COALESCE(
+            (SELECT behavior = 'liability' FROM custom_wallet_types WHERE id = custom_type_id), FALSE)
+        ELSE wallet_type = 'CreditCard'
+    END
+$$ LANGUAGE sql STABLE;
+
+COMMENT ON FUNCTION wallet_is_liability(VARCHAR, UUID) IS 'Whether a wallet of this built-in or custom type is a liability';
+
+-- STEP 2: Refuse a negative balance on an asset wallet
+CREATE OR REPLACE FUNCTION check_asset_balance() RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.balance < 0 AND NOT wallet_is_liability(NEW.wallet_type::text, NEW.custom_type_id) THEN
+        RAISE EXCEPTION 'Asset wallet % can''t go below zero (balance %)', NEW.id, NEW.balance
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'wallets_asset_balance_not_negative';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_wallets_asset_balance ON wallets;
+CREATE TRIGGER trg_wallets_asset_balance
+    BEFORE INSERT OR UPDATE OF balance ON wallets
+    FOR EACH ROW EXECUTE FUNCTION check_asset_balance();
@@ -0,0 +1,507 @@
+use std::collections::BTreeMap;
+
+use actix_web::http::Method;
+use actix_web::{web, HttpRequest, HttpResponse};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::admin::authorize_admin;
+use crate::api_keys;
+use crate::auth::{self, AuthUser};
+use crate::cache::{invalidate_user_cache, CacheBackend};
+use crate::config::AppConfig;
+use crate::impersonation;
+use crate::models::{AccountMerge, ApiResponse, LinkAccountRequest, MergeAccountsRequest};
+use crate::routes::ScopedRoutes;
+
+// ==================== ACCOUNT MERGES ====================
+//
+// Someone who ends up with two accounts (e.g. signed up twice, once through
+// a different login) can fold one into the other:
+//
+// - The user, signed in as the account to keep, posts a bearer token of the
+//   other account (`POST /api/account-merges/{user_id}`), proving they own
+//   both. API keys and impersonation tokens can't start a merge.
+// - Support merges any two user ids (`POST /api/admin/account-merges`), with
+//   who asks and why.
+//
+// Everything the source account owns moves to the target in one database
+// transaction, table by table as listed in `TABLES`. Where the target already
+// has a row the source's would clash with:
+//
+// - Named things (wallet types, income sources, templates, groups, import
+//   presets) keep both; the source's gets " (merged)" appended to its name.
+// - Settings and budgets keep the target's; the source's row is dropped.
+//
+// Moved transactions take new sequence numbers after the target's last one,
+// in their old order. The source's API keys, impersonation sessions and login
+// devices are revoked and its login is deleted. The merge is recorded in
+// `account_merges`, which the database refuses to update or delete, and
+// written to the `audit` log target. There is no undo.
+//
+// ============================================================================
+
+/// Appended to a moved row's name when the target already uses it
+const RENAMED_SUFFIX: &str = " (merged)";
+
+const MERGE_COLUMNS: &str =
+    "id, source_user_id, target_user_id, initiated_by, requested_by, reason, moved, renamed, dropped, merged_at";
+
+/// What happens to a source row that clashes with one of the target's
+enum Conflict {
+    /// No per-user uniqueness; every row moves
+    None,
+    /// Rows matching a target row on `key` get `RENAMED_SUFFIX` appended to
+    /// `column`, cut to `max_len`
+    Rename {
+        column: &'static str,
+        max_len: usize,
+        key: &'static str,
+    },
+    /// Rows matching a target row on `key` are dropped
+    KeepTarget { key: &'static str },
+}
+
+/// Tables moved by a merge, besides transactions (renumbered separately).
+/// `key` conditions compare a source row `s` with a target row `d`.
+const TABLES: &[(&str, Conflict)] = &[
+    ("wallets", Conflict::None),
+    ("transaction_splits", Conflict::None),
+    ("wallet_buckets", Conflict::None),
+    ("wallet_freezes", Conflict::None),
+    ("wallet_freeze_events", Conflict::None),
+    ("wallet_history", Conflict::None),
+    ("wallet_balance_snapshots", Conflict::None),
+    ("wallet_opening_balances", Conflict::None),
+    ("wallet_summary_snapshots", Conflict::None),
+    ("wallet_members", Conflict::KeepTarget { key: "d.wallet_id = s.wallet_id" }),
+    ("wallet_balance_replay", Conflict::None),
+    ("wallet_top_up_rules", Conflict::None),
+    ("wallet_top_ups", Conflict::None),
+    ("wallet_transfers", Conflict::None),
+    ("pending_transfers", Conflict::None),
+    ("credit_card_terms", Conflict::None),
+    ("credit_card_statements", Conflict::None),
+    ("installment_plans", Conflict::None),
+    ("savings_goals", Conflict::None),
+    ("goal_contributions", Conflict::None),
+    (
+        "custom_wallet_types",
+        Conflict::Rename { column: "name", max_len: 50, key: "d.name = s.name" },
+    ),
+    (
+        "portfolio_groups",
+        Conflict::Rename { column: "name", max_len: 100, key: "LOWER(d.name) = LOWER(s.name)" },
+    ),
+    (
+        "income_sources",
+        Conflict::Rename { column: "name", max_len: 100, key: "d.name = s.name" },
+    ),
+    (
+        "transaction_templates",
+        Conflict::Rename { column: "name", max_len: 100, key: "d.name = s.name" },
+    ),
+    (
+        "import_presets",
+        Conflict::Rename { column: "name", max_len: 100, key: "LOWER(d.name) = LOWER(s.name)" },
+    ),
+    ("transaction_field_definitions", Conflict::KeepTarget { key: "d.key = s.key" }),
+    ("categories", Conflict::KeepTarget { key: "LOWER(d.name) = LOWER(s.name)" }),
+    ("category_seeds", Conflict::KeepTarget { key: "TRUE" }),
+    ("tax_category_mappings", Conflict::KeepTarget { key: "d.category = s.category" }),
+    ("user_currency_settings", Conflict::KeepTarget { key: "TRUE" }),
+    (
+        "budgets",
+        Conflict::KeepTarget { key: "COALESCE(d.category, '') = COALESCE(s.category, '') AND d.period = s.period" },
+    ),
+    ("budget_events", Conflict::None),
+    ("transaction_imports", Conflict::None),
+    ("recurring_transactions", Conflict::None),
+    ("recurring_transaction_runs", Conflict::None),
+    ("standing_orders", Conflict::None),
+    ("standing_order_runs", Conflict::None),
+    ("debts", Conflict::None),
+    ("debt_settlements", Conflict::None),
+    ("bill_splits", Conflict::None),
+    ("reimbursements", Conflict::None),
+    ("attachments", Conflict::None),
+    ("report_subscriptions", Conflict::None),
+    ("report_deliveries", Conflict::None),
+    ("push_devices", Conflict::None),
+    ("notifications", Conflict::KeepTarget { key: "d.notification_key = s.notification_key" }),
+    ("notification_settings", Conflict::KeepTarget { key: "TRUE" }),
+    ("webhook_endpoints", Conflict::None),
+    ("event_outbox", Conflict::None),
+    ("webhook_deliveries", Conflict::None),
+];
+
+/// Why a merge didn't happen
+enum MergeError {
+    /// The source account has no login and owns nothing
+    UnknownSource,
+    /// A renamed row still clashed with one of the target's
+    Clash,
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for MergeError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => MergeError::Clash,
+            e => MergeError::Database(e),
+        }
+    }
+}
+
+impl MergeError {
+    fn to_response(&self) -> HttpResponse {
+        match self {
+            MergeError::UnknownSource => HttpResponse::NotFound()
+                .json(ApiResponse::<AccountMerge>::error("No account to merge with this user_id".to_string())),
+            MergeError::Clash => HttpResponse::Conflict().json(ApiResponse::<AccountMerge>::error(format!(
+                "The target already has a name ending in \"{}\" that the merge would reuse; rename it and retry",
+                RENAMED_SUFFIX
+            ))),
+            MergeError::Database(e) => {
+                log::error!("Error merging accounts: {}", e);
+                HttpResponse::InternalServerError()
+                    .json(ApiResponse::<AccountMerge>::error("Failed to merge accounts".to_string()))
+            }
+        }
+    }
+}
+
+// ==================== Handlers ====================
+
+/// Merge another account of the caller, proven by its bearer token, into this one
+pub async fn link_account(
+    http_req: HttpRequest,
+    user: AuthUser,
+    req: web::Json<LinkAccountRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    let Some(secret) = config.jwt_secret.as_deref() else {
+        return HttpResponse::Forbidden()
+            .json(ApiResponse::<AccountMerge>::error("Authentication is disabled".to_string()));
+    };
+    let headers = http_req.headers();
+    if headers.contains_key(api_keys::KEY_HEADER) || headers.contains_key(impersonation::TOKEN_HEADER) {
+        return HttpResponse::Forbidden().json(ApiResponse::<AccountMerge>::error(
+            "Accounts can only be merged with the user's own login".to_string(),
+        ));
+    }
+
+    let source_user_id = match auth::token_user(db.get_ref(), secret, req.source_token.trim()).await {
+        Ok(Some(source)) if source.user_id == user.user_id => {
+            return HttpResponse::BadRequest()
+                .json(ApiResponse::<AccountMerge>::error("source_token is a token of this account".to_string()));
+        }
+        Ok(Some(source)) => source.user_id,
+        Ok(None) => {
+            return HttpResponse::Unauthorized()
+                .json(ApiResponse::<AccountMerge>::error("source_token is invalid or expired".to_string()));
+        }
+        Err(e) => {
+            log::error!("Error checking source token: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<AccountMerge>::error("Database error".to_string()));
+        }
+    };
+
+    let result = merge_accounts(db.get_ref(), &source_user_id, &user.user_id, "user", &user.user_id, None).await;
+    finish(result, cache.get_ref()).await
+}
+
+/// Merge one account into another (admin)
+pub async fn merge_accounts_admin(
+    http_req: HttpRequest,
+    req: web::Json<MergeAccountsRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    let requested_by = req.requested_by.trim();
+    let reason = req.reason.trim();
+    let invalid = if req.source_user_id.is_empty() || req.target_user_id.is_empty() {
+        Some("source_user_id and target_user_id are required".to_string())
+    } else if req.source_user_id == req.target_user_id {
+        Some("source_user_id and target_user_id must differ".to_string())
+    } else if requested_by.is_empty() || requested_by.chars().count() > 100 {
+        Some("requested_by must be 1-100 characters".to_string())
+    } else if reason.is_empty() || reason.chars().count() > 500 {
+        Some("reason must be 1-500 characters".to_string())
+    } else {
+        None
+    };
+    if let Some(msg) = invalid {
+        return HttpResponse::BadRequest().json(ApiResponse::<AccountMerge>::error(msg));
+    }
+
+    let result = merge_accounts(
+        db.get_ref(),
+        &req.source_user_id,
+        &req.target_user_id,
+        "admin",
+        requested_by,
+        Some(reason),
+    )
+    .await;
+    finish(result, cache.get_ref()).await
+}
+
+/// Merges into or out of a user's account, newest first
+pub async fn get_account_merges(user_id: web::Path<String>, db: web::Data<PgPool>) -> HttpResponse {
+    let result = sqlx::query_as::<_, AccountMerge>(&format!(
+        "SELECT {} FROM account_merges
+         WHERE source_user_id = $1 OR target_user_id = $1
+         ORDER BY merged_at DESC",
+        MERGE_COLUMNS
+    ))
+    .bind(user_id.as_str())
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(merges) => HttpResponse::Ok().json(ApiResponse::success(merges)),
+        Err(e) => {
+            log::error!("Error fetching account merges: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<AccountMerge>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Answer a merge, invalidating both users' caches once it committed
+async fn finish(result: Result<AccountMerge, MergeError>, cache: &dyn CacheBackend) -> HttpResponse {
+    match result {
+        Ok(merge) => {
+            let _ = invalidate_user_cache(cache, &merge.source_user_id).await;
+            let _ = invalidate_user_cache(cache, &merge.target_user_id).await;
+            log::info!(
+                target: "audit",
+                "Account {} merged into {} ({} by {}, merge {}): moved {}, renamed {}, dropped {}",
+                merge.source_user_id,
+                merge.target_user_id,
+                merge.initiated_by,
+                merge.requested_by,
+                merge.id,
+                merge.moved,
+                merge.renamed,
+                merge.dropped
+            );
+            HttpResponse::Created().json(ApiResponse::success(merge))
+        }
+        Err(e) => e.to_response(),
+    }
+}
+
+// ==================== Merge ====================
+
+/// Move everything of `source` to `target` and record it, in one transaction
+async fn merge_accounts(
+    pool: &PgPool,
+    source: &str,
+    target: &str,
+    initiated_by: &str,
+    requested_by: &str,
+    reason: Option<&str>,
+) -> Result<AccountMerge, MergeError> {
+    let mut tx = pool.begin().await?;
+
+    // Concurrent merges of either account wait, in a fixed order so two
+    // merges in opposite directions can't deadlock
+    let mut users = [source, target];
+    users.sort();
+    for user in users {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('account_merge:' || $1))")
+            .bind(user)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let (exists,): (bool,) = sqlx::query_as(
+        "SELECT EXISTS (SELECT 1 FROM users WHERE id = $1)
+             OR EXISTS (SELECT 1 FROM wallets WHERE user_id = $1)
+             OR EXISTS (SELECT 1 FROM debts WHERE user_id = $1)",
+    )
+    .bind(source)
+    .fetch_one(&mut *tx)
+    .await?;
+    if !exists {
+        return Err(MergeError::UnknownSource);
+    }
+
+    let mut moved = BTreeMap::new();
+    let mut renamed = BTreeMap::new();
+    let mut dropped = BTreeMap::new();
+
+    let transactions = move_transactions(&mut tx, source, target).await?;
+    if transactions > 0 {
+        moved.insert("transactions", transactions);
+    }
+
+    for (table, conflict) in TABLES {
+        match conflict {
+            Conflict::None => {}
+            Conflict::Rename { column, max_len, key } => {
+                let count = sqlx::query(&format!(
+                    "UPDATE {table} s SET {column} = LEFT(s.{column}, {keep}) || $3
+                     WHERE s.user_id = $1 AND EXISTS (SELECT 1 FROM {table} d WHERE d.user_id = $2 AND {key})",
+                    table = table,
+                    column = column,
+                    keep = max_len - RENAMED_SUFFIX.chars().count(),
+                    key = key,
+                ))
+                .bind(source)
+                .bind(target)
+                .bind(RENAMED_SUFFIX)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+                if count > 0 {
+                    renamed.insert(*table, count);
+                }
+            }
+            Conflict::KeepTarget { key } => {
+                let count = sqlx::query(&format!(
+                    "DELETE FROM {table} s
+                     WHERE s.user_id = $1 AND EXISTS (SELECT 1 FROM {table} d WHERE d.user_id = $2 AND {key})",
+                    table = table,
+                    key = key,
+                ))
+                .bind(source)
+                .bind(target)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+                if count > 0 {
+                    dropped.insert(*table, count);
+                }
+            }
+        }
+
+        let count = sqlx::query(&format!("UPDATE {} SET user_id = $2 WHERE user_id = $1", table))
+            .bind(source)
+            .bind(target)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        if count > 0 {
+            moved.insert(*table, count);
+        }
+    }
+
+    // The source account is closed: its delegated access ends and it can't log in
+    sqlx::query(
+        "UPDATE api_keys SET revoked_at = CURRENT_TIMESTAMP WHERE user_id = $1 AND revoked_at IS NULL",
+    )
+    .bind(source)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        "UPDATE impersonation_sessions SET revoked_at = CURRENT_TIMESTAMP WHERE user_id = $1 AND revoked_at IS NULL",
+    )
+    .bind(source)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("UPDATE login_devices SET revoked_at = CURRENT_TIMESTAMP WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(source)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(source)
+        .execute(&mut *tx)
+        .await?;
+
+    let merge = sqlx::query_as::<_, AccountMerge>(&format!(
+        "INSERT INTO account_merges
+             (id, source_user_id, target_user_id, initiated_by, requested_by, reason, moved, renamed, dropped)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+         RETURNING {}",
+        MERGE_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(source)
+    .bind(target)
+    .bind(initiated_by)
+    .bind(requested_by)
+    .bind(reason)
+    .bind(serde_json::json!(moved))
+    .bind(serde_json::json!(renamed))
+    .bind(serde_json::json!(dropped))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(merge)
+}
+
+/// Move the source's transactions, numbered after the target's last one in
+/// their old order; returns how many moved
+///
+/// The source's counter row stays locked until commit, so no transaction of
+/// the source can be inserted (and left behind) meanwhile. The numbers are
+/// set here under `ketobook.renumbering`, which the sequence trigger honours.
+async fn move_transactions(tx: &mut Transaction<'_, Postgres>, source: &str, target: &str) -> Result<u64, sqlx::Error> {
+    sqlx::query("SELECT seed_transaction_counter($1), seed_transaction_counter($2)")
+        .bind(source)
+        .bind(target)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query("SELECT 1 FROM user_transaction_counters WHERE user_id = $1 FOR UPDATE")
+        .bind(source)
+        .execute(&mut **tx)
+        .await?;
+
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM transactions WHERE user_id = $1")
+        .bind(source)
+        .fetch_one(&mut **tx)
+        .await?;
+    if count == 0 {
+        return Ok(0);
+    }
+
+    let (base,): (i64,) = sqlx::query_as(
+        "UPDATE user_transaction_counters
+         SET last_sequence = last_sequence + $2, updated_at = CURRENT_TIMESTAMP
+         WHERE user_id = $1
+         RETURNING last_sequence - $2",
+    )
+    .bind(target)
+    .bind(count)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    sqlx::query("SET LOCAL ketobook.renumbering = 'on'").execute(&mut **tx).await?;
+    let moved = sqlx::query(
+        "UPDATE transactions t SET user_id = $2, sequence_number = $3 + m.n
+         FROM (SELECT id, ROW_NUMBER() OVER (ORDER BY sequence_number NULLS FIRST, created_at, id) AS n
+               FROM transactions WHERE user_id = $1) m
+         WHERE t.id = m.id",
+    )
+    .bind(source)
+    .bind(target)
+    .bind(base)
+    .execute(&mut **tx)
+    .await?
+    .rows_affected();
+    Ok(moved)
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/account-merges")
+        .user(Method::GET, "/{user_id}", get_account_merges)
+        .user(Method::POST, "/{user_id}", link_account)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
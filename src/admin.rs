@@ -0,0 +1,263 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpRequest, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::account_merges;
+use crate::anonymize;
+use crate::backups;
+use crate::cache::{invalidate_user_cache, CacheBackend};
+use crate::config::AppConfig;
+use crate::currencies;
+use crate::email_templates;
+use crate::impersonation;
+use crate::import_presets;
+use crate::lockout::{self, LockoutPolicy};
+use crate::log_control;
+use crate::online_migrations;
+use crate::policy;
+use crate::provider_webhooks;
+use crate::routes::ScopedRoutes;
+use crate::models::{ApiResponse, ReplayQuery, ReplayReport, WalletReplay};
+
+// ==================== BALANCE REPLAY ====================
+//
+// Recovery tool for balance drift. Every wallet satisfies the invariant
+//
+//   balance = opening_balance + SUM(income) - SUM(expense)
+//
+// over its transaction history. A replay run recomputes the right-hand side
+// for every wallet of a user into the `wallet_balance_replay` shadow table,
+// diffs it against the live balance, and (with `?apply=true`) swaps the
+// replayed balance into `wallets` for mismatched rows.
+//
+// The whole run happens in one DB transaction with the user's wallets locked
+// (`FOR UPDATE`), so concurrent transaction writes can't interleave with it.
+//
+// ============================================================================
+
+/// Header carrying the admin token
+pub(crate) const ADMIN_TOKEN_HEADER: &str = "X-Admin-Token";
+
+/// Lockout scope for admin token failures (subject: client IP)
+const ADMIN_LOCKOUT_SCOPE: &str = "admin";
+
+// ==================== Authorization ====================
+
+/// Check the admin token header against `ADMIN_TOKEN`
+///
+/// Admin endpoints are disabled entirely when no token is configured.
+/// Repeated wrong tokens from one client IP lock it out (see lockout.rs).
+pub(crate) async fn authorize_admin(
+    req: &HttpRequest,
+    config: &AppConfig,
+    cache: &dyn CacheBackend,
+) -> Result<(), HttpResponse> {
+    let provided = req
+        .headers()
+        .get(ADMIN_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    verify_admin_token(req, config, cache, provided).await
+}
+
+/// Check `provided` against `ADMIN_TOKEN`, counting failures toward the
+/// client IP's lockout like `authorize_admin` (the admin panel's sign-in)
+pub(crate) async fn verify_admin_token(
+    req: &HttpRequest,
+    config: &AppConfig,
+    cache: &dyn CacheBackend,
+    provided: &str,
+) -> Result<(), HttpResponse> {
+    let expected = match &config.admin_token {
+        Some(token) if !token.is_empty() => token,
+        _ => {
+            return Err(HttpResponse::Forbidden()
+                .json(ApiResponse::<String>::error("Admin endpoints are disabled".to_string())));
+        }
+    };
+
+    let client_ip = req
+        .connection_info()
+        .realip_remote_addr()
+        .unwrap_or("unknown")
+        .to_string();
+    let policy = LockoutPolicy::from_config(config);
+
+    lockout::check(cache, &policy, None, ADMIN_LOCKOUT_SCOPE, &client_ip, &client_ip)
+        .await
+        .map_err(|rejection| rejection.to_response())?;
+
+    if constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+        lockout::record_success(cache, ADMIN_LOCKOUT_SCOPE, &client_ip).await;
+        Ok(())
+    } else if let Some(retry_after) = lockout::record_failure(cache, &policy, ADMIN_LOCKOUT_SCOPE, &client_ip).await {
+        Err(lockout::Rejection::Locked(retry_after).to_response())
+    } else {
+        Err(HttpResponse::Unauthorized()
+            .json(ApiResponse::<String>::error("Invalid admin token".to_string())))
+    }
+}
+
+/// Compare two byte strings without short-circuiting on the first difference
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// ==================== Handlers ====================
+
+/// Rebuild a user's wallet balances from transaction history
+///
+/// `POST /api/admin/replay/{user_id}?apply=true|false`
+pub async fn replay_user_balances(
+    req: HttpRequest,
+    user_id: web::Path<String>,
+    query: web::Query<ReplayQuery>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    let user_id = user_id.into_inner();
+
+    match run_replay(db.get_ref(), &user_id, query.apply).await {
+        Ok(report) => {
+            if report.applied && report.mismatch_count > 0 {
+                let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+            }
+            log::info!(
+                "Balance replay {} for user {}: {} wallet(s), {} mismatch(es), applied={}",
+                report.run_id, user_id, report.wallet_count, report.mismatch_count, report.applied
+            );
+            HttpResponse::Ok().json(ApiResponse::success(report))
+        }
+        Err(e) => {
+            log::error!("Balance replay failed for user {}: {}", user_id, e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<ReplayReport>::error("Balance replay failed".to_string()))
+        }
+    }
+}
+
+// ==================== Replay ====================
+
+async fn run_replay(pool: &PgPool, user_id: &str, apply: bool) -> Result<ReplayReport, sqlx::Error> {
+    let run_id = Uuid::new_v4();
+    let replayed_at = Utc::now();
+
+    let mut db_tx = pool.begin().await?;
+
+    // Lock the user's wallets so the history can't change mid-replay
+    sqlx::query("SELECT id FROM wallets WHERE user_id = $1 FOR UPDATE")
+        .bind(user_id)
+        .execute(&mut *db_tx)
+        .await?;
+
+    let mut wallets = sqlx::query_as::<_, WalletReplay>(
+        "INSERT INTO wallet_balance_replay
+             (run_id, wallet_id, user_id, live_balance, replayed_balance, transaction_count,
+              total_income, total_expense, replayed_at)
+         SELECT $1, w.id, w.user_id, w.balance,
+                w.opening_balance
+                    + COALESCE(SUM(t.amount) FILTER (WHERE t.transaction_type = 'income'), 0)
+                    - COALESCE(SUM(t.amount) FILTER (WHERE t.transaction_type = 'expense'), 0),
+                COUNT(t.id),
+                COALESCE(SUM(t.amount) FILTER (WHERE t.transaction_type = 'income'), 0),
+                COALESCE(SUM(t.amount) FILTER (WHERE t.transaction_type = 'expense'), 0),
+                $3
+         FROM wallets w
+         LEFT JOIN transactions t ON t.wallet_id = w.id AND t.deleted_at IS NULL
+         WHERE w.user_id = $2 AND w.deleted_at IS NULL
+         GROUP BY w.id, w.user_id, w.balance, w.opening_balance
+         RETURNING wallet_id, live_balance, replayed_balance, transaction_count, total_income, total_expense, applied",
+    )
+    .bind(run_id)
+    .bind(user_id)
+    .bind(replayed_at)
+    .fetch_all(&mut *db_tx)
+    .await?;
+
+    let mismatch_count = wallets.iter().filter(|w| w.is_mismatch()).count();
+
+    if apply && mismatch_count > 0 {
+        sqlx::query(
+            "UPDATE wallets w
+             SET balance = r.replayed_balance
+             FROM wallet_balance_replay r
+             WHERE r.run_id = $1 AND r.wallet_id = w.id AND r.replayed_balance <> w.balance",
+        )
+        .bind(run_id)
+        .execute(&mut *db_tx)
+        .await?;
+
+        sqlx::query(
+            "UPDATE wallet_balance_replay SET applied = TRUE
+             WHERE run_id = $1 AND replayed_balance <> live_balance",
+        )
+        .bind(run_id)
+        .execute(&mut *db_tx)
+        .await?;
+
+        for wallet in wallets.iter_mut().filter(|w| w.is_mismatch()) {
+            wallet.applied = true;
+        }
+    }
+
+    db_tx.commit().await?;
+
+    Ok(ReplayReport {
+        run_id,
+        user_id: user_id.to_string(),
+        replayed_at,
+        applied: apply && mismatch_count > 0,
+        wallet_count: wallets.len(),
+        mismatch_count,
+        wallets,
+    })
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/admin")
+        .admin(Method::POST, "/replay/{user_id}", replay_user_balances)
+        .extended()
+        .admin(Method::POST, "/impersonations", impersonation::create_impersonation)
+        .admin(Method::POST, "/impersonations/{session_id}/revoke", impersonation::revoke_impersonation)
+        .admin(Method::GET, "/impersonations/{session_id}/events", impersonation::get_impersonation_events)
+        .admin(Method::GET, "/users/{user_id}/impersonations", impersonation::get_user_impersonations)
+        .admin(Method::GET, "/users/{user_id}/export-anonymized", anonymize::export_anonymized)
+        .admin(Method::POST, "/account-merges", account_merges::merge_accounts_admin)
+        .admin(Method::GET, "/transaction-policy", policy::get_transaction_policy)
+        .admin(Method::PUT, "/transaction-policy", policy::update_transaction_policy)
+        .admin(Method::PUT, "/exchange-rates", currencies::upsert_exchange_rates)
+        .admin(Method::POST, "/exchange-rates/refresh", currencies::refresh_exchange_rates)
+        .extended()
+        .admin(Method::POST, "/import-presets", import_presets::create_community_preset)
+        .admin(Method::PUT, "/import-presets/{preset_id}", import_presets::update_community_preset)
+        .admin(Method::DELETE, "/import-presets/{preset_id}", import_presets::delete_community_preset)
+        .admin(Method::GET, "/email-templates", email_templates::list_templates)
+        .admin(Method::POST, "/email-templates/preview", email_templates::preview_template)
+        .admin(Method::GET, "/email-templates/{name}/{locale}", email_templates::get_template_versions)
+        .admin(Method::POST, "/email-templates/{name}/{locale}", email_templates::save_template)
+        .admin(Method::GET, "/backups", backups::list_backups)
+        .admin(Method::POST, "/backups", backups::trigger_backup)
+        .admin(Method::POST, "/backups/{backup_id}/verify", backups::verify_backup)
+        .admin(Method::GET, "/migrations", online_migrations::get_migration_status)
+        .admin(Method::GET, "/logging", log_control::get_log_settings)
+        .admin(Method::PUT, "/logging", log_control::update_log_settings)
+        .admin(Method::DELETE, "/logging", log_control::reset_log_settings)
+        .extended()
+        .admin(Method::GET, "/provider-webhooks/dead-letters", provider_webhooks::list_dead_letters)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
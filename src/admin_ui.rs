@@ -0,0 +1,317 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::http::header::{ContentType, LOCATION};
+use actix_web::http::{Method, StatusCode};
+use actix_web::{web, HttpRequest, HttpResponse};
+use handlebars::Handlebars;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use serde_json::json;
+use sha2::Sha256;
+use sqlx::PgPool;
+
+use crate::admin::verify_admin_token;
+use crate::audit;
+use crate::cache::{self, CacheBackend};
+use crate::config::AppConfig;
+use crate::jobs;
+use crate::models::{AdminLoginForm, AdminUserSearchQuery, AdminUserSummary};
+use crate::routes::ScopedRoutes;
+use crate::telemetry;
+use crate::wallets::fetch_wallets_from_db;
+
+// ==================== ADMIN PANEL ====================
+//
+// A read-only, server-rendered panel under `/admin` for operators: user
+// lookup with wallet balances, recent audit events, the background jobs and
+// the cache. Pages are Handlebars templates from `templates/admin/`, with
+// values HTML-escaped, so no frontend needs to be deployed.
+//
+// Browsers can't send `X-Admin-Token`, so operators sign in with the admin
+// token once (failures count toward the same lockout as the admin API) and
+// get a session cookie: `{expires}.{HMAC-SHA256 of the expiry keyed with the
+// token}`, valid for `SESSION_TTL`. Changing `ADMIN_TOKEN` signs everyone
+// out. The cookie is HttpOnly and SameSite=Strict, which keeps other sites
+// from using it for the sign-out form, the only other POST.
+//
+// Job status is this instance's: other replicas run the same schedules, so
+// runs taken by another instance show as skipped. Cache counts are also per
+// instance, since startup.
+//
+// ============================================================================
+
+type HmacSha256 = Hmac<Sha256>;
+
+const SESSION_COOKIE: &str = "ketobook_admin";
+
+/// How long a sign-in lasts
+const SESSION_TTL: Duration = Duration::from_secs(8 * 3600);
+
+/// Signed with the expiry, so the MAC can't be reused for anything else
+const SESSION_CONTEXT: &str = "ketobook-admin-session:";
+
+/// Rows shown per list
+const USER_SEARCH_LIMIT: i64 = 50;
+const AUDIT_LIMIT: i64 = 50;
+
+const PAGES: [(&str, &str); 6] = [
+    ("layout", include_str!("../templates/admin/layout.hbs")),
+    ("audit_table", include_str!("../templates/admin/audit_table.hbs")),
+    ("login", include_str!("../templates/admin/login.hbs")),
+    ("dashboard", include_str!("../templates/admin/dashboard.hbs")),
+    ("users", include_str!("../templates/admin/users.hbs")),
+    ("user", include_str!("../templates/admin/user.hbs")),
+];
+
+static TEMPLATES: LazyLock<Handlebars<'static>> = LazyLock::new(|| {
+    let mut handlebars = Handlebars::new();
+    for (name, source) in PAGES {
+        if let Err(e) = handlebars.register_template_string(name, source) {
+            log::error!("Admin panel template '{}' doesn't compile: {}", name, e);
+        }
+    }
+    handlebars
+});
+
+/// Render a page with `data`
+fn render(status: StatusCode, page: &str, data: &impl Serialize) -> HttpResponse {
+    match TEMPLATES.render(page, data) {
+        Ok(html) => HttpResponse::build(status).content_type(ContentType::html()).body(html),
+        Err(e) => {
+            log::error!("Error rendering admin page '{}': {}", page, e);
+            HttpResponse::InternalServerError().content_type(ContentType::plaintext()).body("Failed to render page")
+        }
+    }
+}
+
+fn server_error(context: &str, e: sqlx::Error) -> HttpResponse {
+    log::error!("{}: {}", context, e);
+    HttpResponse::InternalServerError().content_type(ContentType::plaintext()).body(context.to_string())
+}
+
+fn redirect(location: &str) -> HttpResponse {
+    HttpResponse::SeeOther().insert_header((LOCATION, location)).finish()
+}
+
+// ==================== Sessions ====================
+
+fn session_mac(token: &str, expires: i64) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(token.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}{}", SESSION_CONTEXT, expires).as_bytes());
+    mac
+}
+
+fn session_cookie(req: &HttpRequest, value: String, max_age: Duration) -> Cookie<'static> {
+    Cookie::build(SESSION_COOKIE, value)
+        .path("/admin")
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .secure(req.connection_info().scheme() == "https")
+        .max_age(actix_web::cookie::time::Duration::seconds(max_age.as_secs() as i64))
+        .finish()
+}
+
+/// Let a signed-in operator through; otherwise send them to the sign-in page
+fn require_session(req: &HttpRequest, config: &AppConfig) -> Result<(), HttpResponse> {
+    let Some(token) = config.admin_token.as_deref().filter(|token| !token.is_empty()) else {
+        return Err(HttpResponse::Forbidden()
+            .content_type(ContentType::plaintext())
+            .body("Admin endpoints are disabled"));
+    };
+    let valid = req.cookie(SESSION_COOKIE).is_some_and(|cookie| {
+        let Some((expires, signature)) = cookie.value().split_once('.') else {
+            return false;
+        };
+        let (Ok(expires), Ok(signature)) = (expires.parse::<i64>(), hex::decode(signature)) else {
+            return false;
+        };
+        expires > chrono::Utc::now().timestamp() && session_mac(token, expires).verify_slice(&signature).is_ok()
+    });
+    if valid { Ok(()) } else { Err(redirect("/admin/login")) }
+}
+
+// ==================== Handlers ====================
+
+/// `GET /admin/login` - the sign-in form
+pub async fn login_page(config: web::Data<AppConfig>) -> HttpResponse {
+    let error = config
+        .admin_token
+        .as_deref()
+        .is_none_or(str::is_empty)
+        .then_some("Admin endpoints are disabled");
+    render(StatusCode::OK, "login", &json!({ "error": error }))
+}
+
+/// `POST /admin/login` - check the admin token and start a session
+pub async fn login(
+    req: HttpRequest,
+    form: web::Form<AdminLoginForm>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = verify_admin_token(&req, &config, cache.get_ref(), &form.token).await {
+        let status = response.status();
+        let error = match status {
+            StatusCode::UNAUTHORIZED => "Invalid admin token",
+            _ if config.admin_token.as_deref().is_none_or(str::is_empty) => "Admin endpoints are disabled",
+            _ => "Too many failed attempts; try again later",
+        };
+        return render(status, "login", &json!({ "error": error }));
+    }
+
+    let expires = chrono::Utc::now().timestamp() + SESSION_TTL.as_secs() as i64;
+    let token = config.admin_token.as_deref().unwrap_or_default();
+    let signature = hex::encode(session_mac(token, expires).finalize().into_bytes());
+    log::info!(target: "audit", "admin panel sign-in from {}", req.connection_info().realip_remote_addr().unwrap_or("unknown"));
+
+    let mut response = redirect("/admin");
+    if let Err(e) = response.add_cookie(&session_cookie(&req, format!("{}.{}", expires, signature), SESSION_TTL)) {
+        log::error!("Error setting admin session cookie: {}", e);
+    }
+    response
+}
+
+/// `POST /admin/logout` - end the session
+pub async fn logout(req: HttpRequest) -> HttpResponse {
+    let mut response = redirect("/admin/login");
+    if let Err(e) = response.add_cookie(&session_cookie(&req, String::new(), Duration::ZERO)) {
+        log::error!("Error clearing admin session cookie: {}", e);
+    }
+    response
+}
+
+/// `GET /admin` - jobs, cache and the latest audit events
+pub async fn dashboard(
+    req: HttpRequest,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = require_session(&req, &config) {
+        return response;
+    }
+
+    let executions = match jobs::execution_summaries(db.get_ref()).await {
+        Ok(executions) => executions,
+        Err(e) => return server_error("Failed to fetch job executions", e),
+    };
+    let audit = match audit::fetch_recent_entries(db.get_ref(), None, AUDIT_LIMIT).await {
+        Ok(audit) => audit,
+        Err(e) => return server_error("Failed to fetch audit events", e),
+    };
+
+    render(
+        StatusCode::OK,
+        "dashboard",
+        &json!({
+            "signed_in": true,
+            "jobs": jobs::job_statuses(),
+            "executions": executions,
+            "cache": cache::stats(cache.get_ref()),
+            "audit": audit,
+        }),
+    )
+}
+
+/// `GET /admin/users?q=` - users whose id or email starts with `q`
+pub async fn search_users(
+    req: HttpRequest,
+    query: web::Query<AdminUserSearchQuery>,
+    db: web::Data<PgPool>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = require_session(&req, &config) {
+        return response;
+    }
+
+    let q = query.q.as_deref().map(str::trim).unwrap_or_default();
+    let users = if q.is_empty() {
+        Vec::new()
+    } else {
+        match fetch_users_by_prefix(db.get_ref(), q).await {
+            Ok(users) => users,
+            Err(e) => return server_error("Failed to search users", e),
+        }
+    };
+    render(StatusCode::OK, "users", &json!({ "signed_in": true, "q": q, "users": users }))
+}
+
+/// `GET /admin/users/{user_id}` - a user's wallets and recent audit events
+pub async fn user_detail(
+    req: HttpRequest,
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = require_session(&req, &config) {
+        return response;
+    }
+    let user_id = user_id.into_inner();
+
+    let user = match fetch_user(db.get_ref(), &user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return HttpResponse::NotFound().content_type(ContentType::plaintext()).body("User not found");
+        }
+        Err(e) => return server_error("Failed to fetch user", e),
+    };
+    let wallets = match fetch_wallets_from_db(db.get_ref(), &user_id).await {
+        Ok(wallets) => wallets,
+        Err(e) => return server_error("Failed to fetch wallets", e),
+    };
+    let audit = match audit::fetch_recent_entries(db.get_ref(), Some(&user_id), AUDIT_LIMIT).await {
+        Ok(audit) => audit,
+        Err(e) => return server_error("Failed to fetch audit events", e),
+    };
+
+    render(
+        StatusCode::OK,
+        "user",
+        &json!({ "signed_in": true, "user": user, "wallets": wallets, "audit": audit }),
+    )
+}
+
+// ==================== Database Functions ====================
+
+async fn fetch_users_by_prefix(pool: &PgPool, prefix: &str) -> Result<Vec<AdminUserSummary>, sqlx::Error> {
+    let pattern = format!("{}%", prefix.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+    telemetry::sql(
+        "users",
+        "select",
+        sqlx::query_as::<_, AdminUserSummary>(
+            "SELECT id, email, created_at, last_login_at FROM users
+             WHERE id ILIKE $1 OR email ILIKE $1
+             ORDER BY created_at DESC
+             LIMIT $2",
+        )
+        .bind(pattern)
+        .bind(USER_SEARCH_LIMIT)
+        .fetch_all(pool),
+    )
+    .await
+}
+
+async fn fetch_user(pool: &PgPool, user_id: &str) -> Result<Option<AdminUserSummary>, sqlx::Error> {
+    sqlx::query_as::<_, AdminUserSummary>("SELECT id, email, created_at, last_login_at FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+}
+
+// ==================== Routes ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/admin")
+        .admin(Method::GET, "", dashboard)
+        .admin(Method::GET, "/login", login_page)
+        .admin(Method::POST, "/login", login)
+        .admin(Method::POST, "/logout", logout)
+        .admin(Method::GET, "/users", search_users)
+        .admin(Method::GET, "/users/{user_id}", user_detail)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
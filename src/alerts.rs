@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Timelike, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::budget_alerts::DEFAULT_ALERT_THRESHOLDS;
+use crate::push::{PushError, PushMessage, PushSenders};
+
+// ==================== PUSH ALERTS ====================
+//
+// A periodic job (`ALERT_JOB`) pushes alerts to registered devices according
+// to each device's preferences:
+//
+// - Low balance: an asset wallet's balance is below the device's
+//   `low_balance_below`. Sent at most once per wallet and device-local day
+//   while the balance stays low.
+// - Due date: an active payable debt is due within `due_date_days_before`
+//   days. Sent once per debt and due date (rescheduling re-arms it).
+// - New login device: the account logged in from a device it hadn't used
+//   before, in the last `NEW_DEVICE_WINDOW_DAYS` days (see login_devices.rs).
+//   Sent once per login device, to devices registered before the login.
+//   Always on.
+// - Standing order failed: a standing order run failed (insufficient funds,
+//   frozen wallet) in the last `FAILED_RUN_WINDOW_DAYS` days. Sent once per
+//   run, to devices registered before the run.
+// - Top-up: an auto top-up rule refilled a wallet, or could not (daily cap
+//   reached, insufficient funds, frozen wallet), in the last
+//   `TOP_UP_WINDOW_DAYS` days. Sent once per recorded attempt, to devices
+//   registered before it (see top_ups.rs).
+// - Transfer overdue: a pending transfer is still in transit past its
+//   `expected_by` (see transfers.rs). Sent once per transfer and device-local
+//   day until it is settled or cancelled.
+// - Support access: support opened an impersonation session on the account
+//   (see impersonation.rs). Sent once per session while it is live, to
+//   devices registered before it was opened. Always on.
+//
+// - Budget: an expense crossed one of a budget's alert thresholds (see
+//   budgets.rs) in the last `BUDGET_EVENT_WINDOW_DAYS` days. A budget
+//   without thresholds of its own uses the user's (see budget_alerts.rs), and
+//   a crossing is dropped if its threshold no longer applies. Sent once per
+//   budget, period and threshold, to devices with `budget_alerts` on that
+//   were registered before the crossing.
+//
+// Alerts are not sent during a device's quiet hours; they stay pending and go
+// out on the first run after the quiet period ends. Each (device, alert key)
+// is claimed in `push_alert_log` before sending, so overlapping runs can't
+// send twice. A failed send releases the claim to be retried next run; a
+// token the provider no longer knows deletes the device.
+//
+// ============================================================================
+
+pub const ALERT_JOB: &str = "push_alerts";
+
+/// How long sent alert keys are remembered (must exceed the longest re-arm window)
+const ALERT_LOG_RETENTION_DAYS: i64 = 90;
+
+/// How far back logins from new devices are still worth alerting about
+const NEW_DEVICE_WINDOW_DAYS: i64 = 1;
+
+/// How far back failed standing order runs are still worth alerting about
+const FAILED_RUN_WINDOW_DAYS: i64 = 3;
+
+/// How far back top-up attempts are still worth alerting about
+const TOP_UP_WINDOW_DAYS: i64 = 1;
+
+/// How far back budget threshold crossings are still worth alerting about
+const BUDGET_EVENT_WINDOW_DAYS: i64 = 1;
+
+/// An alert owed to one device
+#[derive(sqlx::FromRow)]
+struct PendingAlert {
+    device_id: Uuid,
+    platform: String,
+    token: String,
+    quiet_start_minute: Option<i16>,
+    quiet_end_minute: Option<i16>,
+    utc_offset_minutes: i16,
+    kind: String,
+    subject_id: Uuid,
+    alert_key: String,
+    subject_name: String,
+    amount: BigDecimal,
+    due_date: Option<DateTime<Utc>>,
+    detail: Option<String>,
+}
+
+/// Send every pending alert; returns the number delivered
+pub async fn send_due_alerts(pool: &PgPool, senders: &PushSenders) -> Result<u64, sqlx::Error> {
+    let now = Utc::now();
+
+    // Local date of the device, so "once a day" follows the user's day
+    let mut pending = sqlx::query_as::<_, PendingAlert>(
+        "SELECT d.id AS device_id, d.platform, d.token, d.quiet_start_minute, d.quiet_end_minute,
+                d.utc_offset_minutes, 'low_balance' AS kind, w.id AS subject_id,
+                'low_balance:' || w.id || ':'
+                    || ($1::timestamptz AT TIME ZONE 'UTC' + make_interval(mins => d.utc_offset_minutes))::date
+                    AS alert_key,
+                w.name AS subject_name, w.balance AS amount, NULL::timestamptz AS due_date,
+                NULL::text AS detail
+         FROM push_devices d
+         JOIN wallets w ON w.user_id = d.user_id
+         LEFT JOIN custom_wallet_types ct ON ct.id = w.custom_type_id
+         WHERE d.low_balance_below IS NOT NULL
+           AND w.deleted_at IS NULL
+           AND NOT w.sandbox
+           AND w.balance < d.low_balance_below
+           AND COALESCE(ct.behavior, CASE WHEN w.wallet_type::text = 'CreditCard' THEN 'liability' ELSE 'asset' END) = 'asset'",
+    )
+    .bind(now)
+    .fetch_all(pool)
+    .await?;
+
+    pending.extend(
+        sqlx::query_as::<_, PendingAlert>(
+            "SELECT d.id AS device_id, d.platform, d.token, d.quiet_start_minute, d.quiet_end_minute,
+                    d.utc_offset_minutes, 'due_date' AS kind, debt.id AS subject_id,
+                    'due_date:' || debt.id || ':' || (debt.due_date AT TIME ZONE 'UTC')::date AS alert_key,
+                    debt.creditor_name AS subject_name, debt.amount, debt.due_date, NULL::text AS detail
+             FROM push_devices d
+             JOIN debts debt ON debt.user_id = d.user_id
+             WHERE d.due_date_days_before IS NOT NULL
+               AND debt.status = 'active'
+               AND debt.direction = 'payable'
+               AND debt.due_date >= $1
+               AND debt.due_date <= $1 + make_interval(days => d.due_date_days_before)",
+        )
+        .bind(now)
+        .fetch_all(pool)
+        .await?,
+    );
+
+    pending.extend(
+        sqlx::query_as::<_, PendingAlert>(
+            "SELECT d.id AS device_id, d.platform, d.token, d.quiet_start_minute, d.quiet_end_minute,
+                    d.utc_offset_minutes, 'new_login_device' AS kind, l.id AS subject_id,
+                    'new_login_device:' || l.id AS alert_key,
+                    l.user_agent AS subject_name, 0::numeric AS amount, l.first_seen_at AS due_date,
+                    l.first_ip AS detail
+             FROM push_devices d
+             JOIN login_devices l ON l.user_id = d.user_id
+             WHERE l.new_device_alert
+               AND l.revoked_at IS NULL
+               AND l.first_seen_at >= $1
+               AND l.first_seen_at >= d.created_at",
+        )
+        .bind(now - Duration::days(NEW_DEVICE_WINDOW_DAYS))
+        .fetch_all(pool)
+        .await?,
+    );
+
+    pending.extend(
+        sqlx::query_as::<_, PendingAlert>(
+            "SELECT d.id AS device_id, d.platform, d.token, d.quiet_start_minute, d.quiet_end_minute,
+                    d.utc_offset_minutes, 'standing_order_failed' AS kind, o.id AS subject_id,
+                    'standing_order_failed:' || r.id AS alert_key,
+                    o.name AS subject_name, COALESCE(r.amount, 0) AS amount, NULL::timestamptz AS due_date,
+                    r.error AS detail
+             FROM push_devices d
+             JOIN standing_order_runs r ON r.user_id = d.user_id
+             JOIN standing_orders o ON o.id = r.order_id
+             WHERE r.status = 'failed'
+               AND r.created_at >= $1
+               AND r.created_at >= d.created_at",
+        )
+        .bind(now - Duration::days(FAILED_RUN_WINDOW_DAYS))
+        .fetch_all(pool)
+        .await?,
+    );
+
+    pending.extend(
+        sqlx::query_as::<_, PendingAlert>(
+            "SELECT d.id AS device_id, d.platform, d.token, d.quiet_start_minute, d.quiet_end_minute,
+                    d.utc_offset_minutes,
+                    CASE WHEN t.status = 'completed' THEN 'top_up' ELSE 'top_up_failed' END AS kind,
+                    w.id AS subject_id, 'top_up:' || t.id AS alert_key,
+                    w.name AS subject_name, COALESCE(t.amount, 0) AS amount, NULL::timestamptz AS due_date,
+                    CASE WHEN t.status = 'completed' THEN f.name ELSE t.error END AS detail
+             FROM push_devices d
+             JOIN wallet_top_ups t ON t.user_id = d.user_id
+             JOIN wallet_top_up_rules r ON r.id = t.rule_id
+             JOIN wallets w ON w.id = r.wallet_id
+             JOIN wallets f ON f.id = r.funding_wallet_id
+             WHERE t.created_at >= $1
+               AND t.created_at >= d.created_at",
+        )
+        .bind(now - Duration::days(TOP_UP_WINDOW_DAYS))
+        .fetch_all(pool)
+        .await?,
+    );
+
+    pending.extend(
+        sqlx::query_as::<_, PendingAlert>(
+            "SELECT d.id AS device_id, d.platform, d.token, d.quiet_start_minute, d.quiet_end_minute,
+                    d.utc_offset_minutes, 'budget' AS kind, b.id AS subject_id,
+                    'budget:' || e.id AS alert_key,
+                    b.name AS subject_name, e.spent AS amount, NULL::timestamptz AS due_date,
+                    e.threshold::text AS detail
+             FROM push_devices d
+             JOIN budget_events e ON e.user_id = d.user_id
+             JOIN budgets b ON b.id = e.budget_id
+             WHERE d.budget_alerts
+               AND e.created_at >= $1
+               AND e.created_at >= d.created_at
+               AND e.threshold = ANY(COALESCE(b.alert_thresholds,
+                                              (SELECT s.thresholds FROM budget_alert_settings s WHERE s.user_id = b.user_id),
+                                              $2))",
+        )
+        .bind(now - Duration::days(BUDGET_EVENT_WINDOW_DAYS))
+        .bind(DEFAULT_ALERT_THRESHOLDS.to_vec())
+        .fetch_all(pool)
+        .await?,
+    );
+
+    pending.extend(
+        sqlx::query_as::<_, PendingAlert>(
+            "SELECT d.id AS device_id, d.platform, d.token, d.quiet_start_minute, d.quiet_end_minute,
+                    d.utc_offset_minutes, 'transfer_overdue' AS kind, t.id AS subject_id,
+                    'transfer_overdue:' || t.id || ':'
+                        || ($1::timestamptz AT TIME ZONE 'UTC' + make_interval(mins => d.utc_offset_minutes))::date
+                        AS alert_key,
+                    w.name AS subject_name, t.amount, t.expected_by AS due_date, NULL::text AS detail
+             FROM push_devices d
+             JOIN pending_transfers t ON t.user_id = d.user_id
+             JOIN wallets w ON w.id = t.to_wallet_id
+             WHERE t.status = 'in_transit'
+               AND t.expected_by < $1",
+        )
+        .bind(now)
+        .fetch_all(pool)
+        .await?,
+    );
+
+    pending.extend(
+        sqlx::query_as::<_, PendingAlert>(
+            "SELECT d.id AS device_id, d.platform, d.token, d.quiet_start_minute, d.quiet_end_minute,
+                    d.utc_offset_minutes, 'support_access' AS kind, s.id AS subject_id,
+                    'support_access:' || s.id AS alert_key,
+                    s.requested_by AS subject_name, 0::numeric AS amount, s.expires_at AS due_date,
+                    CASE WHEN s.write_access THEN 'read-write' ELSE 'read-only' END AS detail
+             FROM push_devices d
+             JOIN impersonation_sessions s ON s.user_id = d.user_id
+             WHERE s.revoked_at IS NULL
+               AND s.expires_at > $1
+               AND s.created_at >= d.created_at",
+        )
+        .bind(now)
+        .fetch_all(pool)
+        .await?,
+    );
+
+    let mut sent = 0;
+    for alert in pending {
+        let Some(sender) = senders.for_platform(&alert.platform) else {
+            continue;
+        };
+        if in_quiet_hours(&alert, now) {
+            continue;
+        }
+
+        if !claim(pool, &alert).await? {
+            continue;
+        }
+        match sender.send(&alert.token, &message(&alert)).await {
+            Ok(()) => sent += 1,
+            Err(PushError::Unregistered) => {
+                log::info!("Push token of device {} is no longer registered; removing it", alert.device_id);
+                sqlx::query("DELETE FROM push_devices WHERE id = $1")
+                    .bind(alert.device_id)
+                    .execute(pool)
+                    .await?;
+            }
+            Err(e) => {
+                log::warn!("Failed to send alert {} to device {}: {}", alert.alert_key, alert.device_id, e);
+                release(pool, &alert).await?;
+            }
+        }
+    }
+
+    sqlx::query("DELETE FROM push_alert_log WHERE sent_at < $1")
+        .bind(now - Duration::days(ALERT_LOG_RETENTION_DAYS))
+        .execute(pool)
+        .await?;
+
+    Ok(sent)
+}
+
+/// Record the alert as sent; `false` if it already was
+async fn claim(pool: &PgPool, alert: &PendingAlert) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO push_alert_log (device_id, alert_key, sent_at)
+         VALUES ($1, $2, CURRENT_TIMESTAMP)
+         ON CONFLICT (device_id, alert_key) DO NOTHING",
+    )
+    .bind(alert.device_id)
+    .bind(&alert.alert_key)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+async fn release(pool: &PgPool, alert: &PendingAlert) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM push_alert_log WHERE device_id = $1 AND alert_key = $2")
+        .bind(alert.device_id)
+        .bind(&alert.alert_key)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Whether `now` falls in the device's quiet hours (which may wrap midnight)
+fn in_quiet_hours(alert: &PendingAlert, now: DateTime<Utc>) -> bool {
+    let (Some(start), Some(end)) = (alert.quiet_start_minute, alert.quiet_end_minute) else {
+        return false;
+    };
+    let local = (now.hour() as i32 * 60 + now.minute() as i32 + alert.utc_offset_minutes as i32).rem_euclid(1440);
+    let (start, end) = (start as i32, end as i32);
+    if start <= end {
+        start <= local && local < end
+    } else {
+        local >= start || local < end
+    }
+}
+
+fn message(alert: &PendingAlert) -> PushMessage {
+    let amount = alert.amount.with_scale(2);
+    let mut data = HashMap::new();
+    data.insert("kind".to_string(), alert.kind.clone());
+
+    let (title, body) = match alert.kind.as_str() {
+        "due_date" => {
+            data.insert("debt_id".to_string(), alert.subject_id.to_string());
+            let due = alert
+                .due_date
+                .map(|d| (d + Duration::minutes(alert.utc_offset_minutes as i64)).format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            ("Payment due soon".to_string(), format!("{} due to {} on {}", amount, alert.subject_name, due))
+        }
+        "support_access" => {
+            data.insert("session_id".to_string(), alert.subject_id.to_string());
+            let until = alert
+                .due_date
+                .map(|d| (d + Duration::minutes(alert.utc_offset_minutes as i64)).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            let access = alert.detail.as_deref().unwrap_or("read-only");
+            (
+                "Support is accessing your account".to_string(),
+                format!("{} has {} access until {}", alert.subject_name, access, until),
+            )
+        }
+        "new_login_device" => {
+            data.insert("login_device_id".to_string(), alert.subject_id.to_string());
+            let at = alert
+                .due_date
+                .map(|d| (d + Duration::minutes(alert.utc_offset_minutes as i64)).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            let ip = alert.detail.as_deref().unwrap_or("unknown");
+            (
+                "New login to your account".to_string(),
+                format!("{} logged in from {} at {}", alert.subject_name, ip, at),
+            )
+        }
+        "transfer_overdue" => {
+            data.insert("transfer_id".to_string(), alert.subject_id.to_string());
+            let expected = alert
+                .due_date
+                .map(|d| (d + Duration::minutes(alert.utc_offset_minutes as i64)).format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            (
+                "Transfer not settled yet".to_string(),
+                format!("{} to {} was expected by {}", amount, alert.subject_name, expected),
+            )
+        }
+        "budget" => {
+            data.insert("budget_id".to_string(), alert.subject_id.to_string());
+            let threshold = alert.detail.as_deref().unwrap_or("100");
+            let title = if threshold.parse::<i32>().is_ok_and(|t| t >= 100) {
+                "Budget limit reached"
+            } else {
+                "Budget alert"
+            };
+            (
+                title.to_string(),
+                format!("{} has used {}% of its budget ({} spent)", alert.subject_name, threshold, amount),
+            )
+        }
+        "top_up" => {
+            data.insert("wallet_id".to_string(), alert.subject_id.to_string());
+            let funding = alert.detail.as_deref().unwrap_or("your funding wallet");
+            ("Wallet topped up".to_string(), format!("{} moved from {} to {}", amount, funding, alert.subject_name))
+        }
+        "top_up_failed" => {
+            data.insert("wallet_id".to_string(), alert.subject_id.to_string());
+            let reason = alert.detail.as_deref().unwrap_or("top-up failed");
+            ("Auto top-up failed".to_string(), format!("{}: {}", alert.subject_name, reason))
+        }
+        "standing_order_failed" => {
+            data.insert("standing_order_id".to_string(), alert.subject_id.to_string());
+            let reason = alert.detail.as_deref().unwrap_or("transfer failed");
+            ("Standing order failed".to_string(), format!("{}: {}", alert.subject_name, reason))
+        }
+        _ => {
+            data.insert("wallet_id".to_string(), alert.subject_id.to_string());
+            ("Low balance".to_string(), format!("{} is down to {}", alert.subject_name, amount))
+        }
+    };
+
+    PushMessage { title, body, data }
+}
@@ -0,0 +1,298 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde_json::{Map, Value};
+use sha2::Sha256;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::admin::authorize_admin;
+use crate::cache::CacheBackend;
+use crate::config::AppConfig;
+use crate::models::{AnonymizedExport, ApiResponse};
+
+// ==================== ANONYMIZED EXPORT ====================
+//
+// `GET /api/admin/users/{user_id}/export-anonymized` dumps a user's data
+// table by table, in the shape of the database rows (`to_jsonb`), with
+// everything that identifies the user or the people they deal with replaced
+// by fakes, so a bug can be reproduced from it without exposing real data.
+//
+// - Ids (`id`, `*_id` UUID columns) become other UUIDs, and `user_id` a fake
+//   user id, so references between rows still line up.
+// - Names, descriptions, notes, creditor contact details and account numbers,
+//   payers, reasons and custom field text values are replaced according to
+//   `TABLES`.
+// - Amounts, balances, dates, categories, types and statuses are kept: they
+//   are what reproduces a bug.
+//
+// Fakes are keyed hashes (HMAC-SHA256) under a random key drawn per export:
+// the same value always gets the same fake within one export (two debts with
+// one creditor keep sharing it), but exports can't be linked to each other or
+// reversed by guessing. Push device tokens, impersonation sessions and job
+// bookkeeping are not exported.
+//
+// ============================================================================
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// How a column's value is replaced
+#[derive(Clone, Copy)]
+enum Fake {
+    /// `"{label} {hex}"`
+    Name(&'static str),
+    /// Filler words, as many as the original had
+    Text,
+    Email,
+    Phone,
+    /// Fake stem, original extension
+    FileName,
+    /// Text values of a custom field object
+    Metadata,
+    /// Digits, as many as the original had
+    AccountNumber,
+}
+
+/// Columns of a table to fake, besides ids
+type ColumnFakes = &'static [(&'static str, Fake)];
+
+/// Exported tables: name, ordering column, and the columns to fake
+const TABLES: &[(&str, &str, ColumnFakes)] = &[
+    ("wallets", "created_at", &[("name", Fake::Name("Wallet"))]),
+    ("custom_wallet_types", "created_at", &[("name", Fake::Name("Wallet type"))]),
+    ("wallet_buckets", "created_at", &[("name", Fake::Name("Bucket"))]),
+    ("wallet_freezes", "frozen_at", &[("reason", Fake::Text)]),
+    ("income_sources", "created_at", &[("name", Fake::Name("Income source"))]),
+    ("transaction_field_definitions", "created_at", &[]),
+    ("categories", "created_at", &[]),
+    (
+        "transactions",
+        "created_at",
+        &[("description", Fake::Text), ("notes", Fake::Text), ("metadata", Fake::Metadata)],
+    ),
+    ("transaction_splits", "created_at", &[]),
+    (
+        "transaction_templates",
+        "created_at",
+        &[("name", Fake::Name("Template")), ("description", Fake::Text)],
+    ),
+    ("tax_category_mappings", "created_at", &[]),
+    (
+        "debts",
+        "created_at",
+        &[
+            ("creditor_name", Fake::Name("Creditor")),
+            ("creditor_phone", Fake::Phone),
+            ("creditor_email", Fake::Email),
+            ("creditor_address", Fake::Text),
+            ("creditor_account_number", Fake::AccountNumber),
+        ],
+    ),
+    ("debt_settlements", "settled_at", &[("note", Fake::Text)]),
+    ("savings_goals", "created_at", &[("name", Fake::Name("Goal"))]),
+    ("goal_contributions", "created_at", &[]),
+    ("bill_splits", "created_at", &[]),
+    ("reimbursements", "created_at", &[("payer", Fake::Name("Payer"))]),
+    (
+        "attachments",
+        "created_at",
+        &[("file_name", Fake::FileName), ("storage_key", Fake::Text)],
+    ),
+    ("standing_orders", "created_at", &[("name", Fake::Name("Standing order"))]),
+    ("standing_order_runs", "created_at", &[("error", Fake::Text)]),
+    ("report_subscriptions", "created_at", &[("email", Fake::Email)]),
+];
+
+const FILLER_WORDS: [&str; 16] = [
+    "lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit", "sed", "do", "eiusmod",
+    "tempor", "incididunt", "ut", "labore", "magna",
+];
+
+// ==================== Handlers ====================
+
+/// Download a user's data with identifying values replaced by fakes
+pub async fn export_anonymized(
+    req: HttpRequest,
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    let user_id = user_id.into_inner();
+    let mut key = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    let faker = Faker { key };
+
+    let export = match build_export(db.get_ref(), &user_id, &faker, config.max_export_rows).await {
+        Ok(export) => export,
+        Err(e) => {
+            log::error!("Anonymized export failed for user {}: {}", user_id, e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<AnonymizedExport>::error("Export failed".to_string()));
+        }
+    };
+    log::info!(
+        target: "audit",
+        "Anonymized export of user {} generated as {}",
+        user_id,
+        export.user_id
+    );
+
+    let filename = format!("ketobook-anonymized-{}.json", export.user_id);
+    HttpResponse::Ok()
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}\"", filename)))
+        .json(ApiResponse::success(export))
+}
+
+// ==================== Export ====================
+
+async fn build_export(
+    pool: &PgPool,
+    user_id: &str,
+    faker: &Faker,
+    max_rows: i64,
+) -> Result<AnonymizedExport, sqlx::Error> {
+    // One snapshot across all tables, so references between them hold
+    let mut db_tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ READ ONLY")
+        .execute(&mut *db_tx)
+        .await?;
+
+    let mut tables = Map::new();
+    let mut truncated = Vec::new();
+    for (table, order_by, fakes) in TABLES {
+        let rows: Vec<(Value,)> = sqlx::query_as(&format!(
+            "SELECT to_jsonb(t) FROM {} t WHERE t.user_id = $1 ORDER BY t.{} LIMIT $2",
+            table, order_by
+        ))
+        .bind(user_id)
+        .bind(max_rows + 1)
+        .fetch_all(&mut *db_tx)
+        .await?;
+
+        if rows.len() as i64 > max_rows {
+            truncated.push(table.to_string());
+        }
+        let rows = rows
+            .into_iter()
+            .take(max_rows as usize)
+            .map(|(row,)| faker.row(row, fakes))
+            .collect();
+        tables.insert(table.to_string(), Value::Array(rows));
+    }
+
+    db_tx.commit().await?;
+
+    Ok(AnonymizedExport {
+        user_id: faker.user_id(user_id),
+        generated_at: Utc::now(),
+        tables,
+        truncated,
+    })
+}
+
+// ==================== Fakes ====================
+
+struct Faker {
+    key: [u8; 32],
+}
+
+impl Faker {
+    /// Keyed digest of `value`, separated by `purpose`
+    fn digest(&self, purpose: &str, value: &str) -> [u8; 32] {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(purpose.as_bytes());
+        mac.update(&[0]);
+        mac.update(value.as_bytes());
+        mac.finalize().into_bytes().into()
+    }
+
+    fn hex(&self, purpose: &str, value: &str, len: usize) -> String {
+        let mut out = hex::encode(self.digest(purpose, value));
+        out.truncate(len);
+        out
+    }
+
+    fn user_id(&self, user_id: &str) -> String {
+        format!("user-{}", self.hex("user", user_id, 12))
+    }
+
+    fn uuid(&self, id: Uuid) -> Uuid {
+        let digest = self.digest("uuid", &id.to_string());
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&digest[..16]);
+        uuid::Builder::from_random_bytes(bytes).into_uuid()
+    }
+
+    /// Replace ids everywhere and the listed columns of one row
+    fn row(&self, row: Value, fakes: &[(&str, Fake)]) -> Value {
+        let Value::Object(columns) = row else {
+            return row;
+        };
+        let mut out = Map::with_capacity(columns.len());
+        for (column, value) in columns {
+            let value = match (fakes.iter().find(|(c, _)| *c == column), value) {
+                (_, Value::Null) => Value::Null,
+                (Some((_, fake)), value) => self.value(*fake, value),
+                (None, Value::String(s)) if column == "user_id" => Value::String(self.user_id(&s)),
+                (None, Value::String(s)) if column == "id" || column.ends_with("_id") => match Uuid::parse_str(&s) {
+                    Ok(id) => Value::String(self.uuid(id).to_string()),
+                    Err(_) => Value::String(s),
+                },
+                (None, value) => value,
+            };
+            out.insert(column, value);
+        }
+        Value::Object(out)
+    }
+
+    fn value(&self, fake: Fake, value: Value) -> Value {
+        match (fake, value) {
+            (Fake::Metadata, Value::Object(fields)) => Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(k, v)| match v {
+                        Value::String(s) => (k, Value::String(self.text(&s))),
+                        v => (k, v),
+                    })
+                    .collect(),
+            ),
+            (_, Value::String(s)) if s.is_empty() => Value::String(s),
+            (Fake::Name(label), Value::String(s)) => Value::String(format!("{} {}", label, self.hex("name", &s, 6))),
+            (Fake::Text, Value::String(s)) => Value::String(self.text(&s)),
+            (Fake::Email, Value::String(s)) => Value::String(format!("{}@example.com", self.hex("email", &s, 10))),
+            (Fake::Phone, Value::String(s)) => {
+                let digits: String = self.digest("phone", &s)[..7].iter().map(|b| char::from(b'0' + b % 10)).collect();
+                Value::String(format!("+1555{}", digits))
+            }
+            (Fake::AccountNumber, Value::String(s)) => {
+                let digest = self.digest("account", &s);
+                Value::String(digest[..s.len().min(19)].iter().map(|b| char::from(b'0' + b % 10)).collect())
+            }
+            (Fake::FileName, Value::String(s)) => {
+                let stem = format!("file-{}", self.hex("file", &s, 8));
+                match s.rsplit_once('.') {
+                    Some((_, ext)) if !ext.is_empty() && ext.len() <= 10 => Value::String(format!("{}.{}", stem, ext)),
+                    _ => Value::String(stem),
+                }
+            }
+            (_, value) => value,
+        }
+    }
+
+    /// Filler words, as many as `text` had (at least one)
+    fn text(&self, text: &str) -> String {
+        let words = text.split_whitespace().count().max(1);
+        let digest = self.digest("text", text);
+        (0..words)
+            .map(|i| FILLER_WORDS[usize::from(digest[i % digest.len()].wrapping_add((i / digest.len()) as u8)) % FILLER_WORDS.len()])
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
@@ -0,0 +1,454 @@
+use std::collections::BTreeSet;
+use std::sync::LazyLock;
+
+use actix_web::body::BoxBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, HttpRequest, HttpResponse};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::audit::Actor;
+use crate::impersonation::{body_field, body_user_id, token_hash};
+use crate::models::{ApiKey, ApiKeyGrant, ApiResponse, CreateApiKeyRequest};
+use crate::routes::{self, Ownership, ScopedRoutes};
+
+// ==================== API KEYS ====================
+//
+// A user can hand an integration a key limited to what it needs: a reporting
+// dashboard gets `read:wallets` and `read:transactions`, a Telegram bot only
+// `create:transactions`.
+//
+// - Every `ScopedRoutes` route has a scope (see routes.rs): `read:{resource}`
+//   for reads, `create:{resource}` for collection creates (`POST /api/x`) and
+//   `write:{resource}` for other mutations. `write:x` also grants `create:x`.
+//   The manifest (`GET /api/routes`) lists each route's scope, and only
+//   scopes some route requires can be granted.
+// - Requests carrying a key in `X-Api-Key` are checked by `enforce`: the key
+//   must be live, the route must belong to the key's user and the key must
+//   hold the route's scope. Admin routes and the key management routes never
+//   accept a key, so a key can't mint a broader one.
+// - The key is returned once on creation; only its SHA-256 is stored, with a
+//   short prefix to tell keys apart. Keys can expire and be revoked.
+// - A sandbox key (`sandbox: true`) lets an integrator test without touching
+//   real finances: the wallets it creates are sandbox wallets, and the only
+//   other writes it may make are to those wallets and their transactions
+//   (`sandbox_write_denied`). Transactions on a sandbox wallet are tagged
+//   `sandbox` by the database and left out of reports. Reads are as for any
+//   key.
+//
+// A key stands in for the user's bearer token (see auth.rs): a request
+// carrying one is not asked for a token. Bearer tokens carry no scopes; they
+// grant everything the user can do.
+//
+// ============================================================================
+
+/// Header carrying an API key
+pub(crate) const KEY_HEADER: &str = "X-Api-Key";
+
+/// Resource of the key management routes, which keys can't call
+const KEYS_RESOURCE: &str = "api_keys";
+
+/// Maximum number of live (unrevoked) keys per user
+const MAX_KEYS_PER_USER: i64 = 20;
+
+/// Characters of the key kept to tell keys apart
+const PREFIX_LEN: usize = 11;
+
+const KEY_COLUMNS: &str =
+    "id, user_id, name, key_prefix, scopes, sandbox, expires_at, last_used_at, revoked_at, created_at";
+
+/// Marks a request made with a sandbox key (see `is_sandbox`)
+#[derive(Debug, Clone, Copy)]
+struct Sandbox;
+
+/// Every scope a key can be granted
+static GRANTABLE_SCOPES: LazyLock<BTreeSet<String>> = LazyLock::new(|| {
+    routes::manifest()
+        .into_iter()
+        .filter_map(|spec| spec.scope)
+        .filter(|scope| !scope.ends_with(&format!(":{}", KEYS_RESOURCE)))
+        .collect()
+});
+
+// ==================== Handlers ====================
+
+/// Create a key and return its secret (shown only this once)
+pub async fn create_api_key(req: web::Json<CreateApiKeyRequest>, db: web::Data<PgPool>) -> HttpResponse {
+    let name = req.name.trim();
+    let mut scopes: Vec<String> = req.scopes.iter().map(|s| s.trim().to_string()).collect();
+    scopes.sort();
+    scopes.dedup();
+
+    let invalid = if req.user_id.is_empty() {
+        Some("user_id is required".to_string())
+    } else if name.is_empty() || name.chars().count() > 100 {
+        Some("name must be 1-100 characters".to_string())
+    } else if scopes.is_empty() {
+        Some("At least one scope is required".to_string())
+    } else if let Some(unknown) = scopes.iter().find(|s| !GRANTABLE_SCOPES.contains(*s)) {
+        Some(format!("Unknown scope: {}", unknown))
+    } else if req.expires_at.is_some_and(|at| at <= Utc::now()) {
+        Some("expires_at must be in the future".to_string())
+    } else {
+        None
+    };
+    if let Some(msg) = invalid {
+        return HttpResponse::BadRequest().json(ApiResponse::<ApiKeyGrant>::error(msg));
+    }
+
+    let count: Result<(i64,), _> =
+        sqlx::query_as("SELECT COUNT(*) FROM api_keys WHERE user_id = $1 AND revoked_at IS NULL")
+            .bind(&req.user_id)
+            .fetch_one(db.get_ref())
+            .await;
+    match count {
+        Ok((count,)) if count >= MAX_KEYS_PER_USER => {
+            return HttpResponse::BadRequest().json(ApiResponse::<ApiKeyGrant>::error(format!(
+                "A user can have at most {} API keys",
+                MAX_KEYS_PER_USER
+            )));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Error counting API keys: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<ApiKeyGrant>::error("Database error".to_string()));
+        }
+    }
+
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+    let secret = format!("kb_{}", hex::encode(secret));
+
+    let result = sqlx::query_as::<_, ApiKey>(&format!(
+        "INSERT INTO api_keys (id, user_id, name, key_prefix, token_hash, scopes, sandbox, expires_at, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, CURRENT_TIMESTAMP)
+         RETURNING {}",
+        KEY_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(name)
+    .bind(&secret[..PREFIX_LEN])
+    .bind(token_hash(&secret))
+    .bind(&scopes)
+    .bind(req.sandbox)
+    .bind(req.expires_at)
+    .fetch_one(db.get_ref())
+    .await;
+
+    match result {
+        Ok(key) => {
+            log::info!(
+                target: "audit",
+                "API key {} ({}) created for user {} with scopes {}{}",
+                key.id,
+                key.key_prefix,
+                key.user_id,
+                key.scopes.join(" "),
+                if key.sandbox { " (sandbox)" } else { "" }
+            );
+            HttpResponse::Created().json(ApiResponse::success(ApiKeyGrant { key, secret }))
+        }
+        Err(e) => {
+            log::error!("Error creating API key: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<ApiKeyGrant>::error("Failed to create API key".to_string()))
+        }
+    }
+}
+
+/// A user's keys, newest first (without their secrets)
+pub async fn get_user_api_keys(user_id: web::Path<String>, db: web::Data<PgPool>) -> HttpResponse {
+    let result = sqlx::query_as::<_, ApiKey>(&format!(
+        "SELECT {} FROM api_keys WHERE user_id = $1 ORDER BY created_at DESC",
+        KEY_COLUMNS
+    ))
+    .bind(user_id.into_inner())
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(keys) => HttpResponse::Ok().json(ApiResponse::success(keys)),
+        Err(e) => {
+            log::error!("Error fetching API keys: {}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<Vec<ApiKey>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Revoke a key; requests made with it are refused from then on
+pub async fn revoke_api_key(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, key_id) = path.into_inner();
+
+    let result = sqlx::query_as::<_, ApiKey>(&format!(
+        "UPDATE api_keys SET revoked_at = COALESCE(revoked_at, CURRENT_TIMESTAMP)
+         WHERE id::text = $1 AND user_id = $2
+         RETURNING {}",
+        KEY_COLUMNS
+    ))
+    .bind(&key_id)
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(key)) => {
+            log::info!(target: "audit", "API key {} ({}) of user {} revoked", key.id, key.key_prefix, key.user_id);
+            HttpResponse::Ok().json(ApiResponse::success(key))
+        }
+        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<ApiKey>::error("API key not found".to_string())),
+        Err(e) => {
+            log::error!("Error revoking API key: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<ApiKey>::error("Failed to revoke API key".to_string()))
+        }
+    }
+}
+
+// ==================== Request Guard ====================
+
+/// Check a request carrying an API key against the route's owner and scope
+///
+/// Requests without the header pass through untouched.
+pub async fn enforce(
+    ownership: Ownership,
+    scope: Option<String>,
+    mut req: ServiceRequest,
+    next: Next<BoxBody>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let Some(secret) = req.headers().get(KEY_HEADER).map(|v| v.to_str().unwrap_or_default().to_string()) else {
+        return next.call(req).await;
+    };
+    let Some(pool) = req.app_data::<web::Data<PgPool>>().cloned() else {
+        return next.call(req).await;
+    };
+    if ownership == Ownership::Public {
+        return next.call(req).await;
+    }
+
+    let key = sqlx::query_as::<_, ApiKey>(&format!(
+        "SELECT {} FROM api_keys
+         WHERE token_hash = $1 AND revoked_at IS NULL
+           AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP)",
+        KEY_COLUMNS
+    ))
+    .bind(token_hash(&secret))
+    .fetch_optional(pool.get_ref())
+    .await;
+    let key = match key {
+        Ok(Some(key)) => key,
+        Ok(None) => {
+            return Ok(req.into_response(
+                HttpResponse::Unauthorized().json(ApiResponse::<String>::error("Invalid or expired API key".to_string())),
+            ));
+        }
+        Err(e) => {
+            log::error!("Error checking API key: {}", e);
+            return Ok(req.into_response(
+                HttpResponse::InternalServerError().json(ApiResponse::<String>::error("Database error".to_string())),
+            ));
+        }
+    };
+
+    let target = match ownership {
+        Ownership::PathUser => req.match_info().get("user_id").map(str::to_string),
+        Ownership::BodyUser => body_user_id(&mut req).await,
+        Ownership::Admin | Ownership::Credentials | Ownership::Public | Ownership::Session => None,
+    };
+    let denied = match scope.as_deref() {
+        None => Some("API keys can't call this route".to_string()),
+        Some(scope) if scope.ends_with(&format!(":{}", KEYS_RESOURCE)) => {
+            Some("API keys can't manage API keys".to_string())
+        }
+        Some(_) if target.as_deref() != Some(key.user_id.as_str()) => {
+            Some("API key does not cover this user".to_string())
+        }
+        Some(scope) if !grants(&key.scopes, scope) => Some(format!("API key lacks scope {}", scope)),
+        Some(_) => None,
+    };
+    if let Some(msg) = denied {
+        return Ok(req.into_response(HttpResponse::Forbidden().json(ApiResponse::<String>::error(msg))));
+    }
+    if key.sandbox && scope.as_deref().is_some_and(|scope| !scope.starts_with("read:")) {
+        match sandbox_write_denied(pool.get_ref(), &mut req, &key.user_id).await {
+            Ok(None) => {}
+            Ok(Some(msg)) => {
+                return Ok(req.into_response(
+                    HttpResponse::Forbidden().json(ApiResponse::<String>::error(msg.to_string())),
+                ));
+            }
+            Err(e) => {
+                log::error!("Error checking sandbox write: {}", e);
+                return Ok(req.into_response(
+                    HttpResponse::InternalServerError().json(ApiResponse::<String>::error("Database error".to_string())),
+                ));
+            }
+        }
+    }
+
+    // Recorded at most once a minute, to keep busy keys from writing on every request
+    if let Err(e) = sqlx::query(
+        "UPDATE api_keys SET last_used_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND (last_used_at IS NULL OR last_used_at < CURRENT_TIMESTAMP - INTERVAL '1 minute')",
+    )
+    .bind(key.id)
+    .execute(pool.get_ref())
+    .await
+    {
+        log::warn!("Failed to record use of API key {}: {}", key.id, e);
+    }
+
+    req.extensions_mut().insert(Actor(format!("api_key:{}", key.id)));
+    if key.sandbox {
+        req.extensions_mut().insert(Sandbox);
+    }
+    next.call(req).await
+}
+
+/// Whether the request was made with a sandbox key
+pub(crate) fn is_sandbox(req: &HttpRequest) -> bool {
+    req.extensions().contains::<Sandbox>()
+}
+
+/// Why a sandbox key can't make this write, if it can't
+///
+/// It may create wallets (which become sandbox wallets), change or delete its
+/// sandbox wallets, and create, change or delete transactions on them.
+async fn sandbox_write_denied(
+    pool: &PgPool,
+    req: &mut ServiceRequest,
+    user_id: &str,
+) -> Result<Option<&'static str>, sqlx::Error> {
+    const ONLY_SANDBOX: &str = "Sandbox API keys can only write to sandbox wallets";
+
+    let pattern = req.match_pattern().unwrap_or_default();
+    let wallet_ids: Vec<String> = match (req.method().as_str(), pattern.as_str()) {
+        ("POST", "/api/wallets") => return Ok(None),
+        ("PUT" | "DELETE", "/api/wallets/{user_id}/{wallet_id}") => {
+            req.match_info().get("wallet_id").map(str::to_string).into_iter().collect()
+        }
+        ("POST", "/api/transactions") => body_field(req, "wallet_id").await.into_iter().collect(),
+        ("PUT" | "DELETE", "/api/transactions/{user_id}/{transaction_id}") => {
+            let transaction_id = req.match_info().get("transaction_id").and_then(|id| id.parse::<Uuid>().ok());
+            let sandbox: Option<(bool,)> =
+                sqlx::query_as("SELECT sandbox FROM transactions WHERE id = $1 AND user_id = $2")
+                    .bind(transaction_id)
+                    .bind(user_id)
+                    .fetch_optional(pool)
+                    .await?;
+            if sandbox.is_some_and(|(sandbox,)| !sandbox) {
+                return Ok(Some(ONLY_SANDBOX));
+            }
+            // Moving it to another wallet needs that one to be sandbox too
+            body_field(req, "wallet_id").await.into_iter().collect()
+        }
+        _ => return Ok(Some("Sandbox API keys can only write sandbox wallets and their transactions")),
+    };
+
+    // Unknown ids are left to the handler's 404
+    for wallet_id in wallet_ids.iter().filter_map(|id| id.parse::<Uuid>().ok()) {
+        let sandbox: Option<(bool,)> = sqlx::query_as("SELECT sandbox FROM wallets WHERE id = $1")
+            .bind(wallet_id)
+            .fetch_optional(pool)
+            .await?;
+        if sandbox.is_some_and(|(sandbox,)| !sandbox) {
+            return Ok(Some(ONLY_SANDBOX));
+        }
+    }
+    Ok(None)
+}
+
+/// Whether `scopes` grant `required` (`write:x` covers `create:x`)
+fn grants(scopes: &[String], required: &str) -> bool {
+    let write = required.strip_prefix("create:").map(|resource| format!("write:{}", resource));
+    scopes.iter().any(|s| s == required || write.as_deref() == Some(s.as_str()))
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/api-keys")
+        .create("", create_api_key)
+        .user(Method::GET, "/{user_id}", get_user_api_keys)
+        .user(Method::DELETE, "/{user_id}/{key_id}", revoke_api_key)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::test::{self, TestRequest};
+    use actix_web::App;
+
+    use super::*;
+    use crate::test_support;
+
+    async fn ok() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    #[test]
+    fn write_scopes_cover_creates_only() {
+        let scopes = ["write:wallets".to_string(), "read:debts".to_string()];
+        assert!(grants(&scopes, "write:wallets"));
+        assert!(grants(&scopes, "create:wallets"));
+        assert!(grants(&scopes, "read:debts"));
+        assert!(!grants(&scopes, "read:wallets"));
+        assert!(!grants(&scopes, "create:debts"));
+    }
+
+    #[actix_web::test]
+    async fn a_key_reaches_only_its_users_routes_within_its_scopes() {
+        let Some(pool) = test_support::database("a_key_reaches_only_its_users_routes_within_its_scopes").await else {
+            return;
+        };
+        let user_id = format!("test-api-key-{}", Uuid::new_v4());
+        let secret = format!("kb_test_{}", Uuid::new_v4().simple());
+        sqlx::query(
+            "INSERT INTO api_keys (user_id, name, key_prefix, token_hash, scopes)
+             VALUES ($1, 'Test', 'kb_test', $2, ARRAY['read:widgets'])",
+        )
+        .bind(&user_id)
+        .bind(token_hash(&secret))
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let app = test::init_service(App::new().app_data(web::Data::new(pool.clone())).configure(|cfg| {
+            ScopedRoutes::new("/api/widgets")
+                .user(Method::GET, "/{user_id}", ok)
+                .user(Method::PUT, "/{user_id}", ok)
+                .register(cfg)
+        }))
+        .await;
+        let mut statuses = Vec::new();
+        for (method, owner, key) in [
+            (Method::GET, user_id.as_str(), secret.as_str()),
+            (Method::GET, "someone-else", secret.as_str()),
+            (Method::PUT, user_id.as_str(), secret.as_str()),
+            (Method::GET, user_id.as_str(), "kb_not_a_key"),
+        ] {
+            let req = TestRequest::default()
+                .method(method)
+                .uri(&format!("/api/widgets/{}", owner))
+                .insert_header((KEY_HEADER, key))
+                .to_request();
+            statuses.push(test::call_service(&app, req).await.status());
+        }
+        sqlx::query("DELETE FROM api_keys WHERE user_id = $1").bind(&user_id).execute(&pool).await.unwrap();
+
+        assert_eq!(
+            statuses,
+            [StatusCode::OK, StatusCode::FORBIDDEN, StatusCode::FORBIDDEN, StatusCode::UNAUTHORIZED]
+        );
+    }
+}
@@ -0,0 +1,420 @@
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use sqlx::PgPool;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::models::{ApiResponse, Attachment};
+use crate::telemetry;
+
+// ==================== ATTACHMENTS SUBSYSTEM ====================
+//
+// Documents (contracts, receipts, invoices) can be attached to debts and
+// transactions. Blobs are written through a `StorageBackend` (local disk, or
+// an S3-compatible bucket when `ATTACHMENTS_S3_*` is set) and metadata rows
+// are kept in the `attachments` table. The blob is written first and removed again if the
+// metadata insert fails, so a row never points at a missing file.
+//
+// Entity modules (e.g. debts) own authorization: they verify the parent entity
+// belongs to the user before delegating to the helpers below.
+//
+// ============================================================================
+
+/// MIME types accepted for upload
+const ALLOWED_CONTENT_TYPES: &[&str] = &["image/jpeg", "image/png", "image/webp", "application/pdf"];
+
+// ==================== Storage Backend ====================
+
+/// Blob storage for attachment contents
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Store `data` under `key`, overwriting any existing blob
+    async fn put(&self, key: &str, data: &[u8]) -> std::io::Result<()>;
+
+    /// Read the blob stored under `key`
+    async fn get(&self, key: &str) -> std::io::Result<Vec<u8>>;
+
+    /// Remove the blob stored under `key` (missing blobs are not an error)
+    async fn delete(&self, key: &str) -> std::io::Result<()>;
+
+    /// A URL that fetches the blob under `key` without credentials until
+    /// `expires_in` has passed, if the backend can sign one
+    fn presigned_url(&self, _key: &str, _expires_in: Duration) -> Option<String> {
+        None
+    }
+}
+
+/// Stores attachments as files under a root directory on local disk
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Resolve a storage key to a path, rejecting keys that escape the root
+    fn path_for(&self, key: &str) -> std::io::Result<PathBuf> {
+        let relative = Path::new(key);
+        if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Invalid storage key: {}", key),
+            ));
+        }
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LocalStorage {
+    async fn put(&self, key: &str, data: &[u8]) -> std::io::Result<()> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, data).await
+    }
+
+    async fn get(&self, key: &str) -> std::io::Result<Vec<u8>> {
+        tokio::fs::read(self.path_for(key)?).await
+    }
+
+    async fn delete(&self, key: &str) -> std::io::Result<()> {
+        match tokio::fs::remove_file(self.path_for(key)?).await {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            other => other,
+        }
+    }
+}
+
+// ==================== Errors ====================
+
+#[derive(Debug)]
+pub enum AttachmentError {
+    Storage(std::io::Error),
+    Database(sqlx::Error),
+    InvalidUpload(String),
+    TooLarge(usize),
+    UnsupportedType(String),
+}
+
+impl std::fmt::Display for AttachmentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttachmentError::Storage(e) => write!(f, "Storage error: {}", e),
+            AttachmentError::Database(e) => write!(f, "Database error: {}", e),
+            AttachmentError::InvalidUpload(msg) => write!(f, "Invalid upload: {}", msg),
+            AttachmentError::TooLarge(max) => write!(f, "File exceeds maximum size of {} bytes", max),
+            AttachmentError::UnsupportedType(t) => write!(f, "Unsupported file type: {}", t),
+        }
+    }
+}
+
+impl std::error::Error for AttachmentError {}
+
+impl AttachmentError {
+    /// Map to an HTTP response without leaking storage/database internals
+    fn to_response(&self) -> HttpResponse {
+        match self {
+            AttachmentError::Storage(e) => {
+                log::error!("Attachment storage error: {}", e);
+                HttpResponse::InternalServerError()
+                    .json(ApiResponse::<Attachment>::error("Failed to store attachment".to_string()))
+            }
+            AttachmentError::Database(e) => {
+                log::error!("Attachment database error: {}", e);
+                HttpResponse::InternalServerError()
+                    .json(ApiResponse::<Attachment>::error("Database error".to_string()))
+            }
+            AttachmentError::TooLarge(_) => HttpResponse::PayloadTooLarge()
+                .json(ApiResponse::<Attachment>::error(self.to_string())),
+            AttachmentError::InvalidUpload(_) | AttachmentError::UnsupportedType(_) => {
+                HttpResponse::BadRequest().json(ApiResponse::<Attachment>::error(self.to_string()))
+            }
+        }
+    }
+}
+
+// ==================== Handlers (delegated from entity modules) ====================
+
+/// Store every file part of a multipart upload as an attachment of the entity
+pub async fn upload(
+    pool: &PgPool,
+    storage: &dyn StorageBackend,
+    max_bytes: usize,
+    user_id: &str,
+    entity_type: &str,
+    entity_id: Uuid,
+    payload: Multipart,
+) -> HttpResponse {
+    match save_uploads(pool, storage, max_bytes, user_id, entity_type, entity_id, payload).await {
+        Ok(saved) if saved.is_empty() => HttpResponse::BadRequest()
+            .json(ApiResponse::<Vec<Attachment>>::error("No file provided".to_string())),
+        Ok(saved) => HttpResponse::Created().json(ApiResponse::success(saved)),
+        Err(e) => e.to_response(),
+    }
+}
+
+/// List attachments of an entity
+pub async fn list(pool: &PgPool, user_id: &str, entity_type: &str, entity_id: Uuid) -> HttpResponse {
+    match fetch_attachments(pool, user_id, entity_type, entity_id).await {
+        Ok(attachments) => HttpResponse::Ok().json(ApiResponse::success(attachments)),
+        Err(e) => AttachmentError::Database(e).to_response(),
+    }
+}
+
+/// Stream back the contents of a single attachment
+pub async fn download(
+    pool: &PgPool,
+    storage: &dyn StorageBackend,
+    user_id: &str,
+    entity_type: &str,
+    entity_id: Uuid,
+    attachment_id: Uuid,
+) -> HttpResponse {
+    let attachment = match fetch_attachment(pool, user_id, entity_type, entity_id, attachment_id).await {
+        Ok(Some(a)) => a,
+        Ok(None) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<Attachment>::error("Attachment not found".to_string()));
+        }
+        Err(e) => return AttachmentError::Database(e).to_response(),
+    };
+
+    match storage.get(&attachment.storage_key).await {
+        Ok(bytes) => HttpResponse::Ok()
+            .content_type(attachment.content_type.as_str())
+            .insert_header((
+                "Content-Disposition",
+                format!("attachment; filename=\"{}\"", attachment.file_name),
+            ))
+            .body(bytes),
+        Err(e) => AttachmentError::Storage(e).to_response(),
+    }
+}
+
+/// Delete an attachment's metadata row and blob
+pub async fn remove(
+    pool: &PgPool,
+    storage: &dyn StorageBackend,
+    user_id: &str,
+    entity_type: &str,
+    entity_id: Uuid,
+    attachment_id: Uuid,
+) -> HttpResponse {
+    let deleted: Result<Option<(String,)>, sqlx::Error> = sqlx::query_as(
+        "DELETE FROM attachments WHERE id = $1 AND user_id = $2 AND entity_type = $3 AND entity_id = $4
+         RETURNING storage_key",
+    )
+    .bind(attachment_id)
+    .bind(user_id)
+    .bind(entity_type)
+    .bind(entity_id)
+    .fetch_optional(pool)
+    .await;
+
+    match deleted {
+        Ok(Some((storage_key,))) => {
+            // Metadata is gone; a leftover blob is only wasted space
+            if let Err(e) = storage.delete(&storage_key).await {
+                log::warn!("Failed to delete attachment blob {}: {}", storage_key, e);
+            }
+            HttpResponse::NoContent().finish()
+        }
+        Ok(None) => HttpResponse::NotFound()
+            .json(ApiResponse::<String>::error("Attachment not found".to_string())),
+        Err(e) => AttachmentError::Database(e).to_response(),
+    }
+}
+
+/// Remove every attachment of an entity (used when the parent is deleted)
+///
+/// Best-effort: failures are logged rather than failing the parent deletion.
+pub async fn delete_all(
+    pool: &PgPool,
+    storage: &dyn StorageBackend,
+    user_id: &str,
+    entity_type: &str,
+    entity_id: Uuid,
+) {
+    let deleted: Result<Vec<(String,)>, sqlx::Error> = sqlx::query_as(
+        "DELETE FROM attachments WHERE user_id = $1 AND entity_type = $2 AND entity_id = $3
+         RETURNING storage_key",
+    )
+    .bind(user_id)
+    .bind(entity_type)
+    .bind(entity_id)
+    .fetch_all(pool)
+    .await;
+
+    match deleted {
+        Ok(keys) => {
+            for (storage_key,) in keys {
+                if let Err(e) = storage.delete(&storage_key).await {
+                    log::warn!("Failed to delete attachment blob {}: {}", storage_key, e);
+                }
+            }
+        }
+        Err(e) => log::error!("Failed to delete attachments for {} {}: {}", entity_type, entity_id, e),
+    }
+}
+
+/// Remove the attachments of transactions that no longer exist
+///
+/// Hard-deleting a wallet removes its transactions by cascade; this clears
+/// up after it. Best-effort like `delete_all`.
+pub async fn delete_orphaned(pool: &PgPool, storage: &dyn StorageBackend, user_id: &str) {
+    let deleted: Result<Vec<(String,)>, sqlx::Error> = sqlx::query_as(
+        "DELETE FROM attachments a
+         WHERE a.user_id = $1 AND a.entity_type = 'transaction'
+           AND NOT EXISTS (SELECT 1 FROM transactions t WHERE t.id = a.entity_id)
+         RETURNING a.storage_key",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await;
+
+    match deleted {
+        Ok(keys) => {
+            for (storage_key,) in keys {
+                if let Err(e) = storage.delete(&storage_key).await {
+                    log::warn!("Failed to delete attachment blob {}: {}", storage_key, e);
+                }
+            }
+        }
+        Err(e) => log::error!("Failed to delete orphaned attachments of {}: {}", user_id, e),
+    }
+}
+
+// ==================== Upload Processing ====================
+
+async fn save_uploads(
+    pool: &PgPool,
+    storage: &dyn StorageBackend,
+    max_bytes: usize,
+    user_id: &str,
+    entity_type: &str,
+    entity_id: Uuid,
+    mut payload: Multipart,
+) -> Result<Vec<Attachment>, AttachmentError> {
+    let mut saved = Vec::new();
+
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(|e| AttachmentError::InvalidUpload(e.to_string()))?;
+
+        // Skip non-file form fields
+        let file_name = match field.content_disposition().and_then(|cd| cd.get_filename()) {
+            Some(name) => sanitize_file_name(name),
+            None => continue,
+        };
+
+        let content_type = field
+            .content_type()
+            .map(|m| m.essence_str().to_string())
+            .unwrap_or_default();
+        if !ALLOWED_CONTENT_TYPES.contains(&content_type.as_str()) {
+            return Err(AttachmentError::UnsupportedType(content_type));
+        }
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|e| AttachmentError::InvalidUpload(e.to_string()))?;
+            if data.len() + chunk.len() > max_bytes {
+                return Err(AttachmentError::TooLarge(max_bytes));
+            }
+            data.extend_from_slice(&chunk);
+        }
+        if data.is_empty() {
+            return Err(AttachmentError::InvalidUpload(format!("File '{}' is empty", file_name)));
+        }
+
+        let attachment_id = Uuid::new_v4();
+        let storage_key = format!("{}/{}/{}", entity_type, entity_id, attachment_id);
+        storage.put(&storage_key, &data).await.map_err(AttachmentError::Storage)?;
+
+        let inserted = sqlx::query_as::<_, Attachment>(
+            "INSERT INTO attachments (id, user_id, entity_type, entity_id, file_name, content_type, size_bytes, storage_key)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+             RETURNING id, user_id, entity_type, entity_id, file_name, content_type, size_bytes, storage_key, created_at",
+        )
+        .bind(attachment_id)
+        .bind(user_id)
+        .bind(entity_type)
+        .bind(entity_id)
+        .bind(&file_name)
+        .bind(&content_type)
+        .bind(data.len() as i64)
+        .bind(&storage_key)
+        .fetch_one(pool)
+        .await;
+
+        match inserted {
+            Ok(attachment) => saved.push(attachment),
+            Err(e) => {
+                let _ = storage.delete(&storage_key).await;
+                return Err(AttachmentError::Database(e));
+            }
+        }
+    }
+
+    Ok(saved)
+}
+
+/// Keep only the final path segment and strip characters unsafe in headers
+fn sanitize_file_name(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let cleaned: String = base
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(255)
+        .collect();
+    if cleaned.is_empty() { "file".to_string() } else { cleaned }
+}
+
+// ==================== Database Functions ====================
+
+pub async fn fetch_attachments(
+    pool: &PgPool,
+    user_id: &str,
+    entity_type: &str,
+    entity_id: Uuid,
+) -> Result<Vec<Attachment>, sqlx::Error> {
+    telemetry::sql(
+        "attachments",
+        "select",
+        sqlx::query_as::<_, Attachment>(
+            "SELECT id, user_id, entity_type, entity_id, file_name, content_type, size_bytes, storage_key, created_at
+             FROM attachments WHERE user_id = $1 AND entity_type = $2 AND entity_id = $3
+             ORDER BY created_at ASC",
+        )
+        .bind(user_id)
+        .bind(entity_type)
+        .bind(entity_id)
+        .fetch_all(pool),
+    )
+    .await
+}
+
+async fn fetch_attachment(
+    pool: &PgPool,
+    user_id: &str,
+    entity_type: &str,
+    entity_id: Uuid,
+    attachment_id: Uuid,
+) -> Result<Option<Attachment>, sqlx::Error> {
+    sqlx::query_as::<_, Attachment>(
+        "SELECT id, user_id, entity_type, entity_id, file_name, content_type, size_bytes, storage_key, created_at
+         FROM attachments WHERE id = $1 AND user_id = $2 AND entity_type = $3 AND entity_id = $4",
+    )
+    .bind(attachment_id)
+    .bind(user_id)
+    .bind(entity_type)
+    .bind(entity_id)
+    .fetch_optional(pool)
+    .await
+}
@@ -0,0 +1,153 @@
+use std::future::{ready, Ready};
+
+use actix_web::dev::Payload;
+use actix_web::http::Method;
+use actix_web::{web, Error, FromRequest, HttpMessage, HttpRequest, HttpResponse};
+use sqlx::{PgConnection, PgPool};
+
+use crate::auth::AuthUser;
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::filters::{AuditFilter, FilterQuery};
+use crate::limits::{self, PageParams};
+use crate::models::{ApiResponse, AuditEntry, AuditFilterQuery, DateRangeQuery, PageQuery};
+use crate::routes::ScopedRoutes;
+use crate::telemetry;
+use crate::timeouts::RequestId;
+
+// ==================== AUDIT LOG ====================
+//
+// Every insert, update and delete of a wallet, transaction or debt is recorded
+// in `audit_log` by a database trigger, with the row before and after as
+// JSONB. Being a trigger, the entry commits or rolls back with the change and
+// no write path can skip it: handlers, imports, transfers, background jobs,
+// merges. Updates that change nothing are not recorded; setting or clearing
+// `deleted_at` is recorded as a `delete` / `restore`.
+//
+// Who made the change and the request's `X-Request-Id` come from the
+// transaction-local settings `ketobook.actor` and `ketobook.request_id`,
+// which handlers set with `attach` right after BEGIN. The actor is the user,
+// `api_key:<id>`, `impersonation:<session id>` or `job:<name>` for the
+// interest job. Writes that don't attach a context (other background jobs,
+// and endpoints that haven't been wired yet) are recorded without an actor.
+//
+// `audit_log` is append-only; the database refuses updates and deletes.
+//
+// ============================================================================
+
+const AUDIT_COLUMNS: &str =
+    "SELECT id, user_id, entity_type, entity_id, action, actor, request_id, old_values, new_values, created_at FROM audit_log";
+
+/// Who is calling, set as a request extension by the guards of delegated
+/// access (API keys, impersonation); plain requests are made by their user
+#[derive(Debug, Clone)]
+pub struct Actor(pub String);
+
+/// The actor and request id recorded with the changes a request makes
+#[derive(Debug, Clone, Default)]
+pub struct AuditContext {
+    pub actor: Option<String>,
+    pub request_id: Option<String>,
+}
+
+impl FromRequest for AuditContext {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let extensions = req.extensions();
+        let actor = extensions
+            .get::<Actor>()
+            .map(|actor| actor.0.clone())
+            .or_else(|| extensions.get::<AuthUser>().map(|user| user.user_id.clone()));
+        let request_id = extensions.get::<RequestId>().map(|id| id.0.clone());
+        ready(Ok(AuditContext { actor, request_id }))
+    }
+}
+
+/// Record `context` with every change the current DB transaction makes
+///
+/// The settings end with the transaction; outside one they do nothing.
+pub async fn attach(conn: &mut PgConnection, context: &AuditContext) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "SELECT set_config('ketobook.actor', COALESCE($1, ''), TRUE),
+                set_config('ketobook.request_id', COALESCE($2, ''), TRUE)",
+    )
+    .bind(&context.actor)
+    .bind(&context.request_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+// ==================== Handlers ====================
+
+/// Get a page of a user's audit log, newest first
+///
+/// Supports `?page=&per_page=`, `?from=&to=` and the filters of `AuditFilterQuery`.
+pub async fn get_user_audit_log(
+    user: AuthUser,
+    page: web::Query<PageQuery>,
+    filter: web::Query<AuditFilterQuery>,
+    range: web::Query<DateRangeQuery>,
+    db: web::Data<PgPool>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, AppError> {
+    let page = limits::page_params(&config, &page).map_err(AppError::Validation)?;
+    let filter = AuditFilter::from_query(&config, &filter, &range).map_err(AppError::Validation)?;
+
+    let entries = fetch_audit_entries(db.get_ref(), &user.user_id, filter, page)
+        .await
+        .map_err(AppError::database("Failed to fetch audit log"))?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(entries)))
+}
+
+// ==================== Database Functions ====================
+
+async fn fetch_audit_entries(
+    pool: &PgPool,
+    user_id: &str,
+    filter: AuditFilter,
+    page: PageParams,
+) -> Result<Vec<AuditEntry>, sqlx::Error> {
+    telemetry::sql(
+        "audit_log",
+        "select",
+        filter
+            .apply(user_id, FilterQuery::new(AUDIT_COLUMNS))
+            .order_by("created_at DESC, id DESC")
+            .page(page)
+            .fetch_all(pool),
+    )
+    .await
+}
+
+/// The latest entries, of one user or of everyone, for the admin panel
+pub(crate) async fn fetch_recent_entries(
+    pool: &PgPool,
+    user_id: Option<&str>,
+    limit: i64,
+) -> Result<Vec<AuditEntry>, sqlx::Error> {
+    telemetry::sql(
+        "audit_log",
+        "select",
+        sqlx::query_as::<_, AuditEntry>(&format!(
+            "{} WHERE ($1::text IS NULL OR user_id = $1) ORDER BY created_at DESC, id DESC LIMIT $2",
+            AUDIT_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(pool),
+    )
+    .await
+}
+
+// ==================== Routes ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/audit").user(Method::GET, "/user/{user_id}", get_user_audit_log)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
@@ -0,0 +1,428 @@
+use std::future::{ready, Ready};
+use std::sync::LazyLock;
+
+use actix_web::body::BoxBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::error::InternalError;
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, FromRequest, HttpMessage, HttpRequest, HttpResponse};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api_keys;
+use crate::cache::CacheBackend;
+use crate::category_seeds;
+use crate::config::AppConfig;
+use crate::impersonation::{self, body_user_id};
+use crate::lockout::{self, LockoutPolicy};
+use crate::login_devices;
+use crate::mailer::is_valid_address;
+use crate::models::{ApiResponse, AuthToken, LoginRequest, RegisterRequest, User};
+use crate::routes::{Ownership, ScopedRoutes};
+
+// ==================== AUTHENTICATION ====================
+//
+// Users register and log in with an email and password
+// (`POST /api/auth/register`, `POST /api/auth/login`) and get a bearer JWT
+// (HS256 under `JWT_SECRET`, valid `JWT_TTL_SECS`) whose subject is their
+// user id. Passwords are stored as Argon2id hashes; failed logins count toward
+// a lockout per email (see lockout.rs). Each login is recorded against the
+// device it came from and the token names that device, so revoking the device
+// ends its tokens (see login_devices.rs).
+//
+// `enforce` runs on every `ScopedRoutes` route. On user routes (`PathUser`,
+// `BodyUser`) it requires `Authorization: Bearer <token>` whose subject is the
+// route's owner: the `{user_id}` path segment, or `user_id` in the body of a
+// create. Requests carrying an API key or an impersonation token are
+// authenticated by those guards instead (see api_keys.rs, impersonation.rs),
+// which run first and check the same owner. On session routes (`Session`,
+// `/api/users/me/...`) the owner is the token's subject, and only a bearer
+// token will do. Handlers take the verified user through the `AuthUser`
+// extractor.
+//
+// Without `JWT_SECRET` the service runs unauthenticated, as before: routes
+// trust `{user_id}` and the auth endpoints are disabled. That is for local
+// development only; the startup log warns about it.
+//
+// ============================================================================
+
+/// Lockout scope of failed logins (subject: the email)
+const LOGIN_LOCKOUT_SCOPE: &str = "login";
+
+const USER_COLUMNS: &str = "id, email, last_login_at, created_at";
+
+/// Hash checked when the email is unknown, so both cases take as long
+static DUMMY_HASH: LazyLock<String> = LazyLock::new(|| {
+    hash_password(&Uuid::new_v4().to_string()).expect("hashing a random password succeeds")
+});
+
+/// Bearer token claims
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    sub: String,
+    iat: i64,
+    exp: i64,
+    /// Login device the token was issued to (absent from older tokens)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    did: Option<Uuid>,
+}
+
+/// The user a request was authenticated as
+///
+/// Available to handlers of user routes; it is the route's owner.
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub user_id: String,
+    /// Login device of the caller's bearer token, if it names one
+    pub device_id: Option<Uuid>,
+}
+
+impl FromRequest for AuthUser {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(req.extensions().get::<AuthUser>().cloned().ok_or_else(|| {
+            InternalError::from_response(
+                "not authenticated",
+                HttpResponse::Unauthorized().json(ApiResponse::<String>::error("Not authenticated".to_string())),
+            )
+            .into()
+        }))
+    }
+}
+
+// ==================== Handlers ====================
+
+/// Create an account and log it in
+pub async fn register(
+    http_req: HttpRequest,
+    req: web::Json<RegisterRequest>,
+    db: web::Data<PgPool>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    let Some(secret) = config.jwt_secret.as_deref() else {
+        return disabled();
+    };
+
+    let email = req.email.trim().to_lowercase();
+    let invalid = if !is_valid_address(&email) || email.chars().count() > 255 {
+        Some("A valid email is required".to_string())
+    } else if !(8..=128).contains(&req.password.chars().count()) {
+        Some("password must be 8-128 characters".to_string())
+    } else {
+        None
+    };
+    if let Some(msg) = invalid {
+        return HttpResponse::BadRequest().json(ApiResponse::<AuthToken>::error(msg));
+    }
+
+    let password = req.password.clone();
+    let password_hash = match web::block(move || hash_password(&password)).await {
+        Ok(Ok(hash)) => hash,
+        Ok(Err(e)) => {
+            log::error!("Error hashing password: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<AuthToken>::error("Failed to create account".to_string()));
+        }
+        Err(e) => {
+            log::error!("Error hashing password: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<AuthToken>::error("Failed to create account".to_string()));
+        }
+    };
+
+    let result = sqlx::query_as::<_, User>(&format!(
+        "INSERT INTO users (id, email, password_hash, last_login_at, created_at, updated_at)
+         VALUES ($1, $2, $3, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+         ON CONFLICT (email) DO NOTHING
+         RETURNING {}",
+        USER_COLUMNS
+    ))
+    .bind(Uuid::new_v4().to_string())
+    .bind(&email)
+    .bind(&password_hash)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    let user = match result {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return HttpResponse::Conflict()
+                .json(ApiResponse::<AuthToken>::error("An account with this email already exists".to_string()));
+        }
+        Err(e) => {
+            log::error!("Error creating account: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<AuthToken>::error("Failed to create account".to_string()));
+        }
+    };
+
+    log::info!(target: "audit", "Account {} registered", user.id);
+    let device_id = match login_devices::record_login(db.get_ref(), &user.id, &http_req).await {
+        Ok(device_id) => device_id,
+        Err(e) => {
+            log::error!("Error recording login device: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<AuthToken>::error("Failed to create account".to_string()));
+        }
+    };
+    let locale = category_seeds::client_locale(&http_req, req.locale.as_deref());
+    category_seeds::bootstrap(db.get_ref(), &user.id, locale.as_deref()).await;
+    match issue_token(secret, config.jwt_ttl_secs, user, device_id) {
+        Ok(token) => HttpResponse::Created().json(ApiResponse::success(token)),
+        Err(e) => {
+            log::error!("Error issuing token: {}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<AuthToken>::error("Failed to issue token".to_string()))
+        }
+    }
+}
+
+/// Exchange an email and password for a bearer token
+pub async fn login(
+    http_req: HttpRequest,
+    req: web::Json<LoginRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    let Some(secret) = config.jwt_secret.as_deref() else {
+        return disabled();
+    };
+
+    let email = req.email.trim().to_lowercase();
+    let client_ip = http_req
+        .connection_info()
+        .realip_remote_addr()
+        .unwrap_or("unknown")
+        .to_string();
+    let policy = LockoutPolicy::from_config(&config);
+
+    if let Err(rejection) = lockout::check(cache.get_ref(), &policy, None, LOGIN_LOCKOUT_SCOPE, &email, &client_ip).await {
+        return rejection.to_response();
+    }
+
+    let account: Result<Option<(String, String)>, _> =
+        sqlx::query_as("SELECT id, password_hash FROM users WHERE email = $1")
+            .bind(&email)
+            .fetch_optional(db.get_ref())
+            .await;
+    let account = match account {
+        Ok(account) => account,
+        Err(e) => {
+            log::error!("Error looking up account: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<AuthToken>::error("Database error".to_string()));
+        }
+    };
+
+    let (user_id, password_hash) = account.unzip();
+    let password = req.password.clone();
+    let verified = web::block(move || verify_password(&password, password_hash.as_deref().unwrap_or(&DUMMY_HASH)))
+        .await
+        .unwrap_or(false);
+
+    let user_id = match user_id {
+        Some(user_id) if verified => user_id,
+        _ => {
+            if let Some(retry_after) =
+                lockout::record_failure(cache.get_ref(), &policy, LOGIN_LOCKOUT_SCOPE, &email).await
+            {
+                return lockout::Rejection::Locked(retry_after).to_response();
+            }
+            return HttpResponse::Unauthorized()
+                .json(ApiResponse::<AuthToken>::error("Invalid email or password".to_string()));
+        }
+    };
+    lockout::record_success(cache.get_ref(), LOGIN_LOCKOUT_SCOPE, &email).await;
+
+    let result = sqlx::query_as::<_, User>(&format!(
+        "UPDATE users SET last_login_at = CURRENT_TIMESTAMP WHERE id = $1 RETURNING {}",
+        USER_COLUMNS
+    ))
+    .bind(&user_id)
+    .fetch_one(db.get_ref())
+    .await;
+    let user = match result {
+        Ok(user) => user,
+        Err(e) => {
+            log::error!("Error recording login: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<AuthToken>::error("Database error".to_string()));
+        }
+    };
+
+    let device_id = match login_devices::record_login(db.get_ref(), &user.id, &http_req).await {
+        Ok(device_id) => device_id,
+        Err(e) => {
+            log::error!("Error recording login device: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<AuthToken>::error("Database error".to_string()));
+        }
+    };
+
+    log::info!(target: "audit", "Account {} logged in from {} (device {})", user.id, client_ip, device_id);
+    let locale = category_seeds::client_locale(&http_req, req.locale.as_deref());
+    category_seeds::bootstrap(db.get_ref(), &user.id, locale.as_deref()).await;
+    match issue_token(secret, config.jwt_ttl_secs, user, device_id) {
+        Ok(token) => HttpResponse::Ok().json(ApiResponse::success(token)),
+        Err(e) => {
+            log::error!("Error issuing token: {}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<AuthToken>::error("Failed to issue token".to_string()))
+        }
+    }
+}
+
+fn disabled() -> HttpResponse {
+    HttpResponse::Forbidden().json(ApiResponse::<AuthToken>::error("Authentication is disabled".to_string()))
+}
+
+// ==================== Tokens and Passwords ====================
+
+fn issue_token(
+    secret: &str,
+    ttl_secs: i64,
+    user: User,
+    device_id: Uuid,
+) -> Result<AuthToken, jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+    let expires_at = now + Duration::seconds(ttl_secs.max(60));
+    let claims = Claims {
+        sub: user.id.clone(),
+        iat: now.timestamp(),
+        exp: expires_at.timestamp(),
+        did: Some(device_id),
+    };
+    let token = jsonwebtoken::encode(
+        &Header::new(Algorithm::HS256),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )?;
+    Ok(AuthToken {
+        token,
+        token_type: "Bearer".to_string(),
+        expires_at: DateTime::from_timestamp(claims.exp, 0).unwrap_or(expires_at),
+        device_id,
+        user,
+    })
+}
+
+/// The user of a valid, unexpired bearer token whose login device is still active
+pub(crate) async fn token_user(pool: &PgPool, secret: &str, token: &str) -> Result<Option<AuthUser>, sqlx::Error> {
+    let validation = Validation::new(Algorithm::HS256);
+    let Ok(data) = jsonwebtoken::decode::<Claims>(token, &DecodingKey::from_secret(secret.as_bytes()), &validation)
+    else {
+        return Ok(None);
+    };
+    let claims = data.claims;
+    if let Some(device_id) = claims.did
+        && !login_devices::is_active(pool, device_id, &claims.sub).await?
+    {
+        return Ok(None);
+    }
+    Ok(Some(AuthUser { user_id: claims.sub, device_id: claims.did }))
+}
+
+fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default().hash_password(password.as_bytes(), &salt)?.to_string())
+}
+
+fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .is_ok_and(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+}
+
+// ==================== Request Guard ====================
+
+/// Authenticate the caller of a user route as its owner
+///
+/// Other routes pass through untouched.
+pub async fn enforce(
+    ownership: Ownership,
+    mut req: ServiceRequest,
+    next: Next<BoxBody>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let target = match ownership {
+        Ownership::PathUser => req.match_info().get("user_id").map(str::to_string),
+        Ownership::BodyUser => body_user_id(&mut req).await,
+        Ownership::Session => None,
+        Ownership::Admin | Ownership::Credentials | Ownership::Public => return next.call(req).await,
+    };
+    if target.is_none() && ownership != Ownership::Session {
+        return Ok(req.into_response(
+            HttpResponse::BadRequest().json(ApiResponse::<String>::error("user_id is required".to_string())),
+        ));
+    }
+
+    let secret = req.app_data::<web::Data<AppConfig>>().and_then(|config| config.jwt_secret.clone());
+    let delegated = req.headers().contains_key(api_keys::KEY_HEADER)
+        || req.headers().contains_key(impersonation::TOKEN_HEADER);
+    let user = match (secret, target) {
+        // API keys and impersonation tokens were checked against the owner already
+        (Some(_), Some(target)) if delegated => AuthUser { user_id: target, device_id: None },
+        (Some(secret), target) => {
+            let token = req
+                .headers()
+                .get(AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "))
+                .map(str::to_string);
+            let Some(token) = token else {
+                return Ok(req.into_response(
+                    HttpResponse::Unauthorized()
+                        .json(ApiResponse::<String>::error("Authentication required".to_string())),
+                ));
+            };
+            let Some(pool) = req.app_data::<web::Data<PgPool>>().cloned() else {
+                return Ok(req.into_response(disabled()));
+            };
+            match token_user(pool.get_ref(), &secret, &token).await {
+                Ok(Some(user)) if target.as_ref().is_none_or(|target| *target == user.user_id) => user,
+                Ok(Some(_)) => {
+                    return Ok(req.into_response(HttpResponse::Forbidden().json(ApiResponse::<String>::error(
+                        "Token does not cover this user".to_string(),
+                    ))));
+                }
+                Ok(None) => {
+                    return Ok(req.into_response(
+                        HttpResponse::Unauthorized()
+                            .json(ApiResponse::<String>::error("Invalid or expired token".to_string())),
+                    ));
+                }
+                Err(e) => {
+                    log::error!("Error checking login device: {}", e);
+                    return Ok(req.into_response(
+                        HttpResponse::InternalServerError()
+                            .json(ApiResponse::<String>::error("Database error".to_string())),
+                    ));
+                }
+            }
+        }
+        (None, Some(target)) => AuthUser { user_id: target, device_id: None },
+        // Session routes have no owner to trust without a token
+        (None, None) => return Ok(req.into_response(disabled())),
+    };
+
+    req.extensions_mut().insert(user);
+    next.call(req).await
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/auth")
+        .credentials("/register", register)
+        .credentials("/login", login)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
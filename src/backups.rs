@@ -0,0 +1,463 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use tokio::process::Command;
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::admin::authorize_admin;
+use crate::attachments::StorageBackend;
+use crate::cache::CacheBackend;
+use crate::config::AppConfig;
+use crate::models::{ApiResponse, BackupRun};
+
+// ==================== DATABASE BACKUPS ====================
+//
+// Logical backups with `pg_dump --format=custom`, uploaded to an
+// S3-compatible bucket (see s3.rs). Each attempt is a `backup_runs` row:
+//
+// - Scheduled: the `database_backups` job checks hourly and runs a backup
+//   once the last scheduled one is `BACKUP_INTERVAL_SECS` old.
+// - Manual: `POST /api/admin/backups` starts one in the background.
+//
+// After a successful backup:
+//
+// 1. Retention keeps the newest `BACKUP_RETENTION_COUNT` succeeded backups.
+//    Older objects are deleted and their rows marked `expired`.
+// 2. Restore verification downloads the object and checks its SHA-256. It
+//    then reads the archive with `pg_restore --list`. With
+//    `BACKUP_VERIFY_DATABASE_URL` set, it also does a full restore into that
+//    scratch database.
+//
+// The dump is held in memory between pg_dump and the upload, which suits
+// this database's size. pg_dump/pg_restore must be on PATH and match the
+// server's major version.
+//
+// ============================================================================
+
+/// Job name (advisory lock) of the scheduled backup
+pub const BACKUP_JOB: &str = "database_backups";
+
+/// How often the scheduled job checks whether a backup is due
+pub const BACKUP_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// A run still `running` after this long is considered dead
+const STALE_RUN_SECS: i64 = 6 * 3600;
+
+const BACKUP_RUN_COLUMNS: &str = "id, triggered_by, status, object_key, size_bytes, sha256, error, started_at, finished_at, verify_status, verify_error, verified_at";
+
+/// Backup storage and settings, shared with handlers when backups are configured
+pub struct Backups {
+    pub storage: Arc<dyn StorageBackend>,
+    pub database_url: String,
+    pub verify_database_url: Option<String>,
+    pub prefix: String,
+    pub interval: Duration,
+    pub retention_count: u32,
+}
+
+impl Backups {
+    pub fn new(storage: Arc<dyn StorageBackend>, config: &AppConfig) -> Self {
+        Self {
+            storage,
+            database_url: config.database_url.clone(),
+            verify_database_url: config.backup_verify_database_url.clone(),
+            prefix: config.backup_s3_prefix.clone(),
+            interval: Duration::from_secs(config.backup_interval_secs),
+            retention_count: config.backup_retention_count.max(1),
+        }
+    }
+}
+
+// ==================== Errors ====================
+
+#[derive(Debug)]
+pub enum BackupError {
+    NotFound(String),
+    Conflict(String),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for BackupError {
+    fn from(e: sqlx::Error) -> Self {
+        BackupError::Database(e)
+    }
+}
+
+impl BackupError {
+    /// Map to an HTTP response without leaking database internals
+    pub fn to_response<T: serde::Serialize>(&self) -> HttpResponse {
+        match self {
+            BackupError::NotFound(msg) => HttpResponse::NotFound().json(ApiResponse::<T>::error(msg.clone())),
+            BackupError::Conflict(msg) => HttpResponse::Conflict().json(ApiResponse::<T>::error(msg.clone())),
+            BackupError::Database(e) => {
+                log::error!("Backup database error: {}", e);
+                HttpResponse::InternalServerError().json(ApiResponse::<T>::error("Database error".to_string()))
+            }
+        }
+    }
+}
+
+// ==================== Handlers ====================
+
+/// List recent backups, newest first
+///
+/// `GET /api/admin/backups`
+pub async fn list_backups(
+    req: HttpRequest,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    let result = sqlx::query_as::<_, BackupRun>(&format!(
+        "SELECT {} FROM backup_runs ORDER BY started_at DESC LIMIT 100",
+        BACKUP_RUN_COLUMNS
+    ))
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(runs) => HttpResponse::Ok().json(ApiResponse::success(runs)),
+        Err(e) => BackupError::from(e).to_response::<Vec<BackupRun>>(),
+    }
+}
+
+/// Start a backup in the background
+///
+/// `POST /api/admin/backups` - responds 202 with the `running` row
+pub async fn trigger_backup(
+    req: HttpRequest,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+    backups: Option<web::Data<Backups>>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&req, &config, cache.get_ref()).await {
+        return response;
+    }
+    let Some(backups) = backups else {
+        return not_configured::<BackupRun>();
+    };
+
+    let run = match start_run(db.get_ref(), "manual").await {
+        Ok(run) => run,
+        Err(e) => return e.to_response::<BackupRun>(),
+    };
+
+    let pool = db.get_ref().clone();
+    let backups = backups.into_inner();
+    let run_id = run.id;
+    tokio::spawn(async move {
+        if let Err(e) = complete_run(&pool, &backups, run_id).await {
+            log::error!("Failed to record backup {}: {:?}", run_id, e);
+        }
+    });
+
+    log::info!(target: "audit", "backup triggered run_id={}", run.id);
+    HttpResponse::Accepted().json(ApiResponse::success(run))
+}
+
+/// Download a backup and check that it restores
+///
+/// `POST /api/admin/backups/{backup_id}/verify`
+pub async fn verify_backup(
+    req: HttpRequest,
+    backup_id: web::Path<Uuid>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+    backups: Option<web::Data<Backups>>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&req, &config, cache.get_ref()).await {
+        return response;
+    }
+    let Some(backups) = backups else {
+        return not_configured::<BackupRun>();
+    };
+
+    match verify_run(db.get_ref(), &backups, backup_id.into_inner()).await {
+        Ok(run) => HttpResponse::Ok().json(ApiResponse::success(run)),
+        Err(e) => e.to_response::<BackupRun>(),
+    }
+}
+
+fn not_configured<T: serde::Serialize>() -> HttpResponse {
+    HttpResponse::ServiceUnavailable()
+        .json(ApiResponse::<T>::error("Backups are not configured".to_string()))
+}
+
+// ==================== Scheduled Job ====================
+
+/// Run a scheduled backup if the last one is older than the interval
+///
+/// Returns the finished run, or `None` if no backup was due.
+pub async fn run_scheduled_backup(pool: &PgPool, backups: &Backups) -> Result<Option<BackupRun>, BackupError> {
+    let (recent,): (bool,) = sqlx::query_as(
+        "SELECT EXISTS (
+             SELECT 1 FROM backup_runs
+             WHERE triggered_by = 'scheduled' AND status IN ('running', 'succeeded', 'expired')
+               AND started_at > $1
+         )",
+    )
+    .bind(Utc::now() - chrono::Duration::seconds(backups.interval.as_secs() as i64))
+    .fetch_one(pool)
+    .await?;
+    if recent {
+        return Ok(None);
+    }
+
+    // A manual backup in progress counts as this slot's backup being under way
+    let run = match start_run(pool, "scheduled").await {
+        Ok(run) => run,
+        Err(BackupError::Conflict(_)) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    complete_run(pool, backups, run.id).await.map(Some)
+}
+
+// ==================== Backup Runs ====================
+
+/// Insert a `running` row, unless another backup is in progress
+async fn start_run(pool: &PgPool, triggered_by: &str) -> Result<BackupRun, BackupError> {
+    let run = sqlx::query_as::<_, BackupRun>(&format!(
+        "INSERT INTO backup_runs (id, triggered_by, status, started_at)
+         SELECT $1, $2, 'running', $3
+         WHERE NOT EXISTS (SELECT 1 FROM backup_runs WHERE status = 'running' AND started_at > $4)
+         RETURNING {}",
+        BACKUP_RUN_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(triggered_by)
+    .bind(Utc::now())
+    .bind(Utc::now() - chrono::Duration::seconds(STALE_RUN_SECS))
+    .fetch_optional(pool)
+    .await?;
+
+    run.ok_or_else(|| BackupError::Conflict("A backup is already running".to_string()))
+}
+
+/// Dump, upload, then apply retention and verify
+///
+/// Backup and verification outcomes are recorded on the row; only failing to
+/// record them is an error.
+async fn complete_run(pool: &PgPool, backups: &Backups, run_id: Uuid) -> Result<BackupRun, BackupError> {
+    let outcome = dump_and_upload(backups, run_id).await;
+
+    let update = match &outcome {
+        Ok((key, size, sha256)) => sqlx::query_as::<_, BackupRun>(&format!(
+            "UPDATE backup_runs SET status = 'succeeded', object_key = $2, size_bytes = $3, sha256 = $4, finished_at = $5
+             WHERE id = $1 RETURNING {}",
+            BACKUP_RUN_COLUMNS
+        ))
+        .bind(run_id)
+        .bind(key)
+        .bind(*size)
+        .bind(sha256)
+        .bind(Utc::now())
+        .fetch_one(pool)
+        .await,
+        Err(message) => {
+            log::error!("Backup {} failed: {}", run_id, message);
+            sqlx::query_as::<_, BackupRun>(&format!(
+                "UPDATE backup_runs SET status = 'failed', error = $2, finished_at = $3
+                 WHERE id = $1 RETURNING {}",
+                BACKUP_RUN_COLUMNS
+            ))
+            .bind(run_id)
+            .bind(message)
+            .bind(Utc::now())
+            .fetch_one(pool)
+            .await
+        }
+    };
+
+    let mut run = update?;
+    if run.status != "succeeded" {
+        return Ok(run);
+    }
+    log::info!("Backup {} uploaded ({} bytes)", run_id, run.size_bytes.unwrap_or_default());
+
+    if let Err(e) = apply_retention(pool, backups).await {
+        log::error!("Backup retention failed: {:?}", e);
+    }
+    match verify_run(pool, backups, run_id).await {
+        Ok(verified) => run = verified,
+        Err(e) => log::error!("Backup {} verification could not run: {:?}", run_id, e),
+    }
+    Ok(run)
+}
+
+/// Run pg_dump and upload the archive; returns (object key, size, SHA-256)
+async fn dump_and_upload(backups: &Backups, run_id: Uuid) -> Result<(String, i64, String), String> {
+    let output = Command::new("pg_dump")
+        .arg("--format=custom")
+        .arg("--no-owner")
+        .arg("--no-privileges")
+        .arg(format!("--dbname={}", backups.database_url))
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run pg_dump: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("pg_dump exited with {}: {}", output.status, stderr_excerpt(&output.stderr)));
+    }
+
+    let dump = output.stdout;
+    let sha256 = hex::encode(Sha256::digest(&dump));
+    let key = format!(
+        "{}ketobook-{}-{}.dump",
+        backups.prefix,
+        Utc::now().format("%Y%m%dT%H%M%SZ"),
+        run_id.simple()
+    );
+
+    backups
+        .storage
+        .put(&key, &dump)
+        .await
+        .map_err(|e| format!("Upload failed: {}", e))?;
+
+    Ok((key, dump.len() as i64, sha256))
+}
+
+/// Delete all but the newest `retention_count` succeeded backups
+async fn apply_retention(pool: &PgPool, backups: &Backups) -> Result<usize, BackupError> {
+    let expired: Vec<(Uuid, String)> = sqlx::query_as(
+        "SELECT id, object_key FROM backup_runs
+         WHERE status = 'succeeded' AND object_key IS NOT NULL
+         ORDER BY started_at DESC
+         OFFSET $1",
+    )
+    .bind(i64::from(backups.retention_count))
+    .fetch_all(pool)
+    .await?;
+
+    let mut removed = 0;
+    for (id, key) in expired {
+        if let Err(e) = backups.storage.delete(&key).await {
+            // Keep the row as succeeded so the next run retries the delete
+            log::error!("Failed to delete expired backup {}: {}", key, e);
+            continue;
+        }
+        sqlx::query("UPDATE backup_runs SET status = 'expired' WHERE id = $1")
+            .bind(id)
+            .execute(pool)
+            .await?;
+        removed += 1;
+    }
+
+    if removed > 0 {
+        log::info!("Backup retention removed {} backup(s)", removed);
+    }
+    Ok(removed)
+}
+
+// ==================== Restore Verification ====================
+
+/// Check a succeeded backup end to end and record the result on its row
+async fn verify_run(pool: &PgPool, backups: &Backups, run_id: Uuid) -> Result<BackupRun, BackupError> {
+    let run = sqlx::query_as::<_, BackupRun>(&format!("SELECT {} FROM backup_runs WHERE id = $1", BACKUP_RUN_COLUMNS))
+        .bind(run_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| BackupError::NotFound("Backup not found".to_string()))?;
+
+    let (Some(key), Some(expected_sha256)) = (run.object_key.as_deref(), run.sha256.as_deref()) else {
+        return Err(BackupError::Conflict("Backup has no stored object".to_string()));
+    };
+    if run.status != "succeeded" {
+        return Err(BackupError::Conflict(format!("Backup is {}", run.status)));
+    }
+
+    let result = check_restore(backups, run_id, key, expected_sha256).await;
+    if let Err(message) = &result {
+        log::error!("Backup {} failed verification: {}", run_id, message);
+    }
+
+    let run = sqlx::query_as::<_, BackupRun>(&format!(
+        "UPDATE backup_runs SET verify_status = $2, verify_error = $3, verified_at = $4
+         WHERE id = $1 RETURNING {}",
+        BACKUP_RUN_COLUMNS
+    ))
+    .bind(run_id)
+    .bind(if result.is_ok() { "passed" } else { "failed" })
+    .bind(result.err())
+    .bind(Utc::now())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(run)
+}
+
+/// Download, checksum and (trial-)restore a backup object
+async fn check_restore(backups: &Backups, run_id: Uuid, key: &str, expected_sha256: &str) -> Result<(), String> {
+    let dump = backups
+        .storage
+        .get(key)
+        .await
+        .map_err(|e| format!("Download failed: {}", e))?;
+
+    let sha256 = hex::encode(Sha256::digest(&dump));
+    if sha256 != expected_sha256 {
+        return Err(format!("Checksum mismatch: expected {}, got {}", expected_sha256, sha256));
+    }
+
+    let path = std::env::temp_dir().join(format!("ketobook-verify-{}.dump", run_id.simple()));
+    tokio::fs::write(&path, &dump)
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    let result = restore_checks(backups, &path).await;
+    let _ = tokio::fs::remove_file(&path).await;
+    result
+}
+
+async fn restore_checks(backups: &Backups, path: &std::path::Path) -> Result<(), String> {
+    // The archive's table of contents must be readable
+    let list = Command::new("pg_restore")
+        .arg("--list")
+        .arg(path)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run pg_restore: {}", e))?;
+    if !list.status.success() {
+        return Err(format!("pg_restore --list exited with {}: {}", list.status, stderr_excerpt(&list.stderr)));
+    }
+
+    // Full restore into the scratch database, when one is configured
+    if let Some(verify_url) = &backups.verify_database_url {
+        let restore = Command::new("pg_restore")
+            .arg("--clean")
+            .arg("--if-exists")
+            .arg("--no-owner")
+            .arg("--no-privileges")
+            .arg("--exit-on-error")
+            .arg(format!("--dbname={}", verify_url))
+            .arg(path)
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run pg_restore: {}", e))?;
+        if !restore.status.success() {
+            return Err(format!("Trial restore exited with {}: {}", restore.status, stderr_excerpt(&restore.stderr)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Last part of a tool's stderr, for error messages
+fn stderr_excerpt(stderr: &[u8]) -> String {
+    let text = String::from_utf8_lossy(stderr);
+    let text = text.trim();
+    let start = text.char_indices().rev().nth(499).map(|(i, _)| i).unwrap_or(0);
+    text[start..].to_string()
+}
@@ -0,0 +1,292 @@
+use std::time::Duration;
+
+use actix_web::{web, HttpResponse};
+use chrono::{Datelike, Days, Months, NaiveDate, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::explain::DELTA;
+use crate::models::{ApiResponse, BalanceHistory, BalanceHistoryQuery, BalancePoint};
+use crate::telemetry;
+use crate::wallet_members::{self, Permission};
+use crate::wallets::fetch_wallet_by_id;
+
+// ==================== BALANCE HISTORY ====================
+//
+// `GET /api/wallets/{user_id}/{wallet_id}/history` returns a wallet's balance
+// at the end of each day, week or month, for charting. Points come from
+// `wallet_balance_snapshots`, one closing balance per wallet and UTC day:
+//
+// - `SNAPSHOT_JOB` writes the closing balance of every wallet for each of the
+//   last `SNAPSHOT_DAYS` completed days, the same figure an `?as_of=` read at
+//   the end of the day gives (see history.rs). Recomputing a few days back
+//   picks up late edits and days missed while the job was down; edits to
+//   older transactions don't change their snapshots.
+// - Days before the table existed were seeded from the ledger by its
+//   migration, with rows only on days that had transactions.
+// - Setting a wallet's opening balance shifts its snapshots before that day
+//   and retakes every day since (see opening_balances.rs).
+//
+// A period's point is the last snapshot on or before its end, so days
+// without a row carry the previous balance forward. The current period ends
+// with the live balance. Periods before the wallet's first snapshot are left
+// out. Soft-deleted wallets are not snapshotted.
+//
+// ============================================================================
+
+pub const SNAPSHOT_JOB: &str = "wallet_balance_snapshots";
+
+/// How often snapshots are taken
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Completed days recomputed by each run
+const SNAPSHOT_DAYS: i32 = 7;
+
+/// Most points one request may ask for
+const MAX_POINTS: i64 = 366;
+
+/// Periods returned without `?from=`, per granularity
+const DEFAULT_DAYS: u64 = 30;
+const DEFAULT_WEEKS: u64 = 26;
+const DEFAULT_MONTHS: u32 = 12;
+
+/// Length of one period of the series
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Granularity {
+    Day,
+    Week,
+    Month,
+}
+
+impl Granularity {
+    /// `?granularity=`, or `default` without one
+    pub(crate) fn parse(value: Option<&str>, default: Granularity) -> Result<Self, String> {
+        let Some(value) = value else {
+            return Ok(default);
+        };
+        match value {
+            "day" => Ok(Granularity::Day),
+            "week" => Ok(Granularity::Week),
+            "month" => Ok(Granularity::Month),
+            other => Err(format!("Invalid granularity '{}'; expected day, week or month", other)),
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Granularity::Day => "day",
+            Granularity::Week => "week",
+            Granularity::Month => "month",
+        }
+    }
+
+    /// One period as `make_interval` (days, months)
+    fn step(self) -> (i32, i32) {
+        match self {
+            Granularity::Day => (1, 0),
+            Granularity::Week => (7, 0),
+            Granularity::Month => (0, 1),
+        }
+    }
+
+    /// Start of the default range ending at `to`
+    fn default_from(self, to: NaiveDate) -> NaiveDate {
+        let from = match self {
+            Granularity::Day => to.checked_sub_days(Days::new(DEFAULT_DAYS - 1)),
+            Granularity::Week => to.checked_sub_days(Days::new((DEFAULT_WEEKS - 1) * 7)),
+            Granularity::Month => to.checked_sub_months(Months::new(DEFAULT_MONTHS - 1)),
+        };
+        from.unwrap_or(to)
+    }
+
+    /// Periods from the one containing `from` to the one containing `to`
+    fn period_count(self, from: NaiveDate, to: NaiveDate) -> i64 {
+        match self {
+            Granularity::Day => (to - from).num_days() + 1,
+            Granularity::Week => {
+                let monday = from - chrono::Duration::days(from.weekday().num_days_from_monday() as i64);
+                (to - monday).num_days() / 7 + 1
+            }
+            Granularity::Month => (to.year() - from.year()) as i64 * 12 + to.month() as i64 - from.month() as i64 + 1,
+        }
+    }
+}
+
+// ==================== Handlers ====================
+
+/// A wallet's closing balance per day, week or month, oldest first
+///
+/// Supports `?granularity=day|week|month` and a `?from=&to=` date range
+/// (default: the last 30 days, 26 weeks or 12 months).
+pub async fn get_balance_history(
+    path: web::Path<(String, String)>,
+    query: web::Query<BalanceHistoryQuery>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, wallet_id) = path.into_inner();
+    let granularity =
+        Granularity::parse(query.granularity.as_deref(), Granularity::Day).map_err(AppError::Validation)?;
+
+    let today = Utc::now().date_naive();
+    let (from, to) = resolve_range(granularity, query.from, query.to, today).map_err(AppError::Validation)?;
+
+    let access = wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::View).await?;
+    let wallet = fetch_wallet_by_id(db.get_ref(), &wallet_id, &access.owner_id)
+        .await
+        .map_err(AppError::or_not_found("Wallet not found"))?;
+
+    let points = fetch_balance_points(db.get_ref(), &wallet.id, granularity, from, to, today, &wallet.balance)
+        .await
+        .map_err(AppError::database("Failed to fetch balance history"))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(BalanceHistory {
+        wallet_id: wallet.id,
+        currency: wallet.currency,
+        granularity: granularity.as_str().to_string(),
+        from,
+        to,
+        points,
+    })))
+}
+
+/// The `?from=&to=` range, defaulted and checked against `MAX_POINTS`
+pub(crate) fn resolve_range(
+    granularity: Granularity,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    today: NaiveDate,
+) -> Result<(NaiveDate, NaiveDate), String> {
+    let to = to.unwrap_or(today);
+    if to > today {
+        return Err("to must not be in the future".to_string());
+    }
+    let from = from.unwrap_or_else(|| granularity.default_from(to));
+    if from > to {
+        return Err("from must not be after to".to_string());
+    }
+    if granularity.period_count(from, to) > MAX_POINTS {
+        return Err(format!(
+            "The range spans more than {} {}s; narrow it or use a coarser granularity",
+            MAX_POINTS,
+            granularity.as_str()
+        ));
+    }
+    Ok((from, to))
+}
+
+// ==================== Database Functions ====================
+
+/// The last snapshot on or before the end of each period; today is the live balance
+pub(crate) async fn fetch_balance_points(
+    pool: &PgPool,
+    wallet_id: &Uuid,
+    granularity: Granularity,
+    from: NaiveDate,
+    to: NaiveDate,
+    today: NaiveDate,
+    live_balance: &BigDecimal,
+) -> Result<Vec<BalancePoint>, sqlx::Error> {
+    let (step_days, step_months) = granularity.step();
+    telemetry::sql(
+        "wallet_balance_snapshots",
+        "select",
+        sqlx::query_as::<_, BalancePoint>(
+            "WITH periods AS (
+                 SELECT p::date AS period_start,
+                        LEAST((p + make_interval(days => $6, months => $7))::date - 1, $3) AS period_end
+                 FROM generate_series(date_trunc($4, $2::timestamp), $3::timestamp,
+                                      make_interval(days => $6, months => $7)) p
+             )
+             SELECT p.period_start, s.balance
+             FROM periods p
+             JOIN LATERAL (
+                 SELECT balance
+                 FROM (
+                     SELECT snapshot_date, balance FROM wallet_balance_snapshots WHERE wallet_id = $1
+                     UNION ALL
+                     SELECT $5, $8
+                 ) s
+                 WHERE s.snapshot_date <= p.period_end
+                 ORDER BY s.snapshot_date DESC
+                 LIMIT 1
+             ) s ON TRUE
+             ORDER BY p.period_start",
+        )
+        .bind(wallet_id)
+        .bind(from)
+        .bind(to)
+        .bind(granularity.as_str())
+        .bind(today)
+        .bind(step_days)
+        .bind(step_months)
+        .bind(live_balance)
+        .fetch_all(pool),
+    )
+    .await
+}
+
+// ==================== Snapshot Job ====================
+
+/// Write the closing balance of every wallet for the last `SNAPSHOT_DAYS`
+/// completed days; returns how many snapshots were written or changed
+pub async fn take_snapshots(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(&upsert_snapshots(
+        "SELECT (CURRENT_TIMESTAMP AT TIME ZONE 'UTC')::date - n AS day FROM generate_series(1, $1) n",
+        "w.deleted_at IS NULL",
+    ))
+    .bind(SNAPSHOT_DAYS)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Rewrite one wallet's closing balances from `from` through yesterday, after
+/// its past balances changed (see opening_balances.rs)
+pub(crate) async fn retake_snapshots(conn: &mut PgConnection, wallet_id: Uuid, from: NaiveDate) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(&upsert_snapshots(
+        "SELECT day::date AS day
+         FROM generate_series($1::date, (CURRENT_TIMESTAMP AT TIME ZONE 'UTC')::date - 1, INTERVAL '1 day') day",
+        "w.id = $2",
+    ))
+    .bind(from)
+    .bind(wallet_id)
+    .execute(conn)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Upsert the closing balance of the wallets matching `wallets` on each
+/// `day` returned by the `days` query
+fn upsert_snapshots(days: &str, wallets: &str) -> String {
+    format!(
+        "WITH days AS (
+             SELECT day, (day + 1)::timestamp AT TIME ZONE 'UTC' AS day_end
+             FROM ({days}) d
+         )
+         INSERT INTO wallet_balance_snapshots (wallet_id, user_id, snapshot_date, balance)
+         SELECT w.id, w.user_id, d.day, COALESCE(h.opening_balance, w.opening_balance) + COALESCE(l.net, 0)
+         FROM wallets w
+         JOIN days d ON w.created_at < d.day_end
+         LEFT JOIN LATERAL (
+             SELECT wh.opening_balance
+             FROM wallet_history wh
+             WHERE wh.wallet_id = w.id AND wh.changed_at >= d.day_end
+             ORDER BY wh.changed_at
+             LIMIT 1
+         ) h ON TRUE
+         LEFT JOIN LATERAL (
+             SELECT SUM({delta}) AS net
+             FROM transactions t
+             WHERE t.wallet_id = w.id AND t.deleted_at IS NULL AND t.created_at < d.day_end
+         ) l ON TRUE
+         WHERE {wallets}
+         ON CONFLICT (wallet_id, snapshot_date) DO UPDATE
+             SET balance = EXCLUDED.balance, taken_at = CURRENT_TIMESTAMP
+             WHERE wallet_balance_snapshots.balance IS DISTINCT FROM EXCLUDED.balance",
+        days = days,
+        wallets = wallets,
+        delta = DELTA
+    )
+}
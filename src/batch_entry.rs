@@ -0,0 +1,412 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use actix_web::{web, HttpResponse};
+use serde_json::{Map, Value};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::cache::CacheBackend;
+use crate::freezes;
+use crate::policy;
+use crate::transaction_fields;
+use crate::transaction_splits;
+use crate::transactions;
+use crate::wallet_types;
+use crate::models::{
+    ApiResponse, BatchFieldError, BatchRowValidation, BatchValidateRequest, BatchValidation, SplitLine,
+    TransactionFieldDefinition, TransactionPolicy, Wallet, WalletBucket, WalletTemplate,
+};
+
+// ==================== BATCH ENTRY VALIDATION ====================
+//
+// `POST /api/transactions/batch-validate` checks spreadsheet-style draft rows
+// before the web client commits them, so errors can be shown inline next to
+// the offending cell. Nothing is written.
+//
+// Rows are raw JSON objects with the fields of `POST /api/transactions`, so a
+// malformed cell ("12,50", a missing wallet) is reported on its row instead
+// of rejecting the whole request. Every problem of a row is reported, one per
+// field.
+//
+// Rows are checked in order as if each valid row before them had been
+// created: three 40.00 expenses on a wallet holding 100.00 fail on the third
+// row. Invalid rows don't count toward later rows. The checks mirror
+// `transactions::create_transaction`, including the transaction policy,
+// bucket coverage and frozen wallets, but the result is only a preview:
+// balances can change before the rows are committed.
+//
+// ============================================================================
+
+/// Maximum number of rows in one batch
+const MAX_BATCH_ROWS: usize = 500;
+
+/// Fields a draft row may carry
+const ROW_FIELDS: [&str; 10] = [
+    "wallet_id",
+    "amount",
+    "transaction_type",
+    "category",
+    "description",
+    "notes",
+    "metadata",
+    "bucket_id",
+    "income_source_id",
+    "splits",
+];
+
+const WALLET_COLUMNS: &str = "id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, created_at, updated_at, version";
+
+// ==================== Handlers ====================
+
+/// `POST /api/transactions/batch-validate` - validate draft rows without saving them
+pub async fn validate_batch(
+    req: web::Json<BatchValidateRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    if req.rows.is_empty() || req.rows.len() > MAX_BATCH_ROWS {
+        return HttpResponse::BadRequest().json(ApiResponse::<BatchValidation>::error(format!(
+            "rows must contain 1-{} rows",
+            MAX_BATCH_ROWS
+        )));
+    }
+
+    let mut ledger = match Ledger::load(db.get_ref(), cache.get_ref(), &req.user_id).await {
+        Ok(ledger) => ledger,
+        Err(e) => {
+            log::error!("Error loading batch validation state: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<BatchValidation>::error("Database error".to_string()));
+        }
+    };
+
+    let mut rows = Vec::with_capacity(req.rows.len());
+    for (index, row) in req.rows.iter().enumerate() {
+        let errors = match ledger.check_row(db.get_ref(), row).await {
+            Ok(errors) => errors,
+            Err(e) => {
+                log::error!("Error validating batch row: {}", e);
+                return HttpResponse::InternalServerError()
+                    .json(ApiResponse::<BatchValidation>::error("Database error".to_string()));
+            }
+        };
+        rows.push(BatchRowValidation {
+            index,
+            valid: errors.is_empty(),
+            errors,
+        });
+    }
+
+    let invalid_rows = rows.iter().filter(|r| !r.valid).count();
+    HttpResponse::Ok().json(ApiResponse::success(BatchValidation {
+        valid: invalid_rows == 0,
+        invalid_rows,
+        rows,
+    }))
+}
+
+// ==================== Validation ====================
+
+/// The user's wallets and buckets as the valid rows so far would leave them
+struct Ledger {
+    wallets: HashMap<Uuid, Wallet>,
+    templates: HashMap<Uuid, WalletTemplate>,
+    buckets: HashMap<Uuid, WalletBucket>,
+    frozen: HashSet<Uuid>,
+    income_sources: HashSet<Uuid>,
+    field_definitions: Vec<TransactionFieldDefinition>,
+    policy: TransactionPolicy,
+}
+
+impl Ledger {
+    async fn load(pool: &PgPool, cache: &dyn CacheBackend, user_id: &str) -> Result<Self, sqlx::Error> {
+        let wallets = sqlx::query_as::<_, Wallet>(&format!(
+            "SELECT {} FROM wallets WHERE user_id = $1 AND deleted_at IS NULL",
+            WALLET_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        let buckets = sqlx::query_as::<_, WalletBucket>(
+            "SELECT id, wallet_id, user_id, name, balance, target_amount, created_at, updated_at
+             FROM wallet_buckets WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        let income_sources: Vec<(Uuid,)> =
+            sqlx::query_as("SELECT id FROM income_sources WHERE user_id = $1 AND active")
+                .bind(user_id)
+                .fetch_all(pool)
+                .await?;
+
+        Ok(Self {
+            wallets: wallets.into_iter().map(|w| (w.id, w)).collect(),
+            templates: HashMap::new(),
+            buckets: buckets.into_iter().map(|b| (b.id, b)).collect(),
+            frozen: freezes::frozen_wallet_ids(pool, user_id).await?.into_iter().collect(),
+            income_sources: income_sources.into_iter().map(|(id,)| id).collect(),
+            field_definitions: transaction_fields::fetch_field_definitions(pool, user_id).await?,
+            policy: policy::transaction_policy(pool, cache).await?,
+        })
+    }
+
+    /// Errors of one row; a valid row is applied to the running balances
+    async fn check_row(&mut self, pool: &PgPool, row: &Map<String, Value>) -> Result<Vec<BatchFieldError>, sqlx::Error> {
+        let mut errors = Vec::new();
+        let mut error = |field: &str, message: String| {
+            errors.push(BatchFieldError {
+                field: field.to_string(),
+                message,
+            })
+        };
+
+        for key in row.keys().filter(|k| !ROW_FIELDS.contains(&k.as_str())) {
+            error(key, format!("Unknown field '{}'", key));
+        }
+
+        let wallet_id = match uuid_field(row, "wallet_id", true) {
+            Ok(Some(id)) if !self.wallets.contains_key(&id) => {
+                error("wallet_id", "Wallet not found or doesn't belong to user".to_string());
+                None
+            }
+            Ok(Some(id)) if self.frozen.contains(&id) => {
+                error("wallet_id", format!("Wallet {} is frozen", id));
+                None
+            }
+            Ok(id) => id,
+            Err(msg) => {
+                error("wallet_id", msg);
+                None
+            }
+        };
+
+        let transaction_type = match string_field(row, "transaction_type", true) {
+            Ok(Some(t)) if t != "income" && t != "expense" => {
+                error(
+                    "transaction_type",
+                    "Invalid transaction type. Must be 'income' or 'expense'".to_string(),
+                );
+                None
+            }
+            Ok(t) => t,
+            Err(msg) => {
+                error("transaction_type", msg);
+                None
+            }
+        };
+
+        let amount = match amount_field(row) {
+            Ok(amount) => Some(amount),
+            Err(msg) => {
+                error("amount", msg);
+                None
+            }
+        };
+
+        if let Err(msg) = string_field(row, "category", true) {
+            error("category", msg);
+        }
+
+        match string_field(row, "description", true) {
+            Ok(description) => {
+                if let Some(amount) = &amount {
+                    for violation in policy::check(&self.policy, amount, description.unwrap_or("")) {
+                        error(violation.field, violation.message);
+                    }
+                }
+            }
+            Err(msg) => error("description", msg),
+        }
+
+        match row.get("splits") {
+            None | Some(Value::Null) => {}
+            Some(splits) => match serde_json::from_value::<Vec<SplitLine>>(splits.clone()) {
+                Ok(lines) => {
+                    if let Some(amount) = &amount
+                        && !lines.is_empty()
+                        && let Err(msg) = transaction_splits::check_lines(amount, &lines)
+                    {
+                        error("splits", msg);
+                    }
+                }
+                Err(_) => error("splits", "splits must be a list of {category, amount} lines".to_string()),
+            },
+        }
+
+        match string_field(row, "notes", false) {
+            Ok(notes) => {
+                if let Err(msg) = transactions::validate_notes(notes) {
+                    error("notes", msg);
+                }
+            }
+            Err(msg) => error("notes", msg),
+        }
+
+        match row.get("metadata") {
+            None | Some(Value::Null) => {}
+            Some(Value::Object(metadata)) => {
+                if let Err(msg) = transaction_fields::check_metadata(&self.field_definitions, metadata) {
+                    error("metadata", msg);
+                }
+            }
+            Some(_) => error("metadata", "metadata must be an object".to_string()),
+        }
+
+        let bucket_id = match uuid_field(row, "bucket_id", false) {
+            Ok(Some(_)) if transaction_type == Some("income") => {
+                error("bucket_id", "Only expenses can draw from a bucket".to_string());
+                None
+            }
+            Ok(Some(id)) => match (self.buckets.get(&id), wallet_id) {
+                (Some(bucket), Some(wallet_id)) if bucket.wallet_id != wallet_id => {
+                    error("bucket_id", "Bucket not found in this wallet".to_string());
+                    None
+                }
+                (None, _) => {
+                    error("bucket_id", "Bucket not found in this wallet".to_string());
+                    None
+                }
+                _ => Some(id),
+            },
+            Ok(None) => None,
+            Err(msg) => {
+                error("bucket_id", msg);
+                None
+            }
+        };
+
+        match uuid_field(row, "income_source_id", false) {
+            Ok(Some(_)) if transaction_type == Some("expense") => {
+                error("income_source_id", "Only income can have an income source".to_string());
+            }
+            Ok(Some(id)) if !self.income_sources.contains(&id) => {
+                error("income_source_id", "Income source not found or inactive".to_string());
+            }
+            Ok(_) => {}
+            Err(msg) => error("income_source_id", msg),
+        }
+
+        // Funds are only checked once the row is otherwise valid
+        if !errors.is_empty() {
+            return Ok(errors);
+        }
+        let (Some(wallet_id), Some(transaction_type), Some(amount)) = (wallet_id, transaction_type, amount) else {
+            return Ok(errors);
+        };
+        if let Err(msg) = self.apply(pool, wallet_id, transaction_type, &amount, bucket_id).await? {
+            errors.push(BatchFieldError {
+                field: "amount".to_string(),
+                message: msg,
+            });
+        }
+        Ok(errors)
+    }
+
+    /// Apply a row to the running balances, or explain why the wallet can't take it
+    async fn apply(
+        &mut self,
+        pool: &PgPool,
+        wallet_id: Uuid,
+        transaction_type: &str,
+        amount: &BigDecimal,
+        bucket_id: Option<Uuid>,
+    ) -> Result<Result<(), String>, sqlx::Error> {
+        let Some(wallet) = self.wallets.get(&wallet_id) else {
+            return Ok(Err("Wallet not found or doesn't belong to user".to_string()));
+        };
+        let template = match self.templates.get(&wallet_id) {
+            Some(template) => *template,
+            None => {
+                let template = wallet_types::template_for(pool, wallet).await?;
+                self.templates.insert(wallet_id, template);
+                template
+            }
+        };
+
+        if transaction_type == "expense" {
+            if template.supports_credit_limit {
+                let Some(limit) = &wallet.credit_limit else {
+                    return Ok(Err("Wallet missing credit limit".to_string()));
+                };
+                let available = limit - &wallet.balance;
+                if *amount > available {
+                    return Ok(Err(format!("Insufficient credit. Available: {}, Required: {}", available, amount)));
+                }
+            } else if !template.is_liability() && *amount > wallet.balance {
+                return Ok(Err(format!(
+                    "Insufficient balance. Available: {}, Required: {}",
+                    wallet.balance, amount
+                )));
+            }
+            if let Some(bucket) = bucket_id.and_then(|id| self.buckets.get(&id))
+                && *amount > bucket.balance
+            {
+                return Ok(Err(format!("Insufficient bucket balance. Available: {}", bucket.balance)));
+            }
+        }
+
+        let balance = match transaction_type {
+            "expense" => &wallet.balance - amount,
+            _ => &wallet.balance + amount,
+        };
+        let allocated: BigDecimal = self
+            .buckets
+            .values()
+            .filter(|b| b.wallet_id == wallet_id && Some(b.id) != bucket_id)
+            .map(|b| b.balance.clone())
+            .sum::<BigDecimal>()
+            + bucket_id
+                .and_then(|id| self.buckets.get(&id))
+                .map(|b| &b.balance - amount)
+                .unwrap_or_default();
+        if allocated > BigDecimal::from(0) && allocated > balance {
+            return Ok(Err(format!(
+                "Wallet balance {} would not cover the {} allocated to buckets",
+                balance, allocated
+            )));
+        }
+
+        if let Some(bucket) = bucket_id.and_then(|id| self.buckets.get_mut(&id)) {
+            bucket.balance -= amount;
+        }
+        if let Some(wallet) = self.wallets.get_mut(&wallet_id) {
+            wallet.balance = balance;
+        }
+        Ok(Ok(()))
+    }
+}
+
+/// A string field; `required` rejects a missing or null value
+fn string_field<'a>(row: &'a Map<String, Value>, field: &str, required: bool) -> Result<Option<&'a str>, String> {
+    match row.get(field) {
+        Some(Value::String(s)) => Ok(Some(s)),
+        None | Some(Value::Null) if required => Err(format!("{} is required", field)),
+        None | Some(Value::Null) => Ok(None),
+        Some(_) => Err(format!("{} must be a string", field)),
+    }
+}
+
+fn uuid_field(row: &Map<String, Value>, field: &str, required: bool) -> Result<Option<Uuid>, String> {
+    match string_field(row, field, required)? {
+        Some(s) => Uuid::parse_str(s.trim())
+            .map(Some)
+            .map_err(|_| format!("{} must be a UUID", field)),
+        None => Ok(None),
+    }
+}
+
+/// An amount, given as a JSON number or a decimal string (the policy checks its range)
+fn amount_field(row: &Map<String, Value>) -> Result<BigDecimal, String> {
+    let parsed = match row.get("amount") {
+        Some(Value::Number(n)) => BigDecimal::from_str(&n.to_string()),
+        Some(Value::String(s)) => BigDecimal::from_str(s.trim()),
+        None | Some(Value::Null) => return Err("amount is required".to_string()),
+        Some(_) => return Err("amount must be a number".to_string()),
+    };
+    parsed.map_err(|_| "amount must be a number".to_string())
+}
@@ -0,0 +1,542 @@
+use actix_web::{web, HttpResponse, ResponseError};
+use sqlx::types::BigDecimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::cache::{get_or_set_cache, user_cache_key, CacheBackend, CacheError};
+use crate::models::{
+    ApiResponse, BucketAmountRequest, BucketTransferRequest, CreateBucketRequest, UpdateBucketRequest,
+    WalletBucket, WalletBuckets, WalletType,
+};
+use crate::wallet_members::{self, Permission};
+use crate::wallet_types;
+use crate::telemetry;
+
+// ==================== WALLET BUCKETS ====================
+//
+// Buckets earmark parts of a wallet's balance for goals (emergency fund,
+// travel). Money only moves between a bucket and the wallet's unallocated
+// balance (allocate/release) or between two buckets (transfer); the wallet
+// balance itself is unchanged by those moves.
+//
+// Invariant: SUM(bucket balances) <= wallet balance. Every write that lowers
+// the wallet balance or raises allocations locks the wallet row and calls
+// `ensure_covered` before committing. Expenses may draw from a bucket
+// (`draw`), which lowers both sides by the same amount; other expenses can
+// only spend the unallocated balance.
+//
+// Lock order is wallet row first, then bucket rows.
+//
+// ============================================================================
+
+/// Maximum number of buckets per wallet
+const MAX_BUCKETS_PER_WALLET: i64 = 20;
+
+const BUCKET_COLUMNS: &str = "id, wallet_id, user_id, name, balance, target_amount, created_at, updated_at";
+
+// ==================== Handlers ====================
+
+/// List a wallet's buckets with allocated/unallocated totals (with caching)
+pub async fn get_wallet_buckets(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, wallet_id) = path.into_inner();
+    let access =
+        match wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::View).await {
+            Ok(access) => access,
+            Err(e) => return e.error_response(),
+        };
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, &format!("buckets:{}", wallet_id)).await;
+
+    let result = get_or_set_cache(cache.get_ref(), &cache_key, async {
+        fetch_wallet_buckets(db.get_ref(), &wallet_id, &access.owner_id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)
+    })
+    .await;
+
+    match result {
+        Ok(buckets) => HttpResponse::Ok().json(ApiResponse::success(buckets)),
+        Err(CacheError::Database(sqlx::Error::RowNotFound)) => HttpResponse::NotFound()
+            .json(ApiResponse::<WalletBuckets>::error("Wallet not found".to_string())),
+        Err(e) => HttpResponse::InternalServerError()
+            .json(ApiResponse::<WalletBuckets>::error(e.to_string())),
+    }
+}
+
+/// Create an empty bucket on a wallet
+pub async fn create_bucket(
+    path: web::Path<(String, String)>,
+    req: web::Json<CreateBucketRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, wallet_id) = path.into_inner();
+
+    if let Err(msg) = validate_bucket(Some(&req.name), req.target_amount.as_ref()) {
+        return HttpResponse::BadRequest().json(ApiResponse::<WalletBucket>::error(msg));
+    }
+    let access =
+        match wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::Manage).await {
+            Ok(access) => access,
+            Err(e) => return e.error_response(),
+        };
+
+    match insert_bucket(db.get_ref(), &wallet_id, &user_id, &req).await {
+        Ok(bucket) => {
+            wallet_members::invalidate_members(db.get_ref(), cache.get_ref(), &user_id, &[access.wallet_id]).await;
+            HttpResponse::Created().json(ApiResponse::success(bucket))
+        }
+        Err(e) => e.to_response::<WalletBucket>(),
+    }
+}
+
+/// Rename a bucket or change its goal
+pub async fn update_bucket(
+    path: web::Path<(String, String, Uuid)>,
+    req: web::Json<UpdateBucketRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, wallet_id, bucket_id) = path.into_inner();
+
+    if let Err(msg) = validate_bucket(req.name.as_deref(), req.target_amount.as_ref()) {
+        return HttpResponse::BadRequest().json(ApiResponse::<WalletBucket>::error(msg));
+    }
+    let access =
+        match wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::Manage).await {
+            Ok(access) => access,
+            Err(e) => return e.error_response(),
+        };
+
+    let result = sqlx::query_as::<_, WalletBucket>(&format!(
+        "UPDATE wallet_buckets
+         SET name = COALESCE($1, name), target_amount = COALESCE($2, target_amount), updated_at = $3
+         WHERE id = $4 AND wallet_id::text = $5 AND user_id = $6
+         RETURNING {}",
+        BUCKET_COLUMNS
+    ))
+    .bind(req.name.as_deref().map(str::trim))
+    .bind(&req.target_amount)
+    .bind(Utc::now())
+    .bind(bucket_id)
+    .bind(&wallet_id)
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(bucket)) => {
+            wallet_members::invalidate_members(db.get_ref(), cache.get_ref(), &user_id, &[access.wallet_id]).await;
+            HttpResponse::Ok().json(ApiResponse::success(bucket))
+        }
+        Ok(None) => HttpResponse::NotFound()
+            .json(ApiResponse::<WalletBucket>::error("Bucket not found".to_string())),
+        Err(e) => BucketError::from(e).to_response::<WalletBucket>(),
+    }
+}
+
+/// Delete a bucket; its balance returns to the wallet's unallocated balance
+pub async fn delete_bucket(
+    path: web::Path<(String, String, Uuid)>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, wallet_id, bucket_id) = path.into_inner();
+    let access =
+        match wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::Manage).await {
+            Ok(access) => access,
+            Err(e) => return e.error_response(),
+        };
+
+    let result = sqlx::query("DELETE FROM wallet_buckets WHERE id = $1 AND wallet_id::text = $2 AND user_id = $3")
+        .bind(bucket_id)
+        .bind(&wallet_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await;
+
+    match result {
+        Ok(query_result) if query_result.rows_affected() > 0 => {
+            wallet_members::invalidate_members(db.get_ref(), cache.get_ref(), &user_id, &[access.wallet_id]).await;
+            HttpResponse::NoContent().finish()
+        }
+        Ok(_) => HttpResponse::NotFound().json(ApiResponse::<String>::error("Bucket not found".to_string())),
+        Err(e) => {
+            log::error!("Error deleting bucket: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<String>::error("Failed to delete bucket".to_string()))
+        }
+    }
+}
+
+/// Move an amount from the unallocated balance into a bucket
+pub async fn allocate_to_bucket(
+    path: web::Path<(String, String, Uuid)>,
+    req: web::Json<BucketAmountRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, wallet_id, bucket_id) = path.into_inner();
+    move_response(db.get_ref(), cache.get_ref(), &user_id, &wallet_id, None, Some(bucket_id), &req.amount).await
+}
+
+/// Move an amount from a bucket back to the unallocated balance
+pub async fn release_from_bucket(
+    path: web::Path<(String, String, Uuid)>,
+    req: web::Json<BucketAmountRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, wallet_id, bucket_id) = path.into_inner();
+    move_response(db.get_ref(), cache.get_ref(), &user_id, &wallet_id, Some(bucket_id), None, &req.amount).await
+}
+
+/// Move an amount between two buckets of the same wallet
+pub async fn transfer_between_buckets(
+    path: web::Path<(String, String)>,
+    req: web::Json<BucketTransferRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, wallet_id) = path.into_inner();
+
+    if req.from_bucket_id == req.to_bucket_id {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<WalletBuckets>::error("Cannot transfer a bucket to itself".to_string()));
+    }
+
+    move_response(
+        db.get_ref(),
+        cache.get_ref(),
+        &user_id,
+        &wallet_id,
+        Some(req.from_bucket_id),
+        Some(req.to_bucket_id),
+        &req.amount,
+    )
+    .await
+}
+
+async fn move_response(
+    pool: &PgPool,
+    cache: &dyn CacheBackend,
+    user_id: &str,
+    wallet_id: &str,
+    from: Option<Uuid>,
+    to: Option<Uuid>,
+    amount: &BigDecimal,
+) -> HttpResponse {
+    if *amount <= BigDecimal::from(0) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<WalletBuckets>::error("Amount must be greater than 0".to_string()));
+    }
+    let access = match wallet_members::authorize_wallet_access(pool, user_id, wallet_id, Permission::Manage).await {
+        Ok(access) => access,
+        Err(e) => return e.error_response(),
+    };
+
+    match move_funds(pool, user_id, wallet_id, from, to, amount).await {
+        Ok(buckets) => {
+            wallet_members::invalidate_members(pool, cache, user_id, &[access.wallet_id]).await;
+            HttpResponse::Ok().json(ApiResponse::success(buckets))
+        }
+        Err(e) => e.to_response::<WalletBuckets>(),
+    }
+}
+
+// ==================== Errors ====================
+
+#[derive(Debug)]
+pub enum BucketError {
+    NotFound(String),
+    Conflict(String),
+    Invalid(String),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for BucketError {
+    fn from(e: sqlx::Error) -> Self {
+        match &e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                BucketError::Conflict("A bucket with this name already exists on the wallet".to_string())
+            }
+            _ => BucketError::Database(e),
+        }
+    }
+}
+
+impl BucketError {
+    /// Map to an HTTP response without leaking database internals
+    pub fn to_response<T: serde::Serialize>(&self) -> HttpResponse {
+        match self {
+            BucketError::NotFound(msg) => HttpResponse::NotFound().json(ApiResponse::<T>::error(msg.clone())),
+            BucketError::Conflict(msg) => HttpResponse::Conflict().json(ApiResponse::<T>::error(msg.clone())),
+            BucketError::Invalid(msg) => HttpResponse::BadRequest().json(ApiResponse::<T>::error(msg.clone())),
+            BucketError::Database(e) => {
+                log::error!("Bucket database error: {}", e);
+                HttpResponse::InternalServerError().json(ApiResponse::<T>::error("Database error".to_string()))
+            }
+        }
+    }
+}
+
+fn validate_bucket(name: Option<&str>, target_amount: Option<&BigDecimal>) -> Result<(), String> {
+    if let Some(name) = name {
+        let len = name.trim().chars().count();
+        if len == 0 || len > 100 {
+            return Err("Bucket name must be 1-100 characters".to_string());
+        }
+    }
+    if let Some(target) = target_amount
+        && *target <= BigDecimal::from(0)
+    {
+        return Err("target_amount must be greater than 0".to_string());
+    }
+    Ok(())
+}
+
+// ==================== Balance Invariant ====================
+
+/// Take `amount` out of a bucket for an expense on `wallet_id`
+///
+/// Call after the wallet row is locked (its balance updated) in the same transaction.
+pub async fn draw(
+    conn: &mut PgConnection,
+    wallet_id: Uuid,
+    bucket_id: Uuid,
+    amount: &BigDecimal,
+) -> Result<(), BucketError> {
+    let balance: Option<(BigDecimal,)> =
+        sqlx::query_as("SELECT balance FROM wallet_buckets WHERE id = $1 AND wallet_id = $2 FOR UPDATE")
+            .bind(bucket_id)
+            .bind(wallet_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+    let Some((balance,)) = balance else {
+        return Err(BucketError::Invalid("Bucket not found in this wallet".to_string()));
+    };
+    if *amount > balance {
+        return Err(BucketError::Invalid(format!("Insufficient bucket balance. Available: {}", balance)));
+    }
+
+    sqlx::query("UPDATE wallet_buckets SET balance = balance - $1, updated_at = $2 WHERE id = $3")
+        .bind(amount)
+        .bind(Utc::now())
+        .bind(bucket_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Return `amount` to a bucket when its expense is reverted (no-op if the bucket was deleted)
+pub async fn refund(conn: &mut PgConnection, bucket_id: Uuid, amount: &BigDecimal) -> Result<(), BucketError> {
+    sqlx::query("UPDATE wallet_buckets SET balance = balance + $1, updated_at = $2 WHERE id = $3")
+        .bind(amount)
+        .bind(Utc::now())
+        .bind(bucket_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Reject the pending change if bucket allocations now exceed the wallet balance
+pub async fn ensure_covered(conn: &mut PgConnection, wallet_id: Uuid) -> Result<(), BucketError> {
+    let (balance, allocated): (BigDecimal, BigDecimal) = sqlx::query_as(
+        "SELECT w.balance, COALESCE((SELECT SUM(b.balance) FROM wallet_buckets b WHERE b.wallet_id = w.id), 0)
+         FROM wallets w WHERE w.id = $1",
+    )
+    .bind(wallet_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if allocated > BigDecimal::from(0) && allocated > balance {
+        return Err(BucketError::Invalid(format!(
+            "Wallet balance {} would not cover the {} allocated to buckets",
+            balance, allocated
+        )));
+    }
+    Ok(())
+}
+
+// ==================== Database Functions ====================
+
+async fn insert_bucket(
+    pool: &PgPool,
+    wallet_id: &str,
+    user_id: &str,
+    req: &CreateBucketRequest,
+) -> Result<WalletBucket, BucketError> {
+    let mut db_tx = pool.begin().await?;
+
+    let wallet: Option<(Uuid, String, Option<Uuid>)> = sqlx::query_as(
+        "SELECT id, wallet_type, custom_type_id FROM wallets WHERE id::text = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE",
+    )
+    .bind(wallet_id)
+    .bind(user_id)
+    .fetch_optional(&mut *db_tx)
+    .await?;
+
+    let Some((wallet_id, wallet_type, custom_type_id)) = wallet else {
+        return Err(BucketError::NotFound("Wallet not found".to_string()));
+    };
+    let wallet_type = WalletType::from_str(&wallet_type).unwrap_or(WalletType::Other);
+    let template = wallet_types::resolve_template(&mut *db_tx, user_id, &wallet_type, custom_type_id)
+        .await?
+        .unwrap_or_else(|| wallet_type.template());
+    if template.is_liability() {
+        return Err(BucketError::Invalid("Liability wallets cannot have buckets".to_string()));
+    }
+
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM wallet_buckets WHERE wallet_id = $1")
+        .bind(wallet_id)
+        .fetch_one(&mut *db_tx)
+        .await?;
+    if count >= MAX_BUCKETS_PER_WALLET {
+        return Err(BucketError::Invalid(format!(
+            "A wallet can have at most {} buckets",
+            MAX_BUCKETS_PER_WALLET
+        )));
+    }
+
+    let now = Utc::now();
+    let bucket = sqlx::query_as::<_, WalletBucket>(&format!(
+        "INSERT INTO wallet_buckets (id, wallet_id, user_id, name, balance, target_amount, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, 0, $5, $6, $6)
+         RETURNING {}",
+        BUCKET_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(wallet_id)
+    .bind(user_id)
+    .bind(req.name.trim())
+    .bind(&req.target_amount)
+    .bind(now)
+    .fetch_one(&mut *db_tx)
+    .await?;
+
+    db_tx.commit().await?;
+    Ok(bucket)
+}
+
+/// Move `amount` out of `from` and into `to`; `None` is the unallocated balance
+async fn move_funds(
+    pool: &PgPool,
+    user_id: &str,
+    wallet_id: &str,
+    from: Option<Uuid>,
+    to: Option<Uuid>,
+    amount: &BigDecimal,
+) -> Result<WalletBuckets, BucketError> {
+    let mut db_tx = pool.begin().await?;
+
+    let wallet: Option<(Uuid,)> =
+        sqlx::query_as("SELECT id FROM wallets WHERE id::text = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE")
+            .bind(wallet_id)
+            .bind(user_id)
+            .fetch_optional(&mut *db_tx)
+            .await?;
+    let Some((wallet_uuid,)) = wallet else {
+        return Err(BucketError::NotFound("Wallet not found".to_string()));
+    };
+
+    if let Some(from) = from {
+        let balance: Option<(BigDecimal,)> =
+            sqlx::query_as("SELECT balance FROM wallet_buckets WHERE id = $1 AND wallet_id = $2 FOR UPDATE")
+                .bind(from)
+                .bind(wallet_uuid)
+                .fetch_optional(&mut *db_tx)
+                .await?;
+        match balance {
+            None => return Err(BucketError::NotFound("Bucket not found".to_string())),
+            Some((balance,)) if *amount > balance => {
+                return Err(BucketError::Invalid(format!("Insufficient bucket balance. Available: {}", balance)));
+            }
+            Some(_) => {}
+        }
+        sqlx::query("UPDATE wallet_buckets SET balance = balance - $1, updated_at = $2 WHERE id = $3")
+            .bind(amount)
+            .bind(Utc::now())
+            .bind(from)
+            .execute(&mut *db_tx)
+            .await?;
+    }
+
+    if let Some(to) = to {
+        let updated = sqlx::query(
+            "UPDATE wallet_buckets SET balance = balance + $1, updated_at = $2 WHERE id = $3 AND wallet_id = $4",
+        )
+        .bind(amount)
+        .bind(Utc::now())
+        .bind(to)
+        .bind(wallet_uuid)
+        .execute(&mut *db_tx)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Err(BucketError::NotFound("Bucket not found".to_string()));
+        }
+    }
+
+    if from.is_none() {
+        ensure_covered(&mut db_tx, wallet_uuid)
+            .await
+            .map_err(|e| match e {
+                BucketError::Invalid(_) => BucketError::Invalid("Insufficient unallocated balance".to_string()),
+                e => e,
+            })?;
+    }
+
+    db_tx.commit().await?;
+
+    fetch_wallet_buckets(pool, wallet_id, user_id)
+        .await?
+        .ok_or_else(|| BucketError::NotFound("Wallet not found".to_string()))
+}
+
+/// A wallet's buckets, by name
+pub async fn fetch_buckets(pool: &PgPool, wallet_id: Uuid) -> Result<Vec<WalletBucket>, sqlx::Error> {
+    telemetry::sql(
+        "wallet_buckets",
+        "select",
+        sqlx::query_as::<_, WalletBucket>(&format!(
+            "SELECT {} FROM wallet_buckets WHERE wallet_id = $1 ORDER BY name",
+            BUCKET_COLUMNS
+        ))
+        .bind(wallet_id)
+        .fetch_all(pool),
+    )
+    .await
+}
+
+async fn fetch_wallet_buckets(
+    pool: &PgPool,
+    wallet_id: &str,
+    user_id: &str,
+) -> Result<Option<WalletBuckets>, sqlx::Error> {
+    let wallet: Option<(Uuid, BigDecimal)> =
+        sqlx::query_as("SELECT id, balance FROM wallets WHERE id::text = $1 AND user_id = $2 AND deleted_at IS NULL")
+            .bind(wallet_id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+
+    let Some((wallet_id, balance)) = wallet else {
+        return Ok(None);
+    };
+
+    let buckets = fetch_buckets(pool, wallet_id).await?;
+    let allocated: BigDecimal = buckets.iter().map(|b| &b.balance).sum();
+    let unallocated = &balance - &allocated;
+
+    Ok(Some(WalletBuckets {
+        wallet_id,
+        balance,
+        allocated,
+        unallocated,
+        buckets,
+    }))
+}
@@ -0,0 +1,119 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+
+use crate::models::{ApiResponse, BudgetAlertSettings, UpdateBudgetAlertSettingsRequest};
+use crate::routes::ScopedRoutes;
+
+// ==================== BUDGET ALERT SETTINGS ====================
+//
+// Each user chooses the percentages of a budget at which they are alerted:
+// `PUT /api/budget-alerts/{user_id}` replaces them, `GET` returns them (the
+// defaults, 80% and 100%, until the user sets their own).
+//
+// A budget crossing one of these thresholds while a transaction is created
+// raises a threshold event; it is detected by comparing the spent total
+// before and after the amount rather than rescanning the period.
+//
+// ============================================================================
+
+/// Thresholds for users who haven't set their own
+pub const DEFAULT_ALERT_THRESHOLDS: [i16; 2] = [80, 100];
+
+/// Most thresholds a user can set
+pub const MAX_ALERT_THRESHOLDS: usize = 5;
+
+/// Highest threshold, as a percentage of the budget
+const MAX_THRESHOLD_PERCENT: i16 = 200;
+
+/// Sort and dedup thresholds, rejecting an empty, oversized or out of range set
+pub fn validate_thresholds(thresholds: &mut Vec<i16>) -> Result<(), String> {
+    thresholds.sort_unstable();
+    thresholds.dedup();
+    if thresholds.is_empty() {
+        return Err("At least one threshold is required".to_string());
+    }
+    if thresholds.len() > MAX_ALERT_THRESHOLDS {
+        return Err(format!("At most {} thresholds are allowed", MAX_ALERT_THRESHOLDS));
+    }
+    if thresholds.iter().any(|t| !(1..=MAX_THRESHOLD_PERCENT).contains(t)) {
+        return Err(format!("Thresholds must be between 1 and {}", MAX_THRESHOLD_PERCENT));
+    }
+    Ok(())
+}
+
+// ==================== Handlers ====================
+
+/// A user's budget alert thresholds
+pub async fn get_budget_alert_settings(user_id: web::Path<String>, db: web::Data<PgPool>) -> HttpResponse {
+    let user_id = user_id.into_inner();
+
+    let result = sqlx::query_as::<_, BudgetAlertSettings>(
+        "SELECT user_id, thresholds, updated_at FROM budget_alert_settings WHERE user_id = $1",
+    )
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(settings)) => HttpResponse::Ok().json(ApiResponse::success(settings)),
+        Ok(None) => HttpResponse::Ok().json(ApiResponse::success(BudgetAlertSettings {
+            user_id,
+            thresholds: DEFAULT_ALERT_THRESHOLDS.to_vec(),
+            updated_at: None,
+        })),
+        Err(e) => {
+            log::error!("Error fetching budget alert settings: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<BudgetAlertSettings>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Replace a user's budget alert thresholds
+pub async fn update_budget_alert_settings(
+    user_id: web::Path<String>,
+    req: web::Json<UpdateBudgetAlertSettingsRequest>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    let mut thresholds = req.into_inner().thresholds;
+    if let Err(e) = validate_thresholds(&mut thresholds) {
+        return HttpResponse::BadRequest().json(ApiResponse::<BudgetAlertSettings>::error(e));
+    }
+
+    let result = sqlx::query_as::<_, BudgetAlertSettings>(
+        "INSERT INTO budget_alert_settings (user_id, thresholds) VALUES ($1, $2)
+         ON CONFLICT (user_id) DO UPDATE
+             SET thresholds = EXCLUDED.thresholds, updated_at = CURRENT_TIMESTAMP
+         RETURNING user_id, thresholds, updated_at",
+    )
+    .bind(&user_id)
+    .bind(&thresholds)
+    .fetch_one(db.get_ref())
+    .await;
+
+    match result {
+        Ok(settings) => {
+            log::info!("Budget alert thresholds of user {} set to {:?}", user_id, settings.thresholds);
+            HttpResponse::Ok().json(ApiResponse::success(settings))
+        }
+        Err(e) => {
+            log::error!("Error updating budget alert settings: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<BudgetAlertSettings>::error("Database error".to_string()))
+        }
+    }
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/budget-alerts")
+        .user(Method::GET, "/{user_id}", get_budget_alert_settings)
+        .user(Method::PUT, "/{user_id}", update_budget_alert_settings)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
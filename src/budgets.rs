@@ -0,0 +1,469 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use bigdecimal::ToPrimitive;
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveTime, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::budget_alerts::{self, DEFAULT_ALERT_THRESHOLDS};
+use crate::cache::{get_or_set_cache, invalidate_user_cache, user_cache_key, CacheBackend};
+use crate::models::{
+    ApiResponse, Budget, BudgetPeriod, BudgetStatus, BudgetWarning, CreateBudgetRequest, Transaction,
+    UpdateBudgetRequest,
+};
+use crate::money;
+use crate::routes::ScopedRoutes;
+use crate::standing_orders::TRANSFER_CATEGORY;
+
+// ==================== BUDGETS ====================
+//
+// A budget limits a user's spending per week or calendar month (UTC), either
+// on one expense category or on all expenses. Overall budgets leave out
+// `transfer` transactions, which only move money between the user's own
+// wallets (see standing_orders.rs, transfers.rs). Transactions on sandbox
+// wallets never count (see api_keys.rs).
+//
+// Budget amounts are in the user's base currency and expenses are converted
+// to it at the current rate (see currencies.rs); expenses in a currency
+// without a rate to it are left out.
+//
+// Spending is always summed from `transactions` for the current period, so
+// edits and deletions are reflected without bookkeeping:
+//
+// - `GET /api/budgets/user/{user_id}/status` reports spent vs remaining per
+//   budget.
+// - Creating an expense compares the budget's total before and after its
+//   amount with each of the budget's `alert_thresholds`, or the user's
+//   thresholds when the budget has none (see budget_alerts.rs). A crossed
+//   threshold is returned with the new transaction as a warning and recorded
+//   once per period in `budget_events`, which the alert job pushes to devices
+//   with `budget_alerts` on (see alerts.rs). Edits and deletions never raise
+//   events.
+// - The notification job raises a notification for every budget over its
+//   amount, once per period (see notifications.rs).
+//
+// ============================================================================
+
+/// Maximum number of budgets per user
+const MAX_BUDGETS_PER_USER: i64 = 50;
+
+const BUDGET_COLUMNS: &str = "id, user_id, name, category, period, amount, alert_thresholds, created_at, updated_at";
+
+/// A user's budgets covering an expense category (all of them when `$7` is
+/// NULL), with what was spent in the current week (`$2`..`$3`) or month
+/// (`$4`..`$5`) and the thresholds that apply (`$8` when neither the budget
+/// nor the user set any)
+const SPENDING_QUERY: &str = "SELECT b.id, b.user_id, b.name, b.category, b.period, b.amount, b.alert_thresholds,
+            b.created_at, b.updated_at,
+            COALESCE(SUM(convert_amount(t.amount, t.currency, base_currency(b.user_id))), 0) AS spent,
+            COALESCE(b.alert_thresholds,
+                     (SELECT s.thresholds FROM budget_alert_settings s WHERE s.user_id = b.user_id),
+                     $8) AS thresholds
+     FROM budgets b
+     LEFT JOIN transactions t
+            ON t.user_id = b.user_id
+           AND t.deleted_at IS NULL
+           AND NOT t.sandbox
+           AND t.transaction_type = 'expense'
+           AND (t.category = b.category OR (b.category IS NULL AND t.category <> $6))
+           AND t.created_at >= CASE WHEN b.period = 'weekly' THEN $2 ELSE $4 END
+           AND t.created_at < CASE WHEN b.period = 'weekly' THEN $3 ELSE $5 END
+     WHERE b.user_id = $1
+       AND ($7::text IS NULL OR b.category = $7 OR (b.category IS NULL AND $7 <> $6))
+     GROUP BY b.id
+     ORDER BY b.name";
+
+/// A budget and what was spent against it this period
+#[derive(sqlx::FromRow)]
+struct BudgetSpending {
+    #[sqlx(flatten)]
+    budget: Budget,
+    spent: BigDecimal,
+    thresholds: Vec<i16>,
+}
+
+// ==================== Handlers ====================
+
+/// List a user's budgets (with caching)
+pub async fn get_user_budgets(
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, "budgets").await;
+
+    let result = get_or_set_cache(cache.get_ref(), &cache_key, fetch_budgets(db.get_ref(), &user_id)).await;
+
+    match result {
+        Ok(budgets) => HttpResponse::Ok().json(ApiResponse::success(budgets)),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<Vec<Budget>>::error(e.to_string())),
+    }
+}
+
+/// Spent vs remaining for each of a user's budgets in the current period
+pub async fn get_budget_status(user_id: web::Path<String>, db: web::Data<PgPool>) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    let now = Utc::now();
+
+    match current_statuses(db.get_ref(), &user_id, now).await {
+        Ok(statuses) => HttpResponse::Ok().json(ApiResponse::success(statuses)),
+        Err(e) => {
+            log::error!("Error computing budget status: {}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<Vec<BudgetStatus>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Create a budget
+pub async fn create_budget(
+    req: web::Json<CreateBudgetRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let name = req.name.trim();
+    let category = req.category.as_deref().map(str::trim);
+    let thresholds = match validate_budget(Some(name), category, Some(&req.amount), req.alert_thresholds.as_deref()) {
+        Ok(thresholds) => thresholds.filter(|thresholds| !thresholds.is_empty()),
+        Err(msg) => return HttpResponse::BadRequest().json(ApiResponse::<Budget>::error(msg)),
+    };
+
+    let count: Result<(i64,), sqlx::Error> = sqlx::query_as("SELECT COUNT(*) FROM budgets WHERE user_id = $1")
+        .bind(&req.user_id)
+        .fetch_one(db.get_ref())
+        .await;
+    match count {
+        Ok((count,)) if count >= MAX_BUDGETS_PER_USER => {
+            return HttpResponse::BadRequest().json(ApiResponse::<Budget>::error(format!(
+                "A user can have at most {} budgets",
+                MAX_BUDGETS_PER_USER
+            )));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Error counting budgets: {}", e);
+            return HttpResponse::InternalServerError().json(ApiResponse::<Budget>::error("Database error".to_string()));
+        }
+    }
+
+    let now = Utc::now();
+    let result = sqlx::query_as::<_, Budget>(&format!(
+        "INSERT INTO budgets (id, user_id, name, category, period, amount, alert_thresholds, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)
+         RETURNING {}",
+        BUDGET_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(name)
+    .bind(category)
+    .bind(req.period.as_str())
+    .bind(&req.amount)
+    .bind(&thresholds)
+    .bind(now)
+    .fetch_one(db.get_ref())
+    .await;
+
+    match result {
+        Ok(budget) => {
+            let _ = invalidate_user_cache(cache.get_ref(), &req.user_id).await;
+            HttpResponse::Created().json(ApiResponse::success(budget))
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            HttpResponse::Conflict().json(ApiResponse::<Budget>::error(format!(
+                "A {} budget for {} already exists",
+                req.period.as_str(),
+                category.unwrap_or("all expenses")
+            )))
+        }
+        Err(e) => {
+            log::error!("Error creating budget: {}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<Budget>::error("Failed to create budget".to_string()))
+        }
+    }
+}
+
+/// Rename a budget or change its limit or thresholds
+pub async fn update_budget(
+    path: web::Path<(String, String)>,
+    req: web::Json<UpdateBudgetRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, budget_id) = path.into_inner();
+    let name = req.name.as_deref().map(str::trim);
+    let thresholds = match validate_budget(name, None, req.amount.as_ref(), req.alert_thresholds.as_deref()) {
+        Ok(thresholds) => thresholds,
+        Err(msg) => return HttpResponse::BadRequest().json(ApiResponse::<Budget>::error(msg)),
+    };
+
+    let result = sqlx::query_as::<_, Budget>(&format!(
+        "UPDATE budgets
+         SET name = COALESCE($1, name),
+             amount = COALESCE($2, amount),
+             alert_thresholds = CASE WHEN $3::SMALLINT[] IS NULL THEN alert_thresholds
+                                     WHEN cardinality($3) = 0 THEN NULL ELSE $3 END,
+             updated_at = $4
+         WHERE id::text = $5 AND user_id = $6
+         RETURNING {}",
+        BUDGET_COLUMNS
+    ))
+    .bind(name)
+    .bind(&req.amount)
+    .bind(thresholds)
+    .bind(Utc::now())
+    .bind(&budget_id)
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(budget)) => {
+            let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+            HttpResponse::Ok().json(ApiResponse::success(budget))
+        }
+        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<Budget>::error("Budget not found".to_string())),
+        Err(e) => {
+            log::error!("Error updating budget: {}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<Budget>::error("Failed to update budget".to_string()))
+        }
+    }
+}
+
+/// Delete a budget and its events
+pub async fn delete_budget(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, budget_id) = path.into_inner();
+
+    let result = sqlx::query("DELETE FROM budgets WHERE id::text = $1 AND user_id = $2")
+        .bind(&budget_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await;
+
+    match result {
+        Ok(query_result) if query_result.rows_affected() > 0 => {
+            let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+            HttpResponse::NoContent().finish()
+        }
+        Ok(_) => HttpResponse::NotFound().json(ApiResponse::<String>::error("Budget not found".to_string())),
+        Err(e) => {
+            log::error!("Error deleting budget: {}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<String>::error("Failed to delete budget".to_string()))
+        }
+    }
+}
+
+/// Check the given fields; returns the thresholds sorted and deduplicated
+/// (empty to fall back to the user's)
+fn validate_budget(
+    name: Option<&str>,
+    category: Option<&str>,
+    amount: Option<&BigDecimal>,
+    alert_thresholds: Option<&[i16]>,
+) -> Result<Option<Vec<i16>>, String> {
+    if let Some(name) = name
+        && (name.is_empty() || name.chars().count() > 100)
+    {
+        return Err("Name must be 1-100 characters".to_string());
+    }
+    if let Some(category) = category
+        && (category.is_empty() || category.chars().count() > 100)
+    {
+        return Err("Category must be 1-100 characters".to_string());
+    }
+    if let Some(amount) = amount
+        && *amount <= BigDecimal::from(0)
+    {
+        return Err("Amount must be greater than 0".to_string());
+    }
+
+    let Some(thresholds) = alert_thresholds else {
+        return Ok(None);
+    };
+    // Empty: the user's thresholds apply
+    if thresholds.is_empty() {
+        return Ok(Some(Vec::new()));
+    }
+    let mut thresholds = thresholds.to_vec();
+    budget_alerts::validate_thresholds(&mut thresholds)?;
+    Ok(Some(thresholds))
+}
+
+// ==================== Spending ====================
+
+/// Start (inclusive) and end (exclusive) of the period containing `at`
+pub fn period_bounds(period: BudgetPeriod, at: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let day = at.date_naive();
+    let (start, end) = match period {
+        BudgetPeriod::Weekly => {
+            let monday = day - Duration::days(i64::from(day.weekday().num_days_from_monday()));
+            (monday, monday + Duration::days(7))
+        }
+        BudgetPeriod::Monthly => {
+            let first = day.with_day(1).unwrap_or(day);
+            (first, first + Months::new(1))
+        }
+    };
+    (midnight(start), midnight(end))
+}
+
+fn midnight(day: NaiveDate) -> DateTime<Utc> {
+    day.and_time(NaiveTime::MIN).and_utc()
+}
+
+async fn fetch_budgets(pool: &PgPool, user_id: &str) -> Result<Vec<Budget>, sqlx::Error> {
+    sqlx::query_as::<_, Budget>(&format!("SELECT {} FROM budgets WHERE user_id = $1 ORDER BY name", BUDGET_COLUMNS))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+}
+
+/// Budgets of `user_id` with their spending in the period containing `at`,
+/// only those covering `category` if given
+async fn fetch_spending(
+    executor: impl PgExecutor<'_>,
+    user_id: &str,
+    at: DateTime<Utc>,
+    category: Option<&str>,
+) -> Result<Vec<BudgetSpending>, sqlx::Error> {
+    let (week_start, week_end) = period_bounds(BudgetPeriod::Weekly, at);
+    let (month_start, month_end) = period_bounds(BudgetPeriod::Monthly, at);
+    sqlx::query_as::<_, BudgetSpending>(SPENDING_QUERY)
+        .bind(user_id)
+        .bind(week_start)
+        .bind(week_end)
+        .bind(month_start)
+        .bind(month_end)
+        .bind(TRANSFER_CATEGORY)
+        .bind(category)
+        .bind(DEFAULT_ALERT_THRESHOLDS.to_vec())
+        .fetch_all(executor)
+        .await
+}
+
+fn status(row: BudgetSpending, now: DateTime<Utc>) -> BudgetStatus {
+    let BudgetSpending { budget, spent, thresholds } = row;
+    let period = BudgetPeriod::from_str(&budget.period).unwrap_or(BudgetPeriod::Monthly);
+    let (period_start, period_end) = period_bounds(period, now);
+    let percent_used = (&spent * BigDecimal::from(100) / &budget.amount).to_f64().unwrap_or(0.0);
+
+    BudgetStatus {
+        period_start,
+        period_end,
+        thresholds,
+        remaining: &budget.amount - &spent,
+        percent_used: (percent_used * 10.0).round() / 10.0,
+        over_limit: spent > budget.amount,
+        spent,
+        budget,
+    }
+}
+
+/// Spent vs remaining for each of a user's budgets in the period containing `now`
+pub(crate) async fn current_statuses(
+    pool: &PgPool,
+    user_id: &str,
+    now: DateTime<Utc>,
+) -> Result<Vec<BudgetStatus>, sqlx::Error> {
+    let rows = fetch_spending(pool, user_id, now, None).await?;
+    Ok(rows.into_iter().map(|row| status(row, now)).collect())
+}
+
+/// Budgets of every user that are over their amount in the period containing `now`
+pub(crate) async fn exceeded_budgets(pool: &PgPool, now: DateTime<Utc>) -> Result<Vec<BudgetStatus>, sqlx::Error> {
+    let user_ids: Vec<(String,)> = sqlx::query_as("SELECT DISTINCT user_id FROM budgets").fetch_all(pool).await?;
+
+    let mut exceeded = Vec::new();
+    for (user_id,) in user_ids {
+        let rows = fetch_spending(pool, &user_id, now, None).await?;
+        exceeded.extend(rows.into_iter().map(|row| status(row, now)).filter(|status| status.over_limit));
+    }
+    Ok(exceeded)
+}
+
+/// Record the budget thresholds a new expense crossed; returns them as warnings
+///
+/// Call inside the DB transaction that inserted `transaction`, so its amount
+/// is part of the period's total.
+pub async fn record_crossings(
+    conn: &mut PgConnection,
+    transaction: &Transaction,
+) -> Result<Vec<BudgetWarning>, sqlx::Error> {
+    if transaction.transaction_type != "expense" {
+        return Ok(Vec::new());
+    }
+
+    let (amount,): (Option<BigDecimal>,) = sqlx::query_as("SELECT convert_amount($1, $2, base_currency($3))")
+        .bind(&transaction.amount)
+        .bind(&transaction.currency)
+        .bind(&transaction.user_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    // Without a rate the expense counts toward no budget
+    let Some(amount) = amount else {
+        return Ok(Vec::new());
+    };
+
+    let spending =
+        fetch_spending(&mut *conn, &transaction.user_id, transaction.created_at, Some(&transaction.category)).await?;
+
+    let mut warnings = Vec::new();
+    for BudgetSpending { budget, spent, thresholds } in spending {
+        let period = BudgetPeriod::from_str(&budget.period).unwrap_or(BudgetPeriod::Monthly);
+        let (period_start, _) = period_bounds(period, transaction.created_at);
+        let before = &spent - &amount;
+
+        for threshold in thresholds {
+            let line = money::percentage(&budget.amount, &BigDecimal::from(threshold));
+            if before >= line || spent < line {
+                continue;
+            }
+
+            sqlx::query(
+                "INSERT INTO budget_events (id, budget_id, user_id, period_start, threshold, spent, transaction_id, created_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                 ON CONFLICT (budget_id, period_start, threshold) DO NOTHING",
+            )
+            .bind(Uuid::new_v4())
+            .bind(budget.id)
+            .bind(&budget.user_id)
+            .bind(period_start)
+            .bind(threshold)
+            .bind(&spent)
+            .bind(transaction.id)
+            .bind(transaction.created_at)
+            .execute(&mut *conn)
+            .await?;
+
+            warnings.push(BudgetWarning {
+                budget_id: budget.id,
+                name: budget.name.clone(),
+                threshold,
+                spent: spent.clone(),
+                limit: budget.amount.clone(),
+                over_limit: spent > budget.amount,
+            });
+        }
+    }
+    Ok(warnings)
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/budgets")
+        .create("", create_budget)
+        .user(Method::GET, "/user/{user_id}", get_user_budgets)
+        .user(Method::GET, "/user/{user_id}/status", get_budget_status)
+        .user(Method::PUT, "/{user_id}/{budget_id}", update_budget)
+        .user(Method::DELETE, "/{user_id}/{budget_id}", delete_budget)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
@@ -0,0 +1,257 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::attachments::{self, StorageBackend};
+use crate::audit::{self, AuditContext};
+use crate::buckets;
+use crate::cache::{invalidate_user_cache, CacheBackend};
+use crate::dry_run::{self, DryRun};
+use crate::error::AppError;
+use crate::hooks::{self, HookContext, WalletsDebited};
+use crate::idempotency::{self, IdempotencyKey};
+use crate::models::{
+    ApiResponse, BulkCreateTransactionsRequest, BulkCreatedTransactions, BulkDeleteTransactionsRequest,
+    BulkDeletedTransactions, Transaction, Wallet,
+};
+use crate::policy;
+use crate::transactions;
+
+// ==================== BULK TRANSACTIONS ====================
+//
+// Importing a month of data one `POST /api/transactions` at a time is slow
+// and invalidates the user's cache on every call. The bulk endpoints take
+// the whole batch in one request and one DB transaction: either every
+// transaction is created (or deleted) or none is.
+//
+// - `POST /bulk` creates up to `MAX_BULK_TRANSACTIONS` transactions with the
+//   checks of a single create (policy, custom fields, splits, buckets,
+//   sanity limits). The wallets involved are locked up front and funds are
+//   checked against their running balances, so three 40.00 expenses on a
+//   wallet holding 100.00 fail on the third. Each wallet's balance is then
+//   moved once by the sum of its transactions, and frozen wallets and bucket
+//   coverage are checked on the result.
+// - `POST /bulk-delete` deletes up to as many transactions by id, soft by
+//   default, reversing their balances the same way.
+//
+// A rejected transaction fails the whole request; the error names it by its
+// position (`transactions[3]: Insufficient balance...`). Both endpoints
+// support dry runs, bulk create an `Idempotency-Key`. After committing, the
+// cache is invalidated once and top-ups run once per wallet.
+//
+// ============================================================================
+
+/// Maximum number of transactions in one bulk request (as in batch-validate)
+const MAX_BULK_TRANSACTIONS: usize = 500;
+
+/// Idempotency key scope of `create_transactions_bulk`
+const BULK_CREATE_ROUTE: &str = "transactions/bulk";
+
+const WALLET_COLUMNS: &str = "id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, created_at, updated_at, version";
+
+const TRANSACTION_COLUMNS: &str = "id, sequence_number, user_id, wallet_id, bucket_id, income_source_id, amount, currency, transaction_type, category, description, notes, metadata, created_at, updated_at, version";
+
+// ==================== Handlers ====================
+
+/// `POST /bulk` - create many transactions in one DB transaction
+pub async fn create_transactions_bulk(
+    req: web::Json<BulkCreateTransactionsRequest>,
+    key: IdempotencyKey,
+    dry_run: DryRun,
+    context: AuditContext,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    if req.transactions.is_empty() || req.transactions.len() > MAX_BULK_TRANSACTIONS {
+        return Err(AppError::Validation(format!(
+            "transactions must contain 1-{} transactions",
+            MAX_BULK_TRANSACTIONS
+        )));
+    }
+    let transaction_policy = policy::transaction_policy(db.get_ref(), cache.get_ref()).await?;
+
+    let mut db_tx = db.begin().await?;
+    audit::attach(&mut db_tx, &context).await?;
+    if let Some(response) = idempotency::replay(&mut db_tx, &key, &req.user_id, BULK_CREATE_ROUTE, &req.0).await? {
+        return Ok(response);
+    }
+    let BulkCreateTransactionsRequest { user_id, transactions: items } = req.into_inner();
+
+    // Lock the wallets up front; funds are checked against their running balances
+    let mut wallet_ids: Vec<Uuid> = items.iter().map(|item| item.wallet_id).collect();
+    wallet_ids.sort_unstable();
+    wallet_ids.dedup();
+    let mut wallets: HashMap<Uuid, Wallet> = sqlx::query_as::<_, Wallet>(&format!(
+        "SELECT {} FROM wallets WHERE user_id = $1 AND id = ANY($2) AND deleted_at IS NULL ORDER BY id FOR UPDATE",
+        WALLET_COLUMNS
+    ))
+    .bind(&user_id)
+    .bind(&wallet_ids)
+    .fetch_all(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to validate wallet"))?
+    .into_iter()
+    .map(|wallet| (wallet.id, wallet))
+    .collect();
+
+    let mut deltas: BTreeMap<Uuid, BigDecimal> = BTreeMap::new();
+    let mut spent_from: HashSet<Uuid> = HashSet::new();
+    let mut created = Vec::with_capacity(items.len());
+    for (index, item) in items.into_iter().enumerate() {
+        let at = format!("transactions[{}]", index);
+        let req = item.into_request(&user_id);
+        let Some(wallet) = wallets.get_mut(&req.wallet_id) else {
+            return Err(AppError::Validation("Wallet not found or doesn't belong to user".to_string()).at(&at));
+        };
+
+        let transaction = transactions::insert_transaction(&mut db_tx, db.get_ref(), &transaction_policy, &req, wallet)
+            .await
+            .map_err(|e| e.at(&at))?;
+        let delta = transactions::balance_delta(&req.transaction_type, &req.amount)?;
+        wallet.balance += &delta;
+        *deltas.entry(req.wallet_id).or_default() += delta;
+        if req.transaction_type == "expense" {
+            spent_from.insert(req.wallet_id);
+        }
+        created.push(transaction);
+    }
+
+    // One balance update per wallet
+    for (wallet_id, delta) in &deltas {
+        transactions::move_balance(&mut db_tx, *wallet_id, delta).await?;
+    }
+
+    let result = BulkCreatedTransactions {
+        created: created.len(),
+        transactions: created,
+    };
+    if dry_run.0 {
+        return dry_run::preview(db.get_ref(), db_tx, &user_id, StatusCode::CREATED, Some(result), &wallet_ids).await;
+    }
+    let response = idempotency::record(
+        &mut db_tx,
+        &key,
+        &user_id,
+        BULK_CREATE_ROUTE,
+        StatusCode::CREATED,
+        &ApiResponse::success(result),
+    )
+    .await?;
+    db_tx.commit().await.map_err(AppError::database("Failed to save changes"))?;
+
+    let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+    let spent_from: Vec<Uuid> = spent_from.into_iter().collect();
+    let cx = HookContext { pool: db.get_ref(), cache: cache.get_ref() };
+    hooks::wallets_debited(&cx, WalletsDebited { owner_id: &user_id, wallet_ids: &spent_from }).await;
+
+    Ok(response)
+}
+
+/// `POST /bulk-delete` - delete many transactions in one DB transaction
+///
+/// Soft-deletes unless `hard` is set, which also purges transactions that
+/// are already soft-deleted.
+pub async fn delete_transactions_bulk(
+    req: web::Json<BulkDeleteTransactionsRequest>,
+    dry_run: DryRun,
+    context: AuditContext,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    storage: web::Data<dyn StorageBackend>,
+) -> Result<HttpResponse, AppError> {
+    if req.transaction_ids.is_empty() || req.transaction_ids.len() > MAX_BULK_TRANSACTIONS {
+        return Err(AppError::Validation(format!(
+            "transaction_ids must contain 1-{} ids",
+            MAX_BULK_TRANSACTIONS
+        )));
+    }
+    let user_id = &req.user_id;
+
+    let mut db_tx = db.begin().await?;
+    audit::attach(&mut db_tx, &context).await?;
+
+    let live = sqlx::query_as::<_, Transaction>(&format!(
+        "SELECT {} FROM transactions
+         WHERE user_id = $1 AND id = ANY($2) AND deleted_at IS NULL
+         ORDER BY id FOR UPDATE",
+        TRANSACTION_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(&req.transaction_ids)
+    .fetch_all(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to fetch transactions"))?;
+
+    // Soft-deleted ones can only be purged; their balances are already reversed
+    let purged: Vec<(Uuid,)> = if req.hard {
+        sqlx::query_as("SELECT id FROM transactions WHERE user_id = $1 AND id = ANY($2) AND deleted_at IS NOT NULL")
+            .bind(user_id)
+            .bind(&req.transaction_ids)
+            .fetch_all(&mut *db_tx)
+            .await
+            .map_err(AppError::database("Failed to fetch transactions"))?
+    } else {
+        Vec::new()
+    };
+
+    let found: HashSet<Uuid> = live.iter().map(|t| t.id).chain(purged.iter().map(|(id,)| *id)).collect();
+    if let Some(index) = req.transaction_ids.iter().position(|id| !found.contains(id)) {
+        return Err(AppError::NotFound("Transaction not found".to_string()).at(&format!("transaction_ids[{}]", index)));
+    }
+
+    // Put bucketed expenses back, then reverse each wallet's balance once
+    let mut deltas: BTreeMap<Uuid, BigDecimal> = BTreeMap::new();
+    let mut income_from: HashSet<Uuid> = HashSet::new();
+    for transaction in &live {
+        if let Some(bucket_id) = transaction.bucket_id {
+            buckets::refund(&mut db_tx, bucket_id, &transaction.amount).await?;
+        }
+        *deltas.entry(transaction.wallet_id).or_default() -=
+            transactions::balance_delta(&transaction.transaction_type, &transaction.amount)?;
+        if transaction.transaction_type == "income" {
+            income_from.insert(transaction.wallet_id);
+        }
+    }
+    for (wallet_id, delta) in &deltas {
+        transactions::move_balance(&mut db_tx, *wallet_id, delta).await?;
+    }
+
+    let sql = if req.hard {
+        "DELETE FROM transactions WHERE user_id = $1 AND id = ANY($2)"
+    } else {
+        "UPDATE transactions SET deleted_at = CURRENT_TIMESTAMP WHERE user_id = $1 AND id = ANY($2) AND deleted_at IS NULL"
+    };
+    let deleted = sqlx::query(sql)
+        .bind(user_id)
+        .bind(&req.transaction_ids)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(AppError::database("Failed to delete transactions"))?
+        .rows_affected();
+
+    let result = BulkDeletedTransactions {
+        deleted: deleted as usize,
+    };
+    if dry_run.0 {
+        let wallet_ids: Vec<Uuid> = deltas.keys().copied().collect();
+        return dry_run::preview(db.get_ref(), db_tx, user_id, StatusCode::OK, Some(result), &wallet_ids).await;
+    }
+    db_tx.commit().await.map_err(AppError::database("Failed to save changes"))?;
+
+    if req.hard {
+        for id in &found {
+            attachments::delete_all(db.get_ref(), storage.get_ref(), user_id, transactions::TRANSACTION_ENTITY, *id)
+                .await;
+        }
+    }
+    let _ = invalidate_user_cache(cache.get_ref(), user_id).await;
+    let income_from: Vec<Uuid> = income_from.into_iter().collect();
+    let cx = HookContext { pool: db.get_ref(), cache: cache.get_ref() };
+    hooks::wallets_debited(&cx, WalletsDebited { owner_id: user_id, wallet_ids: &income_from }).await;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(result)))
+}
@@ -0,0 +1,219 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::cache::{invalidate_user_cache, CacheBackend};
+use crate::config::AppConfig;
+use crate::filters::{FilterQuery, TransactionFilter};
+use crate::models::{
+    ApiResponse, BulkUpdatePreview, BulkUpdateRequest, BulkUpdateResult, BulkUpdateSample, BulkUpdateValues,
+};
+
+// ==================== BULK UPDATES ====================
+//
+// Recategorize (or re-describe) every transaction matching a filter. The
+// filter is the transaction list's (`TransactionFilterQuery` plus a created_at
+// range), so what a user sees in the list is what gets changed.
+//
+// Only rows whose values would actually change count as affected. The flow is:
+//
+// 1. `POST /bulk-update/preview`: count affected rows, plus a sample with
+//    before/after values. Nothing is written.
+// 2. `POST /bulk-update`: apply. Passing the preview's count as
+//    `expected_count` rejects the update (409) if the matching set changed
+//    in between.
+//
+// Category and description do not affect balances, so no wallet or bucket
+// bookkeeping is involved.
+//
+// ============================================================================
+
+/// Number of affected transactions returned in a preview
+const PREVIEW_SAMPLE_SIZE: i64 = 20;
+
+/// Maximum number of transactions changed by one bulk update
+const MAX_BULK_UPDATE_ROWS: i64 = 5000;
+
+/// (id, created_at, amount, category, description) of a preview sample row
+type SampleRow = (Uuid, DateTime<Utc>, BigDecimal, String, Option<String>);
+
+// ==================== Handlers ====================
+
+/// Show what a bulk update would change without applying it
+pub async fn preview_bulk_update(
+    req: web::Json<BulkUpdateRequest>,
+    db: web::Data<PgPool>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    let filter = match validate(&config, &req) {
+        Ok(filter) => filter,
+        Err(msg) => return HttpResponse::BadRequest().json(ApiResponse::<BulkUpdatePreview>::error(msg)),
+    };
+
+    match fetch_preview(db.get_ref(), &req.user_id, filter, &req.set).await {
+        Ok(preview) => HttpResponse::Ok().json(ApiResponse::success(preview)),
+        Err(e) => {
+            log::error!("Error previewing bulk update: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<BulkUpdatePreview>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Apply a bulk update to every matching transaction
+pub async fn apply_bulk_update(
+    req: web::Json<BulkUpdateRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    let filter = match validate(&config, &req) {
+        Ok(filter) => filter,
+        Err(msg) => return HttpResponse::BadRequest().json(ApiResponse::<BulkUpdateResult>::error(msg)),
+    };
+
+    let ids = match fetch_affected_ids(db.get_ref(), &req.user_id, filter, &req.set).await {
+        Ok(ids) => ids,
+        Err(e) => {
+            log::error!("Error selecting transactions for bulk update: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<BulkUpdateResult>::error("Database error".to_string()));
+        }
+    };
+
+    if ids.len() as i64 > MAX_BULK_UPDATE_ROWS {
+        return HttpResponse::BadRequest().json(ApiResponse::<BulkUpdateResult>::error(format!(
+            "Bulk update matches more than {} transactions; narrow the filter",
+            MAX_BULK_UPDATE_ROWS
+        )));
+    }
+    if let Some(expected) = req.expected_count
+        && expected != ids.len() as i64
+    {
+        return HttpResponse::Conflict().json(ApiResponse::<BulkUpdateResult>::error(format!(
+            "Bulk update now affects {} transactions, not {}; preview again",
+            ids.len(),
+            expected
+        )));
+    }
+    if ids.is_empty() {
+        return HttpResponse::Ok().json(ApiResponse::success(BulkUpdateResult { updated: 0 }));
+    }
+
+    let result = sqlx::query(
+        "UPDATE transactions
+         SET category = COALESCE($1, category), description = COALESCE($2, description), updated_at = $3
+         WHERE id = ANY($4) AND user_id = $5",
+    )
+    .bind(&req.set.category)
+    .bind(&req.set.description)
+    .bind(Utc::now())
+    .bind(&ids)
+    .bind(&req.user_id)
+    .execute(db.get_ref())
+    .await;
+
+    match result {
+        Ok(done) => {
+            let _ = invalidate_user_cache(cache.get_ref(), &req.user_id).await;
+            HttpResponse::Ok().json(ApiResponse::success(BulkUpdateResult { updated: done.rows_affected() }))
+        }
+        Err(e) => {
+            log::error!("Error applying bulk update: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<BulkUpdateResult>::error("Failed to apply bulk update".to_string()))
+        }
+    }
+}
+
+// ==================== Validation ====================
+
+fn validate(config: &AppConfig, req: &BulkUpdateRequest) -> Result<TransactionFilter, String> {
+    let set = &req.set;
+    if set.category.is_none() && set.description.is_none() {
+        return Err("Set a category or description to update".to_string());
+    }
+    if let Some(category) = &set.category
+        && (category.trim().is_empty() || category.chars().count() > 100)
+    {
+        return Err("category must be 1-100 characters".to_string());
+    }
+    if req.expected_count.is_some_and(|n| n < 0) {
+        return Err("expected_count must not be negative".to_string());
+    }
+
+    TransactionFilter::from_query(config, &req.filter, &req.range)
+}
+
+// ==================== Database Functions ====================
+
+/// Restrict `query` to `user_id`'s matching transactions that `set` would change
+fn affected<'args>(
+    query: FilterQuery<'args>,
+    user_id: &str,
+    filter: TransactionFilter,
+    set: &BulkUpdateValues,
+) -> FilterQuery<'args> {
+    filter.apply(user_id, query).any_distinct_from(vec![
+        ("category", set.category.clone()),
+        ("description", set.description.clone()),
+    ])
+}
+
+async fn fetch_preview(
+    pool: &PgPool,
+    user_id: &str,
+    filter: TransactionFilter,
+    set: &BulkUpdateValues,
+) -> Result<BulkUpdatePreview, sqlx::Error> {
+    let sample_filter = filter.clone();
+
+    let (affected_count,): (i64,) = affected(FilterQuery::new("SELECT COUNT(*) FROM transactions"), user_id, filter, set)
+        .fetch_all(pool)
+        .await?
+        .pop()
+        .unwrap_or((0,));
+
+    let rows: Vec<SampleRow> = affected(
+        FilterQuery::new("SELECT id, created_at, amount, category, description FROM transactions"),
+        user_id,
+        sample_filter,
+        set,
+    )
+    .order_by("created_at DESC, id")
+    .limit(PREVIEW_SAMPLE_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    let sample = rows
+        .into_iter()
+        .map(|(id, created_at, amount, category, description)| BulkUpdateSample {
+            transaction_id: id,
+            created_at,
+            amount,
+            after: BulkUpdateValues {
+                category: Some(set.category.clone().unwrap_or_else(|| category.clone())),
+                description: set.description.clone().or_else(|| description.clone()),
+            },
+            before: BulkUpdateValues { category: Some(category), description },
+        })
+        .collect();
+
+    Ok(BulkUpdatePreview { affected: affected_count, sample })
+}
+
+/// IDs of the transactions an update would change, up to one past the cap
+async fn fetch_affected_ids(
+    pool: &PgPool,
+    user_id: &str,
+    filter: TransactionFilter,
+    set: &BulkUpdateValues,
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    let rows: Vec<(Uuid,)> = affected(FilterQuery::new("SELECT id FROM transactions"), user_id, filter, set)
+        .limit(MAX_BULK_UPDATE_ROWS + 1)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
@@ -1,115 +1,1318 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use actix_web::body::BoxBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use futures_util::StreamExt;
 use redis::aio::ConnectionManager;
-use redis::Client;
+use redis::{AsyncCommands, Client, RedisError, Script};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::models::{ApiResponse, CacheStats};
+use crate::telemetry::{self, CacheOutcome};
+
+// ==================== Cache Backends ====================
+//
+// Handlers take the cache as `web::Data<dyn CacheBackend>`, which is always
+// registered, so no request fails because Redis is missing:
+//
+// - `RedisCache` when Redis is reachable at startup. Its connection is
+//   rebuilt when it stays broken (see Connection Supervision).
+// - `MemoryCache` otherwise: a process-local key/value map. Lockouts, drafts
+//   and namespace versions keep working on a single instance. Its entries
+//   aren't shared, so another instance couldn't invalidate them:
+//   `get_or_set_cache` doesn't cache data in it and reads the database.
+//
+// Redis failing mid-flight isn't fatal either: `get_or_set_cache` treats a
+// failed read as a miss and a failed write as "not cached", and serves what
+// it read from the database.
+//
+// ============================================================================
+
+/// Key/value store behind the cache, lockouts and drafts
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    /// Whether every app instance sees the same entries
+    fn is_shared(&self) -> bool;
+
+    /// Value stored under `key`, `None` if missing or expired
+    async fn get(&self, key: &str) -> Result<Option<String>, RedisError>;
+
+    /// Store `value` under `key` for `ttl_secs` seconds
+    async fn set_ex(&self, key: &str, value: String, ttl_secs: u64) -> Result<(), RedisError>;
+
+    /// Remove `key` (missing keys are not an error)
+    async fn del(&self, key: &str) -> Result<(), RedisError>;
+
+    /// Add one to the counter under `key` (missing counts as 0); returns the new value
+    async fn incr(&self, key: &str) -> Result<u64, RedisError>;
+
+    /// Seconds until `key` expires, `None` if it is missing or never expires
+    async fn ttl(&self, key: &str) -> Result<Option<u64>, RedisError>;
 
-#[derive(Clone)]
-pub struct CacheManager(pub ConnectionManager);
+    /// Fields of the hash under `key`, empty if missing or expired
+    async fn hgetall(&self, key: &str) -> Result<HashMap<String, String>, RedisError>;
+
+    /// Set `fields` of the hash under `key`, keeping its other fields, and
+    /// expire the hash in `ttl_secs` seconds
+    async fn hset_ex(&self, key: &str, fields: &[(String, String)], ttl_secs: u64) -> Result<(), RedisError>;
+
+    // The operations below run as one step (see Atomic Scripts)
+
+    /// Add one to the counter under `key`; a new counter expires in
+    /// `ttl_secs` seconds, and with `refresh` every count restarts the expiry
+    async fn incr_ex(&self, key: &str, ttl_secs: u64, refresh: bool) -> Result<u64, RedisError>;
+
+    /// Store `value` under `key` for `ttl_secs` seconds and remove `stale_key`
+    async fn set_ex_and_del(&self, key: &str, value: String, ttl_secs: u64, stale_key: &str) -> Result<(), RedisError>;
+
+    /// Add one to the counter under `key` and broadcast `event`, a JSON
+    /// object, on `channel` with its `version` set to the new value; returns
+    /// the new value
+    async fn incr_and_publish(&self, key: &str, channel: &str, event: String) -> Result<u64, RedisError>;
+
+    /// Take `claim_key` as `token` for `ttl_secs` seconds unless someone holds
+    /// it; returns whether it was taken and, if so, the value under `key` then
+    async fn claim_and_get(
+        &self,
+        claim_key: &str,
+        token: &str,
+        ttl_secs: u64,
+        key: &str,
+    ) -> Result<(bool, Option<String>), RedisError>;
+
+    /// Give up `claim_key` if `token` still holds it
+    async fn release_claim(&self, claim_key: &str, token: &str) -> Result<(), RedisError>;
+}
 
-impl CacheManager {
-    pub async fn new(redis_url: &str) -> Result<Self, redis::RedisError> {
+/// Redis, shared by every instance
+///
+/// The connection is swapped by `spawn_connection_supervisor` when it stays
+/// broken; each command clones the current one.
+pub struct RedisCache {
+    client: Client,
+    conn: RwLock<ConnectionManager>,
+    /// Commands failed in a row (reset by any success)
+    failures: AtomicU32,
+}
+
+impl RedisCache {
+    pub async fn connect(redis_url: &str) -> Result<Self, RedisError> {
         let client = Client::open(redis_url)?;
-        let conn_manager = ConnectionManager::new(client).await?;
-        Ok(CacheManager(conn_manager))
+        let mut conn_manager = ConnectionManager::new(client.clone()).await?;
+        load_scripts(&mut conn_manager).await?;
+        Ok(RedisCache {
+            client,
+            conn: RwLock::new(conn_manager),
+            failures: AtomicU32::new(0),
+        })
+    }
+
+    fn conn(&self) -> ConnectionManager {
+        self.conn.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Count a command's outcome towards the failure streak
+    fn track<T>(&self, result: Result<T, RedisError>) -> Result<T, RedisError> {
+        match &result {
+            Ok(_) => self.failures.store(0, Ordering::Relaxed),
+            Err(_) => {
+                self.failures.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl CacheBackend for RedisCache {
+    fn is_shared(&self) -> bool {
+        true
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>, RedisError> {
+        self.track(self.conn().get(key).await)
+    }
+
+    async fn set_ex(&self, key: &str, value: String, ttl_secs: u64) -> Result<(), RedisError> {
+        self.track(self.conn().set_ex(key, value, ttl_secs).await)
+    }
+
+    async fn del(&self, key: &str) -> Result<(), RedisError> {
+        self.track(self.conn().del(key).await)
+    }
+
+    async fn incr(&self, key: &str) -> Result<u64, RedisError> {
+        self.track(self.conn().incr(key, 1).await)
+    }
+
+    async fn ttl(&self, key: &str) -> Result<Option<u64>, RedisError> {
+        // -2: no such key, -1: no expiry
+        let ttl: i64 = self.track(self.conn().ttl(key).await)?;
+        Ok((ttl > 0).then_some(ttl as u64))
+    }
+
+    async fn hgetall(&self, key: &str) -> Result<HashMap<String, String>, RedisError> {
+        self.track(self.conn().hgetall(key).await)
+    }
+
+    async fn hset_ex(&self, key: &str, fields: &[(String, String)], ttl_secs: u64) -> Result<(), RedisError> {
+        let mut invocation = HSET_EX.key(key);
+        invocation.arg(ttl_secs);
+        for (field, value) in fields {
+            invocation.arg(field).arg(value);
+        }
+        let mut conn = self.conn();
+        self.track(invocation.invoke_async(&mut conn).await)
+    }
+
+    async fn incr_ex(&self, key: &str, ttl_secs: u64, refresh: bool) -> Result<u64, RedisError> {
+        let mut conn = self.conn();
+        self.track(INCR_EX.key(key).arg(ttl_secs).arg(u8::from(refresh)).invoke_async(&mut conn).await)
+    }
+
+    async fn set_ex_and_del(&self, key: &str, value: String, ttl_secs: u64, stale_key: &str) -> Result<(), RedisError> {
+        let mut conn = self.conn();
+        self.track(SET_EX_AND_DEL.key(key).key(stale_key).arg(value).arg(ttl_secs).invoke_async(&mut conn).await)
+    }
+
+    async fn incr_and_publish(&self, key: &str, channel: &str, event: String) -> Result<u64, RedisError> {
+        let mut conn = self.conn();
+        self.track(INCR_AND_PUBLISH.key(key).arg(channel).arg(event).invoke_async(&mut conn).await)
+    }
+
+    async fn claim_and_get(
+        &self,
+        claim_key: &str,
+        token: &str,
+        ttl_secs: u64,
+        key: &str,
+    ) -> Result<(bool, Option<String>), RedisError> {
+        let mut conn = self.conn();
+        let (claimed, value): (u8, Option<String>) =
+            self.track(CLAIM_AND_GET.key(claim_key).key(key).arg(token).arg(ttl_secs).invoke_async(&mut conn).await)?;
+        Ok((claimed == 1, value))
+    }
+
+    async fn release_claim(&self, claim_key: &str, token: &str) -> Result<(), RedisError> {
+        let mut conn = self.conn();
+        self.track(RELEASE_CLAIM.key(claim_key).arg(token).invoke_async(&mut conn).await)
+    }
+}
+
+// ==================== Atomic Scripts ====================
+//
+// Some flows need several commands to take effect together, or another
+// client can slip in between them:
+//
+// - Bumping a namespace version and announcing it (`incr_and_publish`): the
+//   event carries the version the INCR returned, in the order of the INCRs.
+// - Counting in a window (`incr_ex`): a counter can't be left without its
+//   expiry by a crash between INCR and EXPIRE, and live forever.
+// - Locking out (`set_ex_and_del`): the lock appears as the failures go.
+// - Claiming a stale-while-revalidate refresh (`claim_and_get`,
+//   `release_claim`): one instance in the cluster refreshes an entry, and
+//   sees the entry as of its claim, so it skips one refreshed meanwhile.
+// - Writing the dashboard projection (`hset_ex`): the hash never exists
+//   without its expiry.
+//
+// Each is a Lua script, which Redis runs without interleaving other
+// commands. `RedisCache::connect` loads them (SCRIPT LOAD), so a Redis that
+// can't run scripts is reported at startup; calls use EVALSHA and load the
+// script again when Redis answers NOSCRIPT (after a restart or SCRIPT FLUSH).
+// `MemoryCache` runs the same operations under its lock.
+//
+// ============================================================================
+
+/// KEYS: counter. ARGV: ttl, refresh (0/1)
+static INCR_EX: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r"
+        local count = redis.call('INCR', KEYS[1])
+        if count == 1 or ARGV[2] == '1' then
+            redis.call('EXPIRE', KEYS[1], ARGV[1])
+        end
+        return count
+        ",
+    )
+});
+
+/// KEYS: key, stale key. ARGV: value, ttl
+static SET_EX_AND_DEL: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r"
+        redis.call('SET', KEYS[1], ARGV[1], 'EX', ARGV[2])
+        redis.call('DEL', KEYS[2])
+        return 1
+        ",
+    )
+});
+
+/// KEYS: counter. ARGV: channel, event JSON
+static INCR_AND_PUBLISH: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r"
+        local version = redis.call('INCR', KEYS[1])
+        local event = cjson.decode(ARGV[2])
+        event['version'] = version
+        redis.call('PUBLISH', ARGV[1], cjson.encode(event))
+        return version
+        ",
+    )
+});
+
+/// KEYS: claim, key. ARGV: token, ttl. Returns {claimed (0/1), value}
+static CLAIM_AND_GET: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r"
+        if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'EX', ARGV[2]) then
+            return {1, redis.call('GET', KEYS[2])}
+        end
+        return {0, false}
+        ",
+    )
+});
+
+/// KEYS: claim. ARGV: token
+static RELEASE_CLAIM: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r"
+        if redis.call('GET', KEYS[1]) == ARGV[1] then
+            return redis.call('DEL', KEYS[1])
+        end
+        return 0
+        ",
+    )
+});
+
+/// KEYS: hash. ARGV: ttl, field, value[, field, value...]
+static HSET_EX: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r"
+        redis.call('HSET', KEYS[1], unpack(ARGV, 2))
+        redis.call('EXPIRE', KEYS[1], ARGV[1])
+        return 1
+        ",
+    )
+});
+
+/// Load every script into Redis's script cache
+async fn load_scripts(conn: &mut ConnectionManager) -> Result<(), RedisError> {
+    for script in [&INCR_EX, &SET_EX_AND_DEL, &INCR_AND_PUBLISH, &CLAIM_AND_GET, &RELEASE_CLAIM, &HSET_EX] {
+        script.prepare_invoke().load_async(conn).await?;
+    }
+    log::info!("Loaded the cache scripts into Redis");
+    Ok(())
+}
+
+// ==================== Connection Supervision ====================
+//
+// `ConnectionManager` reconnects by itself, but after some Redis outages it
+// stays wedged: every command fails or hangs although Redis is back. So the
+// connection is supervised:
+//
+// - `spawn_connection_supervisor` PINGs Redis every `HEALTH_CHECK_INTERVAL`.
+//   A PING that fails or takes longer than `PING_TIMEOUT` counts as a failed
+//   command, like the failures of the commands handlers run.
+// - After `RECONNECT_AFTER_FAILURES` failures in a row it builds a new
+//   `ConnectionManager` and swaps it in; commands already running finish on
+//   the old one. If Redis is still down it tries again on the next failed
+//   check.
+//
+// Handlers keep working meanwhile: failed reads are misses (see Cache-Aside).
+//
+// ============================================================================
+
+/// How often the supervisor checks the connection
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A PING slower than this counts as a failure
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Failed commands in a row before the connection is rebuilt
+const RECONNECT_AFTER_FAILURES: u32 = 3;
+
+/// How long building a new connection may take
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connections rebuilt since startup
+static RECONNECTS: AtomicU64 = AtomicU64::new(0);
+
+/// Check the Redis connection for the life of the process, rebuilding it when
+/// it keeps failing
+pub fn spawn_connection_supervisor(cache: Arc<RedisCache>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(HEALTH_CHECK_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if cache.ping().await || cache.failures.load(Ordering::Relaxed) < RECONNECT_AFTER_FAILURES {
+                continue;
+            }
+            match cache.reconnect().await {
+                Ok(()) => log::info!("Rebuilt the Redis connection"),
+                Err(e) => log::warn!("Failed to rebuild the Redis connection: {}", e),
+            }
+        }
+    });
+}
+
+impl RedisCache {
+    async fn ping(&self) -> bool {
+        let mut conn = self.conn();
+        let cmd = redis::cmd("PING");
+        let ping = cmd.query_async::<_, String>(&mut conn);
+        let result = match tokio::time::timeout(PING_TIMEOUT, ping).await {
+            Ok(result) => result,
+            Err(_) => Err(RedisError::from((redis::ErrorKind::IoError, "PING timed out"))),
+        };
+        self.track(result).is_ok()
     }
 
-    pub fn get_connection_manager(&self) -> &ConnectionManager {
-        &self.0
+    async fn reconnect(&self) -> Result<(), RedisError> {
+        let connect = ConnectionManager::new(self.client.clone());
+        let conn = match tokio::time::timeout(RECONNECT_TIMEOUT, connect).await {
+            Ok(conn) => conn?,
+            Err(_) => return Err(RedisError::from((redis::ErrorKind::IoError, "connect timed out"))),
+        };
+        *self.conn.write().unwrap_or_else(|e| e.into_inner()) = conn;
+        self.failures.store(0, Ordering::Relaxed);
+        RECONNECTS.fetch_add(1, Ordering::Relaxed);
+        Ok(())
     }
 }
 
-// Cache-Aside Pattern Implementation
+/// Upper bound on in-process entries (expired entries are dropped first, then all)
+const MEMORY_CACHE_CAPACITY: usize = 100_000;
+
+/// In-process fallback when Redis is unavailable; never fails
+#[derive(Default)]
+pub struct MemoryCache {
+    /// key -> (value, expiry)
+    entries: Mutex<HashMap<String, (String, Option<Instant>)>>,
+}
+
+impl MemoryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_entries<T>(&self, f: impl FnOnce(&mut HashMap<String, (String, Option<Instant>)>) -> T) -> T {
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        entries.retain(|_, (_, expires)| expires.is_none_or(|at| at > now));
+        if entries.len() >= MEMORY_CACHE_CAPACITY {
+            entries.clear();
+        }
+        f(&mut entries)
+    }
+}
+
+#[async_trait]
+impl CacheBackend for MemoryCache {
+    fn is_shared(&self) -> bool {
+        false
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>, RedisError> {
+        Ok(self.with_entries(|entries| entries.get(key).map(|(value, _)| value.clone())))
+    }
+
+    async fn set_ex(&self, key: &str, value: String, ttl_secs: u64) -> Result<(), RedisError> {
+        let expires = Instant::now() + Duration::from_secs(ttl_secs);
+        self.with_entries(|entries| entries.insert(key.to_string(), (value, Some(expires))));
+        Ok(())
+    }
+
+    async fn del(&self, key: &str) -> Result<(), RedisError> {
+        self.with_entries(|entries| entries.remove(key));
+        Ok(())
+    }
+
+    async fn incr(&self, key: &str) -> Result<u64, RedisError> {
+        Ok(self.with_entries(|entries| {
+            let entry = entries.entry(key.to_string()).or_insert_with(|| ("0".to_string(), None));
+            let value = entry.0.parse::<u64>().unwrap_or(0) + 1;
+            entry.0 = value.to_string();
+            value
+        }))
+    }
+
+    async fn ttl(&self, key: &str) -> Result<Option<u64>, RedisError> {
+        Ok(self.with_entries(|entries| {
+            entries
+                .get(key)
+                .and_then(|(_, expires)| *expires)
+                .map(|at| at.saturating_duration_since(Instant::now()).as_secs().max(1))
+        }))
+    }
+
+    // A hash is stored as its JSON object
+
+    async fn hgetall(&self, key: &str) -> Result<HashMap<String, String>, RedisError> {
+        Ok(self.with_entries(|entries| {
+            entries
+                .get(key)
+                .and_then(|(value, _)| serde_json::from_str(value).ok())
+                .unwrap_or_default()
+        }))
+    }
+
+    async fn hset_ex(&self, key: &str, fields: &[(String, String)], ttl_secs: u64) -> Result<(), RedisError> {
+        let expires = Instant::now() + Duration::from_secs(ttl_secs);
+        self.with_entries(|entries| {
+            let entry = entries.entry(key.to_string()).or_insert_with(|| ("{}".to_string(), None));
+            let mut hash: HashMap<String, String> = serde_json::from_str(&entry.0).unwrap_or_default();
+            hash.extend(fields.iter().cloned());
+            *entry = (serde_json::to_string(&hash).unwrap_or_default(), Some(expires));
+        });
+        Ok(())
+    }
+
+    async fn incr_ex(&self, key: &str, ttl_secs: u64, refresh: bool) -> Result<u64, RedisError> {
+        let expires = Instant::now() + Duration::from_secs(ttl_secs);
+        Ok(self.with_entries(|entries| {
+            let entry = entries.entry(key.to_string()).or_insert_with(|| ("0".to_string(), None));
+            let value = entry.0.parse::<u64>().unwrap_or(0) + 1;
+            entry.0 = value.to_string();
+            if value == 1 || refresh {
+                entry.1 = Some(expires);
+            }
+            value
+        }))
+    }
+
+    async fn set_ex_and_del(&self, key: &str, value: String, ttl_secs: u64, stale_key: &str) -> Result<(), RedisError> {
+        let expires = Instant::now() + Duration::from_secs(ttl_secs);
+        self.with_entries(|entries| {
+            entries.insert(key.to_string(), (value, Some(expires)));
+            entries.remove(stale_key);
+        });
+        Ok(())
+    }
+
+    async fn incr_and_publish(&self, key: &str, _channel: &str, _event: String) -> Result<u64, RedisError> {
+        // Nobody else to tell
+        self.incr(key).await
+    }
+
+    async fn claim_and_get(
+        &self,
+        claim_key: &str,
+        token: &str,
+        ttl_secs: u64,
+        key: &str,
+    ) -> Result<(bool, Option<String>), RedisError> {
+        let expires = Instant::now() + Duration::from_secs(ttl_secs);
+        Ok(self.with_entries(|entries| {
+            if entries.contains_key(claim_key) {
+                return (false, None);
+            }
+            entries.insert(claim_key.to_string(), (token.to_string(), Some(expires)));
+            (true, entries.get(key).map(|(value, _)| value.clone()))
+        }))
+    }
+
+    async fn release_claim(&self, claim_key: &str, token: &str) -> Result<(), RedisError> {
+        self.with_entries(|entries| {
+            if entries.get(claim_key).is_some_and(|(holder, _)| holder == token) {
+                entries.remove(claim_key);
+            }
+        });
+        Ok(())
+    }
+}
+
+// ==================== Cache-Aside ====================
+//
+// Entries live for `CACHE_TTL_SECS`, plus or minus a random
+// `CACHE_TTL_JITTER_PERCENT` (default 10%). Caches warmed together, e.g. by
+// the traffic right after a deploy or a cache flush, then expire over a
+// window of minutes instead of in the same second, and their refetches don't
+// all hit Postgres at once.
+//
+// ============================================================================
+
+/// Base lifetime of a cached read
+const CACHE_TTL_SECS: u64 = 3600;
+
+/// Jitter installed by `configure_ttl_jitter`, in percent of the TTL
+static TTL_JITTER_PERCENT: AtomicU64 = AtomicU64::new(0);
+
+/// Set the TTL jitter from `CACHE_TTL_JITTER_PERCENT`
+///
+/// 100% or more could expire entries as soon as they are written, so it is
+/// an error.
+pub fn configure_ttl_jitter(config: &AppConfig) -> Result<(), String> {
+    let percent = config.cache_ttl_jitter_percent;
+    if percent >= 100 {
+        return Err(format!("Jitter must be below 100%, got {}%", percent));
+    }
+    TTL_JITTER_PERCENT.store(percent, Ordering::Relaxed);
+    Ok(())
+}
+
+/// `ttl_secs` moved by a random amount within the configured jitter
+fn jittered_ttl(ttl_secs: u64) -> u64 {
+    let spread = ttl_secs * TTL_JITTER_PERCENT.load(Ordering::Relaxed) / 100;
+    if spread == 0 {
+        return ttl_secs;
+    }
+    ttl_secs - spread + OsRng.next_u64() % (2 * spread + 1)
+}
+
+/// Serve `key` from the cache, or run `fetch_fn` and cache its result for
+/// about an hour (see `jittered_ttl`)
+///
+/// Cache failures are logged and fall back to `fetch_fn`; only a database or
+/// serialization error is returned.
 pub async fn get_or_set_cache<T: serde::Serialize + serde::de::DeserializeOwned>(
-    cache: &ConnectionManager,
+    cache: &dyn CacheBackend,
     key: &str,
     fetch_fn: impl std::future::Future<Output = Result<T, sqlx::Error>>,
 ) -> Result<T, CacheError> {
-    use redis::AsyncCommands;
-    let mut cache = cache.clone();
+    telemetry::cache(cache_namespace(key), "get_or_set", async {
+        // Process-local entries couldn't be invalidated by other instances
+        if !cache.is_shared() {
+            return match fetch_fn.await {
+                Ok(data) => (Ok(data), CacheOutcome::Miss),
+                Err(e) => (Err(CacheError::Database(e)), CacheOutcome::Miss),
+            };
+        }
 
-    // Try to get from cache
-    match cache.get::<&str, String>(key).await {
-        Ok(cached_data) => {
-            if let Ok(data) = serde_json::from_str::<T>(&cached_data) {
-                log::info!("Cache hit for key: {}", key);
-                return Ok(data);
+        // Try to get from cache, unless the request asked for a strong read
+        if current_consistency() == Consistency::Strong {
+            log::debug!("Strong read, skipping cache for key: {}", key);
+        } else {
+            match cache.get(key).await {
+                Ok(Some(cached_data)) => {
+                    if let Some(json) = decode_value(key, &cached_data)
+                        && let Ok(data) = serde_json::from_str::<T>(&json)
+                    {
+                        log::info!("Cache hit for key: {}", key);
+                        return (Ok(data), CacheOutcome::Hit);
+                    }
+                }
+                Ok(None) => log::debug!("Cache miss for key: {}", key),
+                Err(e) => log::warn!("Cache read failed for key {}: {}. Reading the database.", key, e),
             }
         }
-        Err(redis::RedisError { .. }) => {
-            log::debug!("Cache miss for key: {}", key);
+
+        // Fetch from database
+        let data = match fetch_fn.await {
+            Ok(data) => data,
+            Err(e) => return (Err(CacheError::Database(e)), CacheOutcome::Miss),
+        };
+
+        // Store in cache (about an hour, jittered)
+        let json_data = match serde_json::to_string(&data) {
+            Ok(json) => json,
+            Err(e) => return (Err(CacheError::Serialization(e)), CacheOutcome::Error),
+        };
+        let Some(value) = encode_value(key, json_data) else {
+            return (Ok(data), CacheOutcome::Miss);
+        };
+        if let Err(e) = cache.set_ex(key, value, jittered_ttl(CACHE_TTL_SECS)).await {
+            log::warn!("Cache write failed for key {}: {}", key, e);
+            return (Ok(data), CacheOutcome::Error);
+        }
+
+        log::info!("Data cached for key: {}", key);
+        (Ok(data), CacheOutcome::Miss)
+    })
+    .await
+}
+
+// ==================== Stale-While-Revalidate ====================
+//
+// Dashboards would rather show slightly old numbers at once than wait for a
+// report query. `CACHE_SWR` lists namespaces (`report:300,wallet_summary:60`)
+// whose entries are served for that many seconds past their TTL:
+//
+// - `get_or_set_cache_swr` stores the entry with the time it goes stale and
+//   keeps it in Redis for the TTL plus the grace window.
+// - A fresh entry is a hit. A stale one is returned as is, and a background
+//   task refetches it and writes it back; a read after the grace window is a
+//   plain miss.
+// - One refresh per key runs at a time across the cluster: the instance that
+//   claims `{key}:refreshing` refreshes it, unless the entry it sees then is
+//   fresh again. The claim expires after `SWR_CLAIM_SECS` if its holder dies.
+//
+// Writes still bump the user's namespace version, so a user never gets data
+// from before their own change; only changes made elsewhere (another user's
+// shared group, exchange rates, jobs) can show up late. Strong reads and the
+// in-process fallback cache behave as with `get_or_set_cache`.
+//
+// The refetch outlives the request, so the fetch future must own what it
+// uses. Only the namespaces in `SWR_NAMESPACES` are read this way; others are
+// rejected at startup.
+//
+// ============================================================================
+
+/// Namespaces read with `get_or_set_cache_swr`
+const SWR_NAMESPACES: &[&str] = &["report", "wallet_summary"];
+
+/// Longest grace window accepted
+const MAX_SWR_GRACE_SECS: u64 = 86_400;
+
+/// Prefix of entries written by `get_or_set_cache_swr`: `swr1:{stale_at}:{json}`
+const SWR_PREFIX: &str = "swr1:";
+
+/// Grace windows installed by `configure_swr`, by namespace
+static SWR_GRACE_SECS: OnceLock<HashMap<String, u64>> = OnceLock::new();
+
+/// How long a claim on a refresh is held at most
+const SWR_CLAIM_SECS: u64 = 60;
+
+/// Set the grace windows from `CACHE_SWR` (`namespace:secs[,namespace:secs...]`)
+pub fn configure_swr(config: &AppConfig) -> Result<(), String> {
+    let mut grace = HashMap::new();
+    for entry in config.cache_swr.as_deref().unwrap_or_default().split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (namespace, secs) = entry
+            .split_once(':')
+            .ok_or_else(|| format!("'{}' must be written as namespace:seconds", entry))?;
+        if !SWR_NAMESPACES.contains(&namespace) {
+            return Err(format!(
+                "Namespace '{}' doesn't support stale reads; expected one of {}",
+                namespace,
+                SWR_NAMESPACES.join(", ")
+            ));
         }
+        let secs: u64 = secs
+            .parse()
+            .ok()
+            .filter(|secs| (1..=MAX_SWR_GRACE_SECS).contains(secs))
+            .ok_or_else(|| format!("Grace of '{}' must be 1-{} seconds", namespace, MAX_SWR_GRACE_SECS))?;
+        if grace.insert(namespace.to_string(), secs).is_some() {
+            return Err(format!("Namespace '{}' is listed twice", namespace));
+        }
+    }
+    if !grace.is_empty() {
+        log::info!("Stale cache entries served for {:?} (seconds past expiry)", grace);
     }
+    SWR_GRACE_SECS
+        .set(grace)
+        .map_err(|_| "Stale-while-revalidate is already configured".to_string())
+}
 
-    // Fetch from database
-    let data = fetch_fn.await.map_err(CacheError::DatabaseError)?;
+/// Like `get_or_set_cache`, but an expired entry of a `CACHE_SWR` namespace
+/// is served during its grace window while `fetch_fn` refreshes it in the
+/// background
+pub async fn get_or_set_cache_swr<T>(
+    cache: &web::Data<dyn CacheBackend>,
+    key: &str,
+    fetch_fn: impl std::future::Future<Output = Result<T, sqlx::Error>> + Send + 'static,
+) -> Result<T, CacheError>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned + Send + Sync + 'static,
+{
+    let namespace = cache_namespace(key);
+    let grace_secs = SWR_GRACE_SECS.get().and_then(|grace| grace.get(namespace)).copied();
+    let Some(grace_secs) = grace_secs.filter(|_| cache.is_shared() && current_consistency() == Consistency::Cached)
+    else {
+        return get_or_set_cache(cache.get_ref(), key, fetch_fn).await;
+    };
 
-    // Store in cache (with 1 hour TTL)
-    let json_data = serde_json::to_string(&data).map_err(CacheError::SerializationError)?;
-    let _: () = cache
-        .set_ex(key, json_data, 3600)
-        .await
-        .map_err(CacheError::CacheError)?;
+    telemetry::cache(namespace, "get_or_set", async {
+        match cache.get(key).await {
+            Ok(Some(cached_data)) => {
+                if let Some((stale_at, data)) = decode_value(key, &cached_data)
+                    .as_deref()
+                    .and_then(split_swr_entry)
+                    .and_then(|(stale_at, json)| Some((stale_at, serde_json::from_str::<T>(json).ok()?)))
+                {
+                    if unix_now() < stale_at {
+                        log::info!("Cache hit for key: {}", key);
+                        return (Ok(data), CacheOutcome::Hit);
+                    }
+                    log::info!("Serving stale cache entry for key: {}", key);
+                    spawn_swr_refresh(cache.clone(), key.to_string(), grace_secs, fetch_fn);
+                    return (Ok(data), CacheOutcome::Stale);
+                }
+            }
+            Ok(None) => log::debug!("Cache miss for key: {}", key),
+            Err(e) => log::warn!("Cache read failed for key {}: {}. Reading the database.", key, e),
+        }
 
-    log::info!("Data cached for key: {}", key);
-    Ok(data)
+        let data = match fetch_fn.await {
+            Ok(data) => data,
+            Err(e) => return (Err(CacheError::Database(e)), CacheOutcome::Miss),
+        };
+        match store_swr_entry(cache.get_ref(), key, &data, grace_secs).await {
+            Ok(()) => (Ok(data), CacheOutcome::Miss),
+            Err(e) => (Err(e), CacheOutcome::Error),
+        }
+    })
+    .await
+}
+
+/// Refetch `key` and write it back, unless another refresh of it holds the
+/// claim or already wrote it
+fn spawn_swr_refresh<T>(
+    cache: web::Data<dyn CacheBackend>,
+    key: String,
+    grace_secs: u64,
+    fetch_fn: impl std::future::Future<Output = Result<T, sqlx::Error>> + Send + 'static,
+) where
+    T: serde::Serialize + Send + Sync + 'static,
+{
+    tokio::spawn(async move {
+        let claim_key = format!("{}:refreshing", key);
+        let token = Uuid::new_v4().to_string();
+        let current = match cache.claim_and_get(&claim_key, &token, SWR_CLAIM_SECS, &key).await {
+            Ok((true, current)) => current,
+            Ok((false, _)) => return,
+            Err(e) => {
+                log::warn!("Failed to claim the refresh of cache key {}: {}", key, e);
+                return;
+            }
+        };
+        let refreshed = current
+            .and_then(|stored| decode_value(&key, &stored))
+            .and_then(|entry| split_swr_entry(&entry).map(|(stale_at, _)| stale_at))
+            .is_some_and(|stale_at| unix_now() < stale_at);
+
+        if !refreshed {
+            let result = match fetch_fn.await {
+                Ok(data) => store_swr_entry(cache.get_ref(), &key, &data, grace_secs).await,
+                Err(e) => Err(CacheError::Database(e)),
+            };
+            if let Err(e) = result {
+                log::warn!("Background refresh of cache key {} failed: {}", key, e);
+            }
+        }
+        if let Err(e) = cache.release_claim(&claim_key, &token).await {
+            log::warn!("Failed to release the refresh of cache key {}: {}", key, e);
+        }
+    });
 }
 
-// Invalidate cache by key
-pub async fn invalidate_cache(cache: &ConnectionManager, key: &str) -> Result<(), redis::RedisError> {
-    use redis::AsyncCommands;
-    let mut cache = cache.clone();
-    let _: () = cache.del(key).await?;
-    log::info!("Cache invalidated for key: {}", key);
+/// Cache `data` under `key` until stale (about an hour, jittered), kept for
+/// `grace_secs` more; a failed write is logged, not returned
+async fn store_swr_entry<T: serde::Serialize>(
+    cache: &dyn CacheBackend,
+    key: &str,
+    data: &T,
+    grace_secs: u64,
+) -> Result<(), CacheError> {
+    let json_data = serde_json::to_string(data).map_err(CacheError::Serialization)?;
+    let ttl = jittered_ttl(CACHE_TTL_SECS);
+    let entry = format!("{}{}:{}", SWR_PREFIX, unix_now() + ttl, json_data);
+    let Some(value) = encode_value(key, entry) else {
+        return Ok(());
+    };
+    match cache.set_ex(key, value, ttl + grace_secs).await {
+        Ok(()) => log::info!("Data cached for key: {}", key),
+        Err(e) => log::warn!("Cache write failed for key {}: {}", key, e),
+    }
     Ok(())
 }
 
-// Invalidate cache by pattern
-pub async fn invalidate_cache_pattern(
-    cache: &ConnectionManager,
-    pattern: &str,
-) -> Result<(), redis::RedisError> {
-    use redis::AsyncCommands;
-    let mut cache = cache.clone();
-    let keys: Vec<String> = cache.keys(pattern).await?;
-    if !keys.is_empty() {
-        let _: () = cache.del(keys).await?;
-        log::info!("Cache invalidated for pattern: {}", pattern);
+/// `(stale_at, json)` of an entry written by `store_swr_entry`
+fn split_swr_entry(entry: &str) -> Option<(u64, &str)> {
+    let (stale_at, json) = entry.strip_prefix(SWR_PREFIX)?.split_once(':')?;
+    Some((stale_at.parse().ok()?, json))
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// What a cache key holds, for telemetry: the first segment after the user
+/// namespace (`user:{id}:v{n}:wallet:{wallet_id}` -> `wallet`), or of the key
+fn cache_namespace(key: &str) -> &str {
+    let rest = match key.strip_prefix("user:") {
+        Some(rest) => rest.splitn(3, ':').nth(2).unwrap_or(rest),
+        None => key,
+    };
+    rest.split(':').next().unwrap_or(rest)
+}
+
+// ==================== Consistency Modes ====================
+//
+// Clients choose per request how fresh a read must be, with the
+// `X-Consistency` header or the `consistency` query parameter (the header
+// wins):
+//
+// - `cached` (default): `get_or_set_cache` serves Redis entries as above.
+// - `strong`: `get_or_set_cache` skips the Redis read and queries the
+//   database (there are no read replicas, so that is the primary), then
+//   stores what it read so later cached reads see it too.
+//
+// `strong` is for flows that read right after a write made elsewhere, e.g.
+// from another device, while another instance may still hold the old
+// namespace version (see Cluster-Wide Invalidation below). `ScopedRoutes`
+// wraps every route with `consistency`, which keeps the mode in a task-local
+// for the request and echoes it in the response header. Writes are
+// unaffected.
+//
+// ============================================================================
+
+const CONSISTENCY_HEADER: HeaderName = HeaderName::from_static("x-consistency");
+
+/// How fresh the reads of a request must be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Consistency {
+    /// Read the database, bypassing cached entries
+    Strong,
+    /// Serve cached entries when present
+    Cached,
+}
+
+impl Consistency {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "strong" => Some(Consistency::Strong),
+            "cached" => Some(Consistency::Cached),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Consistency::Strong => "strong",
+            Consistency::Cached => "cached",
+        }
+    }
+}
+
+tokio::task_local! {
+    static CONSISTENCY: Consistency;
+}
+
+/// Whether the current request asked to skip cached reads
+pub(crate) fn is_strong_read() -> bool {
+    current_consistency() == Consistency::Strong
+}
+
+/// The consistency mode of the request being handled (`Cached` outside one)
+fn current_consistency() -> Consistency {
+    CONSISTENCY.try_with(|mode| *mode).unwrap_or(Consistency::Cached)
+}
+
+/// Run the rest of the chain in the requested consistency mode; 400 for an unknown mode
+pub async fn consistency(req: ServiceRequest, next: Next<BoxBody>) -> Result<ServiceResponse<BoxBody>, Error> {
+    let requested = match req.headers().get(&CONSISTENCY_HEADER) {
+        Some(value) => Some(value.to_str().unwrap_or_default().to_string()),
+        None => web::Query::<HashMap<String, String>>::from_query(req.query_string())
+            .ok()
+            .and_then(|query| query.into_inner().remove("consistency")),
+    };
+    let mode = match requested.as_deref().map(Consistency::parse) {
+        None => Consistency::Cached,
+        Some(Some(mode)) => mode,
+        Some(None) => {
+            let response = HttpResponse::BadRequest().json(ApiResponse::<String>::error(
+                "Invalid consistency mode. Must be 'strong' or 'cached'".to_string(),
+            ));
+            return Ok(req.into_response(response));
+        }
+    };
+
+    let mut res = CONSISTENCY.scope(mode, next.call(req)).await?;
+    res.headers_mut().insert(CONSISTENCY_HEADER, HeaderValue::from_static(mode.as_str()));
+    Ok(res)
+}
+
+// ==================== Per-User Cache Namespaces ====================
+//
+// Every cached entry belongs to exactly one user and its key embeds that
+// user's namespace version:
+//
+//   user:{user_id}:v{version}:{suffix}      e.g. user:u1:v7:wallet:{wallet_id}
+//
+// The version lives in `user:{user_id}:cachever`. Invalidating a user is a
+// single INCR: all keys built with the old version become unreachable and
+// expire through their TTL. No KEYS scans, and no glob that can match
+// another user's keys.
+//
+// Handlers never build or delete keys themselves: reads go through
+// `user_cache_key` + `get_or_set_cache`, and every write calls
+// `invalidate_user_cache` for the user it touched, whatever it changed.
+// There is no per-entity invalidation to keep in sync with the key suffixes.
+//
+// ============================================================================
+
+/// Redis key holding a user's cache namespace version
+fn namespace_version_key(user_id: &str) -> String {
+    format!("user:{}:cachever", user_id)
+}
+
+/// Build a versioned cache key for `suffix` in the user's namespace
+///
+/// The version comes from the in-process version cache when possible (see
+/// below). Falls back to version 0 if the version can't be read, which at
+/// worst serves entries from before the last invalidation until Redis recovers.
+pub async fn user_cache_key(cache: &dyn CacheBackend, user_id: &str, suffix: &str) -> String {
+    let version = user_cache_version(cache, user_id).await;
+    format!("user:{}:v{}:{}", user_id, version, suffix)
+}
+
+/// The user's current namespace version (see `user_cache_key`)
+pub async fn user_cache_version(cache: &dyn CacheBackend, user_id: &str) -> u64 {
+    if let Some(version) = local_version(user_id) {
+        return version;
     }
+
+    telemetry::cache("cachever", "get", async {
+        match cache.get(&namespace_version_key(user_id)).await {
+            Ok(v) => {
+                let v = v.and_then(|v| v.parse::<u64>().ok());
+                let version = v.unwrap_or(0);
+                remember_version(user_id, version);
+                (version, if v.is_some() { CacheOutcome::Hit } else { CacheOutcome::Miss })
+            }
+            Err(e) => {
+                log::warn!("Failed to read cache namespace version for {}: {}", user_id, e);
+                (0, CacheOutcome::Error)
+            }
+        }
+    })
+    .await
+}
+
+/// Invalidate all cached entries of a user by bumping their namespace version
+///
+/// The new version is broadcast to every instance, in the same step as the
+/// bump, so their in-process version caches drop the old namespace
+/// immediately.
+pub async fn invalidate_user_cache(cache: &dyn CacheBackend, user_id: &str) -> Result<(), RedisError> {
+    // The script fills in the version
+    let event = InvalidationEvent {
+        user_id: user_id.to_string(),
+        version: 0,
+        origin: *INSTANCE_ID,
+    };
+    let payload = serde_json::to_string(&event).unwrap_or_default();
+    let version: u64 = telemetry::cache("cachever", "invalidate", async {
+        match cache.incr_and_publish(&namespace_version_key(user_id), INVALIDATION_CHANNEL, payload).await {
+            Ok(version) => (Ok(version), CacheOutcome::Write),
+            Err(e) => (Err(e), CacheOutcome::Error),
+        }
+    })
+    .await?;
+    remember_version(user_id, version);
+    log::info!("Cache namespace for user {} bumped to v{}", user_id, version);
     Ok(())
 }
 
-// Invalidate all cache for a user (transactions and wallets)
-pub async fn invalidate_user_cache(
-    cache: &ConnectionManager,
-    user_id: &str,
-) -> Result<(), redis::RedisError> {
-    let patterns = vec![
-        format!("transactions:{}*", user_id),
-        format!("transaction{}:*", user_id),
-        format!("wallets:{}*", user_id),
-        format!("wallet{}:*", user_id),
-        format!("wallet:{}*", user_id),
-    ];
+// ==================== Cluster-Wide Invalidation ====================
+//
+// Each instance keeps recently read namespace versions in process memory, so
+// building a cache key doesn't cost a Redis round trip per request. To keep
+// that layer coherent across instances:
+//
+// - `invalidate_user_cache` publishes `{user_id, version, origin}` on the
+//   `INVALIDATION_CHANNEL` pub/sub channel in the script that runs the INCR.
+// - Every instance runs `spawn_invalidation_subscriber`, which applies those
+//   events to its local versions (keeping the highest version seen, so a
+//   slower concurrent read can't roll a user back).
+// - The local layer is only consulted while the subscriber is connected. On
+//   disconnect it is cleared and bypassed until the subscription is back,
+//   since events published in between are lost (pub/sub is fire-and-forget).
+// - Entries also expire after `LOCAL_VERSION_TTL` to bound staleness if an
+//   event is dropped without the connection noticing.
+//
+// ============================================================================
+
+/// Redis pub/sub channel carrying namespace invalidation events
+const INVALIDATION_CHANNEL: &str = "ketobook:cache:invalidations";
+
+/// How long a locally cached namespace version is trusted
+const LOCAL_VERSION_TTL: Duration = Duration::from_secs(60);
+
+/// Upper bound on locally cached versions (the map is cleared when exceeded)
+const LOCAL_VERSION_CAPACITY: usize = 10_000;
+
+/// Delay before re-subscribing after the subscriber connection drops
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Identifies this instance in published events (for logging)
+static INSTANCE_ID: LazyLock<Uuid> = LazyLock::new(Uuid::new_v4);
+
+/// Whether the invalidation subscriber is connected (local layer usable)
+static SUBSCRIBER_LIVE: AtomicBool = AtomicBool::new(false);
+
+/// user_id -> (namespace version, when it was cached)
+static LOCAL_VERSIONS: LazyLock<RwLock<HashMap<String, (u64, Instant)>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
 
-    for pattern in patterns {
-        invalidate_cache_pattern(cache, &pattern).await?;
+#[derive(Debug, Serialize, Deserialize)]
+struct InvalidationEvent {
+    user_id: String,
+    version: u64,
+    origin: Uuid,
+}
+
+fn local_version(user_id: &str) -> Option<u64> {
+    if !SUBSCRIBER_LIVE.load(Ordering::Acquire) {
+        return None;
     }
+    let versions = LOCAL_VERSIONS.read().ok()?;
+    versions
+        .get(user_id)
+        .filter(|(_, cached_at)| cached_at.elapsed() < LOCAL_VERSION_TTL)
+        .map(|(version, _)| *version)
+}
 
-    log::info!("All cache invalidated for user: {}", user_id);
+/// Record a namespace version, never lowering one already known
+fn remember_version(user_id: &str, version: u64) {
+    if !SUBSCRIBER_LIVE.load(Ordering::Acquire) {
+        return;
+    }
+    if let Ok(mut versions) = LOCAL_VERSIONS.write() {
+        if versions.len() >= LOCAL_VERSION_CAPACITY && !versions.contains_key(user_id) {
+            versions.clear();
+        }
+        let entry = versions.entry(user_id.to_string()).or_insert((version, Instant::now()));
+        if version >= entry.0 {
+            *entry = (version, Instant::now());
+        }
+    }
+}
+
+fn clear_local_versions() {
+    if let Ok(mut versions) = LOCAL_VERSIONS.write() {
+        versions.clear();
+    }
+}
+
+/// Subscribe to cluster-wide invalidation events for the life of the process
+///
+/// Reconnects with a fixed delay if the subscription drops.
+pub fn spawn_invalidation_subscriber(redis_url: String) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = run_invalidation_subscriber(&redis_url).await {
+                log::warn!("Cache invalidation subscriber error: {}", e);
+            }
+            SUBSCRIBER_LIVE.store(false, Ordering::Release);
+            clear_local_versions();
+            tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+        }
+    });
+}
+
+async fn run_invalidation_subscriber(redis_url: &str) -> Result<(), redis::RedisError> {
+    let client = Client::open(redis_url)?;
+    let mut pubsub = client.get_async_pubsub().await?;
+    pubsub.subscribe(INVALIDATION_CHANNEL).await?;
+
+    clear_local_versions();
+    SUBSCRIBER_LIVE.store(true, Ordering::Release);
+    log::info!("Subscribed to cache invalidations (instance {})", *INSTANCE_ID);
+
+    let mut messages = pubsub.on_message();
+    while let Some(msg) = messages.next().await {
+        let payload: String = match msg.get_payload() {
+            Ok(p) => p,
+            Err(e) => {
+                log::warn!("Malformed cache invalidation message: {}", e);
+                continue;
+            }
+        };
+        match serde_json::from_str::<InvalidationEvent>(&payload) {
+            Ok(event) => {
+                if event.origin != *INSTANCE_ID {
+                    log::debug!(
+                        "Cache namespace for user {} is now v{} (from instance {})",
+                        event.user_id, event.version, event.origin
+                    );
+                }
+                remember_version(&event.user_id, event.version);
+            }
+            Err(e) => log::warn!("Malformed cache invalidation event: {}", e),
+        }
+    }
+
+    log::warn!("Cache invalidation subscription closed");
     Ok(())
 }
 
+// ==================== Encrypted Cache Values ====================
+//
+// Cached lists are plaintext financial data unless `CACHE_ENCRYPTION_KEYS` is
+// set, in which case every value `get_or_set_cache` writes is sealed with
+// AES-256-GCM. Callers don't notice: values are encrypted right after
+// serialization and decrypted right before deserialization.
+//
+// - Keys are configured as `id:base64key` pairs, comma-separated. The first
+//   key encrypts; all of them decrypt.
+// - A sealed value is `enc1:{key_id}:{base64(nonce || ciphertext)}`, with the
+//   Redis key as associated data, so an entry copied under another key (for
+//   example another user's namespace) fails to decrypt.
+// - Anything that doesn't decrypt (a plaintext entry from before encryption
+//   was enabled, a retired key id, tampering) is a cache miss, and the
+//   refetched value is written back under the current key.
+//
+// Rotating: put the new key first and keep the old one after it for at least
+// the cache TTL (1 hour plus `CACHE_TTL_JITTER_PERCENT`), then drop it.
+//
+// ============================================================================
+
+/// Prefix of sealed values (format version 1)
+const SEALED_PREFIX: &str = "enc1:";
+
+/// Cipher installed by `configure_encryption` (unset: values are stored plaintext)
+static CACHE_CIPHER: OnceLock<CacheCipher> = OnceLock::new();
+
+/// AES-256-GCM keys by id; the first one encrypts
+struct CacheCipher {
+    keys: Vec<(String, Aes256Gcm)>,
+}
+
+impl CacheCipher {
+    /// Parse `id:base64key[,id:base64key...]`
+    fn parse(spec: &str) -> Result<Self, String> {
+        let mut keys: Vec<(String, Aes256Gcm)> = Vec::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (id, encoded) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("Cache key '{}' must be written as id:base64key", entry))?;
+            if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                return Err(format!("Cache key id '{}' must be alphanumeric (plus - and _)", id));
+            }
+            if keys.iter().any(|(existing, _)| existing == id) {
+                return Err(format!("Cache key id '{}' is listed twice", id));
+            }
+            let key = BASE64
+                .decode(encoded)
+                .map_err(|e| format!("Cache key '{}' is not valid base64: {}", id, e))?;
+            let cipher = Aes256Gcm::new_from_slice(&key)
+                .map_err(|_| format!("Cache key '{}' must be 32 bytes, got {}", id, key.len()))?;
+            keys.push((id.to_string(), cipher));
+        }
+        if keys.is_empty() {
+            return Err("No cache encryption keys given".to_string());
+        }
+        Ok(Self { keys })
+    }
+
+    fn seal(&self, cache_key: &str, plaintext: &str) -> Option<String> {
+        let (id, cipher) = &self.keys[0];
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, Payload { msg: plaintext.as_bytes(), aad: cache_key.as_bytes() })
+            .ok()?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Some(format!("{}{}:{}", SEALED_PREFIX, id, BASE64.encode(sealed)))
+    }
+
+    fn open(&self, cache_key: &str, stored: &str) -> Option<String> {
+        let (id, encoded) = stored.strip_prefix(SEALED_PREFIX)?.split_once(':')?;
+        let (_, cipher) = self.keys.iter().find(|(key_id, _)| key_id == id)?;
+        let sealed = BASE64.decode(encoded).ok()?;
+        if sealed.len() < 12 {
+            return None;
+        }
+        let (nonce, ciphertext) = sealed.split_at(12);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: cache_key.as_bytes() })
+            .ok()?;
+        String::from_utf8(plaintext).ok()
+    }
+}
+
+/// Enable cache encryption if `CACHE_ENCRYPTION_KEYS` is set
+///
+/// Returns whether encryption is on. A malformed key list is an error rather
+/// than a silent fallback to plaintext.
+pub fn configure_encryption(config: &AppConfig) -> Result<bool, String> {
+    let Some(spec) = &config.cache_encryption_keys else {
+        return Ok(false);
+    };
+    let cipher = CacheCipher::parse(spec)?;
+    log::info!(
+        "Cache values encrypted with key '{}' ({} key(s) accepted)",
+        cipher.keys[0].0,
+        cipher.keys.len()
+    );
+    CACHE_CIPHER
+        .set(cipher)
+        .map_err(|_| "Cache encryption is already configured".to_string())?;
+    Ok(true)
+}
+
+/// Serialized value as stored in Redis, `None` if it couldn't be encrypted
+pub fn encode_value(cache_key: &str, json: String) -> Option<String> {
+    match CACHE_CIPHER.get() {
+        Some(cipher) => {
+            let sealed = cipher.seal(cache_key, &json);
+            if sealed.is_none() {
+                log::error!("Failed to encrypt cache value for key: {}", cache_key);
+            }
+            sealed
+        }
+        None => Some(json),
+    }
+}
+
+/// Serialized value from what is stored in Redis, `None` if it doesn't decrypt
+pub fn decode_value(cache_key: &str, stored: &str) -> Option<String> {
+    match CACHE_CIPHER.get() {
+        Some(cipher) => {
+            let json = cipher.open(cache_key, stored);
+            if json.is_none() {
+                log::debug!("Cache entry for key {} did not decrypt; refetching", cache_key);
+            }
+            json
+        }
+        None => Some(stored.to_string()),
+    }
+}
+
+// ==================== Stats ====================
+
+/// What this instance knows about the cache, for the admin panel
+pub fn stats(cache: &dyn CacheBackend) -> CacheStats {
+    let mut stale_grace_secs: Vec<(String, u64)> = SWR_GRACE_SECS
+        .get()
+        .map(|grace| grace.iter().map(|(namespace, secs)| (namespace.clone(), *secs)).collect())
+        .unwrap_or_default();
+    stale_grace_secs.sort();
+    CacheStats {
+        backend: if cache.is_shared() { "redis" } else { "memory" }.to_string(),
+        invalidation_subscriber: SUBSCRIBER_LIVE.load(Ordering::Acquire),
+        reconnects: RECONNECTS.load(Ordering::Relaxed),
+        local_versions: LOCAL_VERSIONS.read().map(|versions| versions.len()).unwrap_or_default(),
+        encrypted: CACHE_CIPHER.get().is_some(),
+        ttl_jitter_percent: TTL_JITTER_PERCENT.load(Ordering::Relaxed),
+        stale_grace_secs,
+        outcomes: telemetry::cache_outcome_counts()
+            .into_iter()
+            .map(|(outcome, count)| (outcome.to_string(), count))
+            .collect(),
+    }
+}
+
 #[derive(Debug)]
 pub enum CacheError {
-    CacheError(redis::RedisError),
-    DatabaseError(sqlx::Error),
-    SerializationError(serde_json::Error),
+    Database(sqlx::Error),
+    Serialization(serde_json::Error),
 }
 
 impl std::fmt::Display for CacheError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            CacheError::CacheError(e) => write!(f, "Cache error: {}", e),
-            CacheError::DatabaseError(e) => write!(f, "Database error: {}", e),
-            CacheError::SerializationError(e) => write!(f, "Serialization error: {}", e),
+            CacheError::Database(e) => write!(f, "Database error: {}", e),
+            CacheError::Serialization(e) => write!(f, "Serialization error: {}", e),
         }
     }
 }
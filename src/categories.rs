@@ -0,0 +1,648 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::cache::{get_or_set_cache, invalidate_user_cache, user_cache_key, CacheBackend};
+use crate::category_seeds;
+use crate::email_templates::normalize_locale;
+use crate::error::AppError;
+use crate::installments::INSTALLMENT_CATEGORY;
+use crate::interest::INTEREST_CATEGORY;
+use crate::reimbursements::REIMBURSEMENT_CATEGORY;
+use crate::routes::ScopedRoutes;
+use crate::splits::DEBT_PAYMENT_CATEGORY;
+use crate::standing_orders::TRANSFER_CATEGORY;
+use crate::models::{
+    ApiResponse, Category, CategoryChange, CreateCategoryRequest, MergeCategoryRequest, MoveCategoryRequest,
+    SeedCategoriesRequest, SeededCategories, UpdateCategoryRequest,
+};
+
+// ==================== CATEGORIES ====================
+//
+// Transactions store their category as text. Each user has a list of
+// categories that text must come from:
+//
+// - Creating or editing a transaction matches its category against the list
+//   case-insensitively and stores the listed spelling ("Groceries" becomes
+//   "groceries"); a category that isn't listed yet is added.
+// - The list is seeded on first use with the default categories in the
+//   user's language (see category_seeds.rs), the system categories KetoBook
+//   posts itself, and every category the user's transactions already use.
+// - Categories nest one level deep: a parent is a top-level category, and a
+//   category with subcategories can't be nested.
+// - Renaming a category, or merging one into another, rewrites the category
+//   of the user's transactions and split lines, templates, recurring
+//   transactions, budgets and tax mappings in the same database transaction.
+//   A merge moves the merged category's subcategories to the target.
+// - `POST /api/categories/{user_id}/seed` adds back the default categories
+//   the user deleted, in a locale of their choice.
+// - System categories (transfer, interest, ...) are matched on by other
+//   modules, so they can't be renamed, merged or deleted.
+//
+// ============================================================================
+
+/// Maximum number of categories per user
+const MAX_CATEGORIES_PER_USER: i64 = 200;
+
+const CATEGORY_COLUMNS: &str = "id, user_id, name, parent_id, icon, system, created_at, updated_at";
+
+/// Categories other modules post and match on
+pub(crate) const SYSTEM_CATEGORIES: &[&str] = &[
+    TRANSFER_CATEGORY,
+    INTEREST_CATEGORY,
+    DEBT_PAYMENT_CATEGORY,
+    INSTALLMENT_CATEGORY,
+    REIMBURSEMENT_CATEGORY,
+];
+
+/// Tables whose `category` follows renames and merges. Where the category is
+/// part of a unique key, a relabeled row `s` is dropped when a row `d` with
+/// the same rest of the key (`key`) already has the new name, or is another
+/// spelling of the old name kept instead.
+const RELABELED: &[(&str, Option<&str>)] = &[
+    ("transactions", None),
+    ("transaction_splits", None),
+    ("transaction_templates", None),
+    ("recurring_transactions", None),
+    ("budgets", Some("d.period = s.period")),
+    ("tax_category_mappings", Some("TRUE")),
+];
+
+// ==================== Handlers ====================
+
+/// List a user's categories (with caching), parents before their subcategories
+pub async fn get_user_categories(
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = user_id.into_inner();
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, "categories").await;
+
+    let categories = get_or_set_cache(
+        cache.get_ref(),
+        &cache_key,
+        fetch_categories(db.get_ref(), &user_id),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(categories)))
+}
+
+/// Add a category
+pub async fn create_category(
+    req: web::Json<CreateCategoryRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let name = req.name.trim();
+    validate_name(name).map_err(AppError::Validation)?;
+    let icon = req.icon.as_deref().map(str::trim).filter(|icon| !icon.is_empty());
+    if let Some(icon) = icon {
+        validate_icon(icon).map_err(AppError::Validation)?;
+    }
+
+    let mut db_tx = db.begin().await?;
+    ensure_seeded(&mut db_tx, &req.user_id, None).await?;
+
+    if let Some(parent_id) = req.parent_id {
+        check_parent(&mut db_tx, &req.user_id, parent_id).await?;
+    }
+    check_capacity(&mut db_tx, &req.user_id).await?;
+
+    let category = sqlx::query_as::<_, Category>(&format!(
+        "INSERT INTO categories (id, user_id, name, parent_id, icon, system, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, FALSE, $6, $6)
+         ON CONFLICT (user_id, LOWER(name)) DO NOTHING
+         RETURNING {}",
+        CATEGORY_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(name)
+    .bind(req.parent_id)
+    .bind(icon)
+    .bind(Utc::now())
+    .fetch_optional(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to create category"))?
+    .ok_or_else(|| AppError::Conflict(format!("Category '{}' already exists", name)))?;
+
+    db_tx.commit().await.map_err(AppError::database("Failed to create category"))?;
+
+    let _ = invalidate_user_cache(cache.get_ref(), &req.user_id).await;
+    Ok(HttpResponse::Created().json(ApiResponse::success(category)))
+}
+
+/// Rename a category (relabeling its transactions) or change its icon
+pub async fn update_category(
+    path: web::Path<(String, Uuid)>,
+    req: web::Json<UpdateCategoryRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, category_id) = path.into_inner();
+    let name = req.name.as_deref().map(str::trim);
+    if let Some(name) = name {
+        validate_name(name).map_err(AppError::Validation)?;
+    }
+    let icon = req.icon.as_deref().map(str::trim);
+    if let Some(icon) = icon.filter(|icon| !icon.is_empty()) {
+        validate_icon(icon).map_err(AppError::Validation)?;
+    }
+
+    let mut db_tx = db.begin().await?;
+    let current = lock_category(&mut db_tx, &user_id, category_id).await?;
+
+    let rename = name.filter(|name| *name != current.name);
+    if let Some(name) = rename {
+        if current.system {
+            return Err(AppError::Validation("System categories can't be renamed".to_string()));
+        }
+        let clash: Option<(Uuid,)> = sqlx::query_as(
+            "SELECT id FROM categories WHERE user_id = $1 AND LOWER(name) = LOWER($2) AND id <> $3",
+        )
+        .bind(&user_id)
+        .bind(name)
+        .bind(category_id)
+        .fetch_optional(&mut *db_tx)
+        .await?;
+        if clash.is_some() {
+            return Err(AppError::Conflict(format!(
+                "Category '{}' already exists; merge into it instead",
+                name
+            )));
+        }
+    }
+
+    let category = sqlx::query_as::<_, Category>(&format!(
+        "UPDATE categories
+         SET name = COALESCE($1, name),
+             icon = CASE WHEN $2::text IS NULL THEN icon ELSE NULLIF($2, '') END,
+             updated_at = $3
+         WHERE id = $4
+         RETURNING {}",
+        CATEGORY_COLUMNS
+    ))
+    .bind(rename)
+    .bind(icon)
+    .bind(Utc::now())
+    .bind(category_id)
+    .fetch_one(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to update category"))?;
+
+    let transactions_updated = match rename {
+        Some(name) => relabel(&mut db_tx, &user_id, &current.name, name)
+            .await
+            .map_err(AppError::database("Failed to rename category"))?,
+        None => 0,
+    };
+    db_tx.commit().await.map_err(AppError::database("Failed to update category"))?;
+
+    if rename.is_some() {
+        log::info!(
+            target: "audit",
+            "Category {} of user {} renamed from '{}' to '{}' ({} transactions)",
+            category.id,
+            user_id,
+            current.name,
+            category.name,
+            transactions_updated
+        );
+    }
+    let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(CategoryChange { category, transactions_updated })))
+}
+
+/// Nest a category under a top-level category, or move it to the top level
+pub async fn move_category(
+    path: web::Path<(String, Uuid)>,
+    req: web::Json<MoveCategoryRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, category_id) = path.into_inner();
+
+    let mut db_tx = db.begin().await?;
+    lock_category(&mut db_tx, &user_id, category_id).await?;
+
+    if let Some(parent_id) = req.parent_id {
+        if parent_id == category_id {
+            return Err(AppError::Validation("A category can't be its own parent".to_string()));
+        }
+        check_parent(&mut db_tx, &user_id, parent_id).await?;
+        let (children,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM categories WHERE parent_id = $1")
+            .bind(category_id)
+            .fetch_one(&mut *db_tx)
+            .await?;
+        if children > 0 {
+            return Err(AppError::Validation("A category with subcategories can't be nested".to_string()));
+        }
+    }
+
+    let category = sqlx::query_as::<_, Category>(&format!(
+        "UPDATE categories SET parent_id = $1, updated_at = $2 WHERE id = $3 RETURNING {}",
+        CATEGORY_COLUMNS
+    ))
+    .bind(req.parent_id)
+    .bind(Utc::now())
+    .bind(category_id)
+    .fetch_one(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to move category"))?;
+    db_tx.commit().await.map_err(AppError::database("Failed to move category"))?;
+
+    let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(category)))
+}
+
+/// Merge a category into another: its transactions and subcategories move to the target
+pub async fn merge_category(
+    path: web::Path<(String, Uuid)>,
+    req: web::Json<MergeCategoryRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, source_id) = path.into_inner();
+    if req.into_id == source_id {
+        return Err(AppError::Validation("A category can't be merged into itself".to_string()));
+    }
+
+    let mut db_tx = db.begin().await?;
+    // Lock in id order so two opposite merges can't deadlock
+    let (first, second) = if source_id < req.into_id { (source_id, req.into_id) } else { (req.into_id, source_id) };
+    let first = lock_category(&mut db_tx, &user_id, first).await?;
+    let second = lock_category(&mut db_tx, &user_id, second).await?;
+    let (source, target) = if first.id == source_id { (first, second) } else { (second, first) };
+    if source.system || target.system {
+        return Err(AppError::Validation("System categories can't be merged".to_string()));
+    }
+
+    let transactions_updated = relabel(&mut db_tx, &user_id, &source.name, &target.name)
+        .await
+        .map_err(AppError::database("Failed to merge categories"))?;
+
+    // Subcategories move under the target, or under its parent when the
+    // target is a subcategory itself (of the merged category: then it
+    // becomes top-level and takes its former siblings)
+    let new_parent = match target.parent_id {
+        Some(parent_id) if parent_id != source.id => parent_id,
+        _ => target.id,
+    };
+    sqlx::query("UPDATE categories SET parent_id = NULL WHERE id = $1 AND parent_id = $2")
+        .bind(target.id)
+        .bind(source.id)
+        .execute(&mut *db_tx)
+        .await?;
+    sqlx::query("UPDATE categories SET parent_id = $1 WHERE parent_id = $2")
+        .bind(new_parent)
+        .bind(source.id)
+        .execute(&mut *db_tx)
+        .await?;
+    sqlx::query("DELETE FROM categories WHERE id = $1")
+        .bind(source.id)
+        .execute(&mut *db_tx)
+        .await?;
+
+    let category = sqlx::query_as::<_, Category>(&format!(
+        "UPDATE categories SET updated_at = $1 WHERE id = $2 RETURNING {}",
+        CATEGORY_COLUMNS
+    ))
+    .bind(Utc::now())
+    .bind(target.id)
+    .fetch_one(&mut *db_tx)
+    .await?;
+    db_tx.commit().await.map_err(AppError::database("Failed to merge categories"))?;
+
+    log::info!(
+        target: "audit",
+        "Category '{}' of user {} merged into '{}' ({} transactions)",
+        source.name,
+        user_id,
+        target.name,
+        transactions_updated
+    );
+    let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(CategoryChange { category, transactions_updated })))
+}
+
+/// Delete a category no transaction uses; its subcategories become top-level
+pub async fn delete_category(
+    path: web::Path<(String, Uuid)>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, category_id) = path.into_inner();
+
+    let mut db_tx = db.begin().await?;
+    let category = lock_category(&mut db_tx, &user_id, category_id).await?;
+    if category.system {
+        return Err(AppError::Validation("System categories can't be deleted".to_string()));
+    }
+
+    let (used,): (i64,) =
+        sqlx::query_as("SELECT COUNT(DISTINCT id) FROM v_transaction_category_lines WHERE user_id = $1 AND deleted_at IS NULL AND LOWER(category) = LOWER($2)")
+            .bind(&user_id)
+            .bind(&category.name)
+            .fetch_one(&mut *db_tx)
+            .await?;
+    if used > 0 {
+        return Err(AppError::Conflict(format!(
+            "Category '{}' is used by {} transactions; merge it into another category instead",
+            category.name, used
+        )));
+    }
+
+    sqlx::query("DELETE FROM categories WHERE id = $1")
+        .bind(category_id)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(AppError::database("Failed to delete category"))?;
+    db_tx.commit().await.map_err(AppError::database("Failed to delete category"))?;
+
+    let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Add back the default categories a user deleted
+pub async fn seed_categories(
+    user_id: web::Path<String>,
+    req: web::Json<SeedCategoriesRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = user_id.into_inner();
+    let requested = match req.locale.as_deref() {
+        Some(locale) => {
+            Some(normalize_locale(locale).ok_or_else(|| AppError::Validation("Invalid locale".to_string()))?)
+        }
+        None => None,
+    };
+
+    let mut db_tx = db.begin().await?;
+    ensure_seeded(&mut db_tx, &user_id, requested.as_deref()).await?;
+
+    // Re-seeds of the same user wait for each other
+    let (seeded_in,): (Option<String>,) =
+        sqlx::query_as("SELECT locale FROM category_seeds WHERE user_id = $1 FOR UPDATE")
+            .bind(&user_id)
+            .fetch_one(&mut *db_tx)
+            .await?;
+    let locale = requested
+        .or(seeded_in)
+        .unwrap_or_else(|| category_seeds::default_locale().to_string());
+
+    let added = category_seeds::add_defaults(&mut db_tx, &user_id, &locale).await?;
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM categories WHERE user_id = $1")
+        .bind(&user_id)
+        .fetch_one(&mut *db_tx)
+        .await?;
+    if count > MAX_CATEGORIES_PER_USER {
+        return Err(AppError::Validation(format!(
+            "Adding {} default categories would exceed the limit of {} categories",
+            added, MAX_CATEGORIES_PER_USER
+        )));
+    }
+    sqlx::query("UPDATE category_seeds SET locale = $2 WHERE user_id = $1")
+        .bind(&user_id)
+        .bind(&locale)
+        .execute(&mut *db_tx)
+        .await?;
+    db_tx.commit().await.map_err(AppError::database("Failed to seed categories"))?;
+
+    let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+    let categories = fetch_categories(db.get_ref(), &user_id).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(SeededCategories {
+        locale,
+        added,
+        categories,
+    })))
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err("Category must be 1-100 characters".to_string());
+    }
+    Ok(())
+}
+
+fn validate_icon(icon: &str) -> Result<(), String> {
+    if icon.chars().count() > 50 {
+        return Err("Icon must be at most 50 characters".to_string());
+    }
+    Ok(())
+}
+
+// ==================== Transactions ====================
+
+/// The listed spelling of `name` for `user_id`, adding the category if it
+/// isn't listed yet
+///
+/// Call inside the database transaction that stores the transaction.
+pub async fn resolve(conn: &mut PgConnection, user_id: &str, name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    validate_name(name).map_err(AppError::Validation)?;
+    ensure_seeded(&mut *conn, user_id, None).await?;
+
+    if let Some(listed) = find_name(&mut *conn, user_id, name).await? {
+        return Ok(listed);
+    }
+
+    check_capacity(&mut *conn, user_id).await?;
+    sqlx::query(
+        "INSERT INTO categories (user_id, name)
+         VALUES ($1, $2)
+         ON CONFLICT (user_id, LOWER(name)) DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(name)
+    .execute(&mut *conn)
+    .await?;
+
+    // A concurrent insert of the same name may have won; use its spelling
+    Ok(find_name(&mut *conn, user_id, name).await?.unwrap_or_else(|| name.to_string()))
+}
+
+async fn find_name(conn: &mut PgConnection, user_id: &str, name: &str) -> Result<Option<String>, sqlx::Error> {
+    let listed: Option<(String,)> =
+        sqlx::query_as("SELECT name FROM categories WHERE user_id = $1 AND LOWER(name) = LOWER($2)")
+            .bind(user_id)
+            .bind(name)
+            .fetch_optional(&mut *conn)
+            .await?;
+    Ok(listed.map(|(name,)| name))
+}
+
+/// Seed a user's list on first use: defaults (named in `locale`, else the
+/// deployment's), system categories, and the categories their transactions
+/// already use (first spelling by name order)
+pub(crate) async fn ensure_seeded(
+    conn: &mut PgConnection,
+    user_id: &str,
+    locale: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let locale = locale.unwrap_or_else(|| category_seeds::default_locale());
+    let first_use = sqlx::query(
+        "INSERT INTO category_seeds (user_id, locale) VALUES ($1, $2) ON CONFLICT (user_id) DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(locale)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected()
+        > 0;
+    if !first_use {
+        return Ok(());
+    }
+
+    category_seeds::add_defaults(&mut *conn, user_id, locale).await?;
+    sqlx::query(
+        "INSERT INTO categories (user_id, name, system)
+         SELECT $1, name, TRUE FROM UNNEST($2::text[]) AS name
+         ON CONFLICT (user_id, LOWER(name)) DO UPDATE SET system = TRUE",
+    )
+    .bind(user_id)
+    .bind(SYSTEM_CATEGORIES)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(
+        "INSERT INTO categories (user_id, name)
+         SELECT DISTINCT ON (LOWER(TRIM(category))) $1, TRIM(category)
+         FROM transactions
+         WHERE user_id = $1 AND TRIM(category) <> ''
+         ORDER BY LOWER(TRIM(category)), TRIM(category)
+         ON CONFLICT (user_id, LOWER(name)) DO NOTHING",
+    )
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+// ==================== Database Functions ====================
+
+async fn fetch_categories(pool: &PgPool, user_id: &str) -> Result<Vec<Category>, sqlx::Error> {
+    let mut db_tx = pool.begin().await?;
+    ensure_seeded(&mut db_tx, user_id, None).await?;
+    db_tx.commit().await?;
+
+    let categories = sqlx::query_as::<_, Category>(&format!(
+        "SELECT {} FROM categories WHERE user_id = $1 ORDER BY LOWER(name)",
+        CATEGORY_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    // Each top-level category followed by its subcategories
+    let (top_level, nested): (Vec<Category>, Vec<Category>) =
+        categories.into_iter().partition(|c| c.parent_id.is_none());
+    let mut ordered = Vec::with_capacity(top_level.len() + nested.len());
+    for parent in top_level {
+        let parent_id = parent.id;
+        ordered.push(parent);
+        ordered.extend(nested.iter().filter(|c| c.parent_id == Some(parent_id)).cloned());
+    }
+    Ok(ordered)
+}
+
+/// The category, locked for the rest of the database transaction
+async fn lock_category(conn: &mut PgConnection, user_id: &str, category_id: Uuid) -> Result<Category, AppError> {
+    sqlx::query_as::<_, Category>(&format!(
+        "SELECT {} FROM categories WHERE id = $1 AND user_id = $2 FOR UPDATE",
+        CATEGORY_COLUMNS
+    ))
+    .bind(category_id)
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Category not found".to_string()))
+}
+
+/// A parent must be a top-level category of the same user
+async fn check_parent(conn: &mut PgConnection, user_id: &str, parent_id: Uuid) -> Result<(), AppError> {
+    let parent: Option<(Option<Uuid>,)> =
+        sqlx::query_as("SELECT parent_id FROM categories WHERE id = $1 AND user_id = $2 FOR SHARE")
+            .bind(parent_id)
+            .bind(user_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+    match parent {
+        None => Err(AppError::Validation("Parent category not found".to_string())),
+        Some((Some(_),)) => Err(AppError::Validation("A subcategory can't have subcategories".to_string())),
+        Some((None,)) => Ok(()),
+    }
+}
+
+async fn check_capacity(conn: &mut PgConnection, user_id: &str) -> Result<(), AppError> {
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM categories WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    if count >= MAX_CATEGORIES_PER_USER {
+        return Err(AppError::Validation(format!(
+            "A user can have at most {} categories",
+            MAX_CATEGORIES_PER_USER
+        )));
+    }
+    Ok(())
+}
+
+/// Rewrite category `from` (any case) to `to` in `RELABELED`; returns how
+/// many transactions changed
+async fn relabel(conn: &mut PgConnection, user_id: &str, from: &str, to: &str) -> Result<u64, sqlx::Error> {
+    let mut transactions_updated = 0;
+    for (table, key) in RELABELED {
+        if let Some(key) = key {
+            sqlx::query(&format!(
+                "DELETE FROM {table} s
+                 WHERE s.user_id = $1 AND LOWER(s.category) = LOWER($2)
+                   AND EXISTS (SELECT 1 FROM {table} d
+                               WHERE d.user_id = $1 AND d.id <> s.id AND {key}
+                                 AND (d.category = $3 OR (LOWER(d.category) = LOWER($2) AND d.id < s.id)))",
+                table = table,
+                key = key,
+            ))
+            .bind(user_id)
+            .bind(from)
+            .bind(to)
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        let count = sqlx::query(&format!(
+            "UPDATE {} SET category = $3 WHERE user_id = $1 AND LOWER(category) = LOWER($2) AND category <> $3",
+            table
+        ))
+        .bind(user_id)
+        .bind(from)
+        .bind(to)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+        if *table == "transactions" {
+            transactions_updated = count;
+        }
+    }
+    Ok(transactions_updated)
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/categories")
+        .create("", create_category)
+        .user(Method::GET, "/{user_id}", get_user_categories)
+        .user(Method::PUT, "/{user_id}/{category_id}", update_category)
+        .user(Method::PUT, "/{user_id}/{category_id}/parent", move_category)
+        .user(Method::POST, "/{user_id}/{category_id}/merge", merge_category)
+        .user(Method::DELETE, "/{user_id}/{category_id}", delete_category)
+        .user(Method::POST, "/{user_id}/seed", seed_categories)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
@@ -0,0 +1,252 @@
+use std::collections::{BTreeMap, HashSet};
+use std::sync::OnceLock;
+
+use actix_web::http::header::ACCEPT_LANGUAGE;
+use actix_web::HttpRequest;
+use serde::Deserialize;
+use sqlx::{PgConnection, PgPool};
+
+use crate::categories::{self, SYSTEM_CATEGORIES};
+use crate::config::AppConfig;
+use crate::email_templates::{normalize_locale, DEFAULT_LOCALE};
+
+// ==================== CATEGORY SEEDS ====================
+//
+// New users start with a tree of default categories in their language:
+//
+// - The seed set ships in `templates/categories.json`; a deployment replaces
+//   it with the file at `CATEGORY_SEED_FILE`. Each entry has a stable `key`,
+//   optionally the key of a top-level `parent`, an icon, and a name per
+//   locale. `install` loads and checks the set at startup, and the server
+//   refuses to start with a broken file.
+// - Names fall back like email templates: `pt-BR`, then `pt`, then
+//   `CATEGORY_SEED_LOCALE`, then `en`, then the entry's first name.
+// - Registering or logging in seeds the user's list in the locale the client
+//   sends (`locale`, else `Accept-Language`). A user whose list is first
+//   touched another way (an API key posting a transaction) is seeded in
+//   `CATEGORY_SEED_LOCALE`. A list is seeded once (see categories.rs).
+// - `POST /api/categories/{user_id}/seed` adds back the defaults the user
+//   deleted. A seeded category keeps its key when renamed, so it isn't added
+//   again; a user's own category with a default's name takes over its key.
+//
+// ============================================================================
+
+/// The seed set shipped with the service
+const BUILTIN_SEEDS: &str = include_str!("../templates/categories.json");
+
+/// Maximum number of entries in a seed set, leaving room for the user's own
+const MAX_SEEDS: usize = 100;
+
+/// One entry of a seed set file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SeedEntry {
+    key: String,
+    #[serde(default)]
+    parent: Option<String>,
+    #[serde(default)]
+    icon: Option<String>,
+    names: BTreeMap<String, String>,
+}
+
+/// The default categories of this deployment
+struct SeedSet {
+    /// Locale used when the client sends none
+    locale: String,
+    entries: Vec<SeedEntry>,
+}
+
+/// A default category named in one locale
+pub(crate) struct Seed<'a> {
+    pub key: &'a str,
+    pub parent: Option<&'a str>,
+    pub icon: Option<&'a str>,
+    pub name: &'a str,
+}
+
+static SEED_SET: OnceLock<SeedSet> = OnceLock::new();
+
+/// Load and check the seed set; returns how many defaults it has
+pub fn install(config: &AppConfig) -> Result<usize, String> {
+    let locale = normalize_locale(&config.category_seed_locale)
+        .ok_or_else(|| format!("CATEGORY_SEED_LOCALE '{}' is not a locale", config.category_seed_locale))?;
+    let set = match &config.category_seed_file {
+        Some(path) => {
+            let source = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            parse(&source, locale).map_err(|e| format!("{}: {}", path, e))?
+        }
+        None => parse(BUILTIN_SEEDS, locale)?,
+    };
+    let count = set.entries.len();
+    if SEED_SET.set(set).is_err() {
+        log::warn!("Category seeds already installed; ignoring the new set");
+    }
+    Ok(count)
+}
+
+fn seed_set() -> &'static SeedSet {
+    SEED_SET.get_or_init(|| {
+        parse(BUILTIN_SEEDS, DEFAULT_LOCALE.to_string()).expect("built-in category seeds are valid")
+    })
+}
+
+/// Locale used when the client sends none
+pub(crate) fn default_locale() -> &'static str {
+    &seed_set().locale
+}
+
+fn parse(source: &str, locale: String) -> Result<SeedSet, String> {
+    let mut entries: Vec<SeedEntry> = serde_json::from_str(source).map_err(|e| e.to_string())?;
+    if entries.len() > MAX_SEEDS {
+        return Err(format!("at most {} categories can be seeded", MAX_SEEDS));
+    }
+
+    let mut top_level: HashSet<&str> = HashSet::new();
+    let mut keys: HashSet<&str> = HashSet::new();
+    for entry in &mut entries {
+        let mut names = BTreeMap::new();
+        for (entry_locale, name) in &entry.names {
+            let entry_locale = normalize_locale(entry_locale)
+                .ok_or_else(|| format!("'{}': '{}' is not a locale", entry.key, entry_locale))?;
+            names.insert(entry_locale, name.trim().to_string());
+        }
+        entry.names = names;
+    }
+    for entry in &entries {
+        let key = entry.key.as_str();
+        let valid_key = (1..=50).contains(&key.len())
+            && key
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+        if !valid_key {
+            return Err(format!("'{}': a key is 1-50 characters of a-z, 0-9, _ and -", key));
+        }
+        if !keys.insert(key) {
+            return Err(format!("'{}': duplicate key", key));
+        }
+        match entry.parent.as_deref() {
+            Some(parent) if !top_level.contains(parent) => {
+                return Err(format!("'{}': parent '{}' must be a top-level entry listed before it", key, parent));
+            }
+            Some(_) => {}
+            None => {
+                top_level.insert(key);
+            }
+        }
+        if entry.icon.as_ref().is_some_and(|icon| icon.chars().count() > 50) {
+            return Err(format!("'{}': an icon is at most 50 characters", key));
+        }
+        if entry.names.is_empty() {
+            return Err(format!("'{}': needs a name", key));
+        }
+        for name in entry.names.values() {
+            if name.is_empty() || name.chars().count() > 100 {
+                return Err(format!("'{}': a name is 1-100 characters", key));
+            }
+            if SYSTEM_CATEGORIES.iter().any(|system| system.eq_ignore_ascii_case(name)) {
+                return Err(format!("'{}': '{}' is a system category", key, name));
+            }
+        }
+    }
+
+    let set = SeedSet { locale, entries };
+    let mut locales: HashSet<&str> = set.entries.iter().flat_map(|e| e.names.keys().map(String::as_str)).collect();
+    locales.insert(&set.locale);
+    for locale in locales {
+        let mut names = HashSet::new();
+        for seed in set.localized(locale) {
+            if !names.insert(seed.name.to_lowercase()) {
+                return Err(format!("two categories are named '{}' in '{}'", seed.name, locale));
+            }
+        }
+    }
+    Ok(set)
+}
+
+impl SeedSet {
+    /// Every default, named in `locale` or its fallbacks
+    fn localized(&self, locale: &str) -> Vec<Seed<'_>> {
+        let mut fallbacks = vec![locale];
+        if let Some((language, _)) = locale.split_once('-') {
+            fallbacks.push(language);
+        }
+        fallbacks.push(&self.locale);
+        fallbacks.push(DEFAULT_LOCALE);
+
+        self.entries
+            .iter()
+            .filter_map(|entry| {
+                let name = fallbacks
+                    .iter()
+                    .find_map(|locale| entry.names.get(*locale))
+                    .or_else(|| entry.names.values().next())?;
+                Some(Seed {
+                    key: &entry.key,
+                    parent: entry.parent.as_deref(),
+                    icon: entry.icon.as_deref(),
+                    name,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The locale a client asked for: `requested` if it is one, else the first
+/// locale of its `Accept-Language`
+pub(crate) fn client_locale(req: &HttpRequest, requested: Option<&str>) -> Option<String> {
+    if let Some(locale) = requested.and_then(normalize_locale) {
+        return Some(locale);
+    }
+    let header = req.headers().get(ACCEPT_LANGUAGE)?.to_str().ok()?;
+    header
+        .split(',')
+        .filter_map(|tag| normalize_locale(tag.split(';').next().unwrap_or_default()))
+        .next()
+}
+
+// ==================== Seeding ====================
+
+/// Seed a user's categories at login or registration, in `locale` when
+/// given; errors are logged, as the login already succeeded
+pub async fn bootstrap(pool: &PgPool, user_id: &str, locale: Option<&str>) {
+    let result = async {
+        let mut db_tx = pool.begin().await?;
+        categories::ensure_seeded(&mut db_tx, user_id, locale).await?;
+        db_tx.commit().await
+    }
+    .await;
+    if let Err(e) = result {
+        log::error!("Error seeding categories of {}: {}", user_id, e);
+    }
+}
+
+/// Add the defaults `user_id` has no category for, named in `locale`;
+/// returns how many were added
+///
+/// A category already named like a missing default takes over its key.
+pub(crate) async fn add_defaults(conn: &mut PgConnection, user_id: &str, locale: &str) -> Result<u64, sqlx::Error> {
+    let mut added = 0;
+    for seed in seed_set().localized(locale) {
+        let inserted: Option<(bool,)> = sqlx::query_as(
+            "INSERT INTO categories (user_id, name, parent_id, icon, seed_key)
+             SELECT $1, $2,
+                    (SELECT id FROM categories WHERE user_id = $1 AND seed_key = $3 AND parent_id IS NULL),
+                    $4, $5
+             WHERE NOT EXISTS (SELECT 1 FROM categories WHERE user_id = $1 AND seed_key = $5)
+             ON CONFLICT (user_id, LOWER(name)) DO UPDATE SET seed_key = EXCLUDED.seed_key
+                 WHERE categories.seed_key IS NULL AND NOT categories.system
+             RETURNING (xmax = 0)",
+        )
+        .bind(user_id)
+        .bind(seed.name)
+        .bind(seed.parent)
+        .bind(seed.icon)
+        .bind(seed.key)
+        .fetch_optional(&mut *conn)
+        .await?;
+        if let Some((true,)) = inserted {
+            added += 1;
+        }
+    }
+    Ok(added)
+}
@@ -0,0 +1,365 @@
+use std::collections::BTreeMap;
+
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::balance_history::{self, Granularity};
+use crate::cache::{get_or_set_cache_swr, user_cache_key, CacheBackend};
+use crate::currencies;
+use crate::error::AppError;
+use crate::models::{ApiResponse, Chart, ChartQuery, ChartSeries};
+use crate::money;
+use crate::reports::{self, DEFAULT_CASHFLOW_MONTHS};
+use crate::routes::ScopedRoutes;
+use crate::wallet_types;
+use crate::wallets;
+
+// ==================== CHARTS ====================
+//
+// `GET /api/charts/{user_id}/{chart_type}` returns report data already
+// shaped for drawing, so the web, iOS and Android clients don't each
+// aggregate, label and color it:
+//
+// - `spending-by-category`: a donut of expenses per category over
+//   `?from=&to=` (default: this month), the largest `MAX_SLICES - 1`
+//   categories and "Other"
+// - `cash-flow`: bars of income, expenses and net over `?months=` months
+//   ending with the current one (default 12)
+// - `net-worth`: a line of assets, liabilities and net per `?granularity=`
+//   (default: month) over `?from=&to=`, from the daily wallet balance
+//   snapshots (see balance_history.rs). Debts have no history and are left
+//   out; the last point is today's balances.
+//
+// Figures are the same as the reports' (see reports.rs), in the user's base
+// currency. Transfers are left out of spending and cash flow.
+//
+// Every chart is a `Chart`: `labels` and series of one value per label, each
+// with a color. A category keeps its color from chart to chart (picked by a
+// hash of its name, then the next unused one). The shape is a versioned
+// contract: clients pin `?version=`, a change that would break them gets a
+// new version, and old versions are served until no client asks for them.
+// Charts are cached in the user's report namespace per version and
+// parameters, so any write invalidates them.
+//
+// ============================================================================
+
+/// Contract versions served; the last is the default
+const VERSIONS: &[u32] = &[1];
+
+/// Most slices of the donut, "Other" included
+const MAX_SLICES: usize = 8;
+
+/// Series and slice colors
+const PALETTE: [&str; 10] = [
+    "#4E79A7", "#F28E2B", "#E15759", "#76B7B2", "#59A14F", "#EDC948", "#B07AA1", "#FF9DA7", "#9C755F", "#86BCB6",
+];
+const OTHER_COLOR: &str = "#BAB0AC";
+
+const INCOME_COLOR: &str = "#59A14F";
+const EXPENSE_COLOR: &str = "#E15759";
+const NET_COLOR: &str = "#4E79A7";
+
+/// The charts there are, by path segment
+#[derive(Debug, Clone, Copy)]
+enum ChartType {
+    SpendingByCategory,
+    CashFlow,
+    NetWorth,
+}
+
+impl ChartType {
+    const ALL: [ChartType; 3] = [ChartType::SpendingByCategory, ChartType::CashFlow, ChartType::NetWorth];
+
+    fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|chart| chart.as_str() == value)
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            ChartType::SpendingByCategory => "spending-by-category",
+            ChartType::CashFlow => "cash-flow",
+            ChartType::NetWorth => "net-worth",
+        }
+    }
+}
+
+// ==================== Handlers ====================
+
+/// A chart of the user's data, by `chart_type`
+///
+/// Supports `?version=` and the chart's parameters (see `ChartQuery`).
+pub async fn get_chart(
+    path: web::Path<(String, String)>,
+    query: web::Query<ChartQuery>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, chart_type) = path.into_inner();
+    let Some(chart_type) = ChartType::parse(&chart_type) else {
+        let known: Vec<&str> = ChartType::ALL.iter().map(|chart| chart.as_str()).collect();
+        return Err(AppError::NotFound(format!(
+            "Unknown chart '{}'; charts are {}",
+            chart_type,
+            known.join(", ")
+        )));
+    };
+    let version = query.version.unwrap_or(VERSIONS[VERSIONS.len() - 1]);
+    if !VERSIONS.contains(&version) {
+        return Err(AppError::Validation(format!("Unsupported chart version {}", version)));
+    }
+
+    let currency = currencies::reporting_currency(db.get_ref(), &user_id)
+        .await
+        .map_err(AppError::database("Failed to check exchange rates"))?
+        .map_err(AppError::Conflict)?;
+
+    let today = Utc::now().date_naive();
+    let wallet = query.wallet_id.map(|id| id.to_string()).unwrap_or_default();
+    let wallet_id = query.wallet_id;
+    let prefix = format!("report:chart:v{}:{}:{}", version, chart_type.as_str(), currency);
+
+    let chart = match chart_type {
+        ChartType::SpendingByCategory => {
+            let to = query.to.unwrap_or(today);
+            let from = query.from.unwrap_or_else(|| to.with_day(1).unwrap_or(to));
+            if from > to {
+                return Err(AppError::Validation("from must not be after to".to_string()));
+            }
+            let suffix = format!("{}:{}:{}:{}", prefix, from, to, wallet);
+            let cache_key = user_cache_key(cache.get_ref(), &user_id, &suffix).await;
+            get_or_set_cache_swr(&cache, &cache_key, async move {
+                spending_chart(db.get_ref(), &user_id, currency, from, to, wallet_id).await
+            })
+            .await?
+        }
+        ChartType::CashFlow => {
+            let months = query.months.unwrap_or(DEFAULT_CASHFLOW_MONTHS);
+            let (start, end) = reports::cashflow_range(months).map_err(AppError::Validation)?;
+            let suffix = format!("{}:{}:{}:{}", prefix, start.timestamp(), months, wallet);
+            let cache_key = user_cache_key(cache.get_ref(), &user_id, &suffix).await;
+            get_or_set_cache_swr(&cache, &cache_key, async move {
+                cash_flow_chart(db.get_ref(), &user_id, currency, start, end, wallet_id).await
+            })
+            .await?
+        }
+        ChartType::NetWorth => {
+            let granularity = Granularity::parse(query.granularity.as_deref(), Granularity::Month)
+                .map_err(AppError::Validation)?;
+            let (from, to) = balance_history::resolve_range(granularity, query.from, query.to, today)
+                .map_err(AppError::Validation)?;
+            let suffix = format!("{}:{}:{}:{}:{}", prefix, granularity.as_str(), from, to, today);
+            let cache_key = user_cache_key(cache.get_ref(), &user_id, &suffix).await;
+            get_or_set_cache_swr(&cache, &cache_key, async move {
+                net_worth_chart(db.get_ref(), &user_id, currency, granularity, from, to, today).await
+            })
+            .await?
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(chart)))
+}
+
+// ==================== Shaping (contract version 1) ====================
+
+/// Expenses per category, the largest `MAX_SLICES - 1` and "Other"
+async fn spending_chart(
+    pool: &PgPool,
+    user_id: &str,
+    currency: String,
+    from: NaiveDate,
+    to: NaiveDate,
+    wallet_id: Option<Uuid>,
+) -> Result<Chart, sqlx::Error> {
+    let start = from.and_time(NaiveTime::MIN).and_utc();
+    let end = to.checked_add_days(Days::new(1)).unwrap_or(to).and_time(NaiveTime::MIN).and_utc();
+    let spending = reports::fetch_spending(pool, user_id, currency, start, end, wallet_id).await?;
+
+    let mut labels = Vec::new();
+    let mut values = Vec::new();
+    let mut colors: Vec<String> = Vec::new();
+    let (shown, rest) = if spending.categories.len() > MAX_SLICES {
+        spending.categories.split_at(MAX_SLICES - 1)
+    } else {
+        (&spending.categories[..], &[][..])
+    };
+    for category in shown {
+        let color = category_color(&category.category, &colors);
+        labels.push(category.category.clone());
+        values.push(category.total.clone());
+        colors.push(color);
+    }
+    if !rest.is_empty() {
+        labels.push("Other".to_string());
+        values.push(rest.iter().map(|c| &c.total).sum());
+        colors.push(OTHER_COLOR.to_string());
+    }
+
+    Ok(Chart {
+        version: 1,
+        chart_type: ChartType::SpendingByCategory.as_str().to_string(),
+        kind: "donut".to_string(),
+        currency: spending.currency,
+        from,
+        to,
+        labels,
+        series: vec![ChartSeries {
+            key: "expense".to_string(),
+            label: "Expenses".to_string(),
+            color: EXPENSE_COLOR.to_string(),
+            point_colors: Some(colors),
+            values,
+            total: spending.total.clone(),
+        }],
+        total: spending.total,
+    })
+}
+
+/// Income, expenses and net per calendar month
+async fn cash_flow_chart(
+    pool: &PgPool,
+    user_id: &str,
+    currency: String,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    wallet_id: Option<Uuid>,
+) -> Result<Chart, sqlx::Error> {
+    let cashflow = reports::fetch_cashflow(pool, user_id, currency, start, end, wallet_id).await?;
+    let months = &cashflow.months;
+
+    Ok(Chart {
+        version: 1,
+        chart_type: ChartType::CashFlow.as_str().to_string(),
+        kind: "bar".to_string(),
+        currency: cashflow.currency.clone(),
+        from: start.date_naive(),
+        to: end.date_naive().pred_opt().unwrap_or(end.date_naive()),
+        labels: months.iter().map(|m| m.month.format("%Y-%m").to_string()).collect(),
+        series: vec![
+            flow_series("income", "Income", INCOME_COLOR, months.iter().map(|m| m.income.clone())),
+            flow_series("expense", "Expenses", EXPENSE_COLOR, months.iter().map(|m| m.expense.clone())),
+            flow_series("net", "Net", NET_COLOR, months.iter().map(|m| m.net.clone())),
+        ],
+        total: cashflow.net.clone(),
+    })
+}
+
+/// Assets, liabilities and net of the user's wallets per period
+///
+/// Each wallet's balance is its closing balance for the period, converted at
+/// today's rate; liability wallets (credit cards, loans) count as
+/// liabilities. Periods before the first wallet are left out.
+async fn net_worth_chart(
+    pool: &PgPool,
+    user_id: &str,
+    currency: String,
+    granularity: Granularity,
+    from: NaiveDate,
+    to: NaiveDate,
+    today: NaiveDate,
+) -> Result<Chart, sqlx::Error> {
+    let zero = BigDecimal::from(0);
+    let mut periods: BTreeMap<NaiveDate, (BigDecimal, BigDecimal)> = BTreeMap::new();
+
+    for wallet in wallets::fetch_wallets_from_db(pool, user_id).await? {
+        let template = wallet_types::template_for(pool, &wallet).await?;
+        // Rates were checked by the caller; one removed since counts as zero
+        let rate = currencies::convert(pool, &BigDecimal::from(1), &wallet.currency, &currency)
+            .await?
+            .map_or_else(|| zero.clone(), |conversion| conversion.rate);
+        let points = balance_history::fetch_balance_points(
+            pool,
+            &wallet.id,
+            granularity,
+            from,
+            to,
+            today,
+            &wallet.balance,
+        )
+        .await?;
+        for point in points {
+            let balance = money::round_cents(&(&point.balance * &rate));
+            let (assets, liabilities) = periods.entry(point.period_start).or_insert_with(|| (zero.clone(), zero.clone()));
+            if template.is_liability() {
+                *liabilities += balance;
+            } else {
+                *assets += balance;
+            }
+        }
+    }
+
+    let labels = periods.keys().map(|period| period.to_string()).collect();
+    let assets: Vec<BigDecimal> = periods.values().map(|(assets, _)| assets.clone()).collect();
+    let liabilities: Vec<BigDecimal> = periods.values().map(|(_, liabilities)| liabilities.clone()).collect();
+    let net: Vec<BigDecimal> = periods.values().map(|(assets, liabilities)| assets - liabilities).collect();
+    let total = net.last().cloned().unwrap_or_else(|| zero.clone());
+
+    Ok(Chart {
+        version: 1,
+        chart_type: ChartType::NetWorth.as_str().to_string(),
+        kind: "line".to_string(),
+        currency,
+        from,
+        to,
+        labels,
+        series: vec![
+            balance_series("assets", "Assets", INCOME_COLOR, assets),
+            balance_series("liabilities", "Liabilities", EXPENSE_COLOR, liabilities),
+            balance_series("net", "Net worth", NET_COLOR, net),
+        ],
+        total,
+    })
+}
+
+/// A series of amounts over time, totalled
+fn flow_series(key: &str, label: &str, color: &str, values: impl Iterator<Item = BigDecimal>) -> ChartSeries {
+    let values: Vec<BigDecimal> = values.collect();
+    ChartSeries {
+        key: key.to_string(),
+        label: label.to_string(),
+        color: color.to_string(),
+        point_colors: None,
+        total: values.iter().sum(),
+        values,
+    }
+}
+
+/// A series of balances, ending with the latest
+fn balance_series(key: &str, label: &str, color: &str, values: Vec<BigDecimal>) -> ChartSeries {
+    ChartSeries {
+        key: key.to_string(),
+        label: label.to_string(),
+        color: color.to_string(),
+        point_colors: None,
+        total: values.last().cloned().unwrap_or_else(|| BigDecimal::from(0)),
+        values,
+    }
+}
+
+/// A category's palette color, the next one along if the chart already uses it
+fn category_color(category: &str, used: &[String]) -> String {
+    // FNV-1a: stable across builds, unlike the std hasher
+    let hash = category
+        .to_lowercase()
+        .bytes()
+        .fold(0x811c9dc5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x01000193));
+    let start = hash as usize % PALETTE.len();
+    (0..PALETTE.len())
+        .map(|offset| PALETTE[(start + offset) % PALETTE.len()])
+        .find(|color| !used.iter().any(|used| used == color))
+        .unwrap_or(PALETTE[start])
+        .to_string()
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/charts").user(Method::GET, "/{user_id}/{chart_type}", get_chart)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
@@ -1,11 +1,83 @@
 use std::env;
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct AppConfig {
     pub database_url: String,
     pub redis_url: String,
     pub server_host: String,
     pub server_port: String,
+    pub interest_job_interval_secs: u64,
+    pub attachments_dir: String,
+    pub attachments_max_bytes: usize,
+    pub attachments_s3_endpoint: Option<String>,
+    pub attachments_s3_bucket: Option<String>,
+    pub attachments_s3_region: String,
+    pub attachments_s3_access_key: Option<String>,
+    pub attachments_s3_secret_key: Option<String>,
+    pub admin_token: Option<String>,
+    pub jwt_secret: Option<String>,
+    pub jwt_ttl_secs: i64,
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub mail_from: String,
+    pub report_job_interval_secs: u64,
+    pub default_page_size: u32,
+    pub max_page_size: u32,
+    pub max_export_rows: i64,
+    pub max_date_range_days: i64,
+    pub login_max_attempts: u32,
+    pub login_captcha_after: u32,
+    pub login_attempt_window_secs: u64,
+    pub login_lockout_base_secs: u64,
+    pub login_lockout_max_secs: u64,
+    pub stripe_webhook_secret: Option<String>,
+    pub plaid_webhook_secret: Option<String>,
+    pub momo_webhook_secret: Option<String>,
+    pub provider_webhook_tolerance_secs: u64,
+    pub backup_s3_endpoint: Option<String>,
+    pub backup_s3_bucket: Option<String>,
+    pub backup_s3_region: String,
+    pub backup_s3_access_key: Option<String>,
+    pub backup_s3_secret_key: Option<String>,
+    pub backup_s3_prefix: String,
+    pub backup_interval_secs: u64,
+    pub backup_retention_count: u32,
+    pub backup_verify_database_url: Option<String>,
+    pub request_timeout_ms: u64,
+    pub extended_request_timeout_ms: u64,
+    pub cache_encryption_keys: Option<String>,
+    pub cache_ttl_jitter_percent: u64,
+    pub cache_swr: Option<String>,
+    pub draft_ttl_secs: u64,
+    pub fcm_project_id: Option<String>,
+    pub fcm_client_email: Option<String>,
+    pub fcm_private_key: Option<String>,
+    pub apns_key_id: Option<String>,
+    pub apns_team_id: Option<String>,
+    pub apns_private_key: Option<String>,
+    pub apns_topic: Option<String>,
+    pub apns_sandbox: bool,
+    pub alert_job_interval_secs: u64,
+    pub notification_job_interval_secs: u64,
+    pub notification_due_days: i16,
+    pub webhook_job_interval_secs: u64,
+    pub trash_purge_interval_secs: u64,
+    pub trash_transaction_retention_days: u32,
+    pub trash_wallet_retention_days: u32,
+    pub export_workers: usize,
+    pub export_retention_secs: u64,
+    pub category_seed_file: Option<String>,
+    pub category_seed_locale: String,
+    pub standing_order_job_interval_secs: u64,
+    pub recurring_job_interval_secs: u64,
+    pub online_migration_job_interval_secs: u64,
+    pub run_migrations: bool,
+    pub exchange_rate_provider_url: Option<String>,
+    pub exchange_rate_job_interval_secs: u64,
+    pub otel_exporter_otlp_endpoint: Option<String>,
+    pub otel_service_name: String,
 }
 
 impl AppConfig {
@@ -19,6 +91,186 @@ impl AppConfig {
                 .expect("REDIS_URL is not set in environment variables"),
             server_host: env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
             server_port: env::var("SERVER_PORT").unwrap_or_else(|_| "8080".to_string()),
+            interest_job_interval_secs: env::var("INTEREST_JOB_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3600),
+            attachments_dir: env::var("ATTACHMENTS_DIR").unwrap_or_else(|_| "./data/attachments".to_string()),
+            attachments_max_bytes: env::var("ATTACHMENTS_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10 * 1024 * 1024),
+            attachments_s3_endpoint: env::var("ATTACHMENTS_S3_ENDPOINT").ok(),
+            attachments_s3_bucket: env::var("ATTACHMENTS_S3_BUCKET").ok(),
+            attachments_s3_region: env::var("ATTACHMENTS_S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            attachments_s3_access_key: env::var("ATTACHMENTS_S3_ACCESS_KEY").ok(),
+            attachments_s3_secret_key: env::var("ATTACHMENTS_S3_SECRET_KEY").ok(),
+            admin_token: env::var("ADMIN_TOKEN").ok(),
+            jwt_secret: env::var("JWT_SECRET").ok().filter(|v| !v.is_empty()),
+            jwt_ttl_secs: env::var("JWT_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(86_400),
+            smtp_host: env::var("SMTP_HOST").ok(),
+            smtp_port: env::var("SMTP_PORT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(587),
+            smtp_username: env::var("SMTP_USERNAME").ok(),
+            smtp_password: env::var("SMTP_PASSWORD").ok(),
+            mail_from: env::var("MAIL_FROM").unwrap_or_else(|_| "KetoBook <no-reply@ketobook.local>".to_string()),
+            report_job_interval_secs: env::var("REPORT_JOB_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(900),
+            default_page_size: env::var("DEFAULT_PAGE_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(50),
+            max_page_size: env::var("MAX_PAGE_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(500),
+            max_export_rows: env::var("MAX_EXPORT_ROWS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(50_000),
+            max_date_range_days: env::var("MAX_DATE_RANGE_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(366),
+            login_max_attempts: env::var("LOGIN_MAX_ATTEMPTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5),
+            login_captcha_after: env::var("LOGIN_CAPTCHA_AFTER")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3),
+            webhook_job_interval_secs: env::var("WEBHOOK_JOB_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            trash_purge_interval_secs: env::var("TRASH_PURGE_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3600),
+            trash_transaction_retention_days: env::var("TRASH_TRANSACTION_RETENTION_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            trash_wallet_retention_days: env::var("TRASH_WALLET_RETENTION_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(90),
+            export_workers: env::var("EXPORT_WORKERS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2),
+            export_retention_secs: env::var("EXPORT_RETENTION_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(86_400),
+            category_seed_file: env::var("CATEGORY_SEED_FILE").ok().filter(|v| !v.is_empty()),
+            category_seed_locale: env::var("CATEGORY_SEED_LOCALE").unwrap_or_else(|_| "en".to_string()),
+            login_attempt_window_secs: env::var("LOGIN_ATTEMPT_WINDOW_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(900),
+            login_lockout_base_secs: env::var("LOGIN_LOCKOUT_BASE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60),
+            login_lockout_max_secs: env::var("LOGIN_LOCKOUT_MAX_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3600),
+            stripe_webhook_secret: env::var("STRIPE_WEBHOOK_SECRET").ok().filter(|v| !v.is_empty()),
+            plaid_webhook_secret: env::var("PLAID_WEBHOOK_SECRET").ok().filter(|v| !v.is_empty()),
+            momo_webhook_secret: env::var("MOMO_WEBHOOK_SECRET").ok().filter(|v| !v.is_empty()),
+            provider_webhook_tolerance_secs: env::var("PROVIDER_WEBHOOK_TOLERANCE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(300),
+            backup_s3_endpoint: env::var("BACKUP_S3_ENDPOINT").ok(),
+            backup_s3_bucket: env::var("BACKUP_S3_BUCKET").ok(),
+            backup_s3_region: env::var("BACKUP_S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            backup_s3_access_key: env::var("BACKUP_S3_ACCESS_KEY").ok(),
+            backup_s3_secret_key: env::var("BACKUP_S3_SECRET_KEY").ok(),
+            backup_s3_prefix: env::var("BACKUP_S3_PREFIX").unwrap_or_else(|_| "backups/".to_string()),
+            backup_interval_secs: env::var("BACKUP_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(86_400),
+            backup_retention_count: env::var("BACKUP_RETENTION_COUNT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(14),
+            backup_verify_database_url: env::var("BACKUP_VERIFY_DATABASE_URL").ok().filter(|v| !v.is_empty()),
+            request_timeout_ms: env::var("REQUEST_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2_000),
+            extended_request_timeout_ms: env::var("EXTENDED_REQUEST_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30_000),
+            cache_encryption_keys: env::var("CACHE_ENCRYPTION_KEYS").ok().filter(|v| !v.is_empty()),
+            cache_ttl_jitter_percent: env::var("CACHE_TTL_JITTER_PERCENT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10),
+            cache_swr: env::var("CACHE_SWR").ok().filter(|v| !v.is_empty()),
+            draft_ttl_secs: env::var("DRAFT_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(604_800),
+            fcm_project_id: env::var("FCM_PROJECT_ID").ok(),
+            fcm_client_email: env::var("FCM_CLIENT_EMAIL").ok(),
+            fcm_private_key: env::var("FCM_PRIVATE_KEY").ok().filter(|v| !v.is_empty()),
+            apns_key_id: env::var("APNS_KEY_ID").ok(),
+            apns_team_id: env::var("APNS_TEAM_ID").ok(),
+            apns_private_key: env::var("APNS_PRIVATE_KEY").ok().filter(|v| !v.is_empty()),
+            apns_topic: env::var("APNS_TOPIC").ok(),
+            apns_sandbox: env::var("APNS_SANDBOX")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+            alert_job_interval_secs: env::var("ALERT_JOB_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(900),
+            notification_job_interval_secs: env::var("NOTIFICATION_JOB_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(900),
+            notification_due_days: env::var("NOTIFICATION_DUE_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3),
+            standing_order_job_interval_secs: env::var("STANDING_ORDER_JOB_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(300),
+            recurring_job_interval_secs: env::var("RECURRING_JOB_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(300),
+            online_migration_job_interval_secs: env::var("ONLINE_MIGRATION_JOB_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60),
+            run_migrations: env::var("RUN_MIGRATIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+            exchange_rate_provider_url: env::var("EXCHANGE_RATE_PROVIDER_URL").ok().filter(|v| !v.is_empty()),
+            exchange_rate_job_interval_secs: env::var("EXCHANGE_RATE_JOB_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(21_600),
+            otel_exporter_otlp_endpoint: env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok().filter(|v| !v.is_empty()),
+            otel_service_name: env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| "ketobook".to_string()),
         }
     }
 
@@ -26,3 +278,88 @@ impl AppConfig {
         format!("{}:{}", self.server_host, self.server_port)
     }
 }
+
+// Secrets (admin token, JWT secret, SMTP password, storage and backup credentials, cache keys, push keys) are redacted from the startup log
+impl std::fmt::Debug for AppConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let redact = |secret: &Option<String>| secret.as_ref().map(|_| "<redacted>");
+        f.debug_struct("AppConfig")
+            .field("database_url", &self.database_url)
+            .field("redis_url", &self.redis_url)
+            .field("server_host", &self.server_host)
+            .field("server_port", &self.server_port)
+            .field("interest_job_interval_secs", &self.interest_job_interval_secs)
+            .field("attachments_dir", &self.attachments_dir)
+            .field("attachments_max_bytes", &self.attachments_max_bytes)
+            .field("attachments_s3_endpoint", &self.attachments_s3_endpoint)
+            .field("attachments_s3_bucket", &self.attachments_s3_bucket)
+            .field("attachments_s3_region", &self.attachments_s3_region)
+            .field("attachments_s3_access_key", &self.attachments_s3_access_key)
+            .field("attachments_s3_secret_key", &redact(&self.attachments_s3_secret_key))
+            .field("admin_token", &redact(&self.admin_token))
+            .field("jwt_secret", &redact(&self.jwt_secret))
+            .field("jwt_ttl_secs", &self.jwt_ttl_secs)
+            .field("smtp_host", &self.smtp_host)
+            .field("smtp_port", &self.smtp_port)
+            .field("smtp_username", &self.smtp_username)
+            .field("smtp_password", &redact(&self.smtp_password))
+            .field("mail_from", &self.mail_from)
+            .field("report_job_interval_secs", &self.report_job_interval_secs)
+            .field("default_page_size", &self.default_page_size)
+            .field("max_page_size", &self.max_page_size)
+            .field("max_export_rows", &self.max_export_rows)
+            .field("max_date_range_days", &self.max_date_range_days)
+            .field("login_max_attempts", &self.login_max_attempts)
+            .field("login_captcha_after", &self.login_captcha_after)
+            .field("login_attempt_window_secs", &self.login_attempt_window_secs)
+            .field("login_lockout_base_secs", &self.login_lockout_base_secs)
+            .field("login_lockout_max_secs", &self.login_lockout_max_secs)
+            .field("stripe_webhook_secret", &redact(&self.stripe_webhook_secret))
+            .field("plaid_webhook_secret", &redact(&self.plaid_webhook_secret))
+            .field("momo_webhook_secret", &redact(&self.momo_webhook_secret))
+            .field("provider_webhook_tolerance_secs", &self.provider_webhook_tolerance_secs)
+            .field("backup_s3_endpoint", &self.backup_s3_endpoint)
+            .field("backup_s3_bucket", &self.backup_s3_bucket)
+            .field("backup_s3_region", &self.backup_s3_region)
+            .field("backup_s3_access_key", &self.backup_s3_access_key)
+            .field("backup_s3_secret_key", &redact(&self.backup_s3_secret_key))
+            .field("backup_s3_prefix", &self.backup_s3_prefix)
+            .field("backup_interval_secs", &self.backup_interval_secs)
+            .field("backup_retention_count", &self.backup_retention_count)
+            .field("backup_verify_database_url", &redact(&self.backup_verify_database_url))
+            .field("request_timeout_ms", &self.request_timeout_ms)
+            .field("extended_request_timeout_ms", &self.extended_request_timeout_ms)
+            .field("cache_encryption_keys", &redact(&self.cache_encryption_keys))
+            .field("cache_ttl_jitter_percent", &self.cache_ttl_jitter_percent)
+            .field("cache_swr", &self.cache_swr)
+            .field("draft_ttl_secs", &self.draft_ttl_secs)
+            .field("fcm_project_id", &self.fcm_project_id)
+            .field("fcm_client_email", &self.fcm_client_email)
+            .field("fcm_private_key", &redact(&self.fcm_private_key))
+            .field("apns_key_id", &self.apns_key_id)
+            .field("apns_team_id", &self.apns_team_id)
+            .field("apns_private_key", &redact(&self.apns_private_key))
+            .field("apns_topic", &self.apns_topic)
+            .field("apns_sandbox", &self.apns_sandbox)
+            .field("alert_job_interval_secs", &self.alert_job_interval_secs)
+            .field("notification_job_interval_secs", &self.notification_job_interval_secs)
+            .field("notification_due_days", &self.notification_due_days)
+            .field("webhook_job_interval_secs", &self.webhook_job_interval_secs)
+            .field("trash_purge_interval_secs", &self.trash_purge_interval_secs)
+            .field("trash_transaction_retention_days", &self.trash_transaction_retention_days)
+            .field("trash_wallet_retention_days", &self.trash_wallet_retention_days)
+            .field("export_workers", &self.export_workers)
+            .field("export_retention_secs", &self.export_retention_secs)
+            .field("category_seed_file", &self.category_seed_file)
+            .field("category_seed_locale", &self.category_seed_locale)
+            .field("standing_order_job_interval_secs", &self.standing_order_job_interval_secs)
+            .field("recurring_job_interval_secs", &self.recurring_job_interval_secs)
+            .field("online_migration_job_interval_secs", &self.online_migration_job_interval_secs)
+            .field("run_migrations", &self.run_migrations)
+            .field("exchange_rate_provider_url", &self.exchange_rate_provider_url)
+            .field("exchange_rate_job_interval_secs", &self.exchange_rate_job_interval_secs)
+            .field("otel_exporter_otlp_endpoint", &self.otel_exporter_otlp_endpoint)
+            .field("otel_service_name", &self.otel_service_name)
+            .finish()
+    }
+}
@@ -0,0 +1,569 @@
+use actix_web::{web, HttpResponse, ResponseError};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::cache::{invalidate_user_cache, CacheBackend};
+use crate::interest::INTEREST_CATEGORY;
+use crate::jobs;
+use crate::models::{
+    ApiResponse, CreditCardCycle, CreditCardStatement, CreditCardStatements, CreditCardTerms,
+    SetCreditCardTermsRequest,
+};
+use crate::wallet_members::{self, Permission};
+use crate::wallet_types;
+use crate::wallets::fetch_wallet_by_id;
+
+// ==================== CREDIT CARD STATEMENTS ====================
+//
+// A credit-limit wallet with terms closes a statement every month on
+// `statement_day` (midnight UTC). The statement balance must be paid in full
+// within `payment_due_days`; while every statement is, purchases are
+// interest-free.
+//
+// Amounts are worked out from the card's own transactions: an expense adds
+// to what is owed, an income (a payment or refund) takes it off, each as of
+// its `created_at`.
+//
+// When a statement closes, the interest job checks the payments made against
+// the previous statement up to its due date. If that statement wasn't paid in
+// full, interest is charged on the daily balance owed, from each purchase's
+// own date:
+//
+//   interest = sum(owed × seconds) × apr / 100 / (365 × 86400)
+//
+// for the period just closed, and also for the previous period when it was
+// still interest-free when it closed. The interest-free period comes back
+// once a statement is paid in full. The charge is posted as one "interest"
+// expense at the statement date. Like savings interest it is posted on
+// frozen cards too.
+//
+// Each statement is claimed in `job_executions` and unique per card and date,
+// so a statement closes exactly once across app instances; a job that was
+// down catches up one statement at a time.
+//
+// ============================================================================
+
+/// Background job name for execution records
+pub const CARD_STATEMENT_JOB: &str = "card_statements";
+
+/// Number of closed statements returned by the statements endpoint
+const STATEMENT_LIMIT: i64 = 24;
+
+/// Most statements one card closes per job run
+const MAX_CATCH_UP: usize = 12;
+
+const TERMS_COLUMNS: &str =
+    "wallet_id, user_id, statement_day, payment_due_days, apr, tracking_from, created_at, updated_at";
+
+const STATEMENT_COLUMNS: &str = "id, wallet_id, user_id, period_start, period_end, due_date, opening_balance, purchases, payments, interest, interest_free, closing_balance, paid_amount, paid_in_full, interest_transaction_id, created_at";
+
+/// What an expense adds to (and an income takes off) the amount owed
+const OWED_DELTA: &str = "CASE WHEN transaction_type = 'expense' THEN amount ELSE -amount END";
+
+// ==================== Handlers ====================
+
+/// Set or replace the statement terms of a credit-limit wallet
+///
+/// The first statement period starts when terms are first set; changing them
+/// later keeps the periods already closed.
+pub async fn set_card_terms(
+    path: web::Path<(String, String)>,
+    req: web::Json<SetCreditCardTermsRequest>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let (user_id, wallet_id) = path.into_inner();
+
+    if !(1..=28).contains(&req.statement_day) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<CreditCardTerms>::error("statement_day must be between 1 and 28".to_string()));
+    }
+    if !(1..=28).contains(&req.payment_due_days) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<CreditCardTerms>::error("payment_due_days must be between 1 and 28".to_string()));
+    }
+    if req.apr < BigDecimal::from(0) || req.apr > BigDecimal::from(100) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<CreditCardTerms>::error("apr must be between 0 and 100".to_string()));
+    }
+
+    if let Err(e) =
+        wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::Manage).await
+    {
+        return e.error_response();
+    }
+
+    let wallet = match fetch_wallet_by_id(db.get_ref(), &wallet_id, &user_id).await {
+        Ok(w) => w,
+        Err(sqlx::Error::RowNotFound) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<CreditCardTerms>::error("Wallet not found".to_string()));
+        }
+        Err(e) => {
+            log::error!("Error fetching wallet: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<CreditCardTerms>::error("Database error".to_string()));
+        }
+    };
+
+    match wallet_types::template_for(db.get_ref(), &wallet).await {
+        Ok(template) if template.supports_credit_limit => {}
+        Ok(_) => {
+            return HttpResponse::BadRequest()
+                .json(ApiResponse::<CreditCardTerms>::error("Wallet is not a credit card".to_string()));
+        }
+        Err(e) => {
+            log::error!("Error resolving wallet template: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<CreditCardTerms>::error("Database error".to_string()));
+        }
+    }
+
+    let result = sqlx::query_as::<_, CreditCardTerms>(&format!(
+        "INSERT INTO credit_card_terms (wallet_id, user_id, statement_day, payment_due_days, apr)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (wallet_id) DO UPDATE
+         SET statement_day = EXCLUDED.statement_day, payment_due_days = EXCLUDED.payment_due_days,
+             apr = EXCLUDED.apr, updated_at = CURRENT_TIMESTAMP
+         RETURNING {}",
+        TERMS_COLUMNS
+    ))
+    .bind(wallet.id)
+    .bind(&wallet.user_id)
+    .bind(req.statement_day)
+    .bind(req.payment_due_days)
+    .bind(&req.apr)
+    .fetch_one(db.get_ref())
+    .await;
+
+    match result {
+        Ok(terms) => {
+            log::info!(
+                target: "audit",
+                "card terms set wallet_id={} user_id={} statement_day={} payment_due_days={} apr={}",
+                terms.wallet_id,
+                terms.user_id,
+                terms.statement_day,
+                terms.payment_due_days,
+                terms.apr
+            );
+            HttpResponse::Ok().json(ApiResponse::success(terms))
+        }
+        Err(e) => {
+            log::error!("Error saving card terms: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<CreditCardTerms>::error("Failed to save card terms".to_string()))
+        }
+    }
+}
+
+/// Stop statement tracking on a card; closed statements are kept
+pub async fn delete_card_terms(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, wallet_id) = path.into_inner();
+    if let Err(e) =
+        wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::Manage).await
+    {
+        return e.error_response();
+    }
+
+    let result = sqlx::query("DELETE FROM credit_card_terms WHERE wallet_id::text = $1 AND user_id = $2")
+        .bind(&wallet_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await;
+
+    match result {
+        Ok(r) if r.rows_affected() > 0 => {
+            log::info!(target: "audit", "card terms removed wallet_id={} user_id={}", wallet_id, user_id);
+            HttpResponse::NoContent().finish()
+        }
+        Ok(_) => HttpResponse::NotFound().json(ApiResponse::<String>::error("Card terms not found".to_string())),
+        Err(e) => {
+            log::error!("Error deleting card terms: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<String>::error("Failed to delete card terms".to_string()))
+        }
+    }
+}
+
+/// Terms, the cycle in progress and the newest closed statements of a card
+pub async fn get_card_statements(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, wallet_id) = path.into_inner();
+    let access =
+        match wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::View).await {
+            Ok(access) => access,
+            Err(e) => return e.error_response(),
+        };
+
+    match fetch_card_statements(db.get_ref(), &access.owner_id, &wallet_id).await {
+        Ok(Some(statements)) => HttpResponse::Ok().json(ApiResponse::success(statements)),
+        Ok(None) => HttpResponse::NotFound()
+            .json(ApiResponse::<CreditCardStatements>::error("Card terms not found".to_string())),
+        Err(e) => {
+            log::error!("Error fetching card statements: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<CreditCardStatements>::error("Database error".to_string()))
+        }
+    }
+}
+
+// ==================== Statement Math ====================
+
+/// First statement date strictly after `after`
+fn next_statement_date(after: DateTime<Utc>, statement_day: i16) -> DateTime<Utc> {
+    let (mut year, mut month) = (after.year(), after.month());
+    loop {
+        let date = NaiveDate::from_ymd_opt(year, month, statement_day as u32).and_then(|d| d.and_hms_opt(0, 0, 0));
+        if let Some(date) = date
+            && date.and_utc() > after
+        {
+            return date.and_utc();
+        }
+        (year, month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    }
+}
+
+fn due_date(period_end: DateTime<Utc>, terms: &CreditCardTerms) -> DateTime<Utc> {
+    period_end + Duration::days(terms.payment_due_days as i64)
+}
+
+/// A transaction on the card: its date and what it added to the amount owed
+type CardMove = (DateTime<Utc>, BigDecimal);
+
+/// Purchases (expenses) and payments (income) among `moves`
+fn purchases_and_payments(moves: &[CardMove]) -> (BigDecimal, BigDecimal) {
+    let zero = BigDecimal::from(0);
+    moves.iter().fold((zero.clone(), zero.clone()), |(purchases, payments), (_, delta)| {
+        if *delta > zero {
+            (purchases + delta, payments)
+        } else {
+            (purchases, payments - delta)
+        }
+    })
+}
+
+/// Payments among `moves` made by `due_date`
+fn paid_by(moves: &[CardMove], due_date: DateTime<Utc>) -> BigDecimal {
+    let zero = BigDecimal::from(0);
+    moves
+        .iter()
+        .filter(|(at, delta)| *at <= due_date && *delta < zero)
+        .fold(zero.clone(), |paid, (_, delta)| paid - delta)
+}
+
+/// Interest on the daily balance owed over (`start`, `end`], rounded to cents
+fn period_interest(
+    opening: &BigDecimal,
+    moves: &[CardMove],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    apr: &BigDecimal,
+) -> BigDecimal {
+    let zero = BigDecimal::from(0);
+    let mut owed = opening.clone();
+    let mut at = start;
+    let mut owed_seconds = zero.clone();
+
+    for (moved_at, delta) in moves.iter().chain(std::iter::once(&(end, zero.clone()))) {
+        if owed > zero {
+            owed_seconds += &owed * BigDecimal::from((*moved_at - at).num_seconds());
+        }
+        owed += delta;
+        at = *moved_at;
+    }
+
+    (owed_seconds * apr / BigDecimal::from(100i64 * 365 * 86_400)).round(2)
+}
+
+// ==================== Statement Job ====================
+
+/// Close every card statement whose date has passed, charging interest where due
+///
+/// Each statement is closed in its own DB transaction with the wallet row
+/// locked (`FOR UPDATE SKIP LOCKED`), so a failure on one card doesn't block
+/// the rest. Returns the number of statements closed.
+pub async fn close_card_statements(
+    pool: &PgPool,
+    cache: &dyn CacheBackend,
+) -> Result<u64, sqlx::Error> {
+    let cards: Vec<(Uuid, DateTime<Utc>, i16)> = sqlx::query_as(
+        "SELECT t.wallet_id, GREATEST(t.tracking_from, COALESCE(MAX(s.period_end), t.tracking_from)), t.statement_day
+         FROM credit_card_terms t
+         LEFT JOIN credit_card_statements s ON s.wallet_id = t.wallet_id
+         GROUP BY t.wallet_id",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let now = Utc::now();
+    let mut closed = 0;
+    for (wallet_id, period_start, statement_day) in cards {
+        if next_statement_date(period_start, statement_day) > now {
+            continue;
+        }
+
+        let mut user_id = None;
+        for _ in 0..MAX_CATCH_UP {
+            match close_statement(pool, wallet_id).await {
+                Ok(Some(owner)) => {
+                    closed += 1;
+                    user_id = Some(owner);
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    log::error!("Failed to close statement for wallet {}: {}", wallet_id, e);
+                    break;
+                }
+            }
+        }
+
+        if let Some(user_id) = user_id {
+            let _ = invalidate_user_cache(cache, &user_id).await;
+        }
+    }
+
+    Ok(closed)
+}
+
+/// Close the oldest unclosed statement of one card if its date has passed
+///
+/// Returns the owning user id when a statement was closed, `None` if none is
+/// due yet or the card is being closed by a concurrent run.
+async fn close_statement(pool: &PgPool, wallet_id: Uuid) -> Result<Option<String>, sqlx::Error> {
+    let mut db_tx = pool.begin().await?;
+
+    let locked: Option<(String,)> = sqlx::query_as("SELECT user_id FROM wallets WHERE id = $1 AND deleted_at IS NULL FOR UPDATE SKIP LOCKED")
+        .bind(wallet_id)
+        .fetch_optional(&mut *db_tx)
+        .await?;
+    let terms = match locked {
+        Some(_) => fetch_terms(&mut db_tx, wallet_id).await?,
+        None => None,
+    };
+    let Some(terms) = terms else {
+        db_tx.rollback().await?;
+        return Ok(None);
+    };
+
+    let last = fetch_last_statement(&mut db_tx, wallet_id).await?;
+    let period_start = last
+        .as_ref()
+        .map_or(terms.tracking_from, |s| s.period_end.max(terms.tracking_from));
+    let period_end = next_statement_date(period_start, terms.statement_day);
+    if period_end > Utc::now() {
+        db_tx.rollback().await?;
+        return Ok(None);
+    }
+
+    let occurrence = format!("{}:{}", wallet_id, period_end.format("%Y-%m-%d"));
+    if !jobs::claim_occurrence(&mut db_tx, CARD_STATEMENT_JOB, &occurrence).await? {
+        db_tx.rollback().await?;
+        return Ok(None);
+    }
+
+    let opening = owed_at(&mut db_tx, wallet_id, period_start).await?;
+    let moves = fetch_moves(&mut db_tx, wallet_id, period_start, period_end).await?;
+    let (purchases, payments) = purchases_and_payments(&moves);
+
+    // Settle the previous statement: paid in full by its due date?
+    let mut interest_free = true;
+    let mut interest = BigDecimal::from(0);
+    if let Some(last) = last.as_ref().filter(|s| s.period_end == period_start) {
+        let paid = paid_by(&moves, last.due_date);
+        let paid_in_full = paid >= last.closing_balance;
+
+        sqlx::query("UPDATE credit_card_statements SET paid_amount = $1, paid_in_full = $2 WHERE id = $3")
+            .bind(&paid)
+            .bind(paid_in_full)
+            .bind(last.id)
+            .execute(&mut *db_tx)
+            .await?;
+
+        if !paid_in_full {
+            interest_free = false;
+            interest = period_interest(&opening, &moves, period_start, period_end, &terms.apr);
+            // Its purchases lose the interest-free period retroactively
+            if last.interest_free {
+                let last_moves = fetch_moves(&mut db_tx, wallet_id, last.period_start, last.period_end).await?;
+                interest += period_interest(
+                    &last.opening_balance,
+                    &last_moves,
+                    last.period_start,
+                    last.period_end,
+                    &terms.apr,
+                );
+            }
+        }
+    }
+
+    let mut interest_transaction_id = None;
+    if interest > BigDecimal::from(0) {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO transactions (id, user_id, wallet_id, amount, transaction_type, category, description, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, 'expense', $5, $6, $7, $7)",
+        )
+        .bind(id)
+        .bind(&terms.user_id)
+        .bind(wallet_id)
+        .bind(&interest)
+        .bind(INTEREST_CATEGORY)
+        .bind(format!("Card interest ({}% APR)", terms.apr))
+        .bind(period_end)
+        .execute(&mut *db_tx)
+        .await?;
+
+        sqlx::query("UPDATE wallets SET balance = balance - $1 WHERE id = $2")
+            .bind(&interest)
+            .bind(wallet_id)
+            .execute(&mut *db_tx)
+            .await?;
+        interest_transaction_id = Some(id);
+    }
+
+    let closing = &opening + &purchases - &payments + &interest;
+    sqlx::query(
+        "INSERT INTO credit_card_statements
+             (wallet_id, user_id, period_start, period_end, due_date, opening_balance, purchases, payments,
+              interest, interest_free, closing_balance, interest_transaction_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)",
+    )
+    .bind(wallet_id)
+    .bind(&terms.user_id)
+    .bind(period_start)
+    .bind(period_end)
+    .bind(due_date(period_end, &terms))
+    .bind(&opening)
+    .bind(&purchases)
+    .bind(&payments)
+    .bind(&interest)
+    .bind(interest_free)
+    .bind(&closing)
+    .bind(interest_transaction_id)
+    .execute(&mut *db_tx)
+    .await?;
+
+    db_tx.commit().await?;
+
+    log::info!(
+        "Closed statement {} for wallet {}: balance {}, interest {}",
+        period_end.format("%Y-%m-%d"),
+        wallet_id,
+        closing,
+        interest
+    );
+    Ok(Some(terms.user_id))
+}
+
+// ==================== Database Functions ====================
+
+async fn fetch_terms(conn: &mut PgConnection, wallet_id: Uuid) -> Result<Option<CreditCardTerms>, sqlx::Error> {
+    sqlx::query_as::<_, CreditCardTerms>(&format!("SELECT {} FROM credit_card_terms WHERE wallet_id = $1", TERMS_COLUMNS))
+        .bind(wallet_id)
+        .fetch_optional(conn)
+        .await
+}
+
+async fn fetch_last_statement(
+    conn: &mut PgConnection,
+    wallet_id: Uuid,
+) -> Result<Option<CreditCardStatement>, sqlx::Error> {
+    sqlx::query_as::<_, CreditCardStatement>(&format!(
+        "SELECT {} FROM credit_card_statements WHERE wallet_id = $1 ORDER BY period_end DESC LIMIT 1",
+        STATEMENT_COLUMNS
+    ))
+    .bind(wallet_id)
+    .fetch_optional(conn)
+    .await
+}
+
+/// Amount owed on the card as of `at`
+async fn owed_at(conn: &mut PgConnection, wallet_id: Uuid, at: DateTime<Utc>) -> Result<BigDecimal, sqlx::Error> {
+    let (owed,): (BigDecimal,) = sqlx::query_as(&format!(
+        "SELECT COALESCE(SUM({}), 0) FROM transactions WHERE wallet_id = $1 AND deleted_at IS NULL AND created_at <= $2",
+        OWED_DELTA
+    ))
+    .bind(wallet_id)
+    .bind(at)
+    .fetch_one(conn)
+    .await?;
+    Ok(owed)
+}
+
+/// Card transactions in (`start`, `end`], oldest first
+async fn fetch_moves(
+    conn: &mut PgConnection,
+    wallet_id: Uuid,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<CardMove>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT created_at, {} FROM transactions
+         WHERE wallet_id = $1 AND deleted_at IS NULL AND created_at > $2 AND created_at <= $3
+         ORDER BY created_at, id",
+        OWED_DELTA
+    ))
+    .bind(wallet_id)
+    .bind(start)
+    .bind(end)
+    .fetch_all(conn)
+    .await
+}
+
+async fn fetch_card_statements(
+    pool: &PgPool,
+    user_id: &str,
+    wallet_id: &str,
+) -> Result<Option<CreditCardStatements>, sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+
+    let terms = sqlx::query_as::<_, CreditCardTerms>(&format!(
+        "SELECT {} FROM credit_card_terms WHERE wallet_id::text = $1 AND user_id = $2",
+        TERMS_COLUMNS
+    ))
+    .bind(wallet_id)
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(terms) = terms else {
+        return Ok(None);
+    };
+
+    let statements = sqlx::query_as::<_, CreditCardStatement>(&format!(
+        "SELECT {} FROM credit_card_statements WHERE wallet_id = $1 ORDER BY period_end DESC LIMIT $2",
+        STATEMENT_COLUMNS
+    ))
+    .bind(terms.wallet_id)
+    .bind(STATEMENT_LIMIT)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let now = Utc::now();
+    let last = statements.first();
+    let period_start = last.map_or(terms.tracking_from, |s| s.period_end.max(terms.tracking_from));
+    let period_end = next_statement_date(period_start, terms.statement_day);
+    let moves = fetch_moves(&mut conn, terms.wallet_id, period_start, now).await?;
+    let (purchases, payments) = purchases_and_payments(&moves);
+
+    // The cycle keeps its interest-free period until the last statement is
+    // past due without having been paid in full
+    let interest_free = match last.filter(|s| s.period_end == period_start) {
+        None => true,
+        Some(last) => {
+            paid_by(&moves, last.due_date) >= last.closing_balance || now <= last.due_date
+        }
+    };
+
+    let current = CreditCardCycle {
+        period_start,
+        period_end,
+        due_date: due_date(period_end, &terms),
+        balance: owed_at(&mut conn, terms.wallet_id, now).await?,
+        purchases,
+        payments,
+        interest_free,
+    };
+
+    Ok(Some(CreditCardStatements { terms, current, statements }))
+}
@@ -0,0 +1,482 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use actix_web::http::Method;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use serde::Deserialize;
+use sqlx::types::BigDecimal;
+use sqlx::{PgExecutor, PgPool};
+
+use crate::admin::authorize_admin;
+use crate::cache::{invalidate_user_cache, CacheBackend};
+use crate::config::AppConfig;
+use crate::email_templates::normalize_locale;
+use crate::models::{
+    ApiResponse, Currency, CurrencySettings, ExchangeRate, ExchangeRateUpdate, UpdateCurrencySettingsRequest,
+    UpsertExchangeRatesRequest,
+};
+use crate::money;
+use crate::routes::ScopedRoutes;
+
+// ==================== CURRENCIES ====================
+//
+// Every wallet holds one currency (`wallets.currency`, fixed at creation,
+// default the user's base currency), and its balance, credit limit and
+// transactions are amounts in that currency. The base currency is the one the
+// user set, else the currency of their locale (`locale_currency` in SQL),
+// else VND. Transactions copy their wallet's
+// currency on insert so aggregates can convert without a join.
+//
+// Rates live in `exchange_rates` (1 `base` = `rate` `quote`). The SQL function
+// `exchange_rate(from, to)` uses the direct rate, the inverse one, or a cross
+// rate through a common base, so a provider quoting everything against USD
+// is enough for any pair. Rates are set by operators
+// (`PUT /api/admin/exchange-rates`) or pulled from `EXCHANGE_RATE_PROVIDER_URL`
+// by the refresh job, which expects `{"base": "USD", "rates": {"VND": ...}}`.
+//
+// Conversion happens at the current rate, rounded to cents:
+//
+// - Transfers between wallets of different currencies debit the amount in the
+//   source currency and credit the converted amount (standing orders, top-ups
+//   and pending transfers too); without a rate they are refused.
+// - Reports, tax and income summaries and net worth are in the user's base
+//   currency (`user_currency_settings`, default VND) and answer 409 while a
+//   wallet's currency has no rate to it. Budgets are in the base currency
+//   too; expenses without a rate are left out of their totals.
+//
+// Past amounts are converted at today's rate, not the rate of their day.
+//
+// ============================================================================
+
+/// Background job name (advisory lock and execution records)
+pub const RATE_JOB: &str = "exchange_rate_refresh";
+
+/// Most rates accepted by one upsert
+const MAX_RATES_PER_REQUEST: usize = 200;
+
+/// How long a provider request may take
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(15);
+
+const RATE_COLUMNS: &str = "base, quote, rate, source, updated_at";
+
+// ==================== Conversion ====================
+
+/// An amount converted to another currency, with the rate used
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    pub amount: BigDecimal,
+    pub rate: BigDecimal,
+}
+
+/// `amount` in `from` converted to `to` at the current rate; `None` without a rate
+pub async fn convert(
+    executor: impl PgExecutor<'_>,
+    amount: &BigDecimal,
+    from: &str,
+    to: &str,
+) -> Result<Option<Conversion>, sqlx::Error> {
+    let (rate,): (Option<BigDecimal>,) = sqlx::query_as("SELECT exchange_rate($1, $2)")
+        .bind(from)
+        .bind(to)
+        .fetch_one(executor)
+        .await?;
+    Ok(rate.map(|rate| Conversion {
+        amount: money::round_cents(&(amount * &rate)),
+        rate,
+    }))
+}
+
+/// Message for a conversion without a rate
+pub fn no_rate(from: &str, to: &str) -> String {
+    format!("No exchange rate from {} to {}", from, to)
+}
+
+/// The user's base currency, or why their wallets can't all be converted to it
+pub async fn reporting_currency(
+    executor: impl PgExecutor<'_>,
+    user_id: &str,
+) -> Result<Result<String, String>, sqlx::Error> {
+    let (base, missing): (String, Option<String>) = sqlx::query_as(
+        "SELECT base_currency($1),
+                (SELECT MIN(currency) FROM wallets
+                 WHERE user_id = $1 AND deleted_at IS NULL AND exchange_rate(currency, base_currency($1)) IS NULL)",
+    )
+    .bind(user_id)
+    .fetch_one(executor)
+    .await?;
+    Ok(match missing {
+        Some(currency) => Err(no_rate(&currency, &base)),
+        None => Ok(base),
+    })
+}
+
+/// Whether `code` is a supported currency
+pub async fn is_supported(executor: impl PgExecutor<'_>, code: &str) -> Result<bool, sqlx::Error> {
+    let found: Option<(String,)> = sqlx::query_as("SELECT code FROM currencies WHERE code = $1")
+        .bind(code)
+        .fetch_optional(executor)
+        .await?;
+    Ok(found.is_some())
+}
+
+/// Currency code as stored (`usd` -> `USD`)
+pub fn normalize_code(code: &str) -> String {
+    code.trim().to_ascii_uppercase()
+}
+
+/// The supported currency of `locale`'s region, or of its language
+async fn locale_currency(executor: impl PgExecutor<'_>, locale: &str) -> Result<Option<String>, sqlx::Error> {
+    let (currency,): (Option<String>,) = sqlx::query_as("SELECT locale_currency($1)")
+        .bind(locale)
+        .fetch_one(executor)
+        .await?;
+    Ok(currency)
+}
+
+// ==================== Handlers ====================
+
+/// List the supported currencies with their symbols and minor units
+pub async fn get_currencies(db: web::Data<PgPool>) -> HttpResponse {
+    let result = sqlx::query_as::<_, Currency>("SELECT code, name, symbol, minor_units FROM currencies ORDER BY code")
+        .fetch_all(db.get_ref())
+        .await;
+
+    match result {
+        Ok(currencies) => HttpResponse::Ok().json(ApiResponse::success(currencies)),
+        Err(e) => {
+            log::error!("Error fetching currencies: {}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<Vec<Currency>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// List the stored exchange rates
+pub async fn get_exchange_rates(db: web::Data<PgPool>) -> HttpResponse {
+    match fetch_rates(db.get_ref()).await {
+        Ok(rates) => HttpResponse::Ok().json(ApiResponse::success(rates)),
+        Err(e) => {
+            log::error!("Error fetching exchange rates: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<ExchangeRate>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// A user's base currency
+pub async fn get_currency_settings(user_id: web::Path<String>, db: web::Data<PgPool>) -> HttpResponse {
+    let user_id = user_id.into_inner();
+
+    let result = sqlx::query_as::<_, CurrencySettings>("SELECT $1::text AS user_id, base_currency($1) AS base_currency,
+                (SELECT locale FROM user_currency_settings WHERE user_id = $1) AS locale")
+        .bind(&user_id)
+        .fetch_one(db.get_ref())
+        .await;
+
+    match result {
+        Ok(settings) => HttpResponse::Ok().json(ApiResponse::success(settings)),
+        Err(e) => {
+            log::error!("Error fetching currency settings: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<CurrencySettings>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Change the currency a user's reports and net worth are shown in, or the
+/// locale it follows
+///
+/// A `locale` without `base_currency` clears the explicit currency, so the
+/// locale's currency is used; it must map to a supported currency.
+pub async fn update_currency_settings(
+    user_id: web::Path<String>,
+    req: web::Json<UpdateCurrencySettingsRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    let base_currency = req.base_currency.as_deref().map(normalize_code);
+    let locale = match req.locale.as_deref().map(normalize_locale) {
+        None => None,
+        Some(Some(locale)) => Some(locale),
+        Some(None) => {
+            return HttpResponse::BadRequest()
+                .json(ApiResponse::<CurrencySettings>::error("Invalid locale".to_string()));
+        }
+    };
+    if base_currency.is_none() && locale.is_none() {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<CurrencySettings>::error("Set base_currency or locale".to_string()));
+    }
+
+    if let Some(base_currency) = &base_currency {
+        match is_supported(db.get_ref(), base_currency).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return HttpResponse::BadRequest()
+                    .json(ApiResponse::<CurrencySettings>::error(format!("Unsupported currency {}", base_currency)));
+            }
+            Err(e) => {
+                log::error!("Error checking currency: {}", e);
+                return HttpResponse::InternalServerError()
+                    .json(ApiResponse::<CurrencySettings>::error("Database error".to_string()));
+            }
+        }
+    } else if let Some(locale) = &locale {
+        match locale_currency(db.get_ref(), locale).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return HttpResponse::BadRequest().json(ApiResponse::<CurrencySettings>::error(format!(
+                    "No supported currency for locale {}; set base_currency",
+                    locale
+                )));
+            }
+            Err(e) => {
+                log::error!("Error checking locale currency: {}", e);
+                return HttpResponse::InternalServerError()
+                    .json(ApiResponse::<CurrencySettings>::error("Database error".to_string()));
+            }
+        }
+    }
+
+    let result = sqlx::query_as::<_, CurrencySettings>(
+        "INSERT INTO user_currency_settings (user_id, base_currency, locale, updated_at)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (user_id) DO UPDATE
+         SET base_currency = EXCLUDED.base_currency,
+             locale = COALESCE(EXCLUDED.locale, user_currency_settings.locale),
+             updated_at = EXCLUDED.updated_at
+         RETURNING user_id, COALESCE(base_currency, locale_currency(locale), 'VND') AS base_currency, locale",
+    )
+    .bind(&user_id)
+    .bind(&base_currency)
+    .bind(&locale)
+    .bind(Utc::now())
+    .fetch_one(db.get_ref())
+    .await;
+
+    match result {
+        Ok(settings) => {
+            let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+            HttpResponse::Ok().json(ApiResponse::success(settings))
+        }
+        Err(e) => {
+            log::error!("Error updating currency settings: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<CurrencySettings>::error("Failed to update currency settings".to_string()))
+        }
+    }
+}
+
+/// Set exchange rates by hand
+///
+/// `PUT /api/admin/exchange-rates`
+pub async fn upsert_exchange_rates(
+    http_req: HttpRequest,
+    req: web::Json<UpsertExchangeRatesRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    if req.rates.is_empty() || req.rates.len() > MAX_RATES_PER_REQUEST {
+        return HttpResponse::BadRequest().json(ApiResponse::<ExchangeRateUpdate>::error(format!(
+            "rates must have 1 to {} entries",
+            MAX_RATES_PER_REQUEST
+        )));
+    }
+    let mut rates = Vec::with_capacity(req.rates.len());
+    for input in &req.rates {
+        let (base, quote) = (normalize_code(&input.base), normalize_code(&input.quote));
+        if base == quote {
+            return HttpResponse::BadRequest().json(ApiResponse::<ExchangeRateUpdate>::error(format!(
+                "Rate of {} to itself is always 1",
+                base
+            )));
+        }
+        if input.rate <= BigDecimal::from(0) {
+            return HttpResponse::BadRequest().json(ApiResponse::<ExchangeRateUpdate>::error(format!(
+                "Rate {} to {} must be greater than 0",
+                base, quote
+            )));
+        }
+        rates.push((base, quote, input.rate.clone()));
+    }
+
+    match store_rates(db.get_ref(), &rates, "manual").await {
+        Ok(Ok(update)) => {
+            log::info!(target: "audit", "Admin set {} exchange rate(s)", update.updated);
+            HttpResponse::Ok().json(ApiResponse::success(update))
+        }
+        Ok(Err(msg)) => HttpResponse::BadRequest().json(ApiResponse::<ExchangeRateUpdate>::error(msg)),
+        Err(e) => {
+            log::error!("Error storing exchange rates: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<ExchangeRateUpdate>::error("Failed to store exchange rates".to_string()))
+        }
+    }
+}
+
+/// Pull rates from the configured provider now
+///
+/// `POST /api/admin/exchange-rates/refresh`
+pub async fn refresh_exchange_rates(
+    req: HttpRequest,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    let Some(url) = &config.exchange_rate_provider_url else {
+        return HttpResponse::BadRequest().json(ApiResponse::<ExchangeRateUpdate>::error(
+            "No exchange rate provider is configured".to_string(),
+        ));
+    };
+
+    match refresh_from_provider(db.get_ref(), url).await {
+        Ok(update) => HttpResponse::Ok().json(ApiResponse::success(update)),
+        Err(RefreshError::Provider(e)) => {
+            log::warn!("Exchange rate provider failed: {}", e);
+            HttpResponse::BadGateway()
+                .json(ApiResponse::<ExchangeRateUpdate>::error(format!("Exchange rate provider failed: {}", e)))
+        }
+        Err(RefreshError::Database(e)) => {
+            log::error!("Error storing exchange rates: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<ExchangeRateUpdate>::error("Failed to store exchange rates".to_string()))
+        }
+    }
+}
+
+// ==================== Rate Provider ====================
+
+#[derive(Debug)]
+pub enum RefreshError {
+    /// The provider was unreachable or answered something unusable
+    Provider(String),
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for RefreshError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RefreshError::Provider(e) => write!(f, "Exchange rate provider failed: {}", e),
+            RefreshError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+/// Provider response: 1 `base` = `rates[code]` of each currency
+#[derive(Deserialize)]
+struct ProviderRates {
+    #[serde(alias = "base_code")]
+    base: String,
+    rates: HashMap<String, serde_json::Number>,
+}
+
+/// Fetch the provider's rates and store those between supported currencies
+pub async fn refresh_from_provider(pool: &PgPool, url: &str) -> Result<ExchangeRateUpdate, RefreshError> {
+    let client = reqwest::Client::builder()
+        .timeout(PROVIDER_TIMEOUT)
+        .build()
+        .map_err(|e| RefreshError::Provider(e.to_string()))?;
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| RefreshError::Provider(e.to_string()))?;
+    let body: ProviderRates = response.json().await.map_err(|e| RefreshError::Provider(e.to_string()))?;
+
+    let supported: Vec<(String,)> =
+        sqlx::query_as("SELECT code FROM currencies").fetch_all(pool).await.map_err(RefreshError::Database)?;
+    let supported: Vec<String> = supported.into_iter().map(|(code,)| code).collect();
+
+    let base = normalize_code(&body.base);
+    if !supported.contains(&base) {
+        return Err(RefreshError::Provider(format!("Unsupported base currency {}", base)));
+    }
+    let rates: Vec<(String, String, BigDecimal)> = body
+        .rates
+        .iter()
+        .filter_map(|(code, rate)| {
+            let quote = normalize_code(code);
+            let rate = BigDecimal::from_str(&rate.to_string()).ok()?;
+            (quote != base && supported.contains(&quote) && rate > BigDecimal::from(0)).then(|| (base.clone(), quote, rate))
+        })
+        .collect();
+    if rates.is_empty() {
+        return Err(RefreshError::Provider("No usable rates in the response".to_string()));
+    }
+
+    match store_rates(pool, &rates, "provider").await.map_err(RefreshError::Database)? {
+        Ok(update) => Ok(update),
+        Err(msg) => Err(RefreshError::Provider(msg)),
+    }
+}
+
+// ==================== Database Functions ====================
+
+async fn fetch_rates(executor: impl PgExecutor<'_>) -> Result<Vec<ExchangeRate>, sqlx::Error> {
+    sqlx::query_as::<_, ExchangeRate>(&format!("SELECT {} FROM exchange_rates ORDER BY base, quote", RATE_COLUMNS))
+        .fetch_all(executor)
+        .await
+}
+
+/// Upsert `(base, quote, rate)` rows in one transaction; `Err` names an unsupported currency
+async fn store_rates(
+    pool: &PgPool,
+    rates: &[(String, String, BigDecimal)],
+    source: &str,
+) -> Result<Result<ExchangeRateUpdate, String>, sqlx::Error> {
+    let mut db_tx = pool.begin().await?;
+    let now = Utc::now();
+    let mut updated = 0;
+
+    for (base, quote, rate) in rates {
+        for code in [base, quote] {
+            if !is_supported(&mut *db_tx, code).await? {
+                return Ok(Err(format!("Unsupported currency {}", code)));
+            }
+        }
+        updated += sqlx::query(
+            "INSERT INTO exchange_rates (base, quote, rate, source, updated_at)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (base, quote) DO UPDATE
+                 SET rate = EXCLUDED.rate, source = EXCLUDED.source, updated_at = EXCLUDED.updated_at",
+        )
+        .bind(base)
+        .bind(quote)
+        .bind(rate)
+        .bind(source)
+        .bind(now)
+        .execute(&mut *db_tx)
+        .await?
+        .rows_affected();
+    }
+
+    let rates = fetch_rates(&mut *db_tx).await?;
+    db_tx.commit().await?;
+    Ok(Ok(ExchangeRateUpdate { updated, rates }))
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/currencies")
+        .public("", get_currencies)
+        .public("/rates", get_exchange_rates)
+        .user(Method::GET, "/user/{user_id}", get_currency_settings)
+        .user(Method::PUT, "/user/{user_id}", update_currency_settings)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+
+use crate::budgets;
+use crate::cache::{self, decode_value, encode_value, user_cache_version, CacheBackend};
+use crate::currencies;
+use crate::error::AppError;
+use crate::hooks::{
+    DebtPaid, Hook, HookContext, TransactionCreated, TransactionDeleted, TransactionRestored, TransactionUpdated,
+    WalletsDebited,
+};
+use crate::models::{
+    ApiResponse, BudgetPeriod, Dashboard, DashboardBalances, DashboardWallet, MonthTotals, UpcomingItem,
+};
+use crate::routes::ScopedRoutes;
+use crate::standing_orders::TRANSFER_CATEGORY;
+use crate::telemetry::{self, CacheOutcome};
+use crate::wallet_types;
+use crate::wallets;
+
+// ==================== DASHBOARD ====================
+//
+// `GET /api/summary/{user_id}` is the home screen: wallet balances, this
+// month's income and expenses, budget statuses, and what falls due within
+// `UPCOMING_DAYS`. It is served from a projection kept in one Redis hash per
+// user, `user:{user_id}:dashboard`, with a field per section (`balances`,
+// `month_to_date`, `budgets`, `upcoming`) plus `as_of` and `version`:
+//
+// - A read returns the hash when its `version` is the user's current cache
+//   namespace version (see cache.rs). Otherwise, or when a field is missing,
+//   the dashboard is read from the database and the hash rewritten.
+// - Every write to the user's data bumps that version, so a projection is
+//   never served after a change. For the changes that move balances
+//   (transactions, transfers, imports, debt payments), `DashboardHook`
+//   rebuilds an existing projection right after the change, so the next
+//   read is still a single cache read.
+// - The version is read before the database, so a projection never claims a
+//   version whose change it hasn't seen; a change racing a rebuild only
+//   leaves the hash one version behind, and the next read rebuilds it.
+// - The hash expires at the next UTC midnight, when months, budget weeks and
+//   due dates roll over, and at most `MAX_AGE` after it was built, which
+//   also bounds how long a new exchange rate goes unseen.
+//
+// Values are sealed like other cache entries when CACHE_ENCRYPTION_KEYS is
+// set. Without a shared cache, and for `consistency=strong` reads, the
+// dashboard is read from the database every time.
+//
+// ============================================================================
+
+/// Longest a projection is kept
+const MAX_AGE: Duration = Duration::hours(1);
+
+/// How far ahead upcoming items are listed (overdue ones are always listed)
+const UPCOMING_DAYS: i64 = 14;
+
+/// Maximum number of upcoming items
+const MAX_UPCOMING: i64 = 50;
+
+const VERSION_FIELD: &str = "version";
+
+fn projection_key(user_id: &str) -> String {
+    format!("user:{}:dashboard", user_id)
+}
+
+// ==================== Handlers ====================
+
+/// A user's dashboard, from the projection when it is current
+pub async fn get_summary(
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = user_id.into_inner();
+    let cache = cache.get_ref();
+
+    if !cache.is_shared() || cache::is_strong_read() {
+        let dashboard = fetch_dashboard(db.get_ref(), &user_id).await?;
+        return Ok(HttpResponse::Ok().json(ApiResponse::success(dashboard)));
+    }
+
+    let version = user_cache_version(cache, &user_id).await;
+    let projected = telemetry::cache("dashboard", "get", async {
+        match read_projection(cache, &user_id, version).await {
+            Some(dashboard) => (Some(dashboard), CacheOutcome::Hit),
+            None => (None, CacheOutcome::Miss),
+        }
+    })
+    .await;
+
+    let dashboard = match projected {
+        Some(dashboard) => dashboard,
+        None => {
+            let dashboard = fetch_dashboard(db.get_ref(), &user_id).await?;
+            write_projection(cache, &user_id, version, &dashboard).await;
+            dashboard
+        }
+    };
+    Ok(HttpResponse::Ok().json(ApiResponse::success(dashboard)))
+}
+
+// ==================== Projection ====================
+
+/// The projection of `user_id` if it is complete and at `version`
+async fn read_projection(cache: &dyn CacheBackend, user_id: &str, version: u64) -> Option<Dashboard> {
+    let key = projection_key(user_id);
+    let fields = match cache.hgetall(&key).await {
+        Ok(fields) => fields,
+        Err(e) => {
+            log::warn!("Cache read failed for key {}: {}. Reading the database.", key, e);
+            return None;
+        }
+    };
+    if fields.get(VERSION_FIELD)?.parse::<u64>().ok()? != version {
+        return None;
+    }
+    Some(Dashboard {
+        balances: open_field(&key, &fields, "balances")?,
+        month_to_date: open_field(&key, &fields, "month_to_date")?,
+        budgets: open_field(&key, &fields, "budgets")?,
+        upcoming: open_field(&key, &fields, "upcoming")?,
+        as_of: open_field(&key, &fields, "as_of")?,
+    })
+}
+
+/// Replace the projection of `user_id` with `dashboard`, read at `version`
+///
+/// Failures are logged; the next read rebuilds it.
+async fn write_projection(cache: &dyn CacheBackend, user_id: &str, version: u64, dashboard: &Dashboard) {
+    let key = projection_key(user_id);
+    let fields: Option<Vec<(String, String)>> = [
+        Some((VERSION_FIELD.to_string(), version.to_string())),
+        seal_field(&key, "balances", &dashboard.balances),
+        seal_field(&key, "month_to_date", &dashboard.month_to_date),
+        seal_field(&key, "budgets", &dashboard.budgets),
+        seal_field(&key, "upcoming", &dashboard.upcoming),
+        seal_field(&key, "as_of", &dashboard.as_of),
+    ]
+    .into_iter()
+    .collect();
+    let Some(fields) = fields else {
+        return;
+    };
+
+    let midnight = (dashboard.as_of.date_naive() + Duration::days(1)).and_time(NaiveTime::MIN).and_utc();
+    let expires_at = midnight.min(dashboard.as_of + MAX_AGE);
+    let ttl_secs = (expires_at - Utc::now()).num_seconds().max(1) as u64;
+
+    let written = telemetry::cache("dashboard", "set", async {
+        let result = cache.hset_ex(&key, &fields, ttl_secs).await;
+        let outcome = if result.is_ok() { CacheOutcome::Write } else { CacheOutcome::Error };
+        (result, outcome)
+    })
+    .await;
+    if let Err(e) = written {
+        log::warn!("Cache write failed for key {}: {}", key, e);
+    }
+}
+
+/// A section serialized (and sealed) for its field
+fn seal_field<T: Serialize>(key: &str, field: &str, value: &T) -> Option<(String, String)> {
+    let json = serde_json::to_string(value).ok()?;
+    let stored = encode_value(&format!("{}:{}", key, field), json)?;
+    Some((field.to_string(), stored))
+}
+
+fn open_field<T: DeserializeOwned>(key: &str, fields: &HashMap<String, String>, field: &str) -> Option<T> {
+    let json = decode_value(&format!("{}:{}", key, field), fields.get(field)?)?;
+    serde_json::from_str(&json).ok()
+}
+
+// ==================== Hook ====================
+
+/// Rebuilds the projection of the user whose balances a change moved, if
+/// they have one
+pub struct DashboardHook;
+
+#[async_trait]
+impl Hook for DashboardHook {
+    fn name(&self) -> &'static str {
+        "dashboard"
+    }
+
+    async fn on_transaction_created(&self, cx: &HookContext<'_>, event: &TransactionCreated<'_>) {
+        refresh(cx, &event.transaction.user_id).await;
+    }
+
+    async fn on_transaction_updated(&self, cx: &HookContext<'_>, event: &TransactionUpdated<'_>) {
+        refresh(cx, &event.after.user_id).await;
+    }
+
+    async fn on_transaction_deleted(&self, cx: &HookContext<'_>, event: &TransactionDeleted<'_>) {
+        refresh(cx, &event.transaction.user_id).await;
+    }
+
+    async fn on_transaction_restored(&self, cx: &HookContext<'_>, event: &TransactionRestored<'_>) {
+        refresh(cx, &event.transaction.user_id).await;
+    }
+
+    async fn on_wallets_debited(&self, cx: &HookContext<'_>, event: &WalletsDebited<'_>) {
+        refresh(cx, event.owner_id).await;
+    }
+
+    async fn on_debt_paid(&self, cx: &HookContext<'_>, event: &DebtPaid<'_>) {
+        refresh(cx, &event.debt.user_id).await;
+    }
+}
+
+async fn refresh(cx: &HookContext<'_>, user_id: &str) {
+    if !cx.cache.is_shared() {
+        return;
+    }
+    // Users who haven't opened the dashboard lately get it built on their next read
+    match cx.cache.ttl(&projection_key(user_id)).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return,
+        Err(e) => {
+            log::warn!("Failed to look up the dashboard of {}: {}", user_id, e);
+            return;
+        }
+    }
+
+    let version = user_cache_version(cx.cache, user_id).await;
+    match fetch_dashboard(cx.pool, user_id).await {
+        Ok(dashboard) => write_projection(cx.cache, user_id, version, &dashboard).await,
+        Err(e) => log::error!("Error rebuilding the dashboard of {}: {}", user_id, e),
+    }
+}
+
+// ==================== Database Functions ====================
+
+async fn fetch_dashboard(pool: &PgPool, user_id: &str) -> Result<Dashboard, sqlx::Error> {
+    let now = Utc::now();
+    Ok(Dashboard {
+        balances: fetch_balances(pool, user_id).await?,
+        month_to_date: fetch_month_totals(pool, user_id, now).await?,
+        budgets: budgets::current_statuses(pool, user_id, now).await?,
+        upcoming: fetch_upcoming(pool, user_id, now).await?,
+        as_of: now,
+    })
+}
+
+/// The user's wallets; balances without a rate to the base currency are
+/// left out of the totals
+async fn fetch_balances(pool: &PgPool, user_id: &str) -> Result<DashboardBalances, sqlx::Error> {
+    let (currency,): (String,) = sqlx::query_as("SELECT base_currency($1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    let mut balances = DashboardBalances {
+        currency,
+        assets: BigDecimal::from(0),
+        liabilities: BigDecimal::from(0),
+        wallets: Vec::new(),
+    };
+
+    // Sandbox wallets hold an integrator's test data (see api_keys.rs)
+    for wallet in wallets::fetch_wallets_from_db(pool, user_id).await?.into_iter().filter(|w| !w.sandbox) {
+        let template = wallet_types::template_for(pool, &wallet).await?;
+        if let Some(conversion) = currencies::convert(pool, &wallet.balance, &wallet.currency, &balances.currency).await? {
+            if template.is_liability() {
+                balances.liabilities += conversion.amount;
+            } else {
+                balances.assets += conversion.amount;
+            }
+        }
+        let available_credit = match &wallet.credit_limit {
+            Some(limit) if template.supports_credit_limit => Some(limit - &wallet.balance),
+            _ => None,
+        };
+        balances.wallets.push(DashboardWallet {
+            wallet_id: wallet.id,
+            name: wallet.name,
+            currency: wallet.currency,
+            balance: wallet.balance,
+            available_credit,
+            liability: template.is_liability(),
+        });
+    }
+    Ok(balances)
+}
+
+async fn fetch_month_totals(pool: &PgPool, user_id: &str, now: DateTime<Utc>) -> Result<MonthTotals, sqlx::Error> {
+    let (month_start, _) = budgets::period_bounds(BudgetPeriod::Monthly, now);
+    sqlx::query_as::<_, MonthTotals>(
+        "SELECT $2::timestamptz AS month_start,
+                COALESCE(SUM(convert_amount(amount, currency, base_currency($1)))
+                         FILTER (WHERE transaction_type = 'income'), 0) AS income,
+                COALESCE(SUM(convert_amount(amount, currency, base_currency($1)))
+                         FILTER (WHERE transaction_type = 'expense'), 0) AS expense,
+                COUNT(*) AS transaction_count
+         FROM transactions
+         WHERE user_id = $1 AND deleted_at IS NULL AND NOT sandbox AND created_at >= $2 AND category <> $3",
+    )
+    .bind(user_id)
+    .bind(month_start)
+    .bind(TRANSFER_CATEGORY)
+    .fetch_one(pool)
+    .await
+}
+
+/// Scheduled transactions, installments and debts due before `UPCOMING_DAYS`
+/// from `now`, soonest (or most overdue) first
+async fn fetch_upcoming(pool: &PgPool, user_id: &str, now: DateTime<Utc>) -> Result<Vec<UpcomingItem>, sqlx::Error> {
+    sqlx::query_as::<_, UpcomingItem>(
+        "SELECT kind, id, name, due_at, amount, wallet_id FROM (
+             SELECT 'recurring' AS kind, id, name, next_run_at AS due_at, amount, wallet_id
+             FROM recurring_transactions
+             WHERE user_id = $1 AND active AND next_run_at < $2
+             UNION ALL
+             SELECT 'standing_order', id, name, next_run_at, amount, from_wallet_id
+             FROM standing_orders
+             WHERE user_id = $1 AND active AND next_run_at < $2
+             UNION ALL
+             SELECT 'installment', id, COALESCE(description, 'Installment'), next_due_at,
+                    LEAST(installment_amount, total_amount - billed), wallet_id
+             FROM installment_plans
+             WHERE user_id = $1 AND status = 'active' AND next_due_at < $2
+             UNION ALL
+             SELECT CASE WHEN d.direction = 'receivable' THEN 'receivable' ELSE 'debt' END, d.id, d.creditor_name,
+                    d.due_date,
+                    d.amount - COALESCE((SELECT SUM(s.amount) FROM debt_settlements s WHERE s.debt_id = d.id), 0),
+                    d.wallet_id
+             FROM debts d
+             WHERE d.user_id = $1 AND d.status = 'active' AND d.due_date < $2
+         ) upcoming
+         ORDER BY due_at, name
+         LIMIT $3",
+    )
+    .bind(user_id)
+    .bind(now + Duration::days(UPCOMING_DAYS))
+    .bind(MAX_UPCOMING)
+    .fetch_all(pool)
+    .await
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/summary").user(Method::GET, "/{user_id}", get_summary)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
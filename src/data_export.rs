@@ -0,0 +1,518 @@
+use std::io;
+
+use actix_web::http::Method;
+use actix_web::web::Bytes;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use futures_util::{stream, TryStreamExt};
+use serde_json::Value;
+use sqlx::{PgConnection, PgPool, Row};
+use tokio::sync::mpsc;
+
+use crate::auth::AuthUser;
+use crate::export::csv_field;
+use crate::models::{ApiResponse, DataExportQuery, DataFormat, DateRangeQuery};
+use crate::routes::ScopedRoutes;
+
+// ==================== DATA EXPORTS ====================
+//
+// Downloads of a user's own data, as CSV or JSON:
+//
+// - `GET /api/transactions/user/{user_id}/export?format=&from=&to=`: the
+//   transactions, oldest first, optionally within a created_at range
+// - `GET /api/account/{user_id}/export?format=`: everything the account
+//   holds (wallets, buckets, debts with creditor contacts, debt payments,
+//   savings goals and contributions, attachment metadata and transactions), for backups and data requests
+//
+// Unlike reports, exports are not capped at `MAX_EXPORT_ROWS`: rows are
+// streamed from the database with `fetch()` and written to the response in
+// chunks as they arrive, so a long history is never held in memory. The
+// export runs in one read-only REPEATABLE READ transaction, so every section
+// is read from the same snapshot. Timestamps are written in UTC.
+//
+// CSV follows export.rs: a single table for transactions, titled sections
+// for the account. JSON is an `ApiResponse` holding the rows (account: one
+// array per section) with each value typed as in the database. A database
+// error after the first chunk went out aborts the download, so a cut file is
+// never mistaken for a complete one.
+//
+// Export jobs (see export_jobs.rs) build the same files in the background
+// with `render`, which collects the chunks in memory instead.
+//
+// ============================================================================
+
+/// Buffered bytes sent to the client at once
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// Chunks queued ahead of a slow client
+const CHUNK_QUEUE: usize = 16;
+
+/// A table of the export: columns as (name, SQL expression), rows from `source`
+struct Section {
+    key: &'static str,
+    title: &'static str,
+    columns: &'static [(&'static str, &'static str)],
+    /// FROM/WHERE clause; `$1` is the user, and for `ranged` sections `$2`/`$3` the range
+    source: &'static str,
+    order_by: &'static str,
+    ranged: bool,
+}
+
+const TRANSACTIONS: Section = Section {
+    key: "transactions",
+    title: "Transactions",
+    columns: &[
+        ("id", "t.id"),
+        ("sequence_number", "t.sequence_number"),
+        ("created_at", "t.created_at"),
+        ("wallet_id", "t.wallet_id"),
+        ("wallet_name", "w.name"),
+        ("transaction_type", "t.transaction_type"),
+        ("amount", "t.amount"),
+        ("currency", "t.currency"),
+        ("category", "t.category"),
+        ("description", "t.description"),
+        ("notes", "t.notes"),
+        ("metadata", "t.metadata"),
+        ("bucket_id", "t.bucket_id"),
+        ("income_source_id", "t.income_source_id"),
+        ("updated_at", "t.updated_at"),
+    ],
+    source: "transactions t
+         LEFT JOIN wallets w ON w.id = t.wallet_id
+         WHERE t.user_id = $1 AND t.deleted_at IS NULL
+           AND ($2::timestamptz IS NULL OR t.created_at >= $2)
+           AND ($3::timestamptz IS NULL OR t.created_at < $3)",
+    order_by: "t.created_at, t.id",
+    ranged: true,
+};
+
+/// Sections of the account export; transactions last, as the longest
+const ACCOUNT_SECTIONS: [Section; 9] = [
+    Section {
+        key: "wallets",
+        title: "Wallets",
+        columns: &[
+            ("id", "t.id"),
+            ("name", "t.name"),
+            ("wallet_type", "t.wallet_type"),
+            ("currency", "t.currency"),
+            ("balance", "t.balance"),
+            ("credit_limit", "t.credit_limit"),
+            ("apy", "t.apy"),
+            ("group_id", "t.group_id"),
+            ("created_at", "t.created_at"),
+            ("updated_at", "t.updated_at"),
+        ],
+        source: "wallets t WHERE t.user_id = $1 AND t.deleted_at IS NULL",
+        order_by: "t.created_at, t.id",
+        ranged: false,
+    },
+    Section {
+        key: "buckets",
+        title: "Wallet buckets",
+        columns: &[
+            ("id", "t.id"),
+            ("wallet_id", "t.wallet_id"),
+            ("name", "t.name"),
+            ("balance", "t.balance"),
+            ("target_amount", "t.target_amount"),
+            ("created_at", "t.created_at"),
+            ("updated_at", "t.updated_at"),
+        ],
+        source: "wallet_buckets t WHERE t.user_id = $1",
+        order_by: "t.created_at, t.id",
+        ranged: false,
+    },
+    Section {
+        key: "debts",
+        title: "Debts",
+        columns: &[
+            ("id", "t.id"),
+            ("direction", "t.direction"),
+            ("creditor_name", "t.creditor_name"),
+            ("creditor_phone", "t.creditor_phone"),
+            ("creditor_email", "t.creditor_email"),
+            ("creditor_address", "t.creditor_address"),
+            ("creditor_bank_bin", "t.creditor_bank_bin"),
+            ("creditor_account_number", "t.creditor_account_number"),
+            ("amount", "t.amount"),
+            ("interest_rate", "t.interest_rate"),
+            ("due_date", "t.due_date"),
+            ("status", "t.status"),
+            ("wallet_id", "t.wallet_id"),
+            ("created_at", "t.created_at"),
+            ("updated_at", "t.updated_at"),
+        ],
+        source: "debts t WHERE t.user_id = $1",
+        order_by: "t.created_at, t.id",
+        ranged: false,
+    },
+    Section {
+        key: "debt_payments",
+        title: "Debt payments",
+        columns: &[
+            ("id", "t.id"),
+            ("debt_id", "t.debt_id"),
+            ("amount", "t.amount"),
+            ("note", "t.note"),
+            ("settled_at", "t.settled_at"),
+            ("wallet_id", "t.wallet_id"),
+            ("transaction_id", "t.transaction_id"),
+        ],
+        source: "debt_settlements t WHERE t.user_id = $1",
+        order_by: "t.settled_at, t.id",
+        ranged: false,
+    },
+    Section {
+        key: "goals",
+        title: "Savings goals",
+        columns: &[
+            ("id", "t.id"),
+            ("name", "t.name"),
+            ("target_amount", "t.target_amount"),
+            ("currency", "t.currency"),
+            ("deadline", "t.deadline"),
+            ("wallet_id", "t.wallet_id"),
+            ("created_at", "t.created_at"),
+            ("updated_at", "t.updated_at"),
+        ],
+        source: "savings_goals t WHERE t.user_id = $1",
+        order_by: "t.created_at, t.id",
+        ranged: false,
+    },
+    Section {
+        key: "goal_contributions",
+        title: "Goal contributions",
+        columns: &[
+            ("id", "t.id"),
+            ("goal_id", "t.goal_id"),
+            ("from_wallet_id", "t.from_wallet_id"),
+            ("amount", "t.amount"),
+            ("goal_amount", "t.goal_amount"),
+            ("exchange_rate", "t.exchange_rate"),
+            ("out_transaction_id", "t.out_transaction_id"),
+            ("in_transaction_id", "t.in_transaction_id"),
+            ("created_at", "t.created_at"),
+        ],
+        source: "goal_contributions t WHERE t.user_id = $1",
+        order_by: "t.created_at, t.id",
+        ranged: false,
+    },
+    Section {
+        key: "attachments",
+        title: "Attachments",
+        columns: &[
+            ("id", "t.id"),
+            ("entity_type", "t.entity_type"),
+            ("entity_id", "t.entity_id"),
+            ("file_name", "t.file_name"),
+            ("content_type", "t.content_type"),
+            ("size_bytes", "t.size_bytes"),
+            ("created_at", "t.created_at"),
+        ],
+        source: "attachments t WHERE t.user_id = $1",
+        order_by: "t.created_at, t.id",
+        ranged: false,
+    },
+    Section {
+        key: "transaction_splits",
+        title: "Transaction splits",
+        columns: &[
+            ("id", "t.id"),
+            ("transaction_id", "t.transaction_id"),
+            ("category", "t.category"),
+            ("amount", "t.amount"),
+            ("created_at", "t.created_at"),
+        ],
+        source: "transaction_splits t
+             JOIN transactions x ON x.id = t.transaction_id
+             WHERE t.user_id = $1 AND x.deleted_at IS NULL",
+        order_by: "t.created_at, t.id",
+        ranged: false,
+    },
+    TRANSACTIONS,
+];
+
+/// Why an export stopped early
+enum ExportError {
+    Database(sqlx::Error),
+    /// The client went away; nothing left to do
+    Disconnected,
+}
+
+impl From<sqlx::Error> for ExportError {
+    fn from(e: sqlx::Error) -> Self {
+        ExportError::Database(e)
+    }
+}
+
+// ==================== Handlers ====================
+
+/// Download the user's transactions, oldest first
+pub async fn export_transactions(
+    user: AuthUser,
+    query: web::Query<DataExportQuery>,
+    range: web::Query<DateRangeQuery>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    if let (Some(from), Some(to)) = (range.from, range.to)
+        && from > to
+    {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<String>::error("from must be earlier than to".to_string()));
+    }
+
+    let format = query.format.unwrap_or(DataFormat::Csv);
+    let export = Export::transactions(user.user_id, format, (range.from, range.to));
+    stream_response(db.get_ref().clone(), export, "transactions")
+}
+
+/// Download everything the account holds
+pub async fn export_account(user: AuthUser, query: web::Query<DataExportQuery>, db: web::Data<PgPool>) -> HttpResponse {
+    let format = query.format.unwrap_or(DataFormat::Csv);
+    log::info!(target: "audit", "Account export of user {} as {}", user.user_id, format.as_str());
+
+    let export = Export::account(user.user_id, format);
+    stream_response(db.get_ref().clone(), export, "account")
+}
+
+// ==================== Streaming ====================
+
+/// What to export
+pub(crate) struct Export {
+    user_id: String,
+    format: DataFormat,
+    range: (Option<DateTime<Utc>>, Option<DateTime<Utc>>),
+    sections: &'static [Section],
+}
+
+impl Export {
+    /// The user's transactions, optionally within a created_at range
+    pub(crate) fn transactions(
+        user_id: String,
+        format: DataFormat,
+        range: (Option<DateTime<Utc>>, Option<DateTime<Utc>>),
+    ) -> Self {
+        Self { user_id, format, range, sections: std::slice::from_ref(&TRANSACTIONS) }
+    }
+
+    /// Everything the account holds
+    pub(crate) fn account(user_id: String, format: DataFormat) -> Self {
+        Self { user_id, format, range: (None, None), sections: &ACCOUNT_SECTIONS }
+    }
+}
+
+/// Content type and file extension of `format`
+pub(crate) fn file_type(format: DataFormat) -> (&'static str, &'static str) {
+    match format {
+        DataFormat::Csv => ("text/csv; charset=utf-8", "csv"),
+        DataFormat::Json => ("application/json", "json"),
+    }
+}
+
+/// Start the export in the background and stream its chunks as the response
+fn stream_response(pool: PgPool, export: Export, name: &str) -> HttpResponse {
+    let (content_type, extension) = file_type(export.format);
+    let file_name = format!("ketobook-{}-{}.{}", name, Utc::now().format("%Y-%m-%d"), extension);
+
+    let (sender, receiver) = mpsc::channel::<Result<Bytes, io::Error>>(CHUNK_QUEUE);
+    actix_web::rt::spawn(async move {
+        let mut writer = ChunkWriter { sender, buffer: String::new() };
+        let result = match pool.begin().await {
+            Ok(mut db_tx) => {
+                let written = write_export(&mut db_tx, &export, &mut writer).await;
+                let _ = db_tx.rollback().await;
+                written
+            }
+            Err(e) => Err(ExportError::Database(e)),
+        };
+        match result {
+            Ok(()) => {
+                let _ = writer.flush().await;
+            }
+            Err(ExportError::Database(e)) => {
+                log::error!("Export for user {} failed: {}", export.user_id, e);
+                let _ = writer.sender.send(Err(io::Error::other("export failed"))).await;
+            }
+            Err(ExportError::Disconnected) => {}
+        }
+    });
+
+    let body = stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|chunk| (chunk, receiver))
+    });
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}\"", file_name)))
+        .streaming(body)
+}
+
+/// Write the whole export into memory, for a background export job
+pub(crate) async fn render(pool: &PgPool, export: &Export) -> Result<Vec<u8>, String> {
+    let (sender, mut receiver) = mpsc::channel::<Result<Bytes, io::Error>>(CHUNK_QUEUE);
+    let mut writer = ChunkWriter { sender, buffer: String::new() };
+    let write = async move {
+        let mut db_tx = pool.begin().await?;
+        let written = write_export(&mut db_tx, export, &mut writer).await;
+        let _ = db_tx.rollback().await;
+        written?;
+        writer.flush().await
+    };
+    let collect = async {
+        let mut data = Vec::new();
+        while let Some(Ok(chunk)) = receiver.recv().await {
+            data.extend_from_slice(&chunk);
+        }
+        data
+    };
+
+    match tokio::join!(write, collect) {
+        (Ok(()), data) => Ok(data),
+        (Err(ExportError::Database(e)), _) => Err(e.to_string()),
+        (Err(ExportError::Disconnected), _) => Err("Export output closed early".to_string()),
+    }
+}
+
+/// Collects output and hands it to the response in `CHUNK_BYTES` pieces
+struct ChunkWriter {
+    sender: mpsc::Sender<Result<Bytes, io::Error>>,
+    buffer: String,
+}
+
+impl ChunkWriter {
+    async fn write(&mut self, text: &str) -> Result<(), ExportError> {
+        self.buffer.push_str(text);
+        if self.buffer.len() >= CHUNK_BYTES {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), ExportError> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = Bytes::from(std::mem::take(&mut self.buffer));
+        self.sender.send(Ok(chunk)).await.map_err(|_| ExportError::Disconnected)
+    }
+}
+
+async fn write_export(conn: &mut PgConnection, export: &Export, out: &mut ChunkWriter) -> Result<(), ExportError> {
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ READ ONLY")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("SET LOCAL TIME ZONE 'UTC'").execute(&mut *conn).await?;
+
+    let single = export.sections.len() == 1;
+    match export.format {
+        DataFormat::Json if single => out.write(r#"{"success":true,"data":"#).await?,
+        DataFormat::Json => {
+            let header = serde_json::json!({ "user_id": export.user_id, "generated_at": Utc::now() });
+            let header = header.to_string();
+            out.write(r#"{"success":true,"data":"#).await?;
+            out.write(&header[..header.len() - 1]).await?;
+        }
+        DataFormat::Csv => {}
+    }
+
+    for (i, section) in export.sections.iter().enumerate() {
+        match export.format {
+            DataFormat::Csv => {
+                if !single {
+                    if i > 0 {
+                        out.write("\n").await?;
+                    }
+                    out.write(&format!("{}\n", csv_field(section.title))).await?;
+                }
+                write_csv_section(conn, export, section, out).await?;
+            }
+            DataFormat::Json => {
+                if !single {
+                    out.write(&format!(",\"{}\":", section.key)).await?;
+                }
+                write_json_section(conn, export, section, out).await?;
+            }
+        }
+    }
+
+    match export.format {
+        DataFormat::Json if single => out.write(r#","error":null}"#).await,
+        DataFormat::Json => out.write(r#"},"error":null}"#).await,
+        DataFormat::Csv => Ok(()),
+    }
+}
+
+/// Header row, then one line per row, every value as text
+async fn write_csv_section(
+    conn: &mut PgConnection,
+    export: &Export,
+    section: &Section,
+    out: &mut ChunkWriter,
+) -> Result<(), ExportError> {
+    let names: Vec<&str> = section.columns.iter().map(|(name, _)| *name).collect();
+    out.write(&format!("{}\n", names.join(","))).await?;
+
+    let select: Vec<String> = section.columns.iter().map(|(name, expr)| format!("({})::text AS {}", expr, name)).collect();
+    let sql = format!("SELECT {} FROM {} ORDER BY {}", select.join(", "), section.source, section.order_by);
+    let mut query = sqlx::query(&sql).bind(&export.user_id);
+    if section.ranged {
+        query = query.bind(export.range.0).bind(export.range.1);
+    }
+
+    let mut rows = query.fetch(&mut *conn);
+    while let Some(row) = rows.try_next().await? {
+        let mut line = Vec::with_capacity(section.columns.len());
+        for i in 0..section.columns.len() {
+            let value: Option<String> = row.try_get(i)?;
+            line.push(value.map(|v| csv_field(&v)).unwrap_or_default());
+        }
+        out.write(&format!("{}\n", line.join(","))).await?;
+    }
+    Ok(())
+}
+
+/// A JSON array of objects, values typed as in the database
+async fn write_json_section(
+    conn: &mut PgConnection,
+    export: &Export,
+    section: &Section,
+    out: &mut ChunkWriter,
+) -> Result<(), ExportError> {
+    let fields: Vec<String> = section.columns.iter().map(|(name, expr)| format!("'{}', {}", name, expr)).collect();
+    let sql = format!(
+        "SELECT jsonb_build_object({}) FROM {} ORDER BY {}",
+        fields.join(", "),
+        section.source,
+        section.order_by
+    );
+    let mut query = sqlx::query(&sql).bind(&export.user_id);
+    if section.ranged {
+        query = query.bind(export.range.0).bind(export.range.1);
+    }
+
+    out.write("[").await?;
+    let mut first = true;
+    let mut rows = query.fetch(&mut *conn);
+    while let Some(row) = rows.try_next().await? {
+        let value: Value = row.try_get(0)?;
+        if !first {
+            out.write(",").await?;
+        }
+        first = false;
+        out.write(&value.to_string()).await?;
+    }
+    out.write("]").await
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/account")
+        .user(Method::GET, "/{user_id}/export", export_account)
+        .extended()
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
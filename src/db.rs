@@ -1,6 +1,17 @@
+use sqlx::migrate::{MigrateError, Migrator};
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 
+/// The migrations in `migrations/`, embedded at build time
+///
+/// Applied on startup when `RUN_MIGRATIONS` is set, or alone with
+/// `--migrate-only` (e.g. from CI before rolling the servers). Instances
+/// starting together serialize on sqlx's advisory lock, and migrations already
+/// recorded in `_sqlx_migrations` (also by `sqlx migrate run`) are skipped.
+/// Background steps of online migrations aren't part of it (see
+/// online_migrations.rs).
+static MIGRATOR: Migrator = sqlx::migrate!();
+
 #[derive(Clone)]
 pub struct DbPool(pub PgPool);
 
@@ -11,15 +22,17 @@ impl DbPool {
             .connect(database_url)
             .await?;
 
-        // Run migrations (optional)
-        // sqlx::migrate!().run(&pool).await?;
-
         Ok(DbPool(pool))
     }
 
     pub fn get_pool(&self) -> &PgPool {
         &self.0
     }
+
+    /// Apply the embedded migrations that haven't been applied yet
+    pub async fn migrate(&self) -> Result<(), MigrateError> {
+        MIGRATOR.run(&self.0).await
+    }
 }
 
 // Database initialization helper
@@ -1,173 +1,776 @@
+use actix_multipart::Multipart;
+use actix_web::http::{Method, StatusCode};
 use actix_web::{web, HttpResponse};
-use redis::aio::ConnectionManager;
+use sqlx::types::BigDecimal;
 use sqlx::PgPool;
 use uuid::Uuid;
-use chrono::Utc;
+use bigdecimal::{FromPrimitive, ToPrimitive};
+use chrono::{DateTime, Datelike, Months, NaiveTime, Utc};
 
-use crate::models::{ApiResponse, CreateDebtRequest, Debt, UpdateDebtRequest};
-use crate::cache::{get_or_set_cache, invalidate_cache_pattern};
+use crate::attachments::{self, StorageBackend};
+use crate::audit::{self, AuditContext};
+use crate::auth::AuthUser;
+use crate::config::AppConfig;
+use crate::dry_run::{self, DryRun};
+use crate::error::AppError;
+use crate::export::{render_csv, render_pdf, ReportTable};
+use crate::fieldsets;
+use crate::hooks::{self, DebtPaid, HookContext};
+use crate::filters::{DebtFilter, FilterQuery};
+use crate::idempotency::{self, IdempotencyKey};
+use crate::limits::{self, PageParams};
+use crate::list_versions;
+use crate::mailer::is_valid_address;
+use crate::payment_qr;
+use crate::routes::ScopedRoutes;
+use crate::splits;
+use crate::telemetry;
+use crate::validation::{self, Fields, Validate};
+use crate::wallet_members;
+use crate::models::{
+    ApiResponse, CreateDebtRequest, Debt, DebtAccrualMonth, DebtAmortization, DebtDetail, DebtExportQuery,
+    DebtFilterQuery, DebtInstallment, FieldsQuery, PageQuery, ReportFormat, StatementVisibility, UpdateDebtRequest,
+    UpdateStatementVisibilityRequest,
+};
+use crate::cache::{get_or_set_cache, invalidate_user_cache, user_cache_key, CacheBackend};
+
+/// Entity type used for debt attachments
+const DEBT_ENTITY: &str = "debt";
+
+/// Idempotency key scope of `create_debt`
+const DEBTS_ROUTE: &str = "debts";
 
 // ==================== CRUD Handlers ====================
 
-/// Get all debts for a user (with caching)
+/// Get a page of a user's debts, by due date (with caching)
+///
+/// Supports `?page=&per_page=`, the filters of `DebtFilterQuery` and `?fields=`.
 pub async fn get_user_debts(
-    user_id: web::Path<String>,
+    user: AuthUser,
+    page: web::Query<PageQuery>,
+    filter: web::Query<DebtFilterQuery>,
+    fields: web::Query<FieldsQuery>,
     db: web::Data<PgPool>,
-    cache: web::Data<ConnectionManager>,
-) -> HttpResponse {
-    let user_id = user_id.into_inner();
-    let cache_key = format!("debts:{}", user_id);
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = user.user_id;
+
+    let page = limits::page_params(&config, &page).map_err(AppError::Validation)?;
+    let filter = DebtFilter::from_query(&filter).map_err(AppError::Validation)?;
+    let fields = fieldsets::parse(fields.fields.as_deref(), Debt::FIELDS).map_err(AppError::Validation)?;
 
-    let result = get_or_set_cache(
-        &cache.get_ref(),
+    let suffix = format!("debts:{}:{}", page.cache_suffix(), filter.cache_suffix());
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, &suffix).await;
+
+    let debts = get_or_set_cache(
+        cache.get_ref(),
         &cache_key,
-        fetch_debts_from_db(db.get_ref(), &user_id),
+        fetch_debts_from_db(db.get_ref(), &user_id, filter, page),
     )
-    .await;
+    .await?;
 
-    match result {
-        Ok(debts) => HttpResponse::Ok().json(ApiResponse::success(debts)),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<Vec<Debt>>::error(e.to_string())),
-    }
+    let version = list_versions::list_version(&debts, None);
+    let debts = fieldsets::project(&debts, fields.as_deref())?;
+    Ok(list_versions::list_response(version, debts))
 }
 
-/// Get a single debt by ID
+/// Get a single debt by ID, including attached documents
 pub async fn get_debt(
     path: web::Path<(String, String)>,
     db: web::Data<PgPool>,
-    cache: web::Data<ConnectionManager>,
-) -> HttpResponse {
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
     let (user_id, debt_id) = path.into_inner();
-    let cache_key = format!("debt:{}:{}", user_id, debt_id);
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, &format!("debt:{}", debt_id)).await;
 
-    let result = get_or_set_cache(
-        &cache.get_ref(),
+    let debt = get_or_set_cache(
+        cache.get_ref(),
         &cache_key,
-        fetch_debt_by_id(db.get_ref(), &debt_id, &user_id),
+        fetch_debt_detail(db.get_ref(), &debt_id, &user_id),
     )
-    .await;
+    .await
+    .map_err(AppError::or_not_found("Debt not found"))?;
 
-    match result {
-        Ok(debt) => HttpResponse::Ok().json(ApiResponse::success(debt)),
-        Err(e) => HttpResponse::NotFound()
-            .json(ApiResponse::<Debt>::error(e.to_string())),
-    }
+    Ok(HttpResponse::Ok().json(ApiResponse::success(debt)))
 }
 
 /// Create a new debt
 pub async fn create_debt(
     req: web::Json<CreateDebtRequest>,
+    key: IdempotencyKey,
+    dry_run: DryRun,
+    context: AuditContext,
     db: web::Data<PgPool>,
-    cache: web::Data<ConnectionManager>,
-) -> HttpResponse {
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
     let debt_id = Uuid::new_v4().to_string();
     let now = Utc::now();
 
-    let query = sqlx::query_as::<_, Debt>(
-        "INSERT INTO debts (id, user_id, creditor_name, amount, interest_rate, due_date, status, created_at, updated_at) 
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) 
+    let mut db_tx = db.begin().await?;
+    audit::attach(&mut db_tx, &context).await?;
+
+    // A retry with the same Idempotency-Key gets the first response back
+    if let Some(response) = idempotency::replay(&mut db_tx, &key, &req.user_id, DEBTS_ROUTE, &req.0).await? {
+        return Ok(response);
+    }
+
+    validation::check(&req.0)?;
+    let direction = req.direction.as_deref().unwrap_or("payable");
+
+    let debt = sqlx::query_as::<_, Debt>(
+        "INSERT INTO debts (id, user_id, wallet_id, creditor_name, creditor_phone, creditor_email, creditor_address,
+                            amount, interest_rate, due_date, status, created_at, updated_at, direction,
+                            creditor_bank_bin, creditor_account_number) 
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16) 
          RETURNING *"
     )
     .bind(&debt_id)
     .bind(&req.user_id)
+    .bind(req.wallet_id)
     .bind(&req.creditor_name)
+    .bind(&req.creditor_phone)
+    .bind(&req.creditor_email)
+    .bind(&req.creditor_address)
     .bind(req.amount.clone())
     .bind(req.interest_rate.clone())
     .bind(req.due_date)
     .bind("active")
     .bind(now)
-    .bind(now);
+    .bind(now)
+    .bind(direction)
+    .bind(req.creditor_bank_bin.as_deref().map(str::trim))
+    .bind(req.creditor_account_number.as_deref().map(str::trim))
+    .fetch_one(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to create debt"))?;
 
-    match query.fetch_one(db.get_ref()).await {
-        Ok(debt) => {
-            // Invalidate cache for this user's debts
-            let _ = invalidate_cache_pattern(&cache.get_ref(), &format!("debts:{}*", req.user_id)).await;
-            HttpResponse::Created().json(ApiResponse::success(debt))
-        }
-        Err(e) => {
-            log::error!("Error creating debt: {}", e);
-            HttpResponse::BadRequest()
-                .json(ApiResponse::<Debt>::error("Failed to create debt".to_string()))
-        }
+    if dry_run.0 {
+        return dry_run::preview(db.get_ref(), db_tx, &req.user_id, StatusCode::CREATED, Some(debt), &[]).await;
     }
+
+    let response = idempotency::record(
+        &mut db_tx,
+        &key,
+        &req.user_id,
+        DEBTS_ROUTE,
+        StatusCode::CREATED,
+        &ApiResponse::success(debt),
+    )
+    .await?;
+    db_tx.commit().await.map_err(AppError::database("Failed to create debt"))?;
+
+    // Invalidate cache for this user's debts
+    let _ = invalidate_user_cache(cache.get_ref(), &req.user_id).await;
+    Ok(response)
 }
 
 /// Update a debt
 pub async fn update_debt(
     path: web::Path<(String, String)>,
     req: web::Json<UpdateDebtRequest>,
+    dry_run: DryRun,
+    context: AuditContext,
     db: web::Data<PgPool>,
-    cache: web::Data<ConnectionManager>,
-) -> HttpResponse {
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
     let (user_id, debt_id) = path.into_inner();
     let now = Utc::now();
+    validation::check(&req.0)?;
+
+    let mut db_tx = db.begin().await?;
+    audit::attach(&mut db_tx, &context).await?;
 
-    let query = sqlx::query_as::<_, Debt>(
+    // Lock the debt, so exactly one edit sees it become paid
+    let was_paid: Option<(bool,)> =
+        sqlx::query_as("SELECT status = 'paid' FROM debts WHERE id::text = $1 AND user_id = $2 FOR UPDATE")
+            .bind(&debt_id)
+            .bind(&user_id)
+            .fetch_optional(&mut *db_tx)
+            .await
+            .map_err(AppError::database("Failed to update debt"))?;
+    let was_paid = was_paid.is_some_and(|(paid,)| paid);
+
+    let debt = sqlx::query_as::<_, Debt>(
         "UPDATE debts 
          SET creditor_name = COALESCE($1, creditor_name),
-             amount = COALESCE($2, amount),
-             interest_rate = COALESCE($3, interest_rate),
-             due_date = COALESCE($4, due_date),
-             status = COALESCE($5, status),
-             updated_at = $6
-         WHERE id = $7 AND user_id = $8
+             creditor_phone = COALESCE($2, creditor_phone),
+             creditor_email = COALESCE($3, creditor_email),
+             creditor_address = COALESCE($4, creditor_address),
+             amount = COALESCE($5, amount),
+             interest_rate = COALESCE($6, interest_rate),
+             due_date = COALESCE($7, due_date),
+             status = COALESCE($8, status),
+             updated_at = $9,
+             creditor_bank_bin = COALESCE($12, creditor_bank_bin),
+             creditor_account_number = COALESCE($13, creditor_account_number)
+         WHERE id = $10 AND user_id = $11
          RETURNING *"
     )
     .bind(&req.creditor_name)
+    .bind(&req.creditor_phone)
+    .bind(&req.creditor_email)
+    .bind(&req.creditor_address)
     .bind(req.amount.clone())
     .bind(req.interest_rate.clone())
     .bind(req.due_date)
     .bind(&req.status)
     .bind(now)
     .bind(&debt_id)
-    .bind(&user_id);
+    .bind(&user_id)
+    .bind(req.creditor_bank_bin.as_deref().map(str::trim))
+    .bind(req.creditor_account_number.as_deref().map(str::trim))
+    .fetch_optional(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to update debt"))?
+    .ok_or_else(|| AppError::NotFound("Debt not found".to_string()))?;
+    if dry_run.0 {
+        return dry_run::preview(db.get_ref(), db_tx, &user_id, StatusCode::OK, Some(debt), &[]).await;
+    }
+    db_tx.commit().await.map_err(AppError::database("Failed to update debt"))?;
 
-    match query.fetch_optional(db.get_ref()).await {
-        Ok(Some(debt)) => {
-            let _ = invalidate_cache_pattern(&cache.get_ref(), &format!("debt*:{}*", user_id)).await;
-            HttpResponse::Ok().json(ApiResponse::success(debt))
-        }
-        Ok(None) => HttpResponse::NotFound()
-            .json(ApiResponse::<Debt>::error("Debt not found".to_string())),
-        Err(e) => {
-            log::error!("Error updating debt: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<Debt>::error("Failed to update debt".to_string()))
-        }
+    let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+    if debt.status == "paid" && !was_paid {
+        let cx = HookContext { pool: db.get_ref(), cache: cache.get_ref() };
+        hooks::debt_paid(&cx, DebtPaid { debt: &debt }).await;
     }
+    Ok(HttpResponse::Ok().json(ApiResponse::success(debt)))
 }
 
-/// Delete a debt
+/// Delete a debt and its attachments
 pub async fn delete_debt(
     path: web::Path<(String, String)>,
+    dry_run: DryRun,
+    context: AuditContext,
     db: web::Data<PgPool>,
-    cache: web::Data<ConnectionManager>,
-) -> HttpResponse {
+    cache: web::Data<dyn CacheBackend>,
+    storage: web::Data<dyn StorageBackend>,
+) -> Result<HttpResponse, AppError> {
     let (user_id, debt_id) = path.into_inner();
 
+    let mut db_tx = db.begin().await?;
+    audit::attach(&mut db_tx, &context).await?;
+
     let result = sqlx::query("DELETE FROM debts WHERE id = $1 AND user_id = $2")
         .bind(&debt_id)
         .bind(&user_id)
-        .execute(db.get_ref())
-        .await;
-
-    match result {
-        Ok(query_result) => {
-            if query_result.rows_affected() > 0 {
-                let _ = invalidate_cache_pattern(&cache.get_ref(), &format!("debt*:{}*", user_id)).await;
-                HttpResponse::NoContent().finish()
-            } else {
-                HttpResponse::NotFound()
-                    .json(ApiResponse::<String>::error("Debt not found".to_string()))
+        .execute(&mut *db_tx)
+        .await
+        .map_err(AppError::database("Failed to delete debt"))?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Debt not found".to_string()));
+    }
+    if dry_run.0 {
+        return dry_run::preview::<()>(db.get_ref(), db_tx, &user_id, StatusCode::NO_CONTENT, None, &[]).await;
+    }
+    db_tx.commit().await.map_err(AppError::database("Failed to delete debt"))?;
+    if let Ok(debt_uuid) = Uuid::parse_str(&debt_id) {
+        attachments::delete_all(db.get_ref(), storage.get_ref(), &user_id, DEBT_ENTITY, debt_uuid).await;
+    }
+    let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// ==================== Attachment Handlers ====================
+
+/// Upload contract/receipt documents for a debt (multipart/form-data)
+pub async fn upload_debt_attachment(
+    path: web::Path<(String, String)>,
+    payload: Multipart,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    storage: web::Data<dyn StorageBackend>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, debt_id) = path.into_inner();
+    let debt = fetch_debt_by_id(db.get_ref(), &debt_id, &user_id)
+        .await
+        .map_err(AppError::or_not_found("Debt not found"))?;
+
+    let response = attachments::upload(
+        db.get_ref(),
+        storage.get_ref(),
+        config.attachments_max_bytes,
+        &user_id,
+        DEBT_ENTITY,
+        debt.id,
+        payload,
+    )
+    .await;
+
+    let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+    Ok(response)
+}
+
+/// List documents attached to a debt
+pub async fn list_debt_attachments(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, debt_id) = path.into_inner();
+    let debt = fetch_debt_by_id(db.get_ref(), &debt_id, &user_id)
+        .await
+        .map_err(AppError::or_not_found("Debt not found"))?;
+    Ok(attachments::list(db.get_ref(), &user_id, DEBT_ENTITY, debt.id).await)
+}
+
+/// Download a document attached to a debt
+pub async fn download_debt_attachment(
+    path: web::Path<(String, String, Uuid)>,
+    db: web::Data<PgPool>,
+    storage: web::Data<dyn StorageBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, debt_id, attachment_id) = path.into_inner();
+    let debt = fetch_debt_by_id(db.get_ref(), &debt_id, &user_id)
+        .await
+        .map_err(AppError::or_not_found("Debt not found"))?;
+    Ok(attachments::download(db.get_ref(), storage.get_ref(), &user_id, DEBT_ENTITY, debt.id, attachment_id).await)
+}
+
+/// Delete a document attached to a debt
+pub async fn delete_debt_attachment(
+    path: web::Path<(String, String, Uuid)>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    storage: web::Data<dyn StorageBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, debt_id, attachment_id) = path.into_inner();
+    let debt = fetch_debt_by_id(db.get_ref(), &debt_id, &user_id)
+        .await
+        .map_err(AppError::or_not_found("Debt not found"))?;
+
+    let response =
+        attachments::remove(db.get_ref(), storage.get_ref(), &user_id, DEBT_ENTITY, debt.id, attachment_id).await;
+    let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+    Ok(response)
+}
+
+// ==================== Amortization ====================
+
+/// Estimated interest accrual since the debt was opened and the remaining
+/// repayment schedule, as in the statement
+pub async fn get_debt_amortization(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, debt_id) = path.into_inner();
+
+    let detail = fetch_debt_detail(db.get_ref(), &debt_id, &user_id)
+        .await
+        .map_err(AppError::or_not_found("Debt not found"))?;
+
+    let now = Utc::now();
+    let (accrual, accrual_truncated) = accrual(&detail, now);
+    let schedule = schedule(&detail, now);
+    Ok(HttpResponse::Ok().json(ApiResponse::success(DebtAmortization {
+        debt_id: detail.debt.id,
+        as_of: now,
+        interest_rate: detail.debt.interest_rate.clone(),
+        outstanding: detail.outstanding.clone(),
+        accrued_interest: accrual.iter().map(|m| &m.interest).sum(),
+        accrual,
+        accrual_truncated,
+        schedule_interest: schedule.iter().map(|i| &i.interest).sum(),
+        schedule,
+    })))
+}
+
+// ==================== Statement Export ====================
+
+/// Cap on generated months (accrual history and remaining schedule)
+const MAX_STATEMENT_MONTHS: u32 = 600;
+
+const STATEMENT_VISIBILITY_COLUMNS: &str =
+    "id AS debt_id, wallet_id, statement_visibility AS visibility, statement_shared_with AS shared_with";
+
+/// Download a debt statement: terms, payment history, estimated interest
+/// accrual and the remaining repayment schedule, as CSV or PDF
+///
+/// Members of the debt's wallet may download it too when the owner shared
+/// the statement with them (see `update_statement_visibility`).
+pub async fn export_debt(
+    path: web::Path<(String, String)>,
+    query: web::Query<DebtExportQuery>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, debt_id) = path.into_inner();
+
+    let (owner_id,): (String,) = sqlx::query_as(
+        "SELECT user_id FROM debts
+         WHERE id::text = $1
+           AND report_visible_to(user_id, wallet_id, statement_visibility, statement_shared_with, $2)",
+    )
+    .bind(&debt_id)
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to fetch debt"))?
+    .ok_or_else(|| AppError::NotFound("Debt not found".to_string()))?;
+
+    let detail = fetch_debt_detail(db.get_ref(), &debt_id, &owner_id)
+        .await
+        .map_err(AppError::or_not_found("Debt not found"))?;
+
+    let title = format!("Debt statement: {}", detail.debt.creditor_name);
+    let tables = statement_tables(&detail, Utc::now());
+
+    let (bytes, content_type) = match query.format {
+        ReportFormat::Csv => (render_csv(&tables), "text/csv"),
+        ReportFormat::Pdf => {
+            let bytes = render_pdf(&title, &tables).map_err(|e| {
+                log::error!("Error rendering debt statement: {}", e);
+                AppError::Internal("Failed to render statement".to_string())
+            })?;
+            (bytes, "application/pdf")
+        }
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"ketobook-debt-{}.{}\"", detail.debt.id, query.format.as_str()),
+        ))
+        .body(bytes))
+}
+
+/// Who besides the owner may download a debt's statement
+pub async fn get_statement_visibility(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, debt_id) = path.into_inner();
+
+    let visibility = sqlx::query_as::<_, StatementVisibility>(&format!(
+        "SELECT {} FROM debts WHERE id::text = $1 AND user_id = $2",
+        STATEMENT_VISIBILITY_COLUMNS
+    ))
+    .bind(&debt_id)
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to fetch debt"))?
+    .ok_or_else(|| AppError::NotFound("Debt not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(visibility)))
+}
+
+/// Share a debt's statement with its wallet's household or some of its
+/// members, or make it private again (owner only)
+pub async fn update_statement_visibility(
+    path: web::Path<(String, String)>,
+    req: web::Json<UpdateStatementVisibilityRequest>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, debt_id) = path.into_inner();
+
+    let current = sqlx::query_as::<_, StatementVisibility>(&format!(
+        "SELECT {} FROM debts WHERE id::text = $1 AND user_id = $2",
+        STATEMENT_VISIBILITY_COLUMNS
+    ))
+    .bind(&debt_id)
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to fetch debt"))?
+    .ok_or_else(|| AppError::NotFound("Debt not found".to_string()))?;
+    let shared_with = wallet_members::check_report_sharing(
+        db.get_ref(),
+        &user_id,
+        current.wallet_id,
+        req.visibility,
+        req.shared_with.as_deref(),
+    )
+    .await?;
+
+    let visibility = sqlx::query_as::<_, StatementVisibility>(&format!(
+        "UPDATE debts SET statement_visibility = $3, statement_shared_with = $4
+         WHERE id = $1 AND user_id = $2
+         RETURNING {}",
+        STATEMENT_VISIBILITY_COLUMNS
+    ))
+    .bind(current.debt_id)
+    .bind(&user_id)
+    .bind(req.visibility.as_str())
+    .bind(&shared_with)
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to update statement visibility"))?
+    .ok_or_else(|| AppError::NotFound("Debt not found".to_string()))?;
+
+    log::info!(
+        target: "audit",
+        "debt statement visibility changed debt_id={} user_id={} visibility={} shared_with={:?}",
+        visibility.debt_id,
+        user_id,
+        visibility.visibility,
+        visibility.shared_with
+    );
+    Ok(HttpResponse::Ok().json(ApiResponse::success(visibility)))
+}
+
+/// Build the statement sections for `detail` as of `now`
+fn statement_tables(detail: &DebtDetail, now: DateTime<Utc>) -> Vec<ReportTable> {
+    let debt = &detail.debt;
+    let date = |at: DateTime<Utc>| at.format("%Y-%m-%d").to_string();
+    let settled = &debt.amount - &detail.outstanding;
+
+    let terms = ReportTable {
+        title: "Terms".to_string(),
+        headers: &["term", "value"],
+        rows: vec![
+            vec!["creditor".to_string(), debt.creditor_name.clone()],
+            vec!["direction".to_string(), debt.direction.clone()],
+            vec!["principal".to_string(), debt.amount.to_string()],
+            vec!["interest rate (% per year)".to_string(), debt.interest_rate.to_string()],
+            vec!["opened".to_string(), date(debt.created_at)],
+            vec!["due date".to_string(), debt.due_date.map(date).unwrap_or_else(|| "none".to_string())],
+            vec!["status".to_string(), debt.status.clone()],
+            vec!["settled".to_string(), settled.to_string()],
+            vec!["outstanding".to_string(), detail.outstanding.to_string()],
+        ],
+        truncated: false,
+    };
+
+    let mut remaining = debt.amount.clone();
+    let payments = ReportTable {
+        title: "Payment history".to_string(),
+        headers: &["date", "amount", "note", "outstanding"],
+        rows: detail
+            .settlements
+            .iter()
+            .map(|s| {
+                remaining = &remaining - &s.amount;
+                vec![date(s.settled_at), s.amount.to_string(), s.note.clone().unwrap_or_default(), remaining.to_string()]
+            })
+            .collect(),
+        truncated: false,
+    };
+
+    vec![terms, payments, accrual_table(detail, now), schedule_table(detail, now)]
+}
+
+/// Interest accrual table of the statement
+fn accrual_table(detail: &DebtDetail, now: DateTime<Utc>) -> ReportTable {
+    let (months, truncated) = accrual(detail, now);
+    let total_interest: BigDecimal = months.iter().map(|m| &m.interest).sum();
+
+    let mut rows: Vec<Vec<String>> = months
+        .into_iter()
+        .map(|m| {
+            vec![m.month, m.opening.to_string(), m.interest.to_string(), m.payments.to_string(), m.closing.to_string()]
+        })
+        .collect();
+    rows.push(vec!["total".to_string(), String::new(), total_interest.to_string(), String::new(), String::new()]);
+
+    ReportTable {
+        title: "Interest accrual (estimated, not added to the balance)".to_string(),
+        headers: &["month", "opening", "interest", "payments", "closing"],
+        rows,
+        truncated,
+    }
+}
+
+/// Remaining schedule table of the statement
+fn schedule_table(detail: &DebtDetail, now: DateTime<Utc>) -> ReportTable {
+    ReportTable {
+        title: "Remaining schedule".to_string(),
+        headers: &["date", "payment", "interest", "principal", "remaining"],
+        rows: schedule(detail, now)
+            .into_iter()
+            .map(|i| {
+                vec![
+                    i.date.format("%Y-%m-%d").to_string(),
+                    i.payment.to_string(),
+                    i.interest.to_string(),
+                    i.principal.to_string(),
+                    i.remaining.to_string(),
+                ]
+            })
+            .collect(),
+        truncated: false,
+    }
+}
+
+/// Monthly interest on the outstanding principal since the debt was opened;
+/// the flag is set when capped at `MAX_STATEMENT_MONTHS`
+///
+/// KetoBook tracks principal only, so this is an estimate for reconciling a
+/// lender's statement: simple interest at rate/12 on each month's opening
+/// balance, not added to the debt.
+fn accrual(detail: &DebtDetail, now: DateTime<Utc>) -> (Vec<DebtAccrualMonth>, bool) {
+    let debt = &detail.debt;
+    let monthly_rate = &debt.interest_rate / BigDecimal::from(1200);
+    let first_month = debt.created_at.date_naive().with_day(1).unwrap_or(debt.created_at.date_naive());
+
+    let mut months = Vec::new();
+    let mut opening = debt.amount.clone();
+
+    for offset in 0..=MAX_STATEMENT_MONTHS {
+        let Some(month) = first_month.checked_add_months(Months::new(offset)) else { break };
+        let month_start = month.and_time(NaiveTime::MIN).and_utc();
+        if month_start > now {
+            break;
+        }
+        if offset == MAX_STATEMENT_MONTHS {
+            return (months, true);
+        }
+        let month_end = month
+            .checked_add_months(Months::new(1))
+            .map(|m| m.and_time(NaiveTime::MIN).and_utc())
+            .unwrap_or(now);
+
+        let payments: BigDecimal = detail
+            .settlements
+            .iter()
+            .filter(|s| s.settled_at >= month_start && s.settled_at < month_end)
+            .map(|s| &s.amount)
+            .sum();
+        let interest = (&opening * &monthly_rate).round(2);
+        let closing = &opening - &payments;
+
+        months.push(DebtAccrualMonth {
+            month: month.format("%Y-%m").to_string(),
+            opening,
+            interest,
+            payments,
+            closing: closing.clone(),
+        });
+        opening = closing;
+    }
+    (months, false)
+}
+
+/// Equal monthly installments that clear the outstanding amount by the due date
+///
+/// Without a future due date the whole outstanding amount is due now. Empty
+/// unless the debt is active with something outstanding.
+fn schedule(detail: &DebtDetail, now: DateTime<Utc>) -> Vec<DebtInstallment> {
+    let debt = &detail.debt;
+    let zero = BigDecimal::from(0);
+    let mut installments = Vec::new();
+
+    if debt.status == "active" && detail.outstanding > zero {
+        // Installment dates: one month apart from now, the last on the due date
+        let mut dates: Vec<DateTime<Utc>> = Vec::new();
+        if let Some(due) = debt.due_date.filter(|due| *due > now) {
+            for k in 1..=MAX_STATEMENT_MONTHS {
+                match now.checked_add_months(Months::new(k)) {
+                    Some(at) if at < due => dates.push(at),
+                    _ => break,
+                }
             }
+            dates.push(due);
+        } else {
+            dates.push(now);
         }
-        Err(e) => {
-            log::error!("Error deleting debt: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<String>::error("Failed to delete debt".to_string()))
+
+        let monthly_rate = &debt.interest_rate / BigDecimal::from(1200);
+        let installment = installment_amount(&detail.outstanding, &monthly_rate, dates.len() as i32);
+        let mut remaining = detail.outstanding.clone();
+
+        for (i, at) in dates.iter().enumerate() {
+            let interest = if *at > now { (&remaining * &monthly_rate).round(2) } else { zero.clone() };
+            let principal = if i + 1 == dates.len() {
+                remaining.clone()
+            } else {
+                (&installment - &interest).min(remaining.clone())
+            };
+            remaining = &remaining - &principal;
+            installments.push(DebtInstallment {
+                date: *at,
+                payment: &principal + &interest,
+                interest,
+                principal,
+                remaining: remaining.clone(),
+            });
         }
     }
+    installments
+}
+
+/// Annuity payment for `principal` over `periods` at `rate` per period, in cents
+fn installment_amount(principal: &BigDecimal, rate: &BigDecimal, periods: i32) -> BigDecimal {
+    let r = rate.to_f64().unwrap_or(0.0);
+    let p = principal.to_f64().unwrap_or(0.0);
+    let n = periods.max(1);
+
+    let payment = if r == 0.0 {
+        p / f64::from(n)
+    } else {
+        p * r / (1.0 - (1.0 + r).powi(-n))
+    };
+    BigDecimal::from_f64(payment).unwrap_or_else(|| principal.clone()).round(2)
+}
+
+// ==================== Validation ====================
+
+/// Longest creditor name, in characters
+const MAX_CREDITOR_NAME_CHARS: usize = 255;
+
+impl Validate for CreateDebtRequest {
+    fn validate(&self, fields: &mut Fields) {
+        fields.length("creditor_name", &self.creditor_name, 1, MAX_CREDITOR_NAME_CHARS);
+        fields.positive("amount", Some(&self.amount));
+        fields.not_negative("interest_rate", self.interest_rate.as_ref());
+        fields.one_of("direction", self.direction.as_deref(), &["payable", "receivable"]);
+        check_creditor_contact(fields, self.creditor_email.as_deref(), self.creditor_phone.as_deref());
+        check_creditor_bank(fields, self.creditor_bank_bin.as_deref(), self.creditor_account_number.as_deref());
+    }
+}
+
+impl Validate for UpdateDebtRequest {
+    fn validate(&self, fields: &mut Fields) {
+        fields.optional_length("creditor_name", self.creditor_name.as_deref(), 1, MAX_CREDITOR_NAME_CHARS);
+        fields.positive("amount", self.amount.as_ref());
+        fields.not_negative("interest_rate", self.interest_rate.as_ref());
+        fields.one_of("status", self.status.as_deref(), &["active", "paid", "cancelled"]);
+        check_creditor_contact(fields, self.creditor_email.as_deref(), self.creditor_phone.as_deref());
+        check_creditor_bank(fields, self.creditor_bank_bin.as_deref(), self.creditor_account_number.as_deref());
+    }
+}
+
+/// Basic sanity checks on creditor contact details
+fn check_creditor_contact(fields: &mut Fields, email: Option<&str>, phone: Option<&str>) {
+    if let Some(email) = email
+        && !is_valid_address(email)
+    {
+        fields.invalid("creditor_email", "Invalid creditor email address");
+    }
+    if let Some(phone) = phone {
+        let digits = phone.chars().filter(|c| c.is_ascii_digit()).count();
+        let allowed = phone.chars().all(|c| c.is_ascii_digit() || " +-().".contains(c));
+        if !allowed || !(6..=20).contains(&digits) {
+            fields.invalid("creditor_phone", "Invalid creditor phone number");
+        }
+    }
+}
+
+/// Check the creditor's bank account; the BIN and account number come together
+fn check_creditor_bank(fields: &mut Fields, bin: Option<&str>, account_number: Option<&str>) {
+    match (bin.map(str::trim), account_number.map(str::trim)) {
+        (None, None) => {}
+        (Some(bin), Some(account_number)) => {
+            if bin.len() != 6 || !bin.chars().all(|c| c.is_ascii_digit()) {
+                fields.invalid("creditor_bank_bin", "creditor_bank_bin must be the bank's 6-digit NAPAS BIN");
+            }
+            if account_number.is_empty()
+                || account_number.len() > 19
+                || !account_number.chars().all(|c| c.is_ascii_alphanumeric())
+            {
+                fields.invalid("creditor_account_number", "creditor_account_number must be 1-19 letters or digits");
+            }
+        }
+        (Some(_), None) => fields.invalid(
+            "creditor_account_number",
+            "creditor_bank_bin and creditor_account_number must be given together",
+        ),
+        (None, Some(_)) => fields.invalid(
+            "creditor_bank_bin",
+            "creditor_bank_bin and creditor_account_number must be given together",
+        ),
+    }
 }
 
 // ==================== Database Queries ====================
@@ -175,34 +778,83 @@ pub async fn delete_debt(
 async fn fetch_debts_from_db(
     pool: &PgPool,
     user_id: &str,
+    filter: DebtFilter,
+    page: PageParams,
 ) -> Result<Vec<Debt>, sqlx::Error> {
-    sqlx::query_as::<_, Debt>("SELECT * FROM debts WHERE user_id = $1 ORDER BY due_date ASC")
-        .bind(user_id)
-        .fetch_all(pool)
-        .await
+    telemetry::sql(
+        "debts",
+        "select",
+        filter
+            .apply(user_id, FilterQuery::new("SELECT * FROM debts"))
+            .order_by("due_date ASC, id")
+            .page(page)
+            .fetch_all(pool),
+    )
+    .await
 }
 
-async fn fetch_debt_by_id(
+pub(crate) async fn fetch_debt_by_id(
     pool: &PgPool,
     debt_id: &str,
     user_id: &str,
 ) -> Result<Debt, sqlx::Error> {
-    sqlx::query_as::<_, Debt>("SELECT * FROM debts WHERE id = $1 AND user_id = $2")
-        .bind(debt_id)
-        .bind(user_id)
-        .fetch_one(pool)
-        .await
+    telemetry::sql_one(
+        "debts",
+        "select",
+        sqlx::query_as::<_, Debt>("SELECT * FROM debts WHERE id = $1 AND user_id = $2")
+            .bind(debt_id)
+            .bind(user_id)
+            .fetch_one(pool),
+    )
+    .await
+}
+
+async fn fetch_debt_detail(
+    pool: &PgPool,
+    debt_id: &str,
+    user_id: &str,
+) -> Result<DebtDetail, sqlx::Error> {
+    let debt = fetch_debt_by_id(pool, debt_id, user_id).await?;
+    let settlements = splits::fetch_settlements(pool, debt.id).await?;
+    let settled: BigDecimal = settlements.iter().map(|s| &s.amount).sum();
+    let outstanding = &debt.amount - settled;
+    let attachments = attachments::fetch_attachments(pool, user_id, DEBT_ENTITY, debt.id).await?;
+    Ok(DebtDetail { debt, outstanding, settlements, attachments })
 }
 
 // ==================== Route Configuration ====================
 
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/debts")
+        .user(Method::GET, "/user/{user_id}", get_user_debts)
+        // Bill splits and balances: literal segments before the `{debt_id}` routes
+        .create("/splits", splits::create_split)
+        .user(Method::GET, "/{user_id}/splits/{split_id}", splits::get_split)
+        .user(Method::GET, "/{user_id}/balances", splits::get_balances)
+        .user(Method::POST, "/{user_id}/{debt_id}/settlements", splits::create_settlement)
+        .user(Method::GET, "/{user_id}/{debt_id}/payments", splits::get_settlements)
+        .user(Method::POST, "/{user_id}/{debt_id}/payments", splits::create_settlement)
+        .user(Method::GET, "/{user_id}/{debt_id}/amortization", get_debt_amortization)
+        .user(Method::GET, "/{user_id}/{debt_id}/export", export_debt)
+        .extended()
+        .user(Method::GET, "/{user_id}/{debt_id}/statement-visibility", get_statement_visibility)
+        .user(Method::PUT, "/{user_id}/{debt_id}/statement-visibility", update_statement_visibility)
+        .user(Method::GET, "/{user_id}/{debt_id}/payment-qr", payment_qr::get_debt_payment_qr)
+        .user(Method::GET, "/{user_id}/{debt_id}", get_debt)
+        .create("", create_debt)
+        .dry_run()
+        .user(Method::PUT, "/{user_id}/{debt_id}", update_debt)
+        .dry_run()
+        .user(Method::DELETE, "/{user_id}/{debt_id}", delete_debt)
+        .dry_run()
+        .user(Method::GET, "/{user_id}/{debt_id}/attachments", list_debt_attachments)
+        .user(Method::POST, "/{user_id}/{debt_id}/attachments", upload_debt_attachment)
+        .extended()
+        .user(Method::GET, "/{user_id}/{debt_id}/attachments/{attachment_id}", download_debt_attachment)
+        .extended()
+        .user(Method::DELETE, "/{user_id}/{debt_id}/attachments/{attachment_id}", delete_debt_attachment)
+}
+
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(
-        web::scope("/api/debts")
-            .route("/user/{user_id}", web::get().to(get_user_debts))
-            .route("/{user_id}/{debt_id}", web::get().to(get_debt))
-            .route("", web::post().to(create_debt))
-            .route("/{user_id}/{debt_id}", web::put().to(update_debt))
-            .route("/{user_id}/{debt_id}", web::delete().to(delete_debt)),
-    );
+    routes().register(cfg);
 }
@@ -0,0 +1,251 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{ApiResponse, DevicePreferences, PushDevice, PushPlatform, RegisterDeviceRequest};
+use crate::routes::ScopedRoutes;
+
+// ==================== PUSH DEVICES ====================
+//
+// The mobile apps register their FCM/APNs token here after sign-in, together
+// with the user's alert preferences for that device. Registering a token that
+// is already known moves it to the registering user (a shared tablet, a
+// reinstall) and replaces its preferences.
+//
+// Alerts themselves are sent by the alert job (see alerts.rs).
+//
+// ============================================================================
+
+/// Maximum number of devices per user
+const MAX_DEVICES_PER_USER: i64 = 20;
+
+const MAX_TOKEN_LEN: usize = 4096;
+
+const DEVICE_COLUMNS: &str = "id, user_id, platform, name, low_balance_below, due_date_days_before, \
+     budget_alerts, quiet_start_minute, quiet_end_minute, utc_offset_minutes, created_at, updated_at";
+
+// ==================== Handlers ====================
+
+/// Register a device token (or re-register it with new preferences)
+pub async fn register_device(req: web::Json<RegisterDeviceRequest>, db: web::Data<PgPool>) -> HttpResponse {
+    let token = req.token.trim();
+    if let Err(msg) = validate_token(req.platform, token) {
+        return HttpResponse::BadRequest().json(ApiResponse::<PushDevice>::error(msg));
+    }
+    let name = req.name.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    if name.is_some_and(|n| n.chars().count() > 100) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<PushDevice>::error("Name must be at most 100 characters".to_string()));
+    }
+    if let Err(msg) = validate_preferences(&req.preferences) {
+        return HttpResponse::BadRequest().json(ApiResponse::<PushDevice>::error(msg));
+    }
+
+    // The token being re-registered doesn't count toward the cap
+    let count: Result<(i64,), sqlx::Error> = sqlx::query_as(
+        "SELECT COUNT(*) FROM push_devices WHERE user_id = $1 AND NOT (platform = $2 AND token = $3)",
+    )
+    .bind(&req.user_id)
+    .bind(req.platform.as_str())
+    .bind(token)
+    .fetch_one(db.get_ref())
+    .await;
+    match count {
+        Ok((count,)) if count >= MAX_DEVICES_PER_USER => {
+            return HttpResponse::BadRequest().json(ApiResponse::<PushDevice>::error(format!(
+                "A user can register at most {} devices",
+                MAX_DEVICES_PER_USER
+            )));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Error counting push devices: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<PushDevice>::error("Database error".to_string()));
+        }
+    }
+
+    let prefs = &req.preferences;
+    let now = Utc::now();
+    let result = sqlx::query_as::<_, PushDevice>(&format!(
+        "INSERT INTO push_devices (id, user_id, platform, token, name, low_balance_below, due_date_days_before,
+                                   budget_alerts, quiet_start_minute, quiet_end_minute, utc_offset_minutes,
+                                   created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $12)
+         ON CONFLICT (platform, token) DO UPDATE
+         SET user_id = EXCLUDED.user_id,
+             name = EXCLUDED.name,
+             low_balance_below = EXCLUDED.low_balance_below,
+             due_date_days_before = EXCLUDED.due_date_days_before,
+             budget_alerts = EXCLUDED.budget_alerts,
+             quiet_start_minute = EXCLUDED.quiet_start_minute,
+             quiet_end_minute = EXCLUDED.quiet_end_minute,
+             utc_offset_minutes = EXCLUDED.utc_offset_minutes,
+             updated_at = EXCLUDED.updated_at
+         RETURNING {}",
+        DEVICE_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(req.platform.as_str())
+    .bind(token)
+    .bind(name)
+    .bind(&prefs.low_balance_below)
+    .bind(prefs.due_date_days_before)
+    .bind(prefs.budget_alerts)
+    .bind(prefs.quiet_start_minute)
+    .bind(prefs.quiet_end_minute)
+    .bind(prefs.utc_offset_minutes)
+    .bind(now)
+    .fetch_one(db.get_ref())
+    .await;
+
+    match result {
+        Ok(device) => HttpResponse::Created().json(ApiResponse::success(device)),
+        Err(e) => {
+            log::error!("Error registering push device: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<PushDevice>::error("Failed to register device".to_string()))
+        }
+    }
+}
+
+/// List a user's registered devices
+pub async fn get_user_devices(user_id: web::Path<String>, db: web::Data<PgPool>) -> HttpResponse {
+    let result = sqlx::query_as::<_, PushDevice>(&format!(
+        "SELECT {} FROM push_devices WHERE user_id = $1 ORDER BY created_at",
+        DEVICE_COLUMNS
+    ))
+    .bind(user_id.into_inner())
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(devices) => HttpResponse::Ok().json(ApiResponse::success(devices)),
+        Err(e) => {
+            log::error!("Error fetching push devices: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<PushDevice>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Replace a device's alert preferences
+pub async fn update_device_preferences(
+    path: web::Path<(String, String)>,
+    req: web::Json<DevicePreferences>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let (user_id, device_id) = path.into_inner();
+    if let Err(msg) = validate_preferences(&req) {
+        return HttpResponse::BadRequest().json(ApiResponse::<PushDevice>::error(msg));
+    }
+
+    let result = sqlx::query_as::<_, PushDevice>(&format!(
+        "UPDATE push_devices
+         SET low_balance_below = $1,
+             due_date_days_before = $2,
+             budget_alerts = $3,
+             quiet_start_minute = $4,
+             quiet_end_minute = $5,
+             utc_offset_minutes = $6,
+             updated_at = $7
+         WHERE id::text = $8 AND user_id = $9
+         RETURNING {}",
+        DEVICE_COLUMNS
+    ))
+    .bind(&req.low_balance_below)
+    .bind(req.due_date_days_before)
+    .bind(req.budget_alerts)
+    .bind(req.quiet_start_minute)
+    .bind(req.quiet_end_minute)
+    .bind(req.utc_offset_minutes)
+    .bind(Utc::now())
+    .bind(&device_id)
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(device)) => HttpResponse::Ok().json(ApiResponse::success(device)),
+        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<PushDevice>::error("Device not found".to_string())),
+        Err(e) => {
+            log::error!("Error updating push device: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<PushDevice>::error("Failed to update device".to_string()))
+        }
+    }
+}
+
+/// Unregister a device (e.g. on sign-out)
+pub async fn delete_device(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, device_id) = path.into_inner();
+
+    let result = sqlx::query("DELETE FROM push_devices WHERE id::text = $1 AND user_id = $2")
+        .bind(&device_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await;
+
+    match result {
+        Ok(query_result) if query_result.rows_affected() > 0 => HttpResponse::NoContent().finish(),
+        Ok(_) => HttpResponse::NotFound().json(ApiResponse::<String>::error("Device not found".to_string())),
+        Err(e) => {
+            log::error!("Error deleting push device: {}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<String>::error("Failed to delete device".to_string()))
+        }
+    }
+}
+
+// ==================== Validation ====================
+
+/// APNs tokens are hex; FCM tokens are opaque printable strings
+fn validate_token(platform: PushPlatform, token: &str) -> Result<(), String> {
+    if token.is_empty() || token.len() > MAX_TOKEN_LEN {
+        return Err(format!("token must be 1-{} characters", MAX_TOKEN_LEN));
+    }
+    let valid = match platform {
+        PushPlatform::Apns => token.chars().all(|c| c.is_ascii_hexdigit()),
+        PushPlatform::Fcm => token.chars().all(|c| c.is_ascii_graphic()),
+    };
+    if !valid {
+        return Err(format!("Invalid {} token", platform.as_str()));
+    }
+    Ok(())
+}
+
+fn validate_preferences(prefs: &DevicePreferences) -> Result<(), String> {
+    if prefs.low_balance_below.as_ref().is_some_and(|t| *t < BigDecimal::from(0)) {
+        return Err("low_balance_below must not be negative".to_string());
+    }
+    if prefs.due_date_days_before.is_some_and(|d| !(0..=30).contains(&d)) {
+        return Err("due_date_days_before must be between 0 and 30".to_string());
+    }
+    match (prefs.quiet_start_minute, prefs.quiet_end_minute) {
+        (None, None) => {}
+        (Some(start), Some(end)) if (0..1440).contains(&start) && (0..1440).contains(&end) => {}
+        (Some(_), Some(_)) => return Err("Quiet hours must be minutes of the day (0-1439)".to_string()),
+        _ => return Err("Give both quiet_start_minute and quiet_end_minute, or neither".to_string()),
+    }
+    if !(-720..=840).contains(&prefs.utc_offset_minutes) {
+        return Err("utc_offset_minutes must be between -720 and 840".to_string());
+    }
+    Ok(())
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/devices")
+        .create("", register_device)
+        .user(Method::GET, "/{user_id}", get_user_devices)
+        .user(Method::PUT, "/{user_id}/{device_id}", update_device_preferences)
+        .user(Method::DELETE, "/{user_id}/{device_id}", delete_device)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
@@ -0,0 +1,162 @@
+use actix_web::{web, HttpResponse};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::{AppError, ErrorCodeInfo};
+use crate::models::ApiResponse;
+use crate::openapi;
+use crate::routes::ScopedRoutes;
+
+// ==================== CLIENT DOCS ====================
+//
+// Reference data for client developers, next to the route manifest
+// (`GET /api/routes`):
+//
+// - `GET /api/docs/errors`: every `code` an error response can carry, with
+//   its status, built from `AppError` so it can't drift from the handlers.
+//   Clients should branch on `code`; `error` messages are for people and may
+//   change.
+// - `GET /api/docs/examples`: curated request/response bodies of the routes
+//   clients use most, with the codes each one can fail with.
+// - `GET /api/docs/swagger`: Swagger UI over the OpenAPI document
+//   (`GET /api/openapi.json`, see openapi.rs).
+//
+// A test fails if an example names a route that doesn't exist or a code
+// that isn't in the catalog.
+//
+// ============================================================================
+
+/// Curated example of one route
+struct RouteExample {
+    method: &'static str,
+    path: &'static str,
+    request: Option<&'static str>,
+    response: &'static str,
+    errors: &'static [&'static str],
+}
+
+const EXAMPLES: &[RouteExample] = &[
+    RouteExample {
+        method: "POST",
+        path: "/api/wallets",
+        request: Some(
+            r#"{"user_id": "user_123", "name": "Everyday", "wallet_type": "BankAccount", "balance": "1500.00", "currency": "EUR"}"#,
+        ),
+        response: r#"{"success": true, "data": {"id": "5b0c9e4e-2a4f-4d3e-9a57-0d6f1c2b7a10", "user_id": "user_123", "name": "Everyday", "balance": "1500.00", "currency": "EUR", "credit_limit": null, "wallet_type": "BankAccount", "custom_type_id": null, "group_id": null, "apy": null, "last_interest_posted_at": null, "created_at": "2026-08-20T09:00:00Z", "updated_at": "2026-08-20T09:00:00Z", "version": 1}, "error": null}"#,
+        errors: &["validation_failed", "not_found", "conflict", "database_error"],
+    },
+    RouteExample {
+        method: "POST",
+        path: "/api/transactions",
+        request: Some(
+            r#"{"user_id": "user_123", "wallet_id": "5b0c9e4e-2a4f-4d3e-9a57-0d6f1c2b7a10", "amount": "45.50", "transaction_type": "expense", "category": "Groceries", "description": "Weekly groceries"}"#,
+        ),
+        response: r#"{"success": true, "data": {"id": "550e8400-e29b-41d4-a716-446655440001", "sequence_number": 10453, "user_id": "user_123", "amount": "45.50", "currency": "EUR", "transaction_type": "expense", "category": "groceries", "description": "Weekly groceries", "notes": null, "metadata": {}, "bucket_id": null, "income_source_id": null, "created_at": "2026-08-20T09:05:00Z", "updated_at": "2026-08-20T09:05:00Z", "version": 1, "over_budget": false, "budget_warnings": [], "splits": [], "wallet_balances": [{"wallet_id": "5b0c9e4e-2a4f-4d3e-9a57-0d6f1c2b7a10", "balance": "1204.50", "available_credit": null}]}, "error": null}"#,
+        errors: &["validation_failed", "invalid_fields", "forbidden", "not_found", "conflict", "locked", "database_error"],
+    },
+    RouteExample {
+        method: "GET",
+        path: "/api/transactions/user/{user_id}",
+        request: None,
+        response: r#"{"success": true, "data": {"transactions": [{"id": "550e8400-e29b-41d4-a716-446655440001", "sequence_number": 10453, "user_id": "user_123", "amount": "45.50", "currency": "EUR", "transaction_type": "expense", "category": "groceries", "description": "Weekly groceries", "notes": null, "metadata": {}, "bucket_id": null, "income_source_id": null, "created_at": "2026-08-20T09:05:00Z", "updated_at": "2026-08-20T09:05:00Z", "version": 1}], "total_count": 1, "next_cursor": null}, "error": null}"#,
+        errors: &["validation_failed", "database_error", "cache_error"],
+    },
+    RouteExample {
+        method: "POST",
+        path: "/api/categories/{user_id}/{category_id}/merge",
+        request: Some(r#"{"into_id": "9a3b6c1e-7d2f-4b8a-a1c4-3e5f6a7b8c9d"}"#),
+        response: r#"{"success": true, "data": {"category": {"id": "9a3b6c1e-7d2f-4b8a-a1c4-3e5f6a7b8c9d", "user_id": "user_123", "name": "groceries", "parent_id": "7c1e2d3f-4a5b-6c7d-8e9f-0a1b2c3d4e5f", "icon": "shopping-cart", "system": false, "created_at": "2026-08-15T08:00:00Z", "updated_at": "2026-08-20T09:10:00Z"}, "transactions_updated": 37}, "error": null}"#,
+        errors: &["validation_failed", "not_found", "database_error"],
+    },
+    RouteExample {
+        method: "DELETE",
+        path: "/api/categories/{user_id}/{category_id}",
+        request: None,
+        response: r#"{"success": false, "data": null, "error": "Category 'groceries' is used by 37 transactions; merge it into another category instead", "code": "conflict"}"#,
+        errors: &["validation_failed", "not_found", "conflict", "database_error"],
+    },
+];
+
+/// An example as served, with its bodies parsed
+#[derive(Debug, Serialize)]
+pub struct RouteExampleDoc {
+    pub method: &'static str,
+    pub path: &'static str,
+    pub request: Option<Value>,
+    pub response: Value,
+    pub errors: Vec<ErrorCodeInfo>,
+}
+
+// ==================== Handlers ====================
+
+/// `GET /api/docs/errors` - every error code with its status and meaning
+pub async fn get_error_catalog() -> HttpResponse {
+    HttpResponse::Ok().json(ApiResponse::success(AppError::catalog()))
+}
+
+/// `GET /api/docs/examples` - curated request/response examples
+pub async fn get_route_examples() -> HttpResponse {
+    let catalog = AppError::catalog();
+    let examples: Vec<RouteExampleDoc> = EXAMPLES
+        .iter()
+        .map(|example| RouteExampleDoc {
+            method: example.method,
+            path: example.path,
+            request: example.request.and_then(|body| serde_json::from_str(body).ok()),
+            response: serde_json::from_str(example.response).unwrap_or(Value::Null),
+            errors: catalog
+                .iter()
+                .filter(|info| example.errors.contains(&info.code))
+                .cloned()
+                .collect(),
+        })
+        .collect();
+    HttpResponse::Ok().json(ApiResponse::success(examples))
+}
+
+// ==================== Routes ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/docs")
+        .public("/errors", get_error_catalog)
+        .public("/examples", get_route_examples)
+        .public("/swagger", openapi::get_swagger_ui)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::{self, RouteSpec};
+
+    /// Examples whose route, body or error codes don't exist, as `METHOD path: problem`
+    fn stale_examples(specs: &[RouteSpec]) -> Vec<String> {
+        let catalog = AppError::catalog();
+        let mut stale = Vec::new();
+        for example in EXAMPLES {
+            let name = format!("{} {}", example.method, example.path);
+            if !specs.iter().any(|s| s.method == example.method && s.path == example.path) {
+                stale.push(format!("{}: no such route", name));
+            }
+            let mut bodies = example.request.into_iter().chain([example.response]);
+            if bodies.any(|body| serde_json::from_str::<Value>(body).is_err()) {
+                stale.push(format!("{}: body is not valid JSON", name));
+            }
+            for code in example.errors {
+                if !catalog.iter().any(|info| info.code == *code) {
+                    stale.push(format!("{}: unknown error code '{}'", name, code));
+                }
+            }
+        }
+        stale
+    }
+
+    #[test]
+    fn examples_name_existing_routes_and_error_codes() {
+        let stale = stale_examples(&routes::manifest());
+        assert!(stale.is_empty(), "Stale: {}", stale.join(", "));
+    }
+}
@@ -0,0 +1,134 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use chrono::{Duration, Utc};
+
+use crate::cache::{decode_value, encode_value, CacheBackend};
+use crate::config::AppConfig;
+use crate::models::{ApiResponse, SaveTransactionDraftRequest, TransactionDraft};
+use crate::routes::ScopedRoutes;
+
+// ==================== DRAFTS ====================
+//
+// Autosave for the transaction entry form, so a user who backgrounds the
+// mobile app mid-entry can pick up where they left off. The client PUTs the
+// form as it is typed (fields may be missing or invalid) and GETs it back on
+// resume; nothing is validated until the transaction is actually created.
+//
+// Drafts live only in Redis, one per user, under a key outside the versioned
+// cache namespace so cache invalidation doesn't discard them. Each save
+// restarts the `DRAFT_TTL_SECS` expiry. Values are sealed like cache entries
+// when `CACHE_ENCRYPTION_KEYS` is set.
+//
+// ============================================================================
+
+/// Largest serialized form accepted
+const MAX_DRAFT_BYTES: usize = 16 * 1024;
+
+fn transaction_draft_key(user_id: &str) -> String {
+    format!("draft:{}:transaction", user_id)
+}
+
+// ==================== Handlers ====================
+
+/// Save (replace) a user's transaction draft
+pub async fn save_transaction_draft(
+    user_id: web::Path<String>,
+    req: web::Json<SaveTransactionDraftRequest>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    let key = transaction_draft_key(&user_id);
+
+    let saved_at = Utc::now();
+    let draft = TransactionDraft {
+        form: req.into_inner().form,
+        saved_at,
+        expires_at: saved_at + Duration::seconds(config.draft_ttl_secs as i64),
+    };
+
+    let json = match serde_json::to_string(&draft) {
+        Ok(json) => json,
+        Err(e) => {
+            log::error!("Error serializing transaction draft: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<TransactionDraft>::error("Failed to save draft".to_string()));
+        }
+    };
+    if json.len() > MAX_DRAFT_BYTES {
+        return HttpResponse::PayloadTooLarge().json(ApiResponse::<TransactionDraft>::error(format!(
+            "Draft exceeds {} bytes",
+            MAX_DRAFT_BYTES
+        )));
+    }
+    let Some(value) = encode_value(&key, json) else {
+        return HttpResponse::InternalServerError()
+            .json(ApiResponse::<TransactionDraft>::error("Failed to save draft".to_string()));
+    };
+
+    match cache.set_ex(&key, value, config.draft_ttl_secs).await {
+        Ok(()) => HttpResponse::Ok().json(ApiResponse::success(draft)),
+        Err(e) => {
+            log::error!("Error saving transaction draft: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<TransactionDraft>::error("Failed to save draft".to_string()))
+        }
+    }
+}
+
+/// Restore a user's transaction draft
+pub async fn get_transaction_draft(
+    user_id: web::Path<String>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let key = transaction_draft_key(&user_id.into_inner());
+
+    let stored = match cache.get(&key).await {
+        Ok(stored) => stored,
+        Err(e) => {
+            log::error!("Error loading transaction draft: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<TransactionDraft>::error("Failed to load draft".to_string()));
+        }
+    };
+
+    // A draft sealed with a retired key (or otherwise unreadable) is treated as gone
+    let draft = stored
+        .and_then(|stored| decode_value(&key, &stored))
+        .and_then(|json| serde_json::from_str::<TransactionDraft>(&json).ok());
+
+    match draft {
+        Some(draft) => HttpResponse::Ok().json(ApiResponse::success(draft)),
+        None => HttpResponse::NotFound().json(ApiResponse::<TransactionDraft>::error("No draft saved".to_string())),
+    }
+}
+
+/// Discard a user's transaction draft (e.g. once the transaction is created)
+pub async fn delete_transaction_draft(
+    user_id: web::Path<String>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let key = transaction_draft_key(&user_id.into_inner());
+
+    match cache.del(&key).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            log::error!("Error deleting transaction draft: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<String>::error("Failed to delete draft".to_string()))
+        }
+    }
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/drafts")
+        .user(Method::PUT, "/{user_id}/transaction", save_transaction_draft)
+        .user(Method::GET, "/{user_id}/transaction", get_transaction_draft)
+        .user(Method::DELETE, "/{user_id}/transaction", delete_transaction_draft)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
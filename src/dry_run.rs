@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+
+use actix_web::body::BoxBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, FromRequest, HttpMessage, HttpRequest, HttpResponse};
+use serde::Serialize;
+use sqlx::types::BigDecimal;
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::{ApiResponse, DryRunBalance, DryRunPreview};
+
+// ==================== DRY RUNS ====================
+//
+// Creates, updates, deletes and transfers on wallets, transactions and debts
+// accept `?dry_run=true` (or an `X-Dry-Run: true` header). The request runs
+// as usual - validation, policy, balance and freeze checks, the writes
+// themselves - and the handler then rolls its database transaction back
+// instead of committing, answering with a `DryRunPreview`: the status and
+// body it would have returned and the wallet balances before and after. The
+// client shows this in its confirmation dialog.
+//
+// Routes opt in with `ScopedRoutes::dry_run()`; `enforce` answers 400 to a
+// dry run of any other route, so a client never commits by accident.
+// Handlers take the `DryRun` extractor and call `preview` where they would
+// commit. Nothing after the commit runs: no cache invalidation, automatic
+// top-ups or attachment cleanup, and an `Idempotency-Key` is not used up.
+//
+// ============================================================================
+
+const DRY_RUN_HEADER: HeaderName = HeaderName::from_static("x-dry-run");
+
+/// Whether the request is a dry run (set by `enforce`)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DryRun(pub bool);
+
+impl FromRequest for DryRun {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(req.extensions().get::<DryRun>().copied().unwrap_or_default()))
+    }
+}
+
+fn parse(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Mark a dry-run request for the handler; 400 if the route has none
+pub async fn enforce(
+    supported: bool,
+    req: ServiceRequest,
+    next: Next<BoxBody>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let requested = match req.headers().get(&DRY_RUN_HEADER) {
+        Some(value) => Some(value.to_str().unwrap_or_default().to_string()),
+        None => web::Query::<HashMap<String, String>>::from_query(req.query_string())
+            .ok()
+            .and_then(|query| query.into_inner().remove("dry_run")),
+    };
+    match requested.as_deref().map(parse) {
+        None | Some(Some(false)) => return next.call(req).await,
+        Some(Some(true)) => {}
+        Some(None) => {
+            let response = HttpResponse::BadRequest()
+                .json(ApiResponse::<String>::error("Invalid dry_run. Must be 'true' or 'false'".to_string()));
+            return Ok(req.into_response(response));
+        }
+    }
+    if !supported {
+        let response = HttpResponse::BadRequest()
+            .json(ApiResponse::<String>::error("This endpoint doesn't support dry runs".to_string()));
+        return Ok(req.into_response(response));
+    }
+
+    req.extensions_mut().insert(DryRun(true));
+    let mut res = next.call(req).await?;
+    res.headers_mut().insert(DRY_RUN_HEADER, HeaderValue::from_static("true"));
+    Ok(res)
+}
+
+/// Roll back a dry run's database transaction and answer with what it did
+///
+/// `status` and `result` are the response the request would have sent;
+/// `wallet_ids` the wallets whose balances it changed.
+pub async fn preview<T: Serialize>(
+    pool: &PgPool,
+    mut db_tx: Transaction<'_, Postgres>,
+    user_id: &str,
+    status: StatusCode,
+    result: Option<T>,
+    wallet_ids: &[Uuid],
+) -> Result<HttpResponse, AppError> {
+    let mut ids: Vec<Uuid> = Vec::with_capacity(wallet_ids.len());
+    for id in wallet_ids {
+        if !ids.contains(id) {
+            ids.push(*id);
+        }
+    }
+
+    let after = fetch_balances(&mut *db_tx, user_id, &ids)
+        .await
+        .map_err(AppError::database("Failed to preview changes"))?;
+    db_tx.rollback().await.map_err(AppError::database("Failed to preview changes"))?;
+    let before = fetch_balances(pool, user_id, &ids)
+        .await
+        .map_err(AppError::database("Failed to preview changes"))?;
+
+    let balances = ids
+        .iter()
+        .filter_map(|id| {
+            let old = before.iter().find(|(wallet_id, ..)| wallet_id == id);
+            let new = after.iter().find(|(wallet_id, ..)| wallet_id == id);
+            let (_, name, currency, _) = new.or(old)?;
+            Some(DryRunBalance {
+                wallet_id: *id,
+                name: name.clone(),
+                currency: currency.clone(),
+                balance_before: old.map(|(.., balance)| balance.clone()),
+                balance_after: new.map(|(.., balance)| balance.clone()),
+            })
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(DryRunPreview {
+        dry_run: true,
+        status: status.as_u16(),
+        result,
+        balances,
+    })))
+}
+
+/// (id, name, currency, balance) of the live wallets among `ids` the user owns or shares
+async fn fetch_balances<'e>(
+    conn: impl PgExecutor<'e>,
+    user_id: &str,
+    ids: &[Uuid],
+) -> Result<Vec<(Uuid, String, String, BigDecimal)>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, name, currency, balance FROM wallets
+         WHERE id = ANY($2) AND has_wallet_role(id, $1, 'viewer') AND deleted_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(ids)
+    .fetch_all(conn)
+    .await
+}
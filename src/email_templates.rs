@@ -0,0 +1,493 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use handlebars::{no_escape, Handlebars, Template};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::admin::authorize_admin;
+use crate::cache::CacheBackend;
+use crate::config::AppConfig;
+use crate::models::{
+    ApiResponse, EmailTemplate, EmailTemplateVariant, PreviewEmailTemplateRequest, RenderedEmail,
+    SaveEmailTemplateRequest,
+};
+
+// ==================== EMAIL TEMPLATES ====================
+//
+// Transactional emails are rendered from Handlebars templates, one per email
+// (`name`) and locale. Each has a subject and a plain-text body; values are
+// inserted as they are, without HTML escaping.
+//
+// The built-in templates ship in `templates/email/{name}.{locale}.hbs` (a
+// `Subject:` line, a blank line, then the body) and are version 0. Admins
+// edit templates under `/api/admin/email-templates`: every save adds a new
+// version in `email_templates`, and the highest version is used. Going back
+// means saving an older version again, so the history is never rewritten.
+//
+// A locale falls back to its language, then to English: `pt-BR` uses a
+// `pt-BR` template, else `pt`, else `en`. Within a locale, a saved version
+// wins over the built-in one.
+//
+// `POST /api/admin/email-templates/preview` renders a stored version or an
+// unsaved draft with the template's sample data, or data sent along.
+//
+// ============================================================================
+
+/// Locale used when none is set, and the last fallback
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Scheduled report delivery (reports.rs)
+pub const REPORT_DELIVERY: &str = "report_delivery";
+
+/// Maximum size of a template's subject and body
+const MAX_SUBJECT_CHARS: usize = 500;
+const MAX_BODY_CHARS: usize = 20_000;
+
+/// A template shipped with the service
+struct BuiltinTemplate {
+    name: &'static str,
+    locale: &'static str,
+    source: &'static str,
+}
+
+const BUILTIN_TEMPLATES: [BuiltinTemplate; 2] = [
+    BuiltinTemplate {
+        name: REPORT_DELIVERY,
+        locale: "en",
+        source: include_str!("../templates/email/report_delivery.en.hbs"),
+    },
+    BuiltinTemplate {
+        name: REPORT_DELIVERY,
+        locale: "vi",
+        source: include_str!("../templates/email/report_delivery.vi.hbs"),
+    },
+];
+
+const TEMPLATE_COLUMNS: &str = "id, name, locale, version, subject, body, note, created_at";
+
+#[derive(Debug)]
+pub enum TemplateError {
+    /// No template of that name exists
+    Unknown(String),
+    /// The template doesn't compile or fails on the data
+    Render(String),
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemplateError::Unknown(name) => write!(f, "Unknown email template '{}'", name),
+            TemplateError::Render(e) => write!(f, "Template error: {}", e),
+            TemplateError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for TemplateError {
+    fn from(e: sqlx::Error) -> Self {
+        TemplateError::Database(e)
+    }
+}
+
+impl TemplateError {
+    fn to_response<T: serde::Serialize>(&self) -> HttpResponse {
+        match self {
+            TemplateError::Unknown(_) => HttpResponse::NotFound().json(ApiResponse::<T>::error(self.to_string())),
+            TemplateError::Render(_) => HttpResponse::BadRequest().json(ApiResponse::<T>::error(self.to_string())),
+            TemplateError::Database(e) => {
+                log::error!("Error loading email templates: {}", e);
+                HttpResponse::InternalServerError().json(ApiResponse::<T>::error("Database error".to_string()))
+            }
+        }
+    }
+}
+
+// ==================== Rendering ====================
+
+/// Render the template in use for `name` in `locale` (with fallbacks)
+pub(crate) async fn render(
+    pool: &PgPool,
+    name: &str,
+    locale: &str,
+    data: &Value,
+) -> Result<RenderedEmail, TemplateError> {
+    let template = resolve(pool, name, locale).await?;
+    render_source(&template, data)
+}
+
+/// The subject and body of a template version, before rendering
+struct TemplateSource {
+    name: String,
+    locale: String,
+    version: i32,
+    subject: String,
+    body: String,
+}
+
+/// The template in use for `name` in `locale`: the first of the fallback
+/// locales with a saved version or a built-in template
+async fn resolve(pool: &PgPool, name: &str, locale: &str) -> Result<TemplateSource, TemplateError> {
+    if !is_known(name) {
+        return Err(TemplateError::Unknown(name.to_string()));
+    }
+    for candidate in fallback_locales(locale) {
+        if let Some(saved) = fetch_version(pool, name, &candidate, None).await? {
+            return Ok(saved.into());
+        }
+        if let Some(builtin) = builtin(name, &candidate) {
+            return Ok(builtin);
+        }
+    }
+    Err(TemplateError::Unknown(name.to_string()))
+}
+
+fn render_source(template: &TemplateSource, data: &Value) -> Result<RenderedEmail, TemplateError> {
+    let mut registry = Handlebars::new();
+    registry.register_escape_fn(no_escape);
+    let render = |source: &str| {
+        registry
+            .render_template(source, data)
+            .map_err(|e| TemplateError::Render(e.to_string()))
+    };
+
+    Ok(RenderedEmail {
+        name: template.name.clone(),
+        locale: template.locale.clone(),
+        version: template.version,
+        subject: render(&template.subject)?.trim().to_string(),
+        body: render(&template.body)?.trim_end().to_string(),
+    })
+}
+
+/// `locale`, its language, then the default locale
+fn fallback_locales(locale: &str) -> Vec<String> {
+    let mut locales = Vec::new();
+    if let Some(locale) = normalize_locale(locale) {
+        if let Some((language, _)) = locale.split_once('-') {
+            locales.push(locale.clone());
+            locales.push(language.to_string());
+        } else {
+            locales.push(locale);
+        }
+    }
+    if !locales.iter().any(|l| l == DEFAULT_LOCALE) {
+        locales.push(DEFAULT_LOCALE.to_string());
+    }
+    locales
+}
+
+/// `en`, `vi`, `pt-BR`: a lowercase language, optionally an uppercase region
+pub(crate) fn normalize_locale(locale: &str) -> Option<String> {
+    let locale = locale.trim().replace('_', "-");
+    let (language, region) = match locale.split_once('-') {
+        Some((language, region)) => (language, Some(region)),
+        None => (locale.as_str(), None),
+    };
+    let is_code = |code: &str, len: usize| code.len() == len && code.chars().all(|c| c.is_ascii_alphabetic());
+    if !is_code(language, 2) || region.is_some_and(|region| !is_code(region, 2)) {
+        return None;
+    }
+    Some(match region {
+        Some(region) => format!("{}-{}", language.to_lowercase(), region.to_uppercase()),
+        None => language.to_lowercase(),
+    })
+}
+
+fn is_known(name: &str) -> bool {
+    BUILTIN_TEMPLATES.iter().any(|t| t.name == name)
+}
+
+fn builtin(name: &str, locale: &str) -> Option<TemplateSource> {
+    let template = BUILTIN_TEMPLATES.iter().find(|t| t.name == name && t.locale == locale)?;
+    let source = template.source.strip_prefix("Subject:").unwrap_or(template.source);
+    let (subject, body) = source.split_once('\n').unwrap_or((source, ""));
+    Some(TemplateSource {
+        name: template.name.to_string(),
+        locale: template.locale.to_string(),
+        version: 0,
+        subject: subject.trim().to_string(),
+        body: body.trim_start_matches(['\r', '\n']).to_string(),
+    })
+}
+
+impl From<EmailTemplate> for TemplateSource {
+    fn from(template: EmailTemplate) -> Self {
+        Self {
+            name: template.name,
+            locale: template.locale,
+            version: template.version,
+            subject: template.subject,
+            body: template.body,
+        }
+    }
+}
+
+/// Data the preview renders a template with when none is given
+fn sample_data(name: &str) -> Value {
+    match name {
+        REPORT_DELIVERY => json!({
+            "title": "Transactions, 2026-06-01 to 2026-06-30",
+            "report_type": "transactions",
+            "frequency": "monthly",
+            "format": "csv",
+            "row_count": 42,
+            "truncated": false,
+            "max_rows": 10000,
+            "period_start": "2026-06-01",
+            "period_end": "2026-06-30"
+        }),
+        _ => json!({}),
+    }
+}
+
+// ==================== Admin Handlers ====================
+
+/// `GET /api/admin/email-templates` - the template in use per name and locale
+pub async fn list_templates(
+    http_req: HttpRequest,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    let latest = sqlx::query_as::<_, EmailTemplate>(&format!(
+        "SELECT DISTINCT ON (name, locale) {} FROM email_templates ORDER BY name, locale, version DESC",
+        TEMPLATE_COLUMNS
+    ))
+    .fetch_all(db.get_ref())
+    .await;
+    let latest = match latest {
+        Ok(latest) => latest,
+        Err(e) => return TemplateError::Database(e).to_response::<Vec<EmailTemplateVariant>>(),
+    };
+
+    let mut variants: Vec<EmailTemplateVariant> = latest
+        .into_iter()
+        .map(|t| EmailTemplateVariant {
+            name: t.name,
+            locale: t.locale,
+            version: t.version,
+            source: "database".to_string(),
+            updated_at: Some(t.created_at),
+        })
+        .collect();
+    for template in &BUILTIN_TEMPLATES {
+        if !variants.iter().any(|v| v.name == template.name && v.locale == template.locale) {
+            variants.push(EmailTemplateVariant {
+                name: template.name.to_string(),
+                locale: template.locale.to_string(),
+                version: 0,
+                source: "builtin".to_string(),
+                updated_at: None,
+            });
+        }
+    }
+    variants.sort_by(|a, b| a.name.cmp(&b.name).then(a.locale.cmp(&b.locale)));
+
+    HttpResponse::Ok().json(ApiResponse::success(variants))
+}
+
+/// `GET /api/admin/email-templates/{name}/{locale}` - saved versions, newest first
+pub async fn get_template_versions(
+    http_req: HttpRequest,
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+    let (name, locale) = path.into_inner();
+    let Some(locale) = normalize_locale(&locale) else {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<Vec<EmailTemplate>>::error(format!("Invalid locale '{}'", locale)));
+    };
+    if !is_known(&name) {
+        return TemplateError::Unknown(name).to_response::<Vec<EmailTemplate>>();
+    }
+
+    let result = sqlx::query_as::<_, EmailTemplate>(&format!(
+        "SELECT {} FROM email_templates WHERE name = $1 AND locale = $2 ORDER BY version DESC",
+        TEMPLATE_COLUMNS
+    ))
+    .bind(&name)
+    .bind(&locale)
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(versions) => HttpResponse::Ok().json(ApiResponse::success(versions)),
+        Err(e) => TemplateError::Database(e).to_response::<Vec<EmailTemplate>>(),
+    }
+}
+
+/// `POST /api/admin/email-templates/{name}/{locale}` - save a new version
+pub async fn save_template(
+    http_req: HttpRequest,
+    path: web::Path<(String, String)>,
+    req: web::Json<SaveEmailTemplateRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+    let (name, locale) = path.into_inner();
+    let Some(locale) = normalize_locale(&locale) else {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<EmailTemplate>::error(format!("Invalid locale '{}'", locale)));
+    };
+    if !is_known(&name) {
+        return TemplateError::Unknown(name).to_response::<EmailTemplate>();
+    }
+    if let Err(msg) = validate_source(&req.subject, &req.body) {
+        return HttpResponse::BadRequest().json(ApiResponse::<EmailTemplate>::error(msg));
+    }
+    let note = req.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    if note.is_some_and(|n| n.chars().count() > 200) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<EmailTemplate>::error("note must be at most 200 characters".to_string()));
+    }
+
+    let result = sqlx::query_as::<_, EmailTemplate>(&format!(
+        "INSERT INTO email_templates (id, name, locale, version, subject, body, note, created_at)
+         SELECT $1, $2, $3, COALESCE(MAX(version), 0) + 1, $4, $5, $6, $7
+         FROM email_templates WHERE name = $2 AND locale = $3
+         RETURNING {}",
+        TEMPLATE_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&name)
+    .bind(&locale)
+    .bind(req.subject.trim())
+    .bind(&req.body)
+    .bind(note)
+    .bind(Utc::now())
+    .fetch_one(db.get_ref())
+    .await;
+
+    match result {
+        Ok(template) => {
+            log::info!(
+                target: "audit",
+                "Admin saved email template {} ({}) version {}",
+                template.name,
+                template.locale,
+                template.version
+            );
+            HttpResponse::Created().json(ApiResponse::success(template))
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => HttpResponse::Conflict().json(
+            ApiResponse::<EmailTemplate>::error("The template was saved concurrently; try again".to_string()),
+        ),
+        Err(e) => TemplateError::Database(e).to_response::<EmailTemplate>(),
+    }
+}
+
+/// `POST /api/admin/email-templates/preview` - render without sending
+pub async fn preview_template(
+    http_req: HttpRequest,
+    req: web::Json<PreviewEmailTemplateRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+    let req = req.into_inner();
+    let locale = req.locale.as_deref().unwrap_or(DEFAULT_LOCALE);
+    let Some(locale) = normalize_locale(locale) else {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<RenderedEmail>::error(format!("Invalid locale '{}'", locale)));
+    };
+    if !is_known(&req.name) {
+        return TemplateError::Unknown(req.name).to_response::<RenderedEmail>();
+    }
+    let data = req.data.unwrap_or_else(|| sample_data(&req.name));
+
+    let template = match (req.subject, req.body, req.version) {
+        (Some(subject), Some(body), None) => {
+            if let Err(msg) = validate_source(&subject, &body) {
+                return HttpResponse::BadRequest().json(ApiResponse::<RenderedEmail>::error(msg));
+            }
+            TemplateSource { name: req.name, locale, version: 0, subject, body }
+        }
+        (None, None, Some(0)) => match builtin(&req.name, &locale) {
+            Some(template) => template,
+            None => {
+                return HttpResponse::NotFound().json(ApiResponse::<RenderedEmail>::error(format!(
+                    "No built-in {} template for locale '{}'",
+                    req.name, locale
+                )));
+            }
+        },
+        (None, None, Some(version)) => match fetch_version(db.get_ref(), &req.name, &locale, Some(version)).await {
+            Ok(Some(template)) => template.into(),
+            Ok(None) => {
+                return HttpResponse::NotFound()
+                    .json(ApiResponse::<RenderedEmail>::error(format!("Version {} not found", version)));
+            }
+            Err(e) => return TemplateError::Database(e).to_response::<RenderedEmail>(),
+        },
+        (None, None, None) => match resolve(db.get_ref(), &req.name, &locale).await {
+            Ok(template) => template,
+            Err(e) => return e.to_response::<RenderedEmail>(),
+        },
+        _ => {
+            return HttpResponse::BadRequest().json(ApiResponse::<RenderedEmail>::error(
+                "Give both subject and body to preview a draft, or a version, not both".to_string(),
+            ));
+        }
+    };
+
+    match render_source(&template, &data) {
+        Ok(rendered) => HttpResponse::Ok().json(ApiResponse::success(rendered)),
+        Err(e) => e.to_response::<RenderedEmail>(),
+    }
+}
+
+// ==================== Validation ====================
+
+/// Check the size of a subject and body and that both compile
+fn validate_source(subject: &str, body: &str) -> Result<(), String> {
+    if subject.trim().is_empty() || subject.chars().count() > MAX_SUBJECT_CHARS {
+        return Err(format!("subject must be 1-{} characters", MAX_SUBJECT_CHARS));
+    }
+    if body.trim().is_empty() || body.chars().count() > MAX_BODY_CHARS {
+        return Err(format!("body must be 1-{} characters", MAX_BODY_CHARS));
+    }
+    Template::compile(subject).map_err(|e| format!("subject: {}", e))?;
+    Template::compile(body).map_err(|e| format!("body: {}", e))?;
+    Ok(())
+}
+
+// ==================== Database Functions ====================
+
+/// A saved version; the highest one for `None`
+async fn fetch_version(
+    pool: &PgPool,
+    name: &str,
+    locale: &str,
+    version: Option<i32>,
+) -> Result<Option<EmailTemplate>, sqlx::Error> {
+    sqlx::query_as::<_, EmailTemplate>(&format!(
+        "SELECT {} FROM email_templates
+         WHERE name = $1 AND locale = $2 AND ($3::int IS NULL OR version = $3)
+         ORDER BY version DESC
+         LIMIT 1",
+        TEMPLATE_COLUMNS
+    ))
+    .bind(name)
+    .bind(locale)
+    .bind(version)
+    .fetch_optional(pool)
+    .await
+}
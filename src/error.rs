@@ -0,0 +1,278 @@
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde::Serialize;
+
+use crate::buckets::BucketError;
+use crate::cache::CacheError;
+use crate::freezes::FreezeError;
+use crate::models::{ApiResponse, FieldError};
+use crate::policy::PolicyViolation;
+use crate::transaction_fields::MetadataError;
+
+// ==================== HANDLER ERRORS ====================
+//
+// Handlers return `Result<HttpResponse, AppError>` and use `?`; actix turns
+// the error into the usual `ApiResponse` body through `ResponseError`, with
+// the status of its variant and a machine-readable `code`:
+//
+//   NotFound       404  not_found
+//   Forbidden      403  forbidden
+//   Validation     400  validation_failed
+//   InvalidFields  422  invalid_fields (with `fields`, see validation.rs)
+//   Conflict       409  conflict
+//   Locked         423  locked
+//   Database       500  database_error
+//   Cache          500  cache_error
+//   Internal       500  internal_error
+//
+// Server errors are logged with their cause when the response is built and
+// only a generic message reaches the client. `AppError::catalog` lists every
+// code for clients (`GET /api/docs/errors`).
+//
+// ============================================================================
+
+#[derive(Debug)]
+pub enum AppError {
+    NotFound(String),
+    /// The caller can see the resource but their role doesn't allow the action
+    Forbidden(String),
+    Validation(String),
+    /// Request body fields that break their declared rules (see `validation`)
+    InvalidFields(Vec<FieldError>),
+    Conflict(String),
+    Locked(String),
+    /// A failed query; `context` is the message the client sees
+    Database { context: &'static str, source: sqlx::Error },
+    Cache(CacheError),
+    /// Data the handler can't make sense of, e.g. an unknown transaction type
+    Internal(String),
+}
+
+impl AppError {
+    /// Map a query error to a database error reported as `context`
+    ///
+    /// `.map_err(AppError::database("Failed to create wallet"))?`
+    pub fn database(context: &'static str) -> impl FnOnce(sqlx::Error) -> AppError {
+        move |source| {
+            if overdraft(&source) {
+                AppError::Validation("Insufficient balance".to_string())
+            } else {
+                AppError::Database { context, source }
+            }
+        }
+    }
+
+    /// Like `From`, but a missing row is a 404 with `message`
+    pub fn or_not_found<E: Into<AppError>>(message: &'static str) -> impl FnOnce(E) -> AppError {
+        move |e| match e.into() {
+            AppError::Database {
+                source: sqlx::Error::RowNotFound,
+                ..
+            } => AppError::NotFound(message.to_string()),
+            e => e,
+        }
+    }
+
+    /// Prefix a client error's message with the part of the request it is
+    /// about, e.g. `transactions[3]: Insufficient balance`
+    pub fn at(self, location: &str) -> AppError {
+        match self {
+            AppError::NotFound(msg) => AppError::NotFound(format!("{}: {}", location, msg)),
+            AppError::Forbidden(msg) => AppError::Forbidden(format!("{}: {}", location, msg)),
+            AppError::Validation(msg) => AppError::Validation(format!("{}: {}", location, msg)),
+            AppError::InvalidFields(fields) => AppError::InvalidFields(
+                fields
+                    .into_iter()
+                    .map(|f| FieldError {
+                        field: format!("{}.{}", location, f.field),
+                        message: f.message,
+                    })
+                    .collect(),
+            ),
+            AppError::Conflict(msg) => AppError::Conflict(format!("{}: {}", location, msg)),
+            AppError::Locked(msg) => AppError::Locked(format!("{}: {}", location, msg)),
+            e => e,
+        }
+    }
+
+    /// Machine-readable code sent alongside the message
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "not_found",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::Validation(_) => "validation_failed",
+            AppError::InvalidFields(_) => "invalid_fields",
+            AppError::Conflict(_) => "conflict",
+            AppError::Locked(_) => "locked",
+            AppError::Database { .. } => "database_error",
+            AppError::Cache(_) => "cache_error",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    /// What the code means, for the error catalog
+    fn description(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "The resource doesn't exist or belongs to another user",
+            AppError::Forbidden(_) => "The wallet is shared with you in a role that doesn't allow this",
+            AppError::Validation(_) => "Invalid request data, or a policy or balance check failed",
+            AppError::InvalidFields(_) => "Request body fields out of range or malformed; `fields` lists each with a message",
+            AppError::Conflict(_) => "Conflicts with existing data, or needs `\"confirm\": true`",
+            AppError::Locked(_) => "The wallet is frozen",
+            AppError::Database { .. } => "Database error; details are only logged",
+            AppError::Cache(_) => "Cache error; details are only logged",
+            AppError::Internal(_) => "Inconsistent stored data, e.g. an unknown transaction type",
+        }
+    }
+
+    /// Every error code with its status and meaning, one per variant
+    pub fn catalog() -> Vec<ErrorCodeInfo> {
+        let variants = [
+            AppError::Validation(String::new()),
+            AppError::InvalidFields(Vec::new()),
+            AppError::NotFound(String::new()),
+            AppError::Forbidden(String::new()),
+            AppError::Conflict(String::new()),
+            AppError::Locked(String::new()),
+            AppError::Database {
+                context: "",
+                source: sqlx::Error::RowNotFound,
+            },
+            AppError::Cache(CacheError::Database(sqlx::Error::RowNotFound)),
+            AppError::Internal(String::new()),
+        ];
+        variants
+            .iter()
+            .map(|e| ErrorCodeInfo {
+                code: e.code(),
+                status: e.status_code().as_u16(),
+                description: e.description(),
+            })
+            .collect()
+    }
+
+    /// Log the cause of a server error; client errors are not logged
+    pub fn log(&self) {
+        match self {
+            AppError::Database { context, source } => log::error!("{}: {}", context, source),
+            AppError::Cache(e) => log::error!("Cache error: {}", e),
+            AppError::Internal(msg) => log::error!("{}", msg),
+            _ => {}
+        }
+    }
+}
+
+/// One entry of the error code catalog
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorCodeInfo {
+    pub code: &'static str,
+    pub status: u16,
+    pub description: &'static str,
+}
+
+/// The message the client sees
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::NotFound(msg)
+            | AppError::Forbidden(msg)
+            | AppError::Validation(msg)
+            | AppError::Conflict(msg)
+            | AppError::Locked(msg)
+            | AppError::Internal(msg) => f.write_str(msg),
+            AppError::InvalidFields(fields) => {
+                let fields: Vec<String> = fields.iter().map(|f| format!("{}: {}", f.field, f.message)).collect();
+                f.write_str(&fields.join("; "))
+            }
+            AppError::Database { context, .. } => f.write_str(context),
+            AppError::Cache(_) => f.write_str("Cache error"),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::InvalidFields(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Locked(_) => StatusCode::LOCKED,
+            AppError::Database { .. } | AppError::Cache(_) | AppError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        self.log();
+        let mut body = ApiResponse::<()>::error_with_code(self.to_string(), self.code());
+        if let AppError::InvalidFields(fields) = self {
+            body.fields = Some(fields.clone());
+        }
+        HttpResponse::build(self.status_code()).json(body)
+    }
+}
+
+// ==================== Conversions ====================
+
+/// Constraints that refuse a negative wallet balance (see the migration
+/// `asset_overdraft_guard`); a path that skipped the funds check gets 400
+const OVERDRAFT_CONSTRAINTS: [&str; 2] = ["wallets_asset_balance_not_negative", "balance_non_negative"];
+
+fn overdraft(source: &sqlx::Error) -> bool {
+    matches!(source, sqlx::Error::Database(e) if e.constraint().is_some_and(|c| OVERDRAFT_CONSTRAINTS.contains(&c)))
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(source: sqlx::Error) -> Self {
+        AppError::database("Database error")(source)
+    }
+}
+
+impl From<CacheError> for AppError {
+    fn from(e: CacheError) -> Self {
+        match e {
+            CacheError::Database(e) => e.into(),
+            e => AppError::Cache(e),
+        }
+    }
+}
+
+impl From<FreezeError> for AppError {
+    fn from(e: FreezeError) -> Self {
+        match e {
+            FreezeError::Frozen(wallet_id) => AppError::Locked(format!("Wallet {} is frozen", wallet_id)),
+            FreezeError::Database(e) => e.into(),
+        }
+    }
+}
+
+impl From<BucketError> for AppError {
+    fn from(e: BucketError) -> Self {
+        match e {
+            BucketError::NotFound(msg) => AppError::NotFound(msg),
+            BucketError::Conflict(msg) => AppError::Conflict(msg),
+            BucketError::Invalid(msg) => AppError::Validation(msg),
+            BucketError::Database(e) => e.into(),
+        }
+    }
+}
+
+impl From<MetadataError> for AppError {
+    fn from(e: MetadataError) -> Self {
+        match e {
+            MetadataError::Invalid(msg) => AppError::Validation(msg),
+            MetadataError::Database(e) => e.into(),
+        }
+    }
+}
+
+impl From<PolicyViolation> for AppError {
+    fn from(violation: PolicyViolation) -> Self {
+        AppError::Validation(violation.message)
+    }
+}
@@ -0,0 +1,182 @@
+use actix_web::{web, HttpResponse, ResponseError};
+use chrono::{DateTime, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{ApiResponse, BalanceExplainQuery, BalanceExplanation, BalanceStart, LedgerEntry};
+use crate::wallet_members::{self, Permission};
+
+// ==================== BALANCE EXPLANATION ====================
+//
+// `GET /api/wallets/{user_id}/{wallet_id}/explain?at=` answers "why is my
+// balance X" from the ledger:
+//
+//   balance(at) = start.balance + SUM(delta of transactions in (start.at, at])
+//
+// The start is the latest balance replay of the wallet (see admin.rs) taken
+// at or before `at`, whose replayed balance was checked against the full
+// history at the time; without one, it is the wallet's opening balance at its
+// creation. Entries list each transaction's signed delta and the running
+// balance after it.
+//
+// The balance is also recomputed from the opening balance over the current
+// history (`history_balance`), as a replay would do today. A mismatch means
+// history before the snapshot changed after it was taken: a transaction was
+// edited or deleted, the balance was edited directly (which shifts the
+// opening balance), or a dated opening balance was set (see
+// opening_balances.rs).
+//
+// ============================================================================
+
+/// Maximum number of ledger entries listed (totals always cover all of them)
+const MAX_EXPLAIN_ENTRIES: i64 = 1000;
+
+/// Signed effect of a transaction on its wallet's balance
+pub(crate) const DELTA: &str = "CASE WHEN t.transaction_type = 'income' THEN t.amount ELSE -t.amount END";
+
+// ==================== Handlers ====================
+
+/// Explain a wallet's balance at a point in time
+pub async fn explain_balance(
+    path: web::Path<(String, String)>,
+    query: web::Query<BalanceExplainQuery>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let (user_id, wallet_id) = path.into_inner();
+    let at = query.at.unwrap_or_else(Utc::now);
+    let access =
+        match wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::View).await {
+            Ok(access) => access,
+            Err(e) => return e.error_response(),
+        };
+
+    let wallet = sqlx::query_as::<_, (Uuid, BigDecimal, BigDecimal, DateTime<Utc>)>(
+        "SELECT id, balance, opening_balance, created_at FROM wallets WHERE id::text = $1 AND user_id = $2 AND deleted_at IS NULL",
+    )
+    .bind(&wallet_id)
+    .bind(&access.owner_id)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    let (wallet_id, live_balance, opening_balance, created_at) = match wallet {
+        Ok(Some(wallet)) => wallet,
+        Ok(None) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<BalanceExplanation>::error("Wallet not found".to_string()));
+        }
+        Err(e) => {
+            log::error!("Error fetching wallet: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<BalanceExplanation>::error("Database error".to_string()));
+        }
+    };
+
+    let opening = BalanceStart {
+        source: "opening_balance".to_string(),
+        replay_run_id: None,
+        at: created_at,
+        balance: opening_balance,
+    };
+
+    match build_explanation(db.get_ref(), wallet_id, at, opening, live_balance).await {
+        Ok(explanation) => HttpResponse::Ok().json(ApiResponse::success(explanation)),
+        Err(e) => {
+            log::error!("Error explaining balance of wallet {}: {}", wallet_id, e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<BalanceExplanation>::error("Database error".to_string()))
+        }
+    }
+}
+
+// ==================== Database Functions ====================
+
+async fn build_explanation(
+    pool: &PgPool,
+    wallet_id: Uuid,
+    at: DateTime<Utc>,
+    opening: BalanceStart,
+    live_balance: BigDecimal,
+) -> Result<BalanceExplanation, sqlx::Error> {
+    // Read the snapshot and the history as of one instant
+    let mut db_tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ READ ONLY")
+        .execute(&mut *db_tx)
+        .await?;
+
+    let snapshot: Option<(Uuid, DateTime<Utc>, BigDecimal)> = sqlx::query_as(
+        "SELECT run_id, replayed_at, replayed_balance FROM wallet_balance_replay
+         WHERE wallet_id = $1 AND replayed_at <= $2
+         ORDER BY replayed_at DESC
+         LIMIT 1",
+    )
+    .bind(wallet_id)
+    .bind(at)
+    .fetch_optional(&mut *db_tx)
+    .await?;
+
+    // Without a snapshot, count every transaction from the opening balance
+    let (start, since) = match snapshot {
+        Some((run_id, replayed_at, balance)) => (
+            BalanceStart {
+                source: "replay".to_string(),
+                replay_run_id: Some(run_id),
+                at: replayed_at,
+                balance,
+            },
+            Some(replayed_at),
+        ),
+        None => (opening.clone(), None),
+    };
+
+    let (entry_count, net, history_net): (i64, BigDecimal, BigDecimal) = sqlx::query_as(&format!(
+        "SELECT COUNT(*) FILTER (WHERE $2::timestamptz IS NULL OR t.created_at > $2),
+                COALESCE(SUM({0}) FILTER (WHERE $2::timestamptz IS NULL OR t.created_at > $2), 0),
+                COALESCE(SUM({0}), 0)
+         FROM transactions t
+         WHERE t.wallet_id = $1 AND t.deleted_at IS NULL AND t.created_at <= $3",
+        DELTA
+    ))
+    .bind(wallet_id)
+    .bind(since)
+    .bind(at)
+    .fetch_one(&mut *db_tx)
+    .await?;
+
+    let entries = sqlx::query_as::<_, LedgerEntry>(&format!(
+        "SELECT t.id AS transaction_id, t.created_at, t.transaction_type, t.category, t.description, t.amount,
+                {0} AS delta,
+                $4 + SUM({0}) OVER (ORDER BY t.created_at, t.id) AS balance_after
+         FROM transactions t
+         WHERE t.wallet_id = $1 AND t.deleted_at IS NULL
+           AND ($2::timestamptz IS NULL OR t.created_at > $2)
+           AND t.created_at <= $3
+         ORDER BY t.created_at, t.id
+         LIMIT $5",
+        DELTA
+    ))
+    .bind(wallet_id)
+    .bind(since)
+    .bind(at)
+    .bind(&start.balance)
+    .bind(MAX_EXPLAIN_ENTRIES)
+    .fetch_all(&mut *db_tx)
+    .await?;
+
+    db_tx.commit().await?;
+
+    let balance = &start.balance + net;
+    let history_balance = opening.balance + history_net;
+    Ok(BalanceExplanation {
+        wallet_id,
+        at,
+        consistent: balance == history_balance,
+        balance,
+        start,
+        entry_count,
+        truncated: entry_count > entries.len() as i64,
+        entries,
+        history_balance,
+        live_balance,
+    })
+}
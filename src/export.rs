@@ -0,0 +1,128 @@
+use printpdf::{BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfLayerReference};
+
+// ==================== TABULAR EXPORTS ====================
+//
+// Reports and downloads are built as `ReportTable`s and rendered to CSV or
+// PDF here, so every export shares one format:
+//
+// - A single table is plain CSV (header row, then rows). Several tables are
+//   written as sections, each introduced by a row holding its title and
+//   separated by a blank line.
+// - PDFs are plain A4 with fixed-width columns per table; cells that don't
+//   fit are cut with `~`.
+//
+// ============================================================================
+
+/// A report rendered to rows, independent of output format
+pub struct ReportTable {
+    pub title: String,
+    pub headers: &'static [&'static str],
+    pub rows: Vec<Vec<String>>,
+    /// Rows beyond `MAX_EXPORT_ROWS` were dropped
+    pub truncated: bool,
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or newline
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_line(out: &mut String, fields: impl Iterator<Item = String>) {
+    out.push_str(&fields.collect::<Vec<_>>().join(","));
+    out.push('\n');
+}
+
+pub fn render_csv(tables: &[ReportTable]) -> Vec<u8> {
+    let sections = tables.len() > 1;
+    let mut out = String::new();
+
+    for (i, table) in tables.iter().enumerate() {
+        if sections {
+            if i > 0 {
+                out.push('\n');
+            }
+            csv_line(&mut out, std::iter::once(csv_field(&table.title)));
+        }
+        csv_line(&mut out, table.headers.iter().map(|h| h.to_string()));
+        for row in &table.rows {
+            csv_line(&mut out, row.iter().map(|f| csv_field(f)));
+        }
+    }
+    out.into_bytes()
+}
+
+/// Render the tables one after another as a plain A4 PDF
+pub fn render_pdf(title: &str, tables: &[ReportTable]) -> Result<Vec<u8>, String> {
+    const PAGE_WIDTH: f32 = 210.0;
+    const PAGE_HEIGHT: f32 = 297.0;
+    const MARGIN: f32 = 15.0;
+    const LINE_HEIGHT: f32 = 5.0;
+    const FONT_SIZE: f32 = 9.0;
+    // Approximate Helvetica glyph width at FONT_SIZE, used to truncate cells
+    const CHAR_WIDTH: f32 = 1.8;
+
+    let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+    let font = doc.add_builtin_font(BuiltinFont::Helvetica).map_err(|e| e.to_string())?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold).map_err(|e| e.to_string())?;
+
+    let mut layer = doc.get_page(page).get_layer(layer);
+    let mut y = PAGE_HEIGHT - MARGIN;
+
+    // Start a new page when the next line would fall into the bottom margin
+    let line = |layer: &mut PdfLayerReference, y: &mut f32, height: f32| {
+        *y -= height;
+        if *y < MARGIN {
+            let (page, new_layer) = doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+            *layer = doc.get_page(page).get_layer(new_layer);
+            *y = PAGE_HEIGHT - MARGIN;
+        }
+    };
+    let write_row = |layer: &PdfLayerReference, y: f32, cells: &[&str], font: &IndirectFontRef, max_chars: usize, column_width: f32| {
+        for (i, cell) in cells.iter().enumerate() {
+            let text = if cell.chars().count() > max_chars {
+                let mut cut: String = cell.chars().take(max_chars.saturating_sub(1)).collect();
+                cut.push('~');
+                cut
+            } else {
+                cell.to_string()
+            };
+            layer.use_text(text, FONT_SIZE, Mm(MARGIN + i as f32 * column_width), Mm(y), font);
+        }
+    };
+
+    layer.use_text(title, 12.0, Mm(MARGIN), Mm(y), &bold);
+    line(&mut layer, &mut y, 2.0 * LINE_HEIGHT);
+
+    for (i, table) in tables.iter().enumerate() {
+        if tables.len() > 1 {
+            if i > 0 {
+                line(&mut layer, &mut y, LINE_HEIGHT);
+            }
+            layer.use_text(table.title.as_str(), 10.0, Mm(MARGIN), Mm(y), &bold);
+            line(&mut layer, &mut y, 1.5 * LINE_HEIGHT);
+        }
+
+        let column_width = (PAGE_WIDTH - 2.0 * MARGIN) / table.headers.len().max(1) as f32;
+        let max_chars = (column_width / CHAR_WIDTH) as usize;
+
+        write_row(&layer, y, table.headers, &bold, max_chars, column_width);
+        line(&mut layer, &mut y, LINE_HEIGHT);
+
+        for row in &table.rows {
+            let cells: Vec<&str> = row.iter().map(String::as_str).collect();
+            write_row(&layer, y, &cells, &font, max_chars, column_width);
+            line(&mut layer, &mut y, LINE_HEIGHT);
+        }
+
+        if table.rows.is_empty() {
+            layer.use_text("No entries.", FONT_SIZE, Mm(MARGIN), Mm(y), &font);
+            line(&mut layer, &mut y, LINE_HEIGHT);
+        }
+    }
+
+    doc.save_to_bytes().map_err(|e| e.to_string())
+}
@@ -0,0 +1,416 @@
+use std::time::Duration;
+
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use futures_util::future::join_all;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::attachments::StorageBackend;
+use crate::data_export::{self, Export};
+use crate::error::AppError;
+use crate::models::{ApiResponse, CreateExportRequest, DataFormat, ExportJob, ExportKind};
+use crate::routes::ScopedRoutes;
+use crate::webhooks;
+
+// ==================== EXPORT JOBS ====================
+//
+// The transaction and account exports of data_export.rs can also be built
+// in the background, for histories too long to download within one request:
+//
+// - `POST /api/exports` queues a job (`202 Accepted`). Asking again for the
+//   same export while it is queued or running returns that job, and a user
+//   can have at most `MAX_PENDING_EXPORTS` jobs waiting.
+// - Workers (`EXPORT_JOB`, on every instance) claim queued jobs with
+//   `FOR UPDATE SKIP LOCKED` and build up to `EXPORT_WORKERS` at once per
+//   instance, so a burst of requests waits in the table instead of piling
+//   up on the database. A job whose instance died is picked up again after
+//   `STALE_RUN_SECS`; a build that fails is retried up to `MAX_ATTEMPTS`.
+// - The file goes into the attachment storage (an S3-compatible bucket or
+//   local disk, see attachments.rs) under `exports/`, and is deleted after
+//   `EXPORT_RETENTION_SECS`.
+// - `GET /api/exports/{user_id}/{export_id}` reports the status and, once it
+//   succeeded, a `download_url` valid for `LINK_TTL`: a presigned S3 URL, or
+//   on local disk a link to `GET /api/exports/download/{export_id}` signed
+//   with the job's own secret. Fetch the status again for a fresh link.
+//
+// ============================================================================
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Job name of the export workers
+pub const EXPORT_JOB: &str = "exports";
+
+/// How often idle workers look for queued jobs
+pub const EXPORT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Queued and running jobs a user can have at once
+const MAX_PENDING_EXPORTS: i64 = 3;
+
+/// Builds of one job before it is marked failed
+const MAX_ATTEMPTS: i16 = 3;
+
+/// A job still `running` after this long lost its worker
+const STALE_RUN_SECS: i64 = 30 * 60;
+
+/// How long a download link works
+const LINK_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Jobs listed by `GET /api/exports/user/{user_id}`
+const LIST_LIMIT: i64 = 50;
+
+const EXPORT_COLUMNS: &str = "id, user_id, kind, format, range_from, range_to, status, size_bytes, error, created_at, started_at, finished_at, expires_at";
+
+/// A job with where its file is and what signs its links
+#[derive(sqlx::FromRow)]
+struct StoredExport {
+    #[sqlx(flatten)]
+    job: ExportJob,
+    object_key: Option<String>,
+    download_secret: String,
+}
+
+/// Query of a signed download link
+#[derive(Debug, Deserialize)]
+pub struct DownloadQuery {
+    expires: i64,
+    signature: String,
+}
+
+// ==================== Handlers ====================
+
+/// Queue an export
+pub async fn create_export(req: web::Json<CreateExportRequest>, db: web::Data<PgPool>) -> Result<HttpResponse, AppError> {
+    if let (Some(from), Some(to)) = (req.from, req.to)
+        && from > to
+    {
+        return Err(AppError::Validation("from must be earlier than to".to_string()));
+    }
+    if req.kind == ExportKind::Account && (req.from.is_some() || req.to.is_some()) {
+        return Err(AppError::Validation("from and to only apply to transaction exports".to_string()));
+    }
+    let format = req.format.unwrap_or(DataFormat::Csv);
+
+    let mut db_tx = db.begin().await?;
+    // One create per user at a time, so concurrent requests can't pass the limit together
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('export_jobs:' || $1))")
+        .bind(&req.user_id)
+        .execute(&mut *db_tx)
+        .await?;
+
+    let pending = sqlx::query_as::<_, ExportJob>(&format!(
+        "SELECT {} FROM export_jobs WHERE user_id = $1 AND status IN ('queued', 'running') ORDER BY created_at",
+        EXPORT_COLUMNS
+    ))
+    .bind(&req.user_id)
+    .fetch_all(&mut *db_tx)
+    .await?;
+    if let Some(same) = pending.iter().find(|job| {
+        job.kind == req.kind.as_str() && job.format == format.as_str() && job.range_from == req.from && job.range_to == req.to
+    }) {
+        return Ok(HttpResponse::Ok().json(ApiResponse::success(same)));
+    }
+    if pending.len() as i64 >= MAX_PENDING_EXPORTS {
+        return Err(AppError::Conflict(format!(
+            "{} exports are already waiting; try again when one has finished",
+            pending.len()
+        )));
+    }
+
+    let job = sqlx::query_as::<_, ExportJob>(&format!(
+        "INSERT INTO export_jobs (id, user_id, kind, format, range_from, range_to, download_secret)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING {}",
+        EXPORT_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(req.kind.as_str())
+    .bind(format.as_str())
+    .bind(req.from)
+    .bind(req.to)
+    .bind(webhooks::generate_secret())
+    .fetch_one(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to queue export"))?;
+    db_tx.commit().await.map_err(AppError::database("Failed to queue export"))?;
+
+    log::info!(target: "audit", "Export {} ({}) queued for user {}", job.id, job.kind, job.user_id);
+    Ok(HttpResponse::Accepted().json(ApiResponse::success(job)))
+}
+
+/// A user's recent exports, newest first
+pub async fn get_user_exports(
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    storage: web::Data<dyn StorageBackend>,
+) -> Result<HttpResponse, AppError> {
+    let jobs = sqlx::query_as::<_, StoredExport>(&format!(
+        "SELECT {}, object_key, download_secret FROM export_jobs WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2",
+        EXPORT_COLUMNS
+    ))
+    .bind(user_id.as_str())
+    .bind(LIST_LIMIT)
+    .fetch_all(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to list exports"))?;
+
+    let jobs: Vec<ExportJob> = jobs
+        .into_iter()
+        .map(|stored| with_download_url(stored, storage.get_ref()))
+        .collect();
+    Ok(HttpResponse::Ok().json(ApiResponse::success(jobs)))
+}
+
+/// An export's status, with a download link once it succeeded
+pub async fn get_export(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+    storage: web::Data<dyn StorageBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, export_id) = path.into_inner();
+    let stored = sqlx::query_as::<_, StoredExport>(&format!(
+        "SELECT {}, object_key, download_secret FROM export_jobs WHERE id::text = $1 AND user_id = $2",
+        EXPORT_COLUMNS
+    ))
+    .bind(&export_id)
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to fetch export"))?
+    .ok_or_else(|| AppError::NotFound("Export not found".to_string()))?;
+
+    let job = with_download_url(stored, storage.get_ref());
+    Ok(HttpResponse::Ok().json(ApiResponse::success(job)))
+}
+
+/// Download a finished export through a signed link (local storage)
+pub async fn download_export(
+    export_id: web::Path<String>,
+    query: web::Query<DownloadQuery>,
+    db: web::Data<PgPool>,
+    storage: web::Data<dyn StorageBackend>,
+) -> Result<HttpResponse, AppError> {
+    let invalid = || AppError::Forbidden("Invalid or expired download link".to_string());
+    if query.expires < Utc::now().timestamp() {
+        return Err(invalid());
+    }
+    let StoredExport { job, object_key, download_secret } = sqlx::query_as::<_, StoredExport>(&format!(
+        "SELECT {}, object_key, download_secret FROM export_jobs WHERE id::text = $1",
+        EXPORT_COLUMNS
+    ))
+    .bind(export_id.as_str())
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to fetch export"))?
+    .ok_or_else(invalid)?;
+    if !signature_matches(&download_secret, job.id, query.expires, &query.signature) {
+        return Err(invalid());
+    }
+    let Some(key) = object_key.filter(|_| job.status == "succeeded") else {
+        return Err(AppError::NotFound("The export is not available".to_string()));
+    };
+
+    let data = storage
+        .get(&key)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to read export {}: {}", job.id, e)))?;
+    let (content_type, extension) = data_export::file_type(parse_format(&job.format));
+    let file_name = format!("ketobook-{}-{}.{}", job.kind, job.created_at.format("%Y-%m-%d"), extension);
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}\"", file_name)))
+        .body(data))
+}
+
+// ==================== Download Links ====================
+
+/// Set `download_url` on a succeeded job
+fn with_download_url(stored: StoredExport, storage: &dyn StorageBackend) -> ExportJob {
+    let StoredExport { mut job, object_key, download_secret } = stored;
+    let Some(key) = object_key.filter(|_| job.status == "succeeded") else {
+        return job;
+    };
+    job.download_url = storage.presigned_url(&key, LINK_TTL).or_else(|| {
+        let expires = Utc::now().timestamp() + LINK_TTL.as_secs() as i64;
+        Some(format!(
+            "/api/exports/download/{}?expires={}&signature={}",
+            job.id,
+            expires,
+            link_signature(&download_secret, job.id, expires)
+        ))
+    });
+    job
+}
+
+fn link_mac(secret: &str, export_id: Uuid, expires: i64) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}:{}", export_id, expires).as_bytes());
+    mac
+}
+
+/// Hex HMAC-SHA256 of `{export_id}:{expires}` under the job's secret
+fn link_signature(secret: &str, export_id: Uuid, expires: i64) -> String {
+    hex::encode(link_mac(secret, export_id, expires).finalize().into_bytes())
+}
+
+/// Constant-time check of a link's signature
+fn signature_matches(secret: &str, export_id: Uuid, expires: i64, signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+    link_mac(secret, export_id, expires).verify_slice(&signature).is_ok()
+}
+
+// ==================== Export Workers ====================
+
+/// Requeue abandoned jobs, delete expired files and build queued exports
+/// with `workers` builds at a time; returns (built, expired)
+pub async fn run(
+    pool: &PgPool,
+    storage: &dyn StorageBackend,
+    workers: usize,
+    retention: Duration,
+) -> Result<(u64, u64), sqlx::Error> {
+    sqlx::query(
+        "UPDATE export_jobs
+         SET status = CASE WHEN attempts >= $1 THEN 'failed' ELSE 'queued' END,
+             error = 'The export stopped before finishing',
+             finished_at = CASE WHEN attempts >= $1 THEN CURRENT_TIMESTAMP END
+         WHERE status = 'running' AND started_at < CURRENT_TIMESTAMP - make_interval(secs => $2)",
+    )
+    .bind(MAX_ATTEMPTS)
+    .bind(STALE_RUN_SECS as f64)
+    .execute(pool)
+    .await?;
+
+    let expired = expire_files(pool, storage).await?;
+
+    let built = join_all((0..workers).map(|_| drain_queue(pool, storage, retention))).await;
+    Ok((built.into_iter().sum(), expired))
+}
+
+/// Build queued jobs one after the other until none is left
+async fn drain_queue(pool: &PgPool, storage: &dyn StorageBackend, retention: Duration) -> u64 {
+    let mut built = 0;
+    loop {
+        let job = sqlx::query_as::<_, ExportJob>(&format!(
+            "UPDATE export_jobs
+             SET status = 'running', started_at = CURRENT_TIMESTAMP, attempts = attempts + 1
+             WHERE id = (SELECT id FROM export_jobs WHERE status = 'queued'
+                         ORDER BY created_at FOR UPDATE SKIP LOCKED LIMIT 1)
+             RETURNING {}",
+            EXPORT_COLUMNS
+        ))
+        .fetch_optional(pool)
+        .await;
+        let job = match job {
+            Ok(Some(job)) => job,
+            Ok(None) => return built,
+            Err(e) => {
+                log::error!("Failed to claim an export: {}", e);
+                return built;
+            }
+        };
+
+        match build(pool, storage, &job, retention).await {
+            Ok(()) => built += 1,
+            Err(e) => {
+                log::error!("Export {} of user {} failed: {}", job.id, job.user_id, e);
+                let failed = sqlx::query(
+                    "UPDATE export_jobs
+                     SET status = CASE WHEN attempts >= $2 THEN 'failed' ELSE 'queued' END, error = $3,
+                         finished_at = CASE WHEN attempts >= $2 THEN CURRENT_TIMESTAMP END
+                     WHERE id = $1",
+                )
+                .bind(job.id)
+                .bind(MAX_ATTEMPTS)
+                .bind(&e)
+                .execute(pool)
+                .await;
+                if let Err(e) = failed {
+                    log::error!("Failed to record export {} failure: {}", job.id, e);
+                }
+            }
+        }
+    }
+}
+
+/// Render one job's file and store it
+async fn build(pool: &PgPool, storage: &dyn StorageBackend, job: &ExportJob, retention: Duration) -> Result<(), String> {
+    let format = parse_format(&job.format);
+    let export = match job.kind.as_str() {
+        "account" => Export::account(job.user_id.clone(), format),
+        _ => Export::transactions(job.user_id.clone(), format, (job.range_from, job.range_to)),
+    };
+    let data = data_export::render(pool, &export).await?;
+
+    let (_, extension) = data_export::file_type(format);
+    let key = format!("exports/{}.{}", job.id, extension);
+    storage.put(&key, &data).await.map_err(|e| format!("Failed to store the file: {}", e))?;
+
+    sqlx::query(
+        "UPDATE export_jobs
+         SET status = 'succeeded', object_key = $2, size_bytes = $3, error = NULL,
+             finished_at = CURRENT_TIMESTAMP, expires_at = CURRENT_TIMESTAMP + make_interval(secs => $4)
+         WHERE id = $1",
+    )
+    .bind(job.id)
+    .bind(&key)
+    .bind(data.len() as i64)
+    .bind(retention.as_secs() as f64)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Delete the files of succeeded jobs past their expiry; returns how many
+async fn expire_files(pool: &PgPool, storage: &dyn StorageBackend) -> Result<u64, sqlx::Error> {
+    let due: Vec<(Uuid, String)> = sqlx::query_as(
+        "SELECT id, object_key FROM export_jobs
+         WHERE status = 'succeeded' AND expires_at <= CURRENT_TIMESTAMP AND object_key IS NOT NULL",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut expired = 0;
+    for (id, key) in due {
+        if let Err(e) = storage.delete(&key).await {
+            log::warn!("Failed to delete export {} file {}: {}", id, key, e);
+            continue;
+        }
+        expired += sqlx::query("UPDATE export_jobs SET status = 'expired' WHERE id = $1 AND status = 'succeeded'")
+            .bind(id)
+            .execute(pool)
+            .await?
+            .rows_affected();
+    }
+    Ok(expired)
+}
+
+fn parse_format(format: &str) -> DataFormat {
+    match format {
+        "json" => DataFormat::Json,
+        _ => DataFormat::Csv,
+    }
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/exports")
+        .create("", create_export)
+        .public("/download/{export_id}", download_export)
+        .extended()
+        .user(Method::GET, "/user/{user_id}", get_user_exports)
+        .user(Method::GET, "/{user_id}/{export_id}", get_export)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
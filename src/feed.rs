@@ -0,0 +1,192 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::config::AppConfig;
+use crate::limits;
+use crate::models::{ApiResponse, FeedEvent, FeedPage, FeedQuery, PageQuery};
+use crate::routes::ScopedRoutes;
+
+// ==================== ACTIVITY FEED ====================
+//
+// `GET /api/feed/{user_id}` merges what happened on an account into one
+// reverse-chronological list for the app's home screen. Each event has a
+// `kind`, the `id` of what it is about, when it occurred and a kind-specific
+// `payload`:
+//
+// - `transaction`: a transaction was recorded (wallet, amount, type,
+//   category, description)
+// - `debt_payment`: a settlement was recorded against a debt (see splits.rs)
+// - `alert`: a push alert went out, once per alert key however many devices
+//   got it. The alert log is kept for 90 days (see alerts.rs), so older
+//   alerts drop out of the feed.
+// - `support_access`: support opened an impersonation session on the account
+// - `import`: a bank statement was imported into a wallet (see
+//   statement_imports.rs), with the number of lines imported, skipped and
+//   failed
+//
+// The service has no logins to report yet; they join the feed as a new kind
+// when it does.
+//
+// Pages are cut by an opaque cursor (the position of the last event served)
+// rather than an offset, so events recorded while the user scrolls don't
+// shift or repeat entries. Events are ordered by time, then kind and id, so
+// the order is total and a cursor is never ambiguous.
+//
+// ============================================================================
+
+const FEED_QUERY: &str = "SELECT kind, id, occurred_at, payload FROM (
+        SELECT 'transaction' AS kind, t.id::text AS id, t.created_at AS occurred_at,
+               jsonb_build_object(
+                   'wallet_id', t.wallet_id, 'wallet_name', w.name, 'amount', t.amount,
+                   'transaction_type', t.transaction_type, 'category', t.category, 'description', t.description
+               ) AS payload
+        FROM transactions t
+        LEFT JOIN wallets w ON w.id = t.wallet_id
+        WHERE t.user_id = $1 AND t.deleted_at IS NULL
+
+        UNION ALL
+
+        SELECT 'debt_payment', s.id::text, s.settled_at,
+               jsonb_build_object(
+                   'debt_id', s.debt_id, 'creditor_name', d.creditor_name, 'direction', d.direction,
+                   'amount', s.amount, 'note', s.note
+               )
+        FROM debt_settlements s
+        JOIN debts d ON d.id = s.debt_id
+        WHERE s.user_id = $1
+
+        UNION ALL
+
+        SELECT 'alert', l.alert_key, MIN(l.sent_at),
+               jsonb_build_object(
+                   'alert', split_part(l.alert_key, ':', 1), 'alert_key', l.alert_key, 'devices', COUNT(*)
+               )
+        FROM push_alert_log l
+        JOIN push_devices d ON d.id = l.device_id
+        WHERE d.user_id = $1
+        GROUP BY l.alert_key
+
+        UNION ALL
+
+        SELECT 'support_access', i.id::text, i.created_at,
+               jsonb_build_object(
+                   'requested_by', i.requested_by, 'reason', i.reason, 'write_access', i.write_access,
+                   'expires_at', i.expires_at, 'revoked_at', i.revoked_at
+               )
+        FROM impersonation_sessions i
+        WHERE i.user_id = $1
+
+        UNION ALL
+
+        SELECT 'import', m.id::text, m.created_at,
+               jsonb_build_object(
+                   'wallet_id', m.wallet_id, 'wallet_name', w.name, 'file_name', m.file_name, 'format', m.format,
+                   'imported', m.imported, 'skipped', m.skipped, 'failed', m.failed
+               )
+        FROM transaction_imports m
+        LEFT JOIN wallets w ON w.id = m.wallet_id
+        WHERE m.user_id = $1
+    ) e
+    WHERE $2::timestamptz IS NULL OR (e.occurred_at, e.kind, e.id) < ($2::timestamptz, $3::text, $4::text)
+    ORDER BY e.occurred_at DESC, e.kind DESC, e.id DESC
+    LIMIT $5";
+
+/// Position of an event in the feed
+struct Cursor {
+    occurred_at: DateTime<Utc>,
+    kind: String,
+    id: String,
+}
+
+impl Cursor {
+    fn after(event: &FeedEvent) -> Self {
+        Self {
+            occurred_at: event.occurred_at,
+            kind: event.kind.clone(),
+            id: event.id.clone(),
+        }
+    }
+
+    fn encode(&self) -> String {
+        BASE64.encode(format!("{}:{}:{}", self.occurred_at.timestamp_micros(), self.kind, self.id))
+    }
+
+    fn decode(cursor: &str) -> Option<Self> {
+        let raw = String::from_utf8(BASE64.decode(cursor).ok()?).ok()?;
+        let mut parts = raw.splitn(3, ':');
+        let occurred_at = DateTime::from_timestamp_micros(parts.next()?.parse().ok()?)?;
+        let kind = parts.next()?.to_string();
+        let id = parts.next()?.to_string();
+        Some(Self { occurred_at, kind, id })
+    }
+}
+
+// ==================== Handlers ====================
+
+/// A page of a user's activity feed, newest first
+///
+/// Supports `?cursor=&per_page=`; `next_cursor` is null on the last page.
+pub async fn get_feed(
+    user_id: web::Path<String>,
+    query: web::Query<FeedQuery>,
+    db: web::Data<PgPool>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+
+    let page_query = PageQuery {
+        page: None,
+        per_page: query.per_page,
+    };
+    let per_page = match limits::page_params(&config, &page_query) {
+        Ok(page) => page.limit(),
+        Err(msg) => return HttpResponse::BadRequest().json(ApiResponse::<FeedPage>::error(msg)),
+    };
+    let cursor = match query.cursor.as_deref().map(Cursor::decode) {
+        None => None,
+        Some(Some(cursor)) => Some(cursor),
+        Some(None) => {
+            return HttpResponse::BadRequest().json(ApiResponse::<FeedPage>::error("Invalid cursor".to_string()));
+        }
+    };
+
+    let result = sqlx::query_as::<_, FeedEvent>(FEED_QUERY)
+        .bind(&user_id)
+        .bind(cursor.as_ref().map(|c| c.occurred_at))
+        .bind(cursor.as_ref().map(|c| c.kind.as_str()))
+        .bind(cursor.as_ref().map(|c| c.id.as_str()))
+        .bind(per_page + 1)
+        .fetch_all(db.get_ref())
+        .await;
+
+    let mut events = match result {
+        Ok(events) => events,
+        Err(e) => {
+            log::error!("Error fetching activity feed for user {}: {}", user_id, e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<FeedPage>::error("Database error".to_string()));
+        }
+    };
+
+    let next_cursor = if events.len() as i64 > per_page {
+        events.truncate(per_page as usize);
+        events.last().map(|event| Cursor::after(event).encode())
+    } else {
+        None
+    };
+    HttpResponse::Ok().json(ApiResponse::success(FeedPage { events, next_cursor }))
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/feed").user(Method::GET, "/{user_id}", get_feed)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
@@ -0,0 +1,57 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::AppError;
+
+// ==================== SPARSE FIELDSETS ====================
+//
+// The transaction, wallet and debt lists take `?fields=id,amount,category` to
+// return only those fields of each item, for clients (the mobile list view)
+// that don't need descriptions or timestamps. The projection happens when the
+// response is built: queries and cached pages keep whole rows, so every
+// fieldset shares one cache entry. Paging fields (`total_count`,
+// `next_cursor`) are always returned.
+//
+// Field names are checked against the model's `FIELDS`; an unknown one is a
+// 400 listing the valid names.
+//
+// ============================================================================
+
+/// Most fields one `?fields=` may name
+const MAX_FIELDS: usize = 50;
+
+/// Parse `?fields=`: `None` returns every field
+pub fn parse(fields: Option<&str>, allowed: &[&'static str]) -> Result<Option<Vec<&'static str>>, String> {
+    let Some(fields) = fields else {
+        return Ok(None);
+    };
+    let names: Vec<&str> = fields.split(',').map(str::trim).filter(|name| !name.is_empty()).collect();
+    if names.is_empty() || names.len() > MAX_FIELDS {
+        return Err(format!("fields must name 1-{} fields", MAX_FIELDS));
+    }
+
+    let mut selected = Vec::with_capacity(names.len());
+    for name in names {
+        let Some(field) = allowed.iter().find(|field| **field == name) else {
+            return Err(format!("Unknown field '{}'; valid fields are {}", name, allowed.join(", ")));
+        };
+        if !selected.contains(field) {
+            selected.push(*field);
+        }
+    }
+    Ok(Some(selected))
+}
+
+/// `items` serialized with only `fields` kept in each, or whole without a fieldset
+pub fn project<T: Serialize>(items: &[T], fields: Option<&[&'static str]>) -> Result<Value, AppError> {
+    let mut value = serde_json::to_value(items)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize list: {}", e)))?;
+    if let (Some(fields), Value::Array(items)) = (fields, &mut value) {
+        for item in items {
+            if let Value::Object(map) = item {
+                map.retain(|key, _| fields.contains(&key.as_str()));
+            }
+        }
+    }
+    Ok(value)
+}
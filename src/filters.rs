@@ -0,0 +1,471 @@
+use chrono::{DateTime, Utc};
+use sqlx::postgres::{PgRow, Postgres};
+use sqlx::types::BigDecimal;
+use sqlx::{Encode, FromRow, PgPool, QueryBuilder, Type};
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::limits::{self, PageParams};
+use crate::models::{AuditFilterQuery, DateRangeQuery, DebtFilterQuery, TransactionFilterQuery};
+
+// ==================== DYNAMIC FILTERS ====================
+//
+// List endpoints and reports compose their WHERE clauses with `FilterQuery`
+// instead of formatting SQL strings:
+//
+// - Column names and operators are `&'static str`, so only identifiers
+//   written in code ever reach the SQL text.
+// - Every value is a bound parameter (`push_bind`).
+// - `*_opt` methods skip the clause when the value is `None`, so a filter
+//   struct with all-optional fields maps onto the builder one line per field.
+//
+// Clauses are appended in call order: filters first, then `group_by`,
+// `order_by` and `page`/`limit`.
+//
+// Filter structs (`TransactionFilter`, `DebtFilter`, `AuditFilter`) are built
+// from the raw query models through `from_query`, which validates values first
+// and returns a 400-ready message on failure.
+//
+// ============================================================================
+
+/// A SELECT with an incrementally built WHERE clause
+pub struct FilterQuery<'args> {
+    builder: QueryBuilder<'args, Postgres>,
+    has_where: bool,
+}
+
+impl<'args> FilterQuery<'args> {
+    /// Start from a `SELECT ... FROM ...` without a WHERE clause
+    pub fn new(select: &'static str) -> Self {
+        Self {
+            builder: QueryBuilder::new(select),
+            has_where: false,
+        }
+    }
+
+    fn condition(&mut self, column: &'static str, op: &'static str) -> &mut QueryBuilder<'args, Postgres> {
+        self.builder.push(if self.has_where { " AND " } else { " WHERE " });
+        self.has_where = true;
+        self.builder.push(column).push(op)
+    }
+
+    fn compare<T>(mut self, column: &'static str, op: &'static str, value: T) -> Self
+    where
+        T: 'args + Encode<'args, Postgres> + Send + Type<Postgres>,
+    {
+        self.condition(column, op).push_bind(value);
+        self
+    }
+
+    /// `column = value`
+    pub fn eq<T>(self, column: &'static str, value: T) -> Self
+    where
+        T: 'args + Encode<'args, Postgres> + Send + Type<Postgres>,
+    {
+        self.compare(column, " = ", value)
+    }
+
+    /// `column IS NULL`
+    pub fn null(mut self, column: &'static str) -> Self {
+        self.condition(column, " IS NULL");
+        self
+    }
+
+    /// `column = value` when `value` is set
+    pub fn eq_opt<T>(self, column: &'static str, value: Option<T>) -> Self
+    where
+        T: 'args + Encode<'args, Postgres> + Send + Type<Postgres>,
+    {
+        match value {
+            Some(value) => self.compare(column, " = ", value),
+            None => self,
+        }
+    }
+
+    /// `column >= value` when `value` is set
+    pub fn gte_opt<T>(self, column: &'static str, value: Option<T>) -> Self
+    where
+        T: 'args + Encode<'args, Postgres> + Send + Type<Postgres>,
+    {
+        match value {
+            Some(value) => self.compare(column, " >= ", value),
+            None => self,
+        }
+    }
+
+    /// `column <= value` when `value` is set
+    pub fn lte_opt<T>(self, column: &'static str, value: Option<T>) -> Self
+    where
+        T: 'args + Encode<'args, Postgres> + Send + Type<Postgres>,
+    {
+        match value {
+            Some(value) => self.compare(column, " <= ", value),
+            None => self,
+        }
+    }
+
+    /// `column < value` when `value` is set
+    pub fn lt_opt<T>(self, column: &'static str, value: Option<T>) -> Self
+    where
+        T: 'args + Encode<'args, Postgres> + Send + Type<Postgres>,
+    {
+        match value {
+            Some(value) => self.compare(column, " < ", value),
+            None => self,
+        }
+    }
+
+    /// `column ->> key = value` (JSONB field compared in text form) when both are set
+    pub fn json_text_eq_opt(mut self, column: &'static str, key: Option<String>, value: Option<String>) -> Self {
+        if let (Some(key), Some(value)) = (key, value) {
+            self.condition(column, " ->> ").push_bind(key).push(" = ").push_bind(value);
+        }
+        self
+    }
+
+    /// `wallet_id` is a wallet shared with `user_id`, or theirs (see wallet_members.rs)
+    pub fn wallet_member(mut self, user_id: String) -> Self {
+        self.condition("wallet_id", " IN (SELECT wallet_id FROM wallet_members WHERE user_id = ")
+            .push_bind(user_id)
+            .push(")");
+        self
+    }
+
+    /// `(column IS DISTINCT FROM value OR ...)` over the pairs whose value is set
+    ///
+    /// Adds nothing when no value is set.
+    pub fn any_distinct_from(mut self, pairs: Vec<(&'static str, Option<String>)>) -> Self {
+        let mut pairs = pairs.into_iter().filter_map(|(column, value)| value.map(|v| (column, v))).peekable();
+        if pairs.peek().is_none() {
+            return self;
+        }
+
+        self.builder.push(if self.has_where { " AND (" } else { " WHERE (" });
+        self.has_where = true;
+        let mut first = true;
+        for (column, value) in pairs {
+            if !first {
+                self.builder.push(" OR ");
+            }
+            first = false;
+            self.builder.push(column).push(" IS DISTINCT FROM ").push_bind(value);
+        }
+        self.builder.push(")");
+        self
+    }
+
+    /// `(columns) < (a, b)`, or `>` when `ascending`: the rows after a keyset cursor
+    ///
+    /// `columns` is a parenthesized pair, e.g. `"(created_at, id)"`; order by
+    /// the same pair in the same direction.
+    pub fn after<A, B>(mut self, columns: &'static str, ascending: bool, a: A, b: B) -> Self
+    where
+        A: 'args + Encode<'args, Postgres> + Send + Type<Postgres>,
+        B: 'args + Encode<'args, Postgres> + Send + Type<Postgres>,
+    {
+        self.condition(columns, if ascending { " > (" } else { " < (" })
+            .push_bind(a)
+            .push(", ")
+            .push_bind(b)
+            .push(")");
+        self
+    }
+
+    /// `GROUP BY columns`
+    pub fn group_by(mut self, columns: &'static str) -> Self {
+        self.builder.push(" GROUP BY ").push(columns);
+        self
+    }
+
+    /// `ORDER BY order`
+    pub fn order_by(mut self, order: &'static str) -> Self {
+        self.builder.push(" ORDER BY ").push(order);
+        self
+    }
+
+    /// `LIMIT n`
+    pub fn limit(mut self, n: i64) -> Self {
+        self.builder.push(" LIMIT ").push_bind(n);
+        self
+    }
+
+    /// `LIMIT limit OFFSET offset`
+    pub fn limit_offset(mut self, limit: i64, offset: i64) -> Self {
+        self.builder.push(" LIMIT ").push_bind(limit).push(" OFFSET ").push_bind(offset);
+        self
+    }
+
+    /// `LIMIT per_page OFFSET (page - 1) * per_page`
+    pub fn page(mut self, page: PageParams) -> Self {
+        self.builder
+            .push(" LIMIT ")
+            .push_bind(page.limit())
+            .push(" OFFSET ")
+            .push_bind(page.offset());
+        self
+    }
+
+    pub async fn fetch_all<T>(mut self, pool: &PgPool) -> Result<Vec<T>, sqlx::Error>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        self.builder.build_query_as::<T>().fetch_all(pool).await
+    }
+
+    pub async fn fetch_one<T>(mut self, pool: &PgPool) -> Result<T, sqlx::Error>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        self.builder.build_query_as::<T>().fetch_one(pool).await
+    }
+}
+
+// ==================== Transaction Filter ====================
+
+/// Validated transaction list filter
+#[derive(Debug, Clone, Default)]
+pub struct TransactionFilter {
+    pub wallet_id: Option<Uuid>,
+    pub transaction_type: Option<String>,
+    pub category: Option<String>,
+    pub income_source_id: Option<Uuid>,
+    pub min_amount: Option<BigDecimal>,
+    pub max_amount: Option<BigDecimal>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub field: Option<String>,
+    pub value: Option<String>,
+}
+
+impl TransactionFilter {
+    pub fn from_query(
+        config: &AppConfig,
+        query: &TransactionFilterQuery,
+        range: &DateRangeQuery,
+    ) -> Result<Self, String> {
+        limits::validate_date_range(config, range)?;
+
+        if let Some(t) = &query.transaction_type
+            && t != "income"
+            && t != "expense"
+        {
+            return Err("transaction_type must be 'income' or 'expense'".to_string());
+        }
+        if let (Some(min), Some(max)) = (&query.min_amount, &query.max_amount)
+            && min > max
+        {
+            return Err("min_amount must not exceed max_amount".to_string());
+        }
+
+        if query.field.is_some() != query.value.is_some() {
+            return Err("field and value must be given together".to_string());
+        }
+
+        Ok(Self {
+            wallet_id: query.wallet_id,
+            transaction_type: query.transaction_type.clone(),
+            category: query.category.clone().filter(|c| !c.is_empty()),
+            income_source_id: query.income_source_id,
+            min_amount: query.min_amount.clone(),
+            max_amount: query.max_amount.clone(),
+            from: range.from,
+            to: range.to,
+            field: query.field.clone(),
+            value: query.value.clone(),
+        })
+    }
+
+    /// Add this filter's clauses for `user_id`'s transactions
+    pub fn apply<'args>(self, user_id: &str, query: FilterQuery<'args>) -> FilterQuery<'args> {
+        self.clauses(query.eq("user_id", user_id.to_string()))
+    }
+
+    /// Add this filter's clauses for the transactions of every wallet
+    /// `user_id` owns or is a member of
+    pub fn apply_shared<'args>(self, user_id: &str, query: FilterQuery<'args>) -> FilterQuery<'args> {
+        self.clauses(query.wallet_member(user_id.to_string()))
+    }
+
+    fn clauses<'args>(self, query: FilterQuery<'args>) -> FilterQuery<'args> {
+        query
+            .null("deleted_at")
+            .eq_opt("wallet_id", self.wallet_id)
+            .eq_opt("transaction_type", self.transaction_type)
+            .eq_opt("category", self.category)
+            .eq_opt("income_source_id", self.income_source_id)
+            .gte_opt("amount", self.min_amount)
+            .lte_opt("amount", self.max_amount)
+            .gte_opt("created_at", self.from)
+            .lt_opt("created_at", self.to)
+            .json_text_eq_opt("metadata", self.field, self.value)
+    }
+
+    /// Cache key fragment identifying this filter
+    pub fn cache_suffix(&self) -> String {
+        format!(
+            "w={}:t={}:c={}:src={}:min={}:max={}:from={}:to={}:f={}={}",
+            self.wallet_id.map(|w| w.to_string()).unwrap_or_default(),
+            self.transaction_type.as_deref().unwrap_or_default(),
+            self.category.as_deref().unwrap_or_default(),
+            self.income_source_id.map(|s| s.to_string()).unwrap_or_default(),
+            self.min_amount.as_ref().map(|a| a.to_string()).unwrap_or_default(),
+            self.max_amount.as_ref().map(|a| a.to_string()).unwrap_or_default(),
+            self.from.map(|d| d.timestamp()).unwrap_or_default(),
+            self.to.map(|d| d.timestamp()).unwrap_or_default(),
+            self.field.as_deref().unwrap_or_default(),
+            self.value.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+/// Order of the transaction list
+///
+/// Every order ends on `id`, so it is total and usable as a keyset cursor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransactionSort {
+    #[default]
+    Newest,
+    Oldest,
+    AmountDesc,
+    AmountAsc,
+}
+
+impl TransactionSort {
+    pub fn parse(sort: Option<&str>) -> Result<Self, String> {
+        match sort {
+            None | Some("newest") => Ok(TransactionSort::Newest),
+            Some("oldest") => Ok(TransactionSort::Oldest),
+            Some("amount_desc") => Ok(TransactionSort::AmountDesc),
+            Some("amount_asc") => Ok(TransactionSort::AmountAsc),
+            Some(_) => Err("sort must be 'newest', 'oldest', 'amount_desc' or 'amount_asc'".to_string()),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TransactionSort::Newest => "newest",
+            TransactionSort::Oldest => "oldest",
+            TransactionSort::AmountDesc => "amount_desc",
+            TransactionSort::AmountAsc => "amount_asc",
+        }
+    }
+
+    pub fn order_by(self) -> &'static str {
+        match self {
+            TransactionSort::Newest => "created_at DESC, id DESC",
+            TransactionSort::Oldest => "created_at ASC, id ASC",
+            TransactionSort::AmountDesc => "amount DESC, id DESC",
+            TransactionSort::AmountAsc => "amount ASC, id ASC",
+        }
+    }
+
+    pub fn ascending(self) -> bool {
+        matches!(self, TransactionSort::Oldest | TransactionSort::AmountAsc)
+    }
+}
+
+// ==================== Debt Filter ====================
+
+/// Validated debt list filter
+#[derive(Debug, Default)]
+pub struct DebtFilter {
+    pub status: Option<String>,
+    pub wallet_id: Option<Uuid>,
+    pub direction: Option<String>,
+    pub group_id: Option<Uuid>,
+}
+
+impl DebtFilter {
+    pub fn from_query(query: &DebtFilterQuery) -> Result<Self, String> {
+        if let Some(status) = &query.status
+            && !["active", "paid", "cancelled"].contains(&status.as_str())
+        {
+            return Err("status must be 'active', 'paid', or 'cancelled'".to_string());
+        }
+        if let Some(direction) = &query.direction
+            && direction != "payable"
+            && direction != "receivable"
+        {
+            return Err("direction must be 'payable' or 'receivable'".to_string());
+        }
+
+        Ok(Self {
+            status: query.status.clone(),
+            wallet_id: query.wallet_id,
+            direction: query.direction.clone(),
+            group_id: query.group_id,
+        })
+    }
+
+    /// Add this filter's clauses for `user_id`'s debts
+    pub fn apply<'args>(self, user_id: &str, query: FilterQuery<'args>) -> FilterQuery<'args> {
+        query
+            .eq("user_id", user_id.to_string())
+            .eq_opt("status", self.status)
+            .eq_opt("wallet_id", self.wallet_id)
+            .eq_opt("direction", self.direction)
+            .eq_opt("group_id", self.group_id)
+    }
+
+    /// Cache key fragment identifying this filter
+    pub fn cache_suffix(&self) -> String {
+        format!(
+            "s={}:w={}:d={}:g={}",
+            self.status.as_deref().unwrap_or_default(),
+            self.wallet_id.map(|w| w.to_string()).unwrap_or_default(),
+            self.direction.as_deref().unwrap_or_default(),
+            self.group_id.map(|g| g.to_string()).unwrap_or_default()
+        )
+    }
+}
+
+// ==================== Audit Filter ====================
+
+/// Validated audit log filter
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub entity_type: Option<String>,
+    pub entity_id: Option<String>,
+    pub action: Option<String>,
+    pub actor: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl AuditFilter {
+    pub fn from_query(config: &AppConfig, query: &AuditFilterQuery, range: &DateRangeQuery) -> Result<Self, String> {
+        limits::validate_date_range(config, range)?;
+
+        if let Some(entity_type) = &query.entity_type
+            && !["wallet", "transaction", "debt"].contains(&entity_type.as_str())
+        {
+            return Err("entity_type must be 'wallet', 'transaction' or 'debt'".to_string());
+        }
+        if let Some(action) = &query.action
+            && !["create", "update", "delete", "restore"].contains(&action.as_str())
+        {
+            return Err("action must be 'create', 'update', 'delete' or 'restore'".to_string());
+        }
+
+        Ok(Self {
+            entity_type: query.entity_type.clone(),
+            entity_id: query.entity_id.clone().filter(|id| !id.is_empty()),
+            action: query.action.clone(),
+            actor: query.actor.clone().filter(|actor| !actor.is_empty()),
+            from: range.from,
+            to: range.to,
+        })
+    }
+
+    /// Add this filter's clauses for `user_id`'s audit entries
+    pub fn apply<'args>(self, user_id: &str, query: FilterQuery<'args>) -> FilterQuery<'args> {
+        query
+            .eq("user_id", user_id.to_string())
+            .eq_opt("entity_type", self.entity_type)
+            .eq_opt("entity_id", self.entity_id)
+            .eq_opt("action", self.action)
+            .eq_opt("actor", self.actor)
+            .gte_opt("created_at", self.from)
+            .lt_opt("created_at", self.to)
+    }
+}
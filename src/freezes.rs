@@ -0,0 +1,430 @@
+use std::time::Duration;
+
+use actix_web::{web, HttpResponse, ResponseError};
+use chrono::Utc;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::models::{ApiResponse, FreezeWalletRequest, WalletFreeze, WalletFreezeEvent};
+use crate::wallet_members::{self, Permission};
+
+// ==================== WALLET FREEZE ====================
+//
+// A user can freeze a wallet (a lost card, a disputed account) so no new
+// transaction can move its balance until it is unfrozen. Reads keep working,
+// and so do bucket moves, which don't change the wallet balance.
+//
+// Every write that changes a wallet balance calls `ensure_not_frozen` inside
+// its DB transaction after updating the balance: the balance UPDATE holds the
+// wallet row lock that `freeze_wallet` also takes, so a freeze can't slip in
+// between the check and the commit.
+//
+// A freeze may carry an `unfreeze_at` time. It stops applying at that time;
+// `FREEZE_JOB` removes the expired freeze and records the automatic unfreeze.
+// Every freeze/unfreeze is recorded in `wallet_freeze_events` and on the
+// `audit` log target.
+//
+// ============================================================================
+
+pub const FREEZE_JOB: &str = "wallet_auto_unfreeze";
+
+/// How often expired freezes are lifted
+pub const FREEZE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Number of audit records returned by the history endpoint
+const HISTORY_LIMIT: i64 = 100;
+
+const FREEZE_COLUMNS: &str = "wallet_id, user_id, reason, frozen_at, unfreeze_at";
+
+const EVENT_COLUMNS: &str = "id, wallet_id, user_id, action, reason, unfreeze_at, created_at";
+
+/// A freeze row only applies until its `unfreeze_at`
+const ACTIVE_FREEZE: &str = "(unfreeze_at IS NULL OR unfreeze_at > CURRENT_TIMESTAMP)";
+
+// ==================== Handlers ====================
+
+/// Freeze a wallet, or update the reason/unfreeze time of an existing freeze
+pub async fn freeze_wallet(
+    path: web::Path<(String, String)>,
+    req: web::Json<FreezeWalletRequest>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let (user_id, wallet_id) = path.into_inner();
+    let reason = req.reason.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    if reason.is_some_and(|r| r.chars().count() > 200) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<WalletFreeze>::error("Reason must be at most 200 characters".to_string()));
+    }
+    let now = Utc::now();
+    if req.unfreeze_at.is_some_and(|at| at <= now) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<WalletFreeze>::error("unfreeze_at must be in the future".to_string()));
+    }
+    if let Err(e) =
+        wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::Manage).await
+    {
+        return e.error_response();
+    }
+
+    match insert_freeze(db.get_ref(), &user_id, &wallet_id, reason, &req).await {
+        Ok(Some(freeze)) => {
+            log::info!(
+                target: "audit",
+                "wallet frozen wallet_id={} user_id={} unfreeze_at={:?}",
+                freeze.wallet_id,
+                freeze.user_id,
+                freeze.unfreeze_at
+            );
+            HttpResponse::Ok().json(ApiResponse::success(freeze))
+        }
+        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<WalletFreeze>::error("Wallet not found".to_string())),
+        Err(e) => {
+            log::error!("Error freezing wallet: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<WalletFreeze>::error("Failed to freeze wallet".to_string()))
+        }
+    }
+}
+
+/// Lift a wallet's freeze
+pub async fn unfreeze_wallet(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, wallet_id) = path.into_inner();
+    if let Err(e) =
+        wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::Manage).await
+    {
+        return e.error_response();
+    }
+
+    match delete_freeze(db.get_ref(), &user_id, &wallet_id).await {
+        Ok(Some(wallet_id)) => {
+            log::info!(target: "audit", "wallet unfrozen wallet_id={} user_id={}", wallet_id, user_id);
+            HttpResponse::NoContent().finish()
+        }
+        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<String>::error("Wallet is not frozen".to_string())),
+        Err(e) => {
+            log::error!("Error unfreezing wallet: {}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<String>::error("Failed to unfreeze wallet".to_string()))
+        }
+    }
+}
+
+/// The wallet's active freeze
+pub async fn get_wallet_freeze(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, wallet_id) = path.into_inner();
+    let access =
+        match wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::View).await {
+            Ok(access) => access,
+            Err(e) => return e.error_response(),
+        };
+
+    let result = sqlx::query_as::<_, WalletFreeze>(&format!(
+        "SELECT {} FROM wallet_freezes WHERE wallet_id::text = $1 AND user_id = $2 AND {}",
+        FREEZE_COLUMNS, ACTIVE_FREEZE
+    ))
+    .bind(&wallet_id)
+    .bind(&access.owner_id)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(freeze)) => HttpResponse::Ok().json(ApiResponse::success(freeze)),
+        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<WalletFreeze>::error("Wallet is not frozen".to_string())),
+        Err(e) => {
+            log::error!("Error fetching wallet freeze: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<WalletFreeze>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Freeze/unfreeze audit records of a wallet, newest first
+pub async fn get_freeze_history(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, wallet_id) = path.into_inner();
+    let access =
+        match wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::View).await {
+            Ok(access) => access,
+            Err(e) => return e.error_response(),
+        };
+
+    let result = sqlx::query_as::<_, WalletFreezeEvent>(&format!(
+        "SELECT {} FROM wallet_freeze_events
+         WHERE wallet_id::text = $1 AND user_id = $2
+         ORDER BY created_at DESC
+         LIMIT $3",
+        EVENT_COLUMNS
+    ))
+    .bind(&wallet_id)
+    .bind(&access.owner_id)
+    .bind(HISTORY_LIMIT)
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(events) => HttpResponse::Ok().json(ApiResponse::success(events)),
+        Err(e) => {
+            log::error!("Error fetching freeze history: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<WalletFreezeEvent>>::error("Database error".to_string()))
+        }
+    }
+}
+
+// ==================== Enforcement ====================
+
+#[derive(Debug)]
+pub enum FreezeError {
+    Frozen(Uuid),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for FreezeError {
+    fn from(e: sqlx::Error) -> Self {
+        FreezeError::Database(e)
+    }
+}
+
+impl FreezeError {
+    /// Map to an HTTP response without leaking database internals
+    pub fn to_response<T: serde::Serialize>(&self) -> HttpResponse {
+        match self {
+            FreezeError::Frozen(wallet_id) => HttpResponse::Locked()
+                .json(ApiResponse::<T>::error(format!("Wallet {} is frozen", wallet_id))),
+            FreezeError::Database(e) => {
+                log::error!("Wallet freeze database error: {}", e);
+                HttpResponse::InternalServerError().json(ApiResponse::<T>::error("Database error".to_string()))
+            }
+        }
+    }
+}
+
+/// Reject the pending balance change if the wallet is frozen
+///
+/// Call after the balance UPDATE, in the same DB transaction.
+pub async fn ensure_not_frozen(conn: &mut PgConnection, wallet_id: Uuid) -> Result<(), FreezeError> {
+    let frozen: Option<(Uuid,)> = sqlx::query_as(&format!(
+        "SELECT wallet_id FROM wallet_freezes WHERE wallet_id = $1 AND {}",
+        ACTIVE_FREEZE
+    ))
+    .bind(wallet_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    match frozen {
+        Some(_) => Err(FreezeError::Frozen(wallet_id)),
+        None => Ok(()),
+    }
+}
+
+/// Wallets of `user_id` that are currently frozen
+pub async fn frozen_wallet_ids(pool: &PgPool, user_id: &str) -> Result<Vec<Uuid>, sqlx::Error> {
+    let rows: Vec<(Uuid,)> = sqlx::query_as(&format!(
+        "SELECT wallet_id FROM wallet_freezes WHERE user_id = $1 AND {}",
+        ACTIVE_FREEZE
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
+
+// ==================== Auto-Unfreeze Job ====================
+
+/// Remove freezes past their `unfreeze_at`, recording each; returns how many were lifted
+pub async fn lift_expired_freezes(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "WITH lifted AS (
+             DELETE FROM wallet_freezes WHERE unfreeze_at <= CURRENT_TIMESTAMP
+             RETURNING wallet_id, user_id, reason, unfreeze_at
+         )
+         INSERT INTO wallet_freeze_events (id, wallet_id, user_id, action, reason, unfreeze_at, created_at)
+         SELECT gen_random_uuid(), wallet_id, user_id, 'auto_unfreeze', reason, unfreeze_at, CURRENT_TIMESTAMP
+         FROM lifted",
+    )
+    .execute(pool)
+    .await?;
+
+    let lifted = result.rows_affected();
+    if lifted > 0 {
+        log::info!(target: "audit", "wallet freezes lifted automatically count={}", lifted);
+    }
+    Ok(lifted)
+}
+
+// ==================== Database Functions ====================
+
+async fn insert_freeze(
+    pool: &PgPool,
+    user_id: &str,
+    wallet_id: &str,
+    reason: Option<&str>,
+    req: &FreezeWalletRequest,
+) -> Result<Option<WalletFreeze>, sqlx::Error> {
+    let mut db_tx = pool.begin().await?;
+
+    // Same lock the balance updates take (see module comment)
+    let wallet: Option<(Uuid,)> =
+        sqlx::query_as("SELECT id FROM wallets WHERE id::text = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE")
+            .bind(wallet_id)
+            .bind(user_id)
+            .fetch_optional(&mut *db_tx)
+            .await?;
+    let Some((wallet_id,)) = wallet else {
+        return Ok(None);
+    };
+
+    // Re-freezing keeps the original freeze time unless the old freeze had already expired
+    let freeze = sqlx::query_as::<_, WalletFreeze>(&format!(
+        "INSERT INTO wallet_freezes (wallet_id, user_id, reason, frozen_at, unfreeze_at)
+         VALUES ($1, $2, $3, CURRENT_TIMESTAMP, $4)
+         ON CONFLICT (wallet_id) DO UPDATE
+         SET reason = EXCLUDED.reason,
+             unfreeze_at = EXCLUDED.unfreeze_at,
+             frozen_at = CASE WHEN wallet_freezes.unfreeze_at <= EXCLUDED.frozen_at
+                              THEN EXCLUDED.frozen_at ELSE wallet_freezes.frozen_at END
+         RETURNING {}",
+        FREEZE_COLUMNS
+    ))
+    .bind(wallet_id)
+    .bind(user_id)
+    .bind(reason)
+    .bind(req.unfreeze_at)
+    .fetch_one(&mut *db_tx)
+    .await?;
+
+    record_event(&mut db_tx, wallet_id, user_id, "freeze", reason, req.unfreeze_at).await?;
+
+    db_tx.commit().await?;
+    Ok(Some(freeze))
+}
+
+async fn delete_freeze(pool: &PgPool, user_id: &str, wallet_id: &str) -> Result<Option<Uuid>, sqlx::Error> {
+    let mut db_tx = pool.begin().await?;
+
+    let lifted: Option<(Uuid,)> = sqlx::query_as(&format!(
+        "DELETE FROM wallet_freezes WHERE wallet_id::text = $1 AND user_id = $2 AND {} RETURNING wallet_id",
+        ACTIVE_FREEZE
+    ))
+    .bind(wallet_id)
+    .bind(user_id)
+    .fetch_optional(&mut *db_tx)
+    .await?;
+    let Some((wallet_id,)) = lifted else {
+        return Ok(None);
+    };
+
+    record_event(&mut db_tx, wallet_id, user_id, "unfreeze", None, None).await?;
+
+    db_tx.commit().await?;
+    Ok(Some(wallet_id))
+}
+
+async fn record_event(
+    conn: &mut PgConnection,
+    wallet_id: Uuid,
+    user_id: &str,
+    action: &str,
+    reason: Option<&str>,
+    unfreeze_at: Option<chrono::DateTime<Utc>>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO wallet_freeze_events (id, wallet_id, user_id, action, reason, unfreeze_at, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(wallet_id)
+    .bind(user_id)
+    .bind(action)
+    .bind(reason)
+    .bind(unfreeze_at)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+
+    use super::*;
+    use crate::test_support;
+
+    async fn remove_freezes(pool: &PgPool, wallet_id: Uuid) {
+        let _ = sqlx::query("DELETE FROM wallet_freeze_events WHERE wallet_id = $1").bind(wallet_id).execute(pool).await;
+        let _ = sqlx::query("DELETE FROM wallet_freezes WHERE wallet_id = $1").bind(wallet_id).execute(pool).await;
+    }
+
+    async fn is_frozen(pool: &PgPool, wallet_id: Uuid) -> bool {
+        let mut conn = pool.acquire().await.unwrap();
+        match ensure_not_frozen(&mut conn, wallet_id).await {
+            Ok(()) => false,
+            Err(FreezeError::Frozen(id)) => id == wallet_id,
+            Err(FreezeError::Database(e)) => panic!("{}", e),
+        }
+    }
+
+    #[tokio::test]
+    async fn a_frozen_wallet_refuses_balance_changes_until_unfrozen() {
+        let Some(pool) = test_support::database("a_frozen_wallet_refuses_balance_changes_until_unfrozen").await else {
+            return;
+        };
+        let user_id = format!("test-freeze-{}", Uuid::new_v4());
+        let wallet_id = test_support::create_wallet(&pool, &user_id, "10.00").await;
+        let path = || web::Path::from((user_id.clone(), wallet_id.to_string()));
+        let request = FreezeWalletRequest { reason: Some("Lost card".to_string()), unfreeze_at: None };
+
+        let frozen = freeze_wallet(path(), web::Json(request), web::Data::new(pool.clone())).await.status();
+        let while_frozen = is_frozen(&pool, wallet_id).await;
+        let unfrozen = unfreeze_wallet(path(), web::Data::new(pool.clone())).await.status();
+        let after = is_frozen(&pool, wallet_id).await;
+        let (events,): (Vec<String>,) = sqlx::query_as(
+            "SELECT array_agg(action ORDER BY created_at) FROM wallet_freeze_events WHERE wallet_id = $1",
+        )
+        .bind(wallet_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        remove_freezes(&pool, wallet_id).await;
+        test_support::remove_wallet(&pool, wallet_id).await;
+
+        assert_eq!(frozen, StatusCode::OK);
+        assert!(while_frozen);
+        assert_eq!(unfrozen, StatusCode::NO_CONTENT);
+        assert!(!after);
+        assert_eq!(events, ["freeze", "unfreeze"]);
+    }
+
+    #[tokio::test]
+    async fn an_expired_freeze_stops_applying_and_is_lifted() {
+        let Some(pool) = test_support::database("an_expired_freeze_stops_applying_and_is_lifted").await else {
+            return;
+        };
+        let user_id = format!("test-freeze-{}", Uuid::new_v4());
+        let wallet_id = test_support::create_wallet(&pool, &user_id, "10.00").await;
+        sqlx::query(
+            "INSERT INTO wallet_freezes (wallet_id, user_id, reason, frozen_at, unfreeze_at)
+             VALUES ($1, $2, NULL, CURRENT_TIMESTAMP - INTERVAL '2 hours', CURRENT_TIMESTAMP - INTERVAL '1 hour')",
+        )
+        .bind(wallet_id)
+        .bind(&user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let while_expired = is_frozen(&pool, wallet_id).await;
+        let lifted = lift_expired_freezes(&pool).await.unwrap();
+        let (rows, auto_unfreezes): (i64, i64) = sqlx::query_as(
+            "SELECT (SELECT COUNT(*) FROM wallet_freezes WHERE wallet_id = $1),
+                    (SELECT COUNT(*) FROM wallet_freeze_events WHERE wallet_id = $1 AND action = 'auto_unfreeze')",
+        )
+        .bind(wallet_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        remove_freezes(&pool, wallet_id).await;
+        test_support::remove_wallet(&pool, wallet_id).await;
+
+        assert!(!while_expired);
+        assert!(lifted >= 1);
+        assert_eq!((rows, auto_unfreezes), (0, 1));
+    }
+}
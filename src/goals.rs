@@ -0,0 +1,523 @@
+use std::collections::HashMap;
+
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use bigdecimal::ToPrimitive;
+use chrono::{DateTime, Days, Duration, NaiveDate, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::audit::{self, AuditContext};
+use crate::cache::{invalidate_user_cache, CacheBackend};
+use crate::currencies;
+use crate::error::AppError;
+use crate::freezes;
+use crate::hooks::{self, HookContext, WalletsDebited};
+use crate::models::{
+    ApiResponse, CreateContributionRequest, CreateGoalRequest, GoalContribution, GoalDetail, GoalProgress,
+    SavingsGoal, UpdateGoalRequest, Wallet,
+};
+use crate::money;
+use crate::routes::ScopedRoutes;
+use crate::standing_orders::{self, WALLET_COLUMNS};
+use crate::telemetry;
+
+// ==================== SAVINGS GOALS ====================
+//
+// A savings goal is the planning counterpart of a debt: `target_amount` to
+// put aside by `deadline`, optionally into a linked wallet. The goal is in
+// the linked wallet's currency, or in the one given at creation (default:
+// the user's base currency).
+//
+// A contribution moves money from a source wallet in one database
+// transaction, like a transfer (see transfers.rs): an expense on the source
+// (category "transfer") and, for a goal with a linked wallet, an income on
+// that wallet. Without a linked wallet the money leaves the tracked wallets.
+// The source must cover the amount as it would an expense, and neither
+// wallet may be frozen. A source in another currency contributes the amount
+// converted at the current rate. Deleting a contribution's expense refunds
+// the source like any expense deletion, and the contribution stops counting.
+//
+// Progress is the sum of contributions against the target. The projected
+// completion date extrapolates the contribution velocity: what was
+// contributed over the last `VELOCITY_DAYS` days (or since the goal was
+// created, counting at least `MIN_VELOCITY_DAYS`), per day, applied to what
+// remains.
+//
+// ============================================================================
+
+/// Maximum number of goals per user
+const MAX_GOALS_PER_USER: i64 = 50;
+
+/// Window the contribution velocity is measured over, in days
+const VELOCITY_DAYS: i64 = 90;
+
+/// Shortest window, so one early contribution isn't read as a daily habit
+const MIN_VELOCITY_DAYS: i64 = 30;
+
+/// Number of contributions returned with a goal
+const CONTRIBUTION_LIMIT: i64 = 100;
+
+const GOAL_COLUMNS: &str = "id, user_id, name, target_amount, currency, deadline, wallet_id, created_at, updated_at";
+
+const CONTRIBUTION_COLUMNS: &str = "id, goal_id, user_id, from_wallet_id, amount, goal_amount, exchange_rate, \
+     out_transaction_id, in_transaction_id, created_at";
+
+/// Only contributions whose expense still stands count (`c` is `goal_contributions`)
+const LIVE_CONTRIBUTION: &str =
+    "EXISTS (SELECT 1 FROM transactions t WHERE t.id = c.out_transaction_id AND t.deleted_at IS NULL)";
+
+// ==================== CRUD Handlers ====================
+
+/// List a user's goals with their progress, by deadline
+pub async fn get_user_goals(user_id: web::Path<String>, db: web::Data<PgPool>) -> Result<HttpResponse, AppError> {
+    let user_id = user_id.into_inner();
+
+    let goals = fetch_goals(db.get_ref(), &user_id).await.map_err(AppError::database("Failed to fetch goals"))?;
+    let totals = fetch_totals(db.get_ref(), &user_id, None)
+        .await
+        .map_err(AppError::database("Failed to fetch goals"))?;
+
+    let now = Utc::now();
+    let goals: Vec<GoalProgress> = goals
+        .into_iter()
+        .map(|goal| {
+            let (saved, recent) = totals.get(&goal.id).cloned().unwrap_or_default();
+            progress(goal, saved, recent, now)
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(ApiResponse::success(goals)))
+}
+
+/// Get a goal with its progress and latest contributions
+pub async fn get_goal(path: web::Path<(String, Uuid)>, db: web::Data<PgPool>) -> Result<HttpResponse, AppError> {
+    let (user_id, goal_id) = path.into_inner();
+
+    let goal = fetch_goal_by_id(db.get_ref(), goal_id, &user_id)
+        .await
+        .map_err(AppError::or_not_found("Goal not found"))?;
+    let totals = fetch_totals(db.get_ref(), &user_id, Some(goal.id))
+        .await
+        .map_err(AppError::database("Failed to fetch goal"))?;
+    let contributions = fetch_contributions(db.get_ref(), goal.id)
+        .await
+        .map_err(AppError::database("Failed to fetch goal"))?;
+
+    let (saved, recent) = totals.get(&goal.id).cloned().unwrap_or_default();
+    let progress = progress(goal, saved, recent, Utc::now());
+    Ok(HttpResponse::Ok().json(ApiResponse::success(GoalDetail { progress, contributions })))
+}
+
+/// Create a savings goal
+pub async fn create_goal(req: web::Json<CreateGoalRequest>, db: web::Data<PgPool>) -> Result<HttpResponse, AppError> {
+    let name = validate_name(&req.name).map_err(AppError::Validation)?;
+    validate_target(&req.target_amount).map_err(AppError::Validation)?;
+    validate_deadline(req.deadline).map_err(AppError::Validation)?;
+
+    // A linked goal is kept in its wallet's currency
+    let currency = match req.wallet_id {
+        Some(wallet_id) => {
+            let (currency,): (String,) = sqlx::query_as(
+                "SELECT currency FROM wallets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+            )
+            .bind(wallet_id)
+            .bind(&req.user_id)
+            .fetch_optional(db.get_ref())
+            .await
+            .map_err(AppError::database("Failed to create goal"))?
+            .ok_or_else(|| AppError::Validation(format!("Wallet {} not found", wallet_id)))?;
+            if let Some(requested) = req.currency.as_deref().map(currencies::normalize_code)
+                && requested != currency
+            {
+                return Err(AppError::Validation(format!(
+                    "A goal linked to a wallet is in the wallet's currency ({})",
+                    currency
+                )));
+            }
+            Some(currency)
+        }
+        None => req.currency.as_deref().map(currencies::normalize_code),
+    };
+    if let Some(currency) = &currency
+        && !currencies::is_supported(db.get_ref(), currency)
+            .await
+            .map_err(AppError::database("Failed to create goal"))?
+    {
+        return Err(AppError::Validation(format!("Unsupported currency {}", currency)));
+    }
+
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM savings_goals WHERE user_id = $1")
+        .bind(&req.user_id)
+        .fetch_one(db.get_ref())
+        .await
+        .map_err(AppError::database("Failed to create goal"))?;
+    if count >= MAX_GOALS_PER_USER {
+        return Err(AppError::Validation(format!("A user can have at most {} goals", MAX_GOALS_PER_USER)));
+    }
+
+    let goal = sqlx::query_as::<_, SavingsGoal>(&format!(
+        "INSERT INTO savings_goals (id, user_id, name, target_amount, currency, deadline, wallet_id, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, COALESCE($5, base_currency($2)), $6, $7, $8, $8)
+         RETURNING {}",
+        GOAL_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(name)
+    .bind(&req.target_amount)
+    .bind(&currency)
+    .bind(req.deadline)
+    .bind(req.wallet_id)
+    .bind(Utc::now())
+    .fetch_one(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to create goal"))?;
+
+    let zero = BigDecimal::from(0);
+    Ok(HttpResponse::Created().json(ApiResponse::success(progress(goal, zero.clone(), zero, Utc::now()))))
+}
+
+/// Rename a goal or change its target or deadline
+pub async fn update_goal(
+    path: web::Path<(String, Uuid)>,
+    req: web::Json<UpdateGoalRequest>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, goal_id) = path.into_inner();
+
+    let name = req.name.as_deref().map(validate_name).transpose().map_err(AppError::Validation)?;
+    if let Some(target) = &req.target_amount {
+        validate_target(target).map_err(AppError::Validation)?;
+    }
+    if let Some(deadline) = req.deadline {
+        validate_deadline(deadline).map_err(AppError::Validation)?;
+    }
+
+    let goal = sqlx::query_as::<_, SavingsGoal>(&format!(
+        "UPDATE savings_goals
+         SET name = COALESCE($1, name),
+             target_amount = COALESCE($2, target_amount),
+             deadline = COALESCE($3, deadline),
+             updated_at = $4
+         WHERE id = $5 AND user_id = $6
+         RETURNING {}",
+        GOAL_COLUMNS
+    ))
+    .bind(name)
+    .bind(&req.target_amount)
+    .bind(req.deadline)
+    .bind(Utc::now())
+    .bind(goal_id)
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to update goal"))?
+    .ok_or_else(|| AppError::NotFound("Goal not found".to_string()))?;
+
+    let totals = fetch_totals(db.get_ref(), &user_id, Some(goal.id))
+        .await
+        .map_err(AppError::database("Failed to update goal"))?;
+    let (saved, recent) = totals.get(&goal.id).cloned().unwrap_or_default();
+    Ok(HttpResponse::Ok().json(ApiResponse::success(progress(goal, saved, recent, Utc::now()))))
+}
+
+/// Delete a goal and its contribution records; the money moved stays where it is
+pub async fn delete_goal(path: web::Path<(String, Uuid)>, db: web::Data<PgPool>) -> Result<HttpResponse, AppError> {
+    let (user_id, goal_id) = path.into_inner();
+
+    let result = sqlx::query("DELETE FROM savings_goals WHERE id = $1 AND user_id = $2")
+        .bind(goal_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await
+        .map_err(AppError::database("Failed to delete goal"))?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Goal not found".to_string()));
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// ==================== Contributions ====================
+
+/// Move money from a wallet toward a goal (into its linked wallet, if any)
+pub async fn contribute(
+    path: web::Path<(String, Uuid)>,
+    req: web::Json<CreateContributionRequest>,
+    context: AuditContext,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, goal_id) = path.into_inner();
+    if req.amount <= BigDecimal::from(0) {
+        return Err(AppError::Validation("Amount must be greater than 0".to_string()));
+    }
+
+    let mut db_tx = db.begin().await?;
+    audit::attach(&mut db_tx, &context).await?;
+
+    let goal = sqlx::query_as::<_, SavingsGoal>(&format!(
+        "SELECT {} FROM savings_goals WHERE id = $1 AND user_id = $2 FOR UPDATE",
+        GOAL_COLUMNS
+    ))
+    .bind(goal_id)
+    .bind(&user_id)
+    .fetch_optional(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to fetch goal"))?
+    .ok_or_else(|| AppError::NotFound("Goal not found".to_string()))?;
+    if goal.wallet_id == Some(req.from_wallet_id) {
+        return Err(AppError::Validation("Contribute from a wallet other than the goal's".to_string()));
+    }
+
+    let wallet_ids: Vec<Uuid> = std::iter::once(req.from_wallet_id).chain(goal.wallet_id).collect();
+    let wallets = lock_wallets(&mut db_tx, &user_id, &wallet_ids)
+        .await
+        .map_err(AppError::database("Failed to fetch wallets"))?;
+    if let Some(missing) = wallet_ids.iter().find(|id| !wallets.iter().any(|w| w.id == **id)) {
+        return Err(AppError::Validation(format!("Wallet {} not found", missing)));
+    }
+    let from = wallets.iter().find(|w| w.id == req.from_wallet_id).expect("source wallet was locked");
+    let to = goal.wallet_id.and_then(|id| wallets.iter().find(|w| w.id == id));
+
+    if let Some(available) = standing_orders::sendable(&mut db_tx, from)
+        .await
+        .map_err(AppError::database("Failed to check available funds"))?
+        && req.amount > available
+    {
+        return Err(AppError::Validation(format!(
+            "Insufficient balance. Available: {}, Required: {}",
+            available, req.amount
+        )));
+    }
+
+    let conversion = currencies::convert(&mut *db_tx, &req.amount, &from.currency, &goal.currency)
+        .await
+        .map_err(AppError::database("Failed to convert contribution"))?
+        .ok_or_else(|| AppError::Conflict(currencies::no_rate(&from.currency, &goal.currency)))?;
+
+    let description = format!("Savings goal: {}", goal.name);
+    let now = Utc::now();
+    let out_transaction_id = standing_orders::post_transfer_leg(
+        &mut db_tx,
+        &user_id,
+        from.id,
+        "expense",
+        &req.amount,
+        &description,
+        now,
+    )
+    .await
+    .map_err(AppError::database("Failed to contribute"))?;
+    let in_transaction_id = match to {
+        Some(to) => Some(
+            standing_orders::post_transfer_leg(
+                &mut db_tx,
+                &user_id,
+                to.id,
+                "income",
+                &conversion.amount,
+                &description,
+                now,
+            )
+            .await
+            .map_err(AppError::database("Failed to contribute"))?,
+        ),
+        None => None,
+    };
+    for wallet in &wallets {
+        freezes::ensure_not_frozen(&mut db_tx, wallet.id).await?;
+    }
+
+    let contribution = sqlx::query_as::<_, GoalContribution>(&format!(
+        "INSERT INTO goal_contributions
+             (id, goal_id, user_id, from_wallet_id, amount, goal_amount, exchange_rate,
+              out_transaction_id, in_transaction_id, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+         RETURNING {}",
+        CONTRIBUTION_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(goal.id)
+    .bind(&user_id)
+    .bind(from.id)
+    .bind(&req.amount)
+    .bind(&conversion.amount)
+    .bind(&conversion.rate)
+    .bind(out_transaction_id)
+    .bind(in_transaction_id)
+    .bind(now)
+    .fetch_one(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to contribute"))?;
+
+    db_tx.commit().await.map_err(AppError::database("Failed to contribute"))?;
+
+    let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+    let cx = HookContext { pool: db.get_ref(), cache: cache.get_ref() };
+    hooks::wallets_debited(&cx, WalletsDebited { owner_id: &user_id, wallet_ids: &[from.id] }).await;
+
+    Ok(HttpResponse::Created().json(ApiResponse::success(contribution)))
+}
+
+// ==================== Progress ====================
+
+/// `goal`'s progress given what was `saved` in all and `recent`ly (over the velocity window)
+fn progress(goal: SavingsGoal, saved: BigDecimal, recent: BigDecimal, now: DateTime<Utc>) -> GoalProgress {
+    let zero = BigDecimal::from(0);
+    let remaining = (&goal.target_amount - &saved).max(zero.clone());
+    let percent = (&saved * BigDecimal::from(100) / &goal.target_amount).to_f64().unwrap_or(0.0);
+
+    let window = (now - goal.created_at).num_days().clamp(MIN_VELOCITY_DAYS, VELOCITY_DAYS);
+    let monthly_velocity = money::prorate(&recent, 30, window);
+    let projected_completion = projected_completion(&remaining, &recent, window, now.date_naive());
+    let on_track = remaining == zero || projected_completion.is_some_and(|date| date <= goal.deadline);
+
+    GoalProgress {
+        goal,
+        saved,
+        remaining,
+        progress_percent: (percent * 10.0).round() / 10.0,
+        monthly_velocity,
+        projected_completion,
+        on_track,
+    }
+}
+
+/// The day `remaining` is reached at `recent / window` a day; `None` if reached or without recent contributions
+fn projected_completion(
+    remaining: &BigDecimal,
+    recent: &BigDecimal,
+    window: i64,
+    today: NaiveDate,
+) -> Option<NaiveDate> {
+    let zero = BigDecimal::from(0);
+    if *remaining == zero || *recent <= zero {
+        return None;
+    }
+    let days = (remaining * BigDecimal::from(window) / recent).to_f64()?.ceil();
+    today.checked_add_days(Days::new(days as u64))
+}
+
+// ==================== Validation ====================
+
+fn validate_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err("name must be 1-100 characters".to_string());
+    }
+    Ok(name)
+}
+
+fn validate_target(target: &BigDecimal) -> Result<(), String> {
+    if *target <= BigDecimal::from(0) {
+        return Err("target_amount must be greater than 0".to_string());
+    }
+    Ok(())
+}
+
+fn validate_deadline(deadline: NaiveDate) -> Result<(), String> {
+    if deadline < Utc::now().date_naive() {
+        return Err("deadline must not be in the past".to_string());
+    }
+    Ok(())
+}
+
+// ==================== Database Queries ====================
+
+async fn fetch_goals(pool: &PgPool, user_id: &str) -> Result<Vec<SavingsGoal>, sqlx::Error> {
+    telemetry::sql(
+        "savings_goals",
+        "select",
+        sqlx::query_as::<_, SavingsGoal>(&format!(
+            "SELECT {} FROM savings_goals WHERE user_id = $1 ORDER BY deadline, created_at",
+            GOAL_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(pool),
+    )
+    .await
+}
+
+async fn fetch_goal_by_id(pool: &PgPool, goal_id: Uuid, user_id: &str) -> Result<SavingsGoal, sqlx::Error> {
+    telemetry::sql_one(
+        "savings_goals",
+        "select",
+        sqlx::query_as::<_, SavingsGoal>(&format!(
+            "SELECT {} FROM savings_goals WHERE id = $1 AND user_id = $2",
+            GOAL_COLUMNS
+        ))
+        .bind(goal_id)
+        .bind(user_id)
+        .fetch_one(pool),
+    )
+    .await
+}
+
+/// Contributed per goal (of the user, or just `goal_id`): in all, and over the velocity window
+async fn fetch_totals(
+    pool: &PgPool,
+    user_id: &str,
+    goal_id: Option<Uuid>,
+) -> Result<HashMap<Uuid, (BigDecimal, BigDecimal)>, sqlx::Error> {
+    let rows: Vec<(Uuid, BigDecimal, BigDecimal)> = telemetry::sql(
+        "goal_contributions",
+        "select",
+        sqlx::query_as(&format!(
+            "SELECT c.goal_id, SUM(c.goal_amount),
+                    COALESCE(SUM(c.goal_amount) FILTER (WHERE c.created_at >= $3), 0)
+             FROM goal_contributions c
+             WHERE c.user_id = $1 AND ($2::uuid IS NULL OR c.goal_id = $2) AND {}
+             GROUP BY c.goal_id",
+            LIVE_CONTRIBUTION
+        ))
+        .bind(user_id)
+        .bind(goal_id)
+        .bind(Utc::now() - Duration::days(VELOCITY_DAYS))
+        .fetch_all(pool),
+    )
+    .await?;
+    Ok(rows.into_iter().map(|(goal_id, saved, recent)| (goal_id, (saved, recent))).collect())
+}
+
+async fn fetch_contributions(pool: &PgPool, goal_id: Uuid) -> Result<Vec<GoalContribution>, sqlx::Error> {
+    telemetry::sql(
+        "goal_contributions",
+        "select",
+        sqlx::query_as::<_, GoalContribution>(&format!(
+            "SELECT {} FROM goal_contributions c WHERE c.goal_id = $1 AND {} ORDER BY c.created_at DESC LIMIT $2",
+            CONTRIBUTION_COLUMNS, LIVE_CONTRIBUTION
+        ))
+        .bind(goal_id)
+        .bind(CONTRIBUTION_LIMIT)
+        .fetch_all(pool),
+    )
+    .await
+}
+
+async fn lock_wallets(conn: &mut PgConnection, user_id: &str, ids: &[Uuid]) -> Result<Vec<Wallet>, sqlx::Error> {
+    sqlx::query_as::<_, Wallet>(&format!(
+        "SELECT {} FROM wallets WHERE id = ANY($1) AND user_id = $2 AND deleted_at IS NULL ORDER BY id FOR UPDATE",
+        WALLET_COLUMNS
+    ))
+    .bind(ids)
+    .bind(user_id)
+    .fetch_all(conn)
+    .await
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/goals")
+        .user(Method::GET, "/user/{user_id}", get_user_goals)
+        .create("", create_goal)
+        .user(Method::GET, "/{user_id}/{goal_id}", get_goal)
+        .user(Method::PUT, "/{user_id}/{goal_id}", update_goal)
+        .user(Method::DELETE, "/{user_id}/{goal_id}", delete_goal)
+        .user(Method::POST, "/{user_id}/{goal_id}/contributions", contribute)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
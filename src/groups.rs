@@ -0,0 +1,477 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::cache::{get_or_set_cache, invalidate_user_cache, user_cache_key, CacheBackend};
+use crate::currencies;
+use crate::models::{
+    ApiResponse, AssignGroupRequest, CreateGroupRequest, GroupAssignment, GroupNetWorth, NetWorth, NetWorthQuery,
+    PortfolioGroup, UpdateGroupRequest,
+};
+use crate::routes::ScopedRoutes;
+use crate::wallet_types;
+use crate::wallets;
+
+// ==================== PORTFOLIO GROUPS ====================
+//
+// Users sort their wallets and debts into groups of their own ("Business",
+// "Personal", "Family") to look at each part of their finances on its own.
+// A wallet or debt is in at most one group, so group totals add up to the
+// overall total; deleting a group leaves its wallets and debts ungrouped.
+//
+// - The wallet and debt lists take `?group_id=` (see wallets.rs, filters.rs).
+// - `GET /api/groups/{user_id}/net-worth` totals assets and liabilities (by
+//   wallet template, see wallet_types.rs) and outstanding active debts
+//   (principal less settlements, see splits.rs) overall and per group, less
+//   card installments not billed yet (see installments.rs).
+//
+// A debt linked to a liability wallet counts both as a payable and as the
+// wallet's liability; the totals don't try to tell whether one mirrors the
+// other.
+//
+// Totals are in the user's base currency: wallet balances are converted at
+// the current rate (409 while one has no rate, see currencies.rs), and debt
+// amounts, which carry no currency, are taken as already in it.
+//
+// ============================================================================
+
+/// Maximum number of groups per user
+const MAX_GROUPS_PER_USER: i64 = 20;
+
+/// Maximum number of wallets and debts moved by one assignment
+const MAX_ASSIGN_ITEMS: usize = 200;
+
+/// Name of the totals of wallets and debts in no group
+const UNGROUPED: &str = "Ungrouped";
+
+const GROUP_COLUMNS: &str = "id, user_id, name, created_at, updated_at";
+
+// ==================== Handlers ====================
+
+/// List a user's groups by name (with caching)
+pub async fn get_user_groups(
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, "groups").await;
+
+    let result = get_or_set_cache(cache.get_ref(), &cache_key, fetch_groups(db.get_ref(), &user_id)).await;
+
+    match result {
+        Ok(groups) => HttpResponse::Ok().json(ApiResponse::success(groups)),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<Vec<PortfolioGroup>>::error(e.to_string())),
+    }
+}
+
+/// Create a group
+pub async fn create_group(
+    req: web::Json<CreateGroupRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let name = req.name.trim();
+    if let Err(msg) = validate_name(name) {
+        return HttpResponse::BadRequest().json(ApiResponse::<PortfolioGroup>::error(msg));
+    }
+
+    let count: Result<(i64,), sqlx::Error> = sqlx::query_as("SELECT COUNT(*) FROM portfolio_groups WHERE user_id = $1")
+        .bind(&req.user_id)
+        .fetch_one(db.get_ref())
+        .await;
+    match count {
+        Ok((count,)) if count >= MAX_GROUPS_PER_USER => {
+            return HttpResponse::BadRequest().json(ApiResponse::<PortfolioGroup>::error(format!(
+                "A user can have at most {} groups",
+                MAX_GROUPS_PER_USER
+            )));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Error counting groups: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<PortfolioGroup>::error("Database error".to_string()));
+        }
+    }
+
+    let result = sqlx::query_as::<_, PortfolioGroup>(&format!(
+        "INSERT INTO portfolio_groups (id, user_id, name, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $4)
+         RETURNING {}",
+        GROUP_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(name)
+    .bind(Utc::now())
+    .fetch_one(db.get_ref())
+    .await;
+
+    match result {
+        Ok(group) => {
+            let _ = invalidate_user_cache(cache.get_ref(), &req.user_id).await;
+            HttpResponse::Created().json(ApiResponse::success(group))
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => HttpResponse::Conflict()
+            .json(ApiResponse::<PortfolioGroup>::error(format!("A group named '{}' already exists", name))),
+        Err(e) => {
+            log::error!("Error creating group: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<PortfolioGroup>::error("Failed to create group".to_string()))
+        }
+    }
+}
+
+/// Rename a group
+pub async fn update_group(
+    path: web::Path<(String, String)>,
+    req: web::Json<UpdateGroupRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, group_id) = path.into_inner();
+    let name = req.name.trim();
+    if let Err(msg) = validate_name(name) {
+        return HttpResponse::BadRequest().json(ApiResponse::<PortfolioGroup>::error(msg));
+    }
+
+    let result = sqlx::query_as::<_, PortfolioGroup>(&format!(
+        "UPDATE portfolio_groups SET name = $1, updated_at = $2
+         WHERE id::text = $3 AND user_id = $4
+         RETURNING {}",
+        GROUP_COLUMNS
+    ))
+    .bind(name)
+    .bind(Utc::now())
+    .bind(&group_id)
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(group)) => {
+            let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+            HttpResponse::Ok().json(ApiResponse::success(group))
+        }
+        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<PortfolioGroup>::error("Group not found".to_string())),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => HttpResponse::Conflict()
+            .json(ApiResponse::<PortfolioGroup>::error(format!("A group named '{}' already exists", name))),
+        Err(e) => {
+            log::error!("Error updating group: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<PortfolioGroup>::error("Failed to update group".to_string()))
+        }
+    }
+}
+
+/// Delete a group; its wallets and debts become ungrouped
+pub async fn delete_group(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, group_id) = path.into_inner();
+
+    let result = sqlx::query("DELETE FROM portfolio_groups WHERE id::text = $1 AND user_id = $2")
+        .bind(&group_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await;
+
+    match result {
+        Ok(query_result) if query_result.rows_affected() > 0 => {
+            let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+            HttpResponse::NoContent().finish()
+        }
+        Ok(_) => HttpResponse::NotFound().json(ApiResponse::<String>::error("Group not found".to_string())),
+        Err(e) => {
+            log::error!("Error deleting group: {}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<String>::error("Failed to delete group".to_string()))
+        }
+    }
+}
+
+/// Move wallets and debts into a group, or ungroup them
+///
+/// All or nothing: every listed wallet and debt must belong to the user.
+pub async fn assign_to_group(
+    user_id: web::Path<String>,
+    req: web::Json<AssignGroupRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+
+    let item_count = req.wallet_ids.len() + req.debt_ids.len();
+    if item_count == 0 {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<GroupAssignment>::error("wallet_ids or debt_ids is required".to_string()));
+    }
+    if item_count > MAX_ASSIGN_ITEMS {
+        return HttpResponse::BadRequest().json(ApiResponse::<GroupAssignment>::error(format!(
+            "At most {} wallets and debts can be assigned at once",
+            MAX_ASSIGN_ITEMS
+        )));
+    }
+
+    match assign(db.get_ref(), &user_id, &req).await {
+        Ok(Ok(assignment)) => {
+            let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+            log::info!(
+                target: "audit",
+                "group_assign user={} group={:?} wallets={} debts={}",
+                user_id,
+                assignment.group_id,
+                assignment.wallets_updated,
+                assignment.debts_updated
+            );
+            HttpResponse::Ok().json(ApiResponse::success(assignment))
+        }
+        Ok(Err(msg)) => HttpResponse::NotFound().json(ApiResponse::<GroupAssignment>::error(msg)),
+        Err(e) => {
+            log::error!("Error assigning to group: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<GroupAssignment>::error("Failed to assign to group".to_string()))
+        }
+    }
+}
+
+/// Net worth overall and per group
+pub async fn get_net_worth(
+    user_id: web::Path<String>,
+    query: web::Query<NetWorthQuery>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+
+    let currency = match currencies::reporting_currency(db.get_ref(), &user_id).await {
+        Ok(Ok(currency)) => currency,
+        Ok(Err(msg)) => return HttpResponse::Conflict().json(ApiResponse::<NetWorth>::error(msg)),
+        Err(e) => {
+            log::error!("Error checking exchange rates: {}", e);
+            return HttpResponse::InternalServerError().json(ApiResponse::<NetWorth>::error("Database error".to_string()));
+        }
+    };
+
+    match compute_net_worth(db.get_ref(), &user_id, currency).await {
+        Ok(mut net_worth) => {
+            if let Some(group_id) = query.group_id {
+                let Some(group) = net_worth.groups.into_iter().find(|g| g.group_id == Some(group_id)) else {
+                    return HttpResponse::NotFound().json(ApiResponse::<NetWorth>::error("Group not found".to_string()));
+                };
+                net_worth.total = group.clone();
+                net_worth.groups = vec![group];
+            }
+            HttpResponse::Ok().json(ApiResponse::success(net_worth))
+        }
+        Err(e) => {
+            log::error!("Error computing net worth: {}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<NetWorth>::error("Database error".to_string()))
+        }
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err("Name must be 1-100 characters".to_string());
+    }
+    Ok(())
+}
+
+// ==================== Database Functions ====================
+
+async fn fetch_groups(pool: &PgPool, user_id: &str) -> Result<Vec<PortfolioGroup>, sqlx::Error> {
+    sqlx::query_as::<_, PortfolioGroup>(&format!(
+        "SELECT {} FROM portfolio_groups WHERE user_id = $1 ORDER BY LOWER(name)",
+        GROUP_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Set the group of the listed wallets and debts; `Err` names what wasn't found
+async fn assign(
+    pool: &PgPool,
+    user_id: &str,
+    req: &AssignGroupRequest,
+) -> Result<Result<GroupAssignment, String>, sqlx::Error> {
+    let mut db_tx = pool.begin().await?;
+
+    if let Some(group_id) = req.group_id {
+        let exists: Option<(Uuid,)> =
+            sqlx::query_as("SELECT id FROM portfolio_groups WHERE id = $1 AND user_id = $2 FOR SHARE")
+                .bind(group_id)
+                .bind(user_id)
+                .fetch_optional(&mut *db_tx)
+                .await?;
+        if exists.is_none() {
+            return Ok(Err("Group not found".to_string()));
+        }
+    }
+
+    let mut wallet_ids = req.wallet_ids.clone();
+    wallet_ids.sort_unstable();
+    wallet_ids.dedup();
+    let wallets_updated = sqlx::query("UPDATE wallets SET group_id = $1 WHERE user_id = $2 AND id = ANY($3) AND deleted_at IS NULL")
+        .bind(req.group_id)
+        .bind(user_id)
+        .bind(&wallet_ids)
+        .execute(&mut *db_tx)
+        .await?
+        .rows_affected();
+    if wallets_updated < wallet_ids.len() as u64 {
+        return Ok(Err("Wallet not found".to_string()));
+    }
+
+    let mut debt_ids = req.debt_ids.clone();
+    debt_ids.sort_unstable();
+    debt_ids.dedup();
+    let debts_updated = sqlx::query("UPDATE debts SET group_id = $1 WHERE user_id = $2 AND id = ANY($3)")
+        .bind(req.group_id)
+        .bind(user_id)
+        .bind(&debt_ids)
+        .execute(&mut *db_tx)
+        .await?
+        .rows_affected();
+    if debts_updated < debt_ids.len() as u64 {
+        return Ok(Err("Debt not found".to_string()));
+    }
+
+    db_tx.commit().await?;
+    Ok(Ok(GroupAssignment {
+        group_id: req.group_id,
+        wallets_updated,
+        debts_updated,
+    }))
+}
+
+fn empty_totals(group_id: Option<Uuid>, name: String) -> GroupNetWorth {
+    GroupNetWorth {
+        group_id,
+        name,
+        wallet_count: 0,
+        debt_count: 0,
+        assets: BigDecimal::from(0),
+        liabilities: BigDecimal::from(0),
+        receivables: BigDecimal::from(0),
+        payables: BigDecimal::from(0),
+        installments: BigDecimal::from(0),
+        net_worth: BigDecimal::from(0),
+    }
+}
+
+/// Totals per group (by name, then ungrouped if anything is) and overall
+pub async fn compute_net_worth(pool: &PgPool, user_id: &str, currency: String) -> Result<NetWorth, sqlx::Error> {
+    let groups = fetch_groups(pool, user_id).await?;
+    let mut totals: Vec<GroupNetWorth> = groups
+        .into_iter()
+        .map(|g| empty_totals(Some(g.id), g.name))
+        .chain(std::iter::once(empty_totals(None, UNGROUPED.to_string())))
+        .collect();
+    let mut total = empty_totals(None, "Total".to_string());
+
+    // Sandbox wallets hold an integrator's test data (see api_keys.rs)
+    for wallet in wallets::fetch_wallets_from_db(pool, user_id).await?.into_iter().filter(|w| !w.sandbox) {
+        let template = wallet_types::template_for(pool, &wallet).await?;
+        let Some(entry) = totals.iter_mut().find(|t| t.group_id == wallet.group_id) else {
+            continue;
+        };
+        // Rates were checked by the caller; one removed since counts as zero
+        let balance = currencies::convert(pool, &wallet.balance, &wallet.currency, &currency)
+            .await?
+            .map_or_else(|| BigDecimal::from(0), |conversion| conversion.amount);
+        for t in [entry, &mut total] {
+            t.wallet_count += 1;
+            if template.is_liability() {
+                t.liabilities += &balance;
+            } else {
+                t.assets += &balance;
+            }
+        }
+    }
+
+    let debts: Vec<(Option<Uuid>, String, i64, BigDecimal)> = sqlx::query_as(
+        "SELECT d.group_id, d.direction, COUNT(*), COALESCE(SUM(d.amount - COALESCE(s.settled, 0)), 0)
+         FROM debts d
+         LEFT JOIN (
+             SELECT debt_id, SUM(amount) AS settled FROM debt_settlements GROUP BY debt_id
+         ) s ON s.debt_id = d.id
+         WHERE d.user_id = $1 AND d.status = 'active'
+         GROUP BY d.group_id, d.direction",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    for (group_id, direction, count, outstanding) in debts {
+        let Some(entry) = totals.iter_mut().find(|t| t.group_id == group_id) else {
+            continue;
+        };
+        for t in [entry, &mut total] {
+            t.debt_count += count;
+            if direction == "receivable" {
+                t.receivables += &outstanding;
+            } else {
+                t.payables += &outstanding;
+            }
+        }
+    }
+
+    // Installment plans still to be billed to a card are owed too
+    let installments: Vec<(Option<Uuid>, String, BigDecimal)> = sqlx::query_as(
+        "SELECT w.group_id, w.currency, SUM(p.total_amount - p.billed)
+         FROM installment_plans p
+         JOIN wallets w ON w.id = p.wallet_id
+         WHERE p.user_id = $1 AND p.status = 'active' AND w.deleted_at IS NULL
+         GROUP BY w.group_id, w.currency",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    for (group_id, wallet_currency, remaining) in installments {
+        let Some(entry) = totals.iter_mut().find(|t| t.group_id == group_id) else {
+            continue;
+        };
+        let remaining = currencies::convert(pool, &remaining, &wallet_currency, &currency)
+            .await?
+            .map_or_else(|| BigDecimal::from(0), |conversion| conversion.amount);
+        for t in [entry, &mut total] {
+            t.installments += &remaining;
+        }
+    }
+
+    for t in totals.iter_mut().chain(std::iter::once(&mut total)) {
+        t.net_worth = &t.assets - &t.liabilities - &t.installments + &t.receivables - &t.payables;
+    }
+    // Only list the ungrouped totals when something is ungrouped
+    totals.retain(|t| t.group_id.is_some() || t.wallet_count > 0 || t.debt_count > 0);
+
+    Ok(NetWorth {
+        as_of: Utc::now(),
+        currency,
+        total,
+        groups: totals,
+    })
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/groups")
+        .create("", create_group)
+        .user(Method::GET, "/user/{user_id}", get_user_groups)
+        .user(Method::GET, "/{user_id}/net-worth", get_net_worth)
+        .user(Method::POST, "/{user_id}/assign", assign_to_group)
+        .user(Method::PUT, "/{user_id}/{group_id}", update_group)
+        .user(Method::DELETE, "/{user_id}/{group_id}", delete_group)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
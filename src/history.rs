@@ -0,0 +1,107 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::explain::DELTA;
+use crate::interest::INTEREST_CATEGORY;
+use crate::models::Wallet;
+use crate::telemetry;
+
+// ==================== AS-OF READS ====================
+//
+// The wallet read endpoints take `?as_of=<timestamp>` to show a wallet as it
+// was at that moment, rebuilt from history instead of stored snapshots:
+//
+// - Attributes (name, type, credit limit, APY, group) come from
+//   `wallet_history`, which a trigger fills with the old values whenever one
+//   of them changes: the version in force at `as_of` is the first one
+//   superseded after it, or the current row if none was.
+// - The balance is the opening balance in force at `as_of` plus every
+//   transaction recorded up to it. Direct balance edits shift the opening
+//   balance (see wallets.rs), so they show up from the moment they were made.
+//
+// Wallets created after `as_of` did not exist yet and are left out; deleted
+// wallets take their history with them. Wallets shared with the user are
+// included as they are shared now (see wallet_members.rs). Transactions are read as they are
+// now, so an edited or deleted transaction changes past balances too (see
+// explain.rs for checking a balance against its ledger). Attributes changed
+// before `wallet_history` existed read as their current value. Rebuilt wallets
+// have version 0, so clients never mistake them for a stored version.
+//
+// ============================================================================
+
+/// A user's wallets and those shared with them (or one of them) as they
+/// were at `as_of`, newest first
+pub(crate) async fn wallets_as_of(
+    pool: &PgPool,
+    user_id: &str,
+    wallet_id: Option<&str>,
+    as_of: DateTime<Utc>,
+) -> Result<Vec<Wallet>, sqlx::Error> {
+    telemetry::sql(
+        "wallet_history",
+        "select",
+        sqlx::query_as::<_, Wallet>(&format!(
+            "WITH versions AS (
+                 SELECT wallet_id AS id, name, wallet_type, credit_limit, custom_type_id, group_id, apy,
+                        opening_balance, changed_at AS valid_until
+                 FROM wallet_history
+                 WHERE wallet_id IN (SELECT wallet_id FROM wallet_members WHERE user_id = $1)
+                 UNION ALL
+                 SELECT id, name, wallet_type, credit_limit, custom_type_id, group_id, apy,
+                        opening_balance, 'infinity'::timestamptz
+                 FROM wallets
+                 WHERE id IN (SELECT wallet_id FROM wallet_members WHERE user_id = $1)
+             ),
+             in_force AS (
+                 SELECT DISTINCT ON (id) *
+                 FROM versions
+                 WHERE valid_until > $2
+                 ORDER BY id, valid_until
+             )
+             SELECT w.id, w.user_id, v.name, v.opening_balance + COALESCE(l.net, 0) AS balance, w.currency,
+                    v.credit_limit, v.wallet_type, v.custom_type_id, v.group_id, v.apy, l.last_interest_posted_at,
+                    w.created_at, GREATEST(w.created_at, l.last_entry_at, h.last_change_at) AS updated_at,
+                    0::bigint AS version
+             FROM wallets w
+             JOIN in_force v ON v.id = w.id
+             LEFT JOIN LATERAL (
+                 SELECT SUM({0}) AS net,
+                        MAX(t.created_at) AS last_entry_at,
+                        MAX(t.created_at) FILTER (WHERE t.category = $4 AND t.transaction_type = 'income')
+                            AS last_interest_posted_at
+                 FROM transactions t
+                 WHERE t.wallet_id = w.id AND t.deleted_at IS NULL AND t.created_at <= $2
+             ) l ON TRUE
+             LEFT JOIN LATERAL (
+                 SELECT MAX(wh.changed_at) AS last_change_at
+                 FROM wallet_history wh
+                 WHERE wh.wallet_id = w.id AND wh.changed_at <= $2
+             ) h ON TRUE
+             WHERE w.id IN (SELECT wallet_id FROM wallet_members WHERE user_id = $1)
+               AND w.deleted_at IS NULL
+               AND w.created_at <= $2
+               AND ($3::text IS NULL OR w.id::text = $3)
+             ORDER BY w.created_at DESC",
+            DELTA
+        ))
+        .bind(user_id)
+        .bind(as_of)
+        .bind(wallet_id)
+        .bind(INTEREST_CATEGORY)
+        .fetch_all(pool),
+    )
+    .await
+}
+
+/// One wallet as it was at `as_of`; `RowNotFound` if it didn't exist yet
+pub(crate) async fn wallet_as_of(
+    pool: &PgPool,
+    wallet_id: &str,
+    user_id: &str,
+    as_of: DateTime<Utc>,
+) -> Result<Wallet, sqlx::Error> {
+    wallets_as_of(pool, user_id, Some(wallet_id), as_of)
+        .await?
+        .pop()
+        .ok_or(sqlx::Error::RowNotFound)
+}
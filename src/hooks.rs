@@ -0,0 +1,155 @@
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::cache::CacheBackend;
+use crate::dashboard;
+use crate::models::{Debt, Transaction};
+use crate::notifications;
+use crate::top_ups;
+
+// ==================== EVENT HOOKS ====================
+//
+// Reactions to a committed change (refilling a wallet, notifying, keeping
+// the dashboard projection current, and whatever a fork adds) register as a
+// `Hook` instead of being called from every handler that makes the change:
+//
+// - Handlers fire an event with `hooks::transaction_created(...)` and
+//   friends after their DB transaction committed. Each event has a typed
+//   context struct; `HookContext` carries the pool and the cache.
+// - `install` sets the registry once at startup (see main.rs): the built-in
+//   hooks from `builtin()` plus any a fork appends. Hooks run in
+//   registration order, one after the other, within the request.
+// - A hook can't fail the request, which already succeeded: it logs its own
+//   errors. Every method has a no-op default, so a hook implements only the
+//   events it cares about.
+//
+// Webhooks and the audit log don't go through hooks: they are written by
+// database triggers in the same transaction as the change (see webhooks.rs),
+// so they see every write path and never miss a committed change.
+//
+// ============================================================================
+
+/// What a hook gets to work with
+pub struct HookContext<'a> {
+    pub pool: &'a PgPool,
+    pub cache: &'a dyn CacheBackend,
+}
+
+/// A transaction was recorded (by a request or a schedule)
+pub struct TransactionCreated<'a> {
+    pub transaction: &'a Transaction,
+}
+
+/// A transaction was edited
+pub struct TransactionUpdated<'a> {
+    pub before: &'a Transaction,
+    pub after: &'a Transaction,
+}
+
+/// A transaction was deleted (soft or hard) and its balance effect reversed
+pub struct TransactionDeleted<'a> {
+    pub transaction: &'a Transaction,
+}
+
+/// A deleted transaction was restored and re-applied to its wallet
+pub struct TransactionRestored<'a> {
+    pub transaction: &'a Transaction,
+}
+
+/// Balances went down other than through one transaction of the
+/// transactions API: transfers, bulk creates and deletes, statement imports,
+/// goal contributions and debt settlements
+pub struct WalletsDebited<'a> {
+    pub owner_id: &'a str,
+    pub wallet_ids: &'a [Uuid],
+}
+
+/// A debt's status became `paid`, by an edit or by a settlement
+pub struct DebtPaid<'a> {
+    pub debt: &'a Debt,
+}
+
+/// Reactions to committed changes; implement the events of interest
+#[async_trait]
+pub trait Hook: Send + Sync {
+    /// Name in logs
+    fn name(&self) -> &'static str;
+
+    async fn on_transaction_created(&self, _cx: &HookContext<'_>, _event: &TransactionCreated<'_>) {}
+
+    async fn on_transaction_updated(&self, _cx: &HookContext<'_>, _event: &TransactionUpdated<'_>) {}
+
+    async fn on_transaction_deleted(&self, _cx: &HookContext<'_>, _event: &TransactionDeleted<'_>) {}
+
+    async fn on_transaction_restored(&self, _cx: &HookContext<'_>, _event: &TransactionRestored<'_>) {}
+
+    async fn on_wallets_debited(&self, _cx: &HookContext<'_>, _event: &WalletsDebited<'_>) {}
+
+    async fn on_debt_paid(&self, _cx: &HookContext<'_>, _event: &DebtPaid<'_>) {}
+}
+
+static HOOKS: OnceLock<Vec<Arc<dyn Hook>>> = OnceLock::new();
+
+/// The hooks the service ships with
+pub fn builtin() -> Vec<Arc<dyn Hook>> {
+    vec![
+        Arc::new(top_ups::TopUpHook),
+        Arc::new(notifications::NotificationHook),
+        Arc::new(dashboard::DashboardHook),
+    ]
+}
+
+/// Set the hooks events are dispatched to; only the first call counts
+pub fn install(hooks: Vec<Arc<dyn Hook>>) {
+    let names: Vec<&str> = hooks.iter().map(|hook| hook.name()).collect();
+    if HOOKS.set(hooks).is_err() {
+        log::warn!("Event hooks already installed; ignoring {:?}", names);
+    } else {
+        log::info!("Event hooks: {:?}", names);
+    }
+}
+
+fn installed() -> &'static [Arc<dyn Hook>] {
+    HOOKS.get().map(Vec::as_slice).unwrap_or_default()
+}
+
+// ==================== Dispatch ====================
+
+pub async fn transaction_created(cx: &HookContext<'_>, event: TransactionCreated<'_>) {
+    for hook in installed() {
+        hook.on_transaction_created(cx, &event).await;
+    }
+}
+
+pub async fn transaction_updated(cx: &HookContext<'_>, event: TransactionUpdated<'_>) {
+    for hook in installed() {
+        hook.on_transaction_updated(cx, &event).await;
+    }
+}
+
+pub async fn transaction_deleted(cx: &HookContext<'_>, event: TransactionDeleted<'_>) {
+    for hook in installed() {
+        hook.on_transaction_deleted(cx, &event).await;
+    }
+}
+
+pub async fn transaction_restored(cx: &HookContext<'_>, event: TransactionRestored<'_>) {
+    for hook in installed() {
+        hook.on_transaction_restored(cx, &event).await;
+    }
+}
+
+pub async fn wallets_debited(cx: &HookContext<'_>, event: WalletsDebited<'_>) {
+    for hook in installed() {
+        hook.on_wallets_debited(cx, &event).await;
+    }
+}
+
+pub async fn debt_paid(cx: &HookContext<'_>, event: DebtPaid<'_>) {
+    for hook in installed() {
+        hook.on_debt_paid(cx, &event).await;
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt::Debug;
+use std::future::{ready, Ready};
+use std::time::Duration;
+
+use actix_web::dev::Payload;
+use actix_web::http::StatusCode;
+use actix_web::{FromRequest, HttpRequest, HttpResponse};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, PgPool};
+
+use crate::error::AppError;
+use crate::models::ApiResponse;
+
+// ==================== IDEMPOTENT CREATES ====================
+//
+// `POST /api/transactions`, `/api/wallets` and `/api/debts` accept an
+// `Idempotency-Key` header, so a client that lost the response to a timeout
+// can retry without creating a duplicate.
+//
+// The handler calls `replay` right after BEGIN: it claims the key for the
+// user and route in `idempotency_keys`, or finds the response of an earlier
+// request with that key and sends it again (with `Idempotent-Replayed: true`).
+// On success the handler stores its response with `record` before COMMIT, so
+// the key and the created row commit together; a failed request rolls back
+// and leaves the key free. A concurrent retry blocks on the key's row until
+// the first request commits, then replays its response.
+//
+// Reusing a key with a different request body is a 409. Keys expire after
+// `KEY_TTL_HOURS`; `PURGE_JOB` deletes them.
+//
+// ============================================================================
+
+pub const PURGE_JOB: &str = "idempotency_key_purge";
+
+/// How often expired keys are deleted
+pub const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// How long a key replays its response
+const KEY_TTL_HOURS: i32 = 24;
+
+const HEADER: &str = "Idempotency-Key";
+
+const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+/// Longest key accepted, in bytes
+const MAX_KEY_LEN: usize = 255;
+
+/// The request's `Idempotency-Key` header, if any
+#[derive(Debug, Clone)]
+pub struct IdempotencyKey(pub Option<String>);
+
+impl FromRequest for IdempotencyKey {
+    type Error = AppError;
+    type Future = Ready<Result<Self, AppError>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(match req.headers().get(HEADER) {
+            None => Ok(IdempotencyKey(None)),
+            Some(value) => value
+                .to_str()
+                .ok()
+                .map(str::trim)
+                .filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LEN)
+                .map(|key| IdempotencyKey(Some(key.to_string())))
+                .ok_or_else(|| {
+                    AppError::Validation(format!("{} must be 1-{} printable ASCII characters", HEADER, MAX_KEY_LEN))
+                }),
+        })
+    }
+}
+
+/// Hash of a request, to tell a retry from another request reusing its key
+fn request_hash(request: &impl Debug) -> String {
+    hex::encode(Sha256::digest(format!("{:?}", request).as_bytes()))
+}
+
+/// Claim `key` for this request, or return the response stored for it
+///
+/// Runs inside the create's DB transaction. `Ok(None)` means the handler
+/// goes ahead and later calls `record`; without a key it always does.
+pub async fn replay(
+    conn: &mut PgConnection,
+    key: &IdempotencyKey,
+    user_id: &str,
+    route: &str,
+    request: &impl Debug,
+) -> Result<Option<HttpResponse>, AppError> {
+    let Some(key) = &key.0 else {
+        return Ok(None);
+    };
+    let hash = request_hash(request);
+
+    // Take over the key if it is new or expired
+    let claimed = sqlx::query_scalar::<_, i32>(
+        "INSERT INTO idempotency_keys (user_id, route, key, request_hash)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (user_id, route, key) DO UPDATE
+             SET request_hash = EXCLUDED.request_hash, status = NULL, response = NULL, created_at = CURRENT_TIMESTAMP
+             WHERE idempotency_keys.created_at < CURRENT_TIMESTAMP - make_interval(hours => $5)
+         RETURNING 1",
+    )
+    .bind(user_id)
+    .bind(route)
+    .bind(key)
+    .bind(&hash)
+    .bind(KEY_TTL_HOURS)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(AppError::database("Failed to check idempotency key"))?;
+    if claimed.is_some() {
+        return Ok(None);
+    }
+
+    let (stored_hash, status, response) = sqlx::query_as::<_, (String, Option<i16>, Option<Value>)>(
+        "SELECT request_hash, status, response FROM idempotency_keys WHERE user_id = $1 AND route = $2 AND key = $3",
+    )
+    .bind(user_id)
+    .bind(route)
+    .bind(key)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(AppError::database("Failed to check idempotency key"))?;
+
+    if stored_hash != hash {
+        return Err(AppError::Conflict(format!(
+            "{} was already used with a different request",
+            HEADER
+        )));
+    }
+    let (Some(status), Some(response)) = (status, response) else {
+        return Err(AppError::Internal(format!("Idempotency key {} has no stored response", key)));
+    };
+    let status = u16::try_from(status)
+        .ok()
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .ok_or_else(|| AppError::Internal(format!("Idempotency key {} has an invalid status", key)))?;
+
+    Ok(Some(
+        HttpResponse::build(status)
+            .insert_header((REPLAYED_HEADER, "true"))
+            .json(response),
+    ))
+}
+
+/// Store the response of a request claimed by `replay` and build it
+pub async fn record<T: Serialize>(
+    conn: &mut PgConnection,
+    key: &IdempotencyKey,
+    user_id: &str,
+    route: &str,
+    status: StatusCode,
+    body: &ApiResponse<T>,
+) -> Result<HttpResponse, AppError> {
+    if let Some(key) = &key.0 {
+        let response = serde_json::to_value(body)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize response: {}", e)))?;
+        sqlx::query(
+            "UPDATE idempotency_keys SET status = $4, response = $5
+             WHERE user_id = $1 AND route = $2 AND key = $3",
+        )
+        .bind(user_id)
+        .bind(route)
+        .bind(key)
+        .bind(status.as_u16() as i16)
+        .bind(response)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::database("Failed to save idempotency key"))?;
+    }
+    Ok(HttpResponse::build(status).json(body))
+}
+
+// ==================== Purge Job ====================
+
+/// Delete keys that no longer replay; returns how many were deleted
+pub async fn purge_expired_keys(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "DELETE FROM idempotency_keys WHERE created_at < CURRENT_TIMESTAMP - make_interval(hours => $1)",
+    )
+    .bind(KEY_TTL_HOURS)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::test_support;
+
+    const ROUTE: &str = "/api/test";
+
+    #[actix_web::test]
+    async fn a_blank_or_oversized_key_is_refused() {
+        let key = |value: &str| {
+            let req = TestRequest::default().insert_header((HEADER, value)).to_http_request();
+            IdempotencyKey::extract(&req).into_inner()
+        };
+        assert_eq!(key(" retry-1 ").unwrap().0.as_deref(), Some("retry-1"));
+        assert!(matches!(key("  "), Err(AppError::Validation(_))));
+        assert!(matches!(key(&"k".repeat(MAX_KEY_LEN + 1)), Err(AppError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn a_retry_replays_the_recorded_response() {
+        let Some(pool) = test_support::database("a_retry_replays_the_recorded_response").await else { return };
+        let user_id = format!("test-idempotency-{}", Uuid::new_v4());
+        let key = IdempotencyKey(Some("retry-1".to_string()));
+        let request = ("Groceries", 12);
+
+        let mut db_tx = pool.begin().await.unwrap();
+        let first = replay(&mut db_tx, &key, &user_id, ROUTE, &request).await.unwrap();
+        record(&mut db_tx, &key, &user_id, ROUTE, StatusCode::CREATED, &ApiResponse::success("created"))
+            .await
+            .unwrap();
+        db_tx.commit().await.unwrap();
+
+        let mut db_tx = pool.begin().await.unwrap();
+        let retry = replay(&mut db_tx, &key, &user_id, ROUTE, &request).await.unwrap();
+        let reused = replay(&mut db_tx, &key, &user_id, ROUTE, &("Rent", 900)).await;
+        drop(db_tx);
+        sqlx::query("DELETE FROM idempotency_keys WHERE user_id = $1").bind(&user_id).execute(&pool).await.unwrap();
+
+        assert!(first.is_none());
+        let retry = retry.expect("the retry is replayed");
+        assert_eq!(retry.status(), StatusCode::CREATED);
+        assert_eq!(retry.headers().get(REPLAYED_HEADER).unwrap(), "true");
+        let body = actix_web::body::to_bytes(retry.into_body()).await.unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&body).unwrap()["data"], "created");
+        assert!(matches!(reused, Err(AppError::Conflict(_))), "{:?}", reused.map(|r| r.map(|r| r.status())));
+    }
+}
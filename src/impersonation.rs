@@ -0,0 +1,461 @@
+use actix_web::body::BoxBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, HttpRequest, HttpResponse};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::admin::authorize_admin;
+use crate::audit::Actor;
+use crate::cache::CacheBackend;
+use crate::config::AppConfig;
+use crate::models::{
+    ApiResponse, CreateImpersonationRequest, ImpersonationEvent, ImpersonationGrant, ImpersonationSession,
+};
+use crate::routes::{Ownership, ScopedRoutes};
+
+// ==================== IMPERSONATION ====================
+//
+// Support can act as a user to debug what they see (a report, a balance):
+//
+// 1. An admin mints a session for the user (`POST /api/admin/impersonations`)
+//    with who asks and why. It expires after `ttl_minutes` (default 30, max
+//    240) and is read-only unless `write_access` is set. The bearer token is
+//    returned once; only its SHA-256 is stored.
+// 2. Requests carrying the token in `X-Impersonation-Token` are checked by
+//    `enforce`, which every `ScopedRoutes` route runs: the route must belong to
+//    the session's user (the `{user_id}` path segment, or `user_id` in the body
+//    of a create), and mutating routes need `write_access`. Admin routes never
+//    accept it.
+// 3. Every request made with a live token, allowed or not, is recorded in
+//    `impersonation_events`, written to the `audit` log target, and answered
+//    with `X-Impersonation-Session` / `X-Impersonated-User` headers.
+// 4. The user is told through a push alert (see alerts.rs) and can list the
+//    sessions opened on their account (`GET /api/support-access/{user_id}`).
+//
+// The token stands in for the user's own credentials (see auth.rs): a request
+// carrying it is not asked for a bearer token. Requests without it are not
+// affected.
+//
+// ============================================================================
+
+/// Header carrying an impersonation token
+pub(crate) const TOKEN_HEADER: &str = "X-Impersonation-Token";
+
+const SESSION_HEADER: HeaderName = HeaderName::from_static("x-impersonation-session");
+const USER_HEADER: HeaderName = HeaderName::from_static("x-impersonated-user");
+
+/// Session lifetime when none is requested, and the longest allowed
+const DEFAULT_TTL_MINUTES: i64 = 30;
+const MAX_TTL_MINUTES: i64 = 240;
+
+/// Number of events returned per session
+const EVENT_HISTORY_LIMIT: i64 = 500;
+
+const SESSION_COLUMNS: &str = "id, user_id, requested_by, reason, write_access, created_at, expires_at, revoked_at";
+
+// ==================== Admin Handlers ====================
+
+/// Mint an impersonation session and its token
+pub async fn create_impersonation(
+    http_req: HttpRequest,
+    req: web::Json<CreateImpersonationRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    let requested_by = req.requested_by.trim();
+    let reason = req.reason.trim();
+    let ttl_minutes = req.ttl_minutes.unwrap_or(DEFAULT_TTL_MINUTES);
+    let invalid = if req.user_id.is_empty() {
+        Some("user_id is required".to_string())
+    } else if requested_by.is_empty() || requested_by.chars().count() > 100 {
+        Some("requested_by must be 1-100 characters".to_string())
+    } else if reason.is_empty() || reason.chars().count() > 500 {
+        Some("reason must be 1-500 characters".to_string())
+    } else if !(1..=MAX_TTL_MINUTES).contains(&ttl_minutes) {
+        Some(format!("ttl_minutes must be between 1 and {}", MAX_TTL_MINUTES))
+    } else {
+        None
+    };
+    if let Some(msg) = invalid {
+        return HttpResponse::BadRequest().json(ApiResponse::<ImpersonationGrant>::error(msg));
+    }
+
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+    let token = format!("imp_{}", hex::encode(secret));
+
+    let now = Utc::now();
+    let result = sqlx::query_as::<_, ImpersonationSession>(&format!(
+        "INSERT INTO impersonation_sessions
+             (id, user_id, requested_by, reason, write_access, token_hash, created_at, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING {}",
+        SESSION_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(requested_by)
+    .bind(reason)
+    .bind(req.write_access)
+    .bind(token_hash(&token))
+    .bind(now)
+    .bind(now + Duration::minutes(ttl_minutes))
+    .fetch_one(db.get_ref())
+    .await;
+
+    match result {
+        Ok(session) => {
+            log::info!(
+                target: "audit",
+                "Impersonation session {} opened for user {} by {} ({}, until {}): {}",
+                session.id,
+                session.user_id,
+                session.requested_by,
+                if session.write_access { "read-write" } else { "read-only" },
+                session.expires_at.to_rfc3339(),
+                session.reason
+            );
+            HttpResponse::Created().json(ApiResponse::success(ImpersonationGrant { session, token }))
+        }
+        Err(e) => {
+            log::error!("Error creating impersonation session: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<ImpersonationGrant>::error("Failed to create session".to_string()))
+        }
+    }
+}
+
+/// End a session before it expires
+pub async fn revoke_impersonation(
+    http_req: HttpRequest,
+    session_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    let result = sqlx::query(
+        "UPDATE impersonation_sessions SET revoked_at = CURRENT_TIMESTAMP
+         WHERE id::text = $1 AND revoked_at IS NULL AND expires_at > CURRENT_TIMESTAMP",
+    )
+    .bind(session_id.as_str())
+    .execute(db.get_ref())
+    .await;
+
+    match result {
+        Ok(query_result) if query_result.rows_affected() > 0 => {
+            log::info!(target: "audit", "Impersonation session {} revoked", session_id.as_str());
+            HttpResponse::NoContent().finish()
+        }
+        Ok(_) => HttpResponse::NotFound()
+            .json(ApiResponse::<String>::error("No active session with this id".to_string())),
+        Err(e) => {
+            log::error!("Error revoking impersonation session: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<String>::error("Failed to revoke session".to_string()))
+        }
+    }
+}
+
+/// Sessions opened on a user's account, newest first
+pub async fn get_user_impersonations(
+    http_req: HttpRequest,
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+    list_sessions(db.get_ref(), &user_id).await
+}
+
+/// Requests made with a session, oldest first
+pub async fn get_impersonation_events(
+    http_req: HttpRequest,
+    session_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    let result = sqlx::query_as::<_, ImpersonationEvent>(
+        "SELECT id, session_id, user_id, method, path, status, created_at FROM impersonation_events
+         WHERE session_id::text = $1
+         ORDER BY created_at
+         LIMIT $2",
+    )
+    .bind(session_id.as_str())
+    .bind(EVENT_HISTORY_LIMIT)
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(events) => HttpResponse::Ok().json(ApiResponse::success(events)),
+        Err(e) => {
+            log::error!("Error fetching impersonation events: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<ImpersonationEvent>>::error("Database error".to_string()))
+        }
+    }
+}
+
+// ==================== User Handlers ====================
+
+/// `GET /api/support-access/{user_id}` - support sessions opened on the account
+pub async fn get_support_access(user_id: web::Path<String>, db: web::Data<PgPool>) -> HttpResponse {
+    list_sessions(db.get_ref(), &user_id).await
+}
+
+async fn list_sessions(pool: &PgPool, user_id: &str) -> HttpResponse {
+    let result = sqlx::query_as::<_, ImpersonationSession>(&format!(
+        "SELECT {} FROM impersonation_sessions WHERE user_id = $1 ORDER BY created_at DESC LIMIT 100",
+        SESSION_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await;
+
+    match result {
+        Ok(sessions) => HttpResponse::Ok().json(ApiResponse::success(sessions)),
+        Err(e) => {
+            log::error!("Error fetching impersonation sessions: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<ImpersonationSession>>::error("Database error".to_string()))
+        }
+    }
+}
+
+// ==================== Request Guard ====================
+
+/// Check, record and flag a request carrying an impersonation token
+///
+/// Requests without the header pass through untouched.
+pub async fn enforce(
+    ownership: Ownership,
+    mutating: bool,
+    mut req: ServiceRequest,
+    next: Next<BoxBody>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let Some(token) = req.headers().get(TOKEN_HEADER).map(|v| v.to_str().unwrap_or_default().to_string()) else {
+        return next.call(req).await;
+    };
+    let Some(pool) = req.app_data::<web::Data<PgPool>>().cloned() else {
+        return next.call(req).await;
+    };
+
+    let session = sqlx::query_as::<_, ImpersonationSession>(&format!(
+        "SELECT {} FROM impersonation_sessions
+         WHERE token_hash = $1 AND revoked_at IS NULL AND expires_at > CURRENT_TIMESTAMP",
+        SESSION_COLUMNS
+    ))
+    .bind(token_hash(&token))
+    .fetch_optional(pool.get_ref())
+    .await;
+    let session = match session {
+        Ok(Some(session)) => session,
+        Ok(None) => {
+            return Ok(req.into_response(
+                HttpResponse::Unauthorized()
+                    .json(ApiResponse::<String>::error("Invalid or expired impersonation token".to_string())),
+            ));
+        }
+        Err(e) => {
+            log::error!("Error checking impersonation token: {}", e);
+            return Ok(req.into_response(
+                HttpResponse::InternalServerError().json(ApiResponse::<String>::error("Database error".to_string())),
+            ));
+        }
+    };
+
+    let target = match ownership {
+        Ownership::PathUser => req.match_info().get("user_id").map(str::to_string),
+        Ownership::BodyUser => body_user_id(&mut req).await,
+        Ownership::Admin | Ownership::Credentials | Ownership::Public | Ownership::Session => None,
+    };
+    let denied = if ownership == Ownership::Public {
+        None
+    } else if target.as_deref() != Some(session.user_id.as_str()) {
+        Some("Impersonation session does not cover this user or route")
+    } else if mutating && !session.write_access {
+        Some("Impersonation session is read-only")
+    } else {
+        None
+    };
+
+    let method = req.method().to_string();
+    let path = req.path().to_string();
+    let mut res = match denied {
+        Some(msg) => req.into_response(HttpResponse::Forbidden().json(ApiResponse::<String>::error(msg.to_string()))),
+        None => {
+            req.extensions_mut().insert(Actor(format!("impersonation:{}", session.id)));
+            next.call(req).await?
+        }
+    };
+
+    let status = res.status().as_u16();
+    log::info!(
+        target: "audit",
+        "Impersonated request {} {} -> {} (session {}, user {}, by {})",
+        method,
+        path,
+        status,
+        session.id,
+        session.user_id,
+        session.requested_by
+    );
+    if let Err(e) = sqlx::query(
+        "INSERT INTO impersonation_events (id, session_id, user_id, method, path, status, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(session.id)
+    .bind(&session.user_id)
+    .bind(&method)
+    .bind(path.chars().take(500).collect::<String>())
+    .bind(status as i16)
+    .execute(pool.get_ref())
+    .await
+    {
+        log::error!("Failed to record impersonated request for session {}: {}", session.id, e);
+    }
+
+    let headers = res.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&session.id.to_string()) {
+        headers.insert(SESSION_HEADER, value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&session.user_id) {
+        headers.insert(USER_HEADER, value);
+    }
+    Ok(res)
+}
+
+/// `user_id` of a JSON body, leaving the body in place for the handler
+pub(crate) async fn body_user_id(req: &mut ServiceRequest) -> Option<String> {
+    body_field(req, "user_id").await
+}
+
+/// A string field of the JSON body, leaving the body for the handler
+pub(crate) async fn body_field(req: &mut ServiceRequest, field: &str) -> Option<String> {
+    let body = req.extract::<web::Bytes>().await.ok()?;
+    req.set_payload(Payload::from(body.clone()));
+    let value: serde_json::Value = serde_json::from_slice(&body).ok()?;
+    value.get(field)?.as_str().map(str::to_string)
+}
+
+pub(crate) fn token_hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/support-access").user(Method::GET, "/{user_id}", get_support_access)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::test::{self, TestRequest};
+    use actix_web::App;
+
+    use super::*;
+    use crate::test_support;
+
+    async fn ok() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::test]
+    async fn a_body_field_is_read_without_consuming_the_body() {
+        let mut req = TestRequest::post()
+            .set_payload(r#"{"user_id": "alice", "amount": 12}"#)
+            .to_srv_request();
+        assert_eq!(body_user_id(&mut req).await.as_deref(), Some("alice"));
+        assert_eq!(body_field(&mut req, "amount").await, None);
+        let body = req.extract::<web::Bytes>().await.unwrap();
+        assert_eq!(body, r#"{"user_id": "alice", "amount": 12}"#);
+    }
+
+    #[actix_web::test]
+    async fn a_read_only_session_reads_its_users_routes_and_is_recorded() {
+        let Some(pool) =
+            test_support::database("a_read_only_session_reads_its_users_routes_and_is_recorded").await
+        else {
+            return;
+        };
+        let user_id = format!("test-impersonated-{}", Uuid::new_v4());
+        let token = Uuid::new_v4().to_string();
+        let (session_id,): (Uuid,) = sqlx::query_as(
+            "INSERT INTO impersonation_sessions (user_id, requested_by, reason, token_hash, expires_at)
+             VALUES ($1, 'support', 'Test', $2, CURRENT_TIMESTAMP + INTERVAL '5 minutes')
+             RETURNING id",
+        )
+        .bind(&user_id)
+        .bind(token_hash(&token))
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let app = test::init_service(App::new().app_data(web::Data::new(pool.clone())).configure(|cfg| {
+            ScopedRoutes::new("/api/widgets")
+                .user(Method::GET, "/{user_id}", ok)
+                .user(Method::PUT, "/{user_id}", ok)
+                .register(cfg)
+        }))
+        .await;
+        let mut responses = Vec::new();
+        for (method, owner) in [(Method::GET, user_id.as_str()), (Method::GET, "someone-else"), (Method::PUT, user_id.as_str())] {
+            let req = TestRequest::default()
+                .method(method)
+                .uri(&format!("/api/widgets/{}", owner))
+                .insert_header((TOKEN_HEADER, token.as_str()))
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            let user = res.headers().get(USER_HEADER).and_then(|v| v.to_str().ok()).map(str::to_string);
+            responses.push((res.status(), user));
+        }
+        let (events,): (Vec<i16>,) = sqlx::query_as(
+            "SELECT array_agg(status ORDER BY created_at) FROM impersonation_events WHERE session_id = $1",
+        )
+        .bind(session_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        sqlx::query("DELETE FROM impersonation_sessions WHERE id = $1").bind(session_id).execute(&pool).await.unwrap();
+
+        let impersonated = Some(user_id.clone());
+        assert_eq!(
+            responses,
+            [
+                (StatusCode::OK, impersonated.clone()),
+                (StatusCode::FORBIDDEN, impersonated.clone()),
+                (StatusCode::FORBIDDEN, impersonated),
+            ]
+        );
+        assert_eq!(events, [200, 403, 403]);
+    }
+}
@@ -0,0 +1,598 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::format::{Item, StrftimeItems};
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::admin::authorize_admin;
+use crate::cache::CacheBackend;
+use crate::config::AppConfig;
+use crate::routes::ScopedRoutes;
+use crate::models::{ApiResponse, CreateImportPresetRequest, ImportPreset, ImportPresetFields, ImportPresetMatch};
+
+// ==================== IMPORT PRESETS ====================
+//
+// Every bank exports statements with its own CSV layout. A preset records
+// one layout: the header row, which columns hold the date, amount (signed,
+// or split into debit and credit), description and category, the date
+// format, the delimiter and the decimal separator.
+//
+// Users keep their own presets. Community presets (`user_id` NULL) are
+// managed by admins under `/api/admin/import-presets` and visible to every
+// user, who can use them as they are or copy one to adjust it.
+//
+// `POST /api/import-presets/{user_id}/detect` takes a file (or just its
+// first line) and ranks the presets whose columns all appear in its header
+// row, by the share of header names in common. Header names are compared
+// trimmed, unquoted and case-insensitively. Statement uploads use the same
+// detection when no preset is named (see statement_imports.rs).
+//
+// ============================================================================
+
+/// Maximum number of presets per user
+const MAX_PRESETS_PER_USER: i64 = 50;
+
+/// Maximum number of columns in a preset's header row
+const MAX_HEADERS: usize = 100;
+
+/// Delimiters a preset may use
+const DELIMITERS: [&str; 4] = [",", ";", "|", "\t"];
+
+const PRESET_COLUMNS: &str = "id, user_id, name, bank, delimiter, headers, date_column, date_format, amount_column, \
+     debit_column, credit_column, description_column, category_column, decimal_comma, expense_negative, \
+     created_at, updated_at";
+
+// ==================== Handlers ====================
+
+/// List the user's presets, then the community presets, by name
+pub async fn get_user_presets(user_id: web::Path<String>, db: web::Data<PgPool>) -> HttpResponse {
+    match fetch_presets(db.get_ref(), Some(&user_id)).await {
+        Ok(presets) => HttpResponse::Ok().json(ApiResponse::success(presets)),
+        Err(e) => {
+            log::error!("Error fetching import presets: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<ImportPreset>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// List the community presets, by name
+pub async fn get_community_presets(db: web::Data<PgPool>) -> HttpResponse {
+    match fetch_presets(db.get_ref(), None).await {
+        Ok(presets) => HttpResponse::Ok().json(ApiResponse::success(presets)),
+        Err(e) => {
+            log::error!("Error fetching community import presets: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<ImportPreset>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Save a preset for the user
+pub async fn create_preset(req: web::Json<CreateImportPresetRequest>, db: web::Data<PgPool>) -> HttpResponse {
+    let req = req.into_inner();
+    let fields = match normalize_fields(req.preset) {
+        Ok(fields) => fields,
+        Err(msg) => return HttpResponse::BadRequest().json(ApiResponse::<ImportPreset>::error(msg)),
+    };
+
+    let count: Result<(i64,), sqlx::Error> =
+        sqlx::query_as("SELECT COUNT(*) FROM import_presets WHERE user_id = $1")
+            .bind(&req.user_id)
+            .fetch_one(db.get_ref())
+            .await;
+    match count {
+        Ok((count,)) if count >= MAX_PRESETS_PER_USER => {
+            return HttpResponse::BadRequest().json(ApiResponse::<ImportPreset>::error(format!(
+                "A user can have at most {} import presets",
+                MAX_PRESETS_PER_USER
+            )));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Error counting import presets: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<ImportPreset>::error("Database error".to_string()));
+        }
+    }
+
+    saved_response(insert_preset(db.get_ref(), Some(&req.user_id), &fields).await.map(Some), &fields.name, true)
+}
+
+/// Replace one of the user's presets
+pub async fn update_preset(
+    path: web::Path<(String, String)>,
+    req: web::Json<ImportPresetFields>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let (user_id, preset_id) = path.into_inner();
+    let fields = match normalize_fields(req.into_inner()) {
+        Ok(fields) => fields,
+        Err(msg) => return HttpResponse::BadRequest().json(ApiResponse::<ImportPreset>::error(msg)),
+    };
+
+    saved_response(replace_preset(db.get_ref(), Some(&user_id), &preset_id, &fields).await, &fields.name, false)
+}
+
+/// Delete one of the user's presets
+pub async fn delete_preset(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, preset_id) = path.into_inner();
+
+    let result = sqlx::query("DELETE FROM import_presets WHERE id::text = $1 AND user_id = $2")
+        .bind(&preset_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await;
+    deleted_response(result)
+}
+
+/// Copy a community preset into the user's presets, to adjust it
+pub async fn copy_preset(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, preset_id) = path.into_inner();
+
+    let count: Result<(i64,), sqlx::Error> =
+        sqlx::query_as("SELECT COUNT(*) FROM import_presets WHERE user_id = $1")
+            .bind(&user_id)
+            .fetch_one(db.get_ref())
+            .await;
+    match count {
+        Ok((count,)) if count >= MAX_PRESETS_PER_USER => {
+            return HttpResponse::BadRequest().json(ApiResponse::<ImportPreset>::error(format!(
+                "A user can have at most {} import presets",
+                MAX_PRESETS_PER_USER
+            )));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Error counting import presets: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<ImportPreset>::error("Database error".to_string()));
+        }
+    }
+
+    let now = Utc::now();
+    let result = sqlx::query_as::<_, ImportPreset>(&format!(
+        "INSERT INTO import_presets
+             (id, user_id, name, bank, delimiter, headers, date_column, date_format, amount_column, debit_column,
+              credit_column, description_column, category_column, decimal_comma, expense_negative, created_at, updated_at)
+         SELECT $1, $2, name, bank, delimiter, headers, date_column, date_format, amount_column, debit_column,
+                credit_column, description_column, category_column, decimal_comma, expense_negative, $3, $3
+         FROM import_presets
+         WHERE id::text = $4 AND user_id IS NULL
+         RETURNING {}",
+        PRESET_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&user_id)
+    .bind(now)
+    .bind(&preset_id)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(preset)) => HttpResponse::Created().json(ApiResponse::success(preset)),
+        Ok(None) => {
+            HttpResponse::NotFound().json(ApiResponse::<ImportPreset>::error("Import preset not found".to_string()))
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => HttpResponse::Conflict().json(
+            ApiResponse::<ImportPreset>::error("You already have a preset with this name".to_string()),
+        ),
+        Err(e) => {
+            log::error!("Error copying import preset: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<ImportPreset>::error("Failed to copy import preset".to_string()))
+        }
+    }
+}
+
+/// Rank the presets matching the header row of the file in the request body
+pub async fn detect_presets(user_id: web::Path<String>, body: web::Bytes, db: web::Data<PgPool>) -> HttpResponse {
+    let Some(header_row) = first_line(&body) else {
+        return HttpResponse::BadRequest().json(ApiResponse::<Vec<ImportPresetMatch>>::error(
+            "Send the file, or its header row, as the request body".to_string(),
+        ));
+    };
+
+    let presets = match fetch_presets(db.get_ref(), Some(&user_id)).await {
+        Ok(presets) => presets,
+        Err(e) => {
+            log::error!("Error fetching import presets: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<ImportPresetMatch>>::error("Database error".to_string()));
+        }
+    };
+
+    HttpResponse::Ok().json(ApiResponse::success(rank_presets(presets, &header_row)))
+}
+
+// ==================== Admin Handlers ====================
+
+/// `POST /api/admin/import-presets` - add a community preset
+pub async fn create_community_preset(
+    http_req: HttpRequest,
+    req: web::Json<ImportPresetFields>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+    let fields = match normalize_fields(req.into_inner()) {
+        Ok(fields) => fields,
+        Err(msg) => return HttpResponse::BadRequest().json(ApiResponse::<ImportPreset>::error(msg)),
+    };
+
+    let result = insert_preset(db.get_ref(), None, &fields).await;
+    if let Ok(preset) = &result {
+        log::info!(target: "audit", "Admin added community import preset {} ({})", preset.id, preset.name);
+    }
+    saved_response(result.map(Some), &fields.name, true)
+}
+
+/// `PUT /api/admin/import-presets/{preset_id}` - replace a community preset
+pub async fn update_community_preset(
+    http_req: HttpRequest,
+    preset_id: web::Path<String>,
+    req: web::Json<ImportPresetFields>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+    let fields = match normalize_fields(req.into_inner()) {
+        Ok(fields) => fields,
+        Err(msg) => return HttpResponse::BadRequest().json(ApiResponse::<ImportPreset>::error(msg)),
+    };
+
+    let result = replace_preset(db.get_ref(), None, &preset_id, &fields).await;
+    if let Ok(Some(preset)) = &result {
+        log::info!(target: "audit", "Admin replaced community import preset {} ({})", preset.id, preset.name);
+    }
+    saved_response(result, &fields.name, false)
+}
+
+/// `DELETE /api/admin/import-presets/{preset_id}` - remove a community preset
+///
+/// Copies users made are kept.
+pub async fn delete_community_preset(
+    http_req: HttpRequest,
+    preset_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    let result = sqlx::query("DELETE FROM import_presets WHERE id::text = $1 AND user_id IS NULL")
+        .bind(preset_id.as_str())
+        .execute(db.get_ref())
+        .await;
+    if matches!(&result, Ok(r) if r.rows_affected() > 0) {
+        log::info!(target: "audit", "Admin deleted community import preset {}", preset_id);
+    }
+    deleted_response(result)
+}
+
+// ==================== Responses ====================
+
+fn saved_response(result: Result<Option<ImportPreset>, sqlx::Error>, name: &str, created: bool) -> HttpResponse {
+    match result {
+        Ok(Some(preset)) if created => HttpResponse::Created().json(ApiResponse::success(preset)),
+        Ok(Some(preset)) => HttpResponse::Ok().json(ApiResponse::success(preset)),
+        Ok(None) => {
+            HttpResponse::NotFound().json(ApiResponse::<ImportPreset>::error("Import preset not found".to_string()))
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => HttpResponse::Conflict()
+            .json(ApiResponse::<ImportPreset>::error(format!("Import preset '{}' already exists", name))),
+        Err(e) => {
+            log::error!("Error saving import preset: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<ImportPreset>::error("Failed to save import preset".to_string()))
+        }
+    }
+}
+
+fn deleted_response(result: Result<sqlx::postgres::PgQueryResult, sqlx::Error>) -> HttpResponse {
+    match result {
+        Ok(query_result) if query_result.rows_affected() > 0 => HttpResponse::NoContent().finish(),
+        Ok(_) => HttpResponse::NotFound().json(ApiResponse::<String>::error("Import preset not found".to_string())),
+        Err(e) => {
+            log::error!("Error deleting import preset: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<String>::error("Failed to delete import preset".to_string()))
+        }
+    }
+}
+
+// ==================== Validation ====================
+
+/// Trim the fields, fill in defaults and check the columns against the headers
+pub(crate) fn normalize_fields(fields: ImportPresetFields) -> Result<ImportPresetFields, String> {
+    let name = fields.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err("Name must be 1-100 characters".to_string());
+    }
+    let bank = fields.bank.map(|b| b.trim().to_string()).filter(|b| !b.is_empty());
+    if bank.as_ref().is_some_and(|b| b.chars().count() > 100) {
+        return Err("bank must be at most 100 characters".to_string());
+    }
+
+    let delimiter = fields.delimiter.unwrap_or_else(|| ",".to_string());
+    if !DELIMITERS.contains(&delimiter.as_str()) {
+        return Err("delimiter must be one of ',', ';', '|' or a tab".to_string());
+    }
+
+    let headers: Vec<String> = fields.headers.iter().map(|h| clean_header(h)).collect();
+    if headers.is_empty() || headers.len() > MAX_HEADERS {
+        return Err(format!("headers must have 1 to {} columns", MAX_HEADERS));
+    }
+    for (i, header) in headers.iter().enumerate() {
+        if header.is_empty() || header.chars().count() > 100 {
+            return Err("Header names must be 1-100 characters".to_string());
+        }
+        if headers[..i].iter().any(|h| same_header(h, header)) {
+            return Err(format!("Header '{}' is listed twice", header));
+        }
+    }
+
+    // Mapped columns are stored as spelled in `headers`
+    let column = |label: &str, value: Option<String>| -> Result<Option<String>, String> {
+        let Some(value) = value.map(|v| clean_header(&v)).filter(|v| !v.is_empty()) else {
+            return Ok(None);
+        };
+        match headers.iter().find(|h| same_header(h, &value)) {
+            Some(header) => Ok(Some(header.clone())),
+            None => Err(format!("{} '{}' is not one of the headers", label, value)),
+        }
+    };
+    let Some(date_column) = column("date_column", Some(fields.date_column))? else {
+        return Err("date_column is required".to_string());
+    };
+    let amount_column = column("amount_column", fields.amount_column)?;
+    let debit_column = column("debit_column", fields.debit_column)?;
+    let credit_column = column("credit_column", fields.credit_column)?;
+    let description_column = column("description_column", fields.description_column)?;
+    let category_column = column("category_column", fields.category_column)?;
+    match (&amount_column, debit_column.is_some() || credit_column.is_some()) {
+        (None, false) => return Err("Give amount_column, or debit_column and/or credit_column".to_string()),
+        (Some(_), true) => return Err("Give amount_column or debit/credit columns, not both".to_string()),
+        _ => {}
+    }
+
+    let date_format = fields.date_format.map(|f| f.trim().to_string()).unwrap_or_else(|| "%Y-%m-%d".to_string());
+    if date_format.is_empty()
+        || date_format.chars().count() > 50
+        || StrftimeItems::new(&date_format).any(|item| matches!(item, Item::Error))
+    {
+        return Err(format!("Invalid date_format '{}'", date_format));
+    }
+
+    Ok(ImportPresetFields {
+        name,
+        bank,
+        delimiter: Some(delimiter),
+        headers,
+        date_column,
+        date_format: Some(date_format),
+        amount_column,
+        debit_column,
+        credit_column,
+        description_column,
+        category_column,
+        decimal_comma: fields.decimal_comma,
+        expense_negative: Some(fields.expense_negative.unwrap_or(true)),
+    })
+}
+
+// ==================== Detection ====================
+
+/// First non-blank line of `body`, without a byte order mark
+pub(crate) fn first_line(body: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(body);
+    text.trim_start_matches('\u{feff}')
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// Split one CSV line on `delimiter`, honouring double quotes
+pub(crate) fn split_record(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Header name as compared: trimmed, without surrounding quotes
+pub(crate) fn clean_header(header: &str) -> String {
+    header.trim().trim_matches('"').trim().to_string()
+}
+
+pub(crate) fn same_header(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+/// Presets whose mapped columns all appear in `header_row`, best match first
+///
+/// The score is the share of distinct header names the file and the preset
+/// have in common, so a file with extra or missing columns ranks lower than
+/// one with the preset's exact layout. Ties go to the user's own presets.
+pub(crate) fn rank_presets(presets: Vec<ImportPreset>, header_row: &str) -> Vec<ImportPresetMatch> {
+    let mut matches: Vec<ImportPresetMatch> = presets
+        .into_iter()
+        .filter_map(|preset| {
+            let delimiter = preset.delimiter.chars().next().unwrap_or(',');
+            let mut file: Vec<String> = split_record(header_row, delimiter)
+                .iter()
+                .map(|h| clean_header(h).to_lowercase())
+                .filter(|h| !h.is_empty())
+                .collect();
+            file.sort();
+            file.dedup();
+
+            let mapped = [
+                Some(&preset.date_column),
+                preset.amount_column.as_ref(),
+                preset.debit_column.as_ref(),
+                preset.credit_column.as_ref(),
+                preset.description_column.as_ref(),
+                preset.category_column.as_ref(),
+            ];
+            if mapped.into_iter().flatten().any(|column| !file.contains(&column.to_lowercase())) {
+                return None;
+            }
+
+            let common = preset.headers.iter().filter(|h| file.contains(&h.to_lowercase())).count();
+            let all = preset.headers.len() + file.len() - common;
+            let score = if all == 0 { 0.0 } else { common as f64 / all as f64 };
+            let score = (score * 100.0).round() / 100.0;
+            Some(ImportPresetMatch { exact: common == all, score, preset })
+        })
+        .collect();
+
+    matches.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a.preset.user_id.is_none().cmp(&b.preset.user_id.is_none()))
+            .then(a.preset.name.cmp(&b.preset.name))
+    });
+    matches
+}
+
+// ==================== Database Functions ====================
+
+/// The user's presets then the community ones; only the community ones for `None`
+pub(crate) async fn fetch_presets(pool: &PgPool, user_id: Option<&str>) -> Result<Vec<ImportPreset>, sqlx::Error> {
+    sqlx::query_as::<_, ImportPreset>(&format!(
+        "SELECT {} FROM import_presets
+         WHERE user_id IS NULL OR user_id = $1
+         ORDER BY user_id IS NULL, LOWER(name)",
+        PRESET_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// A preset the user may use: one of theirs or a community one
+pub(crate) async fn fetch_usable_preset(
+    pool: &PgPool,
+    user_id: &str,
+    preset_id: &str,
+) -> Result<Option<ImportPreset>, sqlx::Error> {
+    sqlx::query_as::<_, ImportPreset>(&format!(
+        "SELECT {} FROM import_presets WHERE id::text = $1 AND (user_id IS NULL OR user_id = $2)",
+        PRESET_COLUMNS
+    ))
+    .bind(preset_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+async fn insert_preset(
+    pool: &PgPool,
+    user_id: Option<&str>,
+    fields: &ImportPresetFields,
+) -> Result<ImportPreset, sqlx::Error> {
+    sqlx::query_as::<_, ImportPreset>(&format!(
+        "INSERT INTO import_presets
+             (id, user_id, name, bank, delimiter, headers, date_column, date_format, amount_column, debit_column,
+              credit_column, description_column, category_column, decimal_comma, expense_negative, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $16)
+         RETURNING {}",
+        PRESET_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(&fields.name)
+    .bind(&fields.bank)
+    .bind(&fields.delimiter)
+    .bind(&fields.headers)
+    .bind(&fields.date_column)
+    .bind(&fields.date_format)
+    .bind(&fields.amount_column)
+    .bind(&fields.debit_column)
+    .bind(&fields.credit_column)
+    .bind(&fields.description_column)
+    .bind(&fields.category_column)
+    .bind(fields.decimal_comma)
+    .bind(fields.expense_negative)
+    .bind(Utc::now())
+    .fetch_one(pool)
+    .await
+}
+
+/// Replace the user's preset (a community one for `None`); `None` if not found
+async fn replace_preset(
+    pool: &PgPool,
+    user_id: Option<&str>,
+    preset_id: &str,
+    fields: &ImportPresetFields,
+) -> Result<Option<ImportPreset>, sqlx::Error> {
+    sqlx::query_as::<_, ImportPreset>(&format!(
+        "UPDATE import_presets
+         SET name = $1, bank = $2, delimiter = $3, headers = $4, date_column = $5, date_format = $6,
+             amount_column = $7, debit_column = $8, credit_column = $9, description_column = $10,
+             category_column = $11, decimal_comma = $12, expense_negative = $13, updated_at = $14
+         WHERE id::text = $15 AND user_id IS NOT DISTINCT FROM $16
+         RETURNING {}",
+        PRESET_COLUMNS
+    ))
+    .bind(&fields.name)
+    .bind(&fields.bank)
+    .bind(&fields.delimiter)
+    .bind(&fields.headers)
+    .bind(&fields.date_column)
+    .bind(&fields.date_format)
+    .bind(&fields.amount_column)
+    .bind(&fields.debit_column)
+    .bind(&fields.credit_column)
+    .bind(&fields.description_column)
+    .bind(&fields.category_column)
+    .bind(fields.decimal_comma)
+    .bind(fields.expense_negative)
+    .bind(Utc::now())
+    .bind(preset_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/import-presets")
+        .public("/community", get_community_presets)
+        .create("", create_preset)
+        .user(Method::GET, "/{user_id}", get_user_presets)
+        .user(Method::POST, "/{user_id}/detect", detect_presets)
+        .user(Method::PUT, "/{user_id}/{preset_id}", update_preset)
+        .user(Method::DELETE, "/{user_id}/{preset_id}", delete_preset)
+        .user(Method::POST, "/{user_id}/{preset_id}/copy", copy_preset)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use bigdecimal::ToPrimitive;
+use chrono::{DateTime, Datelike, Months, NaiveDate, NaiveTime, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::cache::{get_or_set_cache, invalidate_user_cache, user_cache_key, CacheBackend};
+use crate::currencies;
+use crate::routes::ScopedRoutes;
+use crate::models::{
+    ApiResponse, CreateIncomeSourceRequest, IncomeMonth, IncomeReport, IncomeReportQuery, IncomeSource,
+    IncomeSourceSummary, UpdateIncomeSourceRequest,
+};
+
+// ==================== INCOME SOURCES ====================
+//
+// Income transactions can name where the money came from: an employer, a
+// side gig, a passive source. Sources are per user and can be deactivated
+// (kept for history, no new assignments) or deleted (their transactions are
+// unlinked, not removed).
+//
+// The income report (`GET /api/reports/{user_id}/income`) breaks the last
+// complete calendar months down per source:
+//
+// - `stability`: coefficient of variation (standard deviation / mean) of the
+//   monthly totals, counting months without income as zero. 0 is a perfectly
+//   steady salary; values above 1 mean lumpy income.
+// - `growth_percent`: average monthly income in the later half of the window
+//   vs the earlier half.
+//
+// Income without a source is reported as one "Unassigned" entry. Amounts are
+// in the user's base currency (409 while a wallet's currency has no rate to
+// it; see currencies.rs).
+//
+// ============================================================================
+
+/// Maximum number of income sources per user
+const MAX_SOURCES_PER_USER: i64 = 50;
+
+/// Default and maximum number of months covered by the income report
+const DEFAULT_REPORT_MONTHS: u32 = 12;
+const MAX_REPORT_MONTHS: u32 = 60;
+
+const INCOME_SOURCE_COLUMNS: &str = "id, user_id, name, kind, active, created_at, updated_at";
+
+// ==================== Handlers ====================
+
+/// List a user's income sources (with caching)
+pub async fn get_user_income_sources(
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, "income_sources").await;
+
+    let result = get_or_set_cache(
+        cache.get_ref(),
+        &cache_key,
+        fetch_income_sources(db.get_ref(), &user_id),
+    )
+    .await;
+
+    match result {
+        Ok(sources) => HttpResponse::Ok().json(ApiResponse::success(sources)),
+        Err(e) => HttpResponse::InternalServerError()
+            .json(ApiResponse::<Vec<IncomeSource>>::error(e.to_string())),
+    }
+}
+
+/// Add an income source
+pub async fn create_income_source(
+    req: web::Json<CreateIncomeSourceRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let name = req.name.trim();
+    if let Err(msg) = validate_name(name) {
+        return HttpResponse::BadRequest().json(ApiResponse::<IncomeSource>::error(msg));
+    }
+
+    let count: Result<(i64,), sqlx::Error> =
+        sqlx::query_as("SELECT COUNT(*) FROM income_sources WHERE user_id = $1")
+            .bind(&req.user_id)
+            .fetch_one(db.get_ref())
+            .await;
+    match count {
+        Ok((count,)) if count >= MAX_SOURCES_PER_USER => {
+            return HttpResponse::BadRequest().json(ApiResponse::<IncomeSource>::error(format!(
+                "A user can have at most {} income sources",
+                MAX_SOURCES_PER_USER
+            )));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Error counting income sources: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<IncomeSource>::error("Database error".to_string()));
+        }
+    }
+
+    let now = Utc::now();
+    let result = sqlx::query_as::<_, IncomeSource>(&format!(
+        "INSERT INTO income_sources (id, user_id, name, kind, active, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, TRUE, $5, $5)
+         ON CONFLICT (user_id, name) DO NOTHING
+         RETURNING {}",
+        INCOME_SOURCE_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(name)
+    .bind(req.kind.as_str())
+    .bind(now)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(source)) => {
+            let _ = invalidate_user_cache(cache.get_ref(), &req.user_id).await;
+            HttpResponse::Created().json(ApiResponse::success(source))
+        }
+        Ok(None) => HttpResponse::Conflict().json(ApiResponse::<IncomeSource>::error(format!(
+            "Income source '{}' already exists",
+            name
+        ))),
+        Err(e) => {
+            log::error!("Error creating income source: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<IncomeSource>::error("Failed to create income source".to_string()))
+        }
+    }
+}
+
+/// Rename, reclassify, or (de)activate an income source
+pub async fn update_income_source(
+    path: web::Path<(String, String)>,
+    req: web::Json<UpdateIncomeSourceRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, source_id) = path.into_inner();
+    let name = req.name.as_deref().map(str::trim);
+    if let Some(name) = name
+        && let Err(msg) = validate_name(name)
+    {
+        return HttpResponse::BadRequest().json(ApiResponse::<IncomeSource>::error(msg));
+    }
+
+    let result = sqlx::query_as::<_, IncomeSource>(&format!(
+        "UPDATE income_sources
+         SET name = COALESCE($1, name),
+             kind = COALESCE($2, kind),
+             active = COALESCE($3, active),
+             updated_at = $4
+         WHERE id::text = $5 AND user_id = $6
+         RETURNING {}",
+        INCOME_SOURCE_COLUMNS
+    ))
+    .bind(name)
+    .bind(req.kind.map(|k| k.as_str()))
+    .bind(req.active)
+    .bind(Utc::now())
+    .bind(&source_id)
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(source)) => {
+            let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+            HttpResponse::Ok().json(ApiResponse::success(source))
+        }
+        Ok(None) => HttpResponse::NotFound()
+            .json(ApiResponse::<IncomeSource>::error("Income source not found".to_string())),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => HttpResponse::Conflict()
+            .json(ApiResponse::<IncomeSource>::error("An income source with this name already exists".to_string())),
+        Err(e) => {
+            log::error!("Error updating income source: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<IncomeSource>::error("Failed to update income source".to_string()))
+        }
+    }
+}
+
+/// Delete an income source; its transactions are kept, unlinked
+pub async fn delete_income_source(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, source_id) = path.into_inner();
+
+    let result = sqlx::query("DELETE FROM income_sources WHERE id::text = $1 AND user_id = $2")
+        .bind(&source_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await;
+
+    match result {
+        Ok(query_result) if query_result.rows_affected() > 0 => {
+            let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+            HttpResponse::NoContent().finish()
+        }
+        Ok(_) => HttpResponse::NotFound().json(ApiResponse::<String>::error("Income source not found".to_string())),
+        Err(e) => {
+            log::error!("Error deleting income source: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<String>::error("Failed to delete income source".to_string()))
+        }
+    }
+}
+
+/// `GET /api/reports/{user_id}/income` - income per source and month
+pub async fn get_income_report(
+    user_id: web::Path<String>,
+    query: web::Query<IncomeReportQuery>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    let months = query.months.unwrap_or(DEFAULT_REPORT_MONTHS);
+    if months == 0 || months > MAX_REPORT_MONTHS {
+        return HttpResponse::BadRequest().json(ApiResponse::<IncomeReport>::error(format!(
+            "months must be between 1 and {}",
+            MAX_REPORT_MONTHS
+        )));
+    }
+
+    let currency = match currencies::reporting_currency(db.get_ref(), &user_id).await {
+        Ok(Ok(currency)) => currency,
+        Ok(Err(msg)) => return HttpResponse::Conflict().json(ApiResponse::<IncomeReport>::error(msg)),
+        Err(e) => {
+            log::error!("Error checking exchange rates: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<IncomeReport>::error("Database error".to_string()));
+        }
+    };
+
+    match build_income_report(db.get_ref(), &user_id, currency, months).await {
+        Ok(report) => HttpResponse::Ok().json(ApiResponse::success(report)),
+        Err(e) => {
+            log::error!("Error building income report: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<IncomeReport>::error("Database error".to_string()))
+        }
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err("Name must be 1-100 characters".to_string());
+    }
+    Ok(())
+}
+
+// ==================== Assignment ====================
+
+/// Whether `source_id` is an active income source of `user_id`
+pub async fn is_assignable(executor: impl PgExecutor<'_>, user_id: &str, source_id: Uuid) -> Result<bool, sqlx::Error> {
+    let found: Option<(Uuid,)> =
+        sqlx::query_as("SELECT id FROM income_sources WHERE id = $1 AND user_id = $2 AND active")
+            .bind(source_id)
+            .bind(user_id)
+            .fetch_optional(executor)
+            .await?;
+    Ok(found.is_some())
+}
+
+// ==================== Report ====================
+
+#[derive(sqlx::FromRow)]
+struct SourceMonthTotal {
+    income_source_id: Option<Uuid>,
+    month: NaiveDate,
+    total: BigDecimal,
+}
+
+async fn build_income_report(
+    pool: &PgPool,
+    user_id: &str,
+    currency: String,
+    months: u32,
+) -> Result<IncomeReport, sqlx::Error> {
+    let today = Utc::now().date_naive();
+    let end_month = today.with_day(1).unwrap_or(today);
+    let start_month = end_month.checked_sub_months(Months::new(months)).unwrap_or(end_month);
+    let (start, end) = (month_start(start_month), month_start(end_month));
+
+    let totals = sqlx::query_as::<_, SourceMonthTotal>(
+        "SELECT income_source_id, date_trunc('month', created_at AT TIME ZONE 'UTC')::date AS month,
+                SUM(convert_amount(amount, currency, $4)) AS total
+         FROM transactions
+         WHERE user_id = $1 AND deleted_at IS NULL AND transaction_type = 'income' AND created_at >= $2 AND created_at < $3
+         GROUP BY income_source_id, month",
+    )
+    .bind(user_id)
+    .bind(start)
+    .bind(end)
+    .bind(&currency)
+    .fetch_all(pool)
+    .await?;
+
+    let sources = fetch_income_sources(pool, user_id).await?;
+
+    let month_list: Vec<NaiveDate> = (0..months)
+        .filter_map(|i| start_month.checked_add_months(Months::new(i)))
+        .collect();
+    let mut by_source: HashMap<Option<Uuid>, HashMap<NaiveDate, BigDecimal>> = HashMap::new();
+    for row in totals {
+        by_source.entry(row.income_source_id).or_default().insert(row.month, row.total);
+    }
+
+    // Active sources are listed even without income in the window
+    let mut summaries: Vec<IncomeSourceSummary> = sources
+        .into_iter()
+        .filter_map(|s| {
+            let monthly = by_source.remove(&Some(s.id));
+            if !s.active && monthly.is_none() {
+                return None;
+            }
+            Some(summarize(Some(s.id), s.name, Some(s.kind), &month_list, monthly.unwrap_or_default()))
+        })
+        .collect();
+    if let Some(unassigned) = by_source.remove(&None) {
+        summaries.push(summarize(None, "Unassigned".to_string(), None, &month_list, unassigned));
+    }
+    summaries.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+
+    let total = summaries.iter().fold(BigDecimal::from(0), |sum, s| sum + &s.total);
+    Ok(IncomeReport { currency, start, end, total, sources: summaries })
+}
+
+fn summarize(
+    income_source_id: Option<Uuid>,
+    name: String,
+    kind: Option<String>,
+    month_list: &[NaiveDate],
+    mut totals: HashMap<NaiveDate, BigDecimal>,
+) -> IncomeSourceSummary {
+    let monthly: Vec<IncomeMonth> = month_list
+        .iter()
+        .map(|month| IncomeMonth {
+            month: *month,
+            total: totals.remove(month).unwrap_or_else(|| BigDecimal::from(0)),
+        })
+        .collect();
+
+    let zero = BigDecimal::from(0);
+    let total = monthly.iter().fold(zero.clone(), |sum, m| sum + &m.total);
+    let monthly_average = (&total / BigDecimal::from(monthly.len().max(1) as u64)).round(2);
+    let months_with_income = monthly.iter().filter(|m| m.total > zero).count() as u32;
+
+    let values: Vec<f64> = monthly.iter().map(|m| m.total.to_f64().unwrap_or(0.0)).collect();
+    let (stability, growth_percent) = income_trend(&values);
+
+    IncomeSourceSummary {
+        income_source_id,
+        name,
+        kind,
+        total,
+        monthly_average,
+        months_with_income,
+        stability,
+        growth_percent,
+        monthly,
+    }
+}
+
+/// (coefficient of variation, later-half vs earlier-half growth in percent)
+fn income_trend(values: &[f64]) -> (Option<f64>, Option<f64>) {
+    let mean = |v: &[f64]| if v.is_empty() { 0.0 } else { v.iter().sum::<f64>() / v.len() as f64 };
+
+    let average = mean(values);
+    let stability = (average > 0.0).then(|| {
+        let variance = values.iter().map(|v| (v - average).powi(2)).sum::<f64>() / values.len() as f64;
+        round2(variance.sqrt() / average)
+    });
+
+    let (earlier, later) = values.split_at(values.len() / 2);
+    let earlier_average = mean(earlier);
+    let growth_percent = (!earlier.is_empty() && earlier_average > 0.0)
+        .then(|| round2((mean(later) - earlier_average) / earlier_average * 100.0));
+
+    (stability, growth_percent)
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn month_start(month: NaiveDate) -> DateTime<Utc> {
+    month.and_time(NaiveTime::MIN).and_utc()
+}
+
+// ==================== Database Functions ====================
+
+async fn fetch_income_sources(pool: &PgPool, user_id: &str) -> Result<Vec<IncomeSource>, sqlx::Error> {
+    sqlx::query_as::<_, IncomeSource>(&format!(
+        "SELECT {} FROM income_sources WHERE user_id = $1 ORDER BY name",
+        INCOME_SOURCE_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/income-sources")
+        .create("", create_income_source)
+        .user(Method::GET, "/{user_id}", get_user_income_sources)
+        .user(Method::PUT, "/{user_id}/{source_id}", update_income_source)
+        .user(Method::DELETE, "/{user_id}/{source_id}", delete_income_source)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
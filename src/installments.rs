@@ -0,0 +1,470 @@
+use std::time::Duration;
+
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Months, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::cache::{invalidate_user_cache, CacheBackend};
+use crate::freezes::{self, FreezeError};
+use crate::jobs;
+use crate::models::{
+    ApiResponse, CreateInstallmentPlanRequest, Installment, InstallmentPlan, InstallmentPlanDetail, Transaction, Wallet,
+};
+use crate::money;
+use crate::routes::ScopedRoutes;
+use crate::standing_orders::WALLET_COLUMNS;
+use crate::wallet_types;
+
+// ==================== INSTALLMENT PLANS ====================
+//
+// A credit card expense can be converted into an installment plan (trả góp):
+// the purchase is paid back in `months` equal monthly installments instead
+// of with the next statement.
+//
+// Converting posts an income of the principal on the card (category
+// "installment"), which takes the purchase off what is owed now, plus the
+// one-off conversion fee (`fee_rate` percent of the principal) as an
+// expense. Interest is flat, as card issuers quote it: `monthly_rate`
+// percent of the principal per month, so
+//
+//   total = principal + principal × monthly_rate / 100 × months
+//
+// `INSTALLMENT_JOB` bills one installment a month from the conversion date
+// as an "installment" expense on the card, claimed in `job_executions` per
+// (plan, installment number). The total is split into `months` installments
+// a cent apart at most, larger ones first (`money::split_evenly`); the last
+// one bills whatever is left unbilled. Like card interest,
+// installments are billed on frozen cards too.
+//
+// What is not billed yet (`total - billed`) is still owed: net worth counts
+// it with the card's liabilities (see groups.rs).
+//
+// ============================================================================
+
+pub const INSTALLMENT_JOB: &str = "installments";
+
+/// How often due installments are billed
+pub const INSTALLMENT_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Category of the transactions a plan posts
+pub const INSTALLMENT_CATEGORY: &str = "installment";
+
+/// Allowed plan lengths, in months
+const MIN_MONTHS: i16 = 2;
+const MAX_MONTHS: i16 = 60;
+
+/// Highest monthly interest and conversion fee rates, in percent
+const MAX_RATE: i64 = 10;
+
+const PLAN_COLUMNS: &str = "id, user_id, wallet_id, transaction_id, description, principal, months, monthly_rate, \
+     fee_rate, fee, interest, total_amount, installment_amount, installments_posted, billed, next_due_at, status, \
+     created_at, updated_at";
+
+// ==================== Handlers ====================
+
+/// Convert a credit card expense into an installment plan
+pub async fn create_plan(
+    req: web::Json<CreateInstallmentPlanRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    if !(MIN_MONTHS..=MAX_MONTHS).contains(&req.months) {
+        return HttpResponse::BadRequest().json(ApiResponse::<InstallmentPlanDetail>::error(format!(
+            "months must be between {} and {}",
+            MIN_MONTHS, MAX_MONTHS
+        )));
+    }
+    let zero = BigDecimal::from(0);
+    let monthly_rate = req.monthly_rate.clone().unwrap_or_else(|| zero.clone());
+    let fee_rate = req.fee_rate.clone().unwrap_or_else(|| zero.clone());
+    for (field, rate) in [("monthly_rate", &monthly_rate), ("fee_rate", &fee_rate)] {
+        if *rate < zero || *rate > BigDecimal::from(MAX_RATE) {
+            return HttpResponse::BadRequest().json(ApiResponse::<InstallmentPlanDetail>::error(format!(
+                "{} must be between 0 and {}",
+                field, MAX_RATE
+            )));
+        }
+    }
+
+    match convert(db.get_ref(), &req, &monthly_rate, &fee_rate).await {
+        Ok(Ok(plan)) => {
+            let _ = invalidate_user_cache(cache.get_ref(), &plan.user_id).await;
+            log::info!(
+                target: "audit",
+                "installment plan created plan_id={} user_id={} transaction_id={} months={} total={}",
+                plan.id,
+                plan.user_id,
+                req.transaction_id,
+                plan.months,
+                plan.total_amount
+            );
+            HttpResponse::Created().json(ApiResponse::success(plan_detail(plan)))
+        }
+        Ok(Err(rejection)) => rejection.to_response(),
+        Err(e) => {
+            log::error!("Error creating installment plan: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<InstallmentPlanDetail>::error("Failed to create installment plan".to_string()))
+        }
+    }
+}
+
+/// List a user's installment plans, active first, newest first
+pub async fn get_user_plans(user_id: web::Path<String>, db: web::Data<PgPool>) -> HttpResponse {
+    let user_id = user_id.into_inner();
+
+    let result = sqlx::query_as::<_, InstallmentPlan>(&format!(
+        "SELECT {} FROM installment_plans WHERE user_id = $1
+         ORDER BY status = 'active' DESC, created_at DESC",
+        PLAN_COLUMNS
+    ))
+    .bind(&user_id)
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(plans) => HttpResponse::Ok().json(ApiResponse::success(plans)),
+        Err(e) => {
+            log::error!("Error fetching installment plans: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<InstallmentPlan>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Get a plan with its schedule
+pub async fn get_plan(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, plan_id) = path.into_inner();
+
+    let result = sqlx::query_as::<_, InstallmentPlan>(&format!(
+        "SELECT {} FROM installment_plans WHERE id::text = $1 AND user_id = $2",
+        PLAN_COLUMNS
+    ))
+    .bind(&plan_id)
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(plan)) => HttpResponse::Ok().json(ApiResponse::success(plan_detail(plan))),
+        Ok(None) => HttpResponse::NotFound()
+            .json(ApiResponse::<InstallmentPlanDetail>::error("Installment plan not found".to_string())),
+        Err(e) => {
+            log::error!("Error fetching installment plan: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<InstallmentPlanDetail>::error("Database error".to_string()))
+        }
+    }
+}
+
+// ==================== Plan Math ====================
+
+/// Due date of installment `number` (1-based): that many months after conversion
+fn due_at(plan: &InstallmentPlan, number: i16) -> DateTime<Utc> {
+    plan.created_at
+        .checked_add_months(Months::new(number as u32))
+        .unwrap_or(plan.created_at)
+}
+
+/// Amount of installment `number` in the plan's schedule
+fn installment_amount(plan: &InstallmentPlan, number: i16) -> BigDecimal {
+    money::split_evenly(&plan.total_amount, plan.months as usize)
+        .swap_remove((number - 1) as usize)
+}
+
+fn plan_detail(plan: InstallmentPlan) -> InstallmentPlanDetail {
+    let schedule = (1..=plan.months)
+        .map(|number| Installment {
+            number,
+            due_at: due_at(&plan, number),
+            amount: installment_amount(&plan, number),
+            posted: number <= plan.installments_posted,
+        })
+        .collect();
+    InstallmentPlanDetail {
+        remaining: &plan.total_amount - &plan.billed,
+        plan,
+        schedule,
+    }
+}
+
+// ==================== Conversion ====================
+
+/// Why a conversion was refused
+enum ConversionRejection {
+    NotFound,
+    NotExpense,
+    NotCreditCard,
+    AlreadyConverted,
+    Frozen(FreezeError),
+}
+
+impl ConversionRejection {
+    fn to_response(&self) -> HttpResponse {
+        match self {
+            ConversionRejection::NotFound => HttpResponse::NotFound()
+                .json(ApiResponse::<InstallmentPlanDetail>::error("Transaction not found".to_string())),
+            ConversionRejection::NotExpense => HttpResponse::BadRequest().json(
+                ApiResponse::<InstallmentPlanDetail>::error("Only purchases (expenses) can be converted".to_string()),
+            ),
+            ConversionRejection::NotCreditCard => HttpResponse::BadRequest()
+                .json(ApiResponse::<InstallmentPlanDetail>::error("Wallet is not a credit card".to_string())),
+            ConversionRejection::AlreadyConverted => HttpResponse::Conflict().json(
+                ApiResponse::<InstallmentPlanDetail>::error("Transaction is already an installment plan".to_string()),
+            ),
+            ConversionRejection::Frozen(e) => e.to_response::<InstallmentPlanDetail>(),
+        }
+    }
+}
+
+/// Take the expense off the card, charge the fee and create the plan
+async fn convert(
+    pool: &PgPool,
+    req: &CreateInstallmentPlanRequest,
+    monthly_rate: &BigDecimal,
+    fee_rate: &BigDecimal,
+) -> Result<Result<InstallmentPlan, ConversionRejection>, sqlx::Error> {
+    let mut db_tx = pool.begin().await?;
+
+    let transaction = sqlx::query_as::<_, Transaction>(
+        "SELECT id, sequence_number, user_id, wallet_id, bucket_id, income_source_id, amount, currency, transaction_type, category, description, notes, metadata, created_at, updated_at, version
+         FROM transactions WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+    )
+    .bind(req.transaction_id)
+    .bind(&req.user_id)
+    .fetch_optional(&mut *db_tx)
+    .await?;
+    let Some(transaction) = transaction else {
+        return Ok(Err(ConversionRejection::NotFound));
+    };
+    // Installments the card bills can't be converted again
+    if transaction.transaction_type != "expense" || transaction.category == INSTALLMENT_CATEGORY {
+        return Ok(Err(ConversionRejection::NotExpense));
+    }
+
+    let wallet = sqlx::query_as::<_, Wallet>(&format!(
+        "SELECT {} FROM wallets WHERE id = $1 FOR UPDATE",
+        WALLET_COLUMNS
+    ))
+    .bind(transaction.wallet_id)
+    .fetch_one(&mut *db_tx)
+    .await?;
+    if !wallet_types::template_for(&mut *db_tx, &wallet).await?.supports_credit_limit {
+        return Ok(Err(ConversionRejection::NotCreditCard));
+    }
+
+    let now = Utc::now();
+    let principal = transaction.amount.clone();
+    let months = BigDecimal::from(req.months);
+    let fee = money::percentage(&principal, fee_rate);
+    let interest = money::percentage(&principal, &(monthly_rate * &months));
+    let total = &principal + &interest;
+    let installment = money::split_evenly(&total, req.months as usize).swap_remove(0);
+    let first_due = now.checked_add_months(Months::new(1)).unwrap_or(now);
+
+    let plan = sqlx::query_as::<_, InstallmentPlan>(&format!(
+        "INSERT INTO installment_plans
+             (id, user_id, wallet_id, transaction_id, description, principal, months, monthly_rate, fee_rate,
+              fee, interest, total_amount, installment_amount, next_due_at, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $15)
+         ON CONFLICT (transaction_id) DO NOTHING
+         RETURNING {}",
+        PLAN_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(wallet.id)
+    .bind(transaction.id)
+    .bind(&transaction.description)
+    .bind(&principal)
+    .bind(req.months)
+    .bind(monthly_rate)
+    .bind(fee_rate)
+    .bind(&fee)
+    .bind(&interest)
+    .bind(&total)
+    .bind(&installment)
+    .bind(first_due)
+    .bind(now)
+    .fetch_optional(&mut *db_tx)
+    .await?;
+    let Some(plan) = plan else {
+        return Ok(Err(ConversionRejection::AlreadyConverted));
+    };
+
+    let label = transaction.description.as_deref().unwrap_or(&transaction.category);
+    post(
+        &mut db_tx,
+        &plan,
+        "income",
+        &principal,
+        &format!("Converted to {} installments: {}", plan.months, label),
+        now,
+    )
+    .await?;
+    if fee > BigDecimal::from(0) {
+        post(&mut db_tx, &plan, "expense", &fee, &format!("Installment conversion fee: {}", label), now).await?;
+    }
+
+    match freezes::ensure_not_frozen(&mut db_tx, wallet.id).await {
+        Ok(()) => {}
+        Err(FreezeError::Database(e)) => return Err(e),
+        Err(e) => return Ok(Err(ConversionRejection::Frozen(e))),
+    }
+
+    db_tx.commit().await?;
+    Ok(Ok(plan))
+}
+
+/// Post an "installment" transaction on the plan's card and move its balance
+async fn post(
+    conn: &mut PgConnection,
+    plan: &InstallmentPlan,
+    transaction_type: &str,
+    amount: &BigDecimal,
+    description: &str,
+    now: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO transactions (id, user_id, wallet_id, amount, transaction_type, category, description, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(&plan.user_id)
+    .bind(plan.wallet_id)
+    .bind(amount)
+    .bind(transaction_type)
+    .bind(INSTALLMENT_CATEGORY)
+    .bind(description)
+    .bind(now)
+    .execute(&mut *conn)
+    .await?;
+
+    let delta = if transaction_type == "income" { amount.clone() } else { -amount.clone() };
+    sqlx::query("UPDATE wallets SET balance = balance + $1 WHERE id = $2")
+        .bind(delta)
+        .bind(plan.wallet_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+// ==================== Installment Job ====================
+
+/// Bill every installment that has come due
+///
+/// Each installment is billed in its own DB transaction with the plan row
+/// locked (`FOR UPDATE SKIP LOCKED`); a plan that fell behind catches up one
+/// installment at a time. Returns the number of installments billed.
+pub async fn bill_due_installments(pool: &PgPool, cache: &dyn CacheBackend) -> Result<u64, sqlx::Error> {
+    let due: Vec<(Uuid,)> = sqlx::query_as(
+        "SELECT id FROM installment_plans WHERE status = 'active' AND next_due_at <= CURRENT_TIMESTAMP",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut billed = 0;
+    for (plan_id,) in due {
+        let mut user_id = None;
+        loop {
+            match bill_installment(pool, plan_id).await {
+                Ok(Some(owner)) => {
+                    billed += 1;
+                    user_id = Some(owner);
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    log::error!("Failed to bill installment of plan {}: {}", plan_id, e);
+                    break;
+                }
+            }
+        }
+
+        if let Some(user_id) = user_id {
+            let _ = invalidate_user_cache(cache, &user_id).await;
+        }
+    }
+
+    Ok(billed)
+}
+
+/// Bill the next installment of one plan if it is due
+///
+/// Returns the owner when an installment was billed, `None` if none is due
+/// or the plan is being billed by a concurrent run.
+async fn bill_installment(pool: &PgPool, plan_id: Uuid) -> Result<Option<String>, sqlx::Error> {
+    let mut db_tx = pool.begin().await?;
+
+    let plan = sqlx::query_as::<_, InstallmentPlan>(&format!(
+        "SELECT {} FROM installment_plans
+         WHERE id = $1 AND status = 'active' AND next_due_at <= CURRENT_TIMESTAMP
+         FOR UPDATE SKIP LOCKED",
+        PLAN_COLUMNS
+    ))
+    .bind(plan_id)
+    .fetch_optional(&mut *db_tx)
+    .await?;
+    let Some(plan) = plan else {
+        db_tx.rollback().await?;
+        return Ok(None);
+    };
+
+    let number = plan.installments_posted + 1;
+    let occurrence = format!("{}:{}", plan.id, number);
+    if !jobs::claim_occurrence(&mut db_tx, INSTALLMENT_JOB, &occurrence).await? {
+        db_tx.rollback().await?;
+        return Ok(None);
+    }
+
+    // The last installment bills what is left, whatever earlier ones billed
+    let amount = if number >= plan.months {
+        &plan.total_amount - &plan.billed
+    } else {
+        installment_amount(&plan, number)
+    };
+    let label = plan.description.as_deref().unwrap_or("card purchase");
+    post(
+        &mut db_tx,
+        &plan,
+        "expense",
+        &amount,
+        &format!("Installment {}/{}: {}", number, plan.months, label),
+        Utc::now(),
+    )
+    .await?;
+
+    let status = if number >= plan.months { "completed" } else { "active" };
+    sqlx::query(
+        "UPDATE installment_plans
+         SET installments_posted = $1, billed = billed + $2, next_due_at = $3, status = $4, updated_at = CURRENT_TIMESTAMP
+         WHERE id = $5",
+    )
+    .bind(number)
+    .bind(&amount)
+    .bind(due_at(&plan, number + 1))
+    .bind(status)
+    .bind(plan.id)
+    .execute(&mut *db_tx)
+    .await?;
+
+    db_tx.commit().await?;
+
+    log::info!("Billed installment {}/{} of plan {}: {}", number, plan.months, plan.id, amount);
+    Ok(Some(plan.user_id))
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/installments")
+        .create("", create_plan)
+        .user(Method::GET, "/user/{user_id}", get_user_plans)
+        .user(Method::GET, "/{user_id}/{plan_id}", get_plan)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
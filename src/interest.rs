@@ -0,0 +1,284 @@
+use actix_web::{web, HttpResponse, ResponseError};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::{Datelike, Months, NaiveDate, Utc};
+
+use crate::audit::{self, AuditContext};
+use crate::cache::{invalidate_user_cache, CacheBackend};
+use crate::hooks::{self, HookContext, TransactionCreated};
+use crate::jobs;
+use crate::models::{
+    ApiResponse, CreateTransactionRequest, InterestProjection, InterestProjectionQuery, ProjectedMonth, Transaction,
+    TransactionPolicy, Wallet,
+};
+use crate::money;
+use crate::policy;
+use crate::transactions;
+use crate::wallet_members::{self, Permission};
+use crate::wallets::fetch_wallet_by_id;
+
+// ==================== SAVINGS INTEREST ====================
+//
+// BankAccount wallets may carry an APY (annual percentage yield). Interest is
+// compounded monthly at the rate equivalent to the APY:
+//
+//   monthly_rate = (1 + apy / 100)^(1/12) - 1
+//
+// The rate is computed in decimal, and each month's interest is rounded once,
+// at the cent (see money.rs).
+//
+// The monthly interest job posts one "income" transaction per wallet per
+// calendar month (category "interest") for the month before, through
+// `transactions::record_transaction` like any other income, so a frozen
+// wallet is skipped until it is unfrozen and hooks see the posting.
+// `last_interest_posted_at` is the day interest is accrued up to: the start
+// of the month posted for, or the day the APY was first set. The first
+// posting is prorated by the days accrued since then. The marker moves in the
+// same DB transaction, so re-running the job within a month is a no-op. The
+// wallet/month is also claimed in `job_executions`, which makes the post
+// exactly-once across app instances.
+//
+// ============================================================================
+
+/// Default and maximum number of months returned by the projection endpoint
+const DEFAULT_PROJECTION_MONTHS: u32 = 12;
+const MAX_PROJECTION_MONTHS: u32 = 120;
+
+/// Category used for automatically posted interest transactions
+pub const INTEREST_CATEGORY: &str = "interest";
+
+/// Background job name (advisory lock and execution records)
+pub const INTEREST_JOB: &str = "monthly_interest";
+
+/// Significant digits kept of the monthly rate
+const RATE_PRECISION: u64 = 20;
+
+// ==================== Handlers ====================
+
+/// Project interest growth for a BankAccount wallet
+pub async fn get_interest_projection(
+    path: web::Path<(String, String)>,
+    query: web::Query<InterestProjectionQuery>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let (user_id, wallet_id) = path.into_inner();
+    let months = query.months.unwrap_or(DEFAULT_PROJECTION_MONTHS);
+
+    if months == 0 || months > MAX_PROJECTION_MONTHS {
+        return HttpResponse::BadRequest().json(ApiResponse::<InterestProjection>::error(
+            format!("months must be between 1 and {}", MAX_PROJECTION_MONTHS),
+        ));
+    }
+
+    let access =
+        match wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::View).await {
+            Ok(access) => access,
+            Err(e) => return e.error_response(),
+        };
+
+    let wallet = match fetch_wallet_by_id(db.get_ref(), &wallet_id, &access.owner_id).await {
+        Ok(w) => w,
+        Err(sqlx::Error::RowNotFound) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<InterestProjection>::error("Wallet not found".to_string()));
+        }
+        Err(e) => {
+            log::error!("Error fetching wallet: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<InterestProjection>::error("Database error".to_string()));
+        }
+    };
+
+    let apy = match &wallet.apy {
+        Some(apy) if wallet.wallet_type == "BankAccount" => apy.clone(),
+        _ => {
+            return HttpResponse::BadRequest().json(ApiResponse::<InterestProjection>::error(
+                "Wallet is not an interest-bearing BankAccount".to_string(),
+            ));
+        }
+    };
+
+    HttpResponse::Ok().json(ApiResponse::success(project_interest(&wallet, &apy, months)))
+}
+
+// ==================== Interest Math ====================
+
+/// Monthly compounding rate equivalent to the given APY (in percent)
+///
+/// The twelfth root is taken as a cube root and two square roots.
+fn monthly_rate(apy: &BigDecimal) -> BigDecimal {
+    let one = BigDecimal::from(1);
+    let yearly = &one + apy / BigDecimal::from(100);
+    let root = yearly.cbrt().sqrt().and_then(|root| root.sqrt()).unwrap_or_else(|| one.clone());
+    (root - one).with_prec(RATE_PRECISION)
+}
+
+/// Build a month-by-month projection assuming no other activity
+fn project_interest(wallet: &Wallet, apy: &BigDecimal, months: u32) -> InterestProjection {
+    let rate = monthly_rate(apy);
+    let mut balance = wallet.balance.clone();
+    let mut total_interest = BigDecimal::from(0);
+    let mut projected = Vec::with_capacity(months as usize);
+
+    for month in 1..=months {
+        let interest = money::round_cents(&(&balance * &rate));
+        balance += &interest;
+        total_interest += &interest;
+        projected.push(ProjectedMonth {
+            month,
+            interest,
+            balance: balance.clone(),
+        });
+    }
+
+    InterestProjection {
+        wallet_id: wallet.id,
+        apy: apy.clone(),
+        starting_balance: wallet.balance.clone(),
+        total_interest,
+        months: projected,
+    }
+}
+
+// ==================== Monthly Interest Job ====================
+
+/// Post interest for every interest-bearing wallet not yet credited this month
+///
+/// Each wallet is processed in its own DB transaction with the row locked
+/// (`FOR UPDATE SKIP LOCKED`), so a failure on one wallet doesn't block the rest.
+/// Returns the number of wallets credited.
+pub async fn post_monthly_interest(
+    pool: &PgPool,
+    cache: &dyn CacheBackend,
+) -> Result<u64, sqlx::Error> {
+    let due_ids: Vec<(Uuid,)> = sqlx::query_as(
+        "SELECT id FROM wallets
+         WHERE wallet_type = 'BankAccount' AND apy > 0 AND deleted_at IS NULL
+           AND (last_interest_posted_at IS NULL
+                OR date_trunc('month', last_interest_posted_at) < date_trunc('month', CURRENT_TIMESTAMP))",
+    )
+    .fetch_all(pool)
+    .await?;
+    if due_ids.is_empty() {
+        return Ok(0);
+    }
+    let transaction_policy = policy::transaction_policy(pool, cache).await?;
+
+    let mut credited = 0;
+    for (wallet_id,) in due_ids {
+        match post_wallet_interest(pool, &transaction_policy, wallet_id).await {
+            Ok(Some(posted)) => {
+                credited += 1;
+                let _ = invalidate_user_cache(cache, &posted.user_id).await;
+                let cx = HookContext { pool, cache };
+                hooks::transaction_created(&cx, TransactionCreated { transaction: &posted }).await;
+            }
+            Ok(None) => {}
+            Err(e) => log::error!("Failed to post interest for wallet {}: {}", wallet_id, e),
+        }
+    }
+
+    Ok(credited)
+}
+
+/// Days of interest owed for the month before `today`, and the days in that month
+///
+/// Interest accrues from `accrued_from` (see `last_interest_posted_at`), or
+/// over the whole month when it is unset or earlier.
+fn accrual_days(accrued_from: Option<NaiveDate>, today: NaiveDate) -> (i64, i64) {
+    let month_start = today.with_day(1).unwrap_or(today);
+    let previous_start = month_start.checked_sub_months(Months::new(1)).unwrap_or(month_start);
+    let from = accrued_from.filter(|from| *from > previous_start).unwrap_or(previous_start).min(month_start);
+    ((month_start - from).num_days(), (month_start - previous_start).num_days())
+}
+
+/// Post one month of interest for a single wallet
+///
+/// Returns the posted transaction, or `None` if the wallet was already
+/// credited (e.g. by a concurrent run), the interest rounds to zero, or the
+/// posting was refused (a frozen wallet); a refused posting is retried on the
+/// next run.
+async fn post_wallet_interest(
+    pool: &PgPool,
+    transaction_policy: &TransactionPolicy,
+    wallet_id: Uuid,
+) -> Result<Option<Transaction>, sqlx::Error> {
+    let mut db_tx = pool.begin().await?;
+    let context = AuditContext { actor: Some(format!("job:{}", INTEREST_JOB)), request_id: None };
+    audit::attach(&mut db_tx, &context).await?;
+
+    let wallet: Option<Wallet> = sqlx::query_as::<_, Wallet>(
+        "SELECT id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, created_at, updated_at, version
+         FROM wallets
+         WHERE id = $1 AND apy > 0 AND deleted_at IS NULL
+           AND (last_interest_posted_at IS NULL
+                OR date_trunc('month', last_interest_posted_at) < date_trunc('month', CURRENT_TIMESTAMP))
+         FOR UPDATE SKIP LOCKED",
+    )
+    .bind(wallet_id)
+    .fetch_optional(&mut *db_tx)
+    .await?;
+
+    let wallet = match wallet {
+        Some(w) => w,
+        None => {
+            db_tx.rollback().await?;
+            return Ok(None);
+        }
+    };
+
+    let apy = wallet.apy.clone().unwrap_or_else(|| BigDecimal::from(0));
+    let now = Utc::now();
+    let today = now.date_naive();
+    let (days, days_in_month) = accrual_days(wallet.last_interest_posted_at.map(|at| at.date_naive()), today);
+    let interest = money::prorate(&(&wallet.balance * monthly_rate(&apy)), days, days_in_month);
+
+    let occurrence = format!("{}:{}", wallet.id, now.format("%Y-%m"));
+    if !jobs::claim_occurrence(&mut db_tx, INTEREST_JOB, &occurrence).await? {
+        db_tx.rollback().await?;
+        return Ok(None);
+    }
+
+    // Always advance the marker so zero-interest months aren't rescanned
+    let month_start = today.with_day(1).unwrap_or(today).and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    sqlx::query("UPDATE wallets SET last_interest_posted_at = $1 WHERE id = $2")
+        .bind(month_start)
+        .bind(wallet.id)
+        .execute(&mut *db_tx)
+        .await?;
+
+    if interest <= BigDecimal::from(0) {
+        db_tx.commit().await?;
+        return Ok(None);
+    }
+
+    let req = CreateTransactionRequest {
+        user_id: wallet.user_id.clone(),
+        wallet_id: wallet.id,
+        amount: interest,
+        transaction_type: "income".to_string(),
+        category: INTEREST_CATEGORY.to_string(),
+        description: format!("Monthly interest ({}% APY)", apy),
+        notes: None,
+        metadata: None,
+        bucket_id: None,
+        income_source_id: None,
+        splits: Vec::new(),
+        // Computed from the wallet's own APY
+        confirm: true,
+    };
+    let posted = match transactions::record_transaction(&mut db_tx, pool, transaction_policy, &req).await {
+        Ok(created) => created.transaction,
+        Err(e) => {
+            db_tx.rollback().await?;
+            log::warn!("Interest for wallet {} not posted: {}", wallet.id, transactions::rejection_reason(e));
+            return Ok(None);
+        }
+    };
+
+    db_tx.commit().await?;
+
+    log::info!("Posted interest {} to wallet {} ({} of {} days)", posted.amount, wallet.id, days, days_in_month);
+    Ok(Some(posted))
+}
@@ -0,0 +1,275 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::Utc;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{PgConnection, PgPool};
+use tokio::sync::Semaphore;
+
+use crate::models::{JobExecutionSummary, JobStatus};
+
+// ==================== Background Jobs ====================
+//
+// Periodic jobs run as detached tokio tasks started from `main`. Every app
+// instance schedules every job, so two layers keep execution exactly-once
+// across replicas:
+//
+// 1. Leader per run: `spawn_singleton` takes a Postgres transaction-level
+//    advisory lock named after the job before each run; instances that don't
+//    get it skip the tick. The lock's transaction ends with the run (or the
+//    connection drops, if the instance dies mid-run). Locks are held on a
+//    small pool of their own, so a job never waits for a connection that
+//    another job's lock is holding, and at most `MAX_CONCURRENT_SINGLETONS`
+//    runs go at once per instance; the others wait their turn.
+// 2. Idempotent occurrences: jobs call `claim_occurrence` inside the DB
+//    transaction that performs their side effects. The `job_executions`
+//    primary key rejects a second claim of the same occurrence, so even an
+//    overlapping run (lock lost, manual re-run) can't post twice.
+//
+// ============================================================================
+
+/// Singleton runs at once on this instance, and connections in the lock pool
+const MAX_CONCURRENT_SINGLETONS: usize = 2;
+
+/// Turns of the singleton runs, so a queued run waits here rather than
+/// timing out on the lock pool
+static SINGLETON_SLOTS: Semaphore = Semaphore::const_new(MAX_CONCURRENT_SINGLETONS);
+
+/// Connections holding the singleton locks, apart from the app's pool
+static LOCK_POOL: OnceLock<PgPool> = OnceLock::new();
+
+/// Jobs scheduled on this instance, for the admin panel
+static JOB_STATUSES: LazyLock<Mutex<BTreeMap<&'static str, JobStatus>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+fn update_status(name: &'static str, update: impl FnOnce(&mut JobStatus)) {
+    let mut statuses = JOB_STATUSES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(status) = statuses.get_mut(name) {
+        update(status);
+    }
+}
+
+/// The jobs scheduled on this instance and how their runs went, by name
+pub fn job_statuses() -> Vec<JobStatus> {
+    let statuses = JOB_STATUSES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    statuses.values().cloned().collect()
+}
+
+/// Spawn a background task that runs `job` every `period`
+///
+/// The first run happens immediately on startup. Errors are the job's
+/// responsibility to log; a failing run never stops the schedule.
+pub fn spawn_periodic<F, Fut>(name: &'static str, period: Duration, mut job: F)
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    JOB_STATUSES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(
+            name,
+            JobStatus {
+                name: name.to_string(),
+                period_secs: period.as_secs(),
+                running: false,
+                runs: 0,
+                skipped: 0,
+                last_started_at: None,
+                last_finished_at: None,
+            },
+        );
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(period);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            log::debug!("Running background job: {}", name);
+            update_status(name, |status| {
+                status.running = true;
+                status.runs += 1;
+                status.last_started_at = Some(Utc::now());
+            });
+            job().await;
+            update_status(name, |status| {
+                status.running = false;
+                status.last_finished_at = Some(Utc::now());
+            });
+        }
+    });
+
+    log::info!("Scheduled background job '{}' every {:?}", name, period);
+}
+
+/// Like `spawn_periodic`, but each run only happens on the instance holding
+/// the job's advisory lock
+pub fn spawn_singleton<F, Fut>(name: &'static str, period: Duration, pool: PgPool, mut job: F)
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    spawn_periodic(name, period, move || {
+        let pool = pool.clone();
+        let run = job();
+        async move {
+            if run_singleton(name, &pool, run).await.is_none() {
+                update_status(name, |status| status.skipped += 1);
+            }
+        }
+    });
+}
+
+/// Run `job` once, holding the job's advisory lock
+///
+/// Returns `None` without running it when another instance holds the lock
+/// (or the lock can't be taken).
+pub async fn run_singleton<T>(name: &str, pool: &PgPool, job: impl Future<Output = T>) -> Option<T> {
+    let _slot = SINGLETON_SLOTS.acquire().await.expect("the singleton semaphore is never closed");
+
+    let mut lock_tx = match lock_pool(pool).begin().await {
+        Ok(lock_tx) => lock_tx,
+        Err(e) => {
+            log::error!("Job '{}' could not acquire a connection for its lock: {}", name, e);
+            return None;
+        }
+    };
+
+    match try_lock(&mut lock_tx, name).await {
+        Ok(true) => {}
+        Ok(false) => {
+            log::debug!("Job '{}' is running on another instance; skipping", name);
+            return None;
+        }
+        Err(e) => {
+            log::error!("Job '{}' failed to take its advisory lock: {}", name, e);
+            return None;
+        }
+    }
+
+    let result = job.await;
+
+    // Ending the transaction releases the lock; a failed commit rolls back,
+    // which does too
+    if let Err(e) = lock_tx.commit().await {
+        log::error!("Job '{}' failed to release its advisory lock: {}", name, e);
+    }
+    Some(result)
+}
+
+/// The lock pool, opened on first use with the app pool's connect options
+fn lock_pool(pool: &PgPool) -> &'static PgPool {
+    LOCK_POOL.get_or_init(|| {
+        PgPoolOptions::new()
+            .max_connections(MAX_CONCURRENT_SINGLETONS as u32)
+            .connect_lazy_with((*pool.connect_options()).clone())
+    })
+}
+
+async fn try_lock(conn: &mut PgConnection, name: &str) -> Result<bool, sqlx::Error> {
+    let (locked,): (bool,) = sqlx::query_as("SELECT pg_try_advisory_xact_lock(hashtext('ketobook.job.' || $1))")
+        .bind(name)
+        .fetch_one(conn)
+        .await?;
+    Ok(locked)
+}
+
+/// Record that `job_name` executed `occurrence_key`
+///
+/// Call inside the DB transaction that performs the occurrence's side effects.
+/// Returns `false` when the occurrence was already executed; the caller must
+/// then roll back.
+pub async fn claim_occurrence(
+    conn: &mut PgConnection,
+    job_name: &str,
+    occurrence_key: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO job_executions (job_name, occurrence_key) VALUES ($1, $2)
+         ON CONFLICT (job_name, occurrence_key) DO NOTHING",
+    )
+    .bind(job_name)
+    .bind(occurrence_key)
+    .execute(conn)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Last occurrence of each job in `job_executions` and how many ran in the
+/// last 24 hours, across instances
+pub async fn execution_summaries(pool: &PgPool) -> Result<Vec<JobExecutionSummary>, sqlx::Error> {
+    sqlx::query_as::<_, JobExecutionSummary>(
+        "SELECT job_name, MAX(executed_at) AS last_executed_at,
+                COUNT(*) FILTER (WHERE executed_at > CURRENT_TIMESTAMP - INTERVAL '24 hours') AS last_day
+         FROM job_executions
+         GROUP BY job_name
+         ORDER BY job_name",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::test_support;
+
+    #[tokio::test]
+    async fn an_occurrence_is_claimed_once() {
+        let Some(pool) = test_support::database("an_occurrence_is_claimed_once").await else { return };
+        let job = format!("test-job-{}", Uuid::new_v4());
+
+        let mut conn = pool.acquire().await.unwrap();
+        let first = claim_occurrence(&mut conn, &job, "2026-10-16").await.unwrap();
+        let again = claim_occurrence(&mut conn, &job, "2026-10-16").await.unwrap();
+        let next = claim_occurrence(&mut conn, &job, "2026-10-17").await.unwrap();
+        sqlx::query("DELETE FROM job_executions WHERE job_name = $1").bind(&job).execute(&mut *conn).await.unwrap();
+
+        assert!(first);
+        assert!(!again);
+        assert!(next);
+    }
+
+    // One test, as the lock pool outlives the runtime that opened it
+    #[tokio::test]
+    async fn singleton_runs_skip_a_held_lock_and_leave_the_pool_to_their_jobs() {
+        let Some(pool) =
+            test_support::database("singleton_runs_skip_a_held_lock_and_leave_the_pool_to_their_jobs").await
+        else {
+            return;
+        };
+        let job = format!("test-job-{}", Uuid::new_v4());
+
+        // Another instance holds the lock
+        let mut other = pool.begin().await.unwrap();
+        assert!(try_lock(&mut other, &job).await.unwrap());
+        assert_eq!(run_singleton(&job, &pool, async { 1 }).await, None);
+        other.rollback().await.unwrap();
+        assert_eq!(run_singleton(&job, &pool, async { 2 }).await, Some(2));
+
+        // More runs than the app pool has connections, each job using one
+        let small = PgPoolOptions::new()
+            .max_connections(2)
+            .acquire_timeout(Duration::from_secs(5))
+            .connect_with((*pool.connect_options()).clone())
+            .await
+            .unwrap();
+        let runs: Vec<_> = (0..6)
+            .map(|i| {
+                let (small, name) = (small.clone(), format!("{}-{}", job, i));
+                tokio::spawn(async move {
+                    let work = sqlx::query("SELECT pg_sleep(0.05)").execute(&small);
+                    run_singleton(&name, &small, work).await.map(|result| result.is_ok())
+                })
+            })
+            .collect();
+        for run in runs {
+            assert_eq!(run.await.unwrap(), Some(true));
+        }
+    }
+}
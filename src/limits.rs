@@ -0,0 +1,74 @@
+use chrono::Duration;
+
+use crate::config::AppConfig;
+use crate::models::{DateRangeQuery, PageQuery};
+
+// ==================== QUERY LIMITS ====================
+//
+// Global guards on how much data one request (or one report) may pull:
+//
+// - `MAX_PAGE_SIZE`: upper bound on `per_page` for list endpoints
+// - `MAX_EXPORT_ROWS`: upper bound on rows rendered into a report/export
+// - `MAX_DATE_RANGE_DAYS`: upper bound on a `from`..`to` filter span
+//
+// Handlers validate their query through these helpers and return the error
+// message as a 400, so an oversized request fails fast instead of scanning.
+//
+// ============================================================================
+
+/// Validated pagination for a list query
+#[derive(Debug, Clone, Copy)]
+pub struct PageParams {
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl PageParams {
+    pub fn limit(&self) -> i64 {
+        i64::from(self.per_page)
+    }
+
+    pub fn offset(&self) -> i64 {
+        i64::from(self.page - 1) * i64::from(self.per_page)
+    }
+
+    /// Cache key fragment identifying this page
+    pub fn cache_suffix(&self) -> String {
+        format!("p{}:n{}", self.page, self.per_page)
+    }
+}
+
+/// Resolve `page`/`per_page` against the configured defaults and maximum
+pub fn page_params(config: &AppConfig, query: &PageQuery) -> Result<PageParams, String> {
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(config.default_page_size);
+
+    if page == 0 {
+        return Err("page must be 1 or greater".to_string());
+    }
+    if per_page == 0 || per_page > config.max_page_size {
+        return Err(format!(
+            "per_page must be between 1 and {} (got {})",
+            config.max_page_size, per_page
+        ));
+    }
+
+    Ok(PageParams { page, per_page })
+}
+
+/// Reject inverted ranges and spans longer than `MAX_DATE_RANGE_DAYS`
+pub fn validate_date_range(config: &AppConfig, range: &DateRangeQuery) -> Result<(), String> {
+    if let (Some(from), Some(to)) = (range.from, range.to) {
+        if from > to {
+            return Err("from must be earlier than to".to_string());
+        }
+        if to - from > Duration::days(config.max_date_range_days) {
+            return Err(format!(
+                "Date range spans {} days; the maximum is {}",
+                (to - from).num_days(),
+                config.max_date_range_days
+            ));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use actix_web::body::BoxBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{header, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpResponse};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::models::{ApiResponse, Debt, Transaction, Wallet};
+
+// ==================== CLIENT CACHE HINTS ====================
+//
+// Wallets, transactions and debts carry a `version` that a trigger bumps on
+// every change to the row (see the entity versions migration), so an
+// offline-capable client can tell whether its copy of one is current without
+// comparing fields.
+//
+// The wallet, transaction and debt lists send `X-List-Version` (also as the
+// `ETag`): a fingerprint of the ids and versions on the page, in order, and
+// the total count where the list has one. It changes whenever an item on the
+// page is added, removed, moved or changed. A client that sends it back in
+// `If-None-Match` gets `304 Not Modified` without a body while it still
+// matches (`not_modified`, wrapping every route). The list is still read (from
+// the cache where it can be); what's saved is the download.
+//
+// ============================================================================
+
+pub const LIST_VERSION_HEADER: &str = "X-List-Version";
+
+/// A row whose changes bump a version
+pub trait Versioned {
+    fn id(&self) -> Uuid;
+    fn version(&self) -> i64;
+}
+
+impl Versioned for Wallet {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn version(&self) -> i64 {
+        self.version
+    }
+}
+
+impl Versioned for Transaction {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn version(&self) -> i64 {
+        self.version
+    }
+}
+
+impl Versioned for Debt {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn version(&self) -> i64 {
+        self.version
+    }
+}
+
+/// Fingerprint of a list page: its items' ids and versions and the total count
+pub fn list_version<T: Versioned>(items: &[T], total_count: Option<i64>) -> String {
+    let mut hasher = Sha256::new();
+    for item in items {
+        hasher.update(item.id().as_bytes());
+        hasher.update(item.version().to_be_bytes());
+    }
+    if let Some(total_count) = total_count {
+        hasher.update(total_count.to_be_bytes());
+    }
+    hex::encode(&hasher.finalize()[..8])
+}
+
+/// The list with its version headers
+pub fn list_response<T: Serialize>(version: String, data: T) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header((header::ETAG, format!("\"{}\"", version)))
+        .insert_header((LIST_VERSION_HEADER, version))
+        .json(ApiResponse::success(data))
+}
+
+/// Middleware: a list response whose version the client sent in
+/// `If-None-Match` becomes `304 Not Modified` without a body
+pub async fn not_modified(req: ServiceRequest, next: Next<BoxBody>) -> Result<ServiceResponse<BoxBody>, Error> {
+    let known = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let res = next.call(req).await?;
+
+    let Some(known) = known else {
+        return Ok(res);
+    };
+    let current = res.headers().get(LIST_VERSION_HEADER).and_then(|value| value.to_str().ok());
+    let unchanged = res.status() == StatusCode::OK
+        && current.is_some_and(|current| {
+            known
+                .split(',')
+                .any(|tag| tag.trim().trim_start_matches("W/").trim_matches('"') == current)
+        });
+    if !unchanged {
+        return Ok(res);
+    }
+    Ok(res.map_body(|head, _| {
+        head.status = StatusCode::NOT_MODIFIED;
+        BoxBody::new(())
+    }))
+}
@@ -0,0 +1,193 @@
+use std::time::Duration;
+
+use actix_web::HttpResponse;
+use async_trait::async_trait;
+
+use crate::cache::CacheBackend;
+use crate::config::AppConfig;
+use crate::models::ApiResponse;
+
+// ==================== BRUTE-FORCE PROTECTION ====================
+//
+// Failed credential checks are counted in Redis per (scope, subject), e.g.
+// ("admin", client IP), so the limit holds across instances:
+//
+// - Failures are counted in a fixed window (`LOGIN_ATTEMPT_WINDOW_SECS`). The
+//   count and its expiry, and the lock and clearing the count, are each set in
+//   one step (see Atomic Scripts in cache.rs).
+// - Reaching `LOGIN_MAX_ATTEMPTS` locks the subject out. The lockout doubles
+//   with each lockout in the last 24 hours, from `LOGIN_LOCKOUT_BASE_SECS` up
+//   to `LOGIN_LOCKOUT_MAX_SECS`.
+// - From `LOGIN_CAPTCHA_AFTER` failures on, a CAPTCHA token is required when a
+//   `CaptchaVerifier` is configured (none ships yet; this is the hook).
+// - Lockouts are written to the `audit` log target.
+//
+// When Redis is unreachable the guard fails open and logs a warning: a Redis
+// outage should not lock every operator out.
+//
+// ============================================================================
+
+/// How long lockout history counts toward the exponential backoff
+const LOCKOUT_HISTORY_SECS: u64 = 24 * 3600;
+
+/// Thresholds for one credential check
+#[derive(Debug, Clone, Copy)]
+pub struct LockoutPolicy {
+    pub max_attempts: u32,
+    pub captcha_after: u32,
+    pub window_secs: u64,
+    pub base_lockout_secs: u64,
+    pub max_lockout_secs: u64,
+}
+
+impl LockoutPolicy {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            max_attempts: config.login_max_attempts.max(1),
+            captcha_after: config.login_captcha_after,
+            window_secs: config.login_attempt_window_secs.max(1),
+            base_lockout_secs: config.login_lockout_base_secs.max(1),
+            max_lockout_secs: config.login_lockout_max_secs.max(config.login_lockout_base_secs),
+        }
+    }
+
+    /// Lockout length after `previous` lockouts: base * 2^previous, capped
+    fn lockout_secs(&self, previous: u32) -> u64 {
+        self.base_lockout_secs
+            .saturating_mul(1u64 << previous.min(32))
+            .min(self.max_lockout_secs)
+    }
+}
+
+/// Verifies a CAPTCHA response token (reCAPTCHA, hCaptcha, Turnstile, ...)
+#[async_trait]
+pub trait CaptchaVerifier: Send + Sync {
+    async fn verify(&self, token: &str, remote_addr: &str) -> bool;
+}
+
+/// Why a credential check may not proceed
+#[derive(Debug)]
+pub enum Rejection {
+    /// Locked out; retry after the given time
+    Locked(Duration),
+    /// Too many recent failures; a valid CAPTCHA token is required
+    CaptchaRequired,
+}
+
+impl Rejection {
+    pub fn to_response(&self) -> HttpResponse {
+        match self {
+            Rejection::Locked(retry_after) => HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", retry_after.as_secs().to_string()))
+                .json(ApiResponse::<String>::error(format!(
+                    "Too many failed attempts. Try again in {} seconds",
+                    retry_after.as_secs()
+                ))),
+            Rejection::CaptchaRequired => HttpResponse::Forbidden()
+                .json(ApiResponse::<String>::error("CAPTCHA verification required".to_string())),
+        }
+    }
+}
+
+fn failures_key(scope: &str, subject: &str) -> String {
+    format!("ketobook:auth:failures:{}:{}", scope, subject)
+}
+
+fn lock_key(scope: &str, subject: &str) -> String {
+    format!("ketobook:auth:lock:{}:{}", scope, subject)
+}
+
+fn history_key(scope: &str, subject: &str) -> String {
+    format!("ketobook:auth:lockouts:{}:{}", scope, subject)
+}
+
+/// Check whether `subject` may attempt the credential check now
+///
+/// Call before verifying the credential.
+pub async fn check(
+    cache: &dyn CacheBackend,
+    policy: &LockoutPolicy,
+    captcha: Option<(&dyn CaptchaVerifier, Option<&str>)>,
+    scope: &str,
+    subject: &str,
+    remote_addr: &str,
+) -> Result<(), Rejection> {
+    let locked_for = match cache.ttl(&lock_key(scope, subject)).await {
+        Ok(ttl) => ttl,
+        Err(e) => {
+            log::warn!("Lockout check unavailable for {}:{}: {}", scope, subject, e);
+            return Ok(());
+        }
+    };
+    if let Some(secs) = locked_for {
+        return Err(Rejection::Locked(Duration::from_secs(secs)));
+    }
+
+    if let Some((verifier, token)) = captcha
+        && policy.captcha_after > 0
+    {
+        let failures: u32 = cache
+            .get(&failures_key(scope, subject))
+            .await
+            .ok()
+            .flatten()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(0);
+        if failures >= policy.captcha_after {
+            let passed = match token {
+                Some(token) => verifier.verify(token, remote_addr).await,
+                None => false,
+            };
+            if !passed {
+                return Err(Rejection::CaptchaRequired);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Count a failed attempt; returns the lockout if this failure triggered one
+pub async fn record_failure(
+    cache: &dyn CacheBackend,
+    policy: &LockoutPolicy,
+    scope: &str,
+    subject: &str,
+) -> Option<Duration> {
+    let key = failures_key(scope, subject);
+
+    let failures = match cache.incr_ex(&key, policy.window_secs, false).await {
+        Ok(n) => n.min(u32::MAX as u64) as u32,
+        Err(e) => {
+            log::warn!("Failed to count login failure for {}:{}: {}", scope, subject, e);
+            return None;
+        }
+    };
+    if failures < policy.max_attempts {
+        return None;
+    }
+
+    // Lock out, doubling with each lockout in the history window
+    let previous = cache
+        .incr_ex(&history_key(scope, subject), LOCKOUT_HISTORY_SECS, true)
+        .await
+        .unwrap_or(1)
+        .min(u32::MAX as u64) as u32;
+    let secs = policy.lockout_secs(previous.saturating_sub(1));
+
+    let _ = cache.set_ex_and_del(&lock_key(scope, subject), "1".to_string(), secs, &key).await;
+
+    log::warn!(
+        target: "audit",
+        "lockout scope={} subject={} failures={} lockout_secs={} lockout_count={}",
+        scope, subject, failures, secs, previous
+    );
+    Some(Duration::from_secs(secs))
+}
+
+/// Clear the failure count after a successful attempt
+///
+/// Lockout history is kept so that repeated lockouts still escalate.
+pub async fn record_success(cache: &dyn CacheBackend, scope: &str, subject: &str) {
+    let _ = cache.del(&failures_key(scope, subject)).await;
+}
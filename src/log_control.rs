@@ -0,0 +1,482 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::CONTENT_LENGTH;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, HttpRequest, HttpResponse};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use chrono::Utc;
+use tracing::subscriber::Interest;
+use tracing::{span, Event, Metadata, Subscriber};
+use tracing_log::{AsLog, NormalizeEvent};
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::fmt::format::{FormatEvent, FormatFields, Writer};
+use tracing_subscriber::fmt::FmtContext;
+use tracing_subscriber::layer::{Context, Filter};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::registry::{LookupSpan, Registry};
+use tracing_subscriber::reload;
+
+use crate::admin::authorize_admin;
+use crate::auth::AuthUser;
+use crate::cache::CacheBackend;
+use crate::config::AppConfig;
+use crate::models::{ApiResponse, LogSettings, UpdateLogSettingsRequest};
+use crate::timeouts;
+
+// ==================== RUNTIME LOG CONTROL ====================
+//
+// Debugging a production report (a user seeing a stale balance) needs debug
+// logs from a few modules or of a few requests, without a restart and
+// without debug logs of everything:
+//
+// - `PUT /api/admin/logging` replaces the log filter (`RUST_LOG` syntax, e.g.
+//   `info,ketobook::cache=debug`) and request sampling: a percentage of
+//   requests and/or every request of listed users. While a sampled request
+//   is handled, every module logs at `sample_level`; with `log_bodies` its
+//   request and response bodies (up to `MAX_LOGGED_BODY` bytes, streamed
+//   bodies never) are logged on the `sampled` target.
+// - Settings expire after `ttl_secs` (an hour by default), and the instance
+//   returns to `RUST_LOG` without sampling. `DELETE` returns to them at once.
+// - Settings are kept in the cache under `LOG_SETTINGS_KEY` for their TTL
+//   and every instance polls it every `POLL_INTERVAL`, so a change reaches
+//   the cluster within seconds. With the in-process cache it only applies to
+//   the instance that served the request.
+//
+// The subscriber (`install`) writes through tracing-subscriber's fmt layer,
+// filtered by a `reload` layer that new settings are swapped into. The code
+// base logs with the `log` macros; tracing-log forwards those records to the
+// subscriber, so the filter applies to them like to any event. Bodies may
+// hold financial data; route the `sampled` target accordingly and keep
+// sampling short.
+//
+// ============================================================================
+
+/// Cache key holding the settings in force across the cluster
+const LOG_SETTINGS_KEY: &str = "logging:settings";
+
+/// How often each instance picks up changed or expired settings
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+const DEFAULT_TTL_SECS: u64 = 3600;
+const MAX_TTL_SECS: u64 = 86400;
+
+/// Users whose requests can be sampled at once
+const MAX_SAMPLE_USERS: usize = 100;
+
+/// Largest body logged by a sampled request
+const MAX_LOGGED_BODY: usize = 16 * 1024;
+
+const DEFAULT_SAMPLE_LEVEL: &str = "debug";
+
+tokio::task_local! {
+    /// Level logged while the current task handles a sampled request
+    static SAMPLED: LevelFilter;
+}
+
+/// Request sampling in force
+struct Sampling {
+    percent: f64,
+    user_ids: HashSet<String>,
+    level: LevelFilter,
+    bodies: bool,
+}
+
+/// The settings in force and the sampling they ask for
+struct Runtime {
+    settings: LogSettings,
+    sampling: Option<Arc<Sampling>>,
+}
+
+/// What the subscriber lets through: events passing `directives`, and
+/// anything up to `sample_level` while a sampled request is handled
+struct RuntimeFilter {
+    directives: EnvFilter,
+    sample_level: Option<LevelFilter>,
+}
+
+/// The startup settings: `RUST_LOG` (default `info`) without sampling
+static DEFAULTS: OnceLock<LogSettings> = OnceLock::new();
+
+static RUNTIME: OnceLock<RwLock<Runtime>> = OnceLock::new();
+
+/// Swaps the filter of the installed subscriber
+static RELOAD: OnceLock<reload::Handle<RuntimeFilter, Registry>> = OnceLock::new();
+
+impl<S> Filter<S> for RuntimeFilter {
+    fn enabled(&self, metadata: &Metadata<'_>, cx: &Context<'_, S>) -> bool {
+        sampled(metadata) || Filter::<S>::enabled(&self.directives, metadata, cx)
+    }
+
+    fn callsite_enabled(&self, metadata: &'static Metadata<'static>) -> Interest {
+        // A callsite the directives turn off still logs in a sampled request
+        match self.sample_level {
+            Some(_) => Interest::sometimes(),
+            None => Filter::<S>::callsite_enabled(&self.directives, metadata),
+        }
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        let directives = Filter::<S>::max_level_hint(&self.directives)?;
+        Some(self.sample_level.map_or(directives, |level| level.max(directives)))
+    }
+
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, cx: Context<'_, S>) {
+        Filter::<S>::on_new_span(&self.directives, attrs, id, cx);
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, cx: Context<'_, S>) {
+        Filter::<S>::on_record(&self.directives, id, values, cx);
+    }
+
+    fn on_enter(&self, id: &span::Id, cx: Context<'_, S>) {
+        Filter::<S>::on_enter(&self.directives, id, cx);
+    }
+
+    fn on_exit(&self, id: &span::Id, cx: Context<'_, S>) {
+        Filter::<S>::on_exit(&self.directives, id, cx);
+    }
+
+    fn on_close(&self, id: span::Id, cx: Context<'_, S>) {
+        Filter::<S>::on_close(&self.directives, id, cx);
+    }
+}
+
+/// Whether the current task handles a sampled request logging at this level
+fn sampled(metadata: &Metadata<'_>) -> bool {
+    SAMPLED.try_with(|level| metadata.level() <= level).unwrap_or(false)
+}
+
+/// One line per event: `[time LEVEL target request_id=...] message`
+struct LineFormat;
+
+impl<S, N> FormatEvent<S, N> for LineFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, cx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        // Records of the `log` macros carry their own target
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+        let request_id = timeouts::current_request_id()
+            .map(|id| format!(" request_id={}", id))
+            .unwrap_or_default();
+        write!(
+            writer,
+            "[{} {:<5} {}{}] ",
+            Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+            metadata.level(),
+            metadata.target(),
+            request_id
+        )?;
+        cx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+fn defaults() -> &'static LogSettings {
+    DEFAULTS.get_or_init(|| LogSettings {
+        filter: std::env::var("RUST_LOG")
+            .ok()
+            .filter(|filter| !filter.trim().is_empty())
+            .unwrap_or_else(|| "info".to_string()),
+        sample_percent: 0.0,
+        sample_user_ids: Vec::new(),
+        sample_level: DEFAULT_SAMPLE_LEVEL.to_string(),
+        log_bodies: false,
+        expires_at: None,
+    })
+}
+
+fn runtime() -> &'static RwLock<Runtime> {
+    RUNTIME.get_or_init(|| RwLock::new(Runtime { settings: defaults().clone(), sampling: None }))
+}
+
+/// Install the process's subscriber, filtered by `RUST_LOG` until the
+/// settings are changed, and forward the `log` macros to it
+pub fn install() {
+    let settings = defaults().clone();
+    // Nothing can log yet; an invalid `RUST_LOG` is reported once something can
+    let (parsed, invalid) = match parse(&settings) {
+        Ok(parsed) => (parsed, None),
+        Err(e) => {
+            let info = LogSettings { filter: "info".to_string(), ..settings.clone() };
+            (parse(&info).expect("info is a valid filter"), Some(e))
+        }
+    };
+    let (runtime, filter) = parsed;
+
+    let (filter, handle) = reload::Layer::new(filter);
+    let layer = tracing_subscriber::fmt::layer()
+        .event_format(LineFormat)
+        .with_ansi(false)
+        .with_writer(std::io::stderr)
+        .with_filter(filter);
+    if tracing_subscriber::registry().with(layer).try_init().is_err() {
+        return;
+    }
+    let _ = RELOAD.set(handle);
+    if let Ok(mut current) = self::runtime().write() {
+        *current = runtime;
+    }
+
+    if let Some(e) = invalid {
+        log::warn!("Invalid RUST_LOG '{}' ({}); logging at info", settings.filter, e);
+    }
+}
+
+/// Parse settings into a runtime and the filter they ask for, or say why
+/// they are invalid
+fn parse(settings: &LogSettings) -> Result<(Runtime, RuntimeFilter), String> {
+    let directives = EnvFilter::builder()
+        .parse(&settings.filter)
+        .map_err(|e| format!("filter: {}", e))?;
+    let level = LevelFilter::from_str(&settings.sample_level)
+        .ok()
+        .filter(|level| *level >= LevelFilter::INFO)
+        .ok_or_else(|| "sample_level must be info, debug or trace".to_string())?;
+    if !(0.0..=100.0).contains(&settings.sample_percent) {
+        return Err("sample_percent must be between 0 and 100".to_string());
+    }
+    if settings.sample_user_ids.len() > MAX_SAMPLE_USERS {
+        return Err(format!("at most {} users can be sampled", MAX_SAMPLE_USERS));
+    }
+
+    let sampling = (settings.sample_percent > 0.0 || !settings.sample_user_ids.is_empty()).then(|| {
+        Arc::new(Sampling {
+            percent: settings.sample_percent,
+            user_ids: settings.sample_user_ids.iter().cloned().collect(),
+            level,
+            bodies: settings.log_bodies,
+        })
+    });
+    let filter = RuntimeFilter { directives, sample_level: sampling.as_ref().map(|s| s.level) };
+    Ok((Runtime { settings: settings.clone(), sampling }, filter))
+}
+
+/// Put parsed settings in force on this instance
+fn apply((runtime, filter): (Runtime, RuntimeFilter)) {
+    if let Some(handle) = RELOAD.get()
+        && handle.reload(filter).is_ok()
+    {
+        // `log` macros below the subscriber's most verbose level are skipped early
+        log::set_max_level(LevelFilter::current().as_log());
+    }
+    if let Ok(mut current) = self::runtime().write() {
+        *current = runtime;
+    }
+}
+
+/// The settings in force on this instance
+fn current() -> LogSettings {
+    runtime()
+        .read()
+        .map(|r| r.settings.clone())
+        .unwrap_or_else(|_| defaults().clone())
+}
+
+/// Follow the cluster's settings for the life of the process, returning to
+/// the startup settings once they expire or are cleared
+pub fn spawn_settings_poller(cache: Arc<dyn CacheBackend>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let stored = match cache.get(LOG_SETTINGS_KEY).await {
+                Ok(stored) => stored,
+                Err(e) => {
+                    log::warn!("Failed to read log settings: {}", e);
+                    continue;
+                }
+            };
+            let settings = stored
+                .and_then(|json| match serde_json::from_str::<LogSettings>(&json) {
+                    Ok(settings) => Some(settings),
+                    Err(e) => {
+                        log::warn!("Ignoring stored log settings: {}", e);
+                        None
+                    }
+                })
+                .filter(|s| s.expires_at.is_some_and(|at| at > Utc::now()))
+                .unwrap_or_else(|| defaults().clone());
+            if settings == current() {
+                continue;
+            }
+            match parse(&settings) {
+                Ok(runtime) => {
+                    apply(runtime);
+                    log::info!(
+                        "Log settings applied: filter={} sample_percent={} sample_users={} expires_at={:?}",
+                        settings.filter,
+                        settings.sample_percent,
+                        settings.sample_user_ids.len(),
+                        settings.expires_at
+                    );
+                }
+                Err(e) => log::warn!("Ignoring stored log settings: {}", e),
+            }
+        }
+    });
+}
+
+// ==================== Request Sampling ====================
+
+/// Middleware: log a sampled request at the sampling level, with its bodies
+/// when asked
+///
+/// Runs inside authentication, so a request is matched on its owner.
+pub async fn sample(mut req: ServiceRequest, next: Next<BoxBody>) -> Result<ServiceResponse<BoxBody>, Error> {
+    let Some(sampling) = runtime().read().ok().and_then(|r| r.sampling.clone()) else {
+        return next.call(req).await;
+    };
+    let user_id = req
+        .extensions()
+        .get::<AuthUser>()
+        .map(|user| user.user_id.clone())
+        .or_else(|| req.match_info().get("user_id").map(str::to_string));
+    let chosen = user_id.as_ref().is_some_and(|id| sampling.user_ids.contains(id))
+        || (OsRng.next_u64() as f64 / u64::MAX as f64) * 100.0 < sampling.percent;
+    if !chosen {
+        return next.call(req).await;
+    }
+
+    SAMPLED
+        .scope(sampling.level, async move {
+            log::info!(
+                target: "sampled",
+                "{} {} user_id={}",
+                req.method(),
+                req.path(),
+                user_id.as_deref().unwrap_or("-")
+            );
+            if sampling.bodies
+                && body_length(&req).is_some_and(|len| len > 0 && len <= MAX_LOGGED_BODY)
+                && let Ok(body) = req.extract::<web::Bytes>().await
+            {
+                log::info!(target: "sampled", "request body: {}", String::from_utf8_lossy(&body));
+                req.set_payload(Payload::from(body));
+            }
+
+            let res = next.call(req).await?;
+            log::info!(target: "sampled", "response status={}", res.status().as_u16());
+            if !sampling.bodies {
+                return Ok(res);
+            }
+            Ok(res.map_body(|_, body| match body.try_into_bytes() {
+                Ok(bytes) => {
+                    if bytes.len() <= MAX_LOGGED_BODY {
+                        log::info!(target: "sampled", "response body: {}", String::from_utf8_lossy(&bytes));
+                    }
+                    BoxBody::new(bytes)
+                }
+                Err(body) => body,
+            }))
+        })
+        .await
+}
+
+fn body_length(req: &ServiceRequest) -> Option<usize> {
+    req.headers().get(CONTENT_LENGTH)?.to_str().ok()?.parse().ok()
+}
+
+// ==================== Handlers ====================
+
+/// The log settings in force on the serving instance
+pub async fn get_log_settings(
+    req: HttpRequest,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&req, &config, cache.get_ref()).await {
+        return response;
+    }
+    HttpResponse::Ok().json(ApiResponse::success(current()))
+}
+
+/// Replace the log settings of every instance until they expire
+pub async fn update_log_settings(
+    http_req: HttpRequest,
+    req: web::Json<UpdateLogSettingsRequest>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    let ttl_secs = req.ttl_secs.unwrap_or(DEFAULT_TTL_SECS);
+    if !(1..=MAX_TTL_SECS).contains(&ttl_secs) {
+        return HttpResponse::BadRequest().json(ApiResponse::<LogSettings>::error(format!(
+            "ttl_secs must be between 1 and {}",
+            MAX_TTL_SECS
+        )));
+    }
+    let defaults = defaults();
+    let settings = LogSettings {
+        filter: req.filter.clone().unwrap_or_else(|| defaults.filter.clone()),
+        sample_percent: req.sample_percent,
+        sample_user_ids: req.sample_user_ids.clone(),
+        sample_level: req.sample_level.clone().unwrap_or_else(|| defaults.sample_level.clone()),
+        log_bodies: req.log_bodies,
+        expires_at: Some(Utc::now() + chrono::Duration::seconds(ttl_secs as i64)),
+    };
+    let runtime = match parse(&settings) {
+        Ok(runtime) => runtime,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<LogSettings>::error(e)),
+    };
+
+    // Stored first: the poller would otherwise undo a local-only change
+    let json = serde_json::to_string(&settings).unwrap_or_default();
+    if let Err(e) = cache.set_ex(LOG_SETTINGS_KEY, json, ttl_secs).await {
+        log::error!("Error storing log settings: {}", e);
+        return HttpResponse::ServiceUnavailable()
+            .json(ApiResponse::<LogSettings>::error("Log settings could not be stored".to_string()));
+    }
+    apply(runtime);
+
+    log::info!(
+        target: "audit",
+        "log settings changed filter={} sample_percent={} sample_users={} sample_level={} log_bodies={} ttl_secs={}",
+        settings.filter,
+        settings.sample_percent,
+        settings.sample_user_ids.len(),
+        settings.sample_level,
+        settings.log_bodies,
+        ttl_secs
+    );
+
+    HttpResponse::Ok().json(ApiResponse::success(settings))
+}
+
+/// Return every instance to the startup log settings
+pub async fn reset_log_settings(
+    req: HttpRequest,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    if let Err(e) = cache.del(LOG_SETTINGS_KEY).await {
+        log::error!("Error clearing log settings: {}", e);
+        return HttpResponse::ServiceUnavailable()
+            .json(ApiResponse::<LogSettings>::error("Log settings could not be cleared".to_string()));
+    }
+    let settings = defaults().clone();
+    if let Ok(runtime) = parse(&settings) {
+        apply(runtime);
+    }
+
+    log::info!(target: "audit", "log settings reset filter={}", settings.filter);
+
+    HttpResponse::Ok().json(ApiResponse::success(settings))
+}
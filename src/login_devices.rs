@@ -0,0 +1,154 @@
+use actix_web::http::header::USER_AGENT;
+use actix_web::http::Method;
+use actix_web::{web, HttpRequest, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::AuthUser;
+use crate::models::{ApiResponse, LoginDevice};
+use crate::routes::ScopedRoutes;
+
+// ==================== LOGIN DEVICES ====================
+//
+// Every login and registration is recorded against the device it came from,
+// told apart by its `User-Agent`: the first login from a device adds a row,
+// later ones bump its count, last IP and last seen time. The bearer token
+// names its device (see auth.rs).
+//
+// Users list their devices with `GET /api/users/me/devices` (the calling
+// token's device is marked `current`) and revoke one with
+// `DELETE /api/users/me/devices/{device_id}`: every token issued to it stops
+// working at once, and its next login starts a new row. Both routes take the
+// bearer token only.
+//
+// The first login from a new device, once the account has logged in from
+// another, is flagged `new_device_alert` and pushed to the user's phones
+// registered before it (see alerts.rs), so a login they didn't make stands
+// out. The audit log records logins and revocations.
+//
+// ============================================================================
+
+/// Longest user agent kept; longer ones are cut
+const MAX_USER_AGENT_LEN: usize = 500;
+
+/// Devices listed, most recently seen first
+const MAX_LISTED_DEVICES: i64 = 100;
+
+/// Record a login from the request's device; returns the device id
+pub async fn record_login(pool: &PgPool, user_id: &str, req: &HttpRequest) -> Result<Uuid, sqlx::Error> {
+    let user_agent: String = req
+        .headers()
+        .get(USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .unwrap_or("unknown")
+        .chars()
+        .take(MAX_USER_AGENT_LEN)
+        .collect();
+    let ip: String = req
+        .connection_info()
+        .realip_remote_addr()
+        .unwrap_or("unknown")
+        .chars()
+        .take(64)
+        .collect();
+
+    // A brand new account's first device is not news
+    let (device_id,): (Uuid,) = sqlx::query_as(
+        "INSERT INTO login_devices (user_id, user_agent, first_ip, last_ip, new_device_alert)
+         VALUES ($1, $2, $3, $3, EXISTS (SELECT 1 FROM login_devices WHERE user_id = $1))
+         ON CONFLICT (user_id, user_agent) WHERE revoked_at IS NULL DO UPDATE
+             SET last_ip = EXCLUDED.last_ip,
+                 login_count = login_devices.login_count + 1,
+                 last_seen_at = CURRENT_TIMESTAMP
+         RETURNING id",
+    )
+    .bind(user_id)
+    .bind(&user_agent)
+    .bind(&ip)
+    .fetch_one(pool)
+    .await?;
+    Ok(device_id)
+}
+
+/// Whether a device of the user is still allowed to use its tokens
+pub async fn is_active(pool: &PgPool, device_id: Uuid, user_id: &str) -> Result<bool, sqlx::Error> {
+    let (active,): (bool,) = sqlx::query_as(
+        "SELECT EXISTS (SELECT 1 FROM login_devices WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL)",
+    )
+    .bind(device_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(active)
+}
+
+// ==================== Handlers ====================
+
+/// The caller's active login devices
+pub async fn get_my_devices(user: AuthUser, db: web::Data<PgPool>) -> HttpResponse {
+    let result = sqlx::query_as::<_, LoginDevice>(
+        "SELECT id, user_agent, first_ip, last_ip, login_count, first_seen_at, last_seen_at,
+                id IS NOT DISTINCT FROM $2 AS current
+         FROM login_devices
+         WHERE user_id = $1 AND revoked_at IS NULL
+         ORDER BY last_seen_at DESC, id
+         LIMIT $3",
+    )
+    .bind(&user.user_id)
+    .bind(user.device_id)
+    .bind(MAX_LISTED_DEVICES)
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(devices) => HttpResponse::Ok().json(ApiResponse::success(devices)),
+        Err(e) => {
+            log::error!("Error fetching login devices: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<LoginDevice>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Revoke one of the caller's login devices, ending its tokens
+pub async fn revoke_my_device(user: AuthUser, device_id: web::Path<Uuid>, db: web::Data<PgPool>) -> HttpResponse {
+    let result = sqlx::query_as::<_, LoginDevice>(
+        "UPDATE login_devices SET revoked_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+         RETURNING id, user_agent, first_ip, last_ip, login_count, first_seen_at, last_seen_at,
+                   id IS NOT DISTINCT FROM $3 AS current",
+    )
+    .bind(device_id.into_inner())
+    .bind(&user.user_id)
+    .bind(user.device_id)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(device)) => {
+            log::info!(target: "audit", "Login device {} of user {} revoked", device.id, user.user_id);
+            HttpResponse::Ok().json(ApiResponse::success(device))
+        }
+        Ok(None) => HttpResponse::NotFound()
+            .json(ApiResponse::<LoginDevice>::error("Login device not found".to_string())),
+        Err(e) => {
+            log::error!("Error revoking login device: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<LoginDevice>::error("Database error".to_string()))
+        }
+    }
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/users")
+        .session(Method::GET, "/me/devices", get_my_devices)
+        .session(Method::DELETE, "/me/devices/{device_id}", revoke_my_device)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
@@ -0,0 +1,121 @@
+use async_trait::async_trait;
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use crate::config::AppConfig;
+
+// ==================== OUTBOUND EMAIL ====================
+//
+// Outbound email goes through the `Mailer` trait so jobs don't depend on a
+// transport. `SmtpMailer` (lettre, STARTTLS) is the production implementation;
+// it is only constructed when `SMTP_HOST` is configured, and features that
+// send email are disabled otherwise.
+//
+// ============================================================================
+
+/// File attached to an outgoing email
+pub struct EmailAttachment {
+    pub file_name: String,
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+/// A plain-text email with an optional attachment
+pub struct OutgoingEmail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+    pub attachment: Option<EmailAttachment>,
+}
+
+#[derive(Debug)]
+pub enum MailerError {
+    /// The message could not be built (bad address, bad content type)
+    Build(String),
+    /// The transport failed to deliver the message
+    Transport(String),
+}
+
+impl std::fmt::Display for MailerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MailerError::Build(e) => write!(f, "Invalid email: {}", e),
+            MailerError::Transport(e) => write!(f, "Email delivery failed: {}", e),
+        }
+    }
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: OutgoingEmail) -> Result<(), MailerError>;
+}
+
+/// Basic shape check for an email address (`local@domain.tld`)
+pub fn is_valid_address(address: &str) -> bool {
+    address
+        .split_once('@')
+        .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'))
+}
+
+// ==================== SMTP Mailer ====================
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: String,
+}
+
+impl SmtpMailer {
+    /// Build an SMTP mailer from config; `None` when `SMTP_HOST` is unset
+    pub fn from_config(config: &AppConfig) -> Option<Result<Self, MailerError>> {
+        let host = config.smtp_host.as_deref().filter(|h| !h.is_empty())?;
+
+        let builder = match AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host) {
+            Ok(builder) => builder.port(config.smtp_port),
+            Err(e) => return Some(Err(MailerError::Transport(e.to_string()))),
+        };
+        let builder = match (&config.smtp_username, &config.smtp_password) {
+            (Some(user), Some(password)) => {
+                builder.credentials(Credentials::new(user.clone(), password.clone()))
+            }
+            _ => builder,
+        };
+
+        Some(Ok(Self {
+            transport: builder.build(),
+            from: config.mail_from.clone(),
+        }))
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: OutgoingEmail) -> Result<(), MailerError> {
+        let builder = Message::builder()
+            .from(self.from.parse().map_err(|e| MailerError::Build(format!("from: {}", e)))?)
+            .to(email.to.parse().map_err(|e| MailerError::Build(format!("to: {}", e)))?)
+            .subject(email.subject);
+
+        let body = SinglePart::plain(email.body);
+        let message = match email.attachment {
+            Some(file) => {
+                let content_type = ContentType::parse(&file.content_type)
+                    .map_err(|e| MailerError::Build(e.to_string()))?;
+                builder.multipart(
+                    MultiPart::mixed()
+                        .singlepart(body)
+                        .singlepart(Attachment::new(file.file_name).body(file.bytes, content_type)),
+                )
+            }
+            None => builder.singlepart(body),
+        }
+        .map_err(|e| MailerError::Build(e.to_string()))?;
+
+        self.transport
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| MailerError::Transport(e.to_string()))
+    }
+}
@@ -1,72 +1,733 @@
+mod account_merges;
+mod admin;
+mod admin_ui;
+mod alerts;
+mod anonymize;
+mod api_keys;
+mod attachments;
+mod audit;
+mod auth;
+mod backups;
+mod balance_history;
+mod batch_entry;
+mod buckets;
+mod budget_alerts;
+mod budgets;
+mod bulk_transactions;
+mod bulk_updates;
 mod cache;
+mod categories;
+mod category_seeds;
+mod charts;
 mod config;
+mod credit_cards;
+mod currencies;
+mod dashboard;
+mod data_export;
 mod db;
 mod debts;
+mod devices;
+mod docs;
+mod drafts;
+mod dry_run;
+mod email_templates;
+mod error;
+mod explain;
+mod export;
+mod export_jobs;
+mod feed;
+mod fieldsets;
+mod filters;
+mod freezes;
+mod goals;
+mod groups;
+mod history;
+mod hooks;
+mod idempotency;
+mod impersonation;
+mod import_presets;
+mod income_sources;
+mod installments;
+mod interest;
+mod jobs;
+mod limits;
+mod list_versions;
+mod lockout;
+mod log_control;
+mod login_devices;
+mod mailer;
+mod markdown;
+mod metrics;
 mod models;
+mod money;
+mod notifications;
+mod online_migrations;
+mod openapi;
+mod opening_balances;
+mod payment_qr;
+mod policy;
+mod provider_webhooks;
+mod push;
+mod recurring_transactions;
+mod reimbursements;
+mod reports;
+mod routes;
+mod s3;
+mod splits;
+mod standing_orders;
+mod statement_imports;
+mod summary_snapshots;
+mod tax;
+mod telemetry;
+mod templates;
+#[cfg(test)]
+mod test_support;
+mod timeouts;
+mod top_ups;
+mod transaction_fields;
+mod transaction_search;
+mod transaction_splits;
 mod transactions;
+mod transfers;
+mod trash;
+mod validation;
+mod wallet_members;
+mod wallet_types;
 mod wallets;
+mod webhooks;
 
 use actix_web::{web, App, HttpServer, middleware};
-use cache::CacheManager;
+use attachments::{LocalStorage, StorageBackend};
+use cache::{CacheBackend, MemoryCache, RedisCache};
+use std::sync::Arc;
 use config::AppConfig;
 use db::DbPool;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    // Initialize logging
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+    // Initialize logging; lines logged while handling a request carry its id (see timeouts.rs)
+    // and `RUST_LOG` can be changed while running (see log_control.rs)
+    log_control::install();
 
     // Load configuration from .env
     let config = AppConfig::from_env();
     log::info!("Loaded configuration: {:?}", config);
 
+    // Export traces and metrics over OTLP if configured (see telemetry.rs)
+    let telemetry = match telemetry::init(&config) {
+        Ok(Some(telemetry)) => {
+            log::info!("OTLP telemetry export enabled");
+            Some(telemetry)
+        }
+        Ok(None) => None,
+        Err(e) => panic!("Invalid OTLP exporter configuration: {}", e),
+    };
+
+    // New users' default categories (see category_seeds.rs)
+    match category_seeds::install(&config) {
+        Ok(count) => log::info!("Seeding {} default categories", count),
+        Err(e) => panic!("Invalid category seeds: {}", e),
+    }
+
+    // User routes trust {user_id} unless bearer tokens are configured (see auth.rs)
+    match config.jwt_secret.as_deref() {
+        None => log::warn!("JWT_SECRET not set. User routes are not authenticated."),
+        Some(secret) if secret.len() < 32 => log::warn!("JWT_SECRET is shorter than 32 bytes."),
+        Some(_) => {}
+    }
+
     // Initialize database connection pool
     let db_pool = DbPool::new(&config.database_url)
         .await
         .expect("Failed to initialize database pool");
     log::info!("Database pool initialized successfully");
 
-    // Initialize Redis cache manager (optional - continue without cache if connection fails)
-    let cache_manager = match CacheManager::new(&config.redis_url).await {
-        Ok(cache) => {
+    // Apply pending migrations; `--migrate-only` exits afterwards (see db.rs)
+    let migrate_only = std::env::args().any(|arg| arg == "--migrate-only");
+    if migrate_only || config.run_migrations {
+        if let Err(e) = db_pool.migrate().await {
+            return Err(std::io::Error::other(format!("Migration failed: {}", e)));
+        }
+        log::info!("Database schema is up to date");
+        if migrate_only {
+            return Ok(());
+        }
+    }
+
+    // `--online-migrate`: run background schema steps to completion and exit (see online_migrations.rs)
+    if std::env::args().any(|arg| arg == "--online-migrate") {
+        return match online_migrations::run_to_completion(db_pool.get_pool()).await {
+            Ok(n) => {
+                log::info!("Online migrations complete ({} step(s) finished in this run)", n);
+                Ok(())
+            }
+            Err(e) => Err(std::io::Error::other(format!("Online migration failed: {}", e))),
+        };
+    }
+
+    // Encrypt cached values if keys are configured (see cache.rs)
+    match cache::configure_encryption(&config) {
+        Ok(true) => {}
+        Ok(false) => log::warn!("CACHE_ENCRYPTION_KEYS not set. Cached values are stored in plaintext."),
+        Err(e) => panic!("Invalid CACHE_ENCRYPTION_KEYS: {}", e),
+    }
+
+    // Spread cache expiry (see cache.rs)
+    if let Err(e) = cache::configure_ttl_jitter(&config) {
+        panic!("Invalid CACHE_TTL_JITTER_PERCENT: {}", e);
+    }
+
+    // Serve stale dashboard entries while they refresh (see cache.rs)
+    if let Err(e) = cache::configure_swr(&config) {
+        panic!("Invalid CACHE_SWR: {}", e);
+    }
+
+    // Reactions to committed changes, e.g. automatic top-ups (see hooks.rs)
+    hooks::install(hooks::builtin());
+
+    // Initialize the cache (Redis if reachable, otherwise an in-process fallback)
+    let cache: Arc<dyn CacheBackend> = match RedisCache::connect(&config.redis_url).await {
+        Ok(redis) => {
             log::info!("Redis cache initialized successfully");
-            Some(cache)
+            // Keep in-process cache state coherent with other instances
+            cache::spawn_invalidation_subscriber(config.redis_url.clone());
+            // Rebuild the connection if it stays broken after an outage
+            let redis = Arc::new(redis);
+            cache::spawn_connection_supervisor(redis.clone());
+            redis
         }
         Err(e) => {
-            log::warn!("Failed to initialize Redis cache: {}. Continuing without cache.", e);
+            log::warn!("Failed to initialize Redis cache: {}. Falling back to an in-process cache.", e);
+            Arc::new(MemoryCache::new())
+        }
+    };
+
+    // Follow runtime log settings changed on any instance (see log_control.rs)
+    log_control::spawn_settings_poller(cache.clone());
+
+    // Schedule the interest job (monthly savings interest and card statements)
+    {
+        let pool = db_pool.get_pool().clone();
+        let cache = cache.clone();
+        jobs::spawn_singleton(
+            interest::INTEREST_JOB,
+            std::time::Duration::from_secs(config.interest_job_interval_secs),
+            pool.clone(),
+            move || {
+                let pool = pool.clone();
+                let cache = cache.clone();
+                async move {
+                    match interest::post_monthly_interest(&pool, cache.as_ref()).await {
+                        Ok(0) => {}
+                        Ok(n) => log::info!("Monthly interest posted for {} wallet(s)", n),
+                        Err(e) => log::error!("Monthly interest job failed: {}", e),
+                    }
+                    match credit_cards::close_card_statements(&pool, cache.as_ref()).await {
+                        Ok(0) => {}
+                        Ok(n) => log::info!("Closed {} card statement(s)", n),
+                        Err(e) => log::error!("Card statement job failed: {}", e),
+                    }
+                }
+            },
+        );
+    }
+
+    // Schedule automatic unfreezing of wallets
+    {
+        let pool = db_pool.get_pool().clone();
+        jobs::spawn_singleton(freezes::FREEZE_JOB, freezes::FREEZE_CHECK_INTERVAL, pool.clone(), move || {
+            let pool = pool.clone();
+            async move {
+                if let Err(e) = freezes::lift_expired_freezes(&pool).await {
+                    log::error!("Wallet auto-unfreeze job failed: {}", e);
+                }
+            }
+        });
+    }
+
+    // Schedule daily wallet balance snapshots
+    {
+        let pool = db_pool.get_pool().clone();
+        jobs::spawn_singleton(
+            balance_history::SNAPSHOT_JOB,
+            balance_history::SNAPSHOT_INTERVAL,
+            pool.clone(),
+            move || {
+                let pool = pool.clone();
+                async move {
+                    if let Err(e) = balance_history::take_snapshots(&pool).await {
+                        log::error!("Wallet balance snapshot job failed: {}", e);
+                    }
+                }
+            },
+        );
+    }
+
+    // Schedule month-to-date wallet summary snapshots
+    {
+        let pool = db_pool.get_pool().clone();
+        jobs::spawn_singleton(
+            summary_snapshots::SNAPSHOT_JOB,
+            summary_snapshots::SNAPSHOT_INTERVAL,
+            pool.clone(),
+            move || {
+                let pool = pool.clone();
+                async move {
+                    match summary_snapshots::take_snapshots(&pool).await {
+                        Ok(0) => {}
+                        Ok(n) => log::info!("Took {} wallet summary snapshot(s)", n),
+                        Err(e) => log::error!("Wallet summary snapshot job failed: {}", e),
+                    }
+                }
+            },
+        );
+    }
+
+    // Schedule deletion of expired idempotency keys
+    {
+        let pool = db_pool.get_pool().clone();
+        jobs::spawn_singleton(idempotency::PURGE_JOB, idempotency::PURGE_INTERVAL, pool.clone(), move || {
+            let pool = pool.clone();
+            async move {
+                if let Err(e) = idempotency::purge_expired_keys(&pool).await {
+                    log::error!("Idempotency key purge job failed: {}", e);
+                }
+            }
+        });
+    }
+
+    // Schedule billing of card installments
+    {
+        let pool = db_pool.get_pool().clone();
+        let cache = cache.clone();
+        jobs::spawn_singleton(
+            installments::INSTALLMENT_JOB,
+            installments::INSTALLMENT_CHECK_INTERVAL,
+            pool.clone(),
+            move || {
+                let pool = pool.clone();
+                let cache = cache.clone();
+                async move {
+                    match installments::bill_due_installments(&pool, cache.as_ref()).await {
+                        Ok(0) => {}
+                        Ok(n) => log::info!("Billed {} card installment(s)", n),
+                        Err(e) => log::error!("Installment job failed: {}", e),
+                    }
+                }
+            },
+        );
+    }
+
+    // Schedule standing order transfers
+    {
+        let pool = db_pool.get_pool().clone();
+        let cache = cache.clone();
+        jobs::spawn_singleton(
+            standing_orders::STANDING_ORDER_JOB,
+            std::time::Duration::from_secs(config.standing_order_job_interval_secs),
+            pool.clone(),
+            move || {
+                let pool = pool.clone();
+                let cache = cache.clone();
+                async move {
+                    match standing_orders::run_due_orders(&pool, cache.as_ref()).await {
+                        Ok(0) => {}
+                        Ok(n) => log::info!("Completed {} standing order transfer(s)", n),
+                        Err(e) => log::error!("Standing order job failed: {}", e),
+                    }
+                }
+            },
+        );
+    }
+
+    // Schedule recurring transactions
+    {
+        let pool = db_pool.get_pool().clone();
+        let cache = cache.clone();
+        jobs::spawn_singleton(
+            recurring_transactions::RECURRING_JOB,
+            std::time::Duration::from_secs(config.recurring_job_interval_secs),
+            pool.clone(),
+            move || {
+                let pool = pool.clone();
+                let cache = cache.clone();
+                async move {
+                    match recurring_transactions::run_due_rules(&pool, cache.as_ref()).await {
+                        Ok(0) => {}
+                        Ok(n) => log::info!("Posted {} recurring transaction(s)", n),
+                        Err(e) => log::error!("Recurring transaction job failed: {}", e),
+                    }
+                }
+            },
+        );
+    }
+
+    // Schedule background schema steps (backfills, concurrent index builds)
+    if config.online_migration_job_interval_secs > 0 {
+        let pool = db_pool.get_pool().clone();
+        jobs::spawn_singleton(
+            online_migrations::ONLINE_MIGRATION_JOB,
+            std::time::Duration::from_secs(config.online_migration_job_interval_secs),
+            pool.clone(),
+            move || {
+                let pool = pool.clone();
+                async move {
+                    match online_migrations::run_scheduled(&pool).await {
+                        Ok(0) => {}
+                        Ok(n) => log::info!("Completed {} online migration step(s)", n),
+                        Err(e) => log::error!("Online migration job failed: {}", e),
+                    }
+                }
+            },
+        );
+    }
+
+    // Schedule exchange rate refreshes (requires a rate provider)
+    match config.exchange_rate_provider_url.clone() {
+        Some(url) => {
+            let pool = db_pool.get_pool().clone();
+            jobs::spawn_singleton(
+                currencies::RATE_JOB,
+                std::time::Duration::from_secs(config.exchange_rate_job_interval_secs),
+                pool.clone(),
+                move || {
+                    let pool = pool.clone();
+                    let url = url.clone();
+                    async move {
+                        match currencies::refresh_from_provider(&pool, &url).await {
+                            Ok(update) => log::info!("Refreshed {} exchange rate(s)", update.updated),
+                            Err(e) => log::error!("Exchange rate refresh job failed: {}", e),
+                        }
+                    }
+                },
+            );
+        }
+        None => log::warn!("EXCHANGE_RATE_PROVIDER_URL not set. Exchange rates must be set by an admin."),
+    }
+
+    // Schedule report email delivery (requires SMTP)
+    match mailer::SmtpMailer::from_config(&config) {
+        Some(Ok(smtp)) => {
+            let pool = db_pool.get_pool().clone();
+            let max_rows = config.max_export_rows;
+            let smtp: Arc<dyn mailer::Mailer> = Arc::new(smtp);
+            jobs::spawn_singleton(
+                reports::REPORT_JOB,
+                std::time::Duration::from_secs(config.report_job_interval_secs),
+                pool.clone(),
+                move || {
+                    let pool = pool.clone();
+                    let smtp = smtp.clone();
+                    async move {
+                        match reports::send_due_reports(&pool, smtp.as_ref(), max_rows).await {
+                            Ok(0) => {}
+                            Ok(n) => log::info!("Sent {} scheduled report(s)", n),
+                            Err(e) => log::error!("Scheduled report job failed: {}", e),
+                        }
+                    }
+                },
+            );
+        }
+        Some(Err(e)) => log::error!("Failed to configure SMTP mailer: {}. Scheduled reports disabled.", e),
+        None => log::warn!("SMTP_HOST not set. Scheduled reports will not be sent."),
+    }
+
+    // Schedule push alerts (requires FCM and/or APNs credentials)
+    match push::PushSenders::from_config(&config) {
+        Ok(senders) if senders.is_empty() => {
+            log::warn!("FCM/APNs credentials not set. Push alerts will not be sent.")
+        }
+        Ok(senders) => {
+            let pool = db_pool.get_pool().clone();
+            let senders = Arc::new(senders);
+            jobs::spawn_singleton(
+                alerts::ALERT_JOB,
+                std::time::Duration::from_secs(config.alert_job_interval_secs),
+                pool.clone(),
+                move || {
+                    let pool = pool.clone();
+                    let senders = senders.clone();
+                    async move {
+                        match alerts::send_due_alerts(&pool, &senders).await {
+                            Ok(0) => {}
+                            Ok(n) => log::info!("Sent {} push alert(s)", n),
+                            Err(e) => log::error!("Push alert job failed: {}", e),
+                        }
+                    }
+                },
+            );
+        }
+        Err(e) => log::error!("Failed to configure push notifications: {}. Push alerts disabled.", e),
+    }
+
+    // Schedule debt and budget notifications (email delivery requires SMTP)
+    {
+        let pool = db_pool.get_pool().clone();
+        let due_days = config.notification_due_days;
+        let mut channels: Vec<Box<dyn notifications::NotificationChannel>> =
+            vec![Box::new(notifications::WebhookChannel::new())];
+        if let Some(Ok(smtp)) = mailer::SmtpMailer::from_config(&config) {
+            channels.push(Box::new(notifications::EmailChannel::new(Arc::new(smtp))));
+        }
+        let channels = Arc::new(channels);
+        jobs::spawn_singleton(
+            notifications::NOTIFICATION_JOB,
+            std::time::Duration::from_secs(config.notification_job_interval_secs),
+            pool.clone(),
+            move || {
+                let pool = pool.clone();
+                let channels = channels.clone();
+                async move {
+                    match notifications::run(&pool, &channels, due_days).await {
+                        Ok((0, 0)) => {}
+                        Ok((raised, delivered)) => {
+                            log::info!("Raised {} notification(s), delivered {}", raised, delivered)
+                        }
+                        Err(e) => log::error!("Notification job failed: {}", e),
+                    }
+                }
+            },
+        );
+    }
+
+    // Schedule change event delivery to webhook endpoints
+    {
+        let pool = db_pool.get_pool().clone();
+        let client = webhooks::client();
+        jobs::spawn_singleton(
+            webhooks::WEBHOOK_JOB,
+            std::time::Duration::from_secs(config.webhook_job_interval_secs),
+            pool.clone(),
+            move || {
+                let pool = pool.clone();
+                let client = client.clone();
+                async move {
+                    match webhooks::run(&pool, &client).await {
+                        Ok(0) => {}
+                        Ok(n) => log::info!("Delivered {} webhook event(s)", n),
+                        Err(e) => log::error!("Webhook job failed: {}", e),
+                    }
+                }
+            },
+        );
+    }
+
+    // Database backups to S3-compatible storage (optional)
+    let backups = match s3::S3Settings::backups_from_config(&config) {
+        Some(settings) => {
+            let storage: Arc<dyn StorageBackend> = Arc::new(s3::S3Storage::new(settings));
+            Some(web::Data::new(backups::Backups::new(storage, &config)))
+        }
+        None => {
+            log::warn!("BACKUP_S3_ENDPOINT/BACKUP_S3_BUCKET not set. Database backups disabled.");
             None
         }
     };
+    if let Some(backups) = backups.clone()
+        && config.backup_interval_secs > 0
+    {
+        let pool = db_pool.get_pool().clone();
+        jobs::spawn_singleton(backups::BACKUP_JOB, backups::BACKUP_CHECK_INTERVAL, pool.clone(), move || {
+            let pool = pool.clone();
+            let backups = backups.clone();
+            async move {
+                match backups::run_scheduled_backup(&pool, &backups).await {
+                    Ok(Some(run)) => log::info!("Scheduled backup {} finished: {}", run.id, run.status),
+                    Ok(None) => {}
+                    Err(e) => log::error!("Scheduled backup job failed: {:?}", e),
+                }
+            }
+        });
+    }
+
+    // Attachment blob storage: an S3-compatible bucket if configured, else local disk
+    let storage: Arc<dyn StorageBackend> = match s3::S3Settings::attachments_from_config(&config) {
+        Some(settings) => {
+            log::info!("Attachment storage in bucket {} at {}", settings.bucket, settings.endpoint);
+            Arc::new(s3::S3Storage::new(settings))
+        }
+        None => {
+            log::info!("Attachment storage at {}", config.attachments_dir);
+            Arc::new(LocalStorage::new(&config.attachments_dir))
+        }
+    };
+
+    // Schedule purging of trash past its retention window
+    {
+        let pool = db_pool.get_pool().clone();
+        let cache = cache.clone();
+        let storage = storage.clone();
+        let retention = trash::Retention::from_config(&config);
+        jobs::spawn_singleton(
+            trash::PURGE_JOB,
+            std::time::Duration::from_secs(config.trash_purge_interval_secs),
+            pool.clone(),
+            move || {
+                let pool = pool.clone();
+                let cache = cache.clone();
+                let storage = storage.clone();
+                async move {
+                    match trash::purge_expired(&pool, cache.as_ref(), storage.as_ref(), retention).await {
+                        Ok(0) => {}
+                        Ok(n) => log::info!("Purged {} wallet(s) and transaction(s) from the trash", n),
+                        Err(e) => log::error!("Trash purge job failed: {}", e),
+                    }
+                }
+            },
+        );
+    }
+
+    // Build queued exports on every instance; claims keep each job on one worker
+    if config.export_workers > 0 {
+        let pool = db_pool.get_pool().clone();
+        let storage = storage.clone();
+        let workers = config.export_workers;
+        let retention = std::time::Duration::from_secs(config.export_retention_secs);
+        jobs::spawn_periodic(export_jobs::EXPORT_JOB, export_jobs::EXPORT_POLL_INTERVAL, move || {
+            let pool = pool.clone();
+            let storage = storage.clone();
+            async move {
+                match export_jobs::run(&pool, storage.as_ref(), workers, retention).await {
+                    Ok((0, 0)) => {}
+                    Ok((built, expired)) => log::info!("Built {} export(s), expired {}", built, expired),
+                    Err(e) => log::error!("Export job failed: {}", e),
+                }
+            }
+        });
+    } else {
+        log::warn!("EXPORT_WORKERS is 0: this instance builds no queued exports");
+    }
 
     let server_address = config.server_address();
     log::info!("Starting server on {}", server_address);
 
     // Create and start HTTP server
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         let mut app = App::new()
-            // Add logging middleware
-            .wrap(middleware::Logger::default())
+            // Answer unchanged lists with 304 (see list_versions.rs)
+            .wrap(middleware::from_fn(list_versions::not_modified))
+            // Trace every request (see telemetry.rs)
+            .wrap(middleware::from_fn(telemetry::trace_request))
+            // Add logging middleware (with the request id, see timeouts.rs)
+            .wrap(middleware::Logger::new(
+                r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T request_id=%{x-request-id}o"#,
+            ))
             // Share database pool across requests
-            .app_data(web::Data::new(db_pool.get_pool().clone()));
+            .app_data(web::Data::new(db_pool.get_pool().clone()))
+            // Share configuration and attachment storage
+            .app_data(web::Data::new(config.clone()))
+            .app_data(web::Data::from(storage.clone()));
 
-        // Add cache manager if available
-        if let Some(ref cache) = cache_manager {
-            app = app.app_data(web::Data::new(cache.get_connection_manager().clone()));
+        // Add backup storage if configured
+        if let Some(ref backups) = backups {
+            app = app.app_data(backups.clone());
         }
 
+        // Add the cache backend
+        app = app.app_data(web::Data::from(cache.clone()));
+
         app
             // Health check endpoint
             .route("/health", web::get().to(health_check))
+            // Prometheus metrics (see metrics.rs)
+            .route("/metrics", web::get().to(metrics::get_metrics))
+            // Machine-readable route manifest
+            .route("/api/routes", web::get().to(routes::get_route_manifest))
+            // OpenAPI document generated from the manifest
+            .route("/api/openapi.json", web::get().to(openapi::get_openapi))
+            // Error code catalog and route examples for client developers
+            .configure(docs::configure_routes)
             // Configure wallet routes
             .configure(wallets::configure_routes)
+            // Configure custom wallet type routes
+            .configure(wallet_types::configure_routes)
             // Configure transaction routes
             .configure(transactions::configure_routes)
+            // Configure custom transaction field routes
+            .configure(transaction_fields::configure_routes)
+            // Configure transaction template routes
+            .configure(templates::configure_routes)
+            // Configure import preset routes
+            .configure(import_presets::configure_routes)
+            // Configure income source routes
+            .configure(income_sources::configure_routes)
+            // Configure category routes
+            .configure(categories::configure_routes)
+            // Configure chart routes
+            .configure(charts::configure_routes)
+            // Configure reimbursement routes
+            .configure(reimbursements::configure_routes)
             // Configure debt routes
             .configure(debts::configure_routes)
+            // Configure audit log routes
+            .configure(audit::configure_routes)
+            // Configure draft autosave routes
+            .configure(drafts::configure_routes)
+            // Configure standing order routes
+            .configure(standing_orders::configure_routes)
+            // Configure installment plan routes
+            .configure(installments::configure_routes)
+            // Configure recurring transaction routes
+            .configure(recurring_transactions::configure_routes)
+            // Configure auto top-up rule routes
+            .configure(top_ups::configure_routes)
+            // Configure pending transfer routes
+            .configure(transfers::configure_routes)
+            // Configure budget routes
+            .configure(budgets::configure_routes)
+            // Configure savings goal routes
+            .configure(goals::configure_routes)
+            // Configure portfolio group routes
+            .configure(groups::configure_routes)
+            // Configure currency and exchange rate routes
+            .configure(currencies::configure_routes)
+            // Configure push device routes
+            .configure(devices::configure_routes)
+            // Configure notification routes
+            .configure(notifications::configure_routes)
+            // Configure change event webhook routes
+            .configure(webhooks::configure_routes)
+            // Configure trash routes
+            .configure(trash::configure_routes)
+            // Configure support access (impersonation) routes
+            .configure(impersonation::configure_routes)
+            // Configure tax category routes
+            .configure(tax::configure_routes)
+            // Configure report subscription routes
+            .configure(reports::configure_routes)
+            // Configure login device routes
+            .configure(login_devices::configure_routes)
+            // Configure budget alert settings routes
+            .configure(budget_alerts::configure_routes)
+            // Configure inbound provider webhook routes
+            .configure(provider_webhooks::configure_routes)
+            // Configure activity feed routes
+            .configure(feed::configure_routes)
+            // Configure dashboard routes
+            .configure(dashboard::configure_routes)
+            // Configure account export routes
+            .configure(data_export::configure_routes)
+            .configure(export_jobs::configure_routes)
+            // Configure account merge routes
+            .configure(account_merges::configure_routes)
+            // Configure admin routes
+            .configure(admin::configure_routes)
+            // Configure admin panel pages
+            .configure(admin_ui::configure_routes)
+            // Configure API key routes
+            .configure(api_keys::configure_routes)
+            // Configure authentication routes
+            .configure(auth::configure_routes)
     })
     .bind(&server_address)?
     .run()
-    .await
+    .await;
+
+    // Flush buffered spans and metrics
+    if let Some(telemetry) = telemetry {
+        telemetry.shutdown();
+    }
+    server
 }
 
 /// Health check endpoint
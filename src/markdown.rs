@@ -0,0 +1,60 @@
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+
+// ==================== Sanitized Markdown Rendering ====================
+//
+// User-supplied Markdown (transaction notes) is rendered for display in
+// share views. Sanitization happens at the event level:
+//
+// - Raw HTML blocks and inline HTML are emitted as escaped text
+// - Link and image destinations are restricted to safe schemes
+//   (http, https, mailto, or relative); anything else becomes "#"
+//
+// ============================================================================
+
+/// URL schemes allowed in links and images
+const SAFE_SCHEMES: &[&str] = &["http:", "https:", "mailto:"];
+
+/// Render Markdown to HTML that is safe to embed in a page
+pub fn render_sanitized_html(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+
+    let events = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        Event::Start(Tag::Link { link_type, dest_url, title, id }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: sanitize_url(dest_url),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image { link_type, dest_url, title, id }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: sanitize_url(dest_url),
+            title,
+            id,
+        }),
+        other => other,
+    });
+
+    let mut output = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut output, events);
+    output
+}
+
+/// Keep relative URLs and safe absolute schemes, replace everything else
+fn sanitize_url(url: CowStr<'_>) -> CowStr<'_> {
+    let normalized: String = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    let has_scheme = normalized
+        .split_once(':')
+        .is_some_and(|(scheme, _)| !scheme.contains('/') && !scheme.contains('?') && !scheme.contains('#'));
+
+    if !has_scheme || SAFE_SCHEMES.iter().any(|s| normalized.starts_with(s)) {
+        url
+    } else {
+        CowStr::Borrowed("#")
+    }
+}
@@ -0,0 +1,136 @@
+use std::sync::LazyLock;
+
+use actix_web::{web, HttpResponse};
+use prometheus::core::Collector;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use sqlx::PgPool;
+
+// ==================== PROMETHEUS METRICS ====================
+//
+// `GET /metrics` serves this instance's metrics in the Prometheus text
+// format, so a slow dashboard can be pinned on Postgres, Redis or the handler
+// without an OTLP collector (see telemetry.rs, which records the same
+// durations as spans):
+//
+// - `ketobook_http_requests_total` and `ketobook_http_request_duration_seconds`
+//   per method, matched route pattern and status (from `trace_request`).
+// - `ketobook_cache_operations_total` and `ketobook_cache_operation_duration_seconds`
+//   per namespace, operation and outcome (`hit`, `miss`, ...), recorded by
+//   every `get_or_set_cache` call.
+// - `ketobook_db_operation_duration_seconds` per table and operation of the
+//   shared repository reads (`telemetry::sql`).
+// - `ketobook_db_pool_connections` (open, by `state`: `idle` or `in_use`)
+//   and `ketobook_db_pool_max_connections`, read at scrape time. A pool with
+//   no idle connections means requests queue for Postgres.
+//
+// A request's time minus its cache and SQL time is handler code (or inline
+// queries, which aren't measured separately).
+//
+// ============================================================================
+
+struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    cache_operations: IntCounterVec,
+    cache_duration: HistogramVec,
+    db_duration: HistogramVec,
+    pool_connections: IntGaugeVec,
+    pool_max_connections: IntGauge,
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(|| {
+    let registry = Registry::new_custom(Some("ketobook".to_string()), None).expect("valid metric prefix");
+    let metrics = Metrics {
+        requests: IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled"),
+            &["method", "route", "status"],
+        )
+        .expect("valid metric"),
+        request_duration: HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "Duration of HTTP requests"),
+            &["method", "route", "status"],
+        )
+        .expect("valid metric"),
+        cache_operations: IntCounterVec::new(
+            Opts::new("cache_operations_total", "Cache operations, by outcome"),
+            &["namespace", "operation", "outcome"],
+        )
+        .expect("valid metric"),
+        cache_duration: HistogramVec::new(
+            HistogramOpts::new("cache_operation_duration_seconds", "Duration of cache operations"),
+            &["namespace", "operation", "outcome"],
+        )
+        .expect("valid metric"),
+        db_duration: HistogramVec::new(
+            HistogramOpts::new("db_operation_duration_seconds", "Duration of repository calls"),
+            &["table", "operation", "outcome"],
+        )
+        .expect("valid metric"),
+        pool_connections: IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Open database connections"),
+            &["state"],
+        )
+        .expect("valid metric"),
+        pool_max_connections: IntGauge::new("db_pool_max_connections", "Database pool size limit")
+            .expect("valid metric"),
+        registry,
+    };
+    let collectors: [Box<dyn Collector>; 7] = [
+        Box::new(metrics.requests.clone()),
+        Box::new(metrics.request_duration.clone()),
+        Box::new(metrics.cache_operations.clone()),
+        Box::new(metrics.cache_duration.clone()),
+        Box::new(metrics.db_duration.clone()),
+        Box::new(metrics.pool_connections.clone()),
+        Box::new(metrics.pool_max_connections.clone()),
+    ];
+    for collector in collectors {
+        metrics.registry.register(collector).expect("metric names are unique");
+    }
+    metrics
+});
+
+/// Count one request and its duration
+pub fn record_request(method: &str, route: &str, status: u16, seconds: f64) {
+    let status = status.to_string();
+    let labels = [method, route, status.as_str()];
+    METRICS.requests.with_label_values(&labels).inc();
+    METRICS.request_duration.with_label_values(&labels).observe(seconds);
+}
+
+/// Count one cache operation and its duration
+pub fn record_cache(namespace: &str, operation: &str, outcome: &str, seconds: f64) {
+    let labels = [namespace, operation, outcome];
+    METRICS.cache_operations.with_label_values(&labels).inc();
+    METRICS.cache_duration.with_label_values(&labels).observe(seconds);
+}
+
+/// Record the duration of one repository call
+pub fn record_sql(table: &str, operation: &str, outcome: &str, seconds: f64) {
+    METRICS
+        .db_duration
+        .with_label_values(&[table, operation, outcome])
+        .observe(seconds);
+}
+
+/// `GET /metrics` - this instance's metrics in the Prometheus text format
+pub async fn get_metrics(pool: web::Data<PgPool>) -> HttpResponse {
+    let size = i64::from(pool.size());
+    let idle = pool.num_idle() as i64;
+    METRICS.pool_connections.with_label_values(&["idle"]).set(idle);
+    METRICS.pool_connections.with_label_values(&["in_use"]).set(size - idle);
+    METRICS
+        .pool_max_connections
+        .set(i64::from(pool.options().get_max_connections()));
+
+    let encoder = TextEncoder::new();
+    let mut body = Vec::new();
+    if let Err(e) = encoder.encode(&METRICS.registry.gather(), &mut body) {
+        log::error!("Failed to encode metrics: {}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+    HttpResponse::Ok().content_type(encoder.format_type()).body(body)
+}
@@ -0,0 +1,271 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== Balance Replay Models ====================
+
+/// Query parameters for the balance replay operation
+#[derive(Debug, Deserialize)]
+pub struct ReplayQuery {
+    /// Swap replayed balances into `wallets` for mismatched wallets
+    #[serde(default)]
+    pub apply: bool,
+}
+
+/// Replay result for a single wallet (row of the `wallet_balance_replay` shadow table)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WalletReplay {
+    pub wallet_id: Uuid,
+    pub live_balance: BigDecimal,
+    pub replayed_balance: BigDecimal,
+    pub transaction_count: i64,
+    pub total_income: BigDecimal,
+    pub total_expense: BigDecimal,
+    pub applied: bool,
+}
+
+impl WalletReplay {
+    /// Whether the live balance drifted from the transaction history
+    pub fn is_mismatch(&self) -> bool {
+        self.live_balance != self.replayed_balance
+    }
+}
+
+/// Summary of a replay run for one user
+#[derive(Debug, Serialize)]
+pub struct ReplayReport {
+    pub run_id: Uuid,
+    pub user_id: String,
+    pub replayed_at: DateTime<Utc>,
+    pub applied: bool,
+    pub wallet_count: usize,
+    pub mismatch_count: usize,
+    pub wallets: Vec<WalletReplay>,
+}
+
+// ==================== Backup Models ====================
+
+/// One database backup attempt
+///
+/// `status`: running -> succeeded | failed; succeeded backups become
+/// `expired` once retention deletes their object.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct BackupRun {
+    pub id: Uuid,
+    pub triggered_by: String,             // "manual" or "scheduled"
+    pub status: String,                   // "running", "succeeded", "failed", "expired"
+    pub object_key: Option<String>,
+    pub size_bytes: Option<i64>,
+    pub sha256: Option<String>,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub verify_status: Option<String>,    // "passed" or "failed" after a restore verification
+    pub verify_error: Option<String>,
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+// ==================== Impersonation Models ====================
+
+/// A time-limited support session acting as a user
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ImpersonationSession {
+    pub id: Uuid,
+    pub user_id: String,
+    pub requested_by: String,             // Support agent (free text)
+    pub reason: String,
+    pub write_access: bool,               // false: read-only
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Request to mint an impersonation token
+#[derive(Debug, Deserialize)]
+pub struct CreateImpersonationRequest {
+    pub user_id: String,
+    pub requested_by: String,
+    pub reason: String,
+    #[serde(default)]
+    pub write_access: bool,
+    pub ttl_minutes: Option<i64>,         // Default 30, max 240
+}
+
+/// A newly minted session with its token (the token is never shown again)
+#[derive(Debug, Serialize)]
+pub struct ImpersonationGrant {
+    pub session: ImpersonationSession,
+    pub token: String,
+}
+
+/// One request made with an impersonation token
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ImpersonationEvent {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub user_id: String,
+    pub method: String,
+    pub path: String,
+    pub status: i16,                      // HTTP status of the response
+    pub created_at: DateTime<Utc>,
+}
+
+// ==================== Account Merge Models ====================
+
+/// Record of one account merged into another (append-only)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AccountMerge {
+    pub id: Uuid,
+    pub source_user_id: String,           // Account emptied and closed
+    pub target_user_id: String,           // Account that received the data
+    pub initiated_by: String,             // "admin" or "user"
+    pub requested_by: String,             // Support agent, or the target user
+    pub reason: Option<String>,
+    pub moved: serde_json::Value,         // Table name -> rows moved
+    pub renamed: serde_json::Value,       // Table name -> moved rows renamed over a name clash
+    pub dropped: serde_json::Value,       // Table name -> source rows dropped for the target's own
+    pub merged_at: DateTime<Utc>,
+}
+
+/// Request to merge one account into another (admin)
+#[derive(Debug, Deserialize)]
+pub struct MergeAccountsRequest {
+    pub source_user_id: String,
+    pub target_user_id: String,
+    pub requested_by: String,
+    pub reason: String,
+}
+
+/// Request to merge another account of the caller into this one
+#[derive(Debug, Deserialize)]
+pub struct LinkAccountRequest {
+    pub source_token: String,             // Bearer token of the account to merge in
+}
+
+// ==================== Anonymized Export Models ====================
+
+/// A user's data with identifying values replaced by fakes
+#[derive(Debug, Serialize)]
+pub struct AnonymizedExport {
+    pub user_id: String,                  // Fake user id, as used in every row
+    pub generated_at: DateTime<Utc>,
+    pub tables: serde_json::Map<String, serde_json::Value>, // Table name -> rows
+    pub truncated: Vec<String>,           // Tables cut at MAX_EXPORT_ROWS
+}
+
+// ==================== Migration Status Models ====================
+
+/// A migration applied by `sqlx migrate run` (row of `_sqlx_migrations`)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    pub installed_on: DateTime<Utc>,
+    pub success: bool,
+}
+
+/// Progress of one background schema step (see online_migrations.rs)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OnlineMigrationStatus {
+    pub name: String,
+    pub kind: String,                     // "backfill", "concurrent_index" or "validate_constraint"
+    pub status: String,                   // "pending", "running", "failed" or "completed"
+    pub rows_done: i64,
+    pub attempts: i32,
+    pub error: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Schema state of the database
+#[derive(Debug, Serialize)]
+pub struct MigrationStatus {
+    pub applied: Vec<AppliedMigration>,   // Empty if migrations were applied by hand
+    pub online: Vec<OnlineMigrationStatus>, // In run order
+    pub online_complete: bool,
+}
+
+// ==================== Admin Panel Models ====================
+
+/// A background job as scheduled on this instance
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub name: String,
+    pub period_secs: u64,
+    pub running: bool,
+    pub runs: u64,                        // Ticks since startup
+    pub skipped: u64,                     // Singleton ticks left to the instance holding the lock
+    pub last_started_at: Option<DateTime<Utc>>,
+    pub last_finished_at: Option<DateTime<Utc>>,
+}
+
+/// Occurrences of a job recorded in `job_executions`, by every instance
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct JobExecutionSummary {
+    pub job_name: String,
+    pub last_executed_at: DateTime<Utc>,
+    pub last_day: i64,                    // Occurrences in the last 24 hours
+}
+
+/// The cache as this instance sees it
+#[derive(Debug, Serialize)]
+pub struct CacheStats {
+    pub backend: String,                  // "redis" or "memory"
+    pub invalidation_subscriber: bool,    // Subscribed to cluster-wide invalidations
+    pub reconnects: u64,                  // Redis connections rebuilt since startup
+    pub local_versions: usize,            // Namespace versions held in process
+    pub encrypted: bool,
+    pub ttl_jitter_percent: u64,
+    pub stale_grace_secs: Vec<(String, u64)>,
+    pub outcomes: Vec<(String, u64)>,     // Operations since startup, by outcome
+}
+
+/// A user found by the admin panel's lookup
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AdminUserSummary {
+    pub id: String,
+    pub email: String,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
+}
+
+/// `POST /admin/login` form
+#[derive(Debug, Deserialize)]
+pub struct AdminLoginForm {
+    pub token: String,
+}
+
+/// `GET /admin/users` query
+#[derive(Debug, Deserialize)]
+pub struct AdminUserSearchQuery {
+    pub q: Option<String>,          // Prefix of a user id or email
+}
+
+// ==================== Log Control Models ====================
+
+/// Runtime log settings of an instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogSettings {
+    pub filter: String,                   // `RUST_LOG` syntax, e.g. "info,ketobook::cache=debug"
+    pub sample_percent: f64,              // Share of requests sampled, 0-100
+    pub sample_user_ids: Vec<String>,     // Requests of these users are always sampled
+    pub sample_level: String,             // Level logged by every module in a sampled request
+    pub log_bodies: bool,                 // Log sampled request and response bodies
+    pub expires_at: Option<DateTime<Utc>>, // None: the startup settings
+}
+
+/// Request to replace the runtime log settings; omitted fields take their
+/// startup value
+#[derive(Debug, Deserialize)]
+pub struct UpdateLogSettingsRequest {
+    pub filter: Option<String>,
+    #[serde(default)]
+    pub sample_percent: f64,
+    #[serde(default)]
+    pub sample_user_ids: Vec<String>,
+    pub sample_level: Option<String>,     // "info", "debug" (default) or "trace"
+    #[serde(default)]
+    pub log_bodies: bool,
+    pub ttl_secs: Option<u64>,            // Default 3600, max 86400
+}
@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// ==================== API Key Models ====================
+
+/// A key a user handed to an integration, limited to its scopes
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub user_id: String,
+    pub name: String,
+    pub key_prefix: String,               // First characters of the key, to tell keys apart
+    pub scopes: Vec<String>,              // e.g. "read:wallets", "create:transactions"
+    pub sandbox: bool,                    // Writes only to sandbox wallets (see api_keys.rs)
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request to create an API key
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub user_id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    #[serde(default)]
+    pub sandbox: bool,
+    pub expires_at: Option<DateTime<Utc>>, // Default: never
+}
+
+/// A newly created key with its secret (the secret is never shown again)
+#[derive(Debug, Serialize)]
+pub struct ApiKeyGrant {
+    pub key: ApiKey,
+    pub secret: String,
+}
@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// ==================== Attachment Model ====================
+
+/// Metadata for a document attached to an entity (debt contract, receipt, ...)
+///
+/// The file itself lives in the configured storage backend under `storage_key`;
+/// only metadata is stored in Postgres. `storage_key` is internal and never
+/// serialized to clients.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Attachment {
+    pub id: Uuid,
+    pub user_id: String,
+    pub entity_type: String,              // "debt"
+    pub entity_id: Uuid,
+    pub file_name: String,
+    pub content_type: String,             // MIME type (image/png, application/pdf, ...)
+    pub size_bytes: i64,
+    #[serde(skip_serializing, default)]
+    pub storage_key: String,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// ==================== Audit Log Models ====================
+
+/// One recorded change of a wallet, transaction or debt
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub user_id: String,                  // Owner of the changed row
+    pub entity_type: String,              // "wallet", "transaction" or "debt"
+    pub entity_id: String,
+    pub action: String,                   // "create", "update", "delete" or "restore"
+    pub actor: Option<String>,            // Who made the change; None for background jobs
+    pub request_id: Option<String>,       // `X-Request-Id` of the request that made it
+    pub old_values: Option<serde_json::Value>, // The row before the change; None for creates
+    pub new_values: Option<serde_json::Value>, // The row after the change; None for hard deletes
+    pub created_at: DateTime<Utc>,
+}
+
+/// Optional filters for the audit log (`?entity_type=&entity_id=&action=&actor=`)
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditFilterQuery {
+    pub entity_type: Option<String>,
+    pub entity_id: Option<String>,
+    pub action: Option<String>,
+    pub actor: Option<String>,
+}
@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== Bucket Model ====================
+
+/// A named, virtual partition of a wallet's balance (emergency fund, travel)
+///
+/// Buckets hold no money of their own: their balances are earmarks on the
+/// wallet balance, and their total never exceeds it. Whatever is not in a
+/// bucket is the wallet's unallocated balance.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WalletBucket {
+    pub id: Uuid,
+    pub wallet_id: Uuid,
+    pub user_id: String,
+    pub name: String,
+    pub balance: BigDecimal,              // Allocated amount, >= 0
+    pub target_amount: Option<BigDecimal>, // Optional savings goal
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// ==================== Bucket Request Models ====================
+
+/// Request to create a bucket (starts empty)
+#[derive(Debug, Deserialize)]
+pub struct CreateBucketRequest {
+    pub name: String,
+    pub target_amount: Option<BigDecimal>,
+}
+
+/// Request to rename a bucket or change its goal
+#[derive(Debug, Deserialize)]
+pub struct UpdateBucketRequest {
+    pub name: Option<String>,
+    pub target_amount: Option<BigDecimal>,
+}
+
+/// Amount to allocate to or release from a bucket
+#[derive(Debug, Deserialize)]
+pub struct BucketAmountRequest {
+    pub amount: BigDecimal,
+}
+
+/// Request to move an amount between two buckets of the same wallet
+#[derive(Debug, Deserialize)]
+pub struct BucketTransferRequest {
+    pub from_bucket_id: Uuid,
+    pub to_bucket_id: Uuid,
+    pub amount: BigDecimal,
+}
+
+// ==================== Bucket Response Models ====================
+
+/// A wallet's buckets and what is left unallocated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletBuckets {
+    pub wallet_id: Uuid,
+    pub balance: BigDecimal,
+    pub allocated: BigDecimal,
+    pub unallocated: BigDecimal,
+    pub buckets: Vec<WalletBucket>,
+}
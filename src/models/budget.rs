@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== BudgetPeriod Enum ====================
+
+/// How often a budget's spending starts over
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetPeriod {
+    /// Monday to Sunday (UTC)
+    Weekly,
+    /// Calendar month (UTC)
+    Monthly,
+}
+
+impl BudgetPeriod {
+    /// Convert enum variant to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BudgetPeriod::Weekly => "weekly",
+            BudgetPeriod::Monthly => "monthly",
+        }
+    }
+
+    /// Parse string to BudgetPeriod enum
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "weekly" => Some(BudgetPeriod::Weekly),
+            "monthly" => Some(BudgetPeriod::Monthly),
+            _ => None,
+        }
+    }
+}
+
+// ==================== Budget Model ====================
+
+/// A spending limit per period on one expense category, or on all expenses
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Budget {
+    pub id: Uuid,
+    pub user_id: String,
+    pub name: String,
+    pub category: Option<String>,         // None: all expenses except transfers
+    pub period: String,                   // "weekly" or "monthly"
+    pub amount: BigDecimal,               // Limit per period, in the user's base currency
+    pub alert_thresholds: Option<Vec<i16>>, // Percentages of amount that raise an alert; None: the user's
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// ==================== Budget Request Models ====================
+
+/// Request to create a budget
+#[derive(Debug, Deserialize)]
+pub struct CreateBudgetRequest {
+    pub user_id: String,
+    pub name: String,
+    pub category: Option<String>,         // Omit for an overall budget
+    pub period: BudgetPeriod,
+    pub amount: BigDecimal,
+    pub alert_thresholds: Option<Vec<i16>>, // Omit for the user's thresholds
+}
+
+/// Request to change a budget; the category and period are fixed
+#[derive(Debug, Deserialize)]
+pub struct UpdateBudgetRequest {
+    pub name: Option<String>,
+    pub amount: Option<BigDecimal>,
+    pub alert_thresholds: Option<Vec<i16>>, // [] goes back to the user's thresholds
+}
+
+// ==================== Budget Status Models ====================
+
+/// Spending against a budget in its current period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    #[serde(flatten)]
+    pub budget: Budget,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,        // Exclusive
+    pub thresholds: Vec<i16>,             // alert_thresholds, or the user's when unset
+    pub spent: BigDecimal,
+    pub remaining: BigDecimal,            // Negative once over the limit
+    pub percent_used: f64,
+    pub over_limit: bool,
+}
+
+/// A budget threshold a new expense crossed
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BudgetWarning {
+    pub budget_id: Uuid,
+    pub name: String,
+    pub threshold: i16,                   // Percent of the limit crossed
+    pub spent: BigDecimal,                // Spent this period, including the new expense
+    pub limit: BigDecimal,
+    pub over_limit: bool,
+}
@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// ==================== Budget Alert Settings Model ====================
+
+/// A user's budget alert thresholds
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct BudgetAlertSettings {
+    pub user_id: String,
+    pub thresholds: Vec<i16>,                 // Percentages of a budget, ascending
+    pub updated_at: Option<DateTime<Utc>>,    // None while the user is on the defaults
+}
+
+// ==================== Budget Alert Settings Request Models ====================
+
+/// Request to replace a user's budget alert thresholds
+#[derive(Debug, Deserialize)]
+pub struct UpdateBudgetAlertSettingsRequest {
+    pub thresholds: Vec<i16>,
+}
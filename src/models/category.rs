@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// ==================== Category Model ====================
+
+/// A transaction category of a user ("groceries", "salary", ...)
+///
+/// Transactions store the category's name; categories can be nested one
+/// level under a parent ("food" > "groceries").
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Category {
+    pub id: Uuid,
+    pub user_id: String,
+    pub name: String,
+    pub parent_id: Option<Uuid>,          // None: top-level category
+    pub icon: Option<String>,
+    pub system: bool,                     // Posted by KetoBook itself; can't be renamed, merged or deleted
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// ==================== Category Request Models ====================
+
+/// Request to add a category
+#[derive(Debug, Deserialize)]
+pub struct CreateCategoryRequest {
+    pub user_id: String,
+    pub name: String,
+    pub parent_id: Option<Uuid>,
+    pub icon: Option<String>,
+}
+
+/// Request to rename a category or change its icon (`icon: ""` removes it)
+///
+/// A rename relabels the category's transactions too.
+#[derive(Debug, Deserialize)]
+pub struct UpdateCategoryRequest {
+    pub name: Option<String>,
+    pub icon: Option<String>,
+}
+
+/// Move a category under a parent, or to the top level (`parent_id: null`)
+#[derive(Debug, Deserialize)]
+pub struct MoveCategoryRequest {
+    pub parent_id: Option<Uuid>,
+}
+
+/// Merge a category into another one
+#[derive(Debug, Deserialize)]
+pub struct MergeCategoryRequest {
+    pub into_id: Uuid,
+}
+
+/// Add back the default categories a user deleted, named in `locale`
+/// (default: the locale the user was first seeded in)
+#[derive(Debug, Deserialize)]
+pub struct SeedCategoriesRequest {
+    pub locale: Option<String>,
+}
+
+/// The result of a re-seed: the locale used, how many defaults were added,
+/// and the user's categories afterwards
+#[derive(Debug, Serialize)]
+pub struct SeededCategories {
+    pub locale: String,
+    pub added: u64,
+    pub categories: Vec<Category>,
+}
+
+/// A category after a rename or merge, and how many transactions were relabeled
+#[derive(Debug, Serialize)]
+pub struct CategoryChange {
+    pub category: Category,
+    pub transactions_updated: u64,
+}
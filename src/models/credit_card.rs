@@ -0,0 +1,73 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== Credit Card Models ====================
+
+/// Statement cycle and interest terms of a credit-limit wallet
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CreditCardTerms {
+    pub wallet_id: Uuid,
+    pub user_id: String,
+    pub statement_day: i16,               // Day of month (UTC) a statement closes, 1-28
+    pub payment_due_days: i16,            // Days after the statement it must be paid in full
+    pub apr: BigDecimal,                  // Annual percentage rate on purchases
+    pub tracking_from: DateTime<Utc>,     // Start of the first statement period
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A closed statement; its period is (`period_start`, `period_end`]
+///
+/// `paid_amount` and `paid_in_full` stay null until the next statement
+/// closes, which is after `due_date`.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CreditCardStatement {
+    pub id: Uuid,
+    pub wallet_id: Uuid,
+    pub user_id: String,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub due_date: DateTime<Utc>,
+    pub opening_balance: BigDecimal,      // Owed at period_start
+    pub purchases: BigDecimal,            // Expenses in the period
+    pub payments: BigDecimal,             // Income in the period
+    pub interest: BigDecimal,             // Charged at period_end
+    pub interest_free: bool,              // Not charged interest when it closed
+    pub closing_balance: BigDecimal,      // Owed at period_end, to pay by due_date
+    pub paid_amount: Option<BigDecimal>,
+    pub paid_in_full: Option<bool>,
+    pub interest_transaction_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The statement period in progress
+#[derive(Debug, Serialize)]
+pub struct CreditCardCycle {
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,        // Next statement date
+    pub due_date: DateTime<Utc>,          // Due date of the next statement
+    pub purchases: BigDecimal,
+    pub payments: BigDecimal,
+    pub balance: BigDecimal,              // Owed now
+    pub interest_free: bool,              // False once the last statement missed its due date unpaid
+}
+
+/// Terms, current cycle and statement history of a card
+#[derive(Debug, Serialize)]
+pub struct CreditCardStatements {
+    pub terms: CreditCardTerms,
+    pub current: CreditCardCycle,
+    pub statements: Vec<CreditCardStatement>,
+}
+
+// ==================== Credit Card Request Models ====================
+
+/// Request to set (or replace) a card's terms
+#[derive(Debug, Deserialize)]
+pub struct SetCreditCardTermsRequest {
+    pub statement_day: i16,
+    pub payment_due_days: i16,
+    pub apr: BigDecimal,
+}
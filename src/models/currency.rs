@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+
+// ==================== Currency Models ====================
+
+/// A currency wallets can hold (ISO 4217)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Currency {
+    pub code: String,                     // e.g. "VND", "USD"
+    pub name: String,
+    pub symbol: String,                   // e.g. "₫", "$"
+    pub minor_units: i16,                 // Decimal places (0 for VND)
+}
+
+/// 1 unit of `base` is worth `rate` units of `quote`
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ExchangeRate {
+    pub base: String,
+    pub quote: String,
+    pub rate: BigDecimal,
+    pub source: String,                   // "manual" or "provider"
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One rate of an upsert request
+#[derive(Debug, Deserialize)]
+pub struct ExchangeRateInput {
+    pub base: String,
+    pub quote: String,
+    pub rate: BigDecimal,
+}
+
+/// Request to set exchange rates (admin)
+#[derive(Debug, Deserialize)]
+pub struct UpsertExchangeRatesRequest {
+    pub rates: Vec<ExchangeRateInput>,
+}
+
+/// Rates written by an upsert or a provider refresh
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeRateUpdate {
+    pub updated: u64,
+    pub rates: Vec<ExchangeRate>,
+}
+
+// ==================== Currency Settings Models ====================
+
+/// The currency a user's reports and net worth are converted to
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CurrencySettings {
+    pub user_id: String,
+    pub base_currency: String,            // Set explicitly, else the locale's, else "VND"
+    pub locale: Option<String>,           // e.g. "vi", "en-US"
+}
+
+/// Request to change a user's base currency or locale
+#[derive(Debug, Deserialize)]
+pub struct UpdateCurrencySettingsRequest {
+    pub base_currency: Option<String>,    // Leave out with a locale to follow the locale's currency
+    pub locale: Option<String>,
+}
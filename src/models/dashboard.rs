@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+use super::BudgetStatus;
+
+// ==================== Dashboard Models ====================
+
+/// A user's home screen: balances, this month's totals, budgets and what
+/// falls due soon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dashboard {
+    pub balances: DashboardBalances,
+    pub month_to_date: MonthTotals,
+    pub budgets: Vec<BudgetStatus>,
+    pub upcoming: Vec<UpcomingItem>,
+    pub as_of: DateTime<Utc>,             // When these figures were read from the database
+}
+
+/// The user's wallets and their totals in the base currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DashboardBalances {
+    pub currency: String,                 // The user's base currency, which the totals are in
+    pub assets: BigDecimal,               // Balances of asset wallets
+    pub liabilities: BigDecimal,          // Balances of liability wallets (credit cards, loans)
+    pub wallets: Vec<DashboardWallet>,
+}
+
+/// One wallet's balance, in its own currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DashboardWallet {
+    pub wallet_id: Uuid,
+    pub name: String,
+    pub currency: String,
+    pub balance: BigDecimal,
+    pub available_credit: Option<BigDecimal>, // Credit-limited wallets only: credit_limit - balance
+    pub liability: bool,
+}
+
+/// Income and expenses since the start of the month (UTC) in the base
+/// currency, transfers left out
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MonthTotals {
+    pub month_start: DateTime<Utc>,
+    pub income: BigDecimal,
+    pub expense: BigDecimal,
+    pub transaction_count: i64,
+}
+
+/// A scheduled transaction, installment or debt falling due soon (or overdue)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct UpcomingItem {
+    pub kind: String,                     // "recurring", "standing_order", "installment", "debt" or "receivable"
+    pub id: Uuid,
+    pub name: String,
+    pub due_at: DateTime<Utc>,
+    pub amount: Option<BigDecimal>,       // None for sweeping standing orders
+    pub wallet_id: Option<Uuid>,
+}
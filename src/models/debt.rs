@@ -1,8 +1,11 @@
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::types::BigDecimal;
 use uuid::Uuid;
 
+use super::{Attachment, ReportFormat, ReportVisibility};
+
 // ==================== Debt Model ====================
 
 /// Represents a debt (loan, credit, obligation)
@@ -12,39 +15,254 @@ use uuid::Uuid;
 /// be associated with multiple wallets or none at all.
 ///
 /// Debt is preserved even if associated wallet is deleted (FK uses ON DELETE SET NULL).
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, JsonSchema)]
 pub struct Debt {
     pub id: Uuid,
     pub user_id: String,
     pub wallet_id: Option<Uuid>,          // Optional FK to wallets (SET NULL on delete)
     pub creditor_name: String,            // Name of creditor (bank, person, company)
+    pub creditor_phone: Option<String>,   // Optional creditor contact phone
+    pub creditor_email: Option<String>,   // Optional creditor contact email
+    pub creditor_address: Option<String>, // Optional creditor postal address
     pub amount: BigDecimal,               // Principal debt amount
     pub interest_rate: BigDecimal,        // Annual interest rate as percentage
     pub due_date: Option<DateTime<Utc>>,  // Optional payment due date
     pub status: String,                   // "active", "paid", or "cancelled"
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub direction: String,                // "payable" (user owes) or "receivable" (owed to user)
+    pub split_id: Option<Uuid>,           // Bill split that generated this receivable
+    pub group_id: Option<Uuid>,           // Portfolio group (see groups.rs)
+    pub creditor_bank_bin: Option<String>, // NAPAS BIN of the creditor's bank (6 digits)
+    pub creditor_account_number: Option<String>, // Creditor's account there; set together with the BIN
+    pub version: i64,                     // Bumped by every change
+}
+
+impl Debt {
+    /// Field names a list can be narrowed to (`?fields=`)
+    pub const FIELDS: &'static [&'static str] = &[
+        "id", "user_id", "wallet_id", "creditor_name", "creditor_phone", "creditor_email", "creditor_address",
+        "amount", "interest_rate", "due_date", "status", "created_at", "updated_at", "direction", "split_id",
+        "group_id", "creditor_bank_bin", "creditor_account_number", "version",
+    ];
 }
 
 // ==================== Debt Request Models ====================
 
 /// Request to create a new debt
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct CreateDebtRequest {
     pub user_id: String,
     pub wallet_id: Option<Uuid>,
     pub creditor_name: String,
+    pub creditor_phone: Option<String>,
+    pub creditor_email: Option<String>,
+    pub creditor_address: Option<String>,
+    pub creditor_bank_bin: Option<String>,
+    pub creditor_account_number: Option<String>, // Required with the BIN, and vice versa
     pub amount: BigDecimal,
     pub interest_rate: Option<BigDecimal>,
     pub due_date: Option<DateTime<Utc>>,
+    pub direction: Option<String>,        // Defaults to "payable"
 }
 
 /// Request to update an existing debt
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct UpdateDebtRequest {
     pub creditor_name: Option<String>,
+    pub creditor_phone: Option<String>,
+    pub creditor_email: Option<String>,
+    pub creditor_address: Option<String>,
+    pub creditor_bank_bin: Option<String>,
+    pub creditor_account_number: Option<String>, // Required with the BIN, and vice versa
     pub amount: Option<BigDecimal>,
     pub interest_rate: Option<BigDecimal>,
     pub due_date: Option<DateTime<Utc>>,
     pub status: Option<String>,
 }
+
+/// Optional filters for the debt list (`?status=&wallet_id=&direction=`)
+#[derive(Debug, Default, Deserialize)]
+pub struct DebtFilterQuery {
+    pub status: Option<String>,           // "active", "paid", or "cancelled"
+    pub wallet_id: Option<Uuid>,
+    pub direction: Option<String>,        // "payable" or "receivable"
+    pub group_id: Option<Uuid>,
+}
+
+/// Query for the debt statement download (`?format=csv|pdf`)
+#[derive(Debug, Deserialize)]
+pub struct DebtExportQuery {
+    pub format: ReportFormat,
+}
+
+/// Request to share a debt's statement (`PUT .../statement-visibility`)
+#[derive(Debug, Deserialize)]
+pub struct UpdateStatementVisibilityRequest {
+    pub visibility: ReportVisibility,
+    pub shared_with: Option<Vec<String>>, // Required when visibility is members
+}
+
+/// Who may download a debt's statement besides its owner
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct StatementVisibility {
+    pub debt_id: Uuid,
+    pub wallet_id: Option<Uuid>,          // The shared wallet whose members may see it
+    pub visibility: String,               // "private", "household" or "members"
+    pub shared_with: Vec<String>,
+}
+
+/// Response format of a payment QR code
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum QrFormat {
+    #[default]
+    Json,
+    Svg,
+}
+
+/// `?amount=&message=&format=` query parameters of a debt's payment QR code
+#[derive(Debug, Deserialize)]
+pub struct PaymentQrQuery {
+    pub amount: Option<BigDecimal>,       // Default: the outstanding amount
+    pub message: Option<String>,          // Transfer message; default names the debt
+    #[serde(default)]
+    pub format: QrFormat,
+}
+
+// ==================== Debt Response Models ====================
+
+/// Debt detail including attached documents (contracts, receipts) and settlements
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebtDetail {
+    #[serde(flatten)]
+    pub debt: Debt,
+    pub outstanding: BigDecimal,          // amount minus recorded settlements
+    pub settlements: Vec<DebtSettlement>,
+    pub attachments: Vec<Attachment>,
+}
+
+/// A VietQR code paying (part of) a debt to the creditor's bank account
+#[derive(Debug, Clone, Serialize)]
+pub struct DebtPaymentQr {
+    pub debt_id: Uuid,
+    pub creditor_name: String,
+    pub bank_bin: String,
+    pub account_number: String,
+    pub amount: BigDecimal,               // In VND, whole dong
+    pub currency: String,                 // Always "VND"
+    pub exchange_rate: Option<BigDecimal>, // From the debt's currency, when it isn't VND
+    pub message: String,                  // Transfer message (ASCII, at most 25 chars)
+    pub payload: String,                  // EMVCo payload, as encoded in the QR code
+    pub svg: String,                      // The QR code as an SVG image
+}
+
+// ==================== Settlement Models ====================
+
+/// A payment recorded against a debt (either direction)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DebtSettlement {
+    pub id: Uuid,
+    pub debt_id: Uuid,
+    pub user_id: String,
+    pub amount: BigDecimal,
+    pub note: Option<String>,
+    pub settled_at: DateTime<Utc>,
+    pub wallet_id: Option<Uuid>,          // Wallet the payment went through, if any
+    pub transaction_id: Option<Uuid>,     // Its expense (payable) or income (receivable)
+}
+
+/// Request to record a settlement (a debt payment)
+#[derive(Debug, Deserialize)]
+pub struct CreateSettlementRequest {
+    pub amount: BigDecimal,
+    pub note: Option<String>,
+    pub wallet_id: Option<Uuid>,          // Debit (payable) or credit (receivable) this wallet
+}
+
+// ==================== Amortization Models ====================
+
+/// Estimated interest and repayment plan of a debt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebtAmortization {
+    pub debt_id: Uuid,
+    pub as_of: DateTime<Utc>,
+    pub interest_rate: BigDecimal,        // Annual, percent
+    pub outstanding: BigDecimal,
+    pub accrued_interest: BigDecimal,     // Sum of `accrual`, not added to the debt
+    pub accrual: Vec<DebtAccrualMonth>,   // Since the debt was opened
+    pub accrual_truncated: bool,
+    pub schedule: Vec<DebtInstallment>,   // Empty unless active with something outstanding
+    pub schedule_interest: BigDecimal,    // Interest over the remaining schedule
+}
+
+/// One month of estimated interest on the opening balance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebtAccrualMonth {
+    pub month: String,                    // "YYYY-MM"
+    pub opening: BigDecimal,
+    pub interest: BigDecimal,
+    pub payments: BigDecimal,
+    pub closing: BigDecimal,
+}
+
+/// One installment of the remaining schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebtInstallment {
+    pub date: DateTime<Utc>,
+    pub payment: BigDecimal,
+    pub interest: BigDecimal,
+    pub principal: BigDecimal,
+    pub remaining: BigDecimal,
+}
+
+/// Net outstanding balance with one counterparty across all active debts
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CounterpartyBalance {
+    pub counterparty: String,
+    pub owed_to_you: BigDecimal,          // Outstanding receivables
+    pub you_owe: BigDecimal,              // Outstanding payables
+    pub net: BigDecimal,                  // owed_to_you - you_owe
+}
+
+// ==================== Bill Split Models ====================
+
+/// An expense split among named participants
+///
+/// Each participant's share is a receivable debt (`Debt.split_id`).
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct BillSplit {
+    pub id: Uuid,
+    pub user_id: String,
+    pub transaction_id: Uuid,
+    pub total_amount: BigDecimal,
+    pub user_share: BigDecimal,           // What the user keeps paying themselves
+    pub created_at: DateTime<Utc>,
+}
+
+/// A bill split with the receivables it generated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BillSplitDetail {
+    #[serde(flatten)]
+    pub split: BillSplit,
+    pub shares: Vec<Debt>,
+}
+
+/// One participant of a split; `amount` omitted means an equal share
+#[derive(Debug, Deserialize)]
+pub struct SplitParticipant {
+    pub name: String,
+    pub amount: Option<BigDecimal>,
+}
+
+/// Request to split an expense transaction
+///
+/// Either every participant gives an `amount`, or none does and the expense is
+/// divided equally among the participants and the user.
+#[derive(Debug, Deserialize)]
+pub struct CreateBillSplitRequest {
+    pub user_id: String,
+    pub transaction_id: Uuid,
+    pub participants: Vec<SplitParticipant>,
+    pub due_date: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== PushPlatform Enum ====================
+
+/// Push service a device token belongs to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PushPlatform {
+    /// Firebase Cloud Messaging (Android, web)
+    Fcm,
+    /// Apple Push Notification service (iOS)
+    Apns,
+}
+
+impl PushPlatform {
+    /// Convert enum variant to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PushPlatform::Fcm => "fcm",
+            PushPlatform::Apns => "apns",
+        }
+    }
+}
+
+// ==================== Push Device Model ====================
+
+/// A device registered for push notifications, with its alert preferences
+///
+/// The push token itself is never read back through the API.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PushDevice {
+    pub id: Uuid,
+    pub user_id: String,
+    pub platform: String,                      // "fcm" or "apns"
+    pub name: Option<String>,
+    pub low_balance_below: Option<BigDecimal>, // Alert when an asset wallet drops below this
+    pub due_date_days_before: Option<i16>,     // Alert this many days before a debt is due
+    pub budget_alerts: bool,
+    pub quiet_start_minute: Option<i16>,       // Device-local minute of the day (0-1439)
+    pub quiet_end_minute: Option<i16>,
+    pub utc_offset_minutes: i16,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// ==================== Push Device Request Models ====================
+
+/// Alert preferences of a device
+///
+/// Unset thresholds disable that alert. Quiet hours may wrap midnight
+/// (e.g. 22:00-07:00 is `1320`-`420`).
+#[derive(Debug, Deserialize)]
+pub struct DevicePreferences {
+    pub low_balance_below: Option<BigDecimal>,
+    pub due_date_days_before: Option<i16>,
+    #[serde(default = "default_budget_alerts")]
+    pub budget_alerts: bool,
+    pub quiet_start_minute: Option<i16>,
+    pub quiet_end_minute: Option<i16>,
+    #[serde(default)]
+    pub utc_offset_minutes: i16,
+}
+
+fn default_budget_alerts() -> bool {
+    true
+}
+
+/// Request to register a device token (re-registering a token moves it to this user)
+#[derive(Debug, Deserialize)]
+pub struct RegisterDeviceRequest {
+    pub user_id: String,
+    pub platform: PushPlatform,
+    pub token: String,
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub preferences: DevicePreferences,
+}
@@ -0,0 +1,24 @@
+use serde::Serialize;
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== Dry Run Models ====================
+
+/// What a mutating request would have done, answered instead of doing it
+#[derive(Debug, Serialize)]
+pub struct DryRunPreview<T> {
+    pub dry_run: bool,                    // Always true
+    pub status: u16,                      // Status the request would have answered with
+    pub result: Option<T>,                // Body it would have returned; None for deletes
+    pub balances: Vec<DryRunBalance>,     // Wallets whose balance it changes
+}
+
+/// A wallet balance before and after a dry-run request
+#[derive(Debug, Serialize)]
+pub struct DryRunBalance {
+    pub wallet_id: Uuid,
+    pub name: String,
+    pub currency: String,
+    pub balance_before: Option<BigDecimal>, // None if the wallet is new
+    pub balance_after: Option<BigDecimal>,  // None if the wallet would be deleted
+}
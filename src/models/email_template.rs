@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// ==================== Email Template Model ====================
+
+/// A stored version of an email template
+///
+/// `subject` and `body` are Handlebars templates rendered as plain text.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct EmailTemplate {
+    pub id: Uuid,
+    pub name: String,                     // e.g. "report_delivery"
+    pub locale: String,                   // e.g. "en", "vi", "pt-BR"
+    pub version: i32,
+    pub subject: String,
+    pub body: String,
+    pub note: Option<String>,             // What changed, for the version history
+    pub created_at: DateTime<Utc>,
+}
+
+/// The template currently used for a name and locale
+#[derive(Debug, Clone, Serialize)]
+pub struct EmailTemplateVariant {
+    pub name: String,
+    pub locale: String,
+    pub version: i32,                     // 0: the built-in template
+    pub source: String,                   // "builtin" or "database"
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// A rendered email
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderedEmail {
+    pub name: String,
+    pub locale: String,                   // Locale of the template used, after fallback
+    pub version: i32,
+    pub subject: String,
+    pub body: String,
+}
+
+// ==================== Email Template Request Models ====================
+
+/// Request to save a new version of a template
+#[derive(Debug, Deserialize)]
+pub struct SaveEmailTemplateRequest {
+    pub subject: String,
+    pub body: String,
+    pub note: Option<String>,
+}
+
+/// Request to render a template with sample or given data
+///
+/// With `subject` and `body`, that draft is rendered instead of a stored
+/// version, to try an edit before saving it.
+#[derive(Debug, Deserialize)]
+pub struct PreviewEmailTemplateRequest {
+    pub name: String,
+    pub locale: Option<String>,           // Default "en"
+    pub version: Option<i32>,             // Default: the version in use; 0 for the built-in one
+    pub subject: Option<String>,
+    pub body: Option<String>,
+    pub data: Option<serde_json::Value>,  // Default: the template's sample data
+}
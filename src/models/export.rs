@@ -0,0 +1,79 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// ==================== Data Export Models ====================
+
+/// File format of a data export
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DataFormat {
+    Csv,
+    Json,
+}
+
+impl DataFormat {
+    /// Convert enum variant to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataFormat::Csv => "csv",
+            DataFormat::Json => "json",
+        }
+    }
+}
+
+/// `?format=` of the transaction and account exports
+#[derive(Debug, Deserialize)]
+pub struct DataExportQuery {
+    pub format: Option<DataFormat>,       // Default csv
+}
+
+// ==================== Export Job Models ====================
+
+/// What an export job downloads
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportKind {
+    Transactions,
+    Account,
+}
+
+impl ExportKind {
+    /// Convert enum variant to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportKind::Transactions => "transactions",
+            ExportKind::Account => "account",
+        }
+    }
+}
+
+/// Request body of `POST /api/exports`
+#[derive(Debug, Deserialize)]
+pub struct CreateExportRequest {
+    pub user_id: String,
+    pub kind: ExportKind,
+    pub format: Option<DataFormat>,       // Default csv
+    pub from: Option<DateTime<Utc>>,      // Transactions only: created_at range
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// An export built in the background
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ExportJob {
+    pub id: Uuid,
+    pub user_id: String,
+    pub kind: String,                     // "transactions" or "account"
+    pub format: String,                   // "csv" or "json"
+    pub range_from: Option<DateTime<Utc>>,
+    pub range_to: Option<DateTime<Utc>>,
+    pub status: String,                   // "queued", "running", "succeeded", "failed" or "expired"
+    pub size_bytes: Option<i64>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>, // When a succeeded file is deleted
+    #[sqlx(default)]
+    pub download_url: Option<String>,     // Succeeded only; signed, valid for a few minutes
+}
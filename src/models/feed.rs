@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// ==================== Activity Feed Models ====================
+
+/// One entry of a user's activity feed
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct FeedEvent {
+    pub kind: String,                     // "transaction", "debt_payment", "alert", "support_access"
+    pub id: String,                       // Id of the event within its kind
+    pub occurred_at: DateTime<Utc>,
+    pub payload: serde_json::Value,       // Kind-specific fields
+}
+
+/// A page of the feed, newest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedPage {
+    pub events: Vec<FeedEvent>,
+    pub next_cursor: Option<String>,      // Pass as `?cursor=` for the next (older) page
+}
+
+/// `?cursor=&per_page=` query parameters of the feed
+#[derive(Debug, Default, Deserialize)]
+pub struct FeedQuery {
+    pub cursor: Option<String>,
+    pub per_page: Option<u32>,            // Page size (default DEFAULT_PAGE_SIZE)
+}
@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// ==================== FieldType Enum ====================
+
+/// Value type of a user-defined transaction field
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    Text,
+    Number,
+    Boolean,
+}
+
+impl FieldType {
+    /// Convert enum variant to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FieldType::Text => "text",
+            FieldType::Number => "number",
+            FieldType::Boolean => "boolean",
+        }
+    }
+
+    /// Parse string to FieldType enum
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "text" => Some(FieldType::Text),
+            "number" => Some(FieldType::Number),
+            "boolean" => Some(FieldType::Boolean),
+            _ => None,
+        }
+    }
+
+    /// Whether a JSON value has this type
+    pub fn accepts(&self, value: &serde_json::Value) -> bool {
+        match self {
+            FieldType::Text => value.is_string(),
+            FieldType::Number => value.is_number(),
+            FieldType::Boolean => value.is_boolean(),
+        }
+    }
+}
+
+// ==================== Field Definition Model ====================
+
+/// A custom field a user can set in `Transaction.metadata`
+/// (e.g. "project": text, "reimbursable": boolean)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TransactionFieldDefinition {
+    pub id: Uuid,
+    pub user_id: String,
+    pub key: String,                      // Metadata key (lowercase, digits, underscores)
+    pub field_type: String,               // "text", "number", or "boolean"
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request to define a custom transaction field
+#[derive(Debug, Deserialize)]
+pub struct CreateFieldDefinitionRequest {
+    pub user_id: String,
+    pub key: String,
+    pub field_type: FieldType,
+}
@@ -0,0 +1,88 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== Savings Goal Models ====================
+
+/// An amount to put aside by a deadline, optionally into a linked wallet
+///
+/// The goal is preserved if the linked wallet is deleted (FK uses ON DELETE SET NULL).
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SavingsGoal {
+    pub id: Uuid,
+    pub user_id: String,
+    pub name: String,
+    pub target_amount: BigDecimal,
+    pub currency: String,                 // ISO 4217; the linked wallet's currency when linked
+    pub deadline: NaiveDate,
+    pub wallet_id: Option<Uuid>,          // Wallet contributions are moved into
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Money moved toward a goal
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct GoalContribution {
+    pub id: Uuid,
+    pub goal_id: Uuid,
+    pub user_id: String,
+    pub from_wallet_id: Option<Uuid>,
+    pub amount: BigDecimal,               // Taken, in the source wallet's currency
+    pub goal_amount: BigDecimal,          // Counted, in the goal's currency
+    pub exchange_rate: BigDecimal,        // 1 unless the currencies differ
+    pub out_transaction_id: Uuid,
+    pub in_transaction_id: Option<Uuid>,  // None for goals without a linked wallet
+    pub created_at: DateTime<Utc>,
+}
+
+// ==================== Savings Goal Request Models ====================
+
+/// Request to create a savings goal
+#[derive(Debug, Deserialize)]
+pub struct CreateGoalRequest {
+    pub user_id: String,
+    pub name: String,
+    pub target_amount: BigDecimal,
+    pub deadline: NaiveDate,
+    pub wallet_id: Option<Uuid>,
+    pub currency: Option<String>,         // Without a wallet; default: the user's base currency
+}
+
+/// Request to update a savings goal
+#[derive(Debug, Deserialize)]
+pub struct UpdateGoalRequest {
+    pub name: Option<String>,
+    pub target_amount: Option<BigDecimal>,
+    pub deadline: Option<NaiveDate>,
+}
+
+/// Request to move money from a wallet toward a goal
+#[derive(Debug, Deserialize)]
+pub struct CreateContributionRequest {
+    pub from_wallet_id: Uuid,
+    pub amount: BigDecimal,               // In the source wallet's currency
+}
+
+// ==================== Savings Goal Response Models ====================
+
+/// A goal with how far along it is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalProgress {
+    #[serde(flatten)]
+    pub goal: SavingsGoal,
+    pub saved: BigDecimal,                // Sum of contributions whose expense still stands
+    pub remaining: BigDecimal,            // Zero once the target is reached
+    pub progress_percent: f64,            // saved / target_amount, one decimal; may pass 100
+    pub monthly_velocity: BigDecimal,     // Contributed per 30 days, recently
+    pub projected_completion: Option<NaiveDate>, // At that pace; None without recent contributions
+    pub on_track: bool,                   // Reached, or projected by the deadline
+}
+
+/// A goal with its progress and contributions, newest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalDetail {
+    #[serde(flatten)]
+    pub progress: GoalProgress,
+    pub contributions: Vec<GoalContribution>,
+}
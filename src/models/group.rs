@@ -0,0 +1,81 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== Portfolio Group Model ====================
+
+/// A user-defined group of wallets and debts ("Business", "Personal", ...)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PortfolioGroup {
+    pub id: Uuid,
+    pub user_id: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// ==================== Portfolio Group Request Models ====================
+
+/// Request to create a group
+#[derive(Debug, Deserialize)]
+pub struct CreateGroupRequest {
+    pub user_id: String,
+    pub name: String,
+}
+
+/// Request to rename a group
+#[derive(Debug, Deserialize)]
+pub struct UpdateGroupRequest {
+    pub name: String,
+}
+
+/// Move wallets and debts into a group, or out of any group (`group_id: null`)
+#[derive(Debug, Deserialize)]
+pub struct AssignGroupRequest {
+    pub group_id: Option<Uuid>,
+    #[serde(default)]
+    pub wallet_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub debt_ids: Vec<Uuid>,
+}
+
+/// Wallets and debts moved by an assignment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupAssignment {
+    pub group_id: Option<Uuid>,
+    pub wallets_updated: u64,
+    pub debts_updated: u64,
+}
+
+// ==================== Net Worth Models ====================
+
+/// `?group_id=` of the net worth endpoint
+#[derive(Debug, Deserialize)]
+pub struct NetWorthQuery {
+    pub group_id: Option<Uuid>,
+}
+
+/// Totals of one group (`group_id: null` for what is not in any group)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupNetWorth {
+    pub group_id: Option<Uuid>,
+    pub name: String,
+    pub wallet_count: i64,
+    pub debt_count: i64,
+    pub assets: BigDecimal,               // Balances of asset wallets
+    pub liabilities: BigDecimal,          // Balances of liability wallets (credit cards, loans)
+    pub receivables: BigDecimal,          // Outstanding active receivable debts
+    pub payables: BigDecimal,             // Outstanding active payable debts
+    pub installments: BigDecimal,         // Installments of active card plans not billed yet
+    pub net_worth: BigDecimal,            // assets - liabilities - installments + receivables - payables
+}
+
+/// Net worth overall and per group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetWorth {
+    pub as_of: DateTime<Utc>,
+    pub currency: String,                 // The user's base currency, which every amount is in
+    pub total: GroupNetWorth,
+    pub groups: Vec<GroupNetWorth>,
+}
@@ -0,0 +1,112 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// ==================== Import Preset Model ====================
+
+/// How to read one bank's CSV statement: which column holds what
+///
+/// Column names refer to `headers`, the statement's header row, matched
+/// case-insensitively. Amounts come from `amount_column` (signed) or from
+/// `debit_column` / `credit_column` (both positive).
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ImportPreset {
+    pub id: Uuid,
+    pub user_id: Option<String>,          // None: community preset, managed by admins
+    pub name: String,
+    pub bank: Option<String>,
+    pub delimiter: String,                // One character, e.g. "," or ";"
+    pub headers: Vec<String>,             // Header row used for auto-detection
+    pub date_column: String,
+    pub date_format: String,              // chrono format, e.g. "%d/%m/%Y"
+    pub amount_column: Option<String>,
+    pub debit_column: Option<String>,     // Money out
+    pub credit_column: Option<String>,    // Money in
+    pub description_column: Option<String>,
+    pub category_column: Option<String>,
+    pub decimal_comma: bool,              // "1.234,56" instead of "1,234.56"
+    pub expense_negative: bool,           // Negative `amount_column` values are expenses (false: positive ones)
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// ==================== Import Preset Request Models ====================
+
+/// Mapping of a preset, as created or replaced
+#[derive(Debug, Deserialize)]
+pub struct ImportPresetFields {
+    pub name: String,
+    pub bank: Option<String>,
+    pub delimiter: Option<String>,        // Default ","
+    pub headers: Vec<String>,
+    pub date_column: String,
+    pub date_format: Option<String>,      // Default "%Y-%m-%d"
+    pub amount_column: Option<String>,
+    pub debit_column: Option<String>,
+    pub credit_column: Option<String>,
+    pub description_column: Option<String>,
+    pub category_column: Option<String>,
+    #[serde(default)]
+    pub decimal_comma: bool,
+    pub expense_negative: Option<bool>,   // Default true
+}
+
+/// Request to save a user's preset
+#[derive(Debug, Deserialize)]
+pub struct CreateImportPresetRequest {
+    pub user_id: String,
+    #[serde(flatten)]
+    pub preset: ImportPresetFields,
+}
+
+/// A preset whose columns are all in a file's header row
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportPresetMatch {
+    #[serde(flatten)]
+    pub preset: ImportPreset,
+    pub score: f64,                       // Share of the preset's headers found in the file, 0-1
+    pub exact: bool,                      // Same header row
+}
+
+// ==================== Statement Import Models ====================
+
+/// Column mapping sent with a statement instead of a saved preset
+///
+/// Same fields as a preset, without the name and header row: the columns are
+/// looked up in the uploaded file's own header row.
+#[derive(Debug, Deserialize)]
+pub struct ImportMapping {
+    pub delimiter: Option<String>,        // Default ","
+    pub date_column: String,
+    pub date_format: Option<String>,      // Default "%Y-%m-%d"
+    pub amount_column: Option<String>,
+    pub debit_column: Option<String>,
+    pub credit_column: Option<String>,
+    pub description_column: Option<String>,
+    pub category_column: Option<String>,
+    #[serde(default)]
+    pub decimal_comma: bool,
+    pub expense_negative: Option<bool>,   // Default true
+}
+
+/// Outcome of a statement upload
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSummary {
+    pub import_id: Uuid,
+    pub wallet_id: Uuid,
+    pub format: String,                   // "csv" or "ofx"
+    pub preset_id: Option<Uuid>,          // Saved preset used to read a CSV file
+    pub imported: usize,
+    pub skipped: usize,                   // Already imported into the wallet
+    pub failed: usize,
+    pub transaction_ids: Vec<Uuid>,
+    pub skipped_rows: Vec<usize>,
+    pub errors: Vec<ImportRowError>,
+}
+
+/// A statement line that could not be read or was refused
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportRowError {
+    pub row: usize,                       // CSV: line number in the file; OFX: position of the transaction
+    pub error: String,
+}
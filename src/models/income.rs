@@ -0,0 +1,105 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== IncomeSourceKind Enum ====================
+
+/// What kind of income a source provides
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IncomeSourceKind {
+    /// Salary or wages from an employer
+    Employer,
+    /// Freelance work, side business
+    SideGig,
+    /// Interest, dividends, rent
+    Passive,
+    Other,
+}
+
+impl IncomeSourceKind {
+    /// Convert enum variant to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IncomeSourceKind::Employer => "employer",
+            IncomeSourceKind::SideGig => "side_gig",
+            IncomeSourceKind::Passive => "passive",
+            IncomeSourceKind::Other => "other",
+        }
+    }
+}
+
+// ==================== Income Source Model ====================
+
+/// A named source of a user's income (e.g. "Acme Corp", "Etsy shop")
+///
+/// Income transactions link to their source through `income_source_id`.
+/// Inactive sources are kept for history but can't be assigned anymore.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct IncomeSource {
+    pub id: Uuid,
+    pub user_id: String,
+    pub name: String,
+    pub kind: String,                     // "employer", "side_gig", "passive", or "other"
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// ==================== Income Source Request Models ====================
+
+/// Request to add an income source
+#[derive(Debug, Deserialize)]
+pub struct CreateIncomeSourceRequest {
+    pub user_id: String,
+    pub name: String,
+    pub kind: IncomeSourceKind,
+}
+
+/// Request to rename, reclassify, or (de)activate an income source
+#[derive(Debug, Deserialize)]
+pub struct UpdateIncomeSourceRequest {
+    pub name: Option<String>,
+    pub kind: Option<IncomeSourceKind>,
+    pub active: Option<bool>,
+}
+
+/// `?months=` of the income report
+#[derive(Debug, Deserialize)]
+pub struct IncomeReportQuery {
+    pub months: Option<u32>,              // Complete calendar months covered (default 12)
+}
+
+// ==================== Income Report Models ====================
+
+/// Income per source over the last complete months
+#[derive(Debug, Serialize)]
+pub struct IncomeReport {
+    pub currency: String,                 // The user's base currency, which every amount is in
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,               // Exclusive: start of the current month
+    pub total: BigDecimal,
+    pub sources: Vec<IncomeSourceSummary>,
+}
+
+/// One source's income in the report window
+#[derive(Debug, Serialize)]
+pub struct IncomeSourceSummary {
+    pub income_source_id: Option<Uuid>,   // None: income not linked to a source
+    pub name: String,
+    pub kind: Option<String>,
+    pub total: BigDecimal,
+    pub monthly_average: BigDecimal,
+    pub months_with_income: u32,
+    pub stability: Option<f64>,           // Coefficient of variation of monthly totals; lower is steadier
+    pub growth_percent: Option<f64>,      // Later half of the window vs the earlier half
+    pub monthly: Vec<IncomeMonth>,
+}
+
+/// A source's income in one calendar month
+#[derive(Debug, Serialize)]
+pub struct IncomeMonth {
+    pub month: NaiveDate,                 // First day of the month
+    pub total: BigDecimal,
+}
@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== Installment Plan Models ====================
+
+/// A credit card expense converted into monthly installments (trả góp)
+///
+/// `total_amount` (principal plus flat interest) is billed to the card in
+/// `months` installments; `total_amount - billed` is still owed.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct InstallmentPlan {
+    pub id: Uuid,
+    pub user_id: String,
+    pub wallet_id: Uuid,
+    pub transaction_id: Option<Uuid>,     // Converted expense (null once deleted)
+    pub description: Option<String>,
+    pub principal: BigDecimal,
+    pub months: i16,
+    pub monthly_rate: BigDecimal,         // Flat, percent of principal per month
+    pub fee_rate: BigDecimal,             // One-off, percent of principal
+    pub fee: BigDecimal,                  // Conversion fee charged when converting
+    pub interest: BigDecimal,             // Interest over the whole plan
+    pub total_amount: BigDecimal,         // principal + interest
+    pub installment_amount: BigDecimal,   // Every installment but the last, which takes the rounding
+    pub installments_posted: i16,
+    pub billed: BigDecimal,
+    pub next_due_at: DateTime<Utc>,
+    pub status: String,                   // "active" or "completed"
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One installment of a plan's schedule
+#[derive(Debug, Serialize)]
+pub struct Installment {
+    pub number: i16,
+    pub due_at: DateTime<Utc>,
+    pub amount: BigDecimal,
+    pub posted: bool,
+}
+
+/// A plan with its full schedule
+#[derive(Debug, Serialize)]
+pub struct InstallmentPlanDetail {
+    #[serde(flatten)]
+    pub plan: InstallmentPlan,
+    pub remaining: BigDecimal,            // total_amount - billed
+    pub schedule: Vec<Installment>,
+}
+
+// ==================== Installment Plan Request Models ====================
+
+/// Request to convert a card expense into an installment plan
+#[derive(Debug, Deserialize)]
+pub struct CreateInstallmentPlanRequest {
+    pub user_id: String,
+    pub transaction_id: Uuid,
+    pub months: i16,
+    pub monthly_rate: Option<BigDecimal>, // Default 0 (interest-free)
+    pub fee_rate: Option<BigDecimal>,     // Default 0
+}
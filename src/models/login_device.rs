@@ -0,0 +1,18 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+// ==================== Login Device Model ====================
+
+/// A device the account has logged in from
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct LoginDevice {
+    pub id: Uuid,
+    pub user_agent: String,
+    pub first_ip: String,
+    pub last_ip: String,
+    pub login_count: i32,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub current: bool,                    // The device of the token making the request
+}
@@ -2,18 +2,258 @@
 
 /// Wallet module - User wallet accounts and types
 pub mod wallet;
-pub use wallet::{Wallet, WalletType, CreateWalletRequest, UpdateWalletRequest};
+pub use wallet::{
+    Wallet, WalletBalance, WalletType, CreateWalletRequest, UpdateWalletRequest,
+    WalletBehavior, WalletTemplate, CustomWalletType, CreateCustomWalletTypeRequest,
+    InterestProjection, InterestProjectionQuery, ProjectedMonth, WalletSummary, WalletCategoryTotal, AsOfQuery, DeleteQuery, WalletListQuery,
+    WalletFreeze, FreezeWalletRequest, WalletFreezeEvent,
+    WalletMember, AddWalletMemberRequest, UpdateWalletMemberRequest,
+    BalanceExplainQuery, BalanceExplanation, BalanceStart, LedgerEntry,
+    BalanceHistoryQuery, BalanceHistory, BalancePoint,
+    SetOpeningBalanceRequest, WalletOpeningBalance, OpeningBalanceChange,
+};
+
+/// Bucket module - Goal-based sub-balances of a wallet
+pub mod bucket;
+pub use bucket::{
+    WalletBucket, WalletBuckets, CreateBucketRequest, UpdateBucketRequest,
+    BucketAmountRequest, BucketTransferRequest,
+};
 
 /// Transaction module - Financial transactions on wallets
 pub mod transaction;
-pub use transaction::{Transaction, CreateTransactionRequest, UpdateTransactionRequest};
+pub use transaction::{
+    Transaction, CreatedTransaction, UpdatedTransaction, DeletedTransaction, TransactionFilterQuery, TransactionListQuery, TransactionPage, TransactionNotes, TransactionSplit, CreateTransactionRequest, SplitLine, UpdateTransactionRequest,
+    BulkUpdateRequest, BulkUpdateValues, BulkUpdatePreview, BulkUpdateSample, BulkUpdateResult,
+    SaveTransactionDraftRequest, TransactionDraft,
+    BatchValidateRequest, BatchValidation, BatchRowValidation, BatchFieldError,
+    BulkCreateTransactionsRequest, BulkCreatedTransactions, BulkDeleteTransactionsRequest,
+    BulkDeletedTransactions, TransactionSearchQuery, TransactionSearchResults, TransactionSearchHit, SearchHighlight,
+};
+
+/// Category module - Per-user transaction categories
+pub mod category;
+pub use category::{
+    Category, CreateCategoryRequest, UpdateCategoryRequest, MoveCategoryRequest, MergeCategoryRequest,
+    CategoryChange, SeedCategoriesRequest, SeededCategories,
+};
+
+/// Template module - Saved transactions for frequent entries
+pub mod template;
+pub use template::{
+    TransactionTemplate, CreateTransactionTemplateRequest, UpdateTransactionTemplateRequest,
+    CreateFromTemplateRequest,
+};
+
+/// Import preset module - CSV column mappings per bank and statement imports
+pub mod import_preset;
+pub use import_preset::{
+    ImportPreset, ImportPresetFields, CreateImportPresetRequest, ImportPresetMatch,
+    ImportMapping, ImportSummary, ImportRowError,
+};
+
+/// Income module - Income sources and income analytics
+pub mod income;
+pub use income::{
+    IncomeSource, CreateIncomeSourceRequest, UpdateIncomeSourceRequest,
+    IncomeReportQuery, IncomeReport, IncomeSourceSummary, IncomeMonth,
+};
+
+/// Field module - User-defined transaction fields (metadata)
+pub mod field;
+pub use field::{CreateFieldDefinitionRequest, FieldType, TransactionFieldDefinition};
+
+/// Policy module - Deployment-wide transaction validation rules
+pub mod policy;
+pub use policy::{TransactionPolicy, UpdateTransactionPolicyRequest};
+
+/// Reimbursement module - Reimbursable expense tracking
+pub mod reimbursement;
+pub use reimbursement::{
+    Reimbursement, ReimbursementListQuery, ReimbursementStatus, OutstandingByPayer,
+    CreateReimbursementRequest, UpdateReimbursementStatusRequest,
+};
 
 /// Debt module - Debt and obligation tracking
 pub mod debt;
-pub use debt::{Debt, CreateDebtRequest, UpdateDebtRequest};
+pub use debt::{
+    Debt, DebtDetail, DebtExportQuery, DebtFilterQuery, CreateDebtRequest, UpdateDebtRequest,
+    DebtPaymentQr, PaymentQrQuery, QrFormat,
+    DebtSettlement, CreateSettlementRequest, CounterpartyBalance,
+    DebtAmortization, DebtAccrualMonth, DebtInstallment, StatementVisibility, UpdateStatementVisibilityRequest,
+    BillSplit, BillSplitDetail, CreateBillSplitRequest,
+};
+
+/// Goal module - Savings goals and contributions
+pub mod goal;
+pub use goal::{
+    SavingsGoal, GoalContribution, GoalProgress, GoalDetail,
+    CreateGoalRequest, UpdateGoalRequest, CreateContributionRequest,
+};
+
+/// Group module - Portfolio groups of wallets and debts, and net worth
+pub mod group;
+pub use group::{
+    PortfolioGroup, CreateGroupRequest, UpdateGroupRequest, AssignGroupRequest, GroupAssignment,
+    NetWorthQuery, GroupNetWorth, NetWorth,
+};
+
+/// Currency module - Wallet currencies, exchange rates and base currencies
+pub mod currency;
+pub use currency::{
+    Currency, ExchangeRate, UpsertExchangeRatesRequest, ExchangeRateUpdate,
+    CurrencySettings, UpdateCurrencySettingsRequest,
+};
+
+/// Attachment module - Documents attached to debts
+pub mod attachment;
+pub use attachment::Attachment;
+
+/// Admin module - Operator tooling (balance replay, backups, account merges, migration status)
+pub mod admin;
+pub use admin::{
+    BackupRun, ReplayQuery, ReplayReport, WalletReplay,
+    ImpersonationSession, CreateImpersonationRequest, ImpersonationGrant, ImpersonationEvent,
+    AccountMerge, MergeAccountsRequest, LinkAccountRequest,
+    AnonymizedExport, AppliedMigration, OnlineMigrationStatus, MigrationStatus,
+    JobStatus, JobExecutionSummary, CacheStats, AdminUserSummary, AdminLoginForm, AdminUserSearchQuery,
+    LogSettings, UpdateLogSettingsRequest,
+};
+
+/// Report module - Scheduled report email subscriptions
+pub mod report;
+pub use report::{
+    ReportDelivery, ReportFormat, ReportFrequency, ReportSubscription, ReportType, ReportVisibility,
+    CreateReportSubscriptionRequest, UpdateReportSubscriptionRequest,
+    ComparePeriod, PeriodComparisonQuery, PeriodComparison, AmountComparison, CategoryComparison,
+    SpendingQuery, SpendingByCategory, CategorySpending, CashflowQuery, MonthlyCashflow, CashflowMonth,
+    ChartQuery, Chart, ChartSeries,
+};
+
+/// Login device module - Devices an account has logged in from
+pub mod login_device;
+pub use login_device::LoginDevice;
+
+/// Budget alert module - Per-user budget alert thresholds
+pub mod budget_alert;
+pub use budget_alert::{BudgetAlertSettings, UpdateBudgetAlertSettingsRequest};
+
+/// Provider webhook module - Inbound provider deliveries and their dead letters
+pub mod provider_webhook;
+pub use provider_webhook::{ProviderDeadLetterQuery, ProviderWebhookDeadLetter, ProviderWebhookReceipt};
+
+/// Tax module - Tax classification of categories and the annual tax report
+pub mod tax;
+pub use tax::{
+    TaxCategoryMapping, SetTaxCategoryRequest, TaxReportQuery, TaxReport, TaxClassSummary,
+    TaxCategoryTotal, TaxReportTransaction,
+};
+
+/// Standing order module - Scheduled transfers between wallets
+pub mod standing_order;
+pub use standing_order::{
+    StandingOrder, StandingOrderRun, StandingOrderMode, StandingOrderFrequency,
+    CreateStandingOrderRequest, UpdateStandingOrderRequest,
+};
+
+/// Recurring module - Transactions posted on a schedule
+pub mod recurring;
+pub use recurring::{
+    RecurringTransaction, RecurringTransactionRun, CreateRecurringTransactionRequest,
+    UpdateRecurringTransactionRequest,
+};
+
+/// Top-up module - Low-balance auto top-up rules
+pub mod top_up;
+pub use top_up::{TopUp, TopUpRule, CreateTopUpRuleRequest, UpdateTopUpRuleRequest};
+
+/// Credit card module - Statement cycles and interest-free periods
+pub mod credit_card;
+pub use credit_card::{
+    CreditCardTerms, CreditCardStatement, CreditCardCycle, CreditCardStatements, SetCreditCardTermsRequest,
+};
+
+/// Installment module - Card expenses converted into monthly installments
+pub mod installment;
+pub use installment::{Installment, InstallmentPlan, InstallmentPlanDetail, CreateInstallmentPlanRequest};
+
+/// Transfer module - Wallet-to-wallet and two-phase transfers
+pub mod transfer;
+pub use transfer::{
+    WalletTransfer, WalletTransferRequest,
+    PendingTransfer, CreatePendingTransferRequest, PendingTransferListQuery,
+    TransferAgingReport, TransferAgingBucket, AgedTransfer,
+};
+
+/// Budget module - Spending limits per category and period
+pub mod budget;
+pub use budget::{
+    Budget, BudgetPeriod, BudgetStatus, BudgetWarning, CreateBudgetRequest, UpdateBudgetRequest,
+};
+
+/// Device module - Push notification devices and alert preferences
+pub mod device;
+pub use device::{DevicePreferences, PushDevice, PushPlatform, RegisterDeviceRequest};
+
+/// User module - Accounts and login
+pub mod user;
+pub use user::{AuthToken, LoginRequest, RegisterRequest, User};
+
+/// API key module - Scoped keys for integrations
+pub mod api_key;
+pub use api_key::{ApiKey, ApiKeyGrant, CreateApiKeyRequest};
+
+/// Email template module - Versioned, localized transactional email templates
+pub mod email_template;
+pub use email_template::{
+    EmailTemplate, EmailTemplateVariant, RenderedEmail, SaveEmailTemplateRequest, PreviewEmailTemplateRequest,
+};
+
+/// Export module - Transaction and full-account data exports
+pub mod export;
+pub use export::{CreateExportRequest, DataExportQuery, DataFormat, ExportJob, ExportKind};
+
+/// Dashboard module - A user's balances, month-to-date totals, budgets and upcoming items
+pub mod dashboard;
+pub use dashboard::{Dashboard, DashboardBalances, DashboardWallet, MonthTotals, UpcomingItem};
+
+/// Activity feed module - Merged timeline of a user's events
+pub mod feed;
+pub use feed::{FeedEvent, FeedPage, FeedQuery};
+
+/// Audit module - Recorded changes of wallets, transactions and debts
+pub mod audit;
+pub use audit::{AuditEntry, AuditFilterQuery};
+
+/// Dry run module - Previews of mutating requests
+pub mod dry_run;
+pub use dry_run::{DryRunBalance, DryRunPreview};
+
+/// Notification module - In-app notifications and their delivery settings
+pub mod notification;
+pub use notification::{
+    Notification, NotificationListQuery, NotificationPage, NotificationSettings, NotificationsRead,
+    UpdateNotificationSettingsRequest,
+};
+
+/// Webhook module - Change event endpoints and their delivery log
+pub mod webhook;
+pub use webhook::{
+    CreateWebhookEndpointRequest, UpdateWebhookEndpointRequest, WebhookDelivery, WebhookDeliveryQuery,
+    WebhookEndpoint,
+};
+
+/// Trash module - Soft-deleted wallets and transactions
+pub mod trash;
+pub use trash::{TrashItem, TrashPage, TrashQuery};
+
+/// Pagination module - Shared list query parameters
+pub mod pagination;
+pub use pagination::{DateRangeQuery, FieldsQuery, PageQuery};
 
 // ==================== Common API Response Model ====================
 
+use schemars::JsonSchema;
 use serde::Serialize;
 
 /// Generic API response wrapper
@@ -25,6 +265,12 @@ pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub error: Option<String>,
+    /// Machine-readable error code (see `error::AppError`); omitted when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
+    /// The invalid fields of an `invalid_fields` error; omitted otherwise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<FieldError>>,
 }
 
 impl<T> ApiResponse<T> {
@@ -34,6 +280,8 @@ impl<T> ApiResponse<T> {
             success: true,
             data: Some(data),
             error: None,
+            code: None,
+            fields: None,
         }
     }
 
@@ -43,6 +291,23 @@ impl<T> ApiResponse<T> {
             success: false,
             data: None,
             error: Some(error),
+            code: None,
+            fields: None,
         }
     }
+
+    /// Create an error response carrying a machine-readable code
+    pub fn error_with_code(error: String, code: &'static str) -> Self {
+        Self {
+            code: Some(code),
+            ..Self::error(error)
+        }
+    }
+}
+
+/// One field of a request body that failed validation (see `validation`)
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
 }
@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// ==================== Notification Models ====================
+
+/// A notification raised for a user by the notification job
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Notification {
+    pub id: Uuid,
+    pub user_id: String,
+    pub kind: String,                     // "debt_due", "debt_overdue", "budget_exceeded" or "debt_paid"
+    pub subject_id: Uuid,                 // The debt or budget it is about
+    pub title: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+    pub delivery_status: String,          // "pending", "sent", "failed" or "none"
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// Filter of the notification list: `?unread=true` leaves out read ones
+#[derive(Debug, Default, Deserialize)]
+pub struct NotificationListQuery {
+    #[serde(default)]
+    pub unread: bool,
+}
+
+/// A page of notifications with the user's unread count
+#[derive(Debug, Serialize)]
+pub struct NotificationPage {
+    pub notifications: Vec<Notification>,
+    pub unread: i64,
+}
+
+/// Result of marking notifications read
+#[derive(Debug, Serialize)]
+pub struct NotificationsRead {
+    pub marked: u64,
+}
+
+// ==================== Notification Settings ====================
+
+/// Where and when a user's notifications are delivered
+///
+/// `webhook_secret` signs the webhook calls; it is generated when a webhook
+/// URL is set.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct NotificationSettings {
+    pub user_id: String,
+    pub due_days_before: i16,             // Notify this many days before a debt is due
+    pub webhook_url: Option<String>,
+    pub webhook_secret: Option<String>,
+    pub email: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>, // None while the defaults apply
+}
+
+/// Request replacing a user's notification settings (unset disables a channel)
+#[derive(Debug, Deserialize)]
+pub struct UpdateNotificationSettingsRequest {
+    pub due_days_before: i16,
+    pub webhook_url: Option<String>,
+    pub email: Option<String>,
+}
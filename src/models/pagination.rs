@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+// ==================== List Query Models ====================
+
+/// `?page=&per_page=` query parameters accepted by list endpoints
+///
+/// Both are optional; limits and defaults come from `AppConfig`
+/// (see `limits::page_params`).
+#[derive(Debug, Default, Deserialize)]
+pub struct PageQuery {
+    pub page: Option<u32>,              // 1-based page number (default 1)
+    pub per_page: Option<u32>,          // Page size (default DEFAULT_PAGE_SIZE)
+}
+
+/// `?from=&to=` created_at filter (RFC 3339, `from` inclusive, `to` exclusive)
+#[derive(Debug, Default, Deserialize)]
+pub struct DateRangeQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// `?fields=id,amount,...` sparse fieldset of list endpoints (see fieldsets.rs)
+#[derive(Debug, Default, Deserialize)]
+pub struct FieldsQuery {
+    pub fields: Option<String>,
+}
@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+
+// ==================== Transaction Policy Models ====================
+
+/// Validation rules applied to created and edited transactions
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TransactionPolicy {
+    pub allow_zero_amount: bool,
+    pub max_amount: Option<BigDecimal>,                 // None: no limit
+    pub description_required_above: Option<BigDecimal>, // None: description never required
+    pub outlier_multiplier: Option<BigDecimal>,         // None: never compared with the wallet's history
+    pub max_net_worth_change_percent: Option<BigDecimal>, // None: never compared with net worth
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to replace the transaction policy
+#[derive(Debug, Deserialize)]
+pub struct UpdateTransactionPolicyRequest {
+    #[serde(default)]
+    pub allow_zero_amount: bool,
+    pub max_amount: Option<BigDecimal>,
+    pub description_required_above: Option<BigDecimal>,
+    pub outlier_multiplier: Option<BigDecimal>,
+    pub max_net_worth_change_percent: Option<BigDecimal>,
+}
@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// ==================== Provider Webhook Models ====================
+
+/// A provider delivery that failed verification, as received
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ProviderWebhookDeadLetter {
+    pub id: Uuid,
+    pub provider: String,                 // "stripe", "plaid" or "momo"
+    pub reason: String,                   // e.g. "bad_signature", "replayed"
+    pub remote_addr: Option<String>,
+    pub headers: serde_json::Value,
+    pub body: String,
+    pub body_truncated: bool,
+    pub received_at: DateTime<Utc>,
+}
+
+/// Filter of the dead letter list: `?provider=stripe`
+#[derive(Debug, Default, Deserialize)]
+pub struct ProviderDeadLetterQuery {
+    pub provider: Option<String>,
+}
+
+/// A verified provider delivery, queued for its integration
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderWebhookReceipt {
+    pub id: Uuid,
+    pub provider: String,
+    pub received_at: DateTime<Utc>,
+}
@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+use super::StandingOrderFrequency;
+
+// ==================== Recurring Transaction Model ====================
+
+/// An income or expense posted on a schedule (salary, rent, subscriptions)
+///
+/// Each run creates a transaction exactly as if it had been entered by hand.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RecurringTransaction {
+    pub id: Uuid,
+    pub user_id: String,
+    pub name: String,
+    pub wallet_id: Uuid,
+    pub transaction_type: String,         // "income" or "expense"
+    pub amount: BigDecimal,
+    pub category: String,
+    pub description: String,
+    pub frequency: String,                // "daily", "weekly", or "monthly"
+    pub start_at: DateTime<Utc>,
+    pub next_run_at: DateTime<Utc>,
+    pub active: bool,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Outcome of one scheduled run
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RecurringTransactionRun {
+    pub id: Uuid,
+    pub rule_id: Uuid,
+    pub user_id: String,
+    pub status: String,                   // "completed" or "failed"
+    pub scheduled_for: DateTime<Utc>,
+    pub transaction_id: Option<Uuid>,     // The transaction posted (completed runs)
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// ==================== Recurring Transaction Request Models ====================
+
+/// Request to create a recurring transaction
+#[derive(Debug, Deserialize)]
+pub struct CreateRecurringTransactionRequest {
+    pub user_id: String,
+    pub name: String,
+    pub wallet_id: Uuid,
+    pub transaction_type: String,         // "income" or "expense"
+    pub amount: BigDecimal,
+    pub category: String,
+    #[serde(default)]
+    pub description: String,
+    pub frequency: StandingOrderFrequency,
+    pub start_at: Option<DateTime<Utc>>,  // First run (default: now)
+}
+
+/// Request to change or pause/resume a recurring transaction
+#[derive(Debug, Deserialize)]
+pub struct UpdateRecurringTransactionRequest {
+    pub name: Option<String>,
+    pub amount: Option<BigDecimal>,
+    pub category: Option<String>,
+    pub description: Option<String>,
+    pub active: Option<bool>,
+}
@@ -0,0 +1,90 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== ReimbursementStatus Enum ====================
+
+/// Lifecycle of a reimbursable expense (only moves forward)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[serde(rename_all = "snake_case")]
+pub enum ReimbursementStatus {
+    Pending,
+    Submitted,
+    Reimbursed,
+}
+
+impl ReimbursementStatus {
+    /// Convert enum variant to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReimbursementStatus::Pending => "pending",
+            ReimbursementStatus::Submitted => "submitted",
+            ReimbursementStatus::Reimbursed => "reimbursed",
+        }
+    }
+
+    /// Parse string to ReimbursementStatus enum
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(ReimbursementStatus::Pending),
+            "submitted" => Some(ReimbursementStatus::Submitted),
+            "reimbursed" => Some(ReimbursementStatus::Reimbursed),
+            _ => None,
+        }
+    }
+}
+
+// ==================== Reimbursement Model ====================
+
+/// An expense someone else owes the user back
+///
+/// Settled either manually (status update) or automatically when a matching
+/// income transaction is recorded (`settled_by_transaction_id`).
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Reimbursement {
+    pub id: Uuid,
+    pub user_id: String,
+    pub transaction_id: Uuid,             // The reimbursable expense
+    pub payer: String,                    // Who pays it back (employer, client, ...)
+    pub amount: BigDecimal,               // Amount owed (defaults to the expense amount)
+    pub status: String,                   // "pending", "submitted", or "reimbursed"
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub reimbursed_at: Option<DateTime<Utc>>,
+    pub settled_by_transaction_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Outstanding (not yet reimbursed) totals for one payer
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OutstandingByPayer {
+    pub payer: String,
+    pub count: i64,
+    pub total: BigDecimal,
+    pub oldest_expense_at: DateTime<Utc>,
+}
+
+// ==================== Reimbursement Request Models ====================
+
+/// Request to mark an expense as reimbursable
+#[derive(Debug, Deserialize)]
+pub struct CreateReimbursementRequest {
+    pub user_id: String,
+    pub transaction_id: Uuid,
+    pub payer: String,
+    pub amount: Option<BigDecimal>,
+}
+
+/// Request to advance a reimbursement's status
+#[derive(Debug, Deserialize)]
+pub struct UpdateReimbursementStatusRequest {
+    pub status: ReimbursementStatus,
+    pub settled_by_transaction_id: Option<Uuid>,
+}
+
+/// `?status=` filter for the reimbursement list
+#[derive(Debug, Default, Deserialize)]
+pub struct ReimbursementListQuery {
+    pub status: Option<ReimbursementStatus>,
+}
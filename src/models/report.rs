@@ -0,0 +1,336 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== Report Enums ====================
+
+/// Built-in report a subscription delivers
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportType {
+    /// Every transaction in the period
+    Transactions,
+    /// Income/expense totals per category for the period
+    CategorySummary,
+    /// Current wallet bucket balances and goal progress
+    BucketBalances,
+}
+
+impl ReportType {
+    /// Convert enum variant to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportType::Transactions => "transactions",
+            ReportType::CategorySummary => "category_summary",
+            ReportType::BucketBalances => "bucket_balances",
+        }
+    }
+
+    /// Parse string to ReportType enum
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "transactions" => Some(ReportType::Transactions),
+            "category_summary" => Some(ReportType::CategorySummary),
+            "bucket_balances" => Some(ReportType::BucketBalances),
+            _ => None,
+        }
+    }
+}
+
+/// File format of the emailed report
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    Csv,
+    Pdf,
+}
+
+impl ReportFormat {
+    /// Convert enum variant to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "csv",
+            ReportFormat::Pdf => "pdf",
+        }
+    }
+
+    /// Parse string to ReportFormat enum
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "csv" => Some(ReportFormat::Csv),
+            "pdf" => Some(ReportFormat::Pdf),
+            _ => None,
+        }
+    }
+}
+
+/// Delivery schedule of a subscription
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFrequency {
+    Weekly,
+    Monthly,
+}
+
+impl ReportFrequency {
+    /// Convert enum variant to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportFrequency::Weekly => "weekly",
+            ReportFrequency::Monthly => "monthly",
+        }
+    }
+
+    /// Parse string to ReportFrequency enum
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "weekly" => Some(ReportFrequency::Weekly),
+            "monthly" => Some(ReportFrequency::Monthly),
+            _ => None,
+        }
+    }
+}
+
+/// Who sees a report besides its owner
+///
+/// Only a report of one shared wallet can be shared (see the migration
+/// `report_visibility`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportVisibility {
+    /// The owner only
+    Private,
+    /// Every member of the report's wallet
+    Household,
+    /// The listed members of the report's wallet
+    Members,
+}
+
+impl ReportVisibility {
+    /// Convert enum variant to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportVisibility::Private => "private",
+            ReportVisibility::Household => "household",
+            ReportVisibility::Members => "members",
+        }
+    }
+}
+
+/// Calendar period compared by the period-over-period report
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ComparePeriod {
+    /// Monday 00:00 UTC to the next Monday
+    Week,
+    /// 1st 00:00 UTC to the next 1st
+    Month,
+}
+
+// ==================== Report Subscription Model ====================
+
+/// A user's subscription to a report emailed on a schedule
+///
+/// Each delivery covers the period ending at the scheduled run time
+/// (the previous week or month).
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ReportSubscription {
+    pub id: Uuid,
+    pub user_id: String,
+    pub report_type: String,              // "transactions" or "category_summary"
+    pub format: String,                   // "csv" or "pdf"
+    pub frequency: String,                // "weekly" or "monthly"
+    pub email: String,                    // Recipient address
+    pub wallet_id: Option<Uuid>,          // Optional wallet filter (all wallets when None)
+    pub locale: String,                   // Language of the email, e.g. "en" or "vi"
+    pub visibility: String,               // "private", "household" or "members"
+    pub shared_with: Vec<String>,         // Members who see it when visibility is "members"
+    pub active: bool,
+    pub next_run_at: DateTime<Utc>,
+    pub last_sent_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One attempted delivery of a subscription
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ReportDelivery {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub user_id: String,
+    pub status: String,                   // "sent" or "failed"
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub row_count: i32,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// ==================== Report Request Models ====================
+
+/// Request to create a report subscription
+#[derive(Debug, Deserialize)]
+pub struct CreateReportSubscriptionRequest {
+    pub user_id: String,
+    pub report_type: ReportType,
+    pub format: Option<ReportFormat>,
+    pub frequency: ReportFrequency,
+    pub email: String,
+    pub wallet_id: Option<Uuid>,
+    pub locale: Option<String>,           // Default "en"
+    pub visibility: Option<ReportVisibility>, // Default private
+    pub shared_with: Option<Vec<String>>, // Required when visibility is members
+}
+
+/// Request to update a report subscription
+#[derive(Debug, Deserialize)]
+pub struct UpdateReportSubscriptionRequest {
+    pub format: Option<ReportFormat>,
+    pub frequency: Option<ReportFrequency>,
+    pub email: Option<String>,
+    pub locale: Option<String>,
+    pub active: Option<bool>,
+    pub visibility: Option<ReportVisibility>,
+    pub shared_with: Option<Vec<String>>,
+}
+
+/// `?period=&offset=&wallet_id=` of the period comparison report
+#[derive(Debug, Deserialize)]
+pub struct PeriodComparisonQuery {
+    pub period: ComparePeriod,
+    pub offset: Option<u32>,              // Periods back from the current one (default 0: in progress)
+    pub wallet_id: Option<Uuid>,          // Optional wallet filter (all wallets when None)
+}
+
+/// `?from=&to=&wallet_id=` of the spending by category report
+#[derive(Debug, Deserialize)]
+pub struct SpendingQuery {
+    pub from: Option<DateTime<Utc>>,      // Default: start of the current month
+    pub to: Option<DateTime<Utc>>,        // Exclusive; default: now
+    pub wallet_id: Option<Uuid>,
+}
+
+/// `?months=&wallet_id=` of the monthly cashflow report
+#[derive(Debug, Deserialize)]
+pub struct CashflowQuery {
+    pub months: Option<u32>,              // Months ending with the current one (default 12)
+    pub wallet_id: Option<Uuid>,
+}
+
+// ==================== Report Response Models ====================
+
+/// Totals of one period compared with the period before it
+#[derive(Debug, Serialize)]
+pub struct PeriodComparison {
+    pub period: ComparePeriod,
+    pub currency: String,                 // The user's base currency, which every amount is in
+    pub current_start: DateTime<Utc>,     // Also the (exclusive) end of the previous period
+    pub current_end: DateTime<Utc>,       // Exclusive
+    pub previous_start: DateTime<Utc>,
+    pub income: AmountComparison,
+    pub expense: AmountComparison,
+    pub categories: Vec<CategoryComparison>,
+}
+
+/// Current vs previous amount
+#[derive(Debug, Serialize)]
+pub struct AmountComparison {
+    pub current: BigDecimal,
+    pub previous: BigDecimal,
+    pub delta: BigDecimal,                // current - previous
+    pub percent_change: Option<BigDecimal>, // None when previous is 0
+}
+
+/// Period comparison of one category's income or expense
+#[derive(Debug, Serialize)]
+pub struct CategoryComparison {
+    pub category: String,
+    pub transaction_type: String,
+    #[serde(flatten)]
+    pub amounts: AmountComparison,
+}
+
+/// Expenses per category over a date range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpendingByCategory {
+    pub currency: String,                 // The user's base currency, which every amount is in
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,                // Exclusive
+    pub total: BigDecimal,
+    pub count: i64,                       // Expenses; a split one counts once
+    pub categories: Vec<CategorySpending>, // Largest first
+}
+
+/// One category's expenses
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CategorySpending {
+    pub category: String,
+    pub total: BigDecimal,
+    pub count: i64,                       // Expenses in this category, or with a split line in it
+    #[sqlx(default)]
+    pub percent: BigDecimal,              // Share of the total spending
+}
+
+/// Income and expenses per calendar month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonthlyCashflow {
+    pub currency: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,               // Exclusive: the 1st after the current month
+    pub income: BigDecimal,
+    pub expense: BigDecimal,
+    pub net: BigDecimal,                  // income - expense
+    pub months: Vec<CashflowMonth>,       // Oldest first, one per month
+}
+
+/// One month of the cashflow report
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CashflowMonth {
+    pub month: NaiveDate,                 // First day of the month
+    pub income: BigDecimal,
+    pub expense: BigDecimal,
+    pub net: BigDecimal,
+}
+
+// ==================== Chart Models ====================
+
+/// `?version=&from=&to=&months=&granularity=&wallet_id=` of a chart; each chart reads the ones it needs
+#[derive(Debug, Deserialize)]
+pub struct ChartQuery {
+    pub version: Option<u32>,             // Contract version; default: the latest
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,            // Inclusive
+    pub months: Option<u32>,              // Cash flow: months ending with the current one
+    pub granularity: Option<String>,      // Net worth: "day", "week" or "month"
+    pub wallet_id: Option<Uuid>,          // Spending and cash flow
+}
+
+/// A chart's data, shaped for drawing
+///
+/// `labels` are the slices (donut) or the x axis (bar, line); every series
+/// has one value per label.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chart {
+    pub version: u32,                     // Contract version of this shape
+    pub chart_type: String,               // "spending-by-category", "cash-flow" or "net-worth"
+    pub kind: String,                     // "donut", "bar" or "line"
+    pub currency: String,                 // The user's base currency, which every value is in
+    pub from: NaiveDate,
+    pub to: NaiveDate,                    // Inclusive
+    pub labels: Vec<String>,
+    pub series: Vec<ChartSeries>,
+    pub total: BigDecimal,                // Donut: the whole; bar and line: the net series' total
+}
+
+/// One series of a chart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChartSeries {
+    pub key: String,
+    pub label: String,
+    pub color: String,                    // "#rrggbb"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub point_colors: Option<Vec<String>>, // Donut: one per slice
+    pub values: Vec<BigDecimal>,
+    pub total: BigDecimal,                // Sum for flows (donut, bar); last value for balances (line)
+}
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== Standing Order Enums ====================
+
+/// How much a standing order moves on each run
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StandingOrderMode {
+    /// Move `amount`
+    Fixed,
+    /// Move everything above `sweep_above`
+    Sweep,
+}
+
+impl StandingOrderMode {
+    /// Convert enum variant to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StandingOrderMode::Fixed => "fixed",
+            StandingOrderMode::Sweep => "sweep",
+        }
+    }
+
+    /// Parse string to StandingOrderMode enum
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "fixed" => Some(StandingOrderMode::Fixed),
+            "sweep" => Some(StandingOrderMode::Sweep),
+            _ => None,
+        }
+    }
+}
+
+/// How often a standing order runs
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StandingOrderFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl StandingOrderFrequency {
+    /// Convert enum variant to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StandingOrderFrequency::Daily => "daily",
+            StandingOrderFrequency::Weekly => "weekly",
+            StandingOrderFrequency::Monthly => "monthly",
+        }
+    }
+
+    /// Parse string to StandingOrderFrequency enum
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "daily" => Some(StandingOrderFrequency::Daily),
+            "weekly" => Some(StandingOrderFrequency::Weekly),
+            "monthly" => Some(StandingOrderFrequency::Monthly),
+            _ => None,
+        }
+    }
+}
+
+// ==================== Standing Order Model ====================
+
+/// A scheduled transfer between two of a user's wallets
+///
+/// Each run posts an expense on the source wallet and an income on the
+/// destination wallet (category "transfer").
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StandingOrder {
+    pub id: Uuid,
+    pub user_id: String,
+    pub name: String,
+    pub from_wallet_id: Uuid,
+    pub to_wallet_id: Uuid,
+    pub mode: String,                     // "fixed" or "sweep"
+    pub amount: Option<BigDecimal>,       // Fixed mode: amount moved per run
+    pub sweep_above: Option<BigDecimal>,  // Sweep mode: balance left in the source wallet
+    pub frequency: String,                // "daily", "weekly", or "monthly"
+    pub start_at: DateTime<Utc>,
+    pub next_run_at: DateTime<Utc>,
+    pub active: bool,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Outcome of one scheduled run
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StandingOrderRun {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub user_id: String,
+    pub status: String,                   // "completed", "skipped", or "failed"
+    pub scheduled_for: DateTime<Utc>,
+    pub amount: Option<BigDecimal>,       // Amount moved (or that could not be moved)
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// ==================== Standing Order Request Models ====================
+
+/// Request to create a standing order
+#[derive(Debug, Deserialize)]
+pub struct CreateStandingOrderRequest {
+    pub user_id: String,
+    pub name: String,
+    pub from_wallet_id: Uuid,
+    pub to_wallet_id: Uuid,
+    pub mode: StandingOrderMode,
+    pub amount: Option<BigDecimal>,       // Required in fixed mode
+    pub sweep_above: Option<BigDecimal>,  // Required in sweep mode
+    pub frequency: StandingOrderFrequency,
+    pub start_at: Option<DateTime<Utc>>,  // First run (default: now)
+}
+
+/// Request to change or pause/resume a standing order
+///
+/// `amount` applies to fixed orders and `sweep_above` to sweeps.
+#[derive(Debug, Deserialize)]
+pub struct UpdateStandingOrderRequest {
+    pub name: Option<String>,
+    pub amount: Option<BigDecimal>,
+    pub sweep_above: Option<BigDecimal>,
+    pub active: Option<bool>,
+}
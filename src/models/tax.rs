@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+use super::ReportFormat;
+
+// ==================== TaxClass Enum ====================
+
+/// How a category is treated for tax purposes
+///
+/// Income classes apply to the category's income transactions, the other
+/// classes to its expenses.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxClass {
+    TaxableIncome,
+    ExemptIncome,
+    Deductible,
+    BusinessExpense,
+    Charitable,
+}
+
+impl TaxClass {
+    /// Convert enum variant to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaxClass::TaxableIncome => "taxable_income",
+            TaxClass::ExemptIncome => "exempt_income",
+            TaxClass::Deductible => "deductible",
+            TaxClass::BusinessExpense => "business_expense",
+            TaxClass::Charitable => "charitable",
+        }
+    }
+}
+
+// ==================== Tax Category Model ====================
+
+/// A user's tax class for one transaction category
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TaxCategoryMapping {
+    pub id: Uuid,
+    pub user_id: String,
+    pub category: String,                 // Exact transaction category
+    pub tax_class: String,                // See `TaxClass`
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to set (or change) a category's tax class
+#[derive(Debug, Deserialize)]
+pub struct SetTaxCategoryRequest {
+    pub user_id: String,
+    pub category: String,
+    pub tax_class: TaxClass,
+}
+
+/// `?format=` of the tax report (JSON when absent)
+#[derive(Debug, Deserialize)]
+pub struct TaxReportQuery {
+    pub format: Option<ReportFormat>,
+}
+
+// ==================== Tax Report Models ====================
+
+/// A calendar year's classified income and expenses
+#[derive(Debug, Serialize)]
+pub struct TaxReport {
+    pub year: i32,
+    pub currency: String,                 // The user's base currency, which every amount is in
+    pub classes: Vec<TaxClassSummary>,
+    /// Supporting transactions beyond `MAX_EXPORT_ROWS` were dropped (totals are complete)
+    pub truncated: bool,
+}
+
+/// Totals and supporting transactions of one tax class
+#[derive(Debug, Serialize)]
+pub struct TaxClassSummary {
+    pub tax_class: String,
+    pub total: BigDecimal,
+    pub count: i64,
+    pub categories: Vec<TaxCategoryTotal>,
+    pub transactions: Vec<TaxReportTransaction>,
+}
+
+/// One category's total within a tax class
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TaxCategoryTotal {
+    pub category: String,
+    pub total: BigDecimal,
+    pub count: i64,
+}
+
+/// A transaction supporting a tax class total
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TaxReportTransaction {
+    pub transaction_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub category: String,
+    pub amount: BigDecimal,
+    pub description: Option<String>,
+}
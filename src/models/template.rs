@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== Transaction Template Model ====================
+
+/// A saved transaction (e.g. "Parking", "Lunch") that can be entered again in one tap
+///
+/// Templates without an amount ask for it each time they are used.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TransactionTemplate {
+    pub id: Uuid,
+    pub user_id: String,
+    pub name: String,
+    pub wallet_id: Uuid,
+    pub transaction_type: String,         // "income" or "expense"
+    pub amount: Option<BigDecimal>,
+    pub category: String,
+    pub description: String,
+    pub use_count: i32,                   // Transactions created from this template
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// ==================== Transaction Template Request Models ====================
+
+/// Request to save a template
+#[derive(Debug, Deserialize)]
+pub struct CreateTransactionTemplateRequest {
+    pub user_id: String,
+    pub name: String,
+    pub wallet_id: Uuid,
+    pub transaction_type: String,         // "income" or "expense"
+    pub amount: Option<BigDecimal>,
+    pub category: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Request to change a template
+#[derive(Debug, Deserialize)]
+pub struct UpdateTransactionTemplateRequest {
+    pub name: Option<String>,
+    pub wallet_id: Option<Uuid>,
+    pub transaction_type: Option<String>,
+    pub amount: Option<BigDecimal>,
+    pub category: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Request to create a transaction from a template
+///
+/// Every field except `user_id` overrides the template for this transaction only.
+#[derive(Debug, Deserialize)]
+pub struct CreateFromTemplateRequest {
+    pub user_id: String,
+    pub wallet_id: Option<Uuid>,
+    pub amount: Option<BigDecimal>,       // Required when the template has none
+    pub category: Option<String>,
+    pub description: Option<String>,
+    pub notes: Option<String>,
+    pub metadata: Option<serde_json::Map<String, serde_json::Value>>,
+    pub bucket_id: Option<Uuid>,
+    pub income_source_id: Option<Uuid>,
+    #[serde(default)]
+    pub confirm: bool,
+}
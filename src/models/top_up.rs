@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== Top-Up Rule Models ====================
+
+/// Refill a wallet from a funding wallet when its balance drops below a threshold
+///
+/// Each top-up posts an expense on the funding wallet and an income on the
+/// wallet (category "transfer").
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TopUpRule {
+    pub id: Uuid,
+    pub user_id: String,
+    pub wallet_id: Uuid,                  // Wallet kept topped up (asset wallets only)
+    pub funding_wallet_id: Uuid,
+    pub threshold: BigDecimal,            // Top up when the balance drops below this
+    pub amount: BigDecimal,               // Amount moved per top-up
+    pub daily_cap: BigDecimal,            // Most moved per UTC day
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One top-up attempt
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TopUp {
+    pub id: Uuid,
+    pub rule_id: Uuid,
+    pub user_id: String,
+    pub status: String,                   // "completed", "capped", or "failed"
+    pub amount: Option<BigDecimal>,       // Amount moved (or that could not be moved)
+    pub balance_before: BigDecimal,       // Wallet balance that triggered it
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// ==================== Top-Up Rule Request Models ====================
+
+/// Request to create a top-up rule
+#[derive(Debug, Deserialize)]
+pub struct CreateTopUpRuleRequest {
+    pub user_id: String,
+    pub wallet_id: Uuid,
+    pub funding_wallet_id: Uuid,
+    pub threshold: BigDecimal,
+    pub amount: BigDecimal,
+    pub daily_cap: Option<BigDecimal>,    // Default: `amount` (one top-up a day)
+}
+
+/// Request to change or pause/resume a top-up rule
+#[derive(Debug, Deserialize)]
+pub struct UpdateTopUpRuleRequest {
+    pub threshold: Option<BigDecimal>,
+    pub amount: Option<BigDecimal>,
+    pub daily_cap: Option<BigDecimal>,
+    pub active: Option<bool>,
+}
@@ -1,8 +1,11 @@
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::types::BigDecimal;
 use uuid::Uuid;
 
+use super::{BudgetWarning, DateRangeQuery, WalletBalance};
+
 // ==================== Transaction Model ====================
 
 /// Represents a financial transaction on a wallet
@@ -14,23 +17,79 @@ use uuid::Uuid;
 ///
 /// Transactions are linked to a wallet via `wallet_id` foreign key,
 /// and cascade-delete when the wallet is deleted.
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, JsonSchema)]
 pub struct Transaction {
     pub id: Uuid,
+    pub sequence_number: Option<i64>,     // Per-user number (#1, #2, ...) in commit order; null until backfilled
     pub user_id: String,
     pub wallet_id: Uuid,                  // Required FK to wallets
+    pub bucket_id: Option<Uuid>,          // Wallet bucket an expense drew from
+    pub income_source_id: Option<Uuid>,   // Income only: where the money came from
     pub amount: BigDecimal,               // Always positive; type determines operation
+    pub currency: String,                 // The wallet's currency
     pub transaction_type: String,         // "income" or "expense"
     pub category: String,                 // Transaction category (e.g., groceries, salary)
     pub description: Option<String>,      // Optional details
+    pub notes: Option<String>,            // Optional long-form Markdown notes (max 64KB)
+    pub metadata: serde_json::Value,      // Custom field values (object keyed by field definitions)
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub version: i64,                     // Bumped by every change
+}
+
+impl Transaction {
+    /// Field names a list can be narrowed to (`?fields=`)
+    pub const FIELDS: &'static [&'static str] = &[
+        "id", "sequence_number", "user_id", "wallet_id", "bucket_id", "income_source_id", "amount", "currency",
+        "transaction_type", "category", "description", "notes", "metadata", "created_at", "updated_at", "version",
+    ];
+}
+
+/// A newly recorded transaction, with the budget thresholds it crossed
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CreatedTransaction {
+    #[serde(flatten)]
+    pub transaction: Transaction,
+    pub over_budget: bool,                // This expense took a budget over its limit
+    pub budget_warnings: Vec<BudgetWarning>,
+    pub splits: Vec<TransactionSplit>,    // Empty unless spread over several categories
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wallet_balances: Vec<WalletBalance>, // The wallet after this transaction (not sent by bulk creates)
+}
+
+/// An edited transaction, with the balances of the wallets the edit moved
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UpdatedTransaction {
+    #[serde(flatten)]
+    pub transaction: Transaction,
+    pub wallet_balances: Vec<WalletBalance>, // Its wallet, and the one it moved from
+}
+
+/// A deleted transaction, with the balance of its wallet afterwards
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DeletedTransaction {
+    pub id: Uuid,
+    pub wallet_balances: Vec<WalletBalance>,
+}
+
+/// The part of a transaction's amount counted toward one category
+///
+/// A split transaction's lines sum to its amount; category reports count the
+/// lines instead of the transaction's own category.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, JsonSchema)]
+pub struct TransactionSplit {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub user_id: String,
+    pub category: String,
+    pub amount: BigDecimal,               // In the transaction's currency
+    pub created_at: DateTime<Utc>,
 }
 
 // ==================== Transaction Request Models ====================
 
 /// Request to create a new transaction
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct CreateTransactionRequest {
     pub user_id: String,
     pub wallet_id: Uuid,
@@ -38,13 +97,269 @@ pub struct CreateTransactionRequest {
     pub transaction_type: String,         // "income" or "expense"
     pub category: String,
     pub description: String,
+    pub notes: Option<String>,
+    pub metadata: Option<serde_json::Map<String, serde_json::Value>>,
+    pub bucket_id: Option<Uuid>,          // Expenses only: draw from this bucket of the wallet
+    pub income_source_id: Option<Uuid>,   // Income only: an active source of the user
+    #[serde(default)]
+    pub splits: Vec<SplitLine>,           // Spread over categories; must sum to `amount`
+    #[serde(default)]
+    pub confirm: bool,                    // Accept an amount the policy's sanity limits flag
+}
+
+/// One category line of a split transaction
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SplitLine {
+    pub category: String,
+    pub amount: BigDecimal,
 }
 
 /// Request to update an existing transaction
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct UpdateTransactionRequest {
     pub wallet_id: Option<Uuid>,
     pub amount: Option<BigDecimal>,
     pub category: Option<String>,
     pub description: Option<String>,
+    pub notes: Option<String>,
+    pub metadata: Option<serde_json::Map<String, serde_json::Value>>, // Replaces all custom fields
+    pub income_source_id: Option<Uuid>,   // Income only: link to an active source
+    pub splits: Option<Vec<SplitLine>>,   // Replaces the split lines; [] removes them
+    #[serde(default)]
+    pub confirm: bool,                    // Accept an amount the policy's sanity limits flag
+}
+
+/// Optional filters for the transaction list (`?wallet_id=&transaction_type=...`)
+#[derive(Debug, Default, Deserialize)]
+pub struct TransactionFilterQuery {
+    pub wallet_id: Option<Uuid>,
+    pub transaction_type: Option<String>, // "income" or "expense"
+    pub category: Option<String>,         // Exact category match
+    pub income_source_id: Option<Uuid>,
+    pub min_amount: Option<BigDecimal>,   // Inclusive
+    pub max_amount: Option<BigDecimal>,   // Inclusive
+    pub field: Option<String>,            // Custom field key, matched with `value`
+    pub value: Option<String>,            // Custom field value (text form: "acme", "true", "12.5")
+}
+
+/// `?page=&per_page=&sort=&cursor=` query parameters of the transaction list
+#[derive(Debug, Default, Deserialize)]
+pub struct TransactionListQuery {
+    pub page: Option<u32>,                // 1-based page number (default 1)
+    pub per_page: Option<u32>,            // Page size (default DEFAULT_PAGE_SIZE)
+    pub sort: Option<String>,             // "newest" (default), "oldest", "amount_desc" or "amount_asc"
+    pub cursor: Option<String>,           // `next_cursor` of the previous page, instead of `page`
+    pub fields: Option<String>,           // Comma-separated `Transaction::FIELDS` to return (default all)
+}
+
+/// A page of the transaction list
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TransactionPage {
+    pub transactions: Vec<Transaction>,
+    pub total_count: i64,                 // Transactions matching the filters, on all pages
+    pub next_cursor: Option<String>,      // Pass as `?cursor=` for the next page; null on the last one
+}
+
+/// `?q=` and filters of the transaction search
+#[derive(Debug, Deserialize)]
+pub struct TransactionSearchQuery {
+    pub q: String,                        // Words to find (websearch syntax: "quoted phrase", -excluded, or)
+    pub wallet_id: Option<Uuid>,
+    pub transaction_type: Option<String>, // "income" or "expense"
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+/// A page of search results, best match first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionSearchResults {
+    pub results: Vec<TransactionSearchHit>,
+    pub total_count: i64,                 // Matches on all pages
+}
+
+/// One matching transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionSearchHit {
+    pub transaction: Transaction,
+    pub rank: f32,                        // Full-text rank plus trigram similarity; higher is better
+    pub highlights: Vec<SearchHighlight>,
+}
+
+/// Where a query word occurs in a matching transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHighlight {
+    pub field: String,                    // "description", "category", "notes" or "amount"
+    pub start: usize,                     // Character offsets in the field, end exclusive
+    pub end: usize,
+}
+
+/// Request to change every transaction matching a filter (preview or apply)
+#[derive(Debug, Deserialize)]
+pub struct BulkUpdateRequest {
+    pub user_id: String,
+    #[serde(default)]
+    pub filter: TransactionFilterQuery,   // Same filters as the transaction list
+    #[serde(default)]
+    pub range: DateRangeQuery,            // Optional created_at range
+    pub set: BulkUpdateValues,
+    pub expected_count: Option<i64>,      // Apply only: reject if the preview count no longer matches
+}
+
+/// Values assigned by a bulk update; unset fields are left unchanged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkUpdateValues {
+    pub category: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Request to autosave an in-progress transaction form
+///
+/// `form` is stored as-is: fields may be missing or not yet valid.
+#[derive(Debug, Deserialize)]
+pub struct SaveTransactionDraftRequest {
+    pub form: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Request to validate spreadsheet-style draft rows without saving them
+///
+/// Each row has the fields of `CreateTransactionRequest` (minus `user_id`),
+/// kept as raw JSON so one malformed cell doesn't reject the whole batch.
+#[derive(Debug, Deserialize)]
+pub struct BatchValidateRequest {
+    pub user_id: String,
+    pub rows: Vec<serde_json::Map<String, serde_json::Value>>,
+}
+
+/// Request to create many transactions at once, all or none
+#[derive(Debug, Deserialize)]
+pub struct BulkCreateTransactionsRequest {
+    pub user_id: String,
+    pub transactions: Vec<BulkTransaction>,
+}
+
+/// One transaction of a bulk create: the fields of `CreateTransactionRequest` minus `user_id`
+#[derive(Debug, Deserialize)]
+pub struct BulkTransaction {
+    pub wallet_id: Uuid,
+    pub amount: BigDecimal,
+    pub transaction_type: String,         // "income" or "expense"
+    pub category: String,
+    pub description: String,
+    pub notes: Option<String>,
+    pub metadata: Option<serde_json::Map<String, serde_json::Value>>,
+    pub bucket_id: Option<Uuid>,
+    pub income_source_id: Option<Uuid>,
+    #[serde(default)]
+    pub splits: Vec<SplitLine>,
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+impl BulkTransaction {
+    /// The single-transaction request for `user_id`
+    pub fn into_request(self, user_id: &str) -> CreateTransactionRequest {
+        CreateTransactionRequest {
+            user_id: user_id.to_string(),
+            wallet_id: self.wallet_id,
+            amount: self.amount,
+            transaction_type: self.transaction_type,
+            category: self.category,
+            description: self.description,
+            notes: self.notes,
+            metadata: self.metadata,
+            bucket_id: self.bucket_id,
+            income_source_id: self.income_source_id,
+            splits: self.splits,
+            confirm: self.confirm,
+        }
+    }
+}
+
+/// Request to delete many transactions at once, all or none
+#[derive(Debug, Deserialize)]
+pub struct BulkDeleteTransactionsRequest {
+    pub user_id: String,
+    pub transaction_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub hard: bool,                       // Remove instead of soft-deleting (also purges soft-deleted ones)
+}
+
+// ==================== Transaction Response Models ====================
+
+/// Transaction notes in source and rendered form
+///
+/// `html` is rendered from the Markdown with raw HTML escaped and unsafe
+/// link schemes removed, so it can be embedded in share views as-is.
+#[derive(Debug, Serialize)]
+pub struct TransactionNotes {
+    pub transaction_id: Uuid,
+    pub markdown: Option<String>,
+    pub html: Option<String>,
+}
+
+/// Transactions a bulk update would change, with a sample of before/after values
+#[derive(Debug, Serialize)]
+pub struct BulkUpdatePreview {
+    pub affected: i64,
+    pub sample: Vec<BulkUpdateSample>,
+}
+
+/// One affected transaction in a bulk update preview
+#[derive(Debug, Serialize)]
+pub struct BulkUpdateSample {
+    pub transaction_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub amount: BigDecimal,
+    pub before: BulkUpdateValues,
+    pub after: BulkUpdateValues,
+}
+
+/// Outcome of an applied bulk update
+#[derive(Debug, Serialize)]
+pub struct BulkUpdateResult {
+    pub updated: u64,
+}
+
+/// Autosaved transaction form, kept until it expires or is discarded
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransactionDraft {
+    pub form: serde_json::Map<String, serde_json::Value>,
+    pub saved_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Validation result of a batch of draft rows
+#[derive(Debug, Serialize)]
+pub struct BatchValidation {
+    pub valid: bool,                      // Every row can be created
+    pub invalid_rows: usize,
+    pub rows: Vec<BatchRowValidation>,    // One per submitted row, in order
+}
+
+/// Validation result of one draft row
+#[derive(Debug, Serialize)]
+pub struct BatchRowValidation {
+    pub index: usize,                     // Position in the submitted rows (0-based)
+    pub valid: bool,
+    pub errors: Vec<BatchFieldError>,
+}
+
+/// A problem with one field of a draft row
+#[derive(Debug, Serialize)]
+pub struct BatchFieldError {
+    pub field: String,                    // Row field, e.g. "amount"
+    pub message: String,
+}
+
+/// Transactions created by a bulk create, in request order
+#[derive(Debug, Serialize)]
+pub struct BulkCreatedTransactions {
+    pub created: usize,
+    pub transactions: Vec<CreatedTransaction>,
+}
+
+/// Outcome of a bulk delete
+#[derive(Debug, Serialize)]
+pub struct BulkDeletedTransactions {
+    pub deleted: usize,
 }
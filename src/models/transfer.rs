@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== Wallet Transfer Models ====================
+
+/// A transfer posted on both wallets at once
+///
+/// The source gets the `out` leg (an expense) and the destination the `in`
+/// leg (an income), both in category "transfer".
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WalletTransfer {
+    pub id: Uuid,
+    pub user_id: String,
+    pub from_wallet_id: Uuid,
+    pub to_wallet_id: Uuid,
+    pub amount: BigDecimal,               // Debited, in the source wallet's currency
+    pub to_amount: BigDecimal,            // Credited, in the destination wallet's currency
+    pub exchange_rate: BigDecimal,        // 1 unless the currencies differ
+    pub description: String,
+    pub out_transaction_id: Uuid,
+    pub in_transaction_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request to move money between two wallets
+#[derive(Debug, Deserialize)]
+pub struct WalletTransferRequest {
+    pub user_id: String,
+    pub from_wallet_id: Uuid,
+    pub to_wallet_id: Uuid,
+    pub amount: BigDecimal,               // In the source wallet's currency
+    #[serde(default)]
+    pub description: String,
+}
+
+// ==================== Pending Transfer Models ====================
+
+/// A transfer debited from its source wallet and not yet (or no longer) in transit
+///
+/// Initiating posts an expense on the source; settling posts an income on the
+/// destination, cancelling an income on the source (category "transfer").
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PendingTransfer {
+    pub id: Uuid,
+    pub user_id: String,
+    pub from_wallet_id: Uuid,
+    pub to_wallet_id: Uuid,
+    pub amount: BigDecimal,               // Debited, in the source wallet's currency
+    pub reference: String,                // Bank reference or note
+    pub status: String,                   // "in_transit", "settled", or "cancelled"
+    pub expected_by: DateTime<Utc>,       // Reminders start after this
+    pub debit_transaction_id: Uuid,
+    pub resolution_transaction_id: Option<Uuid>, // Credit (settled) or refund (cancelled)
+    pub resolution_amount: Option<BigDecimal>,   // Credited at settlement's rate, or refunded
+    pub initiated_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// Request to initiate a transfer
+#[derive(Debug, Deserialize)]
+pub struct CreatePendingTransferRequest {
+    pub user_id: String,
+    pub from_wallet_id: Uuid,
+    pub to_wallet_id: Uuid,
+    pub amount: BigDecimal,
+    #[serde(default)]
+    pub reference: String,
+    pub expected_by: Option<DateTime<Utc>>, // Default: 3 days from now
+}
+
+/// Filter for the transfer list (`?status=in_transit`)
+#[derive(Debug, Deserialize)]
+pub struct PendingTransferListQuery {
+    pub status: Option<String>,
+}
+
+// ==================== Aging Report Models ====================
+
+/// Unsettled transfers by how long they have been in transit
+#[derive(Debug, Serialize)]
+pub struct TransferAgingReport {
+    pub as_of: DateTime<Utc>,
+    pub count: i64,
+    pub total: BigDecimal,
+    pub overdue_count: i64,               // Past `expected_by`
+    pub buckets: Vec<TransferAgingBucket>,
+    pub transfers: Vec<AgedTransfer>,     // Oldest first
+}
+
+/// Transfers in transit for `min_days` to `max_days` whole days
+#[derive(Debug, Serialize)]
+pub struct TransferAgingBucket {
+    pub label: String,                    // e.g. "3-5 days"
+    pub min_days: i64,
+    pub max_days: Option<i64>,            // None: open-ended
+    pub count: i64,
+    pub total: BigDecimal,
+}
+
+/// An unsettled transfer with its age
+#[derive(Debug, Serialize)]
+pub struct AgedTransfer {
+    #[serde(flatten)]
+    pub transfer: PendingTransfer,
+    pub age_days: i64,
+    pub overdue: bool,
+}
@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+// ==================== Trash Models ====================
+
+/// A soft-deleted wallet or transaction
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TrashItem {
+    pub entity_type: String,              // "wallet" or "transaction"
+    pub id: Uuid,
+    pub wallet_id: Uuid,                  // The wallet itself, or the transaction's
+    pub name: Option<String>,             // Wallet name, or transaction description
+    pub category: Option<String>,         // Transactions only
+    pub amount: BigDecimal,               // Wallet balance, or transaction amount
+    pub currency: String,
+    pub transaction_count: i64,           // Transactions deleted with the wallet (restored with it)
+    pub deleted_at: DateTime<Utc>,
+    #[sqlx(default)]
+    pub purge_at: Option<DateTime<Utc>>,  // When the purge job removes it for good; null if kept
+}
+
+/// A page of the trash, most recently deleted first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashPage {
+    pub items: Vec<TrashItem>,
+    pub total_count: i64,
+}
+
+/// `?entity_type=&page=&per_page=` of the trash
+#[derive(Debug, Default, Deserialize)]
+pub struct TrashQuery {
+    pub entity_type: Option<String>,      // "wallet" or "transaction" (default both)
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
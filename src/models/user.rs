@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// ==================== User Models ====================
+
+/// A user account (the password hash never leaves the database layer)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct User {
+    pub id: String,                       // The user_id of the account's data
+    pub email: String,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request to create an account
+#[derive(Debug, Deserialize)]
+pub struct RegisterRequest {
+    pub email: String,
+    pub password: String,
+    pub locale: Option<String>,           // Seeds default categories in this language
+}
+
+/// Request to log in
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub email: String,
+    pub password: String,
+    pub locale: Option<String>,           // Seeds default categories in this language
+}
+
+/// A bearer token and the account it was issued for
+#[derive(Debug, Serialize)]
+pub struct AuthToken {
+    pub token: String,
+    pub token_type: String,               // Always "Bearer"
+    pub expires_at: DateTime<Utc>,
+    pub device_id: Uuid,                  // Login device the token belongs to
+    pub user: User,
+}
@@ -1,12 +1,15 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::types::BigDecimal;
 use uuid::Uuid;
 
+use super::{Transaction, WalletBucket};
+
 // ==================== WalletType Enum ====================
 
 /// Enumeration of wallet types for organizing user finances
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub enum WalletType {
     #[serde(rename = "Cash")]
     Cash,
@@ -44,32 +47,138 @@ impl WalletType {
     pub fn is_credit_card(&self) -> bool {
         matches!(self, WalletType::CreditCard)
     }
+
+    /// Balance rules of the built-in type
+    pub fn template(&self) -> WalletTemplate {
+        match self {
+            WalletType::CreditCard => WalletTemplate {
+                behavior: WalletBehavior::Liability,
+                supports_credit_limit: true,
+            },
+            WalletType::Cash | WalletType::BankAccount | WalletType::Other => WalletTemplate {
+                behavior: WalletBehavior::Asset,
+                supports_credit_limit: false,
+            },
+        }
+    }
+}
+
+// ==================== Wallet Templates ====================
+
+/// Whether a wallet holds money or tracks money owed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WalletBehavior {
+    Asset,
+    Liability,
+}
+
+impl WalletBehavior {
+    /// Convert enum variant to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WalletBehavior::Asset => "asset",
+            WalletBehavior::Liability => "liability",
+        }
+    }
+
+    /// Parse string to WalletBehavior enum
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "asset" => Some(WalletBehavior::Asset),
+            "liability" => Some(WalletBehavior::Liability),
+            _ => None,
+        }
+    }
+}
+
+/// Balance and credit rules a wallet follows, from its built-in or custom type
+///
+/// - Asset: `balance` is money held; expenses can't exceed it.
+/// - Liability with a credit limit: `balance` is money owed; expenses can't
+///   exceed `credit_limit - balance`.
+/// - Liability without a credit limit: expenses are not capped.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct WalletTemplate {
+    pub behavior: WalletBehavior,
+    pub supports_credit_limit: bool,
+}
+
+impl WalletTemplate {
+    pub fn is_liability(&self) -> bool {
+        self.behavior == WalletBehavior::Liability
+    }
+}
+
+/// A wallet type defined by a user (e.g. "Loan": liability, "Gift Card": asset)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CustomWalletType {
+    pub id: Uuid,
+    pub user_id: String,
+    pub name: String,
+    pub behavior: String,                 // "asset" or "liability"
+    pub supports_credit_limit: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl CustomWalletType {
+    pub fn template(&self) -> WalletTemplate {
+        WalletTemplate {
+            behavior: WalletBehavior::from_str(&self.behavior).unwrap_or(WalletBehavior::Asset),
+            supports_credit_limit: self.supports_credit_limit,
+        }
+    }
+}
+
+/// Request to define a custom wallet type
+#[derive(Debug, Deserialize)]
+pub struct CreateCustomWalletTypeRequest {
+    pub user_id: String,
+    pub name: String,
+    pub behavior: WalletBehavior,
+    #[serde(default)]
+    pub supports_credit_limit: bool,      // Liability types only
 }
 
 // ==================== Wallet Model ====================
 
 /// Represents a user's wallet account
 ///
-/// For CreditCard wallets:
+/// For wallets whose template supports a credit limit (CreditCard):
 /// - `balance` = current debt (0 = no debt, limit = fully used)
 /// - `available_balance()` = credit_limit - balance
 ///
 /// For other wallet types:
 /// - `balance` = current balance
 /// - `available_balance()` = balance
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, JsonSchema)]
 pub struct Wallet {
     pub id: Uuid,
     pub user_id: String,
     pub name: String,
     pub balance: BigDecimal,
+    pub currency: String, // ISO 4217 code of balance and transactions, fixed at creation
     pub credit_limit: Option<BigDecimal>,
     pub wallet_type: String, // Stored as string from database
+    pub custom_type_id: Option<Uuid>, // User-defined type (wallet_type is "Other")
+    pub group_id: Option<Uuid>, // Portfolio group (see groups.rs)
+    pub apy: Option<BigDecimal>, // Annual percentage yield (BankAccount only)
+    pub last_interest_posted_at: Option<DateTime<Utc>>,
+    #[sqlx(default)]
+    #[serde(default)]
+    pub sandbox: bool, // Test wallet; left out of reports and totals
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub version: i64, // Bumped by every change; 0 when rebuilt from history (`?as_of=`)
 }
 
 impl Wallet {
+    /// Field names a list can be narrowed to (`?fields=`)
+    pub const FIELDS: &'static [&'static str] = &[
+        "id", "user_id", "name", "balance", "currency", "credit_limit", "wallet_type", "custom_type_id", "group_id",
+        "apy", "last_interest_posted_at", "sandbox", "created_at", "updated_at", "version",
+    ];
+
     /// Get wallet type enum from string
     ///
     /// # Returns
@@ -78,41 +187,296 @@ impl Wallet {
         WalletType::from_str(&self.wallet_type)
     }
 
-    /// Calculate available balance based on wallet type
+    /// Calculate available balance under the wallet's template
     ///
-    /// For credit cards: `available = credit_limit - balance`
+    /// With a credit limit: `available = credit_limit - balance`
     /// For others: `available = balance`
-    pub fn available_balance(&self) -> BigDecimal {
-        if let Some(limit) = &self.credit_limit {
-            if self.wallet_type == "CreditCard" {
-                // balance represents current debt, so available = limit - debt
-                limit - &self.balance
-            } else {
-                self.balance.clone()
-            }
-        } else {
-            self.balance.clone()
+    pub fn available_balance(&self, template: &WalletTemplate) -> BigDecimal {
+        match &self.credit_limit {
+            // balance represents current debt, so available = limit - debt
+            Some(limit) if template.supports_credit_limit => limit - &self.balance,
+            _ => self.balance.clone(),
         }
     }
 }
 
+/// A wallet's balance as a transaction change left it
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WalletBalance {
+    pub wallet_id: Uuid,
+    pub balance: BigDecimal,
+    pub available_credit: Option<BigDecimal>, // Credit-limited wallets only: credit_limit - balance
+}
+
 // ==================== Wallet Request Models ====================
 
 /// Request to create a new wallet
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct CreateWalletRequest {
     pub user_id: String,
     pub name: String,
-    pub wallet_type: WalletType,
+    pub wallet_type: Option<WalletType>,  // Required unless custom_type_id is set
+    pub custom_type_id: Option<Uuid>,
     #[serde(default)]
     pub balance: BigDecimal,
+    pub currency: Option<String>,         // ISO 4217 code; default: the user's base currency
     pub credit_limit: Option<BigDecimal>,
+    pub apy: Option<BigDecimal>,
+    #[serde(default)]
+    pub sandbox: bool,
 }
 
 /// Request to update an existing wallet
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct UpdateWalletRequest {
     pub name: Option<String>,
     pub balance: Option<BigDecimal>,
     pub credit_limit: Option<BigDecimal>,
+    pub apy: Option<BigDecimal>,
+}
+
+// ==================== Wallet Freeze Models ====================
+
+/// An active freeze: no transaction may change the wallet's balance
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WalletFreeze {
+    pub wallet_id: Uuid,
+    pub user_id: String,
+    pub reason: Option<String>,
+    pub frozen_at: DateTime<Utc>,
+    pub unfreeze_at: Option<DateTime<Utc>>, // Lifted automatically at this time
+}
+
+/// Request to freeze a wallet (re-freezing replaces reason and unfreeze time)
+#[derive(Debug, Deserialize)]
+pub struct FreezeWalletRequest {
+    pub reason: Option<String>,
+    pub unfreeze_at: Option<DateTime<Utc>>,
+}
+
+/// Audit record of a freeze, unfreeze, or automatic unfreeze
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WalletFreezeEvent {
+    pub id: Uuid,
+    pub wallet_id: Uuid,
+    pub user_id: String,
+    pub action: String,                   // "freeze", "unfreeze", or "auto_unfreeze"
+    pub reason: Option<String>,
+    pub unfreeze_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+// ==================== Wallet Member Models ====================
+
+/// A user a wallet is shared with, or its owner
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WalletMember {
+    pub wallet_id: Uuid,
+    pub user_id: String,
+    pub role: String,                     // "owner", "editor", or "viewer"
+    pub added_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to share a wallet with a user
+#[derive(Debug, Deserialize)]
+pub struct AddWalletMemberRequest {
+    pub user_id: String,
+    pub role: String,                     // "editor" or "viewer"
+}
+
+/// Request to change a member's role
+#[derive(Debug, Deserialize)]
+pub struct UpdateWalletMemberRequest {
+    pub role: String,                     // "editor" or "viewer"
+}
+
+// ==================== Wallet Summary Models ====================
+
+/// Month-to-date quick stats for the wallet screen
+///
+/// `projected_month_end_balance` extrapolates the average daily spend over the
+/// remaining days of the month; expected income is not projected.
+/// `mtd_categories` totals the month per category and type, split lines in
+/// their own category.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletSummary {
+    pub wallet: Wallet,
+    pub available_balance: BigDecimal,
+    pub month_start: DateTime<Utc>,
+    pub mtd_income: BigDecimal,
+    pub mtd_expense: BigDecimal,
+    pub mtd_transaction_count: i64,
+    pub mtd_categories: Vec<WalletCategoryTotal>,
+    pub average_daily_spend: BigDecimal,
+    pub projected_month_end_balance: BigDecimal,
+    pub last_transaction: Option<Transaction>,
+    pub buckets: Vec<WalletBucket>,
+}
+
+/// One category's month-to-date income or expenses in a wallet
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WalletCategoryTotal {
+    pub category: String,
+    pub transaction_type: String,
+    pub total: BigDecimal,
+    pub count: i64,                       // Transactions in this category, or with a split line in it
+}
+
+/// `?as_of=` of the wallet read endpoints (default: now)
+#[derive(Debug, Deserialize)]
+pub struct AsOfQuery {
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+/// `?hard=true` of the wallet and transaction delete endpoints: remove the
+/// row instead of soft-deleting it
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteQuery {
+    #[serde(default)]
+    pub hard: bool,
+}
+
+/// Query of the wallet list: `?as_of=` and `?group_id=`
+#[derive(Debug, Deserialize)]
+pub struct WalletListQuery {
+    pub as_of: Option<DateTime<Utc>>,
+    pub group_id: Option<Uuid>,
+}
+
+// ==================== Interest Projection Models ====================
+
+/// Query parameters for the interest projection endpoint
+#[derive(Debug, Deserialize)]
+pub struct InterestProjectionQuery {
+    pub months: Option<u32>,
+}
+
+/// A single projected month of interest
+#[derive(Debug, Serialize)]
+pub struct ProjectedMonth {
+    pub month: u32,
+    pub interest: BigDecimal,
+    pub balance: BigDecimal,
+}
+
+/// Projected savings growth for an interest-bearing wallet
+///
+/// Assumes no further deposits or withdrawals and monthly compounding
+/// at the rate equivalent to the configured APY.
+#[derive(Debug, Serialize)]
+pub struct InterestProjection {
+    pub wallet_id: Uuid,
+    pub apy: BigDecimal,
+    pub starting_balance: BigDecimal,
+    pub total_interest: BigDecimal,
+    pub months: Vec<ProjectedMonth>,
+}
+
+// ==================== Balance Explanation Models ====================
+
+/// `?at=` of the balance explanation (default: now)
+#[derive(Debug, Deserialize)]
+pub struct BalanceExplainQuery {
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// A wallet's balance at a point in time and the ledger entries that make it up
+///
+/// `balance` = `start.balance` + the `delta` of every entry. `history_balance`
+/// recomputes the same point from the opening balance and the full current
+/// history; `consistent` is false when the two differ, i.e. history before
+/// the start snapshot was edited or deleted after the snapshot was taken.
+#[derive(Debug, Serialize)]
+pub struct BalanceExplanation {
+    pub wallet_id: Uuid,
+    pub at: DateTime<Utc>,
+    pub balance: BigDecimal,
+    pub start: BalanceStart,
+    pub entry_count: i64,
+    pub truncated: bool,                  // Only the first entries are listed
+    pub entries: Vec<LedgerEntry>,
+    pub history_balance: BigDecimal,
+    pub consistent: bool,
+    pub live_balance: BigDecimal,         // Current balance, for reference
+}
+
+/// Where a balance explanation starts counting from
+#[derive(Debug, Clone, Serialize)]
+pub struct BalanceStart {
+    pub source: String,                   // "replay" (latest balance replay) or "opening_balance"
+    pub replay_run_id: Option<Uuid>,
+    pub at: DateTime<Utc>,
+    pub balance: BigDecimal,
+}
+
+/// One transaction's effect on a wallet balance
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LedgerEntry {
+    pub transaction_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub transaction_type: String,
+    pub category: String,
+    pub description: Option<String>,
+    pub amount: BigDecimal,
+    pub delta: BigDecimal,                // Signed change: +amount for income, -amount for expense
+    pub balance_after: BigDecimal,
+}
+
+// ==================== Balance History Models ====================
+
+/// `?granularity=&from=&to=` of the balance history (UTC dates, both inclusive)
+#[derive(Debug, Deserialize)]
+pub struct BalanceHistoryQuery {
+    pub granularity: Option<String>,      // "day" (default), "week" or "month"
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,            // Default: today
+}
+
+/// A wallet's closing balance per day, week or month
+#[derive(Debug, Serialize)]
+pub struct BalanceHistory {
+    pub wallet_id: Uuid,
+    pub currency: String,
+    pub granularity: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub points: Vec<BalancePoint>,
+}
+
+/// The balance at the end of one period (or now, for the current one)
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BalancePoint {
+    pub period_start: NaiveDate,          // Monday of a week, 1st of a month
+    pub balance: BigDecimal,
+}
+
+// ==================== Opening Balance Models ====================
+
+/// Request to set a wallet's balance at the start of a past UTC day
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetOpeningBalanceRequest {
+    pub balance: BigDecimal,
+    pub as_of: NaiveDate,                 // Today or earlier
+}
+
+/// One setting of a wallet's opening balance; the latest one is in force
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct WalletOpeningBalance {
+    pub id: Uuid,
+    pub wallet_id: Uuid,
+    pub user_id: String,
+    pub set_by: String,
+    pub as_of: NaiveDate,
+    pub balance: BigDecimal,              // Balance at the start of as_of, before its transactions
+    pub adjustment: BigDecimal,           // Added to every balance of the wallet, past and current
+    pub created_at: DateTime<Utc>,
+}
+
+/// An opening balance just set and the wallet it recomputed
+#[derive(Debug, Serialize)]
+pub struct OpeningBalanceChange {
+    pub opening_balance: WalletOpeningBalance,
+    pub wallet: Wallet,
 }
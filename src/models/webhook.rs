@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// ==================== Webhook Endpoint Models ====================
+
+/// An endpoint receiving a user's change events
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct WebhookEndpoint {
+    pub id: Uuid,
+    pub user_id: String,
+    pub url: String,
+    pub secret: String,                   // Signs the payloads (HMAC-SHA256)
+    pub event_types: Option<Vec<String>>, // None for every event type
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to register a webhook endpoint
+#[derive(Debug, Deserialize)]
+pub struct CreateWebhookEndpointRequest {
+    pub user_id: String,
+    pub url: String,
+    pub event_types: Option<Vec<String>>,
+}
+
+/// Request to update a webhook endpoint (unset fields are kept)
+#[derive(Debug, Deserialize)]
+pub struct UpdateWebhookEndpointRequest {
+    pub url: Option<String>,
+    /// `[]` goes back to every event type
+    pub event_types: Option<Vec<String>>,
+    pub active: Option<bool>,
+}
+
+// ==================== Webhook Delivery Models ====================
+
+/// One event's delivery to one endpoint
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub endpoint_id: Uuid,
+    pub event_id: i64,
+    pub event_type: String,
+    pub entity_id: String,
+    pub status: String,                   // "pending", "sent" or "failed"
+    pub attempts: i16,
+    pub next_attempt_at: DateTime<Utc>,
+    pub response_status: Option<i16>,     // HTTP status of the last attempt
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// Filter of the delivery log: `?status=failed`
+#[derive(Debug, Default, Deserialize)]
+pub struct WebhookDeliveryQuery {
+    pub status: Option<String>,
+}
@@ -0,0 +1,180 @@
+use bigdecimal::{Signed, ToPrimitive, Zero};
+use sqlx::types::BigDecimal;
+
+// ==================== MONEY MATH ====================
+//
+// Amounts are stored in cents (two decimals). Anything that multiplies or
+// divides an amount goes through here, so rounding is the same everywhere:
+//
+// - `round_cents`, `percentage`, `prorate` compute one amount and round it
+//   half away from zero.
+// - `allocate` and `split_evenly` divide an amount into parts that add up to
+//   exactly the amount (largest-remainder method): every part is rounded down
+//   to cents, then the cents left over go one each to the parts that lost the
+//   most, ties to the earlier part. 100.00 in three is 33.34 + 33.33 + 33.33.
+//
+// ============================================================================
+
+/// One cent
+fn cent() -> BigDecimal {
+    BigDecimal::new(1.into(), 2)
+}
+
+/// `amount` rounded to cents
+pub fn round_cents(amount: &BigDecimal) -> BigDecimal {
+    amount.round(2)
+}
+
+/// `percent` percent of `amount`, rounded to cents
+pub fn percentage(amount: &BigDecimal, percent: &BigDecimal) -> BigDecimal {
+    round_cents(&(amount * percent / BigDecimal::from(100)))
+}
+
+/// The share `part / whole` of `amount` (e.g. days of a month), rounded to cents; zero if `whole` is
+pub fn prorate(amount: &BigDecimal, part: i64, whole: i64) -> BigDecimal {
+    if whole == 0 {
+        return BigDecimal::zero();
+    }
+    round_cents(&(amount * BigDecimal::from(part) / BigDecimal::from(whole)))
+}
+
+/// Divide `total` into parts proportional to `weights`, adding up to `total` rounded to cents
+///
+/// `None` if a weight is negative or they are all zero.
+pub fn allocate(total: &BigDecimal, weights: &[BigDecimal]) -> Option<Vec<BigDecimal>> {
+    if weights.is_empty() {
+        return Some(Vec::new());
+    }
+    if weights.iter().any(|w| w.is_negative()) {
+        return None;
+    }
+    let weight_sum: BigDecimal = weights.iter().sum();
+    if weight_sum.is_zero() {
+        return None;
+    }
+
+    // Allocate the magnitude so rounding down is toward zero for refunds too
+    let total = round_cents(total);
+    let magnitude = total.abs();
+
+    let exact: Vec<BigDecimal> = weights.iter().map(|w| &magnitude * w / &weight_sum).collect();
+    let mut parts: Vec<BigDecimal> = exact.iter().map(|share| share.with_scale(2)).collect();
+
+    let allocated: BigDecimal = parts.iter().sum();
+    let left_over = ((&magnitude - allocated) * BigDecimal::from(100)).to_usize().unwrap_or(0);
+    let mut by_remainder: Vec<usize> = (0..parts.len()).collect();
+    by_remainder.sort_by(|&a, &b| (&exact[b] - &parts[b]).cmp(&(&exact[a] - &parts[a])));
+    for &i in by_remainder.iter().take(left_over) {
+        parts[i] += cent();
+    }
+
+    if total.is_negative() {
+        parts.iter_mut().for_each(|part| *part = -part.clone());
+    }
+    Some(parts)
+}
+
+/// Divide `total` into `parts` equal parts, up to a cent, adding up to `total`
+///
+/// Earlier parts get the extra cents.
+pub fn split_evenly(total: &BigDecimal, parts: usize) -> Vec<BigDecimal> {
+    allocate(total, &vec![BigDecimal::from(1); parts]).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn d(amount: &str) -> BigDecimal {
+        BigDecimal::from_str(amount).unwrap()
+    }
+
+    fn ds(amounts: &[&str]) -> Vec<BigDecimal> {
+        amounts.iter().map(|a| d(a)).collect()
+    }
+
+    #[test]
+    fn split_evenly_gives_the_remainder_cent_to_the_first_part() {
+        let parts = split_evenly(&d("100.00"), 3);
+        assert_eq!(parts, ds(&["33.34", "33.33", "33.33"]));
+        assert_eq!(parts.iter().sum::<BigDecimal>(), d("100.00"));
+    }
+
+    #[test]
+    fn allocate_equal_weights_matches_split_evenly() {
+        let parts = allocate(&d("100.00"), &ds(&["1", "1", "1"])).unwrap();
+        assert_eq!(parts, split_evenly(&d("100.00"), 3));
+    }
+
+    #[test]
+    fn allocate_sums_exactly_to_the_total() {
+        let total = d("1000.01");
+        let parts = allocate(&total, &ds(&["3", "7", "11", "13"])).unwrap();
+        assert_eq!(parts.iter().sum::<BigDecimal>(), total);
+    }
+
+    #[test]
+    fn allocate_gives_leftover_cents_to_the_largest_remainders() {
+        // Exact shares 16.666.., 33.333.., 50.00: the first loses the most
+        let parts = allocate(&d("100.00"), &ds(&["1", "2", "3"])).unwrap();
+        assert_eq!(parts, ds(&["16.67", "33.33", "50.00"]));
+    }
+
+    #[test]
+    fn allocate_negative_total_mirrors_the_positive_one() {
+        let parts = allocate(&d("-100.00"), &ds(&["1", "1", "1"])).unwrap();
+        assert_eq!(parts, ds(&["-33.34", "-33.33", "-33.33"]));
+        assert_eq!(parts.iter().sum::<BigDecimal>(), d("-100.00"));
+    }
+
+    #[test]
+    fn allocate_zero_weight_gets_nothing() {
+        let parts = allocate(&d("10.00"), &ds(&["0", "1", "1"])).unwrap();
+        assert_eq!(parts, ds(&["0", "5.00", "5.00"]));
+    }
+
+    #[test]
+    fn allocate_refuses_negative_and_all_zero_weights() {
+        assert_eq!(allocate(&d("10.00"), &ds(&["1", "-1"])), None);
+        assert_eq!(allocate(&d("10.00"), &ds(&["0", "0"])), None);
+    }
+
+    #[test]
+    fn allocate_no_weights_is_no_parts() {
+        assert_eq!(allocate(&d("10.00"), &[]), Some(Vec::new()));
+        assert!(split_evenly(&d("10.00"), 0).is_empty());
+    }
+
+    #[test]
+    fn allocate_zero_total_is_all_zero() {
+        let parts = allocate(&d("0"), &ds(&["1", "2"])).unwrap();
+        assert!(parts.iter().all(|part| part.is_zero()));
+    }
+
+    #[test]
+    fn round_cents_rounds_half_away_from_zero() {
+        assert_eq!(round_cents(&d("0.005")), d("0.01"));
+        assert_eq!(round_cents(&d("0.015")), d("0.02"));
+        assert_eq!(round_cents(&d("2.675")), d("2.68"));
+        assert_eq!(round_cents(&d("-0.005")), d("-0.01"));
+        assert_eq!(round_cents(&d("0.0049")), d("0.00"));
+    }
+
+    #[test]
+    fn percentage_rounds_to_cents() {
+        assert_eq!(percentage(&d("33.33"), &d("15")), d("5.00"));
+        assert_eq!(percentage(&d("0.10"), &d("5")), d("0.01"));
+    }
+
+    #[test]
+    fn prorate_boundaries() {
+        let amount = d("100.00");
+        assert_eq!(prorate(&amount, 0, 30), d("0"));
+        assert_eq!(prorate(&amount, 30, 30), amount);
+        assert_eq!(prorate(&amount, 1, 3), d("33.33"));
+        assert_eq!(prorate(&amount, 2, 3), d("66.67"));
+        assert_eq!(prorate(&amount, 5, 0), d("0"));
+    }
+}
@@ -0,0 +1,566 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde_json::json;
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::AuthUser;
+use crate::budgets;
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::hooks::{DebtPaid, Hook, HookContext};
+use crate::limits;
+use crate::mailer::{is_valid_address, Mailer, OutgoingEmail};
+use crate::models::{
+    ApiResponse, Notification, NotificationListQuery, NotificationPage, NotificationSettings, NotificationsRead,
+    PageQuery, UpdateNotificationSettingsRequest,
+};
+use crate::routes::ScopedRoutes;
+use crate::webhooks::{self, SIGNATURE_HEADER};
+
+// ==================== NOTIFICATIONS ====================
+//
+// A periodic job (`NOTIFICATION_JOB`) raises in-app notifications and
+// delivers them outside the app:
+//
+// - Debt due: an active debt with something outstanding is due within the
+//   user's `due_days_before` days (default `NOTIFICATION_DUE_DAYS`). Raised
+//   once per debt and due date, so rescheduling re-arms it.
+// - Debt overdue: an active debt with something outstanding is past its due
+//   date. Raised once per debt and due date.
+// - Budget exceeded: spending in a budget's current period is over its
+//   amount (see budgets.rs). Raised once per budget and period, whatever the
+//   budget's alert thresholds.
+//
+// Debt paid is raised right away instead, by `NotificationHook` (see
+// hooks.rs), when a debt is marked paid or a settlement pays it off. Once per
+// debt. It is delivered by the next run of the job like the others.
+//
+// Notifications are listed with `GET /api/notifications/user/{user_id}` and
+// marked read by the client. Each one is also handed to every delivery
+// channel (`NotificationChannel`) the user has set up in their settings: a
+// webhook (JSON POST signed with HMAC-SHA256 in `X-KetoBook-Signature`) and
+// email (when SMTP is configured). A failed delivery is retried on later runs,
+// up to `MAX_DELIVERY_ATTEMPTS`; channels that already succeeded are called
+// again, so webhook receivers should dedupe by `id`.
+//
+// Push alerts to devices are separate (see alerts.rs).
+//
+// ============================================================================
+
+pub const NOTIFICATION_JOB: &str = "notifications";
+
+/// Delivery attempts before a notification is marked failed
+const MAX_DELIVERY_ATTEMPTS: i16 = 5;
+
+/// Notifications delivered per run
+const DELIVERY_BATCH: i64 = 500;
+
+/// How long a webhook has to answer
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest due-soon window a user can ask for, in days
+const MAX_DUE_DAYS: i16 = 60;
+
+const NOTIFICATION_COLUMNS: &str =
+    "id, user_id, kind, subject_id, title, body, created_at, read_at, delivery_status, delivered_at";
+
+const SETTINGS_COLUMNS: &str = "user_id, due_days_before, webhook_url, webhook_secret, email, updated_at";
+
+// ==================== Handlers ====================
+
+/// A page of a user's notifications, newest first, with the unread count
+///
+/// Supports `?page=&per_page=` and `?unread=true`.
+pub async fn get_user_notifications(
+    user: AuthUser,
+    page: web::Query<PageQuery>,
+    query: web::Query<NotificationListQuery>,
+    db: web::Data<PgPool>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, AppError> {
+    let page = limits::page_params(&config, &page).map_err(AppError::Validation)?;
+
+    let notifications = sqlx::query_as::<_, Notification>(&format!(
+        "SELECT {} FROM notifications
+         WHERE user_id = $1 AND (NOT $2 OR read_at IS NULL)
+         ORDER BY created_at DESC, id
+         LIMIT $3 OFFSET $4",
+        NOTIFICATION_COLUMNS
+    ))
+    .bind(&user.user_id)
+    .bind(query.unread)
+    .bind(page.limit())
+    .bind(page.offset())
+    .fetch_all(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to fetch notifications"))?;
+
+    let (unread,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND read_at IS NULL")
+            .bind(&user.user_id)
+            .fetch_one(db.get_ref())
+            .await
+            .map_err(AppError::database("Failed to fetch notifications"))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(NotificationPage { notifications, unread })))
+}
+
+/// Mark one notification read (again is a no-op)
+pub async fn mark_notification_read(
+    path: web::Path<(String, Uuid)>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, notification_id) = path.into_inner();
+
+    let notification = sqlx::query_as::<_, Notification>(&format!(
+        "UPDATE notifications SET read_at = COALESCE(read_at, CURRENT_TIMESTAMP)
+         WHERE id = $1 AND user_id = $2
+         RETURNING {}",
+        NOTIFICATION_COLUMNS
+    ))
+    .bind(notification_id)
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to update notification"))?
+    .ok_or_else(|| AppError::NotFound("Notification not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(notification)))
+}
+
+/// Mark all of a user's notifications read
+pub async fn mark_all_notifications_read(
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let result =
+        sqlx::query("UPDATE notifications SET read_at = CURRENT_TIMESTAMP WHERE user_id = $1 AND read_at IS NULL")
+            .bind(user_id.into_inner())
+            .execute(db.get_ref())
+            .await
+            .map_err(AppError::database("Failed to update notifications"))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(NotificationsRead { marked: result.rows_affected() })))
+}
+
+/// A user's notification settings (the defaults if never set)
+pub async fn get_notification_settings(
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = user_id.into_inner();
+    let settings = sqlx::query_as::<_, NotificationSettings>(&format!(
+        "SELECT {} FROM notification_settings WHERE user_id = $1",
+        SETTINGS_COLUMNS
+    ))
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to fetch notification settings"))?
+    .unwrap_or(NotificationSettings {
+        user_id,
+        due_days_before: config.notification_due_days,
+        webhook_url: None,
+        webhook_secret: None,
+        email: None,
+        updated_at: None,
+    });
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(settings)))
+}
+
+/// Replace a user's notification settings
+///
+/// Setting a webhook URL generates its signing secret, which is kept while
+/// the URL changes and dropped with it.
+pub async fn update_notification_settings(
+    user_id: web::Path<String>,
+    req: web::Json<UpdateNotificationSettingsRequest>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let webhook_url = req.webhook_url.as_deref().map(str::trim).filter(|url| !url.is_empty());
+    let email = req.email.as_deref().map(str::trim).filter(|email| !email.is_empty());
+    validate_settings(req.due_days_before, webhook_url, email).map_err(AppError::Validation)?;
+
+    let secret = webhooks::generate_secret();
+    let settings = sqlx::query_as::<_, NotificationSettings>(&format!(
+        "INSERT INTO notification_settings (user_id, due_days_before, webhook_url, webhook_secret, email, updated_at)
+         VALUES ($1, $2, $3, CASE WHEN $3::text IS NULL THEN NULL ELSE $4 END, $5, CURRENT_TIMESTAMP)
+         ON CONFLICT (user_id) DO UPDATE
+         SET due_days_before = EXCLUDED.due_days_before,
+             webhook_url = EXCLUDED.webhook_url,
+             webhook_secret = CASE WHEN EXCLUDED.webhook_url IS NULL THEN NULL
+                                   ELSE COALESCE(notification_settings.webhook_secret, EXCLUDED.webhook_secret) END,
+             email = EXCLUDED.email,
+             updated_at = EXCLUDED.updated_at
+         RETURNING {}",
+        SETTINGS_COLUMNS
+    ))
+    .bind(user_id.into_inner())
+    .bind(req.due_days_before)
+    .bind(webhook_url)
+    .bind(&secret)
+    .bind(email)
+    .fetch_one(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to save notification settings"))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(settings)))
+}
+
+fn validate_settings(due_days_before: i16, webhook_url: Option<&str>, email: Option<&str>) -> Result<(), String> {
+    if !(0..=MAX_DUE_DAYS).contains(&due_days_before) {
+        return Err(format!("due_days_before must be between 0 and {}", MAX_DUE_DAYS));
+    }
+    if let Some(url) = webhook_url {
+        webhooks::validate_url(url)?;
+    }
+    if let Some(email) = email
+        && (email.len() > 255 || !is_valid_address(email))
+    {
+        return Err("email is not a valid address".to_string());
+    }
+    Ok(())
+}
+
+// ==================== Delivery Channels ====================
+
+/// Where one user's notifications go
+pub struct DeliveryTarget {
+    pub webhook_url: Option<String>,
+    pub webhook_secret: Option<String>,
+    pub email: Option<String>,
+}
+
+/// A way of delivering notifications outside the app
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    /// Whether the user has set this channel up
+    fn applies(&self, target: &DeliveryTarget) -> bool;
+
+    async fn deliver(&self, target: &DeliveryTarget, notification: &Notification) -> Result<(), String>;
+}
+
+/// POSTs the notification as JSON to the user's webhook URL
+pub struct WebhookChannel {
+    client: Client,
+}
+
+impl WebhookChannel {
+    pub fn new() -> Self {
+        let client = Client::builder().timeout(WEBHOOK_TIMEOUT).build().unwrap_or_default();
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for WebhookChannel {
+    fn applies(&self, target: &DeliveryTarget) -> bool {
+        target.webhook_url.is_some()
+    }
+
+    async fn deliver(&self, target: &DeliveryTarget, notification: &Notification) -> Result<(), String> {
+        let (Some(url), Some(secret)) = (&target.webhook_url, &target.webhook_secret) else {
+            return Ok(());
+        };
+        let body = json!({
+            "id": notification.id,
+            "user_id": notification.user_id,
+            "kind": notification.kind,
+            "subject_id": notification.subject_id,
+            "title": notification.title,
+            "body": notification.body,
+            "created_at": notification.created_at,
+        })
+        .to_string();
+
+        let signature = webhooks::signature(secret, &body);
+
+        self.client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header(SIGNATURE_HEADER, signature)
+            .body(body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(|e| format!("webhook: {}", e))
+    }
+}
+
+/// Emails the notification through the configured SMTP server
+pub struct EmailChannel {
+    mailer: Arc<dyn Mailer>,
+}
+
+impl EmailChannel {
+    pub fn new(mailer: Arc<dyn Mailer>) -> Self {
+        Self { mailer }
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for EmailChannel {
+    fn applies(&self, target: &DeliveryTarget) -> bool {
+        target.email.is_some()
+    }
+
+    async fn deliver(&self, target: &DeliveryTarget, notification: &Notification) -> Result<(), String> {
+        let Some(to) = &target.email else {
+            return Ok(());
+        };
+        let email = OutgoingEmail {
+            to: to.clone(),
+            subject: format!("KetoBook: {}", notification.title),
+            body: format!("{}\n\nYou receive this email because of your KetoBook notification settings.", notification.body),
+            attachment: None,
+        };
+        self.mailer.send(email).await.map_err(|e| format!("email: {}", e))
+    }
+}
+
+// ==================== Notification Job ====================
+
+/// A debt coming due or overdue
+#[derive(sqlx::FromRow)]
+struct DueDebt {
+    user_id: String,
+    id: Uuid,
+    creditor_name: String,
+    direction: String,
+    outstanding: BigDecimal,
+    due_date: DateTime<Utc>,
+}
+
+/// A pending notification with where it goes
+#[derive(sqlx::FromRow)]
+struct PendingDelivery {
+    #[sqlx(flatten)]
+    notification: Notification,
+    delivery_attempts: i16,
+    webhook_url: Option<String>,
+    webhook_secret: Option<String>,
+    email: Option<String>,
+}
+
+/// Raise new notifications and deliver pending ones; returns (raised, delivered)
+pub async fn run(
+    pool: &PgPool,
+    channels: &[Box<dyn NotificationChannel>],
+    default_due_days: i16,
+) -> Result<(u64, u64), sqlx::Error> {
+    let raised = raise_notifications(pool, default_due_days, Utc::now()).await?;
+    let delivered = deliver_pending(pool, channels).await?;
+    Ok((raised, delivered))
+}
+
+async fn raise_notifications(pool: &PgPool, default_due_days: i16, now: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+    let mut raised = 0;
+
+    // Debts with something left to pay, due soon (per the user's window) or already overdue
+    let debts = sqlx::query_as::<_, DueDebt>(
+        "SELECT d.user_id, d.id, d.creditor_name, d.direction, d.due_date,
+                d.amount - COALESCE((SELECT SUM(ds.amount) FROM debt_settlements ds WHERE ds.debt_id = d.id), 0)
+                    AS outstanding
+         FROM debts d
+         LEFT JOIN notification_settings s ON s.user_id = d.user_id
+         WHERE d.status = 'active'
+           AND d.due_date IS NOT NULL
+           AND d.due_date <= $1 + make_interval(days => COALESCE(s.due_days_before, $2)::int)",
+    )
+    .bind(now)
+    .bind(default_due_days)
+    .fetch_all(pool)
+    .await?;
+
+    for debt in debts {
+        if debt.outstanding <= BigDecimal::from(0) {
+            continue;
+        }
+        let amount = debt.outstanding.with_scale(2);
+        let due = debt.due_date.format("%Y-%m-%d");
+        let overdue = debt.due_date < now;
+        let (kind, title, body) = match (overdue, debt.direction.as_str()) {
+            (false, "receivable") => (
+                "debt_due",
+                "Debt due soon",
+                format!("{} owes you {}, due on {}", debt.creditor_name, amount, due),
+            ),
+            (false, _) => ("debt_due", "Payment due soon", format!("{} due to {} on {}", amount, debt.creditor_name, due)),
+            (true, "receivable") => (
+                "debt_overdue",
+                "Debt overdue",
+                format!("{} still owes you {}, due on {}", debt.creditor_name, amount, due),
+            ),
+            (true, _) => (
+                "debt_overdue",
+                "Payment overdue",
+                format!("{} to {} was due on {}", amount, debt.creditor_name, due),
+            ),
+        };
+        let key = format!("{}:{}:{}", kind, debt.id, debt.due_date.date_naive());
+        raised += insert(pool, &debt.user_id, kind, debt.id, &key, title, &body).await?;
+    }
+
+    for status in budgets::exceeded_budgets(pool, now).await? {
+        let budget = &status.budget;
+        let key = format!("budget_exceeded:{}:{}", budget.id, status.period_start.date_naive());
+        let body = format!(
+            "{} is over its {} {} budget: {} spent",
+            budget.name,
+            budget.period,
+            budget.amount.with_scale(2),
+            status.spent.with_scale(2)
+        );
+        raised += insert(pool, &budget.user_id, "budget_exceeded", budget.id, &key, "Budget exceeded", &body).await?;
+    }
+
+    Ok(raised)
+}
+
+/// Store a notification unless its key was raised before; returns 1 if stored
+async fn insert(
+    pool: &PgPool,
+    user_id: &str,
+    kind: &str,
+    subject_id: Uuid,
+    key: &str,
+    title: &str,
+    body: &str,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO notifications (user_id, kind, subject_id, notification_key, title, body)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (user_id, notification_key) DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(kind)
+    .bind(subject_id)
+    .bind(key)
+    .bind(title)
+    .bind(body)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Raises `debt_paid` when a debt is marked paid or settled in full
+pub struct NotificationHook;
+
+#[async_trait]
+impl Hook for NotificationHook {
+    fn name(&self) -> &'static str {
+        "notifications"
+    }
+
+    async fn on_debt_paid(&self, cx: &HookContext<'_>, event: &DebtPaid<'_>) {
+        let debt = event.debt;
+        let amount = debt.amount.with_scale(2);
+        let (title, body) = match debt.direction.as_str() {
+            "receivable" => ("Debt repaid", format!("{} repaid you {}", debt.creditor_name, amount)),
+            _ => ("Debt paid off", format!("Your {} debt to {} is paid off", amount, debt.creditor_name)),
+        };
+        let key = format!("debt_paid:{}", debt.id);
+        if let Err(e) = insert(cx.pool, &debt.user_id, "debt_paid", debt.id, &key, title, &body).await {
+            log::error!("Failed to raise debt_paid notification for debt {}: {}", debt.id, e);
+        }
+    }
+}
+
+/// Hand pending notifications to their channels; returns the number delivered
+async fn deliver_pending(pool: &PgPool, channels: &[Box<dyn NotificationChannel>]) -> Result<u64, sqlx::Error> {
+    let pending = sqlx::query_as::<_, PendingDelivery>(
+        "SELECT n.id, n.user_id, n.kind, n.subject_id, n.title, n.body, n.created_at, n.read_at,
+                n.delivery_status, n.delivered_at, n.delivery_attempts, s.webhook_url, s.webhook_secret, s.email
+         FROM notifications n
+         LEFT JOIN notification_settings s ON s.user_id = n.user_id
+         WHERE n.delivery_status = 'pending'
+         ORDER BY n.created_at
+         LIMIT $1",
+    )
+    .bind(DELIVERY_BATCH)
+    .fetch_all(pool)
+    .await?;
+
+    let mut delivered = 0;
+    for delivery in pending {
+        let target = DeliveryTarget {
+            webhook_url: delivery.webhook_url,
+            webhook_secret: delivery.webhook_secret,
+            email: delivery.email,
+        };
+        let notification = &delivery.notification;
+        let applicable: Vec<&dyn NotificationChannel> =
+            channels.iter().map(|c| c.as_ref()).filter(|c| c.applies(&target)).collect();
+        if applicable.is_empty() {
+            sqlx::query("UPDATE notifications SET delivery_status = 'none' WHERE id = $1")
+                .bind(notification.id)
+                .execute(pool)
+                .await?;
+            continue;
+        }
+
+        let mut errors = Vec::new();
+        for channel in applicable {
+            if let Err(e) = channel.deliver(&target, notification).await {
+                errors.push(e);
+            }
+        }
+
+        let attempts = delivery.delivery_attempts + 1;
+        if errors.is_empty() {
+            delivered += 1;
+            sqlx::query(
+                "UPDATE notifications
+                 SET delivery_status = 'sent', delivery_attempts = $2, delivery_error = NULL,
+                     delivered_at = CURRENT_TIMESTAMP
+                 WHERE id = $1",
+            )
+            .bind(notification.id)
+            .bind(attempts)
+            .execute(pool)
+            .await?;
+        } else {
+            let error = errors.join("; ");
+            log::warn!("Failed to deliver notification {} (attempt {}): {}", notification.id, attempts, error);
+            let status = if attempts >= MAX_DELIVERY_ATTEMPTS { "failed" } else { "pending" };
+            sqlx::query(
+                "UPDATE notifications SET delivery_status = $2, delivery_attempts = $3, delivery_error = $4
+                 WHERE id = $1",
+            )
+            .bind(notification.id)
+            .bind(status)
+            .bind(attempts)
+            .bind(error)
+            .execute(pool)
+            .await?;
+        }
+    }
+
+    Ok(delivered)
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/notifications")
+        .user(Method::GET, "/user/{user_id}", get_user_notifications)
+        .user(Method::POST, "/{user_id}/read-all", mark_all_notifications_read)
+        .user(Method::GET, "/{user_id}/settings", get_notification_settings)
+        .user(Method::PUT, "/{user_id}/settings", update_notification_settings)
+        .user(Method::POST, "/{user_id}/{notification_id}/read", mark_notification_read)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
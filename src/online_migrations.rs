@@ -0,0 +1,481 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use sqlx::PgPool;
+
+use crate::admin::authorize_admin;
+use crate::cache::CacheBackend;
+use crate::config::AppConfig;
+use crate::jobs;
+use crate::models::{ApiResponse, AppliedMigration, MigrationStatus, OnlineMigrationStatus};
+
+// ==================== ONLINE MIGRATIONS ====================
+//
+// Schema changes to large tables (transactions above all) are split so no
+// step holds a lock that stalls production traffic:
+//
+// 1. Expand: a regular migration (`sqlx migrate run`) adds the new column as
+//    nullable without a default, a trigger filling it on every write and a
+//    `NOT VALID` check constraint. All of these are metadata-only.
+// 2. Background steps: registered in `ONLINE_MIGRATIONS` below and run in
+//    order by the `online_migrations` job (or `--online-migrate` from the
+//    command line), each step only once the previous one completed:
+//    - `Backfill`: updates pending rows in chunks of `BACKFILL_CHUNK_SIZE`,
+//      one short transaction per chunk, skipping rows locked by requests.
+//      Chunks run with `ketobook.backfill` set, which the updated_at triggers
+//      honour, so a backfill doesn't count as an edit.
+//    - `ConcurrentIndex`: `CREATE INDEX CONCURRENTLY`, outside a
+//      transaction. An invalid index left by an interrupted build is dropped
+//      and rebuilt.
+//    - `ValidateConstraint`: `VALIDATE CONSTRAINT` on a `NOT VALID`
+//      constraint, which scans the table without blocking writes.
+// 3. Contract: once `GET /api/admin/migrations` reports every step
+//    completed, a later migration can drop what the change replaced.
+//
+// Progress is kept in `online_migrations`. A failed step records its error
+// and is retried on the next run. The job takes its advisory lock like any
+// other, so only one instance works on the steps at a time.
+//
+// ============================================================================
+
+/// Job name (advisory lock) of the background steps
+pub const ONLINE_MIGRATION_JOB: &str = "online_migrations";
+
+/// Rows updated per backfill transaction
+const BACKFILL_CHUNK_SIZE: i64 = 1000;
+
+/// Backfill chunks per job run, so a run ends and the next tick picks up
+const BACKFILL_CHUNKS_PER_RUN: usize = 100;
+
+/// Pause between backfill chunks, to leave room for replication and vacuum
+const BACKFILL_PAUSE: Duration = Duration::from_millis(50);
+
+/// How often `--online-migrate` retries while the lock or rows are held elsewhere
+const CLI_LOCK_RETRY: Duration = Duration::from_secs(5);
+
+/// One background step of a schema change
+enum Step {
+    /// `UPDATE {table} SET {set} WHERE {pending}` in chunks; `table` needs an `id` key
+    Backfill {
+        table: &'static str,
+        set: &'static str,
+        pending: &'static str,
+    },
+    /// `CREATE [UNIQUE] INDEX CONCURRENTLY {index} {definition}`
+    ConcurrentIndex {
+        index: &'static str,
+        definition: &'static str,
+        unique: bool,
+    },
+    /// `ALTER TABLE {table} VALIDATE CONSTRAINT {constraint}`
+    ValidateConstraint {
+        table: &'static str,
+        constraint: &'static str,
+    },
+}
+
+impl Step {
+    fn kind(&self) -> &'static str {
+        match self {
+            Step::Backfill { .. } => "backfill",
+            Step::ConcurrentIndex { .. } => "concurrent_index",
+            Step::ValidateConstraint { .. } => "validate_constraint",
+        }
+    }
+}
+
+struct OnlineMigration {
+    name: &'static str,
+    step: Step,
+}
+
+/// Every background step, in run order. Never reorder or rename entries
+/// that have shipped; append new ones.
+const ONLINE_MIGRATIONS: &[OnlineMigration] = &[
+    // transactions.occurred_at (expanded in 20260605001_online_migrations.sql)
+    OnlineMigration {
+        name: "20260605_transactions_occurred_at_backfill",
+        step: Step::Backfill {
+            table: "transactions",
+            set: "occurred_at = created_at",
+            pending: "occurred_at IS NULL",
+        },
+    },
+    OnlineMigration {
+        name: "20260605_transactions_occurred_at_index",
+        step: Step::ConcurrentIndex {
+            index: "idx_transactions_user_occurred_at",
+            definition: "ON transactions (user_id, occurred_at DESC, id)",
+            unique: false,
+        },
+    },
+    OnlineMigration {
+        name: "20260605_transactions_occurred_at_validate",
+        step: Step::ValidateConstraint {
+            table: "transactions",
+            constraint: "occurred_at_not_null",
+        },
+    },
+    // Wallet balances as of a past moment (20260620001_wallet_history.sql)
+    OnlineMigration {
+        name: "20260620_transactions_wallet_created_index",
+        step: Step::ConcurrentIndex {
+            index: "idx_transactions_wallet_created",
+            definition: "ON transactions (wallet_id, created_at)",
+            unique: false,
+        },
+    },
+    // transactions.sequence_number (expanded in 20260805001_transaction_sequence_numbers.sql)
+    OnlineMigration {
+        name: "20260805_transactions_sequence_number_backfill",
+        step: Step::Backfill {
+            table: "transactions",
+            set: "sequence_number = backfill_transaction_sequence(user_id)",
+            pending: "sequence_number IS NULL",
+        },
+    },
+    OnlineMigration {
+        name: "20260805_transactions_sequence_number_index",
+        step: Step::ConcurrentIndex {
+            index: "idx_transactions_user_sequence_number",
+            definition: "ON transactions (user_id, sequence_number)",
+            unique: true,
+        },
+    },
+    OnlineMigration {
+        name: "20260805_transactions_sequence_number_validate",
+        step: Step::ValidateConstraint {
+            table: "transactions",
+            constraint: "sequence_number_not_null",
+        },
+    },
+    // Category renames and merges (20260815001_categories.sql)
+    OnlineMigration {
+        name: "20260815_transactions_lower_category_index",
+        step: Step::ConcurrentIndex {
+            index: "idx_transactions_user_lower_category",
+            definition: "ON transactions (user_id, LOWER(category))",
+            unique: false,
+        },
+    },
+    // Restoring a wallet with its transactions (20260820001_soft_delete.sql)
+    OnlineMigration {
+        name: "20260820_transactions_deleted_index",
+        step: Step::ConcurrentIndex {
+            index: "idx_transactions_wallet_deleted_at",
+            definition: "ON transactions (wallet_id, deleted_at) WHERE deleted_at IS NOT NULL",
+            unique: false,
+        },
+    },
+    // Transaction search (20261015001_transaction_search.sql)
+    OnlineMigration {
+        name: "20261015_transactions_search_document_index",
+        step: Step::ConcurrentIndex {
+            index: "idx_transactions_search_document",
+            definition: "ON transactions USING GIN (transaction_search_document(description, category, notes, amount))",
+            unique: false,
+        },
+    },
+    OnlineMigration {
+        name: "20261015_transactions_search_trgm_index",
+        step: Step::ConcurrentIndex {
+            index: "idx_transactions_search_trgm",
+            definition: "ON transactions USING GIN (transaction_search_text(description, category) gin_trgm_ops)",
+            unique: false,
+        },
+    },
+];
+
+const STATUS_COLUMNS: &str = "name, kind, status, rows_done, attempts, error, started_at, finished_at";
+
+// ==================== Runner ====================
+
+/// One run of the background job; returns how many steps completed
+///
+/// A backfill does at most `BACKFILL_CHUNKS_PER_RUN` chunks per run.
+pub async fn run_scheduled(pool: &PgPool) -> Result<usize, sqlx::Error> {
+    run_pending(pool, Some(BACKFILL_CHUNKS_PER_RUN)).await
+}
+
+/// Run pending steps in order; returns how many completed
+///
+/// A backfill stops after `chunk_limit` chunks (if any) and leaves the rest,
+/// and every later step, to the next run.
+async fn run_pending(pool: &PgPool, chunk_limit: Option<usize>) -> Result<usize, sqlx::Error> {
+    let statuses = fetch_statuses(pool).await?;
+    let mut completed = 0;
+
+    for migration in ONLINE_MIGRATIONS {
+        if statuses.get(migration.name).is_some_and(|s| s.status == "completed") {
+            continue;
+        }
+
+        start(pool, migration).await?;
+        let finished = match &migration.step {
+            Step::Backfill { table, set, pending } => {
+                backfill(pool, migration.name, table, set, pending, chunk_limit).await
+            }
+            Step::ConcurrentIndex { index, definition, unique } => {
+                create_index_concurrently(pool, index, definition, *unique).await.map(|_| true)
+            }
+            Step::ValidateConstraint { table, constraint } => {
+                sqlx::query(&format!("ALTER TABLE {} VALIDATE CONSTRAINT {}", table, constraint))
+                    .execute(pool)
+                    .await
+                    .map(|_| true)
+            }
+        };
+
+        match finished {
+            Ok(true) => {
+                finish(pool, migration.name).await?;
+                log::info!("Online migration '{}' completed", migration.name);
+                completed += 1;
+            }
+            Ok(false) => return Ok(completed),
+            Err(e) => {
+                fail(pool, migration.name, &e.to_string()).await?;
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(completed)
+}
+
+/// Run every step to completion, waiting for the lock if a server holds it
+///
+/// Used by `--online-migrate`. Rows locked by requests during a backfill are
+/// retried until none are left.
+pub async fn run_to_completion(pool: &PgPool) -> Result<usize, sqlx::Error> {
+    let run = async {
+        let mut completed = 0;
+        loop {
+            completed += run_pending(pool, None).await?;
+            let statuses = fetch_statuses(pool).await?;
+            if ONLINE_MIGRATIONS
+                .iter()
+                .all(|m| statuses.get(m.name).is_some_and(|s| s.status == "completed"))
+            {
+                return Ok(completed);
+            }
+            tokio::time::sleep(CLI_LOCK_RETRY).await;
+        }
+    };
+
+    let mut run = std::pin::pin!(run);
+    loop {
+        match jobs::run_singleton(ONLINE_MIGRATION_JOB, pool, run.as_mut()).await {
+            Some(result) => return result,
+            None => {
+                log::info!("Online migrations are running elsewhere; retrying in {:?}", CLI_LOCK_RETRY);
+                tokio::time::sleep(CLI_LOCK_RETRY).await;
+            }
+        }
+    }
+}
+
+/// Update pending rows chunk by chunk; returns whether none are left
+async fn backfill(
+    pool: &PgPool,
+    name: &str,
+    table: &str,
+    set: &str,
+    pending: &str,
+    chunk_limit: Option<usize>,
+) -> Result<bool, sqlx::Error> {
+    let chunk = format!(
+        "UPDATE {table} SET {set}
+         WHERE id IN (SELECT id FROM {table} WHERE {pending} LIMIT $1 FOR UPDATE SKIP LOCKED)",
+        table = table,
+        set = set,
+        pending = pending,
+    );
+
+    let mut chunks = 0;
+    loop {
+        if chunk_limit.is_some_and(|limit| chunks >= limit) {
+            return Ok(false);
+        }
+
+        let mut tx = pool.begin().await?;
+        sqlx::query("SET LOCAL ketobook.backfill = 'on'").execute(&mut *tx).await?;
+        let updated = sqlx::query(&chunk)
+            .bind(BACKFILL_CHUNK_SIZE)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+
+        if updated == 0 {
+            // Rows skipped as locked are picked up by a later run
+            let (left,): (bool,) =
+                sqlx::query_as(&format!("SELECT EXISTS (SELECT 1 FROM {} WHERE {})", table, pending))
+                    .fetch_one(pool)
+                    .await?;
+            return Ok(!left);
+        }
+        progress(pool, name, updated as i64).await?;
+        chunks += 1;
+        tokio::time::sleep(BACKFILL_PAUSE).await;
+    }
+}
+
+/// Build an index without blocking writes
+///
+/// `CONCURRENTLY` can't run inside a transaction, so each statement runs on
+/// its own on the pool.
+async fn create_index_concurrently(
+    pool: &PgPool,
+    index: &str,
+    definition: &str,
+    unique: bool,
+) -> Result<(), sqlx::Error> {
+    let invalid: Option<(bool,)> = sqlx::query_as(
+        "SELECT NOT i.indisvalid FROM pg_index i
+         JOIN pg_class c ON c.oid = i.indexrelid
+         WHERE c.relname = $1 AND pg_table_is_visible(c.oid)",
+    )
+    .bind(index)
+    .fetch_optional(pool)
+    .await?;
+
+    // Left behind by an interrupted build; IF NOT EXISTS would keep it as is
+    if invalid == Some((true,)) {
+        log::warn!("Dropping invalid index {} before rebuilding it", index);
+        sqlx::query(&format!("DROP INDEX CONCURRENTLY IF EXISTS {}", index))
+            .execute(pool)
+            .await?;
+    }
+
+    let kind = if unique { "UNIQUE INDEX" } else { "INDEX" };
+    sqlx::query(&format!("CREATE {} CONCURRENTLY IF NOT EXISTS {} {}", kind, index, definition))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// ==================== Progress ====================
+
+async fn fetch_statuses(pool: &PgPool) -> Result<HashMap<String, OnlineMigrationStatus>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, OnlineMigrationStatus>(&format!("SELECT {} FROM online_migrations", STATUS_COLUMNS))
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(|row| (row.name.clone(), row)).collect())
+}
+
+async fn start(pool: &PgPool, migration: &OnlineMigration) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO online_migrations (name, kind, status, attempts)
+         VALUES ($1, $2, 'running', 1)
+         ON CONFLICT (name) DO UPDATE SET
+             status = 'running',
+             attempts = online_migrations.attempts + 1,
+             updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(migration.name)
+    .bind(migration.step.kind())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn progress(pool: &PgPool, name: &str, rows: i64) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE online_migrations SET rows_done = rows_done + $2, updated_at = CURRENT_TIMESTAMP WHERE name = $1",
+    )
+    .bind(name)
+    .bind(rows)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn finish(pool: &PgPool, name: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE online_migrations
+         SET status = 'completed', error = NULL, finished_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+         WHERE name = $1",
+    )
+    .bind(name)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn fail(pool: &PgPool, name: &str, error: &str) -> Result<(), sqlx::Error> {
+    log::error!("Online migration '{}' failed: {}", name, error);
+    sqlx::query(
+        "UPDATE online_migrations SET status = 'failed', error = $2, updated_at = CURRENT_TIMESTAMP WHERE name = $1",
+    )
+    .bind(name)
+    .bind(error)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// ==================== Handlers ====================
+
+/// Applied migrations and the progress of every background step
+///
+/// `GET /api/admin/migrations`
+pub async fn get_migration_status(
+    req: HttpRequest,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    match migration_status(db.get_ref()).await {
+        Ok(status) => HttpResponse::Ok().json(ApiResponse::success(status)),
+        Err(e) => {
+            log::error!("Error fetching migration status: {}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<MigrationStatus>::error("Database error".to_string()))
+        }
+    }
+}
+
+async fn migration_status(pool: &PgPool) -> Result<MigrationStatus, sqlx::Error> {
+    // Only there once migrations were applied (`sqlx migrate run`, `--migrate-only` or `RUN_MIGRATIONS`)
+    let (tracked,): (bool,) = sqlx::query_as("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    let applied = if tracked {
+        sqlx::query_as::<_, AppliedMigration>(
+            "SELECT version, description, installed_on, success FROM _sqlx_migrations ORDER BY version",
+        )
+        .fetch_all(pool)
+        .await?
+    } else {
+        Vec::new()
+    };
+
+    let mut statuses = fetch_statuses(pool).await?;
+    let online: Vec<OnlineMigrationStatus> = ONLINE_MIGRATIONS
+        .iter()
+        .map(|migration| {
+            statuses.remove(migration.name).unwrap_or_else(|| OnlineMigrationStatus {
+                name: migration.name.to_string(),
+                kind: migration.step.kind().to_string(),
+                status: "pending".to_string(),
+                rows_done: 0,
+                attempts: 0,
+                error: None,
+                started_at: None,
+                finished_at: None,
+            })
+        })
+        .collect();
+    let online_complete = online.iter().all(|s| s.status == "completed");
+
+    Ok(MigrationStatus {
+        applied,
+        online,
+        online_complete,
+    })
+}
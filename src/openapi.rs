@@ -0,0 +1,375 @@
+use std::sync::LazyLock;
+
+use actix_web::http::header::ContentType;
+use actix_web::HttpResponse;
+use schemars::r#gen::{SchemaGenerator, SchemaSettings};
+use serde_json::{json, Map, Value};
+
+use crate::admin::ADMIN_TOKEN_HEADER;
+use crate::api_keys::KEY_HEADER;
+use crate::error::AppError;
+use crate::models::{
+    CreateDebtRequest, CreateTransactionRequest, CreateWalletRequest, CreatedTransaction, Debt, DeletedTransaction,
+    FieldError, Transaction, TransactionPage, UpdateDebtRequest, UpdateTransactionRequest, UpdateWalletRequest,
+    UpdatedTransaction, Wallet,
+};
+use crate::routes::{self, Ownership, RouteSpec};
+
+// ==================== OPENAPI ====================
+//
+// `GET /api/openapi.json` describes the API as an OpenAPI 3.0 document,
+// generated from the route manifest so it lists exactly the routes that are
+// registered:
+//
+// - Paths, path parameters, tags (the scope's resource), the credentials each
+//   route takes and the API key scope it needs come from its `RouteSpec`.
+// - The routes in `BODIES` reference request and response schemas derived
+//   from the models (`JsonSchema`), so they follow the structs. Other routes
+//   document the `ApiResponse` envelope only; their bodies and query
+//   parameters are in API_REFERENCE.md.
+// - Every route can fail with the error envelope; `422 invalid_fields` lists
+//   the broken fields (see validation.rs).
+//
+// `GET /api/docs/swagger` serves Swagger UI over the document. A test fails
+// if `BODIES` names a route or schema that doesn't exist.
+//
+// ============================================================================
+
+/// Documented bodies of one route: schema names, and the success status
+struct RouteBody {
+    method: &'static str,
+    path: &'static str,
+    request: Option<&'static str>,
+    response: Option<Response>,
+    status: u16,
+}
+
+/// The `data` of a success response
+enum Response {
+    One(&'static str),
+    List(&'static str),
+}
+
+const BODIES: &[RouteBody] = &[
+    RouteBody {
+        method: "GET",
+        path: "/api/wallets/user/{user_id}",
+        request: None,
+        response: Some(Response::List("Wallet")),
+        status: 200,
+    },
+    RouteBody {
+        method: "GET",
+        path: "/api/wallets/{user_id}/{wallet_id}",
+        request: None,
+        response: Some(Response::One("Wallet")),
+        status: 200,
+    },
+    RouteBody {
+        method: "POST",
+        path: "/api/wallets",
+        request: Some("CreateWalletRequest"),
+        response: Some(Response::One("Wallet")),
+        status: 201,
+    },
+    RouteBody {
+        method: "PUT",
+        path: "/api/wallets/{user_id}/{wallet_id}",
+        request: Some("UpdateWalletRequest"),
+        response: Some(Response::One("Wallet")),
+        status: 200,
+    },
+    RouteBody {
+        method: "GET",
+        path: "/api/transactions/user/{user_id}",
+        request: None,
+        response: Some(Response::One("TransactionPage")),
+        status: 200,
+    },
+    RouteBody {
+        method: "GET",
+        path: "/api/transactions/{user_id}/{transaction_id}",
+        request: None,
+        response: Some(Response::One("Transaction")),
+        status: 200,
+    },
+    RouteBody {
+        method: "POST",
+        path: "/api/transactions",
+        request: Some("CreateTransactionRequest"),
+        response: Some(Response::One("CreatedTransaction")),
+        status: 201,
+    },
+    RouteBody {
+        method: "PUT",
+        path: "/api/transactions/{user_id}/{transaction_id}",
+        request: Some("UpdateTransactionRequest"),
+        response: Some(Response::One("UpdatedTransaction")),
+        status: 200,
+    },
+    RouteBody {
+        method: "DELETE",
+        path: "/api/transactions/{user_id}/{transaction_id}",
+        request: None,
+        response: Some(Response::One("DeletedTransaction")),
+        status: 200,
+    },
+    RouteBody {
+        method: "GET",
+        path: "/api/debts/user/{user_id}",
+        request: None,
+        response: Some(Response::List("Debt")),
+        status: 200,
+    },
+    RouteBody {
+        method: "POST",
+        path: "/api/debts",
+        request: Some("CreateDebtRequest"),
+        response: Some(Response::One("Debt")),
+        status: 201,
+    },
+    RouteBody {
+        method: "PUT",
+        path: "/api/debts/{user_id}/{debt_id}",
+        request: Some("UpdateDebtRequest"),
+        response: Some(Response::One("Debt")),
+        status: 200,
+    },
+];
+
+/// Schemas of the documented models, keyed by name, with what they reference
+fn model_schemas() -> Map<String, Value> {
+    let mut generator = SchemaGenerator::new(SchemaSettings::openapi3());
+    generator.subschema_for::<Wallet>();
+    generator.subschema_for::<CreateWalletRequest>();
+    generator.subschema_for::<UpdateWalletRequest>();
+    generator.subschema_for::<Transaction>();
+    generator.subschema_for::<TransactionPage>();
+    generator.subschema_for::<CreatedTransaction>();
+    generator.subschema_for::<CreateTransactionRequest>();
+    generator.subschema_for::<UpdateTransactionRequest>();
+    generator.subschema_for::<UpdatedTransaction>();
+    generator.subschema_for::<DeletedTransaction>();
+    generator.subschema_for::<Debt>();
+    generator.subschema_for::<CreateDebtRequest>();
+    generator.subschema_for::<UpdateDebtRequest>();
+    generator.subschema_for::<FieldError>();
+    generator
+        .take_definitions()
+        .into_iter()
+        .map(|(name, schema)| (name, serde_json::to_value(schema).unwrap_or(Value::Null)))
+        .collect()
+}
+
+// ==================== Document ====================
+
+static DOCUMENT: LazyLock<Value> = LazyLock::new(|| document(&routes::manifest()));
+
+/// The OpenAPI document of `specs`
+fn document(specs: &[RouteSpec]) -> Value {
+    let mut paths = Map::new();
+    for spec in specs {
+        let path = paths.entry(spec.path.clone()).or_insert_with(|| json!({}));
+        path[spec.method.to_lowercase()] = operation(spec);
+    }
+
+    let mut schemas = model_schemas();
+    let codes: Vec<&str> = AppError::catalog().iter().map(|info| info.code).collect();
+    schemas.insert(
+        "ErrorResponse".to_string(),
+        json!({
+            "type": "object",
+            "required": ["success", "error", "code"],
+            "properties": {
+                "success": {"type": "boolean", "enum": [false]},
+                "data": {"nullable": true},
+                "error": {"type": "string", "description": "For people; may change"},
+                "code": {"type": "string", "enum": codes, "description": "See GET /api/docs/errors"},
+                "fields": {
+                    "type": "array",
+                    "items": {"$ref": "#/components/schemas/FieldError"},
+                    "description": "invalid_fields only"
+                }
+            }
+        }),
+    );
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "KetoBook API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Personal finance API: wallets, transactions, debts and the rest. Errors carry a machine-readable `code`."
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas,
+            "securitySchemes": {
+                "bearerAuth": {"type": "http", "scheme": "bearer", "bearerFormat": "JWT"},
+                "apiKey": {"type": "apiKey", "in": "header", "name": KEY_HEADER},
+                "adminToken": {"type": "apiKey", "in": "header", "name": ADMIN_TOKEN_HEADER}
+            }
+        }
+    })
+}
+
+/// One operation of the document
+fn operation(spec: &RouteSpec) -> Value {
+    let body = BODIES
+        .iter()
+        .find(|body| body.method == spec.method && body.path == spec.path);
+
+    let parameters: Vec<Value> = path_parameters(&spec.path)
+        .into_iter()
+        .map(|name| json!({"name": name, "in": "path", "required": true, "schema": {"type": "string"}}))
+        .collect();
+
+    let security = match spec.ownership {
+        Ownership::PathUser | Ownership::BodyUser => json!([{"bearerAuth": []}, {"apiKey": []}]),
+        Ownership::Session => json!([{"bearerAuth": []}]),
+        Ownership::Admin => json!([{"adminToken": []}]),
+        Ownership::Credentials | Ownership::Public => json!([]),
+    };
+
+    let mut notes = Vec::new();
+    if let Some(scope) = &spec.scope {
+        notes.push(format!("API key scope: `{}`.", scope));
+    }
+    if spec.dry_run {
+        notes.push("Accepts `?dry_run=true`.".to_string());
+    }
+    if spec.signed {
+        notes.push("Signed by the provider (see Provider Webhooks).".to_string());
+    }
+
+    let data = match body.and_then(|body| body.response.as_ref()) {
+        Some(Response::One(schema)) => json!({"$ref": format!("#/components/schemas/{}", schema)}),
+        Some(Response::List(schema)) => {
+            json!({"type": "array", "items": {"$ref": format!("#/components/schemas/{}", schema)}})
+        }
+        None => json!({}),
+    };
+    let status = body.map_or("2XX".to_string(), |body| body.status.to_string());
+    let mut responses = Map::new();
+    responses.insert(
+        status,
+        json!({
+            "description": "Success",
+            "content": {"application/json": {"schema": {
+                "type": "object",
+                "properties": {"success": {"type": "boolean", "enum": [true]}, "data": data}
+            }}}
+        }),
+    );
+    responses.insert(
+        "default".to_string(),
+        json!({
+            "description": "Error",
+            "content": {"application/json": {"schema": {"$ref": "#/components/schemas/ErrorResponse"}}}
+        }),
+    );
+
+    let mut operation = json!({
+        "operationId": operation_id(spec),
+        "tags": [tag(&spec.path)],
+        "parameters": parameters,
+        "security": security,
+        "responses": responses,
+    });
+    if !notes.is_empty() {
+        operation["description"] = json!(notes.join(" "));
+    }
+    if let Some(request) = body.and_then(|body| body.request) {
+        operation["requestBody"] = json!({
+            "required": true,
+            "content": {"application/json": {"schema": {"$ref": format!("#/components/schemas/{}", request)}}}
+        });
+    }
+    operation
+}
+
+/// Names of the `{...}` segments of `path`
+fn path_parameters(path: &str) -> Vec<&str> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .collect()
+}
+
+/// The resource a route belongs to: its first segment after `/api/`
+fn tag(path: &str) -> &str {
+    path.strip_prefix("/api/")
+        .and_then(|rest| rest.split('/').next())
+        .unwrap_or("service")
+}
+
+/// `get_api_wallets_user_id_wallet_id` for `GET /api/wallets/{user_id}/{wallet_id}`
+fn operation_id(spec: &RouteSpec) -> String {
+    let path: String = spec
+        .path
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    let words: Vec<&str> = path.split('_').filter(|word| !word.is_empty()).collect();
+    format!("{}_{}", spec.method.to_lowercase(), words.join("_"))
+}
+
+// ==================== Handlers ====================
+
+/// `GET /api/openapi.json` - the OpenAPI document
+pub async fn get_openapi() -> HttpResponse {
+    HttpResponse::Ok().json(&*DOCUMENT)
+}
+
+/// `GET /api/docs/swagger` - Swagger UI over the document
+pub async fn get_swagger_ui() -> HttpResponse {
+    HttpResponse::Ok().content_type(ContentType::html()).body(SWAGGER_UI)
+}
+
+const SWAGGER_UI: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>KetoBook API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `BODIES` entries whose route or schemas don't exist, as `METHOD path: problem`
+    fn stale_bodies(specs: &[RouteSpec]) -> Vec<String> {
+        let schemas = model_schemas();
+        let mut stale = Vec::new();
+        for body in BODIES {
+            let name = format!("{} {}", body.method, body.path);
+            if !specs.iter().any(|s| s.method == body.method && s.path == body.path) {
+                stale.push(format!("{}: no such route", name));
+            }
+            let response = body.response.as_ref().map(|response| match response {
+                Response::One(schema) | Response::List(schema) => *schema,
+            });
+            for schema in body.request.into_iter().chain(response) {
+                if !schemas.contains_key(schema) {
+                    stale.push(format!("{}: unknown schema '{}'", name, schema));
+                }
+            }
+        }
+        stale
+    }
+
+    #[test]
+    fn documented_bodies_name_existing_routes_and_schemas() {
+        let stale = stale_bodies(&routes::manifest());
+        assert!(stale.is_empty(), "Stale: {}", stale.join(", "));
+    }
+}
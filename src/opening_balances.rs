@@ -0,0 +1,205 @@
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse};
+use chrono::{NaiveTime, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::audit::{self, AuditContext};
+use crate::balance_history;
+use crate::buckets;
+use crate::cache::CacheBackend;
+use crate::dry_run::{self, DryRun};
+use crate::error::AppError;
+use crate::explain::DELTA;
+use crate::freezes;
+use crate::models::{ApiResponse, OpeningBalanceChange, SetOpeningBalanceRequest, Wallet, WalletOpeningBalance};
+use crate::validation::{self, Fields, Validate};
+use crate::wallet_members::{self, Permission};
+
+// ==================== OPENING BALANCES ====================
+//
+// A user who starts tracking a wallet mid-year knows its balance on some past
+// day, not the opening balance it was created with. `POST
+// /api/wallets/{user_id}/{wallet_id}/opening-balance` takes that balance and
+// day (`as_of`, UTC) and makes the ledger agree with it, instead of editing
+// the current balance (which only counts from the moment of the edit, see
+// history.rs):
+//
+// - The adjustment is the given balance minus the balance the wallet had at
+//   the start of `as_of`: the opening balance then in force plus every
+//   transaction recorded before that day.
+// - The adjustment is added to the opening balance of the wallet and of
+//   every version in `wallet_history`, so every past balance moves by it and
+//   direct edits made since keep their effect. The current balance is
+//   recomputed from the ledger.
+// - A wallet created after `as_of` counts as created at its start, so as-of
+//   reads and the balance history include it from then on. Its balance
+//   snapshots before `as_of` move by the adjustment and every day since is
+//   retaken (see balance_history.rs).
+//
+// Each setting is kept in `wallet_opening_balances`; the latest one is in
+// force. Setting it again computes a new adjustment against the ledger as it
+// is then, so re-posting the same balance adjusts by zero. A frozen wallet's
+// opening balance can't be set. Balance replays taken before the change keep
+// their balance, so explaining a balance from one reports it as inconsistent
+// (see explain.rs).
+//
+// ============================================================================
+
+/// Number of settings returned by the history endpoint
+const HISTORY_LIMIT: i64 = 100;
+
+const OPENING_BALANCE_COLUMNS: &str = "id, wallet_id, user_id, set_by, as_of, balance, adjustment, created_at";
+
+// ==================== Handlers ====================
+
+/// Set a wallet's balance at the start of a past day, recomputing its
+/// history and current balance
+pub async fn set_opening_balance(
+    path: web::Path<(String, String)>,
+    req: web::Json<SetOpeningBalanceRequest>,
+    dry_run: DryRun,
+    context: AuditContext,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, wallet_id) = path.into_inner();
+    validation::check(&req.0)?;
+    let access = wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::Manage).await?;
+    let starts_at = req.as_of.and_time(NaiveTime::MIN).and_utc();
+
+    let mut db_tx = db.begin().await.map_err(AppError::database("Failed to set opening balance"))?;
+    audit::attach(&mut db_tx, &context).await?;
+
+    // Lock the wallet so no transaction moves its balance while it is recomputed
+    let locked: Option<(Uuid,)> =
+        sqlx::query_as("SELECT id FROM wallets WHERE id = $1 AND deleted_at IS NULL FOR UPDATE")
+            .bind(access.wallet_id)
+            .fetch_optional(&mut *db_tx)
+            .await
+            .map_err(AppError::database("Failed to set opening balance"))?;
+    if locked.is_none() {
+        return Err(AppError::NotFound("Wallet not found".to_string()));
+    }
+
+    let (balance_then,): (BigDecimal,) = sqlx::query_as(&format!(
+        "SELECT COALESCE((SELECT wh.opening_balance FROM wallet_history wh
+                          WHERE wh.wallet_id = w.id AND wh.changed_at > $2
+                          ORDER BY wh.changed_at
+                          LIMIT 1), w.opening_balance)
+                + COALESCE((SELECT SUM({}) FROM transactions t
+                            WHERE t.wallet_id = w.id AND t.deleted_at IS NULL AND t.created_at < $2), 0)
+         FROM wallets w
+         WHERE w.id = $1",
+        DELTA
+    ))
+    .bind(access.wallet_id)
+    .bind(starts_at)
+    .fetch_one(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to set opening balance"))?;
+    let adjustment = &req.balance - balance_then;
+
+    let wallet = sqlx::query_as::<_, Wallet>(&format!(
+        "UPDATE wallets
+         SET opening_balance = opening_balance + $2,
+             balance = opening_balance + $2 + COALESCE((SELECT SUM({}) FROM transactions t
+                                                        WHERE t.wallet_id = wallets.id AND t.deleted_at IS NULL), 0),
+             created_at = LEAST(created_at, $3)
+         WHERE id = $1
+         RETURNING id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, created_at, updated_at, version",
+        DELTA
+    ))
+    .bind(access.wallet_id)
+    .bind(&adjustment)
+    .bind(starts_at)
+    .fetch_one(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to set opening balance"))?;
+
+    // Every earlier version moves too, including the one the history trigger
+    // just recorded, which now matches the wallet row
+    sqlx::query("UPDATE wallet_history SET opening_balance = opening_balance + $2 WHERE wallet_id = $1")
+        .bind(wallet.id)
+        .bind(&adjustment)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(AppError::database("Failed to set opening balance"))?;
+
+    freezes::ensure_not_frozen(&mut db_tx, wallet.id).await?;
+    // A lower balance must still cover what is allocated to buckets
+    buckets::ensure_covered(&mut db_tx, wallet.id).await?;
+
+    sqlx::query("UPDATE wallet_balance_snapshots SET balance = balance + $2 WHERE wallet_id = $1 AND snapshot_date < $3")
+        .bind(wallet.id)
+        .bind(&adjustment)
+        .bind(req.as_of)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(AppError::database("Failed to set opening balance"))?;
+    balance_history::retake_snapshots(&mut db_tx, wallet.id, req.as_of)
+        .await
+        .map_err(AppError::database("Failed to set opening balance"))?;
+
+    let opening_balance = sqlx::query_as::<_, WalletOpeningBalance>(&format!(
+        "INSERT INTO wallet_opening_balances (wallet_id, user_id, set_by, as_of, balance, adjustment)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING {}",
+        OPENING_BALANCE_COLUMNS
+    ))
+    .bind(wallet.id)
+    .bind(&wallet.user_id)
+    .bind(&user_id)
+    .bind(req.as_of)
+    .bind(&req.balance)
+    .bind(&adjustment)
+    .fetch_one(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to set opening balance"))?;
+
+    let change = OpeningBalanceChange { opening_balance, wallet };
+    if dry_run.0 {
+        let wallet_ids = [change.wallet.id];
+        return dry_run::preview(db.get_ref(), db_tx, &user_id, StatusCode::OK, Some(change), &wallet_ids).await;
+    }
+    db_tx.commit().await.map_err(AppError::database("Failed to set opening balance"))?;
+
+    log::info!(
+        target: "audit",
+        "opening balance set wallet_id={} user_id={} as_of={} balance={} adjustment={}",
+        change.wallet.id, user_id, req.as_of, req.balance, change.opening_balance.adjustment
+    );
+
+    // Invalidate every member's cache namespace
+    wallet_members::invalidate_members(db.get_ref(), cache.get_ref(), &user_id, &[access.wallet_id]).await;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(change)))
+}
+
+/// Opening balances set on a wallet, newest (in force) first
+pub async fn get_opening_balances(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, wallet_id) = path.into_inner();
+    let access = wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::View).await?;
+
+    let settings = sqlx::query_as::<_, WalletOpeningBalance>(&format!(
+        "SELECT {} FROM wallet_opening_balances WHERE wallet_id = $1 ORDER BY created_at DESC LIMIT $2",
+        OPENING_BALANCE_COLUMNS
+    ))
+    .bind(access.wallet_id)
+    .bind(HISTORY_LIMIT)
+    .fetch_all(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to fetch opening balances"))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(settings)))
+}
+
+impl Validate for SetOpeningBalanceRequest {
+    fn validate(&self, fields: &mut Fields) {
+        fields.require("as_of", self.as_of <= Utc::now().date_naive(), "can't be in the future");
+    }
+}
@@ -0,0 +1,224 @@
+use actix_web::{web, HttpResponse};
+use qrcode::render::svg;
+use qrcode::{EcLevel, QrCode};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+
+use crate::currencies;
+use crate::debts;
+use crate::error::AppError;
+use crate::models::{ApiResponse, DebtPaymentQr, PaymentQrQuery, QrFormat};
+use crate::splits;
+
+// ==================== PAYMENT QR CODES ====================
+//
+// `GET /api/debts/{user_id}/{debt_id}/payment-qr` turns a payable debt into a
+// VietQR code: scanned in a Vietnamese banking app, it fills in a NAPAS 247
+// transfer to the creditor's account (`creditor_bank_bin`,
+// `creditor_account_number` on the debt) with the amount and message.
+//
+// The payload is an EMVCo merchant-presented QR (TLV fields: two-digit id,
+// two-digit length, value) with the NAPAS account-transfer service, ending in
+// a CRC-16/CCITT-FALSE checksum. Codes are dynamic: they carry the amount,
+// which is in whole dong. Debts are in the user's base currency; any other
+// currency is converted at today's rate (409 without one).
+//
+// Nothing is recorded: the user records the payment (`POST .../payments`)
+// once the transfer went through.
+//
+// ============================================================================
+
+/// VietQR's EMVCo application id (NAPAS)
+const NAPAS_GUID: &str = "A000000727";
+
+/// Service code of a transfer to an account number
+const ACCOUNT_TRANSFER_SERVICE: &str = "QRIBFTTA";
+
+/// ISO 4217 numeric code of VND
+const VND_NUMERIC: &str = "704";
+
+/// Longest transfer message banks accept in the code
+const MAX_MESSAGE_CHARS: usize = 25;
+
+/// Smallest side of the SVG image, in pixels
+const QR_MIN_SIZE: u32 = 256;
+
+// ==================== Handlers ====================
+
+/// `GET /{user_id}/{debt_id}/payment-qr` - a VietQR code paying a payable debt
+///
+/// Pays the outstanding amount, or `?amount=` of it. `?format=svg` returns
+/// only the image.
+pub async fn get_debt_payment_qr(
+    path: web::Path<(String, String)>,
+    query: web::Query<PaymentQrQuery>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, debt_id) = path.into_inner();
+    let debt = debts::fetch_debt_by_id(db.get_ref(), &debt_id, &user_id)
+        .await
+        .map_err(AppError::or_not_found("Debt not found"))?;
+
+    if debt.direction != "payable" {
+        return Err(AppError::Validation("Only payable debts can be paid by QR code".to_string()));
+    }
+    if debt.status != "active" {
+        return Err(AppError::Conflict(format!("Debt is {}", debt.status)));
+    }
+    let (Some(bank_bin), Some(account_number)) =
+        (debt.creditor_bank_bin.clone(), debt.creditor_account_number.clone())
+    else {
+        return Err(AppError::Validation(
+            "Add the creditor's creditor_bank_bin and creditor_account_number to the debt first".to_string(),
+        ));
+    };
+
+    let settled: BigDecimal = splits::fetch_settlements(db.get_ref(), debt.id)
+        .await
+        .map_err(AppError::database("Failed to fetch debt payments"))?
+        .iter()
+        .map(|s| &s.amount)
+        .sum();
+    let outstanding = &debt.amount - settled;
+    if outstanding <= BigDecimal::from(0) {
+        return Err(AppError::Conflict("Nothing is outstanding on this debt".to_string()));
+    }
+    let amount = match &query.amount {
+        Some(amount) if *amount <= BigDecimal::from(0) => {
+            return Err(AppError::Validation("amount must be greater than 0".to_string()));
+        }
+        Some(amount) if *amount > outstanding => {
+            return Err(AppError::Validation(format!("amount exceeds the outstanding {}", outstanding)));
+        }
+        Some(amount) => amount.clone(),
+        None => outstanding,
+    };
+
+    // Debts are in the base currency; VietQR only carries dong
+    let (currency,): (String,) = sqlx::query_as("SELECT base_currency($1)")
+        .bind(&user_id)
+        .fetch_one(db.get_ref())
+        .await
+        .map_err(AppError::database("Failed to fetch currency settings"))?;
+    let (amount, exchange_rate) = if currency == "VND" {
+        (amount, None)
+    } else {
+        let conversion = currencies::convert(db.get_ref(), &amount, &currency, "VND")
+            .await
+            .map_err(AppError::database("Failed to convert amount"))?
+            .ok_or_else(|| AppError::Conflict(currencies::no_rate(&currency, "VND")))?;
+        (conversion.amount, Some(conversion.rate))
+    };
+    let amount = amount.round(0).with_scale(0);
+    if amount < BigDecimal::from(1) {
+        return Err(AppError::Validation("amount is less than 1 VND".to_string()));
+    }
+
+    let short_id = debt.id.simple().to_string();
+    let message = sanitize_message(
+        query
+            .message
+            .as_deref()
+            .unwrap_or(&format!("KetoBook {}", &short_id[..8])),
+    );
+    let payload = vietqr_payload(&bank_bin, &account_number, &amount.to_string(), &message);
+    let code = QrCode::with_error_correction_level(&payload, EcLevel::M)
+        .map_err(|e| AppError::Internal(format!("Failed to encode QR code: {}", e)))?;
+    let image = code
+        .render::<svg::Color>()
+        .min_dimensions(QR_MIN_SIZE, QR_MIN_SIZE)
+        .build();
+
+    if query.format == QrFormat::Svg {
+        return Ok(HttpResponse::Ok().content_type("image/svg+xml").body(image));
+    }
+    Ok(HttpResponse::Ok().json(ApiResponse::success(DebtPaymentQr {
+        debt_id: debt.id,
+        creditor_name: debt.creditor_name,
+        bank_bin,
+        account_number,
+        amount,
+        currency: "VND".to_string(),
+        exchange_rate,
+        message,
+        payload,
+        svg: image,
+    })))
+}
+
+// ==================== Payload ====================
+
+/// One EMVCo TLV field; values are ASCII and under 100 bytes
+fn field(id: &str, value: &str) -> String {
+    format!("{}{:02}{}", id, value.len(), value)
+}
+
+/// The EMVCo payload of a dynamic VietQR account transfer
+fn vietqr_payload(bank_bin: &str, account_number: &str, amount: &str, message: &str) -> String {
+    let beneficiary = field("00", bank_bin) + &field("01", account_number);
+    let merchant_account =
+        field("00", NAPAS_GUID) + &field("01", &beneficiary) + &field("02", ACCOUNT_TRANSFER_SERVICE);
+
+    let mut payload = field("00", "01") // Payload format indicator
+        + &field("01", "12") // Dynamic: the code carries the amount
+        + &field("38", &merchant_account)
+        + &field("53", VND_NUMERIC)
+        + &field("54", amount)
+        + &field("58", "VN");
+    if !message.is_empty() {
+        payload += &field("62", &field("08", message));
+    }
+
+    // The checksum covers its own id and length
+    payload += "6304";
+    let crc = crc16_ccitt(payload.as_bytes());
+    payload + &format!("{:04X}", crc)
+}
+
+/// CRC-16/CCITT-FALSE (polynomial 0x1021, initial 0xFFFF), as EMVCo requires
+fn crc16_ccitt(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for byte in data {
+        crc ^= u16::from(*byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// A transfer message banks accept: ASCII letters, digits and spaces, with
+/// Vietnamese letters stripped of their accents
+fn sanitize_message(message: &str) -> String {
+    let ascii: String = message
+        .chars()
+        .map(strip_accent)
+        .filter(|c| c.is_ascii_alphanumeric() || c.is_whitespace())
+        .collect();
+    let words = ascii.split_whitespace().collect::<Vec<_>>().join(" ");
+    words.chars().take(MAX_MESSAGE_CHARS).collect::<String>().trim_end().to_string()
+}
+
+/// The base letter of a Vietnamese accented letter
+fn strip_accent(c: char) -> char {
+    const GROUPS: &[(&str, char)] = &[
+        ("àáảãạăằắẳẵặâầấẩẫậ", 'a'),
+        ("ÀÁẢÃẠĂẰẮẲẴẶÂẦẤẨẪẬ", 'A'),
+        ("èéẻẽẹêềếểễệ", 'e'),
+        ("ÈÉẺẼẸÊỀẾỂỄỆ", 'E'),
+        ("ìíỉĩị", 'i'),
+        ("ÌÍỈĨỊ", 'I'),
+        ("òóỏõọôồốổỗộơờớởỡợ", 'o'),
+        ("ÒÓỎÕỌÔỒỐỔỖỘƠỜỚỞỠỢ", 'O'),
+        ("ùúủũụưừứửữự", 'u'),
+        ("ÙÚỦŨỤƯỪỨỬỮỰ", 'U'),
+        ("ỳýỷỹỵ", 'y'),
+        ("ỲÝỶỸỴ", 'Y'),
+        ("đ", 'd'),
+        ("Đ", 'D'),
+    ];
+    GROUPS
+        .iter()
+        .find(|(letters, _)| letters.contains(c))
+        .map_or(c, |(_, base)| *base)
+}
@@ -0,0 +1,339 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use sqlx::types::BigDecimal;
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::admin::authorize_admin;
+use crate::cache::{get_or_set_cache, CacheBackend, CacheError};
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::models::{ApiResponse, TransactionPolicy, UpdateTransactionPolicyRequest};
+use crate::telemetry;
+
+// ==================== TRANSACTION POLICY ====================
+//
+// The business rules a transaction must meet beyond its shape are set per
+// deployment in the single `transaction_policy` row instead of being
+// hardcoded:
+//
+// - `allow_zero_amount`: accept 0.00 amounts (negative ones never are)
+// - `max_amount`: largest amount of one transaction
+// - `description_required_above`: amounts above it need a non-blank
+//   description
+//
+// Two sanity limits catch fat-finger entries (an extra zero) without
+// forbidding large amounts outright. A transaction past either is refused
+// with 409 until resent with `confirm: true`, and a confirmed one is noted in
+// the audit log:
+//
+// - `outlier_multiplier`: more than N times the wallet's largest transaction
+//   so far (a wallet without history is never flagged)
+// - `max_net_worth_change_percent`: moves net worth, the sum of the user's
+//   wallet balances converted to the wallet's currency, by more than this
+//   percent (not checked while net worth is zero or negative)
+//
+// Operators edit it with `PUT /api/admin/transaction-policy`. Creates, edits
+// and batch validation read it through `transaction_policy`, cached in Redis
+// under one key that the update clears, so every instance picks up a change
+// on its next request. Existing transactions are not re-checked; an edit is
+// checked only when it changes the amount or the description.
+//
+// ============================================================================
+
+const POLICY_CACHE_KEY: &str = "policy:transactions";
+
+const POLICY_COLUMNS: &str = "allow_zero_amount, max_amount, description_required_above, outlier_multiplier, max_net_worth_change_percent, updated_at";
+
+/// A rule a transaction breaks, and the field to report it on
+#[derive(Debug)]
+pub struct PolicyViolation {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl PolicyViolation {
+    pub fn to_response<T: serde::Serialize>(&self) -> HttpResponse {
+        HttpResponse::BadRequest().json(ApiResponse::<T>::error(self.message.clone()))
+    }
+}
+
+// ==================== Policy Service ====================
+
+/// The current policy, from the cache when possible
+pub async fn transaction_policy(pool: &PgPool, cache: &dyn CacheBackend) -> Result<TransactionPolicy, sqlx::Error> {
+    match get_or_set_cache(cache, POLICY_CACHE_KEY, fetch_policy(pool)).await {
+        Ok(policy) => Ok(policy),
+        Err(CacheError::Database(e)) => Err(e),
+        Err(e) => {
+            log::warn!("Transaction policy cache unavailable: {}", e);
+            fetch_policy(pool).await
+        }
+    }
+}
+
+/// Every rule of `policy` that a transaction of `amount` described as `description` breaks
+pub fn check(policy: &TransactionPolicy, amount: &BigDecimal, description: &str) -> Vec<PolicyViolation> {
+    let zero = BigDecimal::from(0);
+    let mut violations = Vec::new();
+
+    if *amount < zero {
+        violations.push(PolicyViolation {
+            field: "amount",
+            message: "Amount can't be negative".to_string(),
+        });
+    } else if *amount == zero && !policy.allow_zero_amount {
+        violations.push(PolicyViolation {
+            field: "amount",
+            message: "Amount must be greater than 0".to_string(),
+        });
+    } else if let Some(max) = &policy.max_amount
+        && amount > max
+    {
+        violations.push(PolicyViolation {
+            field: "amount",
+            message: format!("Amount exceeds the maximum of {}", max),
+        });
+    }
+
+    if let Some(threshold) = &policy.description_required_above
+        && amount > threshold
+        && description.trim().is_empty()
+    {
+        violations.push(PolicyViolation {
+            field: "description",
+            message: format!("A description is required for amounts over {}", threshold),
+        });
+    }
+
+    violations
+}
+
+/// Why an amount looks like a mistake under `policy`'s sanity limits, if it does
+///
+/// `amount` is compared with the largest transaction of `wallet_id` (other
+/// than `exclude`, the one being edited) and `change` with the user's net
+/// worth. Returns `None` when it passes or no limit is set.
+pub async fn check_sanity(
+    executor: impl PgExecutor<'_>,
+    policy: &TransactionPolicy,
+    user_id: &str,
+    wallet_id: Uuid,
+    exclude: Option<Uuid>,
+    amount: &BigDecimal,
+    change: &BigDecimal,
+) -> Result<Option<String>, sqlx::Error> {
+    if policy.outlier_multiplier.is_none() && policy.max_net_worth_change_percent.is_none() {
+        return Ok(None);
+    }
+
+    let (wallet_max, net_worth): (Option<BigDecimal>, Option<BigDecimal>) = sqlx::query_as(
+        "SELECT (SELECT MAX(amount) FROM transactions WHERE wallet_id = $1 AND deleted_at IS NULL AND ($2::uuid IS NULL OR id <> $2)),
+                (SELECT SUM(convert_amount(w.balance, w.currency, c.currency))
+                 FROM wallets w, (SELECT currency FROM wallets WHERE id = $1) c
+                 WHERE w.user_id = $3 AND w.deleted_at IS NULL)",
+    )
+    .bind(wallet_id)
+    .bind(exclude)
+    .bind(user_id)
+    .fetch_one(executor)
+    .await?;
+
+    let zero = BigDecimal::from(0);
+    if let (Some(multiplier), Some(wallet_max)) = (&policy.outlier_multiplier, wallet_max)
+        && wallet_max > zero
+        && *amount > &wallet_max * multiplier
+    {
+        return Ok(Some(format!(
+            "Amount {} is more than {}x this wallet's largest transaction ({})",
+            amount, multiplier, wallet_max
+        )));
+    }
+    if let (Some(percent), Some(net_worth)) = (&policy.max_net_worth_change_percent, net_worth)
+        && net_worth > zero
+        && change.abs() * BigDecimal::from(100) > &net_worth * percent
+    {
+        return Ok(Some(format!(
+            "Amount {} changes net worth ({}) by more than {}%",
+            change.abs(),
+            net_worth,
+            percent
+        )));
+    }
+    Ok(None)
+}
+
+/// 409 asking the client to resend with `confirm: true`
+pub fn confirmation_required(reason: &str) -> AppError {
+    AppError::Conflict(format!(
+        "{}. Resend with \"confirm\": true if this is intended",
+        reason
+    ))
+}
+
+async fn fetch_policy(pool: &PgPool) -> Result<TransactionPolicy, sqlx::Error> {
+    telemetry::sql_one(
+        "transaction_policy",
+        "select",
+        sqlx::query_as::<_, TransactionPolicy>(&format!("SELECT {} FROM transaction_policy WHERE id", POLICY_COLUMNS))
+            .fetch_one(pool),
+    )
+    .await
+}
+
+// ==================== Handlers ====================
+
+/// `GET /api/admin/transaction-policy`
+pub async fn get_transaction_policy(
+    http_req: HttpRequest,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    match fetch_policy(db.get_ref()).await {
+        Ok(policy) => HttpResponse::Ok().json(ApiResponse::success(policy)),
+        Err(e) => {
+            log::error!("Error fetching transaction policy: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<TransactionPolicy>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// `PUT /api/admin/transaction-policy` - replace every rule
+pub async fn update_transaction_policy(
+    http_req: HttpRequest,
+    req: web::Json<UpdateTransactionPolicyRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    let zero = BigDecimal::from(0);
+    if let Some(max) = &req.max_amount
+        && *max <= zero
+    {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<TransactionPolicy>::error("max_amount must be greater than 0".to_string()));
+    }
+    if let Some(threshold) = &req.description_required_above
+        && *threshold < zero
+    {
+        return HttpResponse::BadRequest().json(ApiResponse::<TransactionPolicy>::error(
+            "description_required_above can't be negative".to_string(),
+        ));
+    }
+
+    if let Some(multiplier) = &req.outlier_multiplier
+        && *multiplier <= BigDecimal::from(1)
+    {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<TransactionPolicy>::error("outlier_multiplier must be greater than 1".to_string()));
+    }
+    if let Some(percent) = &req.max_net_worth_change_percent
+        && *percent <= zero
+    {
+        return HttpResponse::BadRequest().json(ApiResponse::<TransactionPolicy>::error(
+            "max_net_worth_change_percent must be greater than 0".to_string(),
+        ));
+    }
+
+    let result = sqlx::query_as::<_, TransactionPolicy>(&format!(
+        "UPDATE transaction_policy
+         SET allow_zero_amount = $1, max_amount = $2, description_required_above = $3,
+             outlier_multiplier = $4, max_net_worth_change_percent = $5, updated_at = CURRENT_TIMESTAMP
+         WHERE id
+         RETURNING {}",
+        POLICY_COLUMNS
+    ))
+    .bind(req.allow_zero_amount)
+    .bind(&req.max_amount)
+    .bind(&req.description_required_above)
+    .bind(&req.outlier_multiplier)
+    .bind(&req.max_net_worth_change_percent)
+    .fetch_one(db.get_ref())
+    .await;
+
+    let policy = match result {
+        Ok(policy) => policy,
+        Err(e) => {
+            log::error!("Error updating transaction policy: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<TransactionPolicy>::error("Failed to update policy".to_string()));
+        }
+    };
+
+    if let Err(e) = cache.del(POLICY_CACHE_KEY).await {
+        log::warn!("Failed to clear cached transaction policy: {}", e);
+    }
+
+    let limit = |value: &Option<BigDecimal>| value.as_ref().map_or("none".to_string(), |v| v.to_string());
+    log::info!(
+        target: "audit",
+        "Transaction policy updated: allow_zero_amount={}, max_amount={}, description_required_above={}, outlier_multiplier={}, max_net_worth_change_percent={}",
+        policy.allow_zero_amount,
+        limit(&policy.max_amount),
+        limit(&policy.description_required_above),
+        limit(&policy.outlier_multiplier),
+        limit(&policy.max_net_worth_change_percent)
+    );
+    HttpResponse::Ok().json(ApiResponse::success(policy))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::Utc;
+
+    use super::*;
+
+    fn policy() -> TransactionPolicy {
+        TransactionPolicy {
+            allow_zero_amount: false,
+            max_amount: Some(BigDecimal::from(1000)),
+            description_required_above: Some(BigDecimal::from(100)),
+            outlier_multiplier: None,
+            max_net_worth_change_percent: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn fields(amount: &str, description: &str, policy: &TransactionPolicy) -> Vec<&'static str> {
+        check(policy, &BigDecimal::from_str(amount).unwrap(), description)
+            .iter()
+            .map(|v| v.field)
+            .collect()
+    }
+
+    #[test]
+    fn amounts_within_the_policy_pass() {
+        assert!(fields("0.01", "", &policy()).is_empty());
+        assert!(fields("100.00", "", &policy()).is_empty());
+        assert!(fields("1000.00", "Rent", &policy()).is_empty());
+    }
+
+    #[test]
+    fn negative_amounts_never_pass_and_zero_only_when_allowed() {
+        let allow_zero = TransactionPolicy { allow_zero_amount: true, ..policy() };
+        assert_eq!(fields("-1.00", "Refund", &allow_zero), ["amount"]);
+        assert_eq!(fields("0.00", "", &policy()), ["amount"]);
+        assert!(fields("0.00", "", &allow_zero).is_empty());
+    }
+
+    #[test]
+    fn large_amounts_need_a_description_and_stay_under_the_maximum() {
+        assert_eq!(fields("100.01", "  ", &policy()), ["description"]);
+        assert_eq!(fields("1000.01", "Car", &policy()), ["amount"]);
+        assert_eq!(fields("1000.01", "", &policy()), ["amount", "description"]);
+
+        let unlimited = TransactionPolicy { max_amount: None, description_required_above: None, ..policy() };
+        assert!(fields("1000000.00", "", &unlimited).is_empty());
+    }
+}
@@ -0,0 +1,417 @@
+use actix_web::body::BoxBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{AUTHORIZATION, COOKIE};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde_json::{Map, Value};
+use sha2::Sha256;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::admin::authorize_admin;
+use crate::cache::CacheBackend;
+use crate::config::AppConfig;
+use crate::models::{ApiResponse, ProviderDeadLetterQuery, ProviderWebhookDeadLetter, ProviderWebhookReceipt};
+use crate::routes::ScopedRoutes;
+
+// ==================== PROVIDER WEBHOOKS ====================
+//
+// Payment and bank providers post events to
+// `POST /api/provider-webhooks/{provider}` (`stripe`, `plaid`, `momo`). The
+// route is registered with `.signed()` (see routes.rs), so `verify` checks
+// every delivery before the handler sees it:
+//
+// 1. Signature: HMAC-SHA256 under the provider's secret
+//    (`STRIPE_WEBHOOK_SECRET`, ...), compared in constant time. Stripe
+//    deliveries carry Stripe's own `Stripe-Signature: t=...,v1=...` over
+//    `{t}.{body}`. Plaid and MoMo deliveries must come through a relay that
+//    signs them in KetoBook's scheme: `X-Webhook-Signature: sha256=<hex>` over
+//    `{X-Webhook-Id}.{X-Webhook-Timestamp}.{body}`. A provider without a
+//    secret is not accepted at all.
+// 2. Timestamp: the signed time must be within
+//    `PROVIDER_WEBHOOK_TOLERANCE_SECS` (default 300) of now, either way.
+// 3. Replay: the delivery's nonce (`X-Webhook-Id`, or the Stripe signature,
+//    which is new on every attempt) is claimed in the cache for twice the
+//    tolerance, so a captured delivery can't be sent again while its
+//    timestamp still passes. A delivery the handler fails on (5xx) gives its
+//    nonce back for the provider's retry. Without the cache deliveries are
+//    refused with 503, so the provider retries later.
+//
+// Rejected deliveries get 400/401/409 and are kept as they came, headers and
+// body (cut at `MAX_DEAD_LETTER_BODY`), in `provider_webhook_dead_letters`
+// for `DEAD_LETTER_RETENTION_DAYS`; operators list them with
+// `GET /api/admin/provider-webhooks/dead-letters`. Verified deliveries are
+// queued in `provider_webhook_events` for the provider's integration.
+//
+// ============================================================================
+
+/// Nonce header of KetoBook-scheme deliveries
+const ID_HEADER: &str = "X-Webhook-Id";
+
+/// Signing time header of KetoBook-scheme deliveries (Unix seconds)
+const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+
+/// Signature header of KetoBook-scheme deliveries
+const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// Stripe's signature header
+const STRIPE_SIGNATURE_HEADER: &str = "Stripe-Signature";
+
+/// Body bytes kept with a dead letter
+const MAX_DEAD_LETTER_BODY: usize = 64 * 1024;
+
+/// How long dead letters are kept
+const DEAD_LETTER_RETENTION_DAYS: i32 = 30;
+
+/// Dead letters listed, newest first
+const MAX_LISTED_DEAD_LETTERS: i64 = 100;
+
+const DEAD_LETTER_COLUMNS: &str = "id, provider, reason, remote_addr, headers, body, body_truncated, received_at";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// A provider that posts webhooks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Provider {
+    Stripe,
+    Plaid,
+    Momo,
+}
+
+impl Provider {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "stripe" => Some(Provider::Stripe),
+            "plaid" => Some(Provider::Plaid),
+            "momo" => Some(Provider::Momo),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Provider::Stripe => "stripe",
+            Provider::Plaid => "plaid",
+            Provider::Momo => "momo",
+        }
+    }
+
+    fn secret(self, config: &AppConfig) -> Option<&str> {
+        match self {
+            Provider::Stripe => config.stripe_webhook_secret.as_deref(),
+            Provider::Plaid => config.plaid_webhook_secret.as_deref(),
+            Provider::Momo => config.momo_webhook_secret.as_deref(),
+        }
+    }
+}
+
+/// Why a delivery was turned away
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rejection {
+    TooLarge,
+    MissingSignature,
+    BadSignature,
+    StaleTimestamp,
+    Replayed,
+}
+
+impl Rejection {
+    fn reason(self) -> &'static str {
+        match self {
+            Rejection::TooLarge => "too_large",
+            Rejection::MissingSignature => "missing_signature",
+            Rejection::BadSignature => "bad_signature",
+            Rejection::StaleTimestamp => "stale_timestamp",
+            Rejection::Replayed => "replayed",
+        }
+    }
+
+    fn to_response(self) -> HttpResponse {
+        let msg = match self {
+            Rejection::TooLarge => "Payload too large",
+            Rejection::MissingSignature => "Missing or malformed signature headers",
+            Rejection::BadSignature => "Invalid signature",
+            Rejection::StaleTimestamp => "Signature timestamp outside tolerance",
+            Rejection::Replayed => "Delivery already received",
+        };
+        let mut response = match self {
+            Rejection::TooLarge => HttpResponse::PayloadTooLarge(),
+            Rejection::MissingSignature => HttpResponse::BadRequest(),
+            Rejection::BadSignature | Rejection::StaleTimestamp => HttpResponse::Unauthorized(),
+            Rejection::Replayed => HttpResponse::Conflict(),
+        };
+        response.json(ApiResponse::<String>::error(msg.to_string()))
+    }
+}
+
+/// A delivery that passed `verify`, for the handler
+#[derive(Debug, Clone)]
+struct VerifiedDelivery {
+    provider: Provider,
+    nonce: String,
+}
+
+/// The signed parts of a delivery
+struct SignedDelivery {
+    nonce: String,
+    timestamp: i64,
+    /// Bytes the signature covers
+    payload: Vec<u8>,
+    /// Candidate signatures (Stripe may send several while rolling secrets)
+    signatures: Vec<Vec<u8>>,
+}
+
+// ==================== Request Guard ====================
+
+/// Verify the signature, timestamp and nonce of a delivery to a signed route
+///
+/// Other routes pass through untouched.
+pub async fn verify(signed: bool, mut req: ServiceRequest, next: Next<BoxBody>) -> Result<ServiceResponse<BoxBody>, Error> {
+    if !signed {
+        return next.call(req).await;
+    }
+    let (Some(config), Some(pool), Some(cache)) = (
+        req.app_data::<web::Data<AppConfig>>().cloned(),
+        req.app_data::<web::Data<PgPool>>().cloned(),
+        req.app_data::<web::Data<dyn CacheBackend>>().cloned(),
+    ) else {
+        return next.call(req).await;
+    };
+
+    let provider = req.match_info().get("provider").and_then(Provider::parse);
+    let Some((provider, secret)) = provider.and_then(|p| p.secret(&config).map(|secret| (p, secret.to_string())))
+    else {
+        return Ok(req.into_response(
+            HttpResponse::NotFound().json(ApiResponse::<String>::error("Unknown webhook provider".to_string())),
+        ));
+    };
+
+    let body = match req.extract::<web::Bytes>().await {
+        Ok(body) => body,
+        Err(_) => {
+            dead_letter(pool.get_ref(), provider, Rejection::TooLarge, &req, &[]).await;
+            return Ok(req.into_response(Rejection::TooLarge.to_response()));
+        }
+    };
+
+    let checked = match signed_delivery(provider, &req, &body) {
+        None => Err(Rejection::MissingSignature),
+        Some(delivery) if !signature_matches(&secret, &delivery) => Err(Rejection::BadSignature),
+        Some(delivery)
+            if Utc::now().timestamp().abs_diff(delivery.timestamp) > config.provider_webhook_tolerance_secs =>
+        {
+            Err(Rejection::StaleTimestamp)
+        }
+        Some(delivery) => Ok(delivery.nonce),
+    };
+    let nonce = match checked {
+        Ok(nonce) => nonce,
+        Err(rejection) => {
+            dead_letter(pool.get_ref(), provider, rejection, &req, &body).await;
+            return Ok(req.into_response(rejection.to_response()));
+        }
+    };
+
+    let nonce_key = format!("provider_webhook:nonce:{}:{}", provider.as_str(), nonce);
+    let ttl = config.provider_webhook_tolerance_secs.max(1) * 2;
+    match cache.incr_ex(&nonce_key, ttl, false).await {
+        Ok(1) => {}
+        Ok(_) => {
+            dead_letter(pool.get_ref(), provider, Rejection::Replayed, &req, &body).await;
+            return Ok(req.into_response(Rejection::Replayed.to_response()));
+        }
+        Err(e) => {
+            log::warn!("Can't check {} webhook nonce: {}", provider.as_str(), e);
+            return Ok(req.into_response(
+                HttpResponse::ServiceUnavailable()
+                    .json(ApiResponse::<String>::error("Try again later".to_string())),
+            ));
+        }
+    }
+
+    req.set_payload(Payload::from(body));
+    req.extensions_mut().insert(VerifiedDelivery { provider, nonce });
+    let res = next.call(req).await?;
+    if res.status().is_server_error()
+        && let Err(e) = cache.del(&nonce_key).await
+    {
+        log::warn!("Failed to release {} webhook nonce: {}", provider.as_str(), e);
+    }
+    Ok(res)
+}
+
+/// The nonce, time, signed bytes and signatures of a delivery; `None` when
+/// the headers are missing or malformed
+fn signed_delivery(provider: Provider, req: &ServiceRequest, body: &[u8]) -> Option<SignedDelivery> {
+    let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+
+    if provider == Provider::Stripe {
+        let mut timestamp = None;
+        let mut signatures = Vec::new();
+        for part in header(STRIPE_SIGNATURE_HEADER)?.split(',') {
+            match part.trim().split_once('=') {
+                Some(("t", t)) => timestamp = t.parse::<i64>().ok(),
+                Some(("v1", v1)) => signatures.push(v1.to_string()),
+                _ => {}
+            }
+        }
+        let timestamp = timestamp?;
+        let payload = [timestamp.to_string().as_bytes(), b".", body].concat();
+        return Some(SignedDelivery {
+            nonce: signatures.first()?.clone(),
+            timestamp,
+            payload,
+            signatures: signatures.iter().filter_map(|s| hex::decode(s).ok()).collect(),
+        });
+    }
+
+    let nonce = header(ID_HEADER).filter(|id| !id.is_empty() && id.len() <= 200)?.to_string();
+    let timestamp = header(TIMESTAMP_HEADER)?.parse::<i64>().ok()?;
+    let signature = header(SIGNATURE_HEADER)?.strip_prefix("sha256=")?;
+    let payload = [nonce.as_bytes(), b".", timestamp.to_string().as_bytes(), b".", body].concat();
+    Some(SignedDelivery {
+        nonce,
+        timestamp,
+        payload,
+        signatures: hex::decode(signature).ok().into_iter().collect(),
+    })
+}
+
+/// Whether any of the delivery's signatures is the HMAC of its payload (constant time)
+fn signature_matches(secret: &str, delivery: &SignedDelivery) -> bool {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(&delivery.payload);
+    delivery
+        .signatures
+        .iter()
+        .any(|signature| mac.clone().verify_slice(signature).is_ok())
+}
+
+/// Keep a rejected delivery for debugging; failures are only logged
+async fn dead_letter(pool: &PgPool, provider: Provider, rejection: Rejection, req: &ServiceRequest, body: &[u8]) {
+    let remote_addr = req.connection_info().realip_remote_addr().map(str::to_string);
+    log::warn!(
+        "Rejected {} webhook from {}: {}",
+        provider.as_str(),
+        remote_addr.as_deref().unwrap_or("unknown"),
+        rejection.reason()
+    );
+
+    let headers: Map<String, Value> = req
+        .headers()
+        .iter()
+        .filter(|(name, _)| *name != AUTHORIZATION && *name != COOKIE)
+        .map(|(name, value)| (name.to_string(), Value::String(String::from_utf8_lossy(value.as_bytes()).into_owned())))
+        .collect();
+    let truncated = body.len() > MAX_DEAD_LETTER_BODY;
+    let body = String::from_utf8_lossy(&body[..body.len().min(MAX_DEAD_LETTER_BODY)]).into_owned();
+
+    let result = sqlx::query(
+        "WITH purged AS (
+             DELETE FROM provider_webhook_dead_letters WHERE received_at < CURRENT_TIMESTAMP - make_interval(days => $7)
+         )
+         INSERT INTO provider_webhook_dead_letters (provider, reason, remote_addr, headers, body, body_truncated)
+         VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(provider.as_str())
+    .bind(rejection.reason())
+    .bind(&remote_addr)
+    .bind(Value::Object(headers))
+    .bind(&body)
+    .bind(truncated)
+    .bind(DEAD_LETTER_RETENTION_DAYS)
+    .execute(pool)
+    .await;
+    if let Err(e) = result {
+        log::error!("Failed to store rejected {} webhook: {}", provider.as_str(), e);
+    }
+}
+
+// ==================== Handlers ====================
+
+/// Queue a verified delivery for the provider's integration
+///
+/// A nonce already queued (e.g. the cache was flushed) returns the first receipt.
+pub async fn receive_delivery(
+    http_req: HttpRequest,
+    body: web::Bytes,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let Some(delivery) = http_req.extensions().get::<VerifiedDelivery>().cloned() else {
+        log::error!("Webhook delivery reached the handler unverified");
+        return HttpResponse::InternalServerError()
+            .json(ApiResponse::<ProviderWebhookReceipt>::error("Webhook delivery was not verified".to_string()));
+    };
+
+    let result = sqlx::query_as::<_, (Uuid, DateTime<Utc>)>(
+        "INSERT INTO provider_webhook_events (provider, nonce, payload)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (provider, nonce) DO UPDATE SET provider = EXCLUDED.provider
+         RETURNING id, received_at",
+    )
+    .bind(delivery.provider.as_str())
+    .bind(&delivery.nonce)
+    .bind(String::from_utf8_lossy(&body).as_ref())
+    .fetch_one(db.get_ref())
+    .await;
+
+    match result {
+        Ok((id, received_at)) => HttpResponse::Ok().json(ApiResponse::success(ProviderWebhookReceipt {
+            id,
+            provider: delivery.provider.as_str().to_string(),
+            received_at,
+        })),
+        Err(e) => {
+            log::error!("Failed to store {} webhook delivery: {}", delivery.provider.as_str(), e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<ProviderWebhookReceipt>::error("Failed to store webhook delivery".to_string()))
+        }
+    }
+}
+
+/// `GET /api/admin/provider-webhooks/dead-letters` - rejected deliveries, newest first (admin)
+pub async fn list_dead_letters(
+    http_req: HttpRequest,
+    query: web::Query<ProviderDeadLetterQuery>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = authorize_admin(&http_req, &config, cache.get_ref()).await {
+        return response;
+    }
+
+    let result = sqlx::query_as::<_, ProviderWebhookDeadLetter>(&format!(
+        "SELECT {} FROM provider_webhook_dead_letters
+         WHERE ($1::text IS NULL OR provider = $1)
+         ORDER BY received_at DESC, id
+         LIMIT $2",
+        DEAD_LETTER_COLUMNS
+    ))
+    .bind(&query.provider)
+    .bind(MAX_LISTED_DEAD_LETTERS)
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(letters) => HttpResponse::Ok().json(ApiResponse::success(letters)),
+        Err(e) => {
+            log::error!("Error fetching webhook dead letters: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<ProviderWebhookDeadLetter>>::error("Database error".to_string()))
+        }
+    }
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/provider-webhooks")
+        .signed("/{provider}", receive_delivery)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
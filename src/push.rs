@@ -0,0 +1,371 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::config::AppConfig;
+
+// ==================== PUSH NOTIFICATIONS ====================
+//
+// Push delivery goes through the `PushSender` trait so the alert job doesn't
+// depend on a provider. Two implementations ship:
+//
+// - `FcmSender`: Firebase Cloud Messaging HTTP v1. Authenticates as a Google
+//   service account: a signed RS256 assertion is exchanged for an OAuth access
+//   token, which is reused until shortly before it expires.
+// - `ApnsSender`: Apple Push Notification service over HTTP/2 with token
+//   (.p8 key) authentication. The ES256 provider token is reused for 50
+//   minutes; Apple rejects tokens older than an hour.
+//
+// Each sender is only constructed when its credentials are configured; devices
+// of an unconfigured platform are skipped. A token the provider reports as
+// gone is surfaced as `PushError::Unregistered` so the caller can drop it.
+//
+// ============================================================================
+
+const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const FCM_SCOPE: &str = "https://www.googleapis.com/auth/firebase.messaging";
+const JWT_BEARER_GRANT: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+
+const APNS_HOST: &str = "https://api.push.apple.com";
+const APNS_SANDBOX_HOST: &str = "https://api.sandbox.push.apple.com";
+
+/// How long an APNs provider token is reused
+const APNS_TOKEN_LIFETIME: Duration = Duration::from_secs(50 * 60);
+
+/// Refresh an OAuth access token this long before it expires
+const ACCESS_TOKEN_MARGIN_SECS: u64 = 300;
+
+/// A notification for one device
+pub struct PushMessage {
+    pub title: String,
+    pub body: String,
+    /// Key/value payload handed to the app (e.g. which wallet to open)
+    pub data: HashMap<String, String>,
+}
+
+#[derive(Debug)]
+pub enum PushError {
+    /// The token is no longer valid (app uninstalled, token rotated)
+    Unregistered,
+    /// Authentication or delivery failed; may be retried
+    Failed(String),
+}
+
+impl std::fmt::Display for PushError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PushError::Unregistered => write!(f, "Device token is no longer registered"),
+            PushError::Failed(e) => write!(f, "Push delivery failed: {}", e),
+        }
+    }
+}
+
+#[async_trait]
+pub trait PushSender: Send + Sync {
+    async fn send(&self, token: &str, message: &PushMessage) -> Result<(), PushError>;
+}
+
+/// The configured sender for each platform
+#[derive(Default)]
+pub struct PushSenders {
+    pub fcm: Option<Box<dyn PushSender>>,
+    pub apns: Option<Box<dyn PushSender>>,
+}
+
+impl PushSenders {
+    /// Build every sender whose credentials are configured
+    pub fn from_config(config: &AppConfig) -> Result<Self, String> {
+        let fcm = match FcmSettings::from_config(config) {
+            Some(settings) => Some(Box::new(FcmSender::new(settings)?) as Box<dyn PushSender>),
+            None => None,
+        };
+        let apns = match ApnsSettings::from_config(config) {
+            Some(settings) => Some(Box::new(ApnsSender::new(settings)?) as Box<dyn PushSender>),
+            None => None,
+        };
+        Ok(Self { fcm, apns })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fcm.is_none() && self.apns.is_none()
+    }
+
+    /// Sender for a device's `platform` column, if that platform is configured
+    pub fn for_platform(&self, platform: &str) -> Option<&dyn PushSender> {
+        match platform {
+            "fcm" => self.fcm.as_deref(),
+            "apns" => self.apns.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// PEM keys are usually put in `.env` on one line with `\n` escapes
+fn unescape_pem(pem: &str) -> String {
+    pem.replace("\\n", "\n")
+}
+
+/// Read an error response body for the log (bounded)
+async fn error_body(res: reqwest::Response) -> String {
+    let body = res.text().await.unwrap_or_default();
+    body.chars().take(500).collect()
+}
+
+// ==================== FCM Sender ====================
+
+/// Firebase service-account credentials
+pub struct FcmSettings {
+    pub project_id: String,
+    pub client_email: String,
+    pub private_key: String,
+}
+
+impl FcmSettings {
+    /// FCM settings, or `None` if FCM is not configured
+    pub fn from_config(config: &AppConfig) -> Option<Self> {
+        Some(Self {
+            project_id: config.fcm_project_id.clone().filter(|v| !v.is_empty())?,
+            client_email: config.fcm_client_email.clone().filter(|v| !v.is_empty())?,
+            private_key: unescape_pem(config.fcm_private_key.as_deref().filter(|v| !v.is_empty())?),
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct ServiceAccountClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Deserialize)]
+struct AccessTokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+pub struct FcmSender {
+    client: Client,
+    settings: FcmSettings,
+    key: EncodingKey,
+    access_token: Mutex<Option<(String, Instant)>>,
+}
+
+impl FcmSender {
+    pub fn new(settings: FcmSettings) -> Result<Self, String> {
+        let key = EncodingKey::from_rsa_pem(settings.private_key.as_bytes())
+            .map_err(|e| format!("Invalid FCM_PRIVATE_KEY: {}", e))?;
+        Ok(Self { client: Client::new(), settings, key, access_token: Mutex::new(None) })
+    }
+
+    /// Cached OAuth access token, exchanging a fresh assertion when it is about to expire
+    async fn access_token(&self) -> Result<String, PushError> {
+        let mut cached = self.access_token.lock().await;
+        if let Some((token, expires_at)) = cached.as_ref()
+            && *expires_at > Instant::now()
+        {
+            return Ok(token.clone());
+        }
+
+        let now = Utc::now().timestamp();
+        let claims = ServiceAccountClaims {
+            iss: &self.settings.client_email,
+            scope: FCM_SCOPE,
+            aud: GOOGLE_TOKEN_URL,
+            iat: now,
+            exp: now + 3600,
+        };
+        let assertion = jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &self.key)
+            .map_err(|e| PushError::Failed(format!("Failed to sign FCM assertion: {}", e)))?;
+
+        let res = self
+            .client
+            .post(GOOGLE_TOKEN_URL)
+            .form(&[("grant_type", JWT_BEARER_GRANT), ("assertion", assertion.as_str())])
+            .send()
+            .await
+            .map_err(|e| PushError::Failed(e.to_string()))?;
+        if !res.status().is_success() {
+            let status = res.status();
+            return Err(PushError::Failed(format!("FCM token exchange returned {}: {}", status, error_body(res).await)));
+        }
+        let token: AccessTokenResponse = res.json().await.map_err(|e| PushError::Failed(e.to_string()))?;
+
+        let lifetime = Duration::from_secs(token.expires_in.saturating_sub(ACCESS_TOKEN_MARGIN_SECS));
+        *cached = Some((token.access_token.clone(), Instant::now() + lifetime));
+        Ok(token.access_token)
+    }
+}
+
+#[async_trait]
+impl PushSender for FcmSender {
+    async fn send(&self, token: &str, message: &PushMessage) -> Result<(), PushError> {
+        let access_token = self.access_token().await?;
+        let url = format!("https://fcm.googleapis.com/v1/projects/{}/messages:send", self.settings.project_id);
+        let payload = serde_json::json!({
+            "message": {
+                "token": token,
+                "notification": { "title": message.title, "body": message.body },
+                "data": message.data,
+            }
+        });
+
+        let res = self
+            .client
+            .post(url)
+            .bearer_auth(access_token)
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| PushError::Failed(e.to_string()))?;
+
+        match res.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::NOT_FOUND => Err(PushError::Unregistered),
+            StatusCode::UNAUTHORIZED => {
+                // Revoked or rotated service-account key; re-authenticate next time
+                *self.access_token.lock().await = None;
+                Err(PushError::Failed(format!("FCM rejected the access token: {}", error_body(res).await)))
+            }
+            status => {
+                let body = error_body(res).await;
+                if body.contains("UNREGISTERED") {
+                    Err(PushError::Unregistered)
+                } else {
+                    Err(PushError::Failed(format!("FCM returned {}: {}", status, body)))
+                }
+            }
+        }
+    }
+}
+
+// ==================== APNs Sender ====================
+
+/// APNs token-auth credentials (a .p8 key from the Apple developer account)
+pub struct ApnsSettings {
+    pub key_id: String,
+    pub team_id: String,
+    pub private_key: String,
+    /// The app's bundle id
+    pub topic: String,
+    pub sandbox: bool,
+}
+
+impl ApnsSettings {
+    /// APNs settings, or `None` if APNs is not configured
+    pub fn from_config(config: &AppConfig) -> Option<Self> {
+        Some(Self {
+            key_id: config.apns_key_id.clone().filter(|v| !v.is_empty())?,
+            team_id: config.apns_team_id.clone().filter(|v| !v.is_empty())?,
+            private_key: unescape_pem(config.apns_private_key.as_deref().filter(|v| !v.is_empty())?),
+            topic: config.apns_topic.clone().filter(|v| !v.is_empty())?,
+            sandbox: config.apns_sandbox,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct ProviderClaims<'a> {
+    iss: &'a str,
+    iat: i64,
+}
+
+#[derive(Deserialize)]
+struct ApnsErrorResponse {
+    reason: String,
+}
+
+pub struct ApnsSender {
+    client: Client,
+    settings: ApnsSettings,
+    key: EncodingKey,
+    provider_token: Mutex<Option<(String, Instant)>>,
+}
+
+impl ApnsSender {
+    pub fn new(settings: ApnsSettings) -> Result<Self, String> {
+        let key = EncodingKey::from_ec_pem(settings.private_key.as_bytes())
+            .map_err(|e| format!("Invalid APNS_PRIVATE_KEY: {}", e))?;
+        Ok(Self { client: Client::new(), settings, key, provider_token: Mutex::new(None) })
+    }
+
+    /// Cached provider token, re-signed every `APNS_TOKEN_LIFETIME`
+    async fn provider_token(&self) -> Result<String, PushError> {
+        let mut cached = self.provider_token.lock().await;
+        if let Some((token, expires_at)) = cached.as_ref()
+            && *expires_at > Instant::now()
+        {
+            return Ok(token.clone());
+        }
+
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(self.settings.key_id.clone());
+        let claims = ProviderClaims { iss: &self.settings.team_id, iat: Utc::now().timestamp() };
+        let token = jsonwebtoken::encode(&header, &claims, &self.key)
+            .map_err(|e| PushError::Failed(format!("Failed to sign APNs provider token: {}", e)))?;
+
+        *cached = Some((token.clone(), Instant::now() + APNS_TOKEN_LIFETIME));
+        Ok(token)
+    }
+}
+
+#[async_trait]
+impl PushSender for ApnsSender {
+    async fn send(&self, token: &str, message: &PushMessage) -> Result<(), PushError> {
+        let provider_token = self.provider_token().await?;
+        let host = if self.settings.sandbox { APNS_SANDBOX_HOST } else { APNS_HOST };
+        let url = format!("{}/3/device/{}", host, token);
+
+        // Custom keys sit next to `aps` at the top level of the payload
+        let mut payload = serde_json::Map::new();
+        payload.insert(
+            "aps".to_string(),
+            serde_json::json!({
+                "alert": { "title": message.title, "body": message.body },
+                "sound": "default",
+            }),
+        );
+        for (key, value) in &message.data {
+            payload.insert(key.clone(), serde_json::Value::String(value.clone()));
+        }
+
+        let res = self
+            .client
+            .post(url)
+            .bearer_auth(provider_token)
+            .header("apns-topic", &self.settings.topic)
+            .header("apns-push-type", "alert")
+            .header("apns-priority", "10")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| PushError::Failed(e.to_string()))?;
+
+        let status = res.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        if status == StatusCode::GONE {
+            return Err(PushError::Unregistered);
+        }
+
+        let body = error_body(res).await;
+        let reason = serde_json::from_str::<ApnsErrorResponse>(&body).map(|e| e.reason).unwrap_or(body);
+        match reason.as_str() {
+            "BadDeviceToken" | "Unregistered" | "DeviceTokenNotForTopic" => Err(PushError::Unregistered),
+            "ExpiredProviderToken" | "InvalidProviderToken" => {
+                *self.provider_token.lock().await = None;
+                Err(PushError::Failed(format!("APNs rejected the provider token: {}", reason)))
+            }
+            _ => Err(PushError::Failed(format!("APNs returned {}: {}", status, reason))),
+        }
+    }
+}
@@ -0,0 +1,523 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use chrono::{Duration, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::{Connection, PgPool};
+use uuid::Uuid;
+
+use crate::cache::{invalidate_user_cache, CacheBackend};
+use crate::hooks::{self, HookContext, TransactionCreated};
+use crate::jobs;
+use crate::models::{
+    ApiResponse, CreateRecurringTransactionRequest, CreateTransactionRequest, RecurringTransaction,
+    RecurringTransactionRun, StandingOrderFrequency, Transaction, TransactionPolicy, UpdateRecurringTransactionRequest,
+};
+use crate::policy;
+use crate::routes::ScopedRoutes;
+use crate::standing_orders::next_occurrence;
+use crate::transactions;
+
+// ==================== RECURRING TRANSACTIONS ====================
+//
+// Recurring transactions post an income or expense on a schedule, so salary,
+// rent and subscriptions don't have to be entered by hand every month.
+//
+// `RECURRING_JOB` claims each (rule, scheduled time) in `job_executions` and
+// posts the transaction through `transactions::record_transaction` in the
+// same database transaction, so a run is validated and applied exactly like
+// a transaction entered by hand: the policy, the wallet's funds, freezes and
+// reimbursement matching all apply. The run is recorded in
+// `recurring_transaction_runs` as `completed` (with the transaction it
+// posted) or `failed` (insufficient funds, a frozen wallet, ...). A failed
+// run is not retried; the rule simply runs again at its next scheduled time.
+// Runs missed while the service was down are not backfilled.
+//
+// Schedules follow standing orders (see standing_orders.rs): monthly runs
+// keep the start day, clamped to short months.
+//
+// ============================================================================
+
+pub const RECURRING_JOB: &str = "recurring_transactions";
+
+/// Maximum number of recurring transactions per user
+const MAX_RULES_PER_USER: i64 = 50;
+
+/// Number of runs returned by the history endpoint
+const RUN_HISTORY_LIMIT: i64 = 100;
+
+const RULE_COLUMNS: &str = "id, user_id, name, wallet_id, transaction_type, amount, category, description, \
+     frequency, start_at, next_run_at, active, last_run_at, created_at, updated_at";
+
+const RUN_COLUMNS: &str = "id, rule_id, user_id, status, scheduled_for, transaction_id, error, created_at";
+
+// ==================== Handlers ====================
+
+/// List a user's recurring transactions
+pub async fn get_user_recurring_transactions(user_id: web::Path<String>, db: web::Data<PgPool>) -> HttpResponse {
+    let result = sqlx::query_as::<_, RecurringTransaction>(&format!(
+        "SELECT {} FROM recurring_transactions WHERE user_id = $1 ORDER BY created_at",
+        RULE_COLUMNS
+    ))
+    .bind(user_id.into_inner())
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(rules) => HttpResponse::Ok().json(ApiResponse::success(rules)),
+        Err(e) => {
+            log::error!("Error fetching recurring transactions: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<RecurringTransaction>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Create a recurring transaction
+pub async fn create_recurring_transaction(
+    req: web::Json<CreateRecurringTransactionRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let name = req.name.trim();
+    if let Err(msg) = validate_rule(
+        Some(name),
+        Some(&req.transaction_type),
+        Some(&req.amount),
+        Some(&req.category),
+    ) {
+        return HttpResponse::BadRequest().json(ApiResponse::<RecurringTransaction>::error(msg));
+    }
+    if let Err(response) = check_policy(db.get_ref(), cache.get_ref(), &req.amount, &req.description).await {
+        return response;
+    }
+
+    let now = Utc::now();
+    let start_at = req.start_at.unwrap_or(now);
+    if start_at < now - Duration::minutes(1) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<RecurringTransaction>::error("start_at must not be in the past".to_string()));
+    }
+
+    let wallet: Result<Option<(Uuid,)>, sqlx::Error> =
+        sqlx::query_as("SELECT id FROM wallets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL")
+            .bind(req.wallet_id)
+            .bind(&req.user_id)
+            .fetch_optional(db.get_ref())
+            .await;
+    match wallet {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::BadRequest().json(ApiResponse::<RecurringTransaction>::error(
+                "Wallet not found or doesn't belong to user".to_string(),
+            ));
+        }
+        Err(e) => {
+            log::error!("Error fetching wallet: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<RecurringTransaction>::error("Failed to validate wallet".to_string()));
+        }
+    }
+
+    let count: Result<(i64,), sqlx::Error> =
+        sqlx::query_as("SELECT COUNT(*) FROM recurring_transactions WHERE user_id = $1")
+            .bind(&req.user_id)
+            .fetch_one(db.get_ref())
+            .await;
+    match count {
+        Ok((count,)) if count >= MAX_RULES_PER_USER => {
+            return HttpResponse::BadRequest().json(ApiResponse::<RecurringTransaction>::error(format!(
+                "A user can have at most {} recurring transactions",
+                MAX_RULES_PER_USER
+            )));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Error counting recurring transactions: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<RecurringTransaction>::error("Database error".to_string()));
+        }
+    }
+
+    let result = sqlx::query_as::<_, RecurringTransaction>(&format!(
+        "INSERT INTO recurring_transactions
+             (id, user_id, name, wallet_id, transaction_type, amount, category, description, frequency,
+              start_at, next_run_at, active, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $10, TRUE, $11, $11)
+         RETURNING {}",
+        RULE_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(name)
+    .bind(req.wallet_id)
+    .bind(&req.transaction_type)
+    .bind(&req.amount)
+    .bind(req.category.trim())
+    .bind(&req.description)
+    .bind(req.frequency.as_str())
+    .bind(start_at)
+    .bind(now)
+    .fetch_one(db.get_ref())
+    .await;
+
+    match result {
+        Ok(rule) => HttpResponse::Created().json(ApiResponse::success(rule)),
+        Err(e) => {
+            log::error!("Error creating recurring transaction: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<RecurringTransaction>::error("Failed to create recurring transaction".to_string()))
+        }
+    }
+}
+
+/// Change the name, amount, category or description, or pause/resume a recurring transaction
+pub async fn update_recurring_transaction(
+    path: web::Path<(String, String)>,
+    req: web::Json<UpdateRecurringTransactionRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, rule_id) = path.into_inner();
+    let name = req.name.as_deref().map(str::trim);
+    let category = req.category.as_deref().map(str::trim);
+    if let Err(msg) = validate_rule(name, None, req.amount.as_ref(), req.category.as_ref()) {
+        return HttpResponse::BadRequest().json(ApiResponse::<RecurringTransaction>::error(msg));
+    }
+
+    let rule = match fetch_rule(db.get_ref(), &rule_id, &user_id).await {
+        Ok(Some(rule)) => rule,
+        Ok(None) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<RecurringTransaction>::error("Recurring transaction not found".to_string()));
+        }
+        Err(e) => {
+            log::error!("Error fetching recurring transaction: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<RecurringTransaction>::error("Database error".to_string()));
+        }
+    };
+    let Some(frequency) = StandingOrderFrequency::from_str(&rule.frequency) else {
+        log::error!("Recurring transaction {} has an invalid frequency", rule.id);
+        return HttpResponse::InternalServerError()
+            .json(ApiResponse::<RecurringTransaction>::error("Database error".to_string()));
+    };
+
+    if req.amount.is_some() || req.description.is_some() {
+        let amount = req.amount.as_ref().unwrap_or(&rule.amount);
+        let description = req.description.as_deref().unwrap_or(&rule.description);
+        if let Err(response) = check_policy(db.get_ref(), cache.get_ref(), amount, description).await {
+            return response;
+        }
+    }
+
+    // Resuming picks up at the next scheduled time; runs missed while paused are skipped
+    let now = Utc::now();
+    let next_run_at = (req.active == Some(true) && !rule.active && rule.next_run_at <= now)
+        .then(|| next_occurrence(rule.start_at, frequency, now));
+
+    let result = sqlx::query_as::<_, RecurringTransaction>(&format!(
+        "UPDATE recurring_transactions
+         SET name = COALESCE($1, name),
+             amount = COALESCE($2, amount),
+             category = COALESCE($3, category),
+             description = COALESCE($4, description),
+             active = COALESCE($5, active),
+             next_run_at = COALESCE($6, next_run_at),
+             updated_at = $7
+         WHERE id = $8
+         RETURNING {}",
+        RULE_COLUMNS
+    ))
+    .bind(name)
+    .bind(&req.amount)
+    .bind(category)
+    .bind(&req.description)
+    .bind(req.active)
+    .bind(next_run_at)
+    .bind(now)
+    .bind(rule.id)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(rule)) => HttpResponse::Ok().json(ApiResponse::success(rule)),
+        Ok(None) => HttpResponse::NotFound()
+            .json(ApiResponse::<RecurringTransaction>::error("Recurring transaction not found".to_string())),
+        Err(e) => {
+            log::error!("Error updating recurring transaction: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<RecurringTransaction>::error("Failed to update recurring transaction".to_string()))
+        }
+    }
+}
+
+/// Delete a recurring transaction (its run history is removed with it; posted transactions stay)
+pub async fn delete_recurring_transaction(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, rule_id) = path.into_inner();
+
+    let result = sqlx::query("DELETE FROM recurring_transactions WHERE id::text = $1 AND user_id = $2")
+        .bind(&rule_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await;
+
+    match result {
+        Ok(query_result) if query_result.rows_affected() > 0 => HttpResponse::NoContent().finish(),
+        Ok(_) => {
+            HttpResponse::NotFound().json(ApiResponse::<String>::error("Recurring transaction not found".to_string()))
+        }
+        Err(e) => {
+            log::error!("Error deleting recurring transaction: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<String>::error("Failed to delete recurring transaction".to_string()))
+        }
+    }
+}
+
+/// Run history of a recurring transaction (most recent first)
+pub async fn get_recurring_transaction_runs(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let (user_id, rule_id) = path.into_inner();
+
+    let result = sqlx::query_as::<_, RecurringTransactionRun>(&format!(
+        "SELECT {} FROM recurring_transaction_runs
+         WHERE rule_id::text = $1 AND user_id = $2
+         ORDER BY created_at DESC
+         LIMIT $3",
+        RUN_COLUMNS
+    ))
+    .bind(&rule_id)
+    .bind(&user_id)
+    .bind(RUN_HISTORY_LIMIT)
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(runs) => HttpResponse::Ok().json(ApiResponse::success(runs)),
+        Err(e) => {
+            log::error!("Error fetching recurring transaction runs: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<RecurringTransactionRun>>::error("Database error".to_string()))
+        }
+    }
+}
+
+fn validate_rule(
+    name: Option<&str>,
+    transaction_type: Option<&String>,
+    amount: Option<&BigDecimal>,
+    category: Option<&String>,
+) -> Result<(), String> {
+    if let Some(name) = name
+        && (name.is_empty() || name.chars().count() > 100)
+    {
+        return Err("Name must be 1-100 characters".to_string());
+    }
+    if let Some(transaction_type) = transaction_type
+        && transaction_type != "income"
+        && transaction_type != "expense"
+    {
+        return Err("Invalid transaction type. Must be 'income' or 'expense'".to_string());
+    }
+    if let Some(amount) = amount
+        && *amount <= BigDecimal::from(0)
+    {
+        return Err("Amount must be greater than 0".to_string());
+    }
+    if let Some(category) = category
+        && (category.trim().is_empty() || category.chars().count() > 100)
+    {
+        return Err("Category must be 1-100 characters".to_string());
+    }
+    Ok(())
+}
+
+/// A rule whose transactions the policy would refuse could never run
+async fn check_policy(
+    pool: &PgPool,
+    cache: &dyn CacheBackend,
+    amount: &BigDecimal,
+    description: &str,
+) -> Result<(), HttpResponse> {
+    let transaction_policy = match policy::transaction_policy(pool, cache).await {
+        Ok(transaction_policy) => transaction_policy,
+        Err(e) => {
+            log::error!("Error loading transaction policy: {}", e);
+            return Err(HttpResponse::InternalServerError()
+                .json(ApiResponse::<RecurringTransaction>::error("Database error".to_string())));
+        }
+    };
+    match policy::check(&transaction_policy, amount, description).first() {
+        Some(violation) => Err(violation.to_response::<RecurringTransaction>()),
+        None => Ok(()),
+    }
+}
+
+// ==================== Background Job ====================
+
+/// Post every due recurring transaction; returns the number of transactions posted
+pub async fn run_due_rules(pool: &PgPool, cache: &dyn CacheBackend) -> Result<u64, sqlx::Error> {
+    let due: Vec<(Uuid,)> =
+        sqlx::query_as("SELECT id FROM recurring_transactions WHERE active AND next_run_at <= CURRENT_TIMESTAMP")
+            .fetch_all(pool)
+            .await?;
+    if due.is_empty() {
+        return Ok(0);
+    }
+    let transaction_policy = policy::transaction_policy(pool, cache).await?;
+
+    let mut completed = 0;
+    for (rule_id,) in due {
+        match run_rule(pool, &transaction_policy, rule_id).await {
+            Ok(Some((rule, posted))) => {
+                completed += 1;
+                let _ = invalidate_user_cache(cache, &rule.user_id).await;
+                let cx = HookContext { pool, cache };
+                hooks::transaction_created(&cx, TransactionCreated { transaction: &posted }).await;
+            }
+            Ok(None) => {}
+            Err(e) => log::error!("Failed to run recurring transaction {}: {}", rule_id, e),
+        }
+    }
+
+    Ok(completed)
+}
+
+/// Claim, post and record one run of a rule
+///
+/// Returns the rule and the transaction it posted, or `None` when the run
+/// failed or was claimed by a concurrent run.
+async fn run_rule(
+    pool: &PgPool,
+    transaction_policy: &TransactionPolicy,
+    rule_id: Uuid,
+) -> Result<Option<(RecurringTransaction, Transaction)>, sqlx::Error> {
+    let mut db_tx = pool.begin().await?;
+
+    let rule = sqlx::query_as::<_, RecurringTransaction>(&format!(
+        "SELECT {} FROM recurring_transactions
+         WHERE id = $1 AND active AND next_run_at <= CURRENT_TIMESTAMP
+         FOR UPDATE SKIP LOCKED",
+        RULE_COLUMNS
+    ))
+    .bind(rule_id)
+    .fetch_optional(&mut *db_tx)
+    .await?;
+    let Some(rule) = rule else {
+        db_tx.rollback().await?;
+        return Ok(None);
+    };
+    let Some(frequency) = StandingOrderFrequency::from_str(&rule.frequency) else {
+        db_tx.rollback().await?;
+        log::error!("Recurring transaction {} has an invalid frequency", rule.id);
+        return Ok(None);
+    };
+
+    let scheduled_for = rule.next_run_at;
+    let occurrence = format!("{}:{}", rule.id, scheduled_for.to_rfc3339());
+    if !jobs::claim_occurrence(&mut db_tx, RECURRING_JOB, &occurrence).await? {
+        db_tx.rollback().await?;
+        return Ok(None);
+    }
+
+    let now = Utc::now();
+    sqlx::query("UPDATE recurring_transactions SET next_run_at = $1, last_run_at = $2 WHERE id = $3")
+        .bind(next_occurrence(rule.start_at, frequency, now))
+        .bind(now)
+        .bind(rule.id)
+        .execute(&mut *db_tx)
+        .await?;
+
+    // Post in a savepoint, so a refused transaction leaves the claim and the run record
+    let req = CreateTransactionRequest {
+        user_id: rule.user_id.clone(),
+        wallet_id: rule.wallet_id,
+        amount: rule.amount.clone(),
+        transaction_type: rule.transaction_type.clone(),
+        category: rule.category.clone(),
+        description: rule.description.clone(),
+        notes: None,
+        metadata: None,
+        bucket_id: None,
+        income_source_id: None,
+        splits: Vec::new(),
+        // The amount was chosen when the rule was set up
+        confirm: true,
+    };
+    let mut savepoint = db_tx.begin().await?;
+    let posted = transactions::record_transaction(&mut savepoint, pool, transaction_policy, &req)
+        .await
+        .map(|created| created.transaction)
+        .map_err(transactions::rejection_reason);
+    if posted.is_ok() {
+        savepoint.commit().await?;
+    } else {
+        savepoint.rollback().await?;
+    }
+
+    let (status, transaction_id, error) = match &posted {
+        Ok(transaction) => ("completed", Some(transaction.id), None),
+        Err(reason) => ("failed", None, Some(reason.clone())),
+    };
+    sqlx::query(
+        "INSERT INTO recurring_transaction_runs (id, rule_id, user_id, status, scheduled_for, transaction_id, error, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(rule.id)
+    .bind(&rule.user_id)
+    .bind(status)
+    .bind(scheduled_for)
+    .bind(transaction_id)
+    .bind(&error)
+    .bind(now)
+    .execute(&mut *db_tx)
+    .await?;
+
+    db_tx.commit().await?;
+
+    match posted {
+        Ok(transaction) => {
+            log::info!("Recurring transaction {} posted transaction {}", rule.id, transaction.id);
+            Ok(Some((rule, transaction)))
+        }
+        Err(reason) => {
+            log::warn!("Recurring transaction {} failed: {}", rule.id, reason);
+            Ok(None)
+        }
+    }
+}
+
+// ==================== Database Functions ====================
+
+async fn fetch_rule(
+    pool: &PgPool,
+    rule_id: &str,
+    user_id: &str,
+) -> Result<Option<RecurringTransaction>, sqlx::Error> {
+    sqlx::query_as::<_, RecurringTransaction>(&format!(
+        "SELECT {} FROM recurring_transactions WHERE id::text = $1 AND user_id = $2",
+        RULE_COLUMNS
+    ))
+    .bind(rule_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/recurring-transactions")
+        .create("", create_recurring_transaction)
+        .user(Method::GET, "/{user_id}", get_user_recurring_transactions)
+        .user(Method::PUT, "/{user_id}/{rule_id}", update_recurring_transaction)
+        .user(Method::DELETE, "/{user_id}/{rule_id}", delete_recurring_transaction)
+        .user(Method::GET, "/{user_id}/{rule_id}/runs", get_recurring_transaction_runs)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
@@ -0,0 +1,351 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use sqlx::types::BigDecimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::routes::ScopedRoutes;
+use crate::models::{
+    ApiResponse, CreateReimbursementRequest, OutstandingByPayer, Reimbursement, ReimbursementListQuery,
+    ReimbursementStatus, Transaction, UpdateReimbursementStatusRequest,
+};
+
+// ==================== REIMBURSEMENTS ====================
+//
+// A user marks an expense as reimbursable by a payer (employer, client).
+// The reimbursement then moves forward through pending -> submitted ->
+// reimbursed; steps may be skipped but never undone.
+//
+// Auto-matching: when an income transaction is created, the oldest open
+// reimbursement with the same amount whose payer appears in the income's
+// description (or whose income category is "reimbursement") is settled by it,
+// inside the same DB transaction as the income insert.
+//
+// ============================================================================
+
+/// Income category that marks a transaction as a reimbursement payout
+pub const REIMBURSEMENT_CATEGORY: &str = "reimbursement";
+
+const REIMBURSEMENT_COLUMNS: &str = "id, user_id, transaction_id, payer, amount, status, submitted_at, reimbursed_at, settled_by_transaction_id, created_at, updated_at";
+
+// ==================== Handlers ====================
+
+/// List a user's reimbursements, optionally by status
+pub async fn get_user_reimbursements(
+    user_id: web::Path<String>,
+    query: web::Query<ReimbursementListQuery>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+
+    let result = sqlx::query_as::<_, Reimbursement>(&format!(
+        "SELECT {} FROM reimbursements
+         WHERE user_id = $1 AND ($2::text IS NULL OR status = $2)
+         ORDER BY created_at DESC",
+        REIMBURSEMENT_COLUMNS
+    ))
+    .bind(&user_id)
+    .bind(query.status.map(|s| s.as_str()))
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(reimbursements) => HttpResponse::Ok().json(ApiResponse::success(reimbursements)),
+        Err(e) => {
+            log::error!("Error fetching reimbursements: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<Reimbursement>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Outstanding reimbursements grouped by payer
+pub async fn get_outstanding_by_payer(
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+
+    let result = sqlx::query_as::<_, OutstandingByPayer>(
+        "SELECT r.payer, COUNT(*) AS count, SUM(r.amount) AS total, MIN(t.created_at) AS oldest_expense_at
+         FROM reimbursements r
+         JOIN transactions t ON t.id = r.transaction_id
+         WHERE r.user_id = $1 AND r.status <> 'reimbursed' AND t.deleted_at IS NULL
+         GROUP BY r.payer
+         ORDER BY total DESC",
+    )
+    .bind(&user_id)
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(report) => HttpResponse::Ok().json(ApiResponse::success(report)),
+        Err(e) => {
+            log::error!("Error fetching outstanding reimbursements: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<OutstandingByPayer>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Mark an expense as reimbursable
+pub async fn create_reimbursement(
+    req: web::Json<CreateReimbursementRequest>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let payer = req.payer.trim();
+    if payer.is_empty() {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<Reimbursement>::error("Payer is required".to_string()));
+    }
+
+    let expense = match fetch_user_transaction(db.get_ref(), req.transaction_id, &req.user_id).await {
+        Ok(Some(t)) if t.transaction_type == "expense" => t,
+        Ok(Some(_)) => {
+            return HttpResponse::BadRequest()
+                .json(ApiResponse::<Reimbursement>::error("Only expenses can be reimbursable".to_string()));
+        }
+        Ok(None) => {
+            return HttpResponse::BadRequest()
+                .json(ApiResponse::<Reimbursement>::error("Transaction not found".to_string()));
+        }
+        Err(e) => {
+            log::error!("Error fetching transaction: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<Reimbursement>::error("Database error".to_string()));
+        }
+    };
+
+    let amount = req.amount.clone().unwrap_or_else(|| expense.amount.clone());
+    if amount <= BigDecimal::from(0) || amount > expense.amount {
+        return HttpResponse::BadRequest().json(ApiResponse::<Reimbursement>::error(format!(
+            "Amount must be greater than 0 and at most the expense amount ({})",
+            expense.amount
+        )));
+    }
+
+    let now = Utc::now();
+    let result = sqlx::query_as::<_, Reimbursement>(&format!(
+        "INSERT INTO reimbursements (id, user_id, transaction_id, payer, amount, status, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, 'pending', $6, $6)
+         ON CONFLICT (transaction_id) DO NOTHING
+         RETURNING {}",
+        REIMBURSEMENT_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(expense.id)
+    .bind(payer)
+    .bind(&amount)
+    .bind(now)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(reimbursement)) => HttpResponse::Created().json(ApiResponse::success(reimbursement)),
+        Ok(None) => HttpResponse::Conflict()
+            .json(ApiResponse::<Reimbursement>::error("Transaction is already reimbursable".to_string())),
+        Err(e) => {
+            log::error!("Error creating reimbursement: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Reimbursement>::error("Failed to create reimbursement".to_string()))
+        }
+    }
+}
+
+/// Advance a reimbursement (pending -> submitted -> reimbursed)
+pub async fn update_reimbursement_status(
+    path: web::Path<(String, String)>,
+    req: web::Json<UpdateReimbursementStatusRequest>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let (user_id, reimbursement_id) = path.into_inner();
+
+    let current = match fetch_reimbursement(db.get_ref(), &reimbursement_id, &user_id).await {
+        Ok(Some(r)) => r,
+        Ok(None) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<Reimbursement>::error("Reimbursement not found".to_string()));
+        }
+        Err(e) => {
+            log::error!("Error fetching reimbursement: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<Reimbursement>::error("Database error".to_string()));
+        }
+    };
+
+    let current_status = ReimbursementStatus::from_str(&current.status).unwrap_or(ReimbursementStatus::Pending);
+    if req.status <= current_status {
+        return HttpResponse::BadRequest().json(ApiResponse::<Reimbursement>::error(format!(
+            "Cannot move a {} reimbursement to {}",
+            current_status.as_str(),
+            req.status.as_str()
+        )));
+    }
+
+    if let Some(income_id) = req.settled_by_transaction_id {
+        if req.status != ReimbursementStatus::Reimbursed {
+            return HttpResponse::BadRequest().json(ApiResponse::<Reimbursement>::error(
+                "settled_by_transaction_id is only allowed when marking reimbursed".to_string(),
+            ));
+        }
+        match fetch_user_transaction(db.get_ref(), income_id, &user_id).await {
+            Ok(Some(t)) if t.transaction_type == "income" => {}
+            Ok(_) => {
+                return HttpResponse::BadRequest().json(ApiResponse::<Reimbursement>::error(
+                    "Settling transaction must be one of the user's income transactions".to_string(),
+                ));
+            }
+            Err(e) => {
+                log::error!("Error fetching transaction: {}", e);
+                return HttpResponse::InternalServerError()
+                    .json(ApiResponse::<Reimbursement>::error("Database error".to_string()));
+            }
+        }
+    }
+
+    let now = Utc::now();
+    let result = sqlx::query_as::<_, Reimbursement>(&format!(
+        "UPDATE reimbursements
+         SET status = $1,
+             submitted_at = CASE WHEN $1 = 'submitted' THEN $2 ELSE submitted_at END,
+             reimbursed_at = CASE WHEN $1 = 'reimbursed' THEN $2 ELSE reimbursed_at END,
+             settled_by_transaction_id = COALESCE($3, settled_by_transaction_id),
+             updated_at = $2
+         WHERE id = $4 AND user_id = $5 AND status = $6
+         RETURNING {}",
+        REIMBURSEMENT_COLUMNS
+    ))
+    .bind(req.status.as_str())
+    .bind(now)
+    .bind(req.settled_by_transaction_id)
+    .bind(current.id)
+    .bind(&user_id)
+    .bind(&current.status)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(reimbursement)) => HttpResponse::Ok().json(ApiResponse::success(reimbursement)),
+        // Status changed concurrently (e.g. auto-matched)
+        Ok(None) => HttpResponse::Conflict()
+            .json(ApiResponse::<Reimbursement>::error("Reimbursement was updated concurrently".to_string())),
+        Err(e) => {
+            log::error!("Error updating reimbursement: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Reimbursement>::error("Failed to update reimbursement".to_string()))
+        }
+    }
+}
+
+/// Stop tracking an expense as reimbursable
+pub async fn delete_reimbursement(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let (user_id, reimbursement_id) = path.into_inner();
+
+    let result = sqlx::query("DELETE FROM reimbursements WHERE id::text = $1 AND user_id = $2")
+        .bind(&reimbursement_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await;
+
+    match result {
+        Ok(query_result) if query_result.rows_affected() > 0 => HttpResponse::NoContent().finish(),
+        Ok(_) => HttpResponse::NotFound()
+            .json(ApiResponse::<String>::error("Reimbursement not found".to_string())),
+        Err(e) => {
+            log::error!("Error deleting reimbursement: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<String>::error("Failed to delete reimbursement".to_string()))
+        }
+    }
+}
+
+// ==================== Auto-Matching ====================
+
+/// Settle the oldest open reimbursement matched by a new income transaction
+///
+/// Runs on the caller's DB transaction so the match commits (or rolls back)
+/// with the income insert. Returns the settled reimbursement, if any.
+pub async fn auto_settle(
+    conn: &mut PgConnection,
+    income: &Transaction,
+) -> Result<Option<Reimbursement>, sqlx::Error> {
+    if income.transaction_type != "income" {
+        return Ok(None);
+    }
+
+    sqlx::query_as::<_, Reimbursement>(&format!(
+        "UPDATE reimbursements
+         SET status = 'reimbursed', reimbursed_at = $1, settled_by_transaction_id = $2, updated_at = $1
+         WHERE id = (
+             SELECT id FROM reimbursements
+             WHERE user_id = $3 AND status <> 'reimbursed' AND amount = $4
+               AND (position(lower(payer) IN lower($5)) > 0 OR lower($6) = $7)
+             ORDER BY created_at ASC
+             LIMIT 1
+             FOR UPDATE SKIP LOCKED
+         )
+         RETURNING {}",
+        REIMBURSEMENT_COLUMNS
+    ))
+    .bind(income.created_at)
+    .bind(income.id)
+    .bind(&income.user_id)
+    .bind(&income.amount)
+    .bind(income.description.as_deref().unwrap_or_default())
+    .bind(&income.category)
+    .bind(REIMBURSEMENT_CATEGORY)
+    .fetch_optional(conn)
+    .await
+}
+
+// ==================== Database Functions ====================
+
+async fn fetch_reimbursement(
+    pool: &PgPool,
+    reimbursement_id: &str,
+    user_id: &str,
+) -> Result<Option<Reimbursement>, sqlx::Error> {
+    sqlx::query_as::<_, Reimbursement>(&format!(
+        "SELECT {} FROM reimbursements WHERE id::text = $1 AND user_id = $2",
+        REIMBURSEMENT_COLUMNS
+    ))
+    .bind(reimbursement_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+async fn fetch_user_transaction(
+    pool: &PgPool,
+    transaction_id: Uuid,
+    user_id: &str,
+) -> Result<Option<Transaction>, sqlx::Error> {
+    sqlx::query_as::<_, Transaction>(
+        "SELECT id, sequence_number, user_id, wallet_id, bucket_id, income_source_id, amount, currency, transaction_type, category, description, notes, metadata, created_at, updated_at, version
+         FROM transactions WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+    )
+    .bind(transaction_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/reimbursements")
+        .create("", create_reimbursement)
+        .user(Method::GET, "/{user_id}", get_user_reimbursements)
+        .user(Method::GET, "/{user_id}/outstanding", get_outstanding_by_payer)
+        .user(Method::PUT, "/{user_id}/{reimbursement_id}", update_reimbursement_status)
+        .user(Method::DELETE, "/{user_id}/{reimbursement_id}", delete_reimbursement)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
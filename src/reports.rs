@@ -0,0 +1,1152 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse, ResponseError};
+use chrono::{DateTime, Datelike, Duration, Months, NaiveTime, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::cache::{get_or_set_cache_swr, user_cache_key, CacheBackend};
+use crate::export::{render_csv, render_pdf, ReportTable};
+use crate::filters::{FilterQuery, TransactionFilter};
+use crate::currencies;
+use crate::email_templates::{self, normalize_locale, DEFAULT_LOCALE};
+use crate::groups;
+use crate::income_sources;
+use crate::jobs;
+use crate::mailer::{is_valid_address, EmailAttachment, Mailer, OutgoingEmail};
+use crate::routes::ScopedRoutes;
+use crate::standing_orders::TRANSFER_CATEGORY;
+use crate::tax;
+use crate::wallet_members;
+use crate::models::{
+    AmountComparison, ApiResponse, CashflowMonth, CashflowQuery, CategoryComparison, CategorySpending, ComparePeriod,
+    CreateReportSubscriptionRequest, MonthlyCashflow, NetWorth, PeriodComparison, PeriodComparisonQuery, ReportDelivery,
+    ReportFormat, ReportFrequency, ReportSubscription, ReportType, ReportVisibility, SpendingByCategory, SpendingQuery,
+    Transaction, UpdateReportSubscriptionRequest,
+};
+use crate::wallets::fetch_wallet_by_id;
+
+// ==================== SCHEDULED REPORTS ====================
+//
+// Users subscribe to a built-in report (`ReportType`) delivered weekly or
+// monthly as a CSV or PDF email attachment. Schedules are aligned to calendar
+// periods: weekly subscriptions run Monday 00:00 UTC and cover the previous
+// week, monthly ones run on the 1st and cover the previous month.
+//
+// The report job claims each due subscription with `FOR UPDATE SKIP LOCKED`
+// and advances `next_run_at` before rendering, so a slow or failing send is
+// never retried in a loop. The subscription/period is claimed in
+// `job_executions` in the same transaction, so each period is sent at most
+// once across app instances. Every attempt is recorded in `report_deliveries`.
+// The email text comes from the `report_delivery` template in the
+// subscription's locale (see email_templates.rs).
+// Sandbox wallets and their transactions are left out of every report.
+//
+// A subscription is private to its owner unless it covers one of their
+// shared wallets and is shared with that wallet's household or some of its
+// members (see wallet_members.rs). Those members find it under
+// `shared-subscriptions` and may read its delivery history; they can't change
+// it, and the emails still go to the owner's recipient.
+//
+// On-demand analytics (period comparison, spending by category, monthly
+// cashflow, net worth) are aggregated in SQL and converted to the user's base
+// currency. Category totals count a split transaction's lines in their own
+// categories (see transaction_splits.rs). Spending, cashflow and net worth are
+// cached in the user's namespace, so any transaction, wallet or debt write
+// invalidates them.
+//
+// ============================================================================
+
+/// Background job name (advisory lock and execution records)
+pub const REPORT_JOB: &str = "scheduled_reports";
+
+/// Maximum number of deliveries returned by the history endpoint
+const DELIVERY_HISTORY_LIMIT: i64 = 100;
+
+/// How many periods back a comparison may start
+const MAX_COMPARE_OFFSET: u32 = 120;
+
+/// Months covered by the cashflow report by default, and at most
+pub(crate) const DEFAULT_CASHFLOW_MONTHS: u32 = 12;
+const MAX_CASHFLOW_MONTHS: u32 = 60;
+
+const SUBSCRIPTION_COLUMNS: &str = "id, user_id, report_type, format, frequency, email, wallet_id, locale, visibility, shared_with, active, next_run_at, last_sent_at, created_at, updated_at";
+
+// ==================== Subscription Handlers ====================
+
+/// List a user's report subscriptions
+pub async fn get_user_subscriptions(
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+
+    let result = sqlx::query_as::<_, ReportSubscription>(&format!(
+        "SELECT {} FROM report_subscriptions WHERE user_id = $1 ORDER BY created_at DESC",
+        SUBSCRIPTION_COLUMNS
+    ))
+    .bind(&user_id)
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(subscriptions) => HttpResponse::Ok().json(ApiResponse::success(subscriptions)),
+        Err(e) => {
+            log::error!("Error fetching report subscriptions: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<ReportSubscription>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Subscribe to a scheduled report
+pub async fn create_subscription(
+    req: web::Json<CreateReportSubscriptionRequest>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    if !is_valid_address(&req.email) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<ReportSubscription>::error("Invalid email address".to_string()));
+    }
+
+    if let Some(wallet_id) = req.wallet_id {
+        match fetch_wallet_by_id(db.get_ref(), &wallet_id.to_string(), &req.user_id).await {
+            Ok(_) => {}
+            Err(sqlx::Error::RowNotFound) => {
+                return HttpResponse::BadRequest()
+                    .json(ApiResponse::<ReportSubscription>::error("Wallet not found".to_string()));
+            }
+            Err(e) => {
+                log::error!("Error fetching wallet: {}", e);
+                return HttpResponse::InternalServerError()
+                    .json(ApiResponse::<ReportSubscription>::error("Database error".to_string()));
+            }
+        }
+    }
+
+    let locale = match req.locale.as_deref().map(normalize_locale) {
+        None => DEFAULT_LOCALE.to_string(),
+        Some(Some(locale)) => locale,
+        Some(None) => {
+            return HttpResponse::BadRequest()
+                .json(ApiResponse::<ReportSubscription>::error("Invalid locale".to_string()));
+        }
+    };
+
+    let visibility = req.visibility.unwrap_or(ReportVisibility::Private);
+    let shared_with = match wallet_members::check_report_sharing(
+        db.get_ref(),
+        &req.user_id,
+        req.wallet_id,
+        visibility,
+        req.shared_with.as_deref(),
+    )
+    .await
+    {
+        Ok(shared_with) => shared_with,
+        Err(e) => return e.error_response(),
+    };
+
+    let now = Utc::now();
+    let format = req.format.unwrap_or(ReportFormat::Csv);
+
+    let result = sqlx::query_as::<_, ReportSubscription>(&format!(
+        "INSERT INTO report_subscriptions
+             (id, user_id, report_type, format, frequency, email, wallet_id, locale, visibility, shared_with, active,
+              next_run_at, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, TRUE, $11, $12, $12)
+         RETURNING {}",
+        SUBSCRIPTION_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(req.report_type.as_str())
+    .bind(format.as_str())
+    .bind(req.frequency.as_str())
+    .bind(&req.email)
+    .bind(req.wallet_id)
+    .bind(&locale)
+    .bind(visibility.as_str())
+    .bind(&shared_with)
+    .bind(first_run_after(now, req.frequency))
+    .bind(now)
+    .fetch_one(db.get_ref())
+    .await;
+
+    match result {
+        Ok(subscription) => HttpResponse::Created().json(ApiResponse::success(subscription)),
+        Err(e) => {
+            log::error!("Error creating report subscription: {}", e);
+            HttpResponse::BadRequest()
+                .json(ApiResponse::<ReportSubscription>::error("Failed to create subscription".to_string()))
+        }
+    }
+}
+
+/// Update format, schedule, recipient or pause/resume a subscription
+pub async fn update_subscription(
+    path: web::Path<(String, String)>,
+    req: web::Json<UpdateReportSubscriptionRequest>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let (user_id, subscription_id) = path.into_inner();
+    let now = Utc::now();
+
+    if let Some(email) = &req.email
+        && !is_valid_address(email)
+    {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<ReportSubscription>::error("Invalid email address".to_string()));
+    }
+    let locale = match req.locale.as_deref().map(normalize_locale) {
+        None => None,
+        Some(Some(locale)) => Some(locale),
+        Some(None) => {
+            return HttpResponse::BadRequest()
+                .json(ApiResponse::<ReportSubscription>::error("Invalid locale".to_string()));
+        }
+    };
+
+    let sharing = match req.visibility {
+        None if req.shared_with.is_some() => {
+            return HttpResponse::BadRequest()
+                .json(ApiResponse::<ReportSubscription>::error("Set visibility with shared_with".to_string()));
+        }
+        None => None,
+        Some(visibility) => match subscription_wallet(db.get_ref(), &subscription_id, &user_id).await {
+            Ok(Some(wallet_id)) => {
+                match wallet_members::check_report_sharing(
+                    db.get_ref(),
+                    &user_id,
+                    wallet_id,
+                    visibility,
+                    req.shared_with.as_deref(),
+                )
+                .await
+                {
+                    Ok(shared_with) => Some((visibility.as_str(), shared_with)),
+                    Err(e) => return e.error_response(),
+                }
+            }
+            Ok(None) => {
+                return HttpResponse::NotFound()
+                    .json(ApiResponse::<ReportSubscription>::error("Subscription not found".to_string()));
+            }
+            Err(e) => {
+                log::error!("Error fetching report subscription: {}", e);
+                return HttpResponse::InternalServerError()
+                    .json(ApiResponse::<ReportSubscription>::error("Database error".to_string()));
+            }
+        },
+    };
+    let (visibility, shared_with) = sharing.unzip();
+
+    // A schedule change restarts the subscription at the next period boundary
+    let next_run_at = req.frequency.map(|frequency| first_run_after(now, frequency));
+
+    let result = sqlx::query_as::<_, ReportSubscription>(&format!(
+        "UPDATE report_subscriptions
+         SET format = COALESCE($1, format),
+             frequency = COALESCE($2, frequency),
+             next_run_at = COALESCE($3, next_run_at),
+             email = COALESCE($4, email),
+             locale = COALESCE($5, locale),
+             active = COALESCE($6, active),
+             visibility = COALESCE($10, visibility),
+             shared_with = COALESCE($11, shared_with),
+             updated_at = $7
+         WHERE id::text = $8 AND user_id = $9
+         RETURNING {}",
+        SUBSCRIPTION_COLUMNS
+    ))
+    .bind(req.format.map(|f| f.as_str()))
+    .bind(req.frequency.map(|f| f.as_str()))
+    .bind(next_run_at)
+    .bind(&req.email)
+    .bind(&locale)
+    .bind(req.active)
+    .bind(now)
+    .bind(&subscription_id)
+    .bind(&user_id)
+    .bind(visibility)
+    .bind(shared_with)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(subscription)) => HttpResponse::Ok().json(ApiResponse::success(subscription)),
+        Ok(None) => HttpResponse::NotFound()
+            .json(ApiResponse::<ReportSubscription>::error("Subscription not found".to_string())),
+        Err(e) => {
+            log::error!("Error updating report subscription: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<ReportSubscription>::error("Failed to update subscription".to_string()))
+        }
+    }
+}
+
+/// Unsubscribe (delivery history is removed with the subscription)
+pub async fn delete_subscription(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let (user_id, subscription_id) = path.into_inner();
+
+    let result = sqlx::query("DELETE FROM report_subscriptions WHERE id::text = $1 AND user_id = $2")
+        .bind(&subscription_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await;
+
+    match result {
+        Ok(query_result) if query_result.rows_affected() > 0 => HttpResponse::NoContent().finish(),
+        Ok(_) => HttpResponse::NotFound()
+            .json(ApiResponse::<String>::error("Subscription not found".to_string())),
+        Err(e) => {
+            log::error!("Error deleting report subscription: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<String>::error("Failed to delete subscription".to_string()))
+        }
+    }
+}
+
+/// Subscriptions of other users shared with this one (see wallet_members.rs)
+pub async fn get_shared_subscriptions(
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+
+    let result = sqlx::query_as::<_, ReportSubscription>(&format!(
+        "SELECT {} FROM report_subscriptions
+         WHERE user_id <> $1 AND report_visible_to(user_id, wallet_id, visibility, shared_with, $1)
+         ORDER BY created_at DESC",
+        SUBSCRIPTION_COLUMNS
+    ))
+    .bind(&user_id)
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(subscriptions) => HttpResponse::Ok().json(ApiResponse::success(subscriptions)),
+        Err(e) => {
+            log::error!("Error fetching shared report subscriptions: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<ReportSubscription>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Wallet of a user's subscription; `None` if there's no such subscription
+async fn subscription_wallet(
+    pool: &PgPool,
+    subscription_id: &str,
+    user_id: &str,
+) -> Result<Option<Option<Uuid>>, sqlx::Error> {
+    let row: Option<(Option<Uuid>,)> =
+        sqlx::query_as("SELECT wallet_id FROM report_subscriptions WHERE id::text = $1 AND user_id = $2")
+            .bind(subscription_id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(row.map(|(wallet_id,)| wallet_id))
+}
+
+/// Delivery history of a subscription (most recent first), for its owner
+/// and the members it's shared with
+pub async fn get_subscription_deliveries(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let (user_id, subscription_id) = path.into_inner();
+
+    let result = sqlx::query_as::<_, ReportDelivery>(
+        "SELECT id, subscription_id, user_id, status, period_start, period_end, row_count, error, created_at
+         FROM report_deliveries d
+         WHERE subscription_id::text = $1
+           AND EXISTS (
+               SELECT 1 FROM report_subscriptions s
+               WHERE s.id = d.subscription_id
+                 AND report_visible_to(s.user_id, s.wallet_id, s.visibility, s.shared_with, $2)
+           )
+         ORDER BY created_at DESC
+         LIMIT $3",
+    )
+    .bind(&subscription_id)
+    .bind(&user_id)
+    .bind(DELIVERY_HISTORY_LIMIT)
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(deliveries) => HttpResponse::Ok().json(ApiResponse::success(deliveries)),
+        Err(e) => {
+            log::error!("Error fetching report deliveries: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<ReportDelivery>>::error("Database error".to_string()))
+        }
+    }
+}
+
+// ==================== Schedule ====================
+
+/// Start of the period following `at` (next Monday or next 1st, 00:00 UTC)
+fn first_run_after(at: DateTime<Utc>, frequency: ReportFrequency) -> DateTime<Utc> {
+    let today = at.date_naive();
+    let next = match frequency {
+        ReportFrequency::Weekly => {
+            today + Duration::days(7 - i64::from(today.weekday().num_days_from_monday()))
+        }
+        ReportFrequency::Monthly => {
+            let month_start = today.with_day(1).unwrap_or(today);
+            month_start.checked_add_months(Months::new(1)).unwrap_or(month_start)
+        }
+    };
+    next.and_time(NaiveTime::MIN).and_utc()
+}
+
+/// Period covered by a run scheduled at `run_at`
+fn report_period(run_at: DateTime<Utc>, frequency: ReportFrequency) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start = match frequency {
+        ReportFrequency::Weekly => run_at - Duration::days(7),
+        ReportFrequency::Monthly => run_at.checked_sub_months(Months::new(1)).unwrap_or(run_at),
+    };
+    (start, run_at)
+}
+
+// ==================== Period Comparison ====================
+
+/// `GET /{user_id}/compare` - per-category totals of a period vs the one before
+///
+/// Both periods are summed in a single grouped query; deltas and percentage
+/// changes are derived from its rows. Transfer legs are left out, as in the
+/// spending report. Amounts are converted to the user's
+/// base currency (409 while a wallet's currency has no rate to it).
+pub async fn compare_periods(
+    user_id: web::Path<String>,
+    query: web::Query<PeriodComparisonQuery>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    let offset = query.offset.unwrap_or(0);
+    if offset > MAX_COMPARE_OFFSET {
+        return HttpResponse::BadRequest().json(ApiResponse::<PeriodComparison>::error(format!(
+            "offset must be at most {}",
+            MAX_COMPARE_OFFSET
+        )));
+    }
+
+    let currency = match currencies::reporting_currency(db.get_ref(), &user_id).await {
+        Ok(Ok(currency)) => currency,
+        Ok(Err(msg)) => return HttpResponse::Conflict().json(ApiResponse::<PeriodComparison>::error(msg)),
+        Err(e) => {
+            log::error!("Error checking exchange rates: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<PeriodComparison>::error("Database error".to_string()));
+        }
+    };
+
+    let current_start = shift_periods(period_start(Utc::now(), query.period), query.period, -(offset as i32));
+    let current_end = shift_periods(current_start, query.period, 1);
+    let previous_start = shift_periods(current_start, query.period, -1);
+
+    let result = sqlx::query_as::<_, CategoryPeriodTotals>(
+        "SELECT category, transaction_type,
+                COALESCE(SUM(convert_amount(amount, currency, $6)) FILTER (WHERE created_at >= $2), 0) AS current_total,
+                COALESCE(SUM(convert_amount(amount, currency, $6)) FILTER (WHERE created_at < $2), 0) AS previous_total
+         FROM v_transaction_category_lines
+         WHERE user_id = $1 AND deleted_at IS NULL AND NOT sandbox AND category <> $7
+           AND created_at >= $3 AND created_at < $4 AND ($5::uuid IS NULL OR wallet_id = $5)
+         GROUP BY category, transaction_type
+         ORDER BY transaction_type, current_total DESC, category",
+    )
+    .bind(&user_id)
+    .bind(current_start)
+    .bind(previous_start)
+    .bind(current_end)
+    .bind(query.wallet_id)
+    .bind(&currency)
+    .bind(TRANSFER_CATEGORY)
+    .fetch_all(db.get_ref())
+    .await;
+
+    let rows = match result {
+        Ok(rows) => rows,
+        Err(e) => {
+            log::error!("Error comparing periods: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<PeriodComparison>::error("Database error".to_string()));
+        }
+    };
+
+    let zero = BigDecimal::from(0);
+    let (mut income, mut expense) = ((zero.clone(), zero.clone()), (zero.clone(), zero));
+    for row in &rows {
+        let totals = if row.transaction_type == "income" { &mut income } else { &mut expense };
+        totals.0 += &row.current_total;
+        totals.1 += &row.previous_total;
+    }
+
+    HttpResponse::Ok().json(ApiResponse::success(PeriodComparison {
+        period: query.period,
+        currency,
+        current_start,
+        current_end,
+        previous_start,
+        income: compare_amounts(income.0, income.1),
+        expense: compare_amounts(expense.0, expense.1),
+        categories: rows
+            .into_iter()
+            .map(|row| CategoryComparison {
+                category: row.category,
+                transaction_type: row.transaction_type,
+                amounts: compare_amounts(row.current_total, row.previous_total),
+            })
+            .collect(),
+    }))
+}
+
+#[derive(sqlx::FromRow)]
+struct CategoryPeriodTotals {
+    category: String,
+    transaction_type: String,
+    current_total: BigDecimal,
+    previous_total: BigDecimal,
+}
+
+fn compare_amounts(current: BigDecimal, previous: BigDecimal) -> AmountComparison {
+    let delta = &current - &previous;
+    let percent_change = (previous != BigDecimal::from(0))
+        .then(|| (&delta * BigDecimal::from(100) / &previous).round(2));
+    AmountComparison { current, previous, delta, percent_change }
+}
+
+/// Start of the period containing `at` (Monday or the 1st, 00:00 UTC)
+fn period_start(at: DateTime<Utc>, period: ComparePeriod) -> DateTime<Utc> {
+    let today = at.date_naive();
+    let start = match period {
+        ComparePeriod::Week => today - Duration::days(i64::from(today.weekday().num_days_from_monday())),
+        ComparePeriod::Month => today.with_day(1).unwrap_or(today),
+    };
+    start.and_time(NaiveTime::MIN).and_utc()
+}
+
+/// `start` moved by `n` whole periods (negative: back in time)
+fn shift_periods(start: DateTime<Utc>, period: ComparePeriod, n: i32) -> DateTime<Utc> {
+    match period {
+        ComparePeriod::Week => start + Duration::weeks(i64::from(n)),
+        ComparePeriod::Month if n >= 0 => start.checked_add_months(Months::new(n as u32)).unwrap_or(start),
+        ComparePeriod::Month => start.checked_sub_months(Months::new(n.unsigned_abs())).unwrap_or(start),
+    }
+}
+
+// ==================== Analytics ====================
+
+/// `GET /{user_id}/spending-by-category` - expenses per category over `from..to` (with caching)
+///
+/// Transfers between the user's wallets are not spending and are left out.
+pub async fn get_spending_by_category(
+    user_id: web::Path<String>,
+    query: web::Query<SpendingQuery>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    let now = Utc::now();
+    let from = query.from.unwrap_or_else(|| period_start(now, ComparePeriod::Month));
+    let to = query.to.unwrap_or(now);
+    if from >= to {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<SpendingByCategory>::error("from must be before to".to_string()));
+    }
+
+    let currency = match reporting_currency::<SpendingByCategory>(db.get_ref(), &user_id).await {
+        Ok(currency) => currency,
+        Err(response) => return response,
+    };
+
+    let wallet = query.wallet_id.map(|id| id.to_string()).unwrap_or_default();
+    let suffix = format!("report:spending:{}:{}:{}:{}", currency, from.timestamp(), to.timestamp(), wallet);
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, &suffix).await;
+    let wallet_id = query.wallet_id;
+    let result = get_or_set_cache_swr(&cache, &cache_key, async move {
+        fetch_spending(db.get_ref(), &user_id, currency, from, to, wallet_id).await
+    })
+    .await;
+
+    match result {
+        Ok(report) => HttpResponse::Ok().json(ApiResponse::success(report)),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<SpendingByCategory>::error(e.to_string())),
+    }
+}
+
+/// `GET /{user_id}/monthly-cashflow` - income, expenses and net per month (with caching)
+///
+/// Covers `months` calendar months ending with the current one, which is
+/// still in progress. Transfers are left out.
+pub async fn get_monthly_cashflow(
+    user_id: web::Path<String>,
+    query: web::Query<CashflowQuery>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    let months = query.months.unwrap_or(DEFAULT_CASHFLOW_MONTHS);
+    let (start, end) = match cashflow_range(months) {
+        Ok(range) => range,
+        Err(msg) => return HttpResponse::BadRequest().json(ApiResponse::<MonthlyCashflow>::error(msg)),
+    };
+
+    let currency = match reporting_currency::<MonthlyCashflow>(db.get_ref(), &user_id).await {
+        Ok(currency) => currency,
+        Err(response) => return response,
+    };
+
+    let wallet = query.wallet_id.map(|id| id.to_string()).unwrap_or_default();
+    let suffix = format!("report:cashflow:{}:{}:{}:{}", currency, start.timestamp(), months, wallet);
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, &suffix).await;
+    let wallet_id = query.wallet_id;
+    let result = get_or_set_cache_swr(&cache, &cache_key, async move {
+        fetch_cashflow(db.get_ref(), &user_id, currency, start, end, wallet_id).await
+    })
+    .await;
+
+    match result {
+        Ok(report) => HttpResponse::Ok().json(ApiResponse::success(report)),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<MonthlyCashflow>::error(e.to_string())),
+    }
+}
+
+/// `GET /{user_id}/net-worth` - wallet balances less outstanding debts (with caching)
+///
+/// The same figures as the portfolio groups' net worth (see groups.rs).
+pub async fn get_net_worth(
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+
+    let currency = match reporting_currency::<NetWorth>(db.get_ref(), &user_id).await {
+        Ok(currency) => currency,
+        Err(response) => return response,
+    };
+
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, &format!("report:net_worth:{}", currency)).await;
+    let result = get_or_set_cache_swr(&cache, &cache_key, async move {
+        groups::compute_net_worth(db.get_ref(), &user_id, currency).await
+    })
+    .await;
+
+    match result {
+        Ok(net_worth) => HttpResponse::Ok().json(ApiResponse::success(net_worth)),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<NetWorth>::error(e.to_string())),
+    }
+}
+
+/// `months` calendar months ending with the current one, as `start..end`
+pub(crate) fn cashflow_range(months: u32) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    if !(1..=MAX_CASHFLOW_MONTHS).contains(&months) {
+        return Err(format!("months must be between 1 and {}", MAX_CASHFLOW_MONTHS));
+    }
+    let current = period_start(Utc::now(), ComparePeriod::Month);
+    let start = shift_periods(current, ComparePeriod::Month, 1 - months as i32);
+    let end = shift_periods(current, ComparePeriod::Month, 1);
+    Ok((start, end))
+}
+
+/// The user's base currency, or the 409/500 response when it can't be used
+async fn reporting_currency<T: serde::Serialize>(pool: &PgPool, user_id: &str) -> Result<String, HttpResponse> {
+    match currencies::reporting_currency(pool, user_id).await {
+        Ok(Ok(currency)) => Ok(currency),
+        Ok(Err(msg)) => Err(HttpResponse::Conflict().json(ApiResponse::<T>::error(msg))),
+        Err(e) => {
+            log::error!("Error checking exchange rates: {}", e);
+            Err(HttpResponse::InternalServerError().json(ApiResponse::<T>::error("Database error".to_string())))
+        }
+    }
+}
+
+pub(crate) async fn fetch_spending(
+    pool: &PgPool,
+    user_id: &str,
+    currency: String,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    wallet_id: Option<Uuid>,
+) -> Result<SpendingByCategory, sqlx::Error> {
+    // A split expense counts toward each of its lines' categories, and once in `count`
+    let rows = sqlx::query_as::<_, CategorySpendingRow>(
+        "WITH lines AS (
+             SELECT id, category, convert_amount(amount, currency, $5) AS amount
+             FROM v_transaction_category_lines
+             WHERE user_id = $1 AND deleted_at IS NULL AND NOT sandbox AND transaction_type = 'expense' AND category <> $6
+               AND created_at >= $2 AND created_at < $3 AND ($4::uuid IS NULL OR wallet_id = $4)
+         )
+         SELECT category, COALESCE(SUM(amount), 0) AS total, COUNT(*) AS count,
+                (SELECT COUNT(DISTINCT id) FROM lines) AS transactions
+         FROM lines
+         GROUP BY category
+         ORDER BY total DESC, category",
+    )
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .bind(wallet_id)
+    .bind(&currency)
+    .bind(TRANSFER_CATEGORY)
+    .fetch_all(pool)
+    .await?;
+
+    let count = rows.first().map_or(0, |row| row.transactions);
+    let mut categories: Vec<CategorySpending> = rows
+        .into_iter()
+        .map(|row| CategorySpending {
+            category: row.category,
+            total: row.total,
+            count: row.count,
+            percent: BigDecimal::from(0),
+        })
+        .collect();
+
+    let total: BigDecimal = categories.iter().map(|c| &c.total).sum();
+    let zero = BigDecimal::from(0);
+    for category in &mut categories {
+        if total > zero {
+            category.percent = (&category.total * BigDecimal::from(100) / &total).round(2);
+        }
+    }
+    Ok(SpendingByCategory {
+        currency,
+        from,
+        to,
+        count,
+        total,
+        categories,
+    })
+}
+
+#[derive(sqlx::FromRow)]
+struct CategorySpendingRow {
+    category: String,
+    total: BigDecimal,
+    count: i64,
+    transactions: i64,
+}
+
+pub(crate) async fn fetch_cashflow(
+    pool: &PgPool,
+    user_id: &str,
+    currency: String,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    wallet_id: Option<Uuid>,
+) -> Result<MonthlyCashflow, sqlx::Error> {
+    let months = sqlx::query_as::<_, CashflowMonth>(
+        "WITH totals AS (
+             SELECT date_trunc('month', created_at AT TIME ZONE 'UTC')::date AS month,
+                    COALESCE(SUM(convert_amount(amount, currency, $5)) FILTER (WHERE transaction_type = 'income'), 0) AS income,
+                    COALESCE(SUM(convert_amount(amount, currency, $5)) FILTER (WHERE transaction_type = 'expense'), 0) AS expense
+             FROM transactions
+             WHERE user_id = $1 AND deleted_at IS NULL AND NOT sandbox AND category <> $6
+               AND created_at >= $2 AND created_at < $3 AND ($4::uuid IS NULL OR wallet_id = $4)
+             GROUP BY 1
+         )
+         SELECT m.month::date AS month,
+                COALESCE(t.income, 0) AS income,
+                COALESCE(t.expense, 0) AS expense,
+                COALESCE(t.income, 0) - COALESCE(t.expense, 0) AS net
+         FROM generate_series(($2 AT TIME ZONE 'UTC')::date, ($3 AT TIME ZONE 'UTC')::date - 1, INTERVAL '1 month') AS m(month)
+         LEFT JOIN totals t ON t.month = m.month::date
+         ORDER BY m.month",
+    )
+    .bind(user_id)
+    .bind(start)
+    .bind(end)
+    .bind(wallet_id)
+    .bind(&currency)
+    .bind(TRANSFER_CATEGORY)
+    .fetch_all(pool)
+    .await?;
+
+    let income: BigDecimal = months.iter().map(|m| &m.income).sum();
+    let expense: BigDecimal = months.iter().map(|m| &m.expense).sum();
+    Ok(MonthlyCashflow {
+        currency,
+        start,
+        end,
+        net: &income - &expense,
+        income,
+        expense,
+        months,
+    })
+}
+
+// ==================== Rendering ====================
+
+#[derive(sqlx::FromRow)]
+struct BucketBalance {
+    wallet_name: String,
+    name: String,
+    balance: BigDecimal,
+    target_amount: Option<BigDecimal>,
+}
+
+#[derive(sqlx::FromRow)]
+struct CategoryTotal {
+    category: String,
+    transaction_type: String,
+    total: BigDecimal,
+    count: i64,
+}
+
+/// Render a report; totals are in `currency`, the user's base currency
+async fn build_report(
+    pool: &PgPool,
+    subscription: &ReportSubscription,
+    report_type: ReportType,
+    currency: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    max_rows: i64,
+) -> Result<ReportTable, sqlx::Error> {
+    let period = format!("{} to {}", start.format("%Y-%m-%d"), end.format("%Y-%m-%d"));
+    let filter = TransactionFilter {
+        wallet_id: subscription.wallet_id,
+        from: Some(start),
+        to: Some(end),
+        ..Default::default()
+    };
+
+    match report_type {
+        ReportType::Transactions => {
+            let transactions: Vec<Transaction> = filter
+                .apply(
+                    &subscription.user_id,
+                    FilterQuery::new(
+                        "SELECT id, sequence_number, user_id, wallet_id, bucket_id, income_source_id, amount, currency, transaction_type, category, description, notes, metadata, created_at, updated_at, version FROM transactions",
+                    ),
+                )
+                .eq("sandbox", false)
+                .order_by("created_at ASC")
+                .limit(max_rows + 1)
+                .fetch_all(pool)
+                .await?;
+            let truncated = transactions.len() as i64 > max_rows;
+
+            Ok(ReportTable {
+                title: format!("Transactions, {}", period),
+                headers: &["date", "type", "category", "amount", "currency", "description", "fields"],
+                rows: transactions
+                    .into_iter()
+                    .take(max_rows as usize)
+                    .map(|t| {
+                        vec![
+                            t.created_at.format("%Y-%m-%d").to_string(),
+                            t.transaction_type,
+                            t.category,
+                            t.amount.to_string(),
+                            t.currency,
+                            t.description.unwrap_or_default(),
+                            format_metadata(&t.metadata),
+                        ]
+                    })
+                    .collect(),
+                truncated,
+            })
+        }
+        ReportType::CategorySummary => {
+            let totals: Vec<CategoryTotal> = filter
+                .apply(
+                    &subscription.user_id,
+                    FilterQuery::new(
+                        "SELECT category, transaction_type, SUM(convert_amount(amount, currency, base_currency(user_id))) AS total,
+                                COUNT(*) AS count
+                         FROM v_transaction_category_lines",
+                    ),
+                )
+                .eq("sandbox", false)
+                .group_by("category, transaction_type")
+                .order_by("transaction_type, total DESC")
+                .fetch_all(pool)
+                .await?;
+
+            Ok(ReportTable {
+                title: format!("Spending by category in {}, {}", currency, period),
+                headers: &["type", "category", "total", "transactions"],
+                rows: totals
+                    .into_iter()
+                    .map(|t| vec![t.transaction_type, t.category, t.total.to_string(), t.count.to_string()])
+                    .collect(),
+                truncated: false,
+            })
+        }
+        ReportType::BucketBalances => {
+            let buckets: Vec<BucketBalance> = FilterQuery::new(
+                "SELECT w.name AS wallet_name, b.name, b.balance, b.target_amount
+                 FROM wallet_buckets b JOIN wallets w ON w.id = b.wallet_id",
+            )
+            .eq("b.user_id", subscription.user_id.clone())
+            .null("w.deleted_at")
+            .eq("w.sandbox", false)
+            .eq_opt("b.wallet_id", subscription.wallet_id)
+            .order_by("w.name, b.name")
+            .fetch_all(pool)
+            .await?;
+
+            Ok(ReportTable {
+                title: format!("Bucket balances as of {}", end.format("%Y-%m-%d")),
+                headers: &["wallet", "bucket", "balance", "target", "progress"],
+                rows: buckets
+                    .into_iter()
+                    .map(|b| {
+                        let progress = b
+                            .target_amount
+                            .as_ref()
+                            .map(|target| format!("{}%", (&b.balance * BigDecimal::from(100) / target).round(0)))
+                            .unwrap_or_default();
+                        vec![
+                            b.wallet_name,
+                            b.name,
+                            b.balance.to_string(),
+                            b.target_amount.map(|t| t.to_string()).unwrap_or_default(),
+                            progress,
+                        ]
+                    })
+                    .collect(),
+                truncated: false,
+            })
+        }
+    }
+}
+
+/// Custom field values as `key=value; key=value` (empty when none are set)
+fn format_metadata(metadata: &serde_json::Value) -> String {
+    metadata
+        .as_object()
+        .map(|fields| {
+            fields
+                .iter()
+                .map(|(key, value)| match value {
+                    serde_json::Value::String(text) => format!("{}={}", key, text),
+                    other => format!("{}={}", key, other),
+                })
+                .collect::<Vec<_>>()
+                .join("; ")
+        })
+        .unwrap_or_default()
+}
+
+fn render_attachment(table: &ReportTable, format: ReportFormat, base_name: &str) -> Result<EmailAttachment, String> {
+    match format {
+        ReportFormat::Csv => Ok(EmailAttachment {
+            file_name: format!("{}.csv", base_name),
+            content_type: "text/csv".to_string(),
+            bytes: render_csv(std::slice::from_ref(table)),
+        }),
+        ReportFormat::Pdf => Ok(EmailAttachment {
+            file_name: format!("{}.pdf", base_name),
+            content_type: "application/pdf".to_string(),
+            bytes: render_pdf(&table.title, std::slice::from_ref(table))?,
+        }),
+    }
+}
+
+// ==================== Report Job ====================
+
+/// Deliver every subscription whose `next_run_at` has passed
+///
+/// Reports are capped at `max_rows` rows (`MAX_EXPORT_ROWS`). Returns the
+/// number of reports sent.
+pub async fn send_due_reports(pool: &PgPool, mailer: &dyn Mailer, max_rows: i64) -> Result<u64, sqlx::Error> {
+    let due_ids: Vec<(Uuid,)> = sqlx::query_as(
+        "SELECT id FROM report_subscriptions WHERE active AND next_run_at <= CURRENT_TIMESTAMP",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut sent = 0;
+    for (subscription_id,) in due_ids {
+        match deliver_subscription(pool, mailer, subscription_id, max_rows).await {
+            Ok(true) => sent += 1,
+            Ok(false) => {}
+            Err(e) => log::error!("Failed to deliver report subscription {}: {}", subscription_id, e),
+        }
+    }
+
+    Ok(sent)
+}
+
+/// Claim, render and send one subscription, recording the attempt
+///
+/// Returns `false` when the subscription was claimed by a concurrent run or
+/// the send failed (the failure is recorded in the delivery history).
+async fn deliver_subscription(
+    pool: &PgPool,
+    mailer: &dyn Mailer,
+    subscription_id: Uuid,
+    max_rows: i64,
+) -> Result<bool, sqlx::Error> {
+    let mut db_tx = pool.begin().await?;
+
+    let subscription = sqlx::query_as::<_, ReportSubscription>(&format!(
+        "SELECT {} FROM report_subscriptions
+         WHERE id = $1 AND active AND next_run_at <= CURRENT_TIMESTAMP
+         FOR UPDATE SKIP LOCKED",
+        SUBSCRIPTION_COLUMNS
+    ))
+    .bind(subscription_id)
+    .fetch_optional(&mut *db_tx)
+    .await?;
+
+    let subscription = match subscription {
+        Some(s) => s,
+        None => {
+            db_tx.rollback().await?;
+            return Ok(false);
+        }
+    };
+
+    let (report_type, format, frequency) = match (
+        ReportType::from_str(&subscription.report_type),
+        ReportFormat::from_str(&subscription.format),
+        ReportFrequency::from_str(&subscription.frequency),
+    ) {
+        (Some(t), Some(f), Some(q)) => (t, f, q),
+        _ => {
+            db_tx.rollback().await?;
+            log::error!("Report subscription {} has an invalid configuration", subscription.id);
+            return Ok(false);
+        }
+    };
+
+    let (period_start, period_end) = report_period(subscription.next_run_at, frequency);
+
+    let occurrence = format!("{}:{}", subscription.id, period_start.to_rfc3339());
+    if !jobs::claim_occurrence(&mut db_tx, REPORT_JOB, &occurrence).await? {
+        db_tx.rollback().await?;
+        return Ok(false);
+    }
+
+    // Skip periods missed while the job wasn't running; only the latest is sent
+    let now = Utc::now();
+    let mut next_run_at = subscription.next_run_at;
+    while next_run_at <= now {
+        next_run_at = first_run_after(next_run_at, frequency);
+    }
+
+    sqlx::query("UPDATE report_subscriptions SET next_run_at = $1 WHERE id = $2")
+        .bind(next_run_at)
+        .bind(subscription.id)
+        .execute(&mut *db_tx)
+        .await?;
+
+    db_tx.commit().await?;
+
+    // A report that can't be converted to the base currency is recorded as failed
+    let table = match currencies::reporting_currency(pool, &subscription.user_id).await? {
+        Ok(currency) => {
+            Ok(build_report(pool, &subscription, report_type, &currency, period_start, period_end, max_rows).await?)
+        }
+        Err(msg) => Err(msg),
+    };
+    let row_count = table.as_ref().map_or(0, |table| table.rows.len() as i32);
+    let base_name = format!("ketobook-{}-{}", report_type.as_str(), period_start.format("%Y-%m-%d"));
+
+    let rendered = match table.and_then(|table| {
+        render_attachment(&table, format, &base_name)
+            .map(|attachment| (table, attachment))
+            .map_err(|e| format!("Rendering failed: {}", e))
+    }) {
+        Ok((table, attachment)) => {
+            let data = serde_json::json!({
+                "title": table.title,
+                "report_type": report_type.as_str(),
+                "frequency": frequency.as_str(),
+                "format": format.as_str(),
+                "row_count": row_count,
+                "truncated": table.truncated,
+                "max_rows": max_rows,
+                "period_start": period_start.format("%Y-%m-%d").to_string(),
+                "period_end": period_end.format("%Y-%m-%d").to_string(),
+            });
+            email_templates::render(pool, email_templates::REPORT_DELIVERY, &subscription.locale, &data)
+                .await
+                .map(|email| (email, attachment))
+                .map_err(|e| format!("Email rendering failed: {}", e))
+        }
+        Err(e) => Err(e),
+    };
+    let outcome = match rendered {
+        Ok((email, attachment)) => mailer
+            .send(OutgoingEmail {
+                to: subscription.email.clone(),
+                subject: email.subject,
+                body: email.body,
+                attachment: Some(attachment),
+            })
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+
+    let (status, error) = match &outcome {
+        Ok(()) => ("sent", None),
+        Err(e) => ("failed", Some(e.clone())),
+    };
+
+    sqlx::query(
+        "INSERT INTO report_deliveries
+             (id, subscription_id, user_id, status, period_start, period_end, row_count, error, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(subscription.id)
+    .bind(&subscription.user_id)
+    .bind(status)
+    .bind(period_start)
+    .bind(period_end)
+    .bind(row_count)
+    .bind(&error)
+    .bind(Utc::now())
+    .execute(pool)
+    .await?;
+
+    match outcome {
+        Ok(()) => {
+            sqlx::query("UPDATE report_subscriptions SET last_sent_at = $1 WHERE id = $2")
+                .bind(Utc::now())
+                .bind(subscription.id)
+                .execute(pool)
+                .await?;
+            Ok(true)
+        }
+        Err(e) => {
+            log::warn!("Report subscription {} delivery failed: {}", subscription.id, e);
+            Ok(false)
+        }
+    }
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/reports")
+        .create("/subscriptions", create_subscription)
+        .user(Method::GET, "/{user_id}/subscriptions", get_user_subscriptions)
+        .user(Method::GET, "/{user_id}/shared-subscriptions", get_shared_subscriptions)
+        .user(Method::PUT, "/{user_id}/subscriptions/{subscription_id}", update_subscription)
+        .user(Method::DELETE, "/{user_id}/subscriptions/{subscription_id}", delete_subscription)
+        .user(Method::GET, "/{user_id}/subscriptions/{subscription_id}/deliveries", get_subscription_deliveries)
+        .user(Method::GET, "/{user_id}/compare", compare_periods)
+        .user(Method::GET, "/{user_id}/spending-by-category", get_spending_by_category)
+        .user(Method::GET, "/{user_id}/monthly-cashflow", get_monthly_cashflow)
+        .user(Method::GET, "/{user_id}/net-worth", get_net_worth)
+        .user(Method::GET, "/{user_id}/income", income_sources::get_income_report)
+        .user(Method::GET, "/{user_id}/tax/{year}", tax::get_tax_report)
+        .extended()
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
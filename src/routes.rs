@@ -0,0 +1,332 @@
+use actix_web::dev::HttpServiceFactory;
+use actix_web::http::Method;
+use actix_web::middleware::from_fn;
+use actix_web::{web, FromRequest, Handler, HttpResponse, Responder, Route};
+use serde::Serialize;
+
+use crate::api_keys;
+use crate::auth;
+use crate::cache;
+use crate::dry_run;
+use crate::impersonation;
+use crate::log_control;
+use crate::models::ApiResponse;
+use crate::provider_webhooks;
+use crate::timeouts::{self, LatencyBudget};
+
+// ==================== ROUTE REGISTRATION ====================
+//
+// Every API scope registers its routes through `ScopedRoutes`, which:
+//
+// 1. Enforces the `/{user_id}/...` (or `/user/{user_id}`) path convention for
+//    user-owned resources, panicking at startup on a malformed path.
+// 2. Records how each route establishes ownership (`Ownership`), so the full
+//    set of routes can be emitted as a machine-readable manifest
+//    (`GET /api/routes`) and audited: every mutating route must be owned,
+//    or signed by a provider (`.signed()`, verified by `provider_webhooks`),
+//    which a test checks.
+// 3. Runs each route under its latency budget (see `timeouts`): standard
+//    unless marked `.extended()`.
+// 4. Checks and audits requests made with a support impersonation token
+//    against the route's ownership (see `impersonation`).
+// 5. Derives the scope an API key needs to call each route (`RouteSpec::scope`,
+//    see `api_keys`): `read:{resource}` for reads, `create:{resource}` for
+//    collection creates and `write:{resource}` for other mutations, where the
+//    resource is the scope's path segment after `/api/` (`wallet-types` ->
+//    `wallet_types`). Admin and public routes have no scope.
+// 6. Authenticates the caller of every user route against its owner (see
+//    `auth`) and hands handlers the verified user (`AuthUser`). Session
+//    routes (`/api/users/me/...`) belong to whoever holds the bearer token.
+// 7. Applies the read consistency the client asked for (`X-Consistency`,
+//    see `cache`).
+// 8. Lets routes marked `.dry_run()` preview a change without making it
+//    (`?dry_run=true`, see `dry_run`) and rejects dry runs of the others.
+//
+// ============================================================================
+
+/// How a route establishes which user's data it reads or mutates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ownership {
+    /// `{user_id}` path segment; handlers scope every query by it
+    PathUser,
+    /// `user_id` field of the JSON body (collection create endpoints)
+    BodyUser,
+    /// The subject of the caller's bearer token (`/me` routes about the login
+    /// itself); API keys and impersonation tokens can't call them
+    Session,
+    /// Operator endpoint guarded by the admin token
+    Admin,
+    /// Establishes who the caller is (login, registration)
+    Credentials,
+    /// Not user data (health check, route manifest, currencies, community import
+    /// presets), authorized by a signed link (export downloads) or by a
+    /// provider signature (inbound webhooks)
+    Public,
+}
+
+/// One entry of the route manifest
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteSpec {
+    pub method: String,
+    pub path: String,
+    pub ownership: Ownership,
+    pub mutating: bool,
+    pub budget: LatencyBudget,
+    pub scope: Option<String>,
+    pub dry_run: bool,
+    pub signed: bool,
+}
+
+impl RouteSpec {
+    fn new(method: &Method, path: String, ownership: Ownership) -> Self {
+        let mutating = !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+        let scope = match ownership {
+            Ownership::Admin | Ownership::Credentials | Ownership::Public | Ownership::Session => None,
+            Ownership::BodyUser => route_scope("create", &path),
+            Ownership::PathUser if mutating => route_scope("write", &path),
+            Ownership::PathUser => route_scope("read", &path),
+        };
+        Self {
+            method: method.to_string(),
+            mutating,
+            path,
+            ownership,
+            budget: LatencyBudget::Standard,
+            scope,
+            dry_run: false,
+            signed: false,
+        }
+    }
+}
+
+/// `{verb}:{resource}`, the resource being the path segment after `/api/`
+fn route_scope(verb: &str, path: &str) -> Option<String> {
+    let resource = path.strip_prefix("/api/")?.split('/').next()?;
+    Some(format!("{}:{}", verb, resource.replace('-', "_")))
+}
+
+/// Route builder for one `web::scope`
+pub struct ScopedRoutes {
+    prefix: &'static str,
+    routes: Vec<(RouteSpec, &'static str, Route)>,
+}
+
+impl ScopedRoutes {
+    pub fn new(prefix: &'static str) -> Self {
+        Self { prefix, routes: Vec::new() }
+    }
+
+    /// Register a route on a user-owned resource (path must carry `{user_id}`)
+    pub fn user<F, Args>(self, method: Method, path: &'static str, handler: F) -> Self
+    where
+        F: Handler<Args>,
+        Args: FromRequest + 'static,
+        F::Output: Responder + 'static,
+    {
+        assert!(
+            path.starts_with("/{user_id}") || path.starts_with("/user/{user_id}"),
+            "User-scoped route {}{} must start with /{{user_id}} or /user/{{user_id}}",
+            self.prefix,
+            path
+        );
+        self.add(method, path, Ownership::PathUser, handler)
+    }
+
+    /// Register a route on the caller's own login (path must start with `/me`)
+    pub fn session<F, Args>(self, method: Method, path: &'static str, handler: F) -> Self
+    where
+        F: Handler<Args>,
+        Args: FromRequest + 'static,
+        F::Output: Responder + 'static,
+    {
+        assert!(path.starts_with("/me"), "Session route {}{} must start with /me", self.prefix, path);
+        self.add(method, path, Ownership::Session, handler)
+    }
+
+    /// Register a collection create route whose owner is `user_id` in the body
+    pub fn create<F, Args>(self, path: &'static str, handler: F) -> Self
+    where
+        F: Handler<Args>,
+        Args: FromRequest + 'static,
+        F::Output: Responder + 'static,
+    {
+        self.add(Method::POST, path, Ownership::BodyUser, handler)
+    }
+
+    /// Register an operator route (handler must call the admin authorization check)
+    pub fn admin<F, Args>(self, method: Method, path: &'static str, handler: F) -> Self
+    where
+        F: Handler<Args>,
+        Args: FromRequest + 'static,
+        F::Output: Responder + 'static,
+    {
+        self.add(method, path, Ownership::Admin, handler)
+    }
+
+    /// Register a `POST` route that checks credentials instead of an owner
+    pub fn credentials<F, Args>(self, path: &'static str, handler: F) -> Self
+    where
+        F: Handler<Args>,
+        Args: FromRequest + 'static,
+        F::Output: Responder + 'static,
+    {
+        self.add(Method::POST, path, Ownership::Credentials, handler)
+    }
+
+    /// Register a `GET` route on reference data that belongs to no user
+    pub fn public<F, Args>(self, path: &'static str, handler: F) -> Self
+    where
+        F: Handler<Args>,
+        Args: FromRequest + 'static,
+        F::Output: Responder + 'static,
+    {
+        self.add(Method::GET, path, Ownership::Public, handler)
+    }
+
+    /// Register a `POST` route that only accepts deliveries signed by a provider
+    pub fn signed<F, Args>(mut self, path: &'static str, handler: F) -> Self
+    where
+        F: Handler<Args>,
+        Args: FromRequest + 'static,
+        F::Output: Responder + 'static,
+    {
+        self = self.add(Method::POST, path, Ownership::Public, handler);
+        let (spec, _, _) = self.routes.last_mut().expect("route was just added");
+        spec.signed = true;
+        self
+    }
+
+    fn add<F, Args>(mut self, method: Method, path: &'static str, ownership: Ownership, handler: F) -> Self
+    where
+        F: Handler<Args>,
+        Args: FromRequest + 'static,
+        F::Output: Responder + 'static,
+    {
+        let spec = RouteSpec::new(&method, format!("{}{}", self.prefix, path), ownership);
+        self.routes.push((spec, path, web::route().method(method).to(handler)));
+        self
+    }
+
+    /// Give the most recently registered route the extended latency budget
+    pub fn extended(mut self) -> Self {
+        let (spec, _, _) = self.routes.last_mut().expect("extended() must follow a route");
+        spec.budget = LatencyBudget::Extended;
+        self
+    }
+
+    /// Let the most recently registered route be called as a dry run
+    pub fn dry_run(mut self) -> Self {
+        let (spec, _, _) = self.routes.last_mut().expect("dry_run() must follow a route");
+        assert!(spec.mutating, "Dry run of read-only route {}", spec.path);
+        spec.dry_run = true;
+        self
+    }
+
+    /// Manifest entries for this scope
+    pub fn specs(&self) -> Vec<RouteSpec> {
+        self.routes.iter().map(|(spec, _, _)| spec.clone()).collect()
+    }
+
+    /// Build the actix scope with every registered route
+    pub fn into_service(self) -> impl HttpServiceFactory {
+        self.routes
+            .into_iter()
+            .fold(web::scope(self.prefix), |scope, (spec, path, route)| {
+                let (budget, ownership, mutating) = (spec.budget, spec.ownership, spec.mutating);
+                let (required, dry_run) = (spec.scope.clone(), spec.dry_run);
+                let signed = spec.signed;
+                let route = route
+                    .wrap(from_fn(log_control::sample))
+                    .wrap(from_fn(move |req, next| provider_webhooks::verify(signed, req, next)))
+                    .wrap(from_fn(move |req, next| dry_run::enforce(dry_run, req, next)))
+                    .wrap(from_fn(move |req, next| auth::enforce(ownership, req, next)))
+                    .wrap(from_fn(move |req, next| impersonation::enforce(ownership, mutating, req, next)))
+                    .wrap(from_fn(move |req, next| api_keys::enforce(ownership, required.clone(), req, next)))
+                    .wrap(from_fn(cache::consistency))
+                    .wrap(from_fn(move |req, next| timeouts::enforce(budget, req, next)));
+                scope.route(path, route)
+            })
+    }
+
+    /// Register the scope on an app's `ServiceConfig`
+    pub fn register(self, cfg: &mut web::ServiceConfig) {
+        cfg.service(self.into_service());
+    }
+}
+
+// ==================== Route Manifest ====================
+
+/// Every API route of the service
+pub fn manifest() -> Vec<RouteSpec> {
+    let mut specs = vec![
+        RouteSpec::new(&Method::GET, "/health".to_string(), Ownership::Public),
+        RouteSpec::new(&Method::GET, "/metrics".to_string(), Ownership::Public),
+        RouteSpec::new(&Method::GET, "/api/routes".to_string(), Ownership::Public),
+        RouteSpec::new(&Method::GET, "/api/openapi.json".to_string(), Ownership::Public),
+    ];
+    specs.extend(crate::docs::routes().specs());
+    specs.extend(crate::wallets::routes().specs());
+    specs.extend(crate::wallet_types::routes().specs());
+    specs.extend(crate::transactions::routes().specs());
+    specs.extend(crate::transaction_fields::routes().specs());
+    specs.extend(crate::templates::routes().specs());
+    specs.extend(crate::import_presets::routes().specs());
+    specs.extend(crate::income_sources::routes().specs());
+    specs.extend(crate::categories::routes().specs());
+    specs.extend(crate::charts::routes().specs());
+    specs.extend(crate::reimbursements::routes().specs());
+    specs.extend(crate::debts::routes().specs());
+    specs.extend(crate::audit::routes().specs());
+    specs.extend(crate::drafts::routes().specs());
+    specs.extend(crate::standing_orders::routes().specs());
+    specs.extend(crate::installments::routes().specs());
+    specs.extend(crate::recurring_transactions::routes().specs());
+    specs.extend(crate::top_ups::routes().specs());
+    specs.extend(crate::transfers::routes().specs());
+    specs.extend(crate::budgets::routes().specs());
+    specs.extend(crate::goals::routes().specs());
+    specs.extend(crate::groups::routes().specs());
+    specs.extend(crate::currencies::routes().specs());
+    specs.extend(crate::devices::routes().specs());
+    specs.extend(crate::notifications::routes().specs());
+    specs.extend(crate::webhooks::routes().specs());
+    specs.extend(crate::trash::routes().specs());
+    specs.extend(crate::impersonation::routes().specs());
+    specs.extend(crate::tax::routes().specs());
+    specs.extend(crate::reports::routes().specs());
+    specs.extend(crate::feed::routes().specs());
+    specs.extend(crate::dashboard::routes().specs());
+    specs.extend(crate::data_export::routes().specs());
+    specs.extend(crate::export_jobs::routes().specs());
+    specs.extend(crate::account_merges::routes().specs());
+    specs.extend(crate::provider_webhooks::routes().specs());
+    specs.extend(crate::login_devices::routes().specs());
+    specs.extend(crate::budget_alerts::routes().specs());
+    specs.extend(crate::admin::routes().specs());
+    specs.extend(crate::admin_ui::routes().specs());
+    specs.extend(crate::api_keys::routes().specs());
+    specs.extend(crate::auth::routes().specs());
+    specs
+}
+
+/// `GET /api/routes` - machine-readable route manifest
+pub async fn get_route_manifest() -> HttpResponse {
+    HttpResponse::Ok().json(ApiResponse::success(manifest()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_mutating_route_is_owned_or_signed() {
+        // Signed routes are authorized by the provider's signature
+        let unowned: Vec<String> = manifest()
+            .iter()
+            .filter(|s| s.mutating && s.ownership == Ownership::Public && !s.signed)
+            .map(|s| format!("{} {}", s.method, s.path))
+            .collect();
+        assert!(unowned.is_empty(), "Mutating routes without ownership: {}", unowned.join(", "));
+    }
+}
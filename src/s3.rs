@@ -0,0 +1,241 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, StatusCode, Url};
+use sha2::{Digest, Sha256};
+
+use crate::attachments::StorageBackend;
+use crate::config::AppConfig;
+
+// ==================== S3-COMPATIBLE STORAGE ====================
+//
+// `StorageBackend` over the S3 REST API (AWS S3, MinIO, R2, ...), signed
+// with AWS Signature Version 4. Objects are addressed path-style
+// (`{endpoint}/{bucket}/{key}`), which every S3-compatible service accepts.
+//
+// Downloads can also be handed out as presigned GET URLs (query-string
+// authentication), which work without credentials until they expire.
+//
+// Only single-request PUT/GET/DELETE are used. That is enough for objects up
+// to the 5 GB single-PUT limit; larger ones would need multipart upload.
+//
+// ============================================================================
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Connection settings for one bucket
+#[derive(Clone)]
+pub struct S3Settings {
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+impl S3Settings {
+    /// Attachment bucket settings, or `None` to keep attachments on local disk
+    pub fn attachments_from_config(config: &AppConfig) -> Option<Self> {
+        Some(Self {
+            endpoint: config.attachments_s3_endpoint.clone().filter(|v| !v.is_empty())?,
+            bucket: config.attachments_s3_bucket.clone().filter(|v| !v.is_empty())?,
+            region: config.attachments_s3_region.clone(),
+            access_key: config.attachments_s3_access_key.clone().unwrap_or_default(),
+            secret_key: config.attachments_s3_secret_key.clone().unwrap_or_default(),
+        })
+    }
+
+    /// Backup bucket settings, or `None` if backups are not configured
+    pub fn backups_from_config(config: &AppConfig) -> Option<Self> {
+        Some(Self {
+            endpoint: config.backup_s3_endpoint.clone().filter(|v| !v.is_empty())?,
+            bucket: config.backup_s3_bucket.clone().filter(|v| !v.is_empty())?,
+            region: config.backup_s3_region.clone(),
+            access_key: config.backup_s3_access_key.clone().unwrap_or_default(),
+            secret_key: config.backup_s3_secret_key.clone().unwrap_or_default(),
+        })
+    }
+}
+
+/// Stores blobs as objects in an S3-compatible bucket
+pub struct S3Storage {
+    client: Client,
+    settings: S3Settings,
+}
+
+impl S3Storage {
+    pub fn new(settings: S3Settings) -> Self {
+        Self { client: Client::new(), settings }
+    }
+
+    /// Path-style object URL; each key segment is percent-encoded
+    fn object_url(&self, key: &str) -> std::io::Result<Url> {
+        let path: Vec<String> = key.split('/').map(uri_encode).collect();
+        let url = format!(
+            "{}/{}/{}",
+            self.settings.endpoint.trim_end_matches('/'),
+            uri_encode(&self.settings.bucket),
+            path.join("/")
+        );
+        Url::parse(&url).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))
+    }
+
+    /// `host[:port]` of an object URL, as signed
+    fn host(url: &Url) -> std::io::Result<String> {
+        match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => Ok(format!("{}:{}", host, port)),
+            (Some(host), None) => Ok(host.to_string()),
+            (None, _) => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "S3 endpoint has no host")),
+        }
+    }
+
+    /// SigV4 signature of `string_to_sign` with the key derived for `date` (YYYYMMDD)
+    fn sign(&self, date: &str, string_to_sign: &str) -> String {
+        let mut signing_key = hmac(format!("AWS4{}", self.settings.secret_key).as_bytes(), date.as_bytes());
+        for part in [self.settings.region.as_str(), "s3", "aws4_request"] {
+            signing_key = hmac(&signing_key, part.as_bytes());
+        }
+        hex::encode(hmac(&signing_key, string_to_sign.as_bytes()))
+    }
+
+    /// Send a SigV4-signed request for `key`
+    async fn send(&self, method: Method, key: &str, body: Vec<u8>) -> std::io::Result<reqwest::Response> {
+        let url = self.object_url(key)?;
+        let host = Self::host(&url)?;
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method.as_str(),
+            url.path(),
+            host,
+            payload_hash,
+            amz_date,
+            signed_headers,
+            payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.settings.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let signature = self.sign(&date, &string_to_sign);
+
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.settings.access_key, scope, signed_headers, signature
+        );
+
+        self.client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", authorization)
+            .body(body)
+            .send()
+            .await
+            .map_err(std::io::Error::other)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for S3Storage {
+    async fn put(&self, key: &str, data: &[u8]) -> std::io::Result<()> {
+        let response = self.send(Method::PUT, key, data.to_vec()).await?;
+        check_status(response, key).await.map(|_| ())
+    }
+
+    async fn get(&self, key: &str) -> std::io::Result<Vec<u8>> {
+        let response = self.send(Method::GET, key, Vec::new()).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("S3 object not found: {}", key),
+            ));
+        }
+        let response = check_status(response, key).await?;
+        let bytes = response.bytes().await.map_err(std::io::Error::other)?;
+        Ok(bytes.to_vec())
+    }
+
+    async fn delete(&self, key: &str) -> std::io::Result<()> {
+        let response = self.send(Method::DELETE, key, Vec::new()).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        check_status(response, key).await.map(|_| ())
+    }
+
+    /// A presigned GET (SigV4 query authentication); S3 caps `expires_in` at 7 days
+    fn presigned_url(&self, key: &str, expires_in: Duration) -> Option<String> {
+        let mut url = self.object_url(key).ok()?;
+        let host = Self::host(&url).ok()?;
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let scope = format!("{}/{}/s3/aws4_request", date, self.settings.region);
+        let credential = format!("{}/{}", self.settings.access_key, scope);
+
+        // Already in the sorted order SigV4 requires
+        let query = format!(
+            "X-Amz-Algorithm=AWS4-HMAC-SHA256&X-Amz-Credential={}&X-Amz-Date={}&X-Amz-Expires={}&X-Amz-SignedHeaders=host",
+            uri_encode(&credential),
+            amz_date,
+            expires_in.as_secs().clamp(1, 604_800)
+        );
+        let canonical_request = format!("GET\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD", url.path(), query, host);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let signature = self.sign(&date, &string_to_sign);
+
+        url.set_query(Some(&format!("{}&X-Amz-Signature={}", query, signature)));
+        Some(url.to_string())
+    }
+}
+
+/// Turn a non-2xx response into an error carrying the S3 error body
+async fn check_status(response: reqwest::Response, key: &str) -> std::io::Result<reqwest::Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    Err(std::io::Error::other(format!(
+        "S3 request for {} failed with {}: {}",
+        key,
+        status,
+        body.chars().take(500).collect::<String>()
+    )))
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode everything except RFC 3986 unreserved characters (SigV4 rules)
+fn uri_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
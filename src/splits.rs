@@ -0,0 +1,559 @@
+use actix_web::{web, HttpResponse};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::cache::{get_or_set_cache, invalidate_user_cache, user_cache_key, CacheBackend};
+use crate::currencies;
+use crate::freezes::{self, FreezeError};
+use crate::hooks::{self, DebtPaid, HookContext, WalletsDebited};
+use crate::models::{
+    ApiResponse, BillSplit, BillSplitDetail, CounterpartyBalance, CreateBillSplitRequest,
+    CreateSettlementRequest, Debt, DebtSettlement, Transaction, Wallet,
+};
+use crate::standing_orders;
+use crate::money;
+use crate::telemetry;
+
+// ==================== BILL SPLITS ====================
+//
+// A user splits one of their expenses among named participants. Each
+// participant's share becomes an active receivable debt (`direction =
+// 'receivable'`, `creditor_name` = participant) linked to the split; the
+// remainder is the user's own share.
+//
+// Settlements (debt payments) are recorded against any debt, in either
+// direction. A debt is marked paid once its settlements cover the amount.
+// A payment can go through a wallet: an expense paying a payable or an
+// income collecting a receivable (category `debt_payment`), posted with the
+// settlement. Deleting that transaction drops the settlement, and a paid
+// debt that is no longer covered turns active again (a trigger).
+//
+// Balances net outstanding receivables against outstanding payables per
+// counterparty, matching names case-insensitively.
+//
+// ============================================================================
+
+/// Maximum number of participants in one split
+const MAX_PARTICIPANTS: usize = 50;
+
+/// Category of the wallet transactions of debt payments
+pub const DEBT_PAYMENT_CATEGORY: &str = "debt_payment";
+
+const SETTLEMENT_COLUMNS: &str = "id, debt_id, user_id, amount, note, settled_at, wallet_id, transaction_id";
+
+// ==================== Handlers ====================
+
+/// Split an expense transaction among participants
+pub async fn create_split(
+    req: web::Json<CreateBillSplitRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let transaction = match fetch_user_transaction(db.get_ref(), req.transaction_id, &req.user_id).await {
+        Ok(Some(t)) if t.transaction_type == "expense" => t,
+        Ok(Some(_)) => {
+            return HttpResponse::BadRequest()
+                .json(ApiResponse::<BillSplitDetail>::error("Only expenses can be split".to_string()));
+        }
+        Ok(None) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<BillSplitDetail>::error("Transaction not found".to_string()));
+        }
+        Err(e) => {
+            log::error!("Error fetching transaction for split: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<BillSplitDetail>::error("Database error".to_string()));
+        }
+    };
+
+    let shares = match compute_shares(&req, &transaction.amount) {
+        Ok(shares) => shares,
+        Err(msg) => return HttpResponse::BadRequest().json(ApiResponse::<BillSplitDetail>::error(msg)),
+    };
+
+    match insert_split(db.get_ref(), &req, &transaction, shares).await {
+        Ok(Some(detail)) => {
+            let _ = invalidate_user_cache(cache.get_ref(), &req.user_id).await;
+            HttpResponse::Created().json(ApiResponse::success(detail))
+        }
+        Ok(None) => HttpResponse::Conflict()
+            .json(ApiResponse::<BillSplitDetail>::error("Transaction is already split".to_string())),
+        Err(e) => {
+            log::error!("Error creating bill split: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<BillSplitDetail>::error("Failed to create split".to_string()))
+        }
+    }
+}
+
+/// Get a split with its receivables
+pub async fn get_split(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let (user_id, split_id) = path.into_inner();
+
+    match fetch_split_detail(db.get_ref(), &split_id, &user_id).await {
+        Ok(Some(detail)) => HttpResponse::Ok().json(ApiResponse::success(detail)),
+        Ok(None) => HttpResponse::NotFound()
+            .json(ApiResponse::<BillSplitDetail>::error("Split not found".to_string())),
+        Err(e) => {
+            log::error!("Error fetching bill split: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<BillSplitDetail>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Net outstanding balance per counterparty (with caching)
+pub async fn get_balances(
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, "debt_balances").await;
+
+    let result = get_or_set_cache(
+        cache.get_ref(),
+        &cache_key,
+        fetch_balances(db.get_ref(), &user_id),
+    )
+    .await;
+
+    match result {
+        Ok(balances) => HttpResponse::Ok().json(ApiResponse::success(balances)),
+        Err(e) => HttpResponse::InternalServerError()
+            .json(ApiResponse::<Vec<CounterpartyBalance>>::error(e.to_string())),
+    }
+}
+
+/// Record a (partial) settlement of a debt, optionally through a wallet
+pub async fn create_settlement(
+    path: web::Path<(String, String)>,
+    req: web::Json<CreateSettlementRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, debt_id) = path.into_inner();
+
+    if req.amount <= BigDecimal::from(0) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<DebtSettlement>::error("Settlement amount must be positive".to_string()));
+    }
+    if req.note.as_ref().is_some_and(|n| n.chars().count() > 500) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<DebtSettlement>::error("Note exceeds 500 characters".to_string()));
+    }
+
+    match insert_settlement(db.get_ref(), &debt_id, &user_id, &req).await {
+        Ok(Ok((settlement, paid))) => {
+            let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+            let cx = HookContext { pool: db.get_ref(), cache: cache.get_ref() };
+            if let Some(wallet_id) = settlement.wallet_id {
+                hooks::wallets_debited(&cx, WalletsDebited { owner_id: &user_id, wallet_ids: &[wallet_id] }).await;
+            }
+            if let Some(debt) = paid {
+                hooks::debt_paid(&cx, DebtPaid { debt: &debt }).await;
+            }
+            HttpResponse::Created().json(ApiResponse::success(settlement))
+        }
+        Ok(Err(SettlementRejection::NotFound)) => HttpResponse::NotFound()
+            .json(ApiResponse::<DebtSettlement>::error("Debt not found".to_string())),
+        Ok(Err(SettlementRejection::NotActive)) => HttpResponse::Conflict()
+            .json(ApiResponse::<DebtSettlement>::error("Debt is not active".to_string())),
+        Ok(Err(SettlementRejection::ExceedsOutstanding(outstanding))) => HttpResponse::BadRequest()
+            .json(ApiResponse::<DebtSettlement>::error(format!(
+                "Settlement exceeds outstanding amount {}",
+                outstanding
+            ))),
+        Ok(Err(SettlementRejection::WalletNotFound(wallet_id))) => HttpResponse::BadRequest()
+            .json(ApiResponse::<DebtSettlement>::error(format!("Wallet {} not found", wallet_id))),
+        Ok(Err(SettlementRejection::InsufficientFunds { available, required })) => HttpResponse::BadRequest()
+            .json(ApiResponse::<DebtSettlement>::error(format!(
+                "Insufficient balance. Available: {}, Required: {}",
+                available, required
+            ))),
+        Ok(Err(SettlementRejection::NoRate(msg))) => {
+            HttpResponse::Conflict().json(ApiResponse::<DebtSettlement>::error(msg))
+        }
+        Ok(Err(SettlementRejection::Frozen(e))) => e.to_response::<DebtSettlement>(),
+        Err(e) => {
+            log::error!("Error recording settlement: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<DebtSettlement>::error("Failed to record settlement".to_string()))
+        }
+    }
+}
+
+/// List the settlements (payments) of a debt, oldest first
+pub async fn get_settlements(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, debt_id) = path.into_inner();
+
+    let debt: Result<Option<(Uuid,)>, sqlx::Error> =
+        sqlx::query_as("SELECT id FROM debts WHERE id::text = $1 AND user_id = $2")
+            .bind(&debt_id)
+            .bind(&user_id)
+            .fetch_optional(db.get_ref())
+            .await;
+    let result = match debt {
+        Ok(Some((debt_id,))) => fetch_settlements(db.get_ref(), debt_id).await,
+        Ok(None) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<Vec<DebtSettlement>>::error("Debt not found".to_string()));
+        }
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(settlements) => HttpResponse::Ok().json(ApiResponse::success(settlements)),
+        Err(e) => {
+            log::error!("Error fetching settlements: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<DebtSettlement>>::error("Database error".to_string()))
+        }
+    }
+}
+
+// ==================== Split Computation ====================
+
+/// Resolve each participant's share (name, amount) of `total`
+///
+/// With no explicit amounts the total is divided equally among the user and
+/// participants (`money::split_evenly`); leftover cents go to the user first.
+fn compute_shares(req: &CreateBillSplitRequest, total: &BigDecimal) -> Result<Vec<(String, BigDecimal)>, String> {
+    if req.participants.is_empty() || req.participants.len() > MAX_PARTICIPANTS {
+        return Err(format!("A split needs 1-{} participants", MAX_PARTICIPANTS));
+    }
+
+    let mut names: Vec<String> = Vec::with_capacity(req.participants.len());
+    for participant in &req.participants {
+        let name = participant.name.trim();
+        if name.is_empty() || name.chars().count() > 255 {
+            return Err("Participant names must be 1-255 characters".to_string());
+        }
+        if names.iter().any(|n| n.to_lowercase() == name.to_lowercase()) {
+            return Err(format!("Participant '{}' is listed twice", name));
+        }
+        names.push(name.to_string());
+    }
+
+    let explicit = req.participants.iter().filter(|p| p.amount.is_some()).count();
+    let zero = BigDecimal::from(0);
+
+    if explicit == 0 {
+        // The first part is the user's
+        let shares = money::split_evenly(total, names.len() + 1);
+        if shares.iter().any(|share| *share <= zero) {
+            return Err("Amount is too small to split".to_string());
+        }
+        return Ok(names.into_iter().zip(shares.into_iter().skip(1)).collect());
+    }
+    if explicit != names.len() {
+        return Err("Give an amount for every participant or for none".to_string());
+    }
+
+    let amounts: Vec<BigDecimal> = req.participants.iter().filter_map(|p| p.amount.clone()).collect();
+    if amounts.iter().any(|a| *a <= zero) {
+        return Err("Participant amounts must be positive".to_string());
+    }
+    let assigned: BigDecimal = amounts.iter().sum();
+    if &assigned > total {
+        return Err("Participant amounts exceed the transaction amount".to_string());
+    }
+
+    Ok(names.into_iter().zip(amounts).collect())
+}
+
+// ==================== Database Functions ====================
+
+/// Why a settlement was not recorded
+enum SettlementRejection {
+    NotFound,
+    NotActive,
+    ExceedsOutstanding(BigDecimal),
+    WalletNotFound(Uuid),
+    InsufficientFunds { available: BigDecimal, required: BigDecimal },
+    NoRate(String),
+    Frozen(FreezeError),
+}
+
+/// Insert the split and one receivable per share; `None` if already split
+async fn insert_split(
+    pool: &PgPool,
+    req: &CreateBillSplitRequest,
+    transaction: &Transaction,
+    shares: Vec<(String, BigDecimal)>,
+) -> Result<Option<BillSplitDetail>, sqlx::Error> {
+    let assigned: BigDecimal = shares.iter().map(|(_, amount)| amount).sum();
+    let now = Utc::now();
+
+    let mut db_tx = pool.begin().await?;
+
+    let split = sqlx::query_as::<_, BillSplit>(
+        "INSERT INTO bill_splits (id, user_id, transaction_id, total_amount, user_share, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (transaction_id) DO NOTHING
+         RETURNING id, user_id, transaction_id, total_amount, user_share, created_at",
+    )
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(transaction.id)
+    .bind(&transaction.amount)
+    .bind(&transaction.amount - assigned)
+    .bind(now)
+    .fetch_optional(&mut *db_tx)
+    .await?;
+
+    let Some(split) = split else {
+        return Ok(None);
+    };
+
+    let mut debts = Vec::with_capacity(shares.len());
+    for (name, amount) in shares {
+        let debt = sqlx::query_as::<_, Debt>(
+            "INSERT INTO debts (id, user_id, wallet_id, creditor_name, amount, interest_rate, due_date,
+                                status, created_at, updated_at, direction, split_id)
+             VALUES ($1, $2, $3, $4, $5, 0, $6, 'active', $7, $7, 'receivable', $8)
+             RETURNING *",
+        )
+        .bind(Uuid::new_v4())
+        .bind(&req.user_id)
+        .bind(transaction.wallet_id)
+        .bind(name)
+        .bind(amount)
+        .bind(req.due_date)
+        .bind(now)
+        .bind(split.id)
+        .fetch_one(&mut *db_tx)
+        .await?;
+        debts.push(debt);
+    }
+
+    db_tx.commit().await?;
+    Ok(Some(BillSplitDetail { split, shares: debts }))
+}
+
+/// Lock the debt, check the outstanding amount and record the settlement;
+/// returns it with the debt if this settlement paid it off
+///
+/// With a wallet, a payable is paid with an expense from it and a receivable
+/// collected with an income into it, converted from the user's base currency
+/// to the wallet's, in the same DB transaction.
+async fn insert_settlement(
+    pool: &PgPool,
+    debt_id: &str,
+    user_id: &str,
+    req: &CreateSettlementRequest,
+) -> Result<Result<(DebtSettlement, Option<Debt>), SettlementRejection>, sqlx::Error> {
+    let mut db_tx = pool.begin().await?;
+
+    let debt: Option<(Uuid, BigDecimal, String, String, String)> = sqlx::query_as(
+        "SELECT id, amount, status, direction, creditor_name FROM debts
+         WHERE id::text = $1 AND user_id = $2 FOR UPDATE",
+    )
+    .bind(debt_id)
+    .bind(user_id)
+    .fetch_optional(&mut *db_tx)
+    .await?;
+
+    let Some((debt_id, amount, status, direction, creditor_name)) = debt else {
+        return Ok(Err(SettlementRejection::NotFound));
+    };
+    if status != "active" {
+        return Ok(Err(SettlementRejection::NotActive));
+    }
+
+    let (settled,): (BigDecimal,) =
+        sqlx::query_as("SELECT COALESCE(SUM(amount), 0) FROM debt_settlements WHERE debt_id = $1")
+            .bind(debt_id)
+            .fetch_one(&mut *db_tx)
+            .await?;
+    let outstanding = amount - settled;
+    if req.amount > outstanding {
+        return Ok(Err(SettlementRejection::ExceedsOutstanding(outstanding)));
+    }
+
+    let now = Utc::now();
+    let transaction_id = match req.wallet_id {
+        Some(wallet_id) => {
+            let wallet = sqlx::query_as::<_, Wallet>(&format!(
+                "SELECT {} FROM wallets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE",
+                standing_orders::WALLET_COLUMNS
+            ))
+            .bind(wallet_id)
+            .bind(user_id)
+            .fetch_optional(&mut *db_tx)
+            .await?;
+            let Some(wallet) = wallet else {
+                return Ok(Err(SettlementRejection::WalletNotFound(wallet_id)));
+            };
+
+            let (base,): (String,) = sqlx::query_as("SELECT base_currency($1)")
+                .bind(user_id)
+                .fetch_one(&mut *db_tx)
+                .await?;
+            let Some(conversion) = currencies::convert(&mut *db_tx, &req.amount, &base, &wallet.currency).await?
+            else {
+                return Ok(Err(SettlementRejection::NoRate(currencies::no_rate(&base, &wallet.currency))));
+            };
+
+            let paying = direction == "payable";
+            if paying
+                && let Some(available) = standing_orders::sendable(&mut db_tx, &wallet).await?
+                && conversion.amount > available
+            {
+                return Ok(Err(SettlementRejection::InsufficientFunds { available, required: conversion.amount }));
+            }
+
+            let description = match (paying, req.note.as_deref().map(str::trim)) {
+                (true, Some(note)) if !note.is_empty() => format!("Payment to {}: {}", creditor_name, note),
+                (true, _) => format!("Payment to {}", creditor_name),
+                (false, Some(note)) if !note.is_empty() => format!("Payment from {}: {}", creditor_name, note),
+                (false, _) => format!("Payment from {}", creditor_name),
+            };
+            let transaction_type = if paying { "expense" } else { "income" };
+            let transaction_id = Uuid::new_v4();
+            sqlx::query(
+                "INSERT INTO transactions (id, user_id, wallet_id, amount, transaction_type, category, description, created_at, updated_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)",
+            )
+            .bind(transaction_id)
+            .bind(user_id)
+            .bind(wallet.id)
+            .bind(&conversion.amount)
+            .bind(transaction_type)
+            .bind(DEBT_PAYMENT_CATEGORY)
+            .bind(description)
+            .bind(now)
+            .execute(&mut *db_tx)
+            .await?;
+
+            let delta = if paying { -conversion.amount.clone() } else { conversion.amount.clone() };
+            sqlx::query("UPDATE wallets SET balance = balance + $1 WHERE id = $2")
+                .bind(delta)
+                .bind(wallet.id)
+                .execute(&mut *db_tx)
+                .await?;
+            match freezes::ensure_not_frozen(&mut db_tx, wallet.id).await {
+                Ok(()) => {}
+                Err(FreezeError::Database(e)) => return Err(e),
+                Err(e) => return Ok(Err(SettlementRejection::Frozen(e))),
+            }
+            Some(transaction_id)
+        }
+        None => None,
+    };
+
+    let settlement = sqlx::query_as::<_, DebtSettlement>(&format!(
+        "INSERT INTO debt_settlements (id, debt_id, user_id, amount, note, settled_at, wallet_id, transaction_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING {}",
+        SETTLEMENT_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(debt_id)
+    .bind(user_id)
+    .bind(&req.amount)
+    .bind(&req.note)
+    .bind(now)
+    .bind(req.wallet_id)
+    .bind(transaction_id)
+    .fetch_one(&mut *db_tx)
+    .await?;
+
+    let paid = if req.amount == outstanding {
+        let debt = sqlx::query_as::<_, Debt>(
+            "UPDATE debts SET status = 'paid', updated_at = $1 WHERE id = $2 RETURNING *",
+        )
+        .bind(debt_id)
+        .fetch_one(&mut *db_tx)
+        .await?;
+        Some(debt)
+    } else {
+        None
+    };
+
+    db_tx.commit().await?;
+    Ok(Ok((settlement, paid)))
+}
+
+/// Settlements recorded against a debt, oldest first
+pub async fn fetch_settlements(pool: &PgPool, debt_id: Uuid) -> Result<Vec<DebtSettlement>, sqlx::Error> {
+    telemetry::sql(
+        "debt_settlements",
+        "select",
+        sqlx::query_as::<_, DebtSettlement>(&format!(
+            "SELECT {} FROM debt_settlements WHERE debt_id = $1 ORDER BY settled_at ASC",
+            SETTLEMENT_COLUMNS
+        ))
+        .bind(debt_id)
+        .fetch_all(pool),
+    )
+    .await
+}
+
+async fn fetch_split_detail(
+    pool: &PgPool,
+    split_id: &str,
+    user_id: &str,
+) -> Result<Option<BillSplitDetail>, sqlx::Error> {
+    let split = sqlx::query_as::<_, BillSplit>(
+        "SELECT id, user_id, transaction_id, total_amount, user_share, created_at FROM bill_splits
+         WHERE id::text = $1 AND user_id = $2",
+    )
+    .bind(split_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(split) = split else {
+        return Ok(None);
+    };
+
+    let shares = sqlx::query_as::<_, Debt>("SELECT * FROM debts WHERE split_id = $1 ORDER BY creditor_name")
+        .bind(split.id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(Some(BillSplitDetail { split, shares }))
+}
+
+async fn fetch_balances(pool: &PgPool, user_id: &str) -> Result<Vec<CounterpartyBalance>, sqlx::Error> {
+    sqlx::query_as::<_, CounterpartyBalance>(
+        "WITH outstanding AS (
+             SELECT d.creditor_name, d.direction,
+                    d.amount - COALESCE((SELECT SUM(s.amount) FROM debt_settlements s WHERE s.debt_id = d.id), 0)
+                        AS remaining
+             FROM debts d
+             WHERE d.user_id = $1 AND d.status = 'active'
+         )
+         SELECT MIN(trim(creditor_name)) AS counterparty,
+                COALESCE(SUM(remaining) FILTER (WHERE direction = 'receivable'), 0) AS owed_to_you,
+                COALESCE(SUM(remaining) FILTER (WHERE direction = 'payable'), 0) AS you_owe,
+                COALESCE(SUM(CASE WHEN direction = 'receivable' THEN remaining ELSE -remaining END), 0) AS net
+         FROM outstanding
+         GROUP BY lower(trim(creditor_name))
+         ORDER BY net DESC, counterparty",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+async fn fetch_user_transaction(
+    pool: &PgPool,
+    transaction_id: Uuid,
+    user_id: &str,
+) -> Result<Option<Transaction>, sqlx::Error> {
+    sqlx::query_as::<_, Transaction>(
+        "SELECT id, sequence_number, user_id, wallet_id, bucket_id, income_source_id, amount, currency, transaction_type, category, description, notes, metadata, created_at, updated_at, version
+         FROM transactions WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+    )
+    .bind(transaction_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
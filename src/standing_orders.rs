@@ -0,0 +1,675 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Datelike, Duration, Months, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::cache::{invalidate_user_cache, CacheBackend};
+use crate::currencies;
+use crate::freezes::{self, FreezeError};
+use crate::jobs;
+use crate::models::{
+    ApiResponse, CreateStandingOrderRequest, StandingOrder, StandingOrderFrequency, StandingOrderMode,
+    StandingOrderRun, UpdateStandingOrderRequest, Wallet,
+};
+use crate::routes::ScopedRoutes;
+use crate::wallet_types;
+
+// ==================== STANDING ORDERS ====================
+//
+// Standing orders move money between two of a user's wallets on a schedule:
+// a fixed amount, or a sweep of everything in the source wallet above a floor
+// (e.g. "keep 500 in checking, move the rest to savings").
+//
+// A run posts an expense on the source wallet and an income on the
+// destination (category "transfer"), so balance replay and reports see the
+// transfer like any other pair of transactions. The source must cover the
+// amount the way an expense would: its unallocated balance for asset wallets,
+// its available credit for credit-limit wallets. A sweep only looks at the
+// unallocated balance, so bucket allocations are never swept.
+//
+// Amounts (and the sweep floor) are in the source wallet's currency; a
+// destination in another currency is credited the converted amount, and a
+// run between currencies without a rate fails (see currencies.rs).
+//
+// `STANDING_ORDER_JOB` claims each (order, scheduled time) in
+// `job_executions`, then records the run in `standing_order_runs` as
+// `completed`, `skipped` (a sweep with nothing above the floor) or `failed`
+// (insufficient funds, a frozen wallet). Failed runs are pushed to the user's
+// devices by the alert job (see alerts.rs). A failed run is not retried; the
+// order simply runs again at its next scheduled time. Runs missed while the
+// service was down are not backfilled.
+//
+// ============================================================================
+
+pub const STANDING_ORDER_JOB: &str = "standing_orders";
+
+/// Category of the transactions a run posts
+pub const TRANSFER_CATEGORY: &str = "transfer";
+
+/// Maximum number of standing orders per user
+const MAX_ORDERS_PER_USER: i64 = 50;
+
+/// Number of runs returned by the history endpoint
+const RUN_HISTORY_LIMIT: i64 = 100;
+
+const ORDER_COLUMNS: &str = "id, user_id, name, from_wallet_id, to_wallet_id, mode, amount, sweep_above, frequency, \
+     start_at, next_run_at, active, last_run_at, created_at, updated_at";
+
+const RUN_COLUMNS: &str = "id, order_id, user_id, status, scheduled_for, amount, error, created_at";
+
+pub(crate) const WALLET_COLUMNS: &str = "id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, created_at, updated_at, version";
+
+// ==================== Handlers ====================
+
+/// List a user's standing orders
+pub async fn get_user_standing_orders(user_id: web::Path<String>, db: web::Data<PgPool>) -> HttpResponse {
+    let result = sqlx::query_as::<_, StandingOrder>(&format!(
+        "SELECT {} FROM standing_orders WHERE user_id = $1 ORDER BY created_at",
+        ORDER_COLUMNS
+    ))
+    .bind(user_id.into_inner())
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(orders) => HttpResponse::Ok().json(ApiResponse::success(orders)),
+        Err(e) => {
+            log::error!("Error fetching standing orders: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<StandingOrder>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Create a standing order
+pub async fn create_standing_order(
+    req: web::Json<CreateStandingOrderRequest>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let name = req.name.trim();
+    if let Err(msg) = validate_name(name) {
+        return HttpResponse::BadRequest().json(ApiResponse::<StandingOrder>::error(msg));
+    }
+    if req.from_wallet_id == req.to_wallet_id {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<StandingOrder>::error("Source and destination wallets must differ".to_string()));
+    }
+    let amounts = match req.mode {
+        StandingOrderMode::Fixed if req.sweep_above.is_some() => Err("sweep_above only applies to sweeps".to_string()),
+        StandingOrderMode::Fixed => validate_amount(req.amount.as_ref()).map(|()| (req.amount.clone(), None)),
+        StandingOrderMode::Sweep if req.amount.is_some() => Err("amount only applies to fixed orders".to_string()),
+        StandingOrderMode::Sweep => validate_sweep_above(req.sweep_above.as_ref()).map(|()| (None, req.sweep_above.clone())),
+    };
+    let (amount, sweep_above) = match amounts {
+        Ok(amounts) => amounts,
+        Err(msg) => return HttpResponse::BadRequest().json(ApiResponse::<StandingOrder>::error(msg)),
+    };
+
+    let now = Utc::now();
+    let start_at = req.start_at.unwrap_or(now);
+    if start_at < now - Duration::minutes(1) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<StandingOrder>::error("start_at must not be in the past".to_string()));
+    }
+
+    // Both wallets must be the user's; sweeps need a source that holds money
+    let mut source = None;
+    for wallet_id in [req.from_wallet_id, req.to_wallet_id] {
+        match fetch_user_wallet(db.get_ref(), wallet_id, &req.user_id).await {
+            Ok(Some(wallet)) => source = source.or(Some(wallet)),
+            Ok(None) => {
+                return HttpResponse::BadRequest()
+                    .json(ApiResponse::<StandingOrder>::error(format!("Wallet {} not found", wallet_id)));
+            }
+            Err(e) => {
+                log::error!("Error fetching wallet: {}", e);
+                return HttpResponse::InternalServerError()
+                    .json(ApiResponse::<StandingOrder>::error("Database error".to_string()));
+            }
+        }
+    }
+    if req.mode == StandingOrderMode::Sweep
+        && let Some(source) = &source
+    {
+        match wallet_types::template_for(db.get_ref(), source).await {
+            Ok(template) if template.is_liability() => {
+                return HttpResponse::BadRequest().json(ApiResponse::<StandingOrder>::error(
+                    "Only asset wallets can be swept".to_string(),
+                ));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                log::error!("Error resolving wallet type: {}", e);
+                return HttpResponse::InternalServerError()
+                    .json(ApiResponse::<StandingOrder>::error("Failed to validate wallet".to_string()));
+            }
+        }
+    }
+
+    let count: Result<(i64,), sqlx::Error> = sqlx::query_as("SELECT COUNT(*) FROM standing_orders WHERE user_id = $1")
+        .bind(&req.user_id)
+        .fetch_one(db.get_ref())
+        .await;
+    match count {
+        Ok((count,)) if count >= MAX_ORDERS_PER_USER => {
+            return HttpResponse::BadRequest().json(ApiResponse::<StandingOrder>::error(format!(
+                "A user can have at most {} standing orders",
+                MAX_ORDERS_PER_USER
+            )));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Error counting standing orders: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<StandingOrder>::error("Database error".to_string()));
+        }
+    }
+
+    let result = sqlx::query_as::<_, StandingOrder>(&format!(
+        "INSERT INTO standing_orders
+             (id, user_id, name, from_wallet_id, to_wallet_id, mode, amount, sweep_above, frequency,
+              start_at, next_run_at, active, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $10, TRUE, $11, $11)
+         RETURNING {}",
+        ORDER_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(name)
+    .bind(req.from_wallet_id)
+    .bind(req.to_wallet_id)
+    .bind(req.mode.as_str())
+    .bind(amount)
+    .bind(sweep_above)
+    .bind(req.frequency.as_str())
+    .bind(start_at)
+    .bind(now)
+    .fetch_one(db.get_ref())
+    .await;
+
+    match result {
+        Ok(order) => HttpResponse::Created().json(ApiResponse::success(order)),
+        Err(e) => {
+            log::error!("Error creating standing order: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<StandingOrder>::error("Failed to create standing order".to_string()))
+        }
+    }
+}
+
+/// Rename, change the amount/floor, or pause/resume a standing order
+pub async fn update_standing_order(
+    path: web::Path<(String, String)>,
+    req: web::Json<UpdateStandingOrderRequest>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let (user_id, order_id) = path.into_inner();
+    let name = req.name.as_deref().map(str::trim);
+    if let Some(name) = name
+        && let Err(msg) = validate_name(name)
+    {
+        return HttpResponse::BadRequest().json(ApiResponse::<StandingOrder>::error(msg));
+    }
+
+    let order = match fetch_order(db.get_ref(), &order_id, &user_id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<StandingOrder>::error("Standing order not found".to_string()));
+        }
+        Err(e) => {
+            log::error!("Error fetching standing order: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<StandingOrder>::error("Database error".to_string()));
+        }
+    };
+    let (Some(mode), Some(frequency)) = (
+        StandingOrderMode::from_str(&order.mode),
+        StandingOrderFrequency::from_str(&order.frequency),
+    ) else {
+        log::error!("Standing order {} has an invalid configuration", order.id);
+        return HttpResponse::InternalServerError()
+            .json(ApiResponse::<StandingOrder>::error("Database error".to_string()));
+    };
+
+    let checked = match mode {
+        StandingOrderMode::Fixed if req.sweep_above.is_some() => Err("sweep_above only applies to sweeps".to_string()),
+        StandingOrderMode::Fixed if req.amount.is_some() => validate_amount(req.amount.as_ref()),
+        StandingOrderMode::Sweep if req.amount.is_some() => Err("amount only applies to fixed orders".to_string()),
+        StandingOrderMode::Sweep if req.sweep_above.is_some() => validate_sweep_above(req.sweep_above.as_ref()),
+        _ => Ok(()),
+    };
+    if let Err(msg) = checked {
+        return HttpResponse::BadRequest().json(ApiResponse::<StandingOrder>::error(msg));
+    }
+
+    // Resuming picks up at the next scheduled time; runs missed while paused are skipped
+    let now = Utc::now();
+    let next_run_at = (req.active == Some(true) && !order.active && order.next_run_at <= now)
+        .then(|| next_occurrence(order.start_at, frequency, now));
+
+    let result = sqlx::query_as::<_, StandingOrder>(&format!(
+        "UPDATE standing_orders
+         SET name = COALESCE($1, name),
+             amount = COALESCE($2, amount),
+             sweep_above = COALESCE($3, sweep_above),
+             active = COALESCE($4, active),
+             next_run_at = COALESCE($5, next_run_at),
+             updated_at = $6
+         WHERE id = $7
+         RETURNING {}",
+        ORDER_COLUMNS
+    ))
+    .bind(name)
+    .bind(&req.amount)
+    .bind(&req.sweep_above)
+    .bind(req.active)
+    .bind(next_run_at)
+    .bind(now)
+    .bind(order.id)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(order)) => HttpResponse::Ok().json(ApiResponse::success(order)),
+        Ok(None) => HttpResponse::NotFound()
+            .json(ApiResponse::<StandingOrder>::error("Standing order not found".to_string())),
+        Err(e) => {
+            log::error!("Error updating standing order: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<StandingOrder>::error("Failed to update standing order".to_string()))
+        }
+    }
+}
+
+/// Cancel a standing order (its run history is removed with it)
+pub async fn delete_standing_order(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, order_id) = path.into_inner();
+
+    let result = sqlx::query("DELETE FROM standing_orders WHERE id::text = $1 AND user_id = $2")
+        .bind(&order_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await;
+
+    match result {
+        Ok(query_result) if query_result.rows_affected() > 0 => HttpResponse::NoContent().finish(),
+        Ok(_) => HttpResponse::NotFound().json(ApiResponse::<String>::error("Standing order not found".to_string())),
+        Err(e) => {
+            log::error!("Error deleting standing order: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<String>::error("Failed to delete standing order".to_string()))
+        }
+    }
+}
+
+/// Run history of a standing order (most recent first)
+pub async fn get_standing_order_runs(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, order_id) = path.into_inner();
+
+    let result = sqlx::query_as::<_, StandingOrderRun>(&format!(
+        "SELECT {} FROM standing_order_runs
+         WHERE order_id::text = $1 AND user_id = $2
+         ORDER BY created_at DESC
+         LIMIT $3",
+        RUN_COLUMNS
+    ))
+    .bind(&order_id)
+    .bind(&user_id)
+    .bind(RUN_HISTORY_LIMIT)
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(runs) => HttpResponse::Ok().json(ApiResponse::success(runs)),
+        Err(e) => {
+            log::error!("Error fetching standing order runs: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<StandingOrderRun>>::error("Database error".to_string()))
+        }
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err("Name must be 1-100 characters".to_string());
+    }
+    Ok(())
+}
+
+fn validate_amount(amount: Option<&BigDecimal>) -> Result<(), String> {
+    match amount {
+        Some(amount) if *amount > BigDecimal::from(0) => Ok(()),
+        Some(_) => Err("amount must be greater than 0".to_string()),
+        None => Err("amount is required for fixed orders".to_string()),
+    }
+}
+
+fn validate_sweep_above(sweep_above: Option<&BigDecimal>) -> Result<(), String> {
+    match sweep_above {
+        Some(floor) if *floor >= BigDecimal::from(0) => Ok(()),
+        Some(_) => Err("sweep_above must not be negative".to_string()),
+        None => Err("sweep_above is required for sweeps".to_string()),
+    }
+}
+
+// ==================== Schedule ====================
+
+/// The `n`-th run after `start_at` (monthly runs keep the start day, clamped to short months)
+fn occurrence(start_at: DateTime<Utc>, frequency: StandingOrderFrequency, n: u32) -> Option<DateTime<Utc>> {
+    match frequency {
+        StandingOrderFrequency::Daily => start_at.checked_add_signed(Duration::days(i64::from(n))),
+        StandingOrderFrequency::Weekly => start_at.checked_add_signed(Duration::weeks(i64::from(n))),
+        StandingOrderFrequency::Monthly => start_at.checked_add_months(Months::new(n)),
+    }
+}
+
+/// First scheduled run strictly after `after`
+pub(crate) fn next_occurrence(start_at: DateTime<Utc>, frequency: StandingOrderFrequency, after: DateTime<Utc>) -> DateTime<Utc> {
+    if start_at > after {
+        return start_at;
+    }
+    // Start just below the answer and step forward
+    let elapsed = match frequency {
+        StandingOrderFrequency::Daily => (after - start_at).num_days(),
+        StandingOrderFrequency::Weekly => (after - start_at).num_weeks(),
+        StandingOrderFrequency::Monthly => {
+            i64::from(after.year() - start_at.year()) * 12 + i64::from(after.month()) - i64::from(start_at.month()) - 1
+        }
+    };
+    let mut n = u32::try_from(elapsed.max(0)).unwrap_or(u32::MAX);
+    loop {
+        match occurrence(start_at, frequency, n) {
+            Some(at) if at > after => return at,
+            Some(_) => n = n.saturating_add(1),
+            None => return DateTime::<Utc>::MAX_UTC,
+        }
+    }
+}
+
+// ==================== Background Job ====================
+
+/// How a run ended
+enum RunOutcome {
+    Completed(BigDecimal),
+    Skipped(String),
+    Failed(Option<BigDecimal>, String),
+}
+
+/// Run every due standing order; returns the number of completed transfers
+pub async fn run_due_orders(pool: &PgPool, cache: &dyn CacheBackend) -> Result<u64, sqlx::Error> {
+    let due: Vec<(Uuid,)> =
+        sqlx::query_as("SELECT id FROM standing_orders WHERE active AND next_run_at <= CURRENT_TIMESTAMP")
+            .fetch_all(pool)
+            .await?;
+
+    let mut completed = 0;
+    for (order_id,) in due {
+        match run_order(pool, order_id).await {
+            Ok(Some((user_id, true))) => {
+                completed += 1;
+                let _ = invalidate_user_cache(cache, &user_id).await;
+            }
+            Ok(_) => {}
+            Err(e) => log::error!("Failed to run standing order {}: {}", order_id, e),
+        }
+    }
+
+    Ok(completed)
+}
+
+/// Claim, execute and record one run of an order
+///
+/// Returns the owner and whether money moved, or `None` when the order was
+/// claimed by a concurrent run.
+async fn run_order(pool: &PgPool, order_id: Uuid) -> Result<Option<(String, bool)>, sqlx::Error> {
+    let mut db_tx = pool.begin().await?;
+
+    let order = sqlx::query_as::<_, StandingOrder>(&format!(
+        "SELECT {} FROM standing_orders
+         WHERE id = $1 AND active AND next_run_at <= CURRENT_TIMESTAMP
+         FOR UPDATE SKIP LOCKED",
+        ORDER_COLUMNS
+    ))
+    .bind(order_id)
+    .fetch_optional(&mut *db_tx)
+    .await?;
+    let Some(order) = order else {
+        db_tx.rollback().await?;
+        return Ok(None);
+    };
+
+    let (Some(mode), Some(frequency)) = (
+        StandingOrderMode::from_str(&order.mode),
+        StandingOrderFrequency::from_str(&order.frequency),
+    ) else {
+        db_tx.rollback().await?;
+        log::error!("Standing order {} has an invalid configuration", order.id);
+        return Ok(None);
+    };
+
+    let scheduled_for = order.next_run_at;
+    let occurrence = format!("{}:{}", order.id, scheduled_for.to_rfc3339());
+    if !jobs::claim_occurrence(&mut db_tx, STANDING_ORDER_JOB, &occurrence).await? {
+        db_tx.rollback().await?;
+        return Ok(None);
+    }
+
+    let now = Utc::now();
+    sqlx::query("UPDATE standing_orders SET next_run_at = $1, last_run_at = $2 WHERE id = $3")
+        .bind(next_occurrence(order.start_at, frequency, now))
+        .bind(now)
+        .bind(order.id)
+        .execute(&mut *db_tx)
+        .await?;
+
+    let outcome = transfer(&mut db_tx, &order, mode, now).await?;
+    let (status, amount, error) = match &outcome {
+        RunOutcome::Completed(amount) => ("completed", Some(amount.clone()), None),
+        RunOutcome::Skipped(reason) => ("skipped", None, Some(reason.clone())),
+        RunOutcome::Failed(amount, error) => ("failed", amount.clone(), Some(error.clone())),
+    };
+
+    sqlx::query(
+        "INSERT INTO standing_order_runs (id, order_id, user_id, status, scheduled_for, amount, error, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(order.id)
+    .bind(&order.user_id)
+    .bind(status)
+    .bind(scheduled_for)
+    .bind(&amount)
+    .bind(&error)
+    .bind(now)
+    .execute(&mut *db_tx)
+    .await?;
+
+    db_tx.commit().await?;
+
+    match &outcome {
+        RunOutcome::Completed(amount) => log::info!("Standing order {} moved {}", order.id, amount),
+        RunOutcome::Skipped(_) => {}
+        RunOutcome::Failed(_, error) => log::warn!("Standing order {} failed: {}", order.id, error),
+    }
+    Ok(Some((order.user_id, matches!(outcome, RunOutcome::Completed(_)))))
+}
+
+/// Move the order's amount, or explain why not (nothing is written unless it completes)
+async fn transfer(
+    conn: &mut PgConnection,
+    order: &StandingOrder,
+    mode: StandingOrderMode,
+    now: DateTime<Utc>,
+) -> Result<RunOutcome, sqlx::Error> {
+    // Lock both wallets in id order so concurrent transfers can't deadlock
+    let wallets = sqlx::query_as::<_, Wallet>(&format!(
+        "SELECT {} FROM wallets WHERE id = ANY($1) AND user_id = $2 AND deleted_at IS NULL ORDER BY id FOR UPDATE",
+        WALLET_COLUMNS
+    ))
+    .bind(vec![order.from_wallet_id, order.to_wallet_id])
+    .bind(&order.user_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let (Some(from), Some(to)) = (
+        wallets.iter().find(|w| w.id == order.from_wallet_id),
+        wallets.iter().find(|w| w.id == order.to_wallet_id),
+    ) else {
+        return Ok(RunOutcome::Failed(None, "Wallet not found".to_string()));
+    };
+
+    for wallet_id in [from.id, to.id] {
+        match freezes::ensure_not_frozen(&mut *conn, wallet_id).await {
+            Ok(()) => {}
+            Err(FreezeError::Frozen(_)) => {
+                return Ok(RunOutcome::Failed(order.amount.clone(), format!("Wallet {} is frozen", wallet_id)));
+            }
+            Err(FreezeError::Database(e)) => return Err(e),
+        }
+    }
+
+    let available = sendable(&mut *conn, from).await?;
+
+    let amount = match (mode, &order.amount, &order.sweep_above, &available) {
+        (StandingOrderMode::Fixed, Some(amount), _, _) => amount.clone(),
+        (StandingOrderMode::Sweep, _, Some(floor), Some(available)) => {
+            let excess = available - floor;
+            if excess <= BigDecimal::from(0) {
+                return Ok(RunOutcome::Skipped(format!("Nothing above {} to sweep", floor)));
+            }
+            excess
+        }
+        _ => return Ok(RunOutcome::Failed(None, "Invalid standing order".to_string())),
+    };
+    if let Some(available) = &available
+        && amount > *available
+    {
+        return Ok(RunOutcome::Failed(
+            Some(amount.clone()),
+            format!("Insufficient funds in {}. Available: {}, Required: {}", from.name, available, amount),
+        ));
+    }
+
+    let Some(incoming) = currencies::convert(&mut *conn, &amount, &from.currency, &to.currency).await? else {
+        return Ok(RunOutcome::Failed(Some(amount), currencies::no_rate(&from.currency, &to.currency)));
+    };
+
+    let description = format!("Standing order: {}", order.name);
+    post_transfer(&mut *conn, from, to, &amount, &incoming.amount, &description, now).await?;
+
+    Ok(RunOutcome::Completed(amount))
+}
+
+// ==================== Transfers ====================
+
+/// What `wallet` can send, as for an expense (None: uncapped liability)
+///
+/// Asset wallets send their unallocated balance, credit-limit wallets their
+/// available credit.
+pub(crate) async fn sendable(conn: &mut PgConnection, wallet: &Wallet) -> Result<Option<BigDecimal>, sqlx::Error> {
+    let template = wallet_types::template_for(&mut *conn, wallet).await?;
+    if template.supports_credit_limit {
+        return Ok(Some(wallet.available_balance(&template)));
+    }
+    if template.is_liability() {
+        return Ok(None);
+    }
+    let (allocated,): (BigDecimal,) =
+        sqlx::query_as("SELECT COALESCE(SUM(balance), 0) FROM wallet_buckets WHERE wallet_id = $1")
+            .bind(wallet.id)
+            .fetch_one(&mut *conn)
+            .await?;
+    Ok(Some(&wallet.balance - allocated))
+}
+
+/// Post a transfer as an expense of `out` on `from` and an income of `incoming` on `to`, and move the balances
+///
+/// `out` is in `from`'s currency and `incoming` in `to`'s (the same amount
+/// unless the currencies differ). The caller locks both wallets and checks
+/// funds and freezes first.
+pub(crate) async fn post_transfer(
+    conn: &mut PgConnection,
+    from: &Wallet,
+    to: &Wallet,
+    out: &BigDecimal,
+    incoming: &BigDecimal,
+    description: &str,
+    now: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    post_transfer_leg(&mut *conn, &from.user_id, from.id, "expense", out, description, now).await?;
+    post_transfer_leg(&mut *conn, &to.user_id, to.id, "income", incoming, description, now).await?;
+    Ok(())
+}
+
+/// Post one leg of a transfer and move the wallet's balance; returns the transaction id
+pub(crate) async fn post_transfer_leg(
+    conn: &mut PgConnection,
+    user_id: &str,
+    wallet_id: Uuid,
+    transaction_type: &str,
+    amount: &BigDecimal,
+    description: &str,
+    now: DateTime<Utc>,
+) -> Result<Uuid, sqlx::Error> {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO transactions (id, user_id, wallet_id, amount, transaction_type, category, description, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)",
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(wallet_id)
+    .bind(amount)
+    .bind(transaction_type)
+    .bind(TRANSFER_CATEGORY)
+    .bind(description)
+    .bind(now)
+    .execute(&mut *conn)
+    .await?;
+
+    let delta = if transaction_type == "income" { amount.clone() } else { -amount.clone() };
+    sqlx::query("UPDATE wallets SET balance = balance + $1 WHERE id = $2")
+        .bind(delta)
+        .bind(wallet_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(id)
+}
+
+// ==================== Database Functions ====================
+
+async fn fetch_user_wallet(pool: &PgPool, wallet_id: Uuid, user_id: &str) -> Result<Option<Wallet>, sqlx::Error> {
+    sqlx::query_as::<_, Wallet>(&format!("SELECT {} FROM wallets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL", WALLET_COLUMNS))
+        .bind(wallet_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+}
+
+async fn fetch_order(pool: &PgPool, order_id: &str, user_id: &str) -> Result<Option<StandingOrder>, sqlx::Error> {
+    sqlx::query_as::<_, StandingOrder>(&format!(
+        "SELECT {} FROM standing_orders WHERE id::text = $1 AND user_id = $2",
+        ORDER_COLUMNS
+    ))
+    .bind(order_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/standing-orders")
+        .create("", create_standing_order)
+        .user(Method::GET, "/{user_id}", get_user_standing_orders)
+        .user(Method::PUT, "/{user_id}/{order_id}", update_standing_order)
+        .user(Method::DELETE, "/{user_id}/{order_id}", delete_standing_order)
+        .user(Method::GET, "/{user_id}/{order_id}/runs", get_standing_order_runs)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
@@ -0,0 +1,690 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use actix_multipart::Multipart;
+use actix_web::{web, HttpResponse};
+use chrono::{NaiveDate, Utc};
+use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
+use sqlx::types::BigDecimal;
+use sqlx::{Connection, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::cache::{invalidate_user_cache, CacheBackend};
+use crate::hooks::{self, HookContext, WalletsDebited};
+use crate::import_presets::{self, clean_header, same_header, split_record};
+use crate::models::{
+    ApiResponse, CreateTransactionRequest, ImportMapping, ImportPreset, ImportPresetFields, ImportRowError,
+    ImportSummary, TransactionPolicy,
+};
+use crate::policy;
+use crate::transactions;
+
+// ==================== STATEMENT IMPORTS ====================
+//
+// `POST /api/transactions/{user_id}/import` books a bank statement into one
+// wallet. The body is `multipart/form-data` with:
+//
+// - `file`: the statement, CSV or OFX (`format` part, else `.ofx`/`.qfx`
+//   file names and files with an `<OFX>` tag are read as OFX)
+// - `wallet_id`: the wallet the statement belongs to
+// - CSV only, how to read the columns: `preset_id` (a saved or community
+//   import preset) or `mapping` (the preset fields as JSON, columns named as
+//   in the file's header row). With neither, the best preset matching the
+//   header row is used (see import_presets.rs).
+// - `category`: category of lines without one, default `uncategorized`
+//
+// The owner is the `{user_id}` path segment, as a multipart body has no JSON
+// `user_id` for the ownership guard to read.
+//
+// Lines are booked oldest first through `transactions::record_transaction`,
+// so balances, frozen wallets, buckets, the transaction policy and budget
+// thresholds apply as for a transaction entered by hand; the sanity limits
+// are confirmed, as the amounts come from the bank. Transactions are dated
+// at noon UTC on the statement date. Everything happens in one database
+// transaction, with a savepoint per line: a refused line is reported in
+// `errors` and the rest still go in.
+//
+// A line is skipped when the wallet already has a transaction imported from
+// a line with the same date, signed amount and description (the import
+// hash). Identical lines in one file are told apart by their occurrence, so
+// two equal coffees on one day both import once, and re-uploading an
+// overlapping statement imports only the new lines. Transactions entered by
+// hand carry no hash and are not matched.
+//
+// ============================================================================
+
+/// Maximum size of an uploaded statement
+const MAX_FILE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Maximum number of lines in one statement
+const MAX_IMPORT_ROWS: usize = 5000;
+
+/// Category of lines the statement doesn't categorize
+const DEFAULT_CATEGORY: &str = "uncategorized";
+
+/// Description of lines the statement doesn't describe
+const DEFAULT_DESCRIPTION: &str = "Imported transaction";
+
+/// Parts of the multipart upload
+struct Upload {
+    file_name: String,
+    data: Vec<u8>,
+    wallet_id: Option<String>,
+    format: Option<String>,
+    preset_id: Option<String>,
+    mapping: Option<String>,
+    category: Option<String>,
+}
+
+/// One line of a statement, read
+struct StatementLine {
+    row: usize,
+    date: NaiveDate,
+    amount: BigDecimal,                   // Signed: negative is money out
+    description: String,
+    category: Option<String>,
+}
+
+/// Where a CSV file keeps each field
+struct CsvLayout {
+    delimiter: char,
+    date_column: usize,
+    date_format: String,
+    amount_column: Option<usize>,
+    debit_column: Option<usize>,
+    credit_column: Option<usize>,
+    description_column: Option<usize>,
+    category_column: Option<usize>,
+    decimal_comma: bool,
+    expense_negative: bool,
+}
+
+// ==================== Handlers ====================
+
+/// Import a CSV or OFX bank statement into a wallet
+pub async fn import_statement(
+    user_id: web::Path<String>,
+    payload: Multipart,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    let upload = match read_upload(payload).await {
+        Ok(upload) => upload,
+        Err(response) => return response,
+    };
+
+    let Some(wallet_id) = upload.wallet_id.as_deref().and_then(|id| Uuid::parse_str(id.trim()).ok()) else {
+        return bad_request("wallet_id is required");
+    };
+    let wallet: Result<Option<(Uuid,)>, sqlx::Error> =
+        sqlx::query_as("SELECT id FROM wallets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL")
+            .bind(wallet_id)
+            .bind(&user_id)
+            .fetch_optional(db.get_ref())
+            .await;
+    match wallet {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<ImportSummary>::error("Wallet not found".to_string()));
+        }
+        Err(e) => {
+            log::error!("Error fetching wallet: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<ImportSummary>::error("Database error".to_string()));
+        }
+    }
+
+    let text = String::from_utf8_lossy(&upload.data).trim_start_matches('\u{feff}').to_string();
+    let format = match upload.format.as_deref().map(|f| f.trim().to_lowercase()) {
+        Some(format) if format == "csv" || format == "ofx" => format,
+        Some(_) => return bad_request("format must be 'csv' or 'ofx'"),
+        None => {
+            let file_name = upload.file_name.to_lowercase();
+            if file_name.ends_with(".ofx") || file_name.ends_with(".qfx") || text.to_ascii_uppercase().contains("<OFX>") {
+                "ofx".to_string()
+            } else {
+                "csv".to_string()
+            }
+        }
+    };
+
+    let mut preset_id = None;
+    let parsed = if format == "ofx" {
+        parse_ofx(&text)
+    } else {
+        let layout = match csv_layout(db.get_ref(), &user_id, &upload, &text).await {
+            Ok((layout, preset)) => {
+                preset_id = preset;
+                layout
+            }
+            Err(response) => return response,
+        };
+        Ok(parse_csv(&text, &layout))
+    };
+    let (mut lines, errors) = match parsed {
+        Ok(parsed) => parsed,
+        Err(message) => return bad_request(&message),
+    };
+    if lines.len() + errors.len() > MAX_IMPORT_ROWS {
+        return bad_request(&format!("A statement can have at most {} lines", MAX_IMPORT_ROWS));
+    }
+    if lines.is_empty() && errors.is_empty() {
+        return bad_request("The statement has no transactions");
+    }
+
+    // Oldest first, so balances move as they did at the bank
+    lines.sort_by_key(|line| line.date);
+
+    let transaction_policy = match policy::transaction_policy(db.get_ref(), cache.get_ref()).await {
+        Ok(transaction_policy) => transaction_policy,
+        Err(e) => {
+            log::error!("Error loading transaction policy: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<ImportSummary>::error("Database error".to_string()));
+        }
+    };
+
+    let category = upload
+        .category
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| DEFAULT_CATEGORY.to_string());
+    let batch = ImportBatch {
+        user_id: &user_id,
+        wallet_id,
+        category: &category,
+        transaction_policy: &transaction_policy,
+    };
+    let mut summary = ImportSummary {
+        import_id: Uuid::new_v4(),
+        wallet_id,
+        format,
+        preset_id,
+        imported: 0,
+        skipped: 0,
+        failed: 0,
+        transaction_ids: Vec::new(),
+        skipped_rows: Vec::new(),
+        errors,
+    };
+    if let Err(e) = book_lines(db.get_ref(), &batch, &upload.file_name, lines, &mut summary).await {
+        log::error!("Error importing statement into wallet {}: {}", wallet_id, e);
+        return HttpResponse::InternalServerError()
+            .json(ApiResponse::<ImportSummary>::error("Failed to import statement".to_string()));
+    }
+    summary.errors.sort_by_key(|error| error.row);
+    summary.failed = summary.errors.len();
+
+    let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+    if summary.imported > 0 {
+        let cx = HookContext { pool: db.get_ref(), cache: cache.get_ref() };
+        hooks::wallets_debited(&cx, WalletsDebited { owner_id: &user_id, wallet_ids: &[wallet_id] }).await;
+    }
+
+    log::info!(
+        target: "audit",
+        "Statement {} imported into wallet {} for user {}: {} imported, {} skipped, {} failed",
+        summary.import_id,
+        wallet_id,
+        user_id,
+        summary.imported,
+        summary.skipped,
+        summary.failed
+    );
+    HttpResponse::Ok().json(ApiResponse::success(summary))
+}
+
+fn bad_request(message: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(ApiResponse::<ImportSummary>::error(message.to_string()))
+}
+
+// ==================== Upload ====================
+
+async fn read_upload(mut payload: Multipart) -> Result<Upload, HttpResponse> {
+    let mut upload = Upload {
+        file_name: String::new(),
+        data: Vec::new(),
+        wallet_id: None,
+        format: None,
+        preset_id: None,
+        mapping: None,
+        category: None,
+    };
+    let mut has_file = false;
+
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(|e| bad_request(&e.to_string()))?;
+        let name = field.name().unwrap_or_default().to_string();
+        let file_name = field.content_disposition().and_then(|cd| cd.get_filename()).map(str::to_string);
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|e| bad_request(&e.to_string()))?;
+            if data.len() + chunk.len() > MAX_FILE_BYTES {
+                return Err(HttpResponse::PayloadTooLarge().json(ApiResponse::<ImportSummary>::error(format!(
+                    "The statement is larger than {} bytes",
+                    MAX_FILE_BYTES
+                ))));
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        let value = || Some(String::from_utf8_lossy(&data).to_string());
+        match name.as_str() {
+            "file" => {
+                upload.file_name = file_name.unwrap_or_else(|| "statement".to_string());
+                upload.data = data;
+                has_file = true;
+            }
+            "wallet_id" => upload.wallet_id = value(),
+            "format" => upload.format = value(),
+            "preset_id" => upload.preset_id = value(),
+            "mapping" => upload.mapping = value(),
+            "category" => upload.category = value(),
+            _ => {}
+        }
+    }
+
+    if !has_file || upload.data.is_empty() {
+        return Err(bad_request("Send the statement as the 'file' part"));
+    }
+    Ok(upload)
+}
+
+// ==================== CSV ====================
+
+/// How to read the CSV file, and the saved preset it comes from
+async fn csv_layout(
+    pool: &PgPool,
+    user_id: &str,
+    upload: &Upload,
+    text: &str,
+) -> Result<(CsvLayout, Option<Uuid>), HttpResponse> {
+    let Some(header_row) = import_presets::first_line(text.as_bytes()) else {
+        return Err(bad_request("The statement is empty"));
+    };
+
+    let (fields, preset_id) = if let Some(mapping) = &upload.mapping {
+        let mapping: ImportMapping = serde_json::from_str(mapping)
+            .map_err(|e| bad_request(&format!("Invalid mapping: {}", e)))?;
+        let delimiter = mapping.delimiter.clone().unwrap_or_else(|| ",".to_string());
+        let headers = split_record(&header_row, delimiter.chars().next().unwrap_or(','));
+        (mapping_fields(mapping, headers), None)
+    } else {
+        let preset = match &upload.preset_id {
+            Some(preset_id) => import_presets::fetch_usable_preset(pool, user_id, preset_id.trim()).await,
+            None => import_presets::fetch_presets(pool, Some(user_id))
+                .await
+                .map(|presets| import_presets::rank_presets(presets, &header_row).into_iter().next().map(|m| m.preset)),
+        };
+        match preset {
+            Ok(Some(preset)) => {
+                let preset_id = preset.id;
+                (preset_fields(preset, &header_row), Some(preset_id))
+            }
+            Ok(None) if upload.preset_id.is_some() => {
+                return Err(HttpResponse::NotFound()
+                    .json(ApiResponse::<ImportSummary>::error("Import preset not found".to_string())));
+            }
+            Ok(None) => {
+                return Err(bad_request(
+                    "No import preset matches the file's header row; send preset_id or mapping",
+                ));
+            }
+            Err(e) => {
+                log::error!("Error fetching import presets: {}", e);
+                return Err(HttpResponse::InternalServerError()
+                    .json(ApiResponse::<ImportSummary>::error("Database error".to_string())));
+            }
+        }
+    };
+
+    let fields = import_presets::normalize_fields(fields).map_err(|message| bad_request(&message))?;
+    let position = |column: &Option<String>| {
+        column.as_ref().and_then(|column| fields.headers.iter().position(|h| same_header(h, column)))
+    };
+    let layout = CsvLayout {
+        delimiter: fields.delimiter.as_deref().and_then(|d| d.chars().next()).unwrap_or(','),
+        date_column: position(&Some(fields.date_column.clone())).unwrap_or_default(),
+        date_format: fields.date_format.clone().unwrap_or_else(|| "%Y-%m-%d".to_string()),
+        amount_column: position(&fields.amount_column),
+        debit_column: position(&fields.debit_column),
+        credit_column: position(&fields.credit_column),
+        description_column: position(&fields.description_column),
+        category_column: position(&fields.category_column),
+        decimal_comma: fields.decimal_comma,
+        expense_negative: fields.expense_negative.unwrap_or(true),
+    };
+    Ok((layout, preset_id))
+}
+
+/// An inline mapping as preset fields over the file's header row
+fn mapping_fields(mapping: ImportMapping, headers: Vec<String>) -> ImportPresetFields {
+    ImportPresetFields {
+        name: "Upload mapping".to_string(),
+        bank: None,
+        delimiter: mapping.delimiter,
+        headers,
+        date_column: mapping.date_column,
+        date_format: mapping.date_format,
+        amount_column: mapping.amount_column,
+        debit_column: mapping.debit_column,
+        credit_column: mapping.credit_column,
+        description_column: mapping.description_column,
+        category_column: mapping.category_column,
+        decimal_comma: mapping.decimal_comma,
+        expense_negative: mapping.expense_negative,
+    }
+}
+
+/// A saved preset as preset fields over the file's header row
+///
+/// The file's own header row replaces the preset's, so the columns are
+/// found where this file has them.
+fn preset_fields(preset: ImportPreset, header_row: &str) -> ImportPresetFields {
+    let headers = split_record(header_row, preset.delimiter.chars().next().unwrap_or(','));
+    ImportPresetFields {
+        name: preset.name,
+        bank: preset.bank,
+        delimiter: Some(preset.delimiter),
+        headers,
+        date_column: preset.date_column,
+        date_format: Some(preset.date_format),
+        amount_column: preset.amount_column,
+        debit_column: preset.debit_column,
+        credit_column: preset.credit_column,
+        description_column: preset.description_column,
+        category_column: preset.category_column,
+        decimal_comma: preset.decimal_comma,
+        expense_negative: Some(preset.expense_negative),
+    }
+}
+
+/// Read the lines after the header row; rows are numbered by file line
+fn parse_csv(text: &str, layout: &CsvLayout) -> (Vec<StatementLine>, Vec<ImportRowError>) {
+    let mut lines = Vec::new();
+    let mut errors = Vec::new();
+
+    let rows = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .skip(1);
+    for (row, line) in rows {
+        let cells = split_record(line, layout.delimiter);
+        match read_csv_row(&cells, layout) {
+            Ok(Some((date, amount, description, category))) => lines.push(StatementLine {
+                row,
+                date,
+                amount,
+                description,
+                category,
+            }),
+            Ok(None) => {}
+            Err(error) => errors.push(ImportRowError { row, error }),
+        }
+    }
+    (lines, errors)
+}
+
+type CsvRow = (NaiveDate, BigDecimal, String, Option<String>);
+
+/// Date, signed amount, description and category of a row; `None` for rows
+/// moving no money (e.g. balance lines)
+fn read_csv_row(cells: &[String], layout: &CsvLayout) -> Result<Option<CsvRow>, String> {
+    let cell = |index: Option<usize>| index.and_then(|i| cells.get(i)).map(|c| clean_header(c));
+
+    let raw_date = cell(Some(layout.date_column)).unwrap_or_default();
+    let date = NaiveDate::parse_from_str(&raw_date, &layout.date_format)
+        .map_err(|_| format!("Invalid date '{}' (expected {})", raw_date, layout.date_format))?;
+
+    let amount = match layout.amount_column {
+        Some(_) => {
+            let raw = cell(layout.amount_column).unwrap_or_default();
+            let amount = parse_amount(&raw, layout.decimal_comma).ok_or_else(|| format!("Invalid amount '{}'", raw))?;
+            if layout.expense_negative { amount } else { -amount }
+        }
+        None => {
+            let mut amount = BigDecimal::from(0);
+            for (column, sign) in [(layout.credit_column, 1), (layout.debit_column, -1)] {
+                let raw = cell(column).unwrap_or_default();
+                if raw.is_empty() {
+                    continue;
+                }
+                let value = parse_amount(&raw, layout.decimal_comma).ok_or_else(|| format!("Invalid amount '{}'", raw))?;
+                amount += value.abs() * BigDecimal::from(sign);
+            }
+            amount
+        }
+    };
+    if amount == BigDecimal::from(0) {
+        return Ok(None);
+    }
+
+    let description = cell(layout.description_column).unwrap_or_default();
+    let category = cell(layout.category_column).filter(|c| !c.is_empty());
+    Ok(Some((date, amount, description, category)))
+}
+
+/// Parse a statement amount: currency signs and spaces dropped, thousands
+/// separators removed, `(12.50)` and `12.50-` negative
+fn parse_amount(raw: &str, decimal_comma: bool) -> Option<BigDecimal> {
+    let mut text: String = raw
+        .chars()
+        .filter(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | ',' | '(' | ')'))
+        .collect();
+    let mut negative = false;
+    if text.starts_with('(') && text.ends_with(')') {
+        negative = true;
+        text = text[1..text.len() - 1].to_string();
+    }
+    if let Some(stripped) = text.strip_suffix('-') {
+        negative = !negative;
+        text = stripped.to_string();
+    }
+    let text = if decimal_comma {
+        text.replace('.', "").replace(',', ".")
+    } else {
+        text.replace(',', "")
+    };
+
+    let amount = BigDecimal::from_str(&text).ok()?;
+    Some(if negative { -amount } else { amount })
+}
+
+// ==================== OFX ====================
+
+/// Read the `<STMTTRN>` entries of an OFX file; rows are numbered by entry
+fn parse_ofx(text: &str) -> Result<(Vec<StatementLine>, Vec<ImportRowError>), String> {
+    // Tags are matched in an uppercased copy; ASCII uppercasing keeps offsets
+    let upper = text.to_ascii_uppercase();
+    if !upper.contains("<OFX>") {
+        return Err("Not an OFX file: no <OFX> tag".to_string());
+    }
+
+    let mut lines = Vec::new();
+    let mut errors = Vec::new();
+    let starts: Vec<usize> = upper.match_indices("<STMTTRN>").map(|(i, _)| i).collect();
+    for (n, &start) in starts.iter().enumerate() {
+        let row = n + 1;
+        let end = [upper[start..].find("</STMTTRN>"), starts.get(n + 1).map(|next| next - start)]
+            .into_iter()
+            .flatten()
+            .min()
+            .map(|len| start + len)
+            .unwrap_or(text.len());
+        let entry = &text[start..end];
+        let entry_upper = &upper[start..end];
+        let value = |tag: &str| ofx_value(entry, entry_upper, tag);
+
+        let raw_date = value("DTPOSTED").unwrap_or_default();
+        let Some(date) = raw_date
+            .get(..8)
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y%m%d").ok())
+        else {
+            errors.push(ImportRowError { row, error: format!("Invalid DTPOSTED '{}'", raw_date) });
+            continue;
+        };
+        let raw_amount = value("TRNAMT").unwrap_or_default();
+        let decimal_comma = raw_amount.contains(',') && !raw_amount.contains('.');
+        let Some(amount) = parse_amount(&raw_amount, decimal_comma) else {
+            errors.push(ImportRowError { row, error: format!("Invalid TRNAMT '{}'", raw_amount) });
+            continue;
+        };
+        if amount == BigDecimal::from(0) {
+            continue;
+        }
+
+        let description = value("NAME").or_else(|| value("MEMO")).unwrap_or_default();
+        lines.push(StatementLine { row, date, amount, description, category: None });
+    }
+    Ok((lines, errors))
+}
+
+/// Value of an OFX tag: the text after `<TAG>` up to the next tag or line end
+fn ofx_value(entry: &str, entry_upper: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let start = entry_upper.find(&open)? + open.len();
+    let rest = &entry[start..];
+    let end = rest.find(['<', '\r', '\n']).unwrap_or(rest.len());
+    let value = rest[..end]
+        .trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&");
+    (!value.is_empty()).then_some(value)
+}
+
+// ==================== Booking ====================
+
+/// What every line of one upload shares
+struct ImportBatch<'a> {
+    user_id: &'a str,
+    wallet_id: Uuid,
+    category: &'a str,
+    transaction_policy: &'a TransactionPolicy,
+}
+
+/// Book the lines in one database transaction, filling in the summary
+async fn book_lines(
+    pool: &PgPool,
+    batch: &ImportBatch<'_>,
+    file_name: &str,
+    lines: Vec<StatementLine>,
+    summary: &mut ImportSummary,
+) -> Result<(), sqlx::Error> {
+    let hashes = import_hashes(&lines);
+
+    let mut db_tx = pool.begin().await?;
+    let existing: Vec<(String,)> =
+        sqlx::query_as("SELECT import_hash FROM transactions WHERE wallet_id = $1 AND import_hash = ANY($2)")
+            .bind(batch.wallet_id)
+            .bind(&hashes)
+            .fetch_all(&mut *db_tx)
+            .await?;
+    let existing: HashSet<String> = existing.into_iter().map(|(hash,)| hash).collect();
+
+    for (line, hash) in lines.into_iter().zip(hashes) {
+        if existing.contains(&hash) {
+            summary.skipped += 1;
+            summary.skipped_rows.push(line.row);
+            continue;
+        }
+
+        let mut savepoint = db_tx.begin().await?;
+        match book_line(&mut savepoint, pool, batch, &line, &hash).await? {
+            Ok(transaction_id) => {
+                savepoint.commit().await?;
+                summary.imported += 1;
+                summary.transaction_ids.push(transaction_id);
+            }
+            Err(error) => {
+                savepoint.rollback().await?;
+                summary.errors.push(ImportRowError { row: line.row, error });
+            }
+        }
+    }
+    summary.skipped_rows.sort_unstable();
+
+    sqlx::query(
+        "INSERT INTO transaction_imports (id, user_id, wallet_id, preset_id, file_name, format, imported, skipped, failed, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+    )
+    .bind(summary.import_id)
+    .bind(batch.user_id)
+    .bind(batch.wallet_id)
+    .bind(summary.preset_id)
+    .bind(file_name.chars().take(255).collect::<String>())
+    .bind(&summary.format)
+    .bind(summary.imported as i32)
+    .bind(summary.skipped as i32)
+    .bind(summary.errors.len() as i32)
+    .bind(Utc::now())
+    .execute(&mut *db_tx)
+    .await?;
+
+    db_tx.commit().await
+}
+
+/// Record one line; the inner `Err` is why it was refused
+async fn book_line(
+    conn: &mut PgConnection,
+    pool: &PgPool,
+    batch: &ImportBatch<'_>,
+    line: &StatementLine,
+    hash: &str,
+) -> Result<Result<Uuid, String>, sqlx::Error> {
+    let description = if line.description.is_empty() { DEFAULT_DESCRIPTION } else { &line.description };
+    let req = CreateTransactionRequest {
+        user_id: batch.user_id.to_string(),
+        wallet_id: batch.wallet_id,
+        amount: line.amount.abs().round(2),
+        transaction_type: if line.amount < BigDecimal::from(0) { "expense" } else { "income" }.to_string(),
+        category: line.category.clone().unwrap_or_else(|| batch.category.to_string()),
+        description: description.to_string(),
+        notes: None,
+        metadata: None,
+        bucket_id: None,
+        income_source_id: None,
+        splits: Vec::new(),
+        // The bank already moved the money
+        confirm: true,
+    };
+    let created = match transactions::record_transaction(conn, pool, batch.transaction_policy, &req).await {
+        Ok(created) => created,
+        Err(response) => return Ok(Err(transactions::rejection_reason(response))),
+    };
+
+    let booked_at = line.date.and_hms_opt(12, 0, 0).unwrap_or_default().and_utc();
+    sqlx::query("UPDATE transactions SET created_at = $1, updated_at = $1, import_hash = $2 WHERE id = $3")
+        .bind(booked_at)
+        .bind(hash)
+        .bind(created.transaction.id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(Ok(created.transaction.id))
+}
+
+/// Import hash of each line: date, signed amount, description and how many
+/// identical lines came before it in the file
+fn import_hashes(lines: &[StatementLine]) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    lines
+        .iter()
+        .map(|line| {
+            let key = format!(
+                "{}|{}|{}",
+                line.date,
+                line.amount.round(2),
+                line.description.trim().to_lowercase()
+            );
+            let occurrence = seen.entry(key.clone()).or_default();
+            *occurrence += 1;
+            hex::encode(Sha256::digest(format!("{}|{}", key, occurrence).as_bytes()))
+        })
+        .collect()
+}
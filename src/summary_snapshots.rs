@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::WalletCategoryTotal;
+
+// ==================== WALLET SUMMARY SNAPSHOTS ====================
+//
+// The wallet summary's month-to-date totals aggregate every transaction of
+// the month, which is slow for wallets with thousands of them. For those,
+// `wallet_summary_snapshots` keeps the totals (income, expenses, count and
+// per-category totals) of the month's transactions created before `as_of`,
+// and `month_to_date` adds only the ones created since:
+//
+//   totals(now) = snapshot(as_of) + SUM(transactions in [as_of, now])
+//
+// The balance itself needs no snapshot; it is kept on the wallet row.
+//
+// - `SNAPSHOT_JOB` retakes the snapshot of every wallet with at least
+//   `MIN_TRANSACTIONS` transactions this month, `as_of` `SNAPSHOT_LAG` in the
+//   past so transactions still being written are left to the delta, and drops
+//   snapshots of past months.
+// - A trigger on transactions and split lines drops a wallet's snapshots
+//   when a transaction created before their `as_of` is created, edited,
+//   deleted or restored (backdated entries, edits, imports). Summaries then
+//   aggregate the whole month until the next run.
+//
+// A change racing a run can be missed by the snapshot being taken; it is
+// corrected by the next run, which recomputes every snapshot.
+//
+// ============================================================================
+
+pub const SNAPSHOT_JOB: &str = "wallet_summary_snapshots";
+
+/// How often snapshots are retaken
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Transactions this month from which a wallet is snapshotted
+const MIN_TRANSACTIONS: i64 = 500;
+
+/// How far in the past a snapshot's `as_of` is placed
+const SNAPSHOT_LAG: chrono::Duration = chrono::Duration::minutes(5);
+
+/// A wallet's totals over part of a month
+pub(crate) struct MonthToDate {
+    pub income: BigDecimal,
+    pub expense: BigDecimal,
+    pub transaction_count: i64,
+    pub categories: Vec<WalletCategoryTotal>,
+}
+
+// ==================== Totals ====================
+
+/// Totals of a wallet's transactions created in `[month_start, until]`,
+/// from its snapshot of the month when one was taken before `until`
+pub(crate) async fn month_to_date(
+    pool: &PgPool,
+    wallet_id: Uuid,
+    user_id: &str,
+    month_start: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<MonthToDate, sqlx::Error> {
+    let snapshot: Option<(DateTime<Utc>, BigDecimal, BigDecimal, i64)> = sqlx::query_as(
+        "SELECT as_of, income, expense, transaction_count
+         FROM wallet_summary_snapshots
+         WHERE wallet_id = $1 AND period_start = $2 AND as_of <= $3",
+    )
+    .bind(wallet_id)
+    .bind(month_start.date_naive())
+    .bind(until)
+    .fetch_optional(pool)
+    .await?;
+    let from = snapshot.as_ref().map_or(month_start, |(as_of, ..)| *as_of);
+
+    let (income, expense, transaction_count): (BigDecimal, BigDecimal, i64) = sqlx::query_as(
+        "SELECT COALESCE(SUM(amount) FILTER (WHERE transaction_type = 'income'), 0),
+                COALESCE(SUM(amount) FILTER (WHERE transaction_type = 'expense'), 0),
+                COUNT(*)
+         FROM transactions
+         WHERE wallet_id = $1 AND user_id = $2 AND deleted_at IS NULL AND created_at >= $3 AND created_at <= $4",
+    )
+    .bind(wallet_id)
+    .bind(user_id)
+    .bind(from)
+    .bind(until)
+    .fetch_one(pool)
+    .await?;
+
+    let categories = sqlx::query_as::<_, WalletCategoryTotal>(
+        "SELECT category, transaction_type, SUM(total) AS total, SUM(count)::bigint AS count
+         FROM (
+             SELECT category, transaction_type, total, transaction_count AS count
+             FROM wallet_summary_snapshot_categories
+             WHERE wallet_id = $1 AND period_start = $5 AND $6
+             UNION ALL
+             SELECT category, transaction_type, SUM(amount), COUNT(DISTINCT id)
+             FROM v_transaction_category_lines
+             WHERE wallet_id = $1 AND user_id = $2 AND deleted_at IS NULL AND created_at >= $3 AND created_at <= $4
+             GROUP BY category, transaction_type
+         ) lines
+         GROUP BY category, transaction_type
+         ORDER BY transaction_type, total DESC, category",
+    )
+    .bind(wallet_id)
+    .bind(user_id)
+    .bind(from)
+    .bind(until)
+    .bind(month_start.date_naive())
+    .bind(snapshot.is_some())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(match snapshot {
+        Some((_, snapshot_income, snapshot_expense, snapshot_count)) => MonthToDate {
+            income: snapshot_income + income,
+            expense: snapshot_expense + expense,
+            transaction_count: snapshot_count + transaction_count,
+            categories,
+        },
+        None => MonthToDate {
+            income,
+            expense,
+            transaction_count,
+            categories,
+        },
+    })
+}
+
+// ==================== Snapshot Job ====================
+
+/// Retake the snapshots of this month's busy wallets and drop those of past
+/// months; returns how many snapshots were written
+pub async fn take_snapshots(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let as_of = Utc::now() - SNAPSHOT_LAG;
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "DELETE FROM wallet_summary_snapshots
+         WHERE period_start < date_trunc('month', $1 AT TIME ZONE 'UTC')::date",
+    )
+    .bind(as_of)
+    .execute(&mut *tx)
+    .await?;
+
+    let written = sqlx::query(
+        "WITH month AS (
+             SELECT date_trunc('month', $1 AT TIME ZONE 'UTC')::date AS period_start,
+                    date_trunc('month', $1 AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' AS month_start
+         )
+         INSERT INTO wallet_summary_snapshots (wallet_id, user_id, period_start, as_of, income, expense, transaction_count)
+         SELECT t.wallet_id, w.user_id, m.period_start, $1,
+                COALESCE(SUM(t.amount) FILTER (WHERE t.transaction_type = 'income'), 0),
+                COALESCE(SUM(t.amount) FILTER (WHERE t.transaction_type = 'expense'), 0),
+                COUNT(*)
+         FROM transactions t
+         JOIN wallets w ON w.id = t.wallet_id AND w.deleted_at IS NULL
+         CROSS JOIN month m
+         WHERE t.deleted_at IS NULL AND t.created_at >= m.month_start AND t.created_at < $1
+         GROUP BY t.wallet_id, w.user_id, m.period_start
+         HAVING COUNT(*) >= $2
+         ON CONFLICT (wallet_id, period_start) DO UPDATE
+             SET as_of = EXCLUDED.as_of, income = EXCLUDED.income, expense = EXCLUDED.expense,
+                 transaction_count = EXCLUDED.transaction_count, taken_at = CURRENT_TIMESTAMP",
+    )
+    .bind(as_of)
+    .bind(MIN_TRANSACTIONS)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    // Replace the category totals of the snapshots just taken
+    sqlx::query(
+        "DELETE FROM wallet_summary_snapshot_categories c
+         USING wallet_summary_snapshots s
+         WHERE c.wallet_id = s.wallet_id AND c.period_start = s.period_start AND s.as_of = $1",
+    )
+    .bind(as_of)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        "INSERT INTO wallet_summary_snapshot_categories (wallet_id, period_start, category, transaction_type, total, transaction_count)
+         SELECT s.wallet_id, s.period_start, l.category, l.transaction_type, SUM(l.amount), COUNT(DISTINCT l.id)
+         FROM wallet_summary_snapshots s
+         JOIN v_transaction_category_lines l
+             ON l.wallet_id = s.wallet_id AND l.deleted_at IS NULL
+            AND l.created_at >= s.period_start::timestamp AT TIME ZONE 'UTC' AND l.created_at < s.as_of
+         WHERE s.as_of = $1
+         GROUP BY s.wallet_id, s.period_start, l.category, l.transaction_type",
+    )
+    .bind(as_of)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(written)
+}
@@ -0,0 +1,385 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::cache::{get_or_set_cache, invalidate_user_cache, user_cache_key, CacheBackend};
+use crate::config::AppConfig;
+use crate::currencies;
+use crate::export::{render_csv, render_pdf, ReportTable};
+use crate::routes::ScopedRoutes;
+use crate::models::{
+    ApiResponse, ReportFormat, SetTaxCategoryRequest, TaxCategoryMapping, TaxCategoryTotal, TaxClassSummary,
+    TaxReport, TaxReportQuery, TaxReportTransaction,
+};
+
+// ==================== TAX CATEGORIES ====================
+//
+// Users map their transaction categories to a tax class (`TaxClass`):
+// taxable or exempt income, deductible, business expense, charitable. The
+// annual tax report sums a calendar year (UTC) by class and lists the
+// supporting transactions, as JSON or as a CSV/PDF for filing.
+//
+// A mapping applies by exact category name. Income classes only pick up the
+// category's income transactions and expense classes only its expenses, so a
+// category used both ways (e.g. "consulting") is never counted on the wrong
+// side.
+//
+// Amounts are converted to the user's base currency (see currencies.rs); the
+// report is refused with 409 while a wallet's currency has no rate to it.
+//
+// ============================================================================
+
+/// Maximum number of mapped categories per user
+const MAX_MAPPINGS_PER_USER: i64 = 200;
+
+const MAPPING_COLUMNS: &str = "id, user_id, category, tax_class, created_at, updated_at";
+
+/// Transactions of user $1 in [$2, $3) whose category is mapped, on the side its class applies to
+const CLASSIFIED_TRANSACTIONS: &str = "FROM transactions t
+     JOIN tax_category_mappings m ON m.user_id = t.user_id AND m.category = t.category
+     WHERE t.user_id = $1 AND t.deleted_at IS NULL AND NOT t.sandbox AND t.created_at >= $2 AND t.created_at < $3
+       AND t.transaction_type = CASE WHEN m.tax_class IN ('taxable_income', 'exempt_income')
+                                     THEN 'income' ELSE 'expense' END";
+
+// ==================== Handlers ====================
+
+/// List a user's category tax classes (with caching)
+pub async fn get_user_tax_categories(
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, "tax_categories").await;
+
+    let result = get_or_set_cache(cache.get_ref(), &cache_key, fetch_mappings(db.get_ref(), &user_id)).await;
+
+    match result {
+        Ok(mappings) => HttpResponse::Ok().json(ApiResponse::success(mappings)),
+        Err(e) => HttpResponse::InternalServerError()
+            .json(ApiResponse::<Vec<TaxCategoryMapping>>::error(e.to_string())),
+    }
+}
+
+/// Set a category's tax class, replacing any previous one
+pub async fn set_tax_category(
+    req: web::Json<SetTaxCategoryRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let category = req.category.trim();
+    if category.is_empty() || category.chars().count() > 100 {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<TaxCategoryMapping>::error("category must be 1-100 characters".to_string()));
+    }
+
+    let count: Result<(i64,), sqlx::Error> = sqlx::query_as(
+        "SELECT COUNT(*) FROM tax_category_mappings WHERE user_id = $1 AND category <> $2",
+    )
+    .bind(&req.user_id)
+    .bind(category)
+    .fetch_one(db.get_ref())
+    .await;
+    match count {
+        Ok((count,)) if count >= MAX_MAPPINGS_PER_USER => {
+            return HttpResponse::BadRequest().json(ApiResponse::<TaxCategoryMapping>::error(format!(
+                "A user can classify at most {} categories",
+                MAX_MAPPINGS_PER_USER
+            )));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Error counting tax categories: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<TaxCategoryMapping>::error("Database error".to_string()));
+        }
+    }
+
+    let now = Utc::now();
+    let result = sqlx::query_as::<_, TaxCategoryMapping>(&format!(
+        "INSERT INTO tax_category_mappings (id, user_id, category, tax_class, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $5)
+         ON CONFLICT (user_id, category) DO UPDATE SET tax_class = EXCLUDED.tax_class, updated_at = EXCLUDED.updated_at
+         RETURNING {}",
+        MAPPING_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(category)
+    .bind(req.tax_class.as_str())
+    .bind(now)
+    .fetch_one(db.get_ref())
+    .await;
+
+    match result {
+        Ok(mapping) => {
+            let _ = invalidate_user_cache(cache.get_ref(), &req.user_id).await;
+            HttpResponse::Ok().json(ApiResponse::success(mapping))
+        }
+        Err(e) => {
+            log::error!("Error setting tax category: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<TaxCategoryMapping>::error("Failed to set tax category".to_string()))
+        }
+    }
+}
+
+/// Remove a category's tax class
+pub async fn delete_tax_category(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, mapping_id) = path.into_inner();
+
+    let result = sqlx::query("DELETE FROM tax_category_mappings WHERE id::text = $1 AND user_id = $2")
+        .bind(&mapping_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await;
+
+    match result {
+        Ok(query_result) if query_result.rows_affected() > 0 => {
+            let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+            HttpResponse::NoContent().finish()
+        }
+        Ok(_) => HttpResponse::NotFound().json(ApiResponse::<String>::error("Tax category not found".to_string())),
+        Err(e) => {
+            log::error!("Error deleting tax category: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<String>::error("Failed to delete tax category".to_string()))
+        }
+    }
+}
+
+/// `GET /api/reports/{user_id}/tax/{year}` - classified totals and supporting transactions
+pub async fn get_tax_report(
+    path: web::Path<(String, i32)>,
+    query: web::Query<TaxReportQuery>,
+    db: web::Data<PgPool>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    let (user_id, year) = path.into_inner();
+    let (Some(start), Some(end)) = (year_start(year), year_start(year.saturating_add(1))) else {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<TaxReport>::error("year must be between 1900 and 2100".to_string()));
+    };
+
+    let currency = match currencies::reporting_currency(db.get_ref(), &user_id).await {
+        Ok(Ok(currency)) => currency,
+        Ok(Err(msg)) => return HttpResponse::Conflict().json(ApiResponse::<TaxReport>::error(msg)),
+        Err(e) => {
+            log::error!("Error checking exchange rates: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<TaxReport>::error("Database error".to_string()));
+        }
+    };
+
+    let report = match build_tax_report(db.get_ref(), &user_id, &currency, year, start, end, config.max_export_rows).await {
+        Ok(report) => report,
+        Err(e) => {
+            log::error!("Error building tax report: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<TaxReport>::error("Database error".to_string()));
+        }
+    };
+
+    let Some(format) = query.format else {
+        return HttpResponse::Ok().json(ApiResponse::success(report));
+    };
+
+    let tables = report_tables(&report);
+    let (bytes, content_type) = match format {
+        ReportFormat::Csv => (render_csv(&tables), "text/csv"),
+        ReportFormat::Pdf => match render_pdf(&format!("Tax report {}", year), &tables) {
+            Ok(bytes) => (bytes, "application/pdf"),
+            Err(e) => {
+                log::error!("Error rendering tax report: {}", e);
+                return HttpResponse::InternalServerError()
+                    .json(ApiResponse::<String>::error("Failed to render tax report".to_string()));
+            }
+        },
+    };
+
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"ketobook-tax-{}.{}\"", year, format.as_str()),
+        ))
+        .body(bytes)
+}
+
+/// Jan 1 00:00 UTC of `year`, for years 1900-2101 (the end of 2100)
+fn year_start(year: i32) -> Option<DateTime<Utc>> {
+    if !(1900..=2101).contains(&year) {
+        return None;
+    }
+    Some(NaiveDate::from_ymd_opt(year, 1, 1)?.and_time(NaiveTime::MIN).and_utc())
+}
+
+// ==================== Report ====================
+
+#[derive(sqlx::FromRow)]
+struct ClassCategoryTotal {
+    tax_class: String,
+    category: String,
+    total: BigDecimal,
+    count: i64,
+}
+
+#[derive(sqlx::FromRow)]
+struct ClassifiedTransaction {
+    tax_class: String,
+    transaction_id: Uuid,
+    created_at: DateTime<Utc>,
+    category: String,
+    amount: BigDecimal,
+    description: Option<String>,
+}
+
+async fn build_tax_report(
+    pool: &PgPool,
+    user_id: &str,
+    currency: &str,
+    year: i32,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    max_rows: i64,
+) -> Result<TaxReport, sqlx::Error> {
+    let totals = sqlx::query_as::<_, ClassCategoryTotal>(&format!(
+        "SELECT m.tax_class, t.category, SUM(convert_amount(t.amount, t.currency, $4)) AS total, COUNT(*) AS count
+         {}
+         GROUP BY m.tax_class, t.category
+         ORDER BY m.tax_class, total DESC, t.category",
+        CLASSIFIED_TRANSACTIONS
+    ))
+    .bind(user_id)
+    .bind(start)
+    .bind(end)
+    .bind(currency)
+    .fetch_all(pool)
+    .await?;
+
+    let mut transactions = sqlx::query_as::<_, ClassifiedTransaction>(&format!(
+        "SELECT m.tax_class, t.id AS transaction_id, t.created_at, t.category,
+                convert_amount(t.amount, t.currency, $5) AS amount, t.description
+         {}
+         ORDER BY m.tax_class, t.created_at, t.id
+         LIMIT $4",
+        CLASSIFIED_TRANSACTIONS
+    ))
+    .bind(user_id)
+    .bind(start)
+    .bind(end)
+    .bind(max_rows + 1)
+    .bind(currency)
+    .fetch_all(pool)
+    .await?;
+    let truncated = transactions.len() as i64 > max_rows;
+    transactions.truncate(max_rows as usize);
+
+    let mut classes: Vec<TaxClassSummary> = Vec::new();
+    for row in totals {
+        if classes.last().is_none_or(|c| c.tax_class != row.tax_class) {
+            classes.push(TaxClassSummary {
+                tax_class: row.tax_class.clone(),
+                total: BigDecimal::from(0),
+                count: 0,
+                categories: Vec::new(),
+                transactions: Vec::new(),
+            });
+        }
+        let class = classes.last_mut().expect("pushed above");
+        class.total += &row.total;
+        class.count += row.count;
+        class.categories.push(TaxCategoryTotal { category: row.category, total: row.total, count: row.count });
+    }
+    for row in transactions {
+        if let Some(class) = classes.iter_mut().find(|c| c.tax_class == row.tax_class) {
+            class.transactions.push(TaxReportTransaction {
+                transaction_id: row.transaction_id,
+                created_at: row.created_at,
+                category: row.category,
+                amount: row.amount,
+                description: row.description,
+            });
+        }
+    }
+
+    Ok(TaxReport { year, currency: currency.to_string(), classes, truncated })
+}
+
+/// Summary table, then one table of supporting transactions per class
+fn report_tables(report: &TaxReport) -> Vec<ReportTable> {
+    let summary = ReportTable {
+        title: format!("Tax summary {} ({})", report.year, report.currency),
+        headers: &["tax class", "category", "total", "transactions"],
+        rows: report
+            .classes
+            .iter()
+            .flat_map(|class| {
+                class
+                    .categories
+                    .iter()
+                    .map(|c| vec![class.tax_class.clone(), c.category.clone(), c.total.to_string(), c.count.to_string()])
+                    .chain(std::iter::once(vec![
+                        class.tax_class.clone(),
+                        "total".to_string(),
+                        class.total.to_string(),
+                        class.count.to_string(),
+                    ]))
+            })
+            .collect(),
+        truncated: false,
+    };
+
+    std::iter::once(summary)
+        .chain(report.classes.iter().map(|class| ReportTable {
+            title: format!("Transactions: {}", class.tax_class),
+            headers: &["date", "category", "amount", "description", "transaction id"],
+            rows: class
+                .transactions
+                .iter()
+                .map(|t| {
+                    vec![
+                        t.created_at.format("%Y-%m-%d").to_string(),
+                        t.category.clone(),
+                        t.amount.to_string(),
+                        t.description.clone().unwrap_or_default(),
+                        t.transaction_id.to_string(),
+                    ]
+                })
+                .collect(),
+            truncated: report.truncated,
+        }))
+        .collect()
+}
+
+// ==================== Database Functions ====================
+
+async fn fetch_mappings(pool: &PgPool, user_id: &str) -> Result<Vec<TaxCategoryMapping>, sqlx::Error> {
+    sqlx::query_as::<_, TaxCategoryMapping>(&format!(
+        "SELECT {} FROM tax_category_mappings WHERE user_id = $1 ORDER BY category",
+        MAPPING_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/tax-categories")
+        .create("", set_tax_category)
+        .user(Method::GET, "/{user_id}", get_user_tax_categories)
+        .user(Method::DELETE, "/{user_id}/{mapping_id}", delete_tax_category)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
@@ -0,0 +1,364 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use actix_web::body::BoxBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::Error;
+use opentelemetry::metrics::Histogram;
+use opentelemetry::trace::{FutureExt, SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::{global, Context, KeyValue};
+use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use sqlx::postgres::PgQueryResult;
+
+use crate::config::AppConfig;
+use crate::metrics;
+
+// ==================== TELEMETRY ====================
+//
+// Traces and metrics are exported over OTLP/HTTP when
+// `OTEL_EXPORTER_OTLP_ENDPOINT` is set (e.g. `http://localhost:4318`), so a
+// slow request can be followed from the route down to each cache and SQL call:
+//
+// - `trace_request` (wrapped around the whole app) opens a server span per
+//   request, named after the matched route pattern.
+// - `cache` spans every cache operation (see cache.rs) with its namespace (the
+//   first segment after the user namespace: `wallets`, `debt`, ...) and
+//   outcome (`hit`, `miss`, `error`, ...). The database fetch of a miss is a
+//   child of it.
+// - `sql` / `sql_one` span the shared repository reads (`fetch_*` for
+//   wallets, transactions, debts, settlements, buckets, ...) with the table,
+//   the operation and the number of rows returned.
+//
+// Each kind also records a duration histogram with the same attributes
+// (`http.server.request.duration`, `ketobook.cache.operation.duration`,
+// `db.client.operation.duration`). The Rust SDK does not sample exemplars
+// yet; metric points and spans are tied together by their attributes.
+//
+// The wrappers also feed the Prometheus metrics served at `/metrics` (see
+// metrics.rs), and `tag_request_id` puts the request's `X-Request-Id` on its
+// span, so a span can be found from a log line.
+//
+// Without an endpoint the global providers are no-ops and the wrappers only
+// cost a clock read. Queries written inline in handlers are not spanned
+// individually; they show up as time inside the request span.
+//
+// ============================================================================
+
+const INSTRUMENTATION_NAME: &str = "ketobook";
+
+/// Providers to flush when the server stops
+pub struct Telemetry {
+    tracer_provider: SdkTracerProvider,
+    meter_provider: SdkMeterProvider,
+}
+
+impl Telemetry {
+    pub fn shutdown(self) {
+        if let Err(e) = self.tracer_provider.shutdown() {
+            log::warn!("Failed to flush traces: {}", e);
+        }
+        if let Err(e) = self.meter_provider.shutdown() {
+            log::warn!("Failed to flush metrics: {}", e);
+        }
+    }
+}
+
+/// Install the OTLP exporters as the global providers, if configured
+///
+/// Must run before the first request, since instruments are created once.
+pub fn init(config: &AppConfig) -> Result<Option<Telemetry>, String> {
+    let Some(endpoint) = config.otel_exporter_otlp_endpoint.as_deref() else {
+        return Ok(None);
+    };
+    let endpoint = endpoint.trim_end_matches('/');
+    let resource = Resource::builder().with_service_name(config.otel_service_name.clone()).build();
+
+    let span_exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let tracer_provider = SdkTracerProvider::builder()
+        .with_batch_exporter(span_exporter)
+        .with_resource(resource.clone())
+        .build();
+
+    let metric_exporter = MetricExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/metrics", endpoint))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let meter_provider = SdkMeterProvider::builder()
+        .with_periodic_exporter(metric_exporter)
+        .with_resource(resource)
+        .build();
+
+    global::set_tracer_provider(tracer_provider.clone());
+    global::set_meter_provider(meter_provider.clone());
+    Ok(Some(Telemetry {
+        tracer_provider,
+        meter_provider,
+    }))
+}
+
+struct Instruments {
+    request_duration: Histogram<f64>,
+    cache_duration: Histogram<f64>,
+    db_duration: Histogram<f64>,
+}
+
+static INSTRUMENTS: LazyLock<Instruments> = LazyLock::new(|| {
+    let meter = global::meter(INSTRUMENTATION_NAME);
+    Instruments {
+        request_duration: meter
+            .f64_histogram("http.server.request.duration")
+            .with_unit("s")
+            .with_description("Duration of HTTP requests")
+            .build(),
+        cache_duration: meter
+            .f64_histogram("ketobook.cache.operation.duration")
+            .with_unit("s")
+            .with_description("Duration of cache operations")
+            .build(),
+        db_duration: meter
+            .f64_histogram("db.client.operation.duration")
+            .with_unit("s")
+            .with_description("Duration of repository calls")
+            .build(),
+    }
+});
+
+/// A child span of the current context, open until `finish`
+struct Operation {
+    cx: Context,
+    started: Instant,
+}
+
+impl Operation {
+    fn start(name: String, kind: SpanKind, attributes: Vec<KeyValue>) -> Self {
+        let tracer = global::tracer(INSTRUMENTATION_NAME);
+        let span = tracer
+            .span_builder(name)
+            .with_kind(kind)
+            .with_attributes(attributes)
+            .start_with_context(&tracer, &Context::current());
+        Self {
+            cx: Context::current_with_span(span),
+            started: Instant::now(),
+        }
+    }
+
+    /// End the span with `attributes` and an error if any; returns the elapsed seconds
+    fn finish(self, attributes: Vec<KeyValue>, error: Option<String>) -> f64 {
+        let span = self.cx.span();
+        span.set_attributes(attributes);
+        if let Some(error) = error {
+            span.set_status(Status::error(error));
+        }
+        span.end();
+        self.started.elapsed().as_secs_f64()
+    }
+}
+
+// ==================== Requests ====================
+
+/// Server span and duration of one request
+pub async fn trace_request(req: ServiceRequest, next: Next<BoxBody>) -> Result<ServiceResponse<BoxBody>, Error> {
+    let method = req.method().to_string();
+    let route = req.match_pattern().unwrap_or_else(|| "unmatched".to_string());
+    let op = Operation::start(
+        format!("{} {}", method, route),
+        SpanKind::Server,
+        vec![
+            KeyValue::new("http.request.method", method.clone()),
+            KeyValue::new("http.route", route.clone()),
+        ],
+    );
+
+    let result = next.call(req).with_context(op.cx.clone()).await;
+
+    let status = match &result {
+        Ok(res) => res.status().as_u16(),
+        Err(e) => e.as_response_error().status_code().as_u16(),
+    };
+    let error = (status >= 500).then(|| format!("HTTP {}", status));
+    let elapsed = op.finish(vec![KeyValue::new("http.response.status_code", i64::from(status))], error);
+    metrics::record_request(&method, &route, status, elapsed);
+    INSTRUMENTS.request_duration.record(
+        elapsed,
+        &[
+            KeyValue::new("http.request.method", method),
+            KeyValue::new("http.route", route),
+            KeyValue::new("http.response.status_code", i64::from(status)),
+        ],
+    );
+    result
+}
+
+/// Put the request id on the current request span
+pub fn tag_request_id(request_id: &str) {
+    Context::current()
+        .span()
+        .set_attribute(KeyValue::new("http.request.id", request_id.to_string()));
+}
+
+// ==================== Cache ====================
+
+/// How a cache operation went
+#[derive(Debug, Clone, Copy)]
+pub enum CacheOutcome {
+    Hit,
+    Stale,
+    Miss,
+    Write,
+    Error,
+}
+
+impl CacheOutcome {
+    const ALL: [CacheOutcome; 5] = [
+        CacheOutcome::Hit,
+        CacheOutcome::Stale,
+        CacheOutcome::Miss,
+        CacheOutcome::Write,
+        CacheOutcome::Error,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            CacheOutcome::Hit => "hit",
+            CacheOutcome::Stale => "stale",
+            CacheOutcome::Miss => "miss",
+            CacheOutcome::Write => "write",
+            CacheOutcome::Error => "error",
+        }
+    }
+}
+
+/// Cache operations of this instance since startup, by outcome (`CacheOutcome::ALL` order)
+static CACHE_OUTCOME_COUNTS: [AtomicU64; 5] = [const { AtomicU64::new(0) }; 5];
+
+/// `(outcome, count)` of this instance's cache operations since startup
+pub fn cache_outcome_counts() -> Vec<(&'static str, u64)> {
+    CacheOutcome::ALL
+        .iter()
+        .map(|outcome| (outcome.as_str(), CACHE_OUTCOME_COUNTS[*outcome as usize].load(Ordering::Relaxed)))
+        .collect()
+}
+
+/// Run a cache operation in its own span; `fut` reports its outcome
+pub async fn cache<T>(
+    namespace: &str,
+    operation: &'static str,
+    fut: impl Future<Output = (T, CacheOutcome)>,
+) -> T {
+    let op = Operation::start(
+        format!("cache {} {}", operation, namespace),
+        SpanKind::Client,
+        vec![
+            KeyValue::new("cache.namespace", namespace.to_string()),
+            KeyValue::new("cache.operation", operation),
+        ],
+    );
+
+    let (value, outcome) = fut.with_context(op.cx.clone()).await;
+
+    CACHE_OUTCOME_COUNTS[outcome as usize].fetch_add(1, Ordering::Relaxed);
+    let error = matches!(outcome, CacheOutcome::Error).then(|| "cache error".to_string());
+    let elapsed = op.finish(vec![KeyValue::new("cache.outcome", outcome.as_str())], error);
+    metrics::record_cache(namespace, operation, outcome.as_str(), elapsed);
+    INSTRUMENTS.cache_duration.record(
+        elapsed,
+        &[
+            KeyValue::new("cache.namespace", namespace.to_string()),
+            KeyValue::new("cache.operation", operation),
+            KeyValue::new("cache.outcome", outcome.as_str()),
+        ],
+    );
+    value
+}
+
+// ==================== SQL ====================
+
+/// Number of rows a repository call returned or touched
+pub trait RowCount {
+    fn row_count(&self) -> u64;
+}
+
+impl<T> RowCount for Vec<T> {
+    fn row_count(&self) -> u64 {
+        self.len() as u64
+    }
+}
+
+impl<T> RowCount for Option<T> {
+    fn row_count(&self) -> u64 {
+        u64::from(self.is_some())
+    }
+}
+
+impl RowCount for PgQueryResult {
+    fn row_count(&self) -> u64 {
+        self.rows_affected()
+    }
+}
+
+/// Run a repository call in its own span
+pub async fn sql<T: RowCount>(
+    table: &'static str,
+    operation: &'static str,
+    fut: impl Future<Output = Result<T, sqlx::Error>>,
+) -> Result<T, sqlx::Error> {
+    let op = Operation::start(
+        format!("{} {}", operation, table),
+        SpanKind::Client,
+        vec![
+            KeyValue::new("db.system.name", "postgresql"),
+            KeyValue::new("db.collection.name", table),
+            KeyValue::new("db.operation.name", operation),
+        ],
+    );
+
+    let result = fut.with_context(op.cx.clone()).await;
+
+    let (attributes, error) = match &result {
+        Ok(value) => (vec![KeyValue::new("db.response.returned_rows", value.row_count() as i64)], None),
+        Err(e) => (Vec::new(), Some(e.to_string())),
+    };
+    let outcome = if error.is_some() { "error" } else { "ok" };
+    let elapsed = op.finish(attributes, error);
+    metrics::record_sql(table, operation, outcome, elapsed);
+    INSTRUMENTS.db_duration.record(
+        elapsed,
+        &[
+            KeyValue::new("db.system.name", "postgresql"),
+            KeyValue::new("db.collection.name", table),
+            KeyValue::new("db.operation.name", operation),
+            KeyValue::new("outcome", outcome),
+        ],
+    );
+    result
+}
+
+/// A single row
+struct One<T>(T);
+
+impl<T> RowCount for One<T> {
+    fn row_count(&self) -> u64 {
+        1
+    }
+}
+
+/// `sql` for a call that returns exactly one row
+pub async fn sql_one<T>(
+    table: &'static str,
+    operation: &'static str,
+    fut: impl Future<Output = Result<T, sqlx::Error>>,
+) -> Result<T, sqlx::Error> {
+    sql(table, operation, async { fut.await.map(One) }).await.map(|One(row)| row)
+}
@@ -0,0 +1,357 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse, ResponseError};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::audit::AuditContext;
+use crate::cache::CacheBackend;
+use crate::dry_run::DryRun;
+use crate::idempotency::IdempotencyKey;
+use crate::routes::ScopedRoutes;
+use crate::transactions;
+use crate::models::{
+    ApiResponse, CreateFromTemplateRequest, CreateTransactionRequest, CreateTransactionTemplateRequest, Transaction,
+    TransactionTemplate, UpdateTransactionTemplateRequest,
+};
+
+// ==================== TRANSACTION TEMPLATES ====================
+//
+// Templates (favorites) save the wallet, type, amount, category and
+// description of an entry the user makes over and over, like parking or
+// lunch. `POST /api/transactions/from-template/{template_id}` turns one into
+// a transaction, with optional per-use overrides; the transaction then goes
+// through `transactions::create_transaction`, so it is validated and applied
+// exactly like one entered by hand.
+//
+// A template may leave the amount out (lunch costs something different every
+// day); using it then requires an `amount` override. Templates are listed
+// most used first.
+//
+// ============================================================================
+
+/// Maximum number of templates per user
+const MAX_TEMPLATES_PER_USER: i64 = 50;
+
+const TEMPLATE_COLUMNS: &str = "id, user_id, name, wallet_id, transaction_type, amount, category, description, \
+     use_count, last_used_at, created_at, updated_at";
+
+// ==================== Handlers ====================
+
+/// List a user's templates, most used first
+pub async fn get_user_templates(user_id: web::Path<String>, db: web::Data<PgPool>) -> HttpResponse {
+    let result = sqlx::query_as::<_, TransactionTemplate>(&format!(
+        "SELECT {} FROM transaction_templates WHERE user_id = $1 ORDER BY use_count DESC, name",
+        TEMPLATE_COLUMNS
+    ))
+    .bind(user_id.into_inner())
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(templates) => HttpResponse::Ok().json(ApiResponse::success(templates)),
+        Err(e) => {
+            log::error!("Error fetching transaction templates: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<TransactionTemplate>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Save a template
+pub async fn create_template(
+    req: web::Json<CreateTransactionTemplateRequest>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let name = req.name.trim();
+    if let Err(msg) = validate_template(
+        Some(name),
+        Some(&req.transaction_type),
+        req.amount.as_ref(),
+        Some(&req.category),
+    ) {
+        return HttpResponse::BadRequest().json(ApiResponse::<TransactionTemplate>::error(msg));
+    }
+    if let Err(response) = check_wallet(db.get_ref(), &req.user_id, req.wallet_id).await {
+        return response;
+    }
+
+    let count: Result<(i64,), sqlx::Error> =
+        sqlx::query_as("SELECT COUNT(*) FROM transaction_templates WHERE user_id = $1")
+            .bind(&req.user_id)
+            .fetch_one(db.get_ref())
+            .await;
+    match count {
+        Ok((count,)) if count >= MAX_TEMPLATES_PER_USER => {
+            return HttpResponse::BadRequest().json(ApiResponse::<TransactionTemplate>::error(format!(
+                "A user can have at most {} templates",
+                MAX_TEMPLATES_PER_USER
+            )));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Error counting transaction templates: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<TransactionTemplate>::error("Database error".to_string()));
+        }
+    }
+
+    let now = Utc::now();
+    let result = sqlx::query_as::<_, TransactionTemplate>(&format!(
+        "INSERT INTO transaction_templates
+             (id, user_id, name, wallet_id, transaction_type, amount, category, description, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $9)
+         ON CONFLICT (user_id, name) DO NOTHING
+         RETURNING {}",
+        TEMPLATE_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(name)
+    .bind(req.wallet_id)
+    .bind(&req.transaction_type)
+    .bind(&req.amount)
+    .bind(&req.category)
+    .bind(&req.description)
+    .bind(now)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(template)) => HttpResponse::Created().json(ApiResponse::success(template)),
+        Ok(None) => HttpResponse::Conflict().json(ApiResponse::<TransactionTemplate>::error(format!(
+            "Template '{}' already exists",
+            name
+        ))),
+        Err(e) => {
+            log::error!("Error creating transaction template: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<TransactionTemplate>::error("Failed to create template".to_string()))
+        }
+    }
+}
+
+/// Change a template
+pub async fn update_template(
+    path: web::Path<(String, String)>,
+    req: web::Json<UpdateTransactionTemplateRequest>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let (user_id, template_id) = path.into_inner();
+    let name = req.name.as_deref().map(str::trim);
+    if let Err(msg) = validate_template(
+        name,
+        req.transaction_type.as_ref(),
+        req.amount.as_ref(),
+        req.category.as_ref(),
+    ) {
+        return HttpResponse::BadRequest().json(ApiResponse::<TransactionTemplate>::error(msg));
+    }
+    if let Some(wallet_id) = req.wallet_id
+        && let Err(response) = check_wallet(db.get_ref(), &user_id, wallet_id).await
+    {
+        return response;
+    }
+
+    let result = sqlx::query_as::<_, TransactionTemplate>(&format!(
+        "UPDATE transaction_templates
+         SET name = COALESCE($1, name),
+             wallet_id = COALESCE($2, wallet_id),
+             transaction_type = COALESCE($3, transaction_type),
+             amount = COALESCE($4, amount),
+             category = COALESCE($5, category),
+             description = COALESCE($6, description),
+             updated_at = $7
+         WHERE id::text = $8 AND user_id = $9
+         RETURNING {}",
+        TEMPLATE_COLUMNS
+    ))
+    .bind(name)
+    .bind(req.wallet_id)
+    .bind(&req.transaction_type)
+    .bind(&req.amount)
+    .bind(&req.category)
+    .bind(&req.description)
+    .bind(Utc::now())
+    .bind(&template_id)
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(template)) => HttpResponse::Ok().json(ApiResponse::success(template)),
+        Ok(None) => HttpResponse::NotFound()
+            .json(ApiResponse::<TransactionTemplate>::error("Template not found".to_string())),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => HttpResponse::Conflict()
+            .json(ApiResponse::<TransactionTemplate>::error("A template with this name already exists".to_string())),
+        Err(e) => {
+            log::error!("Error updating transaction template: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<TransactionTemplate>::error("Failed to update template".to_string()))
+        }
+    }
+}
+
+/// Delete a template (transactions created from it are kept)
+pub async fn delete_template(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, template_id) = path.into_inner();
+
+    let result = sqlx::query("DELETE FROM transaction_templates WHERE id::text = $1 AND user_id = $2")
+        .bind(&template_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await;
+
+    match result {
+        Ok(query_result) if query_result.rows_affected() > 0 => HttpResponse::NoContent().finish(),
+        Ok(_) => HttpResponse::NotFound().json(ApiResponse::<String>::error("Template not found".to_string())),
+        Err(e) => {
+            log::error!("Error deleting transaction template: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<String>::error("Failed to delete template".to_string()))
+        }
+    }
+}
+
+/// `POST /api/transactions/from-template/{template_id}` - create a transaction from a template
+pub async fn create_from_template(
+    template_id: web::Path<String>,
+    req: web::Json<CreateFromTemplateRequest>,
+    context: AuditContext,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let req = req.into_inner();
+
+    let template = sqlx::query_as::<_, TransactionTemplate>(&format!(
+        "SELECT {} FROM transaction_templates WHERE id::text = $1 AND user_id = $2",
+        TEMPLATE_COLUMNS
+    ))
+    .bind(template_id.as_str())
+    .bind(&req.user_id)
+    .fetch_optional(db.get_ref())
+    .await;
+    let template = match template {
+        Ok(Some(template)) => template,
+        Ok(None) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<Transaction>::error("Template not found".to_string()));
+        }
+        Err(e) => {
+            log::error!("Error fetching transaction template: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<Transaction>::error("Database error".to_string()));
+        }
+    };
+
+    let Some(amount) = req.amount.or(template.amount) else {
+        return HttpResponse::BadRequest().json(ApiResponse::<Transaction>::error(
+            "This template has no amount; amount is required".to_string(),
+        ));
+    };
+
+    let transaction = CreateTransactionRequest {
+        user_id: req.user_id,
+        wallet_id: req.wallet_id.unwrap_or(template.wallet_id),
+        amount,
+        transaction_type: template.transaction_type,
+        category: req.category.unwrap_or(template.category),
+        description: req.description.unwrap_or(template.description),
+        notes: req.notes,
+        metadata: req.metadata,
+        bucket_id: req.bucket_id,
+        income_source_id: req.income_source_id,
+        splits: Vec::new(),
+        confirm: req.confirm,
+    };
+    let response = transactions::create_transaction(
+        web::Json(transaction),
+        IdempotencyKey(None),
+        DryRun(false),
+        context,
+        db.clone(),
+        cache,
+    )
+    .await
+    .unwrap_or_else(|e| e.error_response());
+
+    if response.status().is_success()
+        && let Err(e) = sqlx::query(
+            "UPDATE transaction_templates SET use_count = use_count + 1, last_used_at = $1 WHERE id = $2",
+        )
+        .bind(Utc::now())
+        .bind(template.id)
+        .execute(db.get_ref())
+        .await
+    {
+        log::warn!("Failed to record use of template {}: {}", template.id, e);
+    }
+
+    response
+}
+
+fn validate_template(
+    name: Option<&str>,
+    transaction_type: Option<&String>,
+    amount: Option<&BigDecimal>,
+    category: Option<&String>,
+) -> Result<(), String> {
+    if let Some(name) = name
+        && (name.is_empty() || name.chars().count() > 100)
+    {
+        return Err("Name must be 1-100 characters".to_string());
+    }
+    if let Some(transaction_type) = transaction_type
+        && transaction_type != "income"
+        && transaction_type != "expense"
+    {
+        return Err("Invalid transaction type. Must be 'income' or 'expense'".to_string());
+    }
+    if let Some(amount) = amount
+        && *amount <= BigDecimal::from(0)
+    {
+        return Err("Amount must be greater than 0".to_string());
+    }
+    if let Some(category) = category
+        && (category.trim().is_empty() || category.chars().count() > 100)
+    {
+        return Err("Category must be 1-100 characters".to_string());
+    }
+    Ok(())
+}
+
+/// The wallet must belong to the user
+async fn check_wallet(pool: &PgPool, user_id: &str, wallet_id: Uuid) -> Result<(), HttpResponse> {
+    let found: Result<Option<(Uuid,)>, sqlx::Error> =
+        sqlx::query_as("SELECT id FROM wallets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL")
+            .bind(wallet_id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await;
+    match found {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(HttpResponse::BadRequest().json(ApiResponse::<TransactionTemplate>::error(
+            "Wallet not found or doesn't belong to user".to_string(),
+        ))),
+        Err(e) => {
+            log::error!("Error fetching wallet: {}", e);
+            Err(HttpResponse::InternalServerError()
+                .json(ApiResponse::<TransactionTemplate>::error("Failed to validate wallet".to_string())))
+        }
+    }
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/transaction-templates")
+        .create("", create_template)
+        .user(Method::GET, "/{user_id}", get_user_templates)
+        .user(Method::PUT, "/{user_id}/{template_id}", update_template)
+        .user(Method::DELETE, "/{user_id}/{template_id}", delete_template)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
@@ -0,0 +1,47 @@
+use std::io::Write;
+use std::str::FromStr;
+
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ==================== TEST SUPPORT ====================
+//
+// Tests that need Postgres run against the migrated database named by
+// `DATABASE_URL` and clean up the rows they create. Without it they pass
+// without running, and say so on stderr, which the test harness doesn't
+// capture when written to directly:
+//
+//     DATABASE_URL=postgres://localhost/ketobook_test cargo test
+//
+// ============================================================================
+
+/// Pool on `DATABASE_URL`; `None` (after a note naming `test`) when unset
+pub(crate) async fn database(test: &str) -> Option<PgPool> {
+    let Ok(url) = std::env::var("DATABASE_URL") else {
+        let _ = writeln!(std::io::stderr(), "skipping {}: DATABASE_URL is not set", test);
+        return None;
+    };
+    Some(PgPool::connect(&url).await.expect("connect to DATABASE_URL"))
+}
+
+/// A cash wallet of `user_id` holding `balance`
+pub(crate) async fn create_wallet(pool: &PgPool, user_id: &str, balance: &str) -> Uuid {
+    let (wallet_id,): (Uuid,) = sqlx::query_as(
+        "INSERT INTO wallets (user_id, name, wallet_type, balance, opening_balance)
+         VALUES ($1, 'Test wallet', 'Cash', $2, $2)
+         RETURNING id",
+    )
+    .bind(user_id)
+    .bind(BigDecimal::from_str(balance).expect("balance is a decimal"))
+    .fetch_one(pool)
+    .await
+    .expect("create wallet");
+    wallet_id
+}
+
+/// Delete a wallet made by `create_wallet` with its transactions
+pub(crate) async fn remove_wallet(pool: &PgPool, wallet_id: Uuid) {
+    let _ = sqlx::query("DELETE FROM transactions WHERE wallet_id = $1").bind(wallet_id).execute(pool).await;
+    let _ = sqlx::query("DELETE FROM wallets WHERE id = $1").bind(wallet_id).execute(pool).await;
+}
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use actix_web::body::BoxBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, HttpResponse};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::models::ApiResponse;
+use crate::telemetry;
+
+// ==================== LATENCY BUDGETS ====================
+//
+// Every API route runs under a latency budget (`ScopedRoutes` wraps it with
+// `enforce`). A handler still running when its budget runs out is dropped,
+// which cancels whatever it was awaiting (a query, a Redis call, an upload),
+// and the client gets a 504 instead of a worker pinned indefinitely.
+//
+// Cancellation happens at await points: an open sqlx transaction is rolled
+// back when dropped, but a statement the database already received may still
+// finish server-side.
+//
+// Each response carries an `X-Request-Id` (the client's, if it sent a usable
+// one) so a 504 can be matched with the server log. Handlers read it through
+// the `RequestId` request extension (the audit log records it). Log lines
+// written while the request is handled carry it too (`current_request_id`,
+// read by the log format in main.rs), and so does the request's trace span.
+//
+// ============================================================================
+
+const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied request id that is echoed back
+const MAX_REQUEST_ID_LEN: usize = 100;
+
+/// The `X-Request-Id` of the current request
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+tokio::task_local! {
+    /// The request id of the request the current task is handling
+    static CURRENT_REQUEST_ID: String;
+}
+
+/// The id of the request being handled, if any (for log lines)
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// How long a route may take before it is cancelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyBudget {
+    /// CRUD and list endpoints (`REQUEST_TIMEOUT_MS`)
+    Standard,
+    /// Exports, attachment transfers and admin operations (`EXTENDED_REQUEST_TIMEOUT_MS`)
+    Extended,
+}
+
+impl LatencyBudget {
+    pub fn duration(&self, config: &AppConfig) -> Duration {
+        let millis = match self {
+            LatencyBudget::Standard => config.request_timeout_ms,
+            LatencyBudget::Extended => config.extended_request_timeout_ms,
+        };
+        Duration::from_millis(millis)
+    }
+}
+
+/// Run the rest of the chain under `budget`, answering 504 when it runs out
+pub async fn enforce(
+    budget: LatencyBudget,
+    req: ServiceRequest,
+    next: Next<BoxBody>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let Some(config) = req.app_data::<web::Data<AppConfig>>() else {
+        return next.call(req).await;
+    };
+    let limit = budget.duration(config);
+    let request_id = request_id(&req);
+    let id = request_id.to_str().unwrap_or_default().to_string();
+    req.extensions_mut().insert(RequestId(id.clone()));
+    telemetry::tag_request_id(&id);
+    let http_req = req.request().clone();
+
+    let handled = CURRENT_REQUEST_ID.scope(id, tokio::time::timeout(limit, next.call(req)));
+    let mut res = match handled.await {
+        Ok(res) => res?,
+        Err(_) => {
+            log::warn!(
+                "Request {} {} {} exceeded its {:?} budget ({} ms); cancelled",
+                request_id.to_str().unwrap_or_default(),
+                http_req.method(),
+                http_req.path(),
+                budget,
+                limit.as_millis()
+            );
+            let response = HttpResponse::GatewayTimeout().json(ApiResponse::<String>::error(format!(
+                "Request timed out after {} ms (request id {})",
+                limit.as_millis(),
+                request_id.to_str().unwrap_or_default()
+            )));
+            ServiceResponse::new(http_req, response)
+        }
+    };
+
+    res.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+    Ok(res)
+}
+
+/// The client's `X-Request-Id` if it is short and printable, otherwise a fresh UUID
+fn request_id(req: &ServiceRequest) -> HeaderValue {
+    req.headers()
+        .get(&REQUEST_ID_HEADER)
+        .filter(|v| {
+            v.to_str()
+                .is_ok_and(|s| !s.is_empty() && s.len() <= MAX_REQUEST_ID_LEN && s.chars().all(|c| c.is_ascii_graphic()))
+        })
+        .cloned()
+        .unwrap_or_else(|| HeaderValue::from_str(&Uuid::new_v4().to_string()).expect("UUIDs are valid header values"))
+}
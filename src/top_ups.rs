@@ -0,0 +1,520 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::cache::{invalidate_user_cache, CacheBackend};
+use crate::currencies;
+use crate::freezes::{self, FreezeError};
+use crate::hooks::{
+    Hook, HookContext, TransactionCreated, TransactionDeleted, TransactionRestored, TransactionUpdated, WalletsDebited,
+};
+use crate::models::{ApiResponse, CreateTopUpRuleRequest, TopUp, TopUpRule, UpdateTopUpRuleRequest, Wallet};
+use crate::routes::ScopedRoutes;
+use crate::standing_orders;
+use crate::wallet_types;
+
+// ==================== AUTO TOP-UP ====================
+//
+// A top-up rule keeps an asset wallet above a threshold: when a transaction
+// leaves the wallet's balance below it, `amount` is moved in from the rule's
+// funding wallet, as a transfer pair like a standing order run (expense on
+// the funding wallet, income on the wallet, category "transfer").
+//
+// The top-up runs synchronously after the triggering transaction commits
+// (`TopUpHook`, on the transaction, transfer and settlement events of
+// hooks.rs), in its own database transaction: a top-up that can't be made never undoes the transaction that
+// triggered it. A top-up does not itself trigger the funding wallet's rule.
+//
+// Threshold, amount and cap are in the topped-up wallet's currency; a
+// funding wallet in another currency pays the converted amount (see
+// currencies.rs).
+//
+// Each rule moves at most `daily_cap` per UTC day; the top-up that reaches
+// the cap is cut to fit. The funding wallet must cover the amount the way an
+// expense would, and neither wallet may be frozen. Attempts are recorded in
+// `wallet_top_ups` as `completed`, `capped` or `failed`; a capped or failed
+// attempt is recorded once per rule and day, not on every transaction. The
+// alert job pushes each recorded attempt to the user's devices (see
+// alerts.rs).
+//
+// ============================================================================
+
+/// Maximum number of top-up rules per user
+const MAX_RULES_PER_USER: i64 = 20;
+
+/// Number of attempts returned by the history endpoint
+const HISTORY_LIMIT: i64 = 100;
+
+const RULE_COLUMNS: &str =
+    "id, user_id, wallet_id, funding_wallet_id, threshold, amount, daily_cap, active, created_at, updated_at";
+
+const TOP_UP_COLUMNS: &str = "id, rule_id, user_id, status, amount, balance_before, error, created_at";
+
+const WALLET_COLUMNS: &str = "id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, created_at, updated_at, version";
+
+// ==================== Handlers ====================
+
+/// List a user's top-up rules
+pub async fn get_user_top_up_rules(user_id: web::Path<String>, db: web::Data<PgPool>) -> HttpResponse {
+    let result = sqlx::query_as::<_, TopUpRule>(&format!(
+        "SELECT {} FROM wallet_top_up_rules WHERE user_id = $1 ORDER BY created_at",
+        RULE_COLUMNS
+    ))
+    .bind(user_id.into_inner())
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(rules) => HttpResponse::Ok().json(ApiResponse::success(rules)),
+        Err(e) => {
+            log::error!("Error fetching top-up rules: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<TopUpRule>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Create a top-up rule (one per wallet)
+pub async fn create_top_up_rule(req: web::Json<CreateTopUpRuleRequest>, db: web::Data<PgPool>) -> HttpResponse {
+    if req.wallet_id == req.funding_wallet_id {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<TopUpRule>::error("Wallet and funding wallet must differ".to_string()));
+    }
+    let daily_cap = req.daily_cap.clone().unwrap_or_else(|| req.amount.clone());
+    if let Err(msg) = validate_amounts(Some(&req.threshold), Some(&req.amount), Some(&daily_cap)) {
+        return HttpResponse::BadRequest().json(ApiResponse::<TopUpRule>::error(msg));
+    }
+
+    // Both wallets must be the user's; only asset wallets have a balance to keep up
+    let mut target = None;
+    for wallet_id in [req.wallet_id, req.funding_wallet_id] {
+        match fetch_user_wallet(db.get_ref(), wallet_id, &req.user_id).await {
+            Ok(Some(wallet)) => target = target.or(Some(wallet)),
+            Ok(None) => {
+                return HttpResponse::BadRequest()
+                    .json(ApiResponse::<TopUpRule>::error(format!("Wallet {} not found", wallet_id)));
+            }
+            Err(e) => {
+                log::error!("Error fetching wallet: {}", e);
+                return HttpResponse::InternalServerError()
+                    .json(ApiResponse::<TopUpRule>::error("Database error".to_string()));
+            }
+        }
+    }
+    if let Some(target) = &target {
+        match wallet_types::template_for(db.get_ref(), target).await {
+            Ok(template) if template.is_liability() => {
+                return HttpResponse::BadRequest()
+                    .json(ApiResponse::<TopUpRule>::error("Only asset wallets can be topped up".to_string()));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                log::error!("Error resolving wallet type: {}", e);
+                return HttpResponse::InternalServerError()
+                    .json(ApiResponse::<TopUpRule>::error("Failed to validate wallet".to_string()));
+            }
+        }
+    }
+
+    let count: Result<(i64,), sqlx::Error> =
+        sqlx::query_as("SELECT COUNT(*) FROM wallet_top_up_rules WHERE user_id = $1")
+            .bind(&req.user_id)
+            .fetch_one(db.get_ref())
+            .await;
+    match count {
+        Ok((count,)) if count >= MAX_RULES_PER_USER => {
+            return HttpResponse::BadRequest().json(ApiResponse::<TopUpRule>::error(format!(
+                "A user can have at most {} top-up rules",
+                MAX_RULES_PER_USER
+            )));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Error counting top-up rules: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<TopUpRule>::error("Database error".to_string()));
+        }
+    }
+
+    let now = Utc::now();
+    let result = sqlx::query_as::<_, TopUpRule>(&format!(
+        "INSERT INTO wallet_top_up_rules
+             (id, user_id, wallet_id, funding_wallet_id, threshold, amount, daily_cap, active, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, TRUE, $8, $8)
+         ON CONFLICT (wallet_id) DO NOTHING
+         RETURNING {}",
+        RULE_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(req.wallet_id)
+    .bind(req.funding_wallet_id)
+    .bind(&req.threshold)
+    .bind(&req.amount)
+    .bind(&daily_cap)
+    .bind(now)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(rule)) => HttpResponse::Created().json(ApiResponse::success(rule)),
+        Ok(None) => HttpResponse::Conflict()
+            .json(ApiResponse::<TopUpRule>::error("This wallet already has a top-up rule".to_string())),
+        Err(e) => {
+            log::error!("Error creating top-up rule: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<TopUpRule>::error("Failed to create top-up rule".to_string()))
+        }
+    }
+}
+
+/// Change the threshold, amount or cap, or pause/resume a top-up rule
+pub async fn update_top_up_rule(
+    path: web::Path<(String, String)>,
+    req: web::Json<UpdateTopUpRuleRequest>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    let (user_id, rule_id) = path.into_inner();
+    if let Err(msg) = validate_amounts(req.threshold.as_ref(), req.amount.as_ref(), req.daily_cap.as_ref()) {
+        return HttpResponse::BadRequest().json(ApiResponse::<TopUpRule>::error(msg));
+    }
+
+    let result = sqlx::query_as::<_, TopUpRule>(&format!(
+        "UPDATE wallet_top_up_rules
+         SET threshold = COALESCE($1, threshold),
+             amount = COALESCE($2, amount),
+             daily_cap = COALESCE($3, daily_cap),
+             active = COALESCE($4, active),
+             updated_at = $5
+         WHERE id::text = $6 AND user_id = $7
+         RETURNING {}",
+        RULE_COLUMNS
+    ))
+    .bind(&req.threshold)
+    .bind(&req.amount)
+    .bind(&req.daily_cap)
+    .bind(req.active)
+    .bind(Utc::now())
+    .bind(&rule_id)
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(rule)) => HttpResponse::Ok().json(ApiResponse::success(rule)),
+        Ok(None) => HttpResponse::NotFound()
+            .json(ApiResponse::<TopUpRule>::error("Top-up rule not found".to_string())),
+        Err(e) => {
+            log::error!("Error updating top-up rule: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<TopUpRule>::error("Failed to update top-up rule".to_string()))
+        }
+    }
+}
+
+/// Delete a top-up rule (its history is removed with it)
+pub async fn delete_top_up_rule(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, rule_id) = path.into_inner();
+
+    let result = sqlx::query("DELETE FROM wallet_top_up_rules WHERE id::text = $1 AND user_id = $2")
+        .bind(&rule_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await;
+
+    match result {
+        Ok(query_result) if query_result.rows_affected() > 0 => HttpResponse::NoContent().finish(),
+        Ok(_) => HttpResponse::NotFound().json(ApiResponse::<String>::error("Top-up rule not found".to_string())),
+        Err(e) => {
+            log::error!("Error deleting top-up rule: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<String>::error("Failed to delete top-up rule".to_string()))
+        }
+    }
+}
+
+/// Top-up attempts of a rule (most recent first)
+pub async fn get_top_up_history(path: web::Path<(String, String)>, db: web::Data<PgPool>) -> HttpResponse {
+    let (user_id, rule_id) = path.into_inner();
+
+    let result = sqlx::query_as::<_, TopUp>(&format!(
+        "SELECT {} FROM wallet_top_ups
+         WHERE rule_id::text = $1 AND user_id = $2
+         ORDER BY created_at DESC
+         LIMIT $3",
+        TOP_UP_COLUMNS
+    ))
+    .bind(&rule_id)
+    .bind(&user_id)
+    .bind(HISTORY_LIMIT)
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(top_ups) => HttpResponse::Ok().json(ApiResponse::success(top_ups)),
+        Err(e) => {
+            log::error!("Error fetching top-up history: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<TopUp>>::error("Database error".to_string()))
+        }
+    }
+}
+
+fn validate_amounts(
+    threshold: Option<&BigDecimal>,
+    amount: Option<&BigDecimal>,
+    daily_cap: Option<&BigDecimal>,
+) -> Result<(), String> {
+    let zero = BigDecimal::from(0);
+    if threshold.is_some_and(|t| *t < zero) {
+        return Err("threshold must not be negative".to_string());
+    }
+    if amount.is_some_and(|a| *a <= zero) {
+        return Err("amount must be greater than 0".to_string());
+    }
+    if daily_cap.is_some_and(|c| *c <= zero) {
+        return Err("daily_cap must be greater than 0".to_string());
+    }
+    Ok(())
+}
+
+// ==================== Top-Up Execution ====================
+
+/// How a top-up attempt ended
+enum TopUpOutcome {
+    Completed(BigDecimal),
+    Capped(String),
+    Failed(BigDecimal, String),
+}
+
+/// Tops up the wallets a committed change can leave below their threshold
+pub struct TopUpHook;
+
+#[async_trait]
+impl Hook for TopUpHook {
+    fn name(&self) -> &'static str {
+        "top_ups"
+    }
+
+    async fn on_transaction_created(&self, cx: &HookContext<'_>, event: &TransactionCreated<'_>) {
+        let transaction = event.transaction;
+        if transaction.transaction_type == "expense" {
+            top_up_after_change(cx.pool, cx.cache, &transaction.user_id, &[transaction.wallet_id]).await;
+        }
+    }
+
+    async fn on_transaction_updated(&self, cx: &HookContext<'_>, event: &TransactionUpdated<'_>) {
+        // Moving or growing the transaction can leave either wallet below its threshold
+        let mut wallet_ids = vec![event.after.wallet_id];
+        if event.before.wallet_id != event.after.wallet_id {
+            wallet_ids.push(event.before.wallet_id);
+        }
+        top_up_after_change(cx.pool, cx.cache, &event.after.user_id, &wallet_ids).await;
+    }
+
+    async fn on_transaction_deleted(&self, cx: &HookContext<'_>, event: &TransactionDeleted<'_>) {
+        let transaction = event.transaction;
+        if transaction.transaction_type == "income" {
+            top_up_after_change(cx.pool, cx.cache, &transaction.user_id, &[transaction.wallet_id]).await;
+        }
+    }
+
+    async fn on_transaction_restored(&self, cx: &HookContext<'_>, event: &TransactionRestored<'_>) {
+        let transaction = event.transaction;
+        if transaction.transaction_type == "expense" {
+            top_up_after_change(cx.pool, cx.cache, &transaction.user_id, &[transaction.wallet_id]).await;
+        }
+    }
+
+    async fn on_wallets_debited(&self, cx: &HookContext<'_>, event: &WalletsDebited<'_>) {
+        top_up_after_change(cx.pool, cx.cache, event.owner_id, event.wallet_ids).await;
+    }
+}
+
+/// Top up each of `wallet_ids` that a committed change left below its rule's threshold
+///
+/// Failures are logged, never returned: the triggering change already succeeded.
+async fn top_up_after_change(pool: &PgPool, cache: &dyn CacheBackend, user_id: &str, wallet_ids: &[Uuid]) {
+    let mut moved = false;
+    for &wallet_id in wallet_ids {
+        match top_up_wallet(pool, wallet_id).await {
+            Ok(completed) => moved |= completed,
+            Err(e) => log::error!("Failed to top up wallet {}: {}", wallet_id, e),
+        }
+    }
+    if moved {
+        let _ = invalidate_user_cache(cache, user_id).await;
+    }
+}
+
+/// Run the wallet's rule if its balance is below the threshold; returns whether money moved
+async fn top_up_wallet(pool: &PgPool, wallet_id: Uuid) -> Result<bool, sqlx::Error> {
+    let mut db_tx = pool.begin().await?;
+
+    // Locking the rule serializes top-ups of one wallet, so the cap holds
+    let rule = sqlx::query_as::<_, TopUpRule>(&format!(
+        "SELECT {} FROM wallet_top_up_rules WHERE wallet_id = $1 AND active FOR UPDATE",
+        RULE_COLUMNS
+    ))
+    .bind(wallet_id)
+    .fetch_optional(&mut *db_tx)
+    .await?;
+    let Some(rule) = rule else {
+        db_tx.rollback().await?;
+        return Ok(false);
+    };
+
+    // Lock both wallets in id order so concurrent transfers can't deadlock
+    let wallets = sqlx::query_as::<_, Wallet>(&format!(
+        "SELECT {} FROM wallets WHERE id = ANY($1) AND user_id = $2 AND deleted_at IS NULL ORDER BY id FOR UPDATE",
+        WALLET_COLUMNS
+    ))
+    .bind(vec![rule.wallet_id, rule.funding_wallet_id])
+    .bind(&rule.user_id)
+    .fetch_all(&mut *db_tx)
+    .await?;
+    let (Some(wallet), Some(funding)) = (
+        wallets.iter().find(|w| w.id == rule.wallet_id),
+        wallets.iter().find(|w| w.id == rule.funding_wallet_id),
+    ) else {
+        db_tx.rollback().await?;
+        return Ok(false);
+    };
+    if wallet.balance >= rule.threshold {
+        db_tx.rollback().await?;
+        return Ok(false);
+    }
+
+    let now = Utc::now();
+    let outcome = transfer(&mut db_tx, &rule, wallet, funding, now).await?;
+    let (status, amount, error) = match &outcome {
+        TopUpOutcome::Completed(amount) => ("completed", Some(amount.clone()), None),
+        TopUpOutcome::Capped(reason) => ("capped", None, Some(reason.clone())),
+        TopUpOutcome::Failed(amount, error) => ("failed", Some(amount.clone()), Some(error.clone())),
+    };
+
+    // Attempts that moved nothing are recorded (and alerted) once per rule and day
+    if !matches!(outcome, TopUpOutcome::Completed(_)) {
+        let (already,): (bool,) = sqlx::query_as(
+            "SELECT EXISTS (SELECT 1 FROM wallet_top_ups WHERE rule_id = $1 AND status = $2 AND created_at >= $3)",
+        )
+        .bind(rule.id)
+        .bind(status)
+        .bind(start_of_day(now))
+        .fetch_one(&mut *db_tx)
+        .await?;
+        if already {
+            db_tx.rollback().await?;
+            return Ok(false);
+        }
+    }
+
+    sqlx::query(
+        "INSERT INTO wallet_top_ups (id, rule_id, user_id, status, amount, balance_before, error, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(rule.id)
+    .bind(&rule.user_id)
+    .bind(status)
+    .bind(&amount)
+    .bind(&wallet.balance)
+    .bind(&error)
+    .bind(now)
+    .execute(&mut *db_tx)
+    .await?;
+
+    db_tx.commit().await?;
+
+    match &outcome {
+        TopUpOutcome::Completed(amount) => log::info!("Top-up rule {} moved {}", rule.id, amount),
+        TopUpOutcome::Capped(_) => {}
+        TopUpOutcome::Failed(_, error) => log::warn!("Top-up rule {} failed: {}", rule.id, error),
+    }
+    Ok(matches!(outcome, TopUpOutcome::Completed(_)))
+}
+
+/// Move the rule's amount (cut to the day's remaining cap), or explain why not
+async fn transfer(
+    conn: &mut PgConnection,
+    rule: &TopUpRule,
+    wallet: &Wallet,
+    funding: &Wallet,
+    now: DateTime<Utc>,
+) -> Result<TopUpOutcome, sqlx::Error> {
+    let (moved_today,): (BigDecimal,) = sqlx::query_as(
+        "SELECT COALESCE(SUM(amount), 0) FROM wallet_top_ups
+         WHERE rule_id = $1 AND status = 'completed' AND created_at >= $2",
+    )
+    .bind(rule.id)
+    .bind(start_of_day(now))
+    .fetch_one(&mut *conn)
+    .await?;
+    let remaining = &rule.daily_cap - moved_today;
+    if remaining <= BigDecimal::from(0) {
+        return Ok(TopUpOutcome::Capped(format!("Daily cap of {} reached", rule.daily_cap)));
+    }
+    let amount = if rule.amount > remaining { remaining } else { rule.amount.clone() };
+
+    for wallet_id in [wallet.id, funding.id] {
+        match freezes::ensure_not_frozen(&mut *conn, wallet_id).await {
+            Ok(()) => {}
+            Err(FreezeError::Frozen(_)) => {
+                return Ok(TopUpOutcome::Failed(amount, format!("Wallet {} is frozen", wallet_id)));
+            }
+            Err(FreezeError::Database(e)) => return Err(e),
+        }
+    }
+
+    // The amount is in the topped-up wallet's currency; the funding wallet pays its equivalent
+    let Some(out) = currencies::convert(&mut *conn, &amount, &wallet.currency, &funding.currency).await? else {
+        return Ok(TopUpOutcome::Failed(amount, currencies::no_rate(&funding.currency, &wallet.currency)));
+    };
+    if let Some(available) = standing_orders::sendable(&mut *conn, funding).await?
+        && out.amount > available
+    {
+        return Ok(TopUpOutcome::Failed(
+            amount.clone(),
+            format!("Insufficient funds in {}. Available: {}, Required: {}", funding.name, available, out.amount),
+        ));
+    }
+
+    let description = format!("Auto top-up from {}", funding.name);
+    standing_orders::post_transfer(&mut *conn, funding, wallet, &out.amount, &amount, &description, now).await?;
+
+    Ok(TopUpOutcome::Completed(amount))
+}
+
+/// Midnight UTC of `now`'s day, where daily caps reset
+fn start_of_day(now: DateTime<Utc>) -> DateTime<Utc> {
+    now.date_naive().and_hms_opt(0, 0, 0).expect("midnight is a valid time").and_utc()
+}
+
+// ==================== Database Functions ====================
+
+async fn fetch_user_wallet(pool: &PgPool, wallet_id: Uuid, user_id: &str) -> Result<Option<Wallet>, sqlx::Error> {
+    sqlx::query_as::<_, Wallet>(&format!("SELECT {} FROM wallets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL", WALLET_COLUMNS))
+        .bind(wallet_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/top-up-rules")
+        .create("", create_top_up_rule)
+        .user(Method::GET, "/{user_id}", get_user_top_up_rules)
+        .user(Method::PUT, "/{user_id}/{rule_id}", update_top_up_rule)
+        .user(Method::DELETE, "/{user_id}/{rule_id}", delete_top_up_rule)
+        .user(Method::GET, "/{user_id}/{rule_id}/history", get_top_up_history)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
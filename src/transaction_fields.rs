@@ -0,0 +1,231 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::cache::{get_or_set_cache, invalidate_user_cache, user_cache_key, CacheBackend};
+use crate::routes::ScopedRoutes;
+use crate::telemetry;
+use crate::models::{ApiResponse, CreateFieldDefinitionRequest, FieldType, TransactionFieldDefinition};
+
+// ==================== CUSTOM TRANSACTION FIELDS ====================
+//
+// Users define typed fields (`project`: text, `reimbursable`: boolean, ...)
+// and set their values in `transactions.metadata`. Writes are validated
+// against the user's definitions: unknown keys and mistyped values are
+// rejected. Deleting a definition leaves existing values in place; they are
+// still returned and filterable but can't be written again.
+//
+// ============================================================================
+
+/// Maximum number of field definitions per user
+const MAX_FIELDS_PER_USER: i64 = 50;
+
+/// Maximum length of a text field value
+const MAX_TEXT_VALUE_CHARS: usize = 500;
+
+// ==================== Handlers ====================
+
+/// List a user's custom field definitions (with caching)
+pub async fn get_user_fields(
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, "transaction_fields").await;
+
+    let result = get_or_set_cache(
+        cache.get_ref(),
+        &cache_key,
+        fetch_field_definitions(db.get_ref(), &user_id),
+    )
+    .await;
+
+    match result {
+        Ok(fields) => HttpResponse::Ok().json(ApiResponse::success(fields)),
+        Err(e) => HttpResponse::InternalServerError()
+            .json(ApiResponse::<Vec<TransactionFieldDefinition>>::error(e.to_string())),
+    }
+}
+
+/// Define a custom field
+pub async fn create_field(
+    req: web::Json<CreateFieldDefinitionRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    if let Err(msg) = validate_key(&req.key) {
+        return HttpResponse::BadRequest().json(ApiResponse::<TransactionFieldDefinition>::error(msg));
+    }
+
+    let count: Result<(i64,), sqlx::Error> =
+        sqlx::query_as("SELECT COUNT(*) FROM transaction_field_definitions WHERE user_id = $1")
+            .bind(&req.user_id)
+            .fetch_one(db.get_ref())
+            .await;
+    match count {
+        Ok((count,)) if count >= MAX_FIELDS_PER_USER => {
+            return HttpResponse::BadRequest().json(ApiResponse::<TransactionFieldDefinition>::error(
+                format!("A user can define at most {} fields", MAX_FIELDS_PER_USER),
+            ));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Error counting field definitions: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<TransactionFieldDefinition>::error("Database error".to_string()));
+        }
+    }
+
+    let result = sqlx::query_as::<_, TransactionFieldDefinition>(
+        "INSERT INTO transaction_field_definitions (id, user_id, key, field_type, created_at)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (user_id, key) DO NOTHING
+         RETURNING id, user_id, key, field_type, created_at",
+    )
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(&req.key)
+    .bind(req.field_type.as_str())
+    .bind(Utc::now())
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(field)) => {
+            let _ = invalidate_user_cache(cache.get_ref(), &req.user_id).await;
+            HttpResponse::Created().json(ApiResponse::success(field))
+        }
+        Ok(None) => HttpResponse::Conflict().json(ApiResponse::<TransactionFieldDefinition>::error(
+            format!("Field '{}' is already defined", req.key),
+        )),
+        Err(e) => {
+            log::error!("Error creating field definition: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<TransactionFieldDefinition>::error("Failed to create field".to_string()))
+        }
+    }
+}
+
+/// Delete a custom field definition (existing values are kept)
+pub async fn delete_field(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, key) = path.into_inner();
+
+    let result = sqlx::query("DELETE FROM transaction_field_definitions WHERE user_id = $1 AND key = $2")
+        .bind(&user_id)
+        .bind(&key)
+        .execute(db.get_ref())
+        .await;
+
+    match result {
+        Ok(query_result) if query_result.rows_affected() > 0 => {
+            let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+            HttpResponse::NoContent().finish()
+        }
+        Ok(_) => HttpResponse::NotFound().json(ApiResponse::<String>::error("Field not found".to_string())),
+        Err(e) => {
+            log::error!("Error deleting field definition: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<String>::error("Failed to delete field".to_string()))
+        }
+    }
+}
+
+// ==================== Validation ====================
+
+#[derive(Debug)]
+pub enum MetadataError {
+    Invalid(String),
+    Database(sqlx::Error),
+}
+
+fn validate_key(key: &str) -> Result<(), String> {
+    let valid = key.len() <= 50
+        && key.starts_with(|c: char| c.is_ascii_lowercase())
+        && key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err("Field key must be 1-50 characters of lowercase letters, digits and underscores, starting with a letter"
+            .to_string())
+    }
+}
+
+/// Check metadata against the user's field definitions
+pub async fn validate_metadata(
+    pool: &PgPool,
+    user_id: &str,
+    metadata: &Map<String, Value>,
+) -> Result<(), MetadataError> {
+    if metadata.is_empty() {
+        return Ok(());
+    }
+
+    let definitions = fetch_field_definitions(pool, user_id)
+        .await
+        .map_err(MetadataError::Database)?;
+
+    check_metadata(&definitions, metadata).map_err(MetadataError::Invalid)
+}
+
+/// Check metadata against already fetched field definitions
+pub fn check_metadata(definitions: &[TransactionFieldDefinition], metadata: &Map<String, Value>) -> Result<(), String> {
+    for (key, value) in metadata {
+        let definition = definitions
+            .iter()
+            .find(|d| &d.key == key)
+            .ok_or_else(|| format!("Unknown field '{}'", key))?;
+        let field_type = FieldType::from_str(&definition.field_type)
+            .ok_or_else(|| format!("Field '{}' has an invalid type", key))?;
+
+        if !field_type.accepts(value) {
+            return Err(format!("Field '{}' must be a {}", key, field_type.as_str()));
+        }
+        if let Value::String(text) = value
+            && text.chars().count() > MAX_TEXT_VALUE_CHARS
+        {
+            return Err(format!("Field '{}' exceeds {} characters", key, MAX_TEXT_VALUE_CHARS));
+        }
+    }
+
+    Ok(())
+}
+
+// ==================== Database Functions ====================
+
+pub(crate) async fn fetch_field_definitions(
+    pool: &PgPool,
+    user_id: &str,
+) -> Result<Vec<TransactionFieldDefinition>, sqlx::Error> {
+    telemetry::sql(
+        "transaction_field_definitions",
+        "select",
+        sqlx::query_as::<_, TransactionFieldDefinition>(
+            "SELECT id, user_id, key, field_type, created_at FROM transaction_field_definitions
+             WHERE user_id = $1 ORDER BY key",
+        )
+        .bind(user_id)
+        .fetch_all(pool),
+    )
+    .await
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/transaction-fields")
+        .create("", create_field)
+        .user(Method::GET, "/{user_id}", get_user_fields)
+        .user(Method::DELETE, "/{user_id}/{key}", delete_field)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
@@ -0,0 +1,207 @@
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+
+use crate::auth::AuthUser;
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::limits;
+use crate::models::{
+    ApiResponse, DateRangeQuery, PageQuery, SearchHighlight, Transaction, TransactionSearchHit, TransactionSearchQuery,
+    TransactionSearchResults,
+};
+use crate::telemetry;
+
+// ==================== TRANSACTION SEARCH ====================
+//
+// `GET /api/transactions/user/{user_id}/search?q=` finds transactions two
+// ways, both served by expression indexes (see the migration):
+//
+// - Full-text: `q` is parsed with `websearch_to_tsquery` ("quoted phrases",
+//   `-excluded` words, `or`) and matched against the description, category,
+//   notes and amount, in that order of weight. Words are not stemmed, so
+//   the search works the same for any language.
+// - Fuzzy: trigram word similarity of `q` to the description and category
+//   catches typos and partial words ("grocerys", "netfl").
+//
+// Results are ordered by full-text rank plus similarity, newest first among
+// equals. `wallet_id`, `transaction_type` and a created_at range narrow the
+// search like the transaction list. Each hit lists where the query's words
+// occur (`highlights`, character offsets per field); fuzzy-only hits may
+// have none.
+//
+// ============================================================================
+
+/// Longest accepted `q`
+const MAX_QUERY_LEN: usize = 200;
+
+const MATCHES: &str = "FROM transactions t, websearch_to_tsquery('simple', $2) query
+     WHERE t.user_id = $1 AND t.deleted_at IS NULL
+       AND ($3::uuid IS NULL OR t.wallet_id = $3)
+       AND ($4::text IS NULL OR t.transaction_type = $4)
+       AND ($5::timestamptz IS NULL OR t.created_at >= $5)
+       AND ($6::timestamptz IS NULL OR t.created_at < $6)
+       AND (transaction_search_document(t.description, t.category, t.notes, t.amount) @@ query
+            OR LOWER($2) <% transaction_search_text(t.description, t.category))";
+
+/// A matching transaction with its rank
+#[derive(sqlx::FromRow)]
+struct SearchRow {
+    #[sqlx(flatten)]
+    transaction: Transaction,
+    rank: f32,
+}
+
+// ==================== Handlers ====================
+
+/// Search a user's transactions, best match first
+pub async fn search_transactions(
+    user: AuthUser,
+    search: web::Query<TransactionSearchQuery>,
+    range: web::Query<DateRangeQuery>,
+    db: web::Data<PgPool>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, AppError> {
+    let q = search.q.trim();
+    if q.is_empty() || q.chars().count() > MAX_QUERY_LEN {
+        return Err(AppError::Validation(format!("q must be 1-{} characters", MAX_QUERY_LEN)));
+    }
+    if let Some(t) = &search.transaction_type
+        && t != "income"
+        && t != "expense"
+    {
+        return Err(AppError::Validation("transaction_type must be 'income' or 'expense'".to_string()));
+    }
+    limits::validate_date_range(&config, &range).map_err(AppError::Validation)?;
+    let page = limits::page_params(
+        &config,
+        &PageQuery {
+            page: search.page,
+            per_page: search.per_page,
+        },
+    )
+    .map_err(AppError::Validation)?;
+
+    let (total_count,): (i64,) = telemetry::sql_one(
+        "transactions",
+        "search",
+        sqlx::query_as(&format!("SELECT COUNT(*) {}", MATCHES))
+            .bind(&user.user_id)
+            .bind(q)
+            .bind(search.wallet_id)
+            .bind(search.transaction_type.as_deref())
+            .bind(range.from)
+            .bind(range.to)
+            .fetch_one(db.get_ref()),
+    )
+    .await
+    .map_err(AppError::database("Failed to search transactions"))?;
+
+    let rows = telemetry::sql(
+        "transactions",
+        "search",
+        sqlx::query_as::<_, SearchRow>(&format!(
+            "SELECT t.id, t.sequence_number, t.user_id, t.wallet_id, t.bucket_id, t.income_source_id, t.amount, t.currency, t.transaction_type, t.category, t.description, t.notes, t.metadata, t.created_at, t.updated_at, t.version,
+                    ts_rank(transaction_search_document(t.description, t.category, t.notes, t.amount), query)
+                        + word_similarity(LOWER($2), transaction_search_text(t.description, t.category)) AS rank
+             {}
+             ORDER BY rank DESC, t.created_at DESC, t.id
+             LIMIT $7 OFFSET $8",
+            MATCHES
+        ))
+        .bind(&user.user_id)
+        .bind(q)
+        .bind(search.wallet_id)
+        .bind(search.transaction_type.as_deref())
+        .bind(range.from)
+        .bind(range.to)
+        .bind(page.limit())
+        .bind(page.offset())
+        .fetch_all(db.get_ref()),
+    )
+    .await
+    .map_err(AppError::database("Failed to search transactions"))?;
+
+    let terms = query_terms(q);
+    let results = rows
+        .into_iter()
+        .map(|row| TransactionSearchHit {
+            highlights: highlights(&row.transaction, &terms),
+            transaction: row.transaction,
+            rank: row.rank,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(TransactionSearchResults {
+        results,
+        total_count,
+    })))
+}
+
+// ==================== Highlighting ====================
+
+/// Lowercased words of `q` a match can contain: excluded words and `or` are
+/// dropped, quotes and punctuation around words are stripped
+fn query_terms(q: &str) -> Vec<Vec<char>> {
+    q.split_whitespace()
+        .filter(|word| !word.starts_with('-') && !word.eq_ignore_ascii_case("or"))
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .map(|word| word.chars().map(fold).collect())
+        .collect()
+}
+
+/// Case-folded character, one for one so offsets stay valid
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Occurrences of `terms` in the searched fields of `transaction`, merged
+/// where they overlap
+fn highlights(transaction: &Transaction, terms: &[Vec<char>]) -> Vec<SearchHighlight> {
+    let amount = transaction.amount.to_string();
+    let fields = [
+        ("description", transaction.description.as_deref()),
+        ("category", Some(transaction.category.as_str())),
+        ("notes", transaction.notes.as_deref()),
+        ("amount", Some(amount.as_str())),
+    ];
+
+    let mut highlights = Vec::new();
+    for (field, text) in fields {
+        let Some(text) = text else {
+            continue;
+        };
+        let chars: Vec<char> = text.chars().map(fold).collect();
+        let mut spans: Vec<(usize, usize)> = terms.iter().flat_map(|term| occurrences(&chars, term)).collect();
+        spans.sort_unstable();
+
+        let mut merged: Vec<(usize, usize)> = Vec::new();
+        for (start, end) in spans {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        highlights.extend(merged.into_iter().map(|(start, end)| SearchHighlight {
+            field: field.to_string(),
+            start,
+            end,
+        }));
+    }
+    highlights
+}
+
+/// Non-overlapping (start, end) offsets of `term` in `text`
+fn occurrences(text: &[char], term: &[char]) -> Vec<(usize, usize)> {
+    let mut found = Vec::new();
+    let mut start = 0;
+    while !term.is_empty() && start + term.len() <= text.len() {
+        if text[start..start + term.len()] == *term {
+            found.push((start, start + term.len()));
+            start += term.len();
+        } else {
+            start += 1;
+        }
+    }
+    found
+}
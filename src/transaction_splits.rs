@@ -0,0 +1,187 @@
+use std::collections::HashSet;
+
+use actix_web::{web, HttpResponse};
+use sqlx::types::BigDecimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::cache::{get_or_set_cache, user_cache_key, CacheBackend};
+use crate::categories;
+use crate::error::AppError;
+use crate::money;
+use crate::models::{ApiResponse, SplitLine, Transaction, TransactionSplit};
+use crate::telemetry;
+
+// ==================== TRANSACTION SPLITS ====================
+//
+// A transaction can be spread over several categories: one supermarket
+// receipt covering groceries, household and alcohol. The lines are stored in
+// `transaction_splits`; the transaction keeps its own category as the
+// headline shown in lists and filters.
+//
+// - Lines are given with the transaction (`splits` on create and update) and
+//   written in the same database transaction. There are at least two, with
+//   distinct categories and positive amounts in cents, summing exactly to
+//   the transaction's amount.
+// - Line categories are matched against the user's list like the
+//   transaction's own (see categories.rs), and follow renames and merges.
+// - Changing the amount of a split transaction needs new lines (or `[]` to
+//   drop them), so the lines always add up.
+// - Category reports aggregate over `v_transaction_category_lines`, which has
+//   a split transaction's lines in place of the transaction itself.
+//
+// ============================================================================
+
+/// Maximum number of lines in one split
+const MAX_SPLIT_LINES: usize = 20;
+
+const SPLIT_COLUMNS: &str = "id, transaction_id, user_id, category, amount, created_at";
+
+// ==================== Handlers ====================
+
+/// `GET /{user_id}/{transaction_id}/splits` - a transaction's category lines (with caching)
+///
+/// Empty for a transaction that isn't split.
+pub async fn get_transaction_splits(
+    path: web::Path<(String, Uuid)>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, transaction_id) = path.into_inner();
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, &format!("transaction:{}:splits", transaction_id)).await;
+
+    let pool = db.get_ref();
+    let splits = get_or_set_cache(cache.get_ref(), &cache_key, async {
+        let exists: Option<(Uuid,)> =
+            sqlx::query_as("SELECT id FROM transactions WHERE id = $1 AND has_wallet_role(wallet_id, $2, 'viewer') AND deleted_at IS NULL")
+                .bind(transaction_id)
+                .bind(&user_id)
+                .fetch_optional(pool)
+                .await?;
+        if exists.is_none() {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        fetch_splits(pool, transaction_id).await
+    })
+    .await
+    .map_err(AppError::or_not_found("Transaction not found"))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(splits)))
+}
+
+// ==================== Validation ====================
+
+/// Check that `lines` can split a transaction of `amount`
+///
+/// Category names are only checked for being distinct as given; resolving
+/// them (see `insert_lines`) may still find two spellings of one category.
+pub fn check_lines(amount: &BigDecimal, lines: &[SplitLine]) -> Result<(), String> {
+    if lines.len() < 2 {
+        return Err("A split needs at least two lines".to_string());
+    }
+    if lines.len() > MAX_SPLIT_LINES {
+        return Err(format!("A split can have at most {} lines", MAX_SPLIT_LINES));
+    }
+
+    let zero = BigDecimal::from(0);
+    let mut seen = HashSet::new();
+    for line in lines {
+        let category = line.category.trim();
+        if category.is_empty() {
+            return Err("Every split line needs a category".to_string());
+        }
+        if !seen.insert(category.to_lowercase()) {
+            return Err(format!("Category '{}' appears in more than one split line", category));
+        }
+        if line.amount <= zero {
+            return Err(format!("Split amount for '{}' must be greater than 0", category));
+        }
+        if money::round_cents(&line.amount) != line.amount {
+            return Err(format!("Split amount for '{}' can't have fractions of a cent", category));
+        }
+    }
+
+    let total: BigDecimal = lines.iter().map(|line| &line.amount).sum();
+    if &total != amount {
+        return Err(format!("Split lines add up to {} but the amount is {}", total, amount));
+    }
+    Ok(())
+}
+
+// ==================== Database Functions ====================
+
+/// Write the lines of a newly recorded transaction inside the caller's database transaction
+///
+/// Call `check_lines` first. Categories are resolved to the user's spelling,
+/// adding new ones.
+pub(crate) async fn insert_lines(
+    conn: &mut PgConnection,
+    transaction: &Transaction,
+    lines: &[SplitLine],
+) -> Result<Vec<TransactionSplit>, AppError> {
+    let mut categories_used = HashSet::new();
+    let mut splits = Vec::with_capacity(lines.len());
+    for line in lines {
+        let category = categories::resolve(&mut *conn, &transaction.user_id, &line.category).await?;
+        if !categories_used.insert(category.clone()) {
+            return Err(AppError::Validation(format!(
+                "Category '{}' appears in more than one split line",
+                category
+            )));
+        }
+
+        let split = sqlx::query_as::<_, TransactionSplit>(&format!(
+            "INSERT INTO transaction_splits (transaction_id, user_id, category, amount)
+             VALUES ($1, $2, $3, $4)
+             RETURNING {}",
+            SPLIT_COLUMNS
+        ))
+        .bind(transaction.id)
+        .bind(&transaction.user_id)
+        .bind(&category)
+        .bind(&line.amount)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(AppError::database("Failed to save split lines"))?;
+        splits.push(split);
+    }
+    Ok(splits)
+}
+
+/// Replace a transaction's lines (none removes the split)
+pub(crate) async fn replace_lines(
+    conn: &mut PgConnection,
+    transaction: &Transaction,
+    lines: &[SplitLine],
+) -> Result<Vec<TransactionSplit>, AppError> {
+    sqlx::query("DELETE FROM transaction_splits WHERE transaction_id = $1")
+        .bind(transaction.id)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::database("Failed to save split lines"))?;
+    insert_lines(conn, transaction, lines).await
+}
+
+/// Whether a transaction is split
+pub(crate) async fn is_split(conn: &mut PgConnection, transaction_id: Uuid) -> Result<bool, sqlx::Error> {
+    let (split,): (bool,) =
+        sqlx::query_as("SELECT EXISTS (SELECT 1 FROM transaction_splits WHERE transaction_id = $1)")
+            .bind(transaction_id)
+            .fetch_one(&mut *conn)
+            .await?;
+    Ok(split)
+}
+
+async fn fetch_splits(pool: &PgPool, transaction_id: Uuid) -> Result<Vec<TransactionSplit>, sqlx::Error> {
+    telemetry::sql(
+        "transaction_splits",
+        "select",
+        sqlx::query_as::<_, TransactionSplit>(&format!(
+            "SELECT {} FROM transaction_splits WHERE transaction_id = $1 ORDER BY amount DESC, category",
+            SPLIT_COLUMNS
+        ))
+        .bind(transaction_id)
+        .fetch_all(pool),
+    )
+    .await
+}
@@ -1,13 +1,65 @@
+use std::collections::BTreeMap;
+
+use actix_multipart::Multipart;
+use actix_web::http::{Method, StatusCode};
 use actix_web::{web, HttpResponse};
-use redis::aio::ConnectionManager;
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use uuid::Uuid;
-use chrono::Utc;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
 use sqlx::types::BigDecimal;
-use std::str::FromStr;
+use serde_json::Value;
+
+use crate::attachments::{self, StorageBackend};
+use crate::audit::{self, AuditContext};
+use crate::auth::AuthUser;
+use crate::batch_entry;
+use crate::budgets;
+use crate::buckets;
+use crate::bulk_transactions;
+use crate::bulk_updates;
+use crate::categories;
+use crate::config::AppConfig;
+use crate::data_export;
+use crate::dry_run::{self, DryRun};
+use crate::error::AppError;
+use crate::fieldsets;
+use crate::filters::{FilterQuery, TransactionFilter, TransactionSort};
+use crate::freezes;
+use crate::hooks::{self, HookContext, TransactionCreated, TransactionDeleted, TransactionRestored, TransactionUpdated};
+use crate::idempotency::{self, IdempotencyKey};
+use crate::income_sources;
+use crate::limits::{self, PageParams};
+use crate::list_versions;
+use crate::policy;
+use crate::routes::ScopedRoutes;
+use crate::statement_imports;
+use crate::templates;
+use crate::telemetry;
+use crate::markdown;
+use crate::reimbursements;
+use crate::transaction_fields;
+use crate::transaction_search;
+use crate::transaction_splits;
+use crate::validation::{self, Fields, Validate};
+use crate::wallet_members::{self, Permission};
+use crate::wallet_types;
+use crate::models::{ApiResponse, CreateTransactionRequest, CreatedTransaction, DateRangeQuery, DeleteQuery, DeletedTransaction, PageQuery, Transaction, TransactionFilterQuery, TransactionListQuery, TransactionNotes, TransactionPage, TransactionPolicy, UpdateTransactionRequest, UpdatedTransaction, Wallet, WalletBalance, WalletTemplate};
+use crate::cache::{get_or_set_cache, user_cache_key, CacheBackend};
+
+/// Maximum size of transaction notes in bytes
+const MAX_NOTES_BYTES: usize = 64 * 1024;
 
-use crate::models::{ApiResponse, CreateTransactionRequest, Transaction, UpdateTransactionRequest, Wallet, WalletType};
-use crate::cache::{get_or_set_cache, invalidate_cache_pattern};
+/// Longest category name and description, in characters
+const MAX_CATEGORY_CHARS: usize = 100;
+const MAX_DESCRIPTION_CHARS: usize = 1000;
+
+/// Entity type used for transaction attachments
+pub(crate) const TRANSACTION_ENTITY: &str = "transaction";
+
+/// Idempotency key scope of `create_transaction`
+const TRANSACTIONS_ROUTE: &str = "transactions";
 
 // ==================== ATOMIC TRANSACTION PATTERN EXAMPLE ====================
 // 
@@ -30,512 +82,774 @@ use crate::cache::{get_or_set_cache, invalidate_cache_pattern};
 //
 // ============================================================================
 
+/// Position of the last transaction of a page in the list's sort
+struct Cursor {
+    sort: TransactionSort,
+    created_at: DateTime<Utc>,
+    amount: BigDecimal,
+    id: Uuid,
+}
+
+impl Cursor {
+    fn after(sort: TransactionSort, transaction: &Transaction) -> Self {
+        Self {
+            sort,
+            created_at: transaction.created_at,
+            amount: transaction.amount.clone(),
+            id: transaction.id,
+        }
+    }
+
+    fn encode(&self) -> String {
+        BASE64.encode(format!(
+            "{}:{}:{}:{}",
+            self.sort.as_str(),
+            self.created_at.timestamp_micros(),
+            self.amount,
+            self.id
+        ))
+    }
+
+    /// Parse a cursor issued for `sort`
+    fn decode(cursor: &str, sort: TransactionSort) -> Option<Self> {
+        let raw = String::from_utf8(BASE64.decode(cursor).ok()?).ok()?;
+        let mut parts = raw.splitn(4, ':');
+        if parts.next()? != sort.as_str() {
+            return None;
+        }
+        let created_at = DateTime::from_timestamp_micros(parts.next()?.parse().ok()?)?;
+        let amount = parts.next()?.parse().ok()?;
+        let id = parts.next()?.parse().ok()?;
+        Some(Self { sort, created_at, amount, id })
+    }
+
+    /// Restrict `query` to the transactions after this one
+    fn apply<'args>(self, query: FilterQuery<'args>) -> FilterQuery<'args> {
+        let ascending = self.sort.ascending();
+        match self.sort {
+            TransactionSort::Newest | TransactionSort::Oldest => {
+                query.after("(created_at, id)", ascending, self.created_at, self.id)
+            }
+            TransactionSort::AmountDesc | TransactionSort::AmountAsc => {
+                query.after("(amount, id)", ascending, self.amount, self.id)
+            }
+        }
+    }
+}
+
 // ==================== CRUD Handlers ====================
+//
+// Handlers return early with `?`; dropping an uncommitted `db_tx` rolls it back.
 
-/// Get all transactions for a user (with caching)
+/// Get a page of the transactions of a user's wallets and of wallets shared
+/// with them, newest first (with caching)
+///
+/// Supports `?page=&per_page=` or `?cursor=&per_page=`, `?sort=`, an optional
+/// `?from=&to=` created_at range, and the filters of `TransactionFilterQuery`.
+/// `?fields=` keeps only the named fields of each transaction. The response
+/// carries the filtered `total_count` and a `next_cursor`.
 pub async fn get_user_transactions(
-    user_id: web::Path<String>,
+    user: AuthUser,
+    list: web::Query<TransactionListQuery>,
+    range: web::Query<DateRangeQuery>,
+    filter: web::Query<TransactionFilterQuery>,
     db: web::Data<PgPool>,
-    cache: web::Data<ConnectionManager>,
-) -> HttpResponse {
-    let user_id = user_id.into_inner();
-    let cache_key = format!("transactions:{}", user_id);
+    cache: web::Data<dyn CacheBackend>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = user.user_id;
 
-    let result = get_or_set_cache(
-        &cache.get_ref(),
+    let page_query = PageQuery {
+        page: list.page,
+        per_page: list.per_page,
+    };
+    let page = limits::page_params(&config, &page_query).map_err(AppError::Validation)?;
+    let filter = TransactionFilter::from_query(&config, &filter, &range).map_err(AppError::Validation)?;
+    let sort = TransactionSort::parse(list.sort.as_deref()).map_err(AppError::Validation)?;
+    let fields = fieldsets::parse(list.fields.as_deref(), Transaction::FIELDS).map_err(AppError::Validation)?;
+    let cursor = match list.cursor.as_deref() {
+        Some(cursor) => Some(
+            Cursor::decode(cursor, sort)
+                .ok_or_else(|| AppError::Validation("Invalid cursor for this sort".to_string()))?,
+        ),
+        None => None,
+    };
+    if cursor.is_some() && page.page > 1 {
+        return Err(AppError::Validation("cursor and page can't be combined".to_string()));
+    }
+
+    let suffix = format!(
+        "transactions:{}:{}:s={}:cur={}",
+        page.cache_suffix(),
+        filter.cache_suffix(),
+        sort.as_str(),
+        list.cursor.as_deref().unwrap_or_default()
+    );
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, &suffix).await;
+
+    let transactions = get_or_set_cache(
+        cache.get_ref(),
         &cache_key,
-        fetch_transactions_from_db(db.get_ref(), &user_id),
+        fetch_transactions_from_db(db.get_ref(), &user_id, filter, sort, cursor, page),
     )
-    .await;
+    .await?;
 
-    match result {
-        Ok(transactions) => HttpResponse::Ok().json(ApiResponse::success(transactions)),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<Vec<Transaction>>::error(e.to_string())),
-    }
+    let version = list_versions::list_version(&transactions.transactions, Some(transactions.total_count));
+    let Some(fields) = fields else {
+        return Ok(list_versions::list_response(version, transactions));
+    };
+    let projected = serde_json::json!({
+        "transactions": fieldsets::project(&transactions.transactions, Some(&fields))?,
+        "total_count": transactions.total_count,
+        "next_cursor": transactions.next_cursor,
+    });
+    Ok(list_versions::list_response(version, projected))
 }
 
 /// Get a single transaction by ID
 pub async fn get_transaction(
-    path: web::Path<(String, String)>,
+    path: web::Path<(String, Uuid)>,
     db: web::Data<PgPool>,
-    cache: web::Data<ConnectionManager>,
-) -> HttpResponse {
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
     let (user_id, transaction_id) = path.into_inner();
-    let cache_key = format!("transaction:{}:{}", user_id, transaction_id);
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, &format!("transaction:{}", transaction_id)).await;
 
-    let result = get_or_set_cache(
-        &cache.get_ref(),
+    let transaction = get_or_set_cache(
+        cache.get_ref(),
         &cache_key,
-        fetch_transaction_by_id(db.get_ref(), &transaction_id, &user_id),
+        fetch_transaction_by_id(db.get_ref(), transaction_id, &user_id),
     )
-    .await;
+    .await
+    .map_err(AppError::or_not_found("Transaction not found"))?;
 
-    match result {
-        Ok(transaction) => HttpResponse::Ok().json(ApiResponse::success(transaction)),
-        Err(e) => HttpResponse::NotFound()
-            .json(ApiResponse::<Transaction>::error(e.to_string())),
-    }
+    Ok(HttpResponse::Ok().json(ApiResponse::success(transaction)))
 }
 
 /// Create a new transaction with atomic balance updates
 pub async fn create_transaction(
     req: web::Json<CreateTransactionRequest>,
+    key: IdempotencyKey,
+    dry_run: DryRun,
+    context: AuditContext,
     db: web::Data<PgPool>,
-    cache: web::Data<ConnectionManager>,
-) -> HttpResponse {
-    let transaction_id = Uuid::new_v4().to_string();
-    let now = Utc::now();
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    // Validate amount and description against the deployment's policy
+    let transaction_policy = policy::transaction_policy(db.get_ref(), cache.get_ref()).await?;
+
+    // Start database transaction (BEGIN/COMMIT)
+    let mut db_tx = db.begin().await?;
+    audit::attach(&mut db_tx, &context).await?;
+
+    // A retry with the same Idempotency-Key gets the first response back
+    if let Some(response) = idempotency::replay(&mut db_tx, &key, &req.user_id, TRANSACTIONS_ROUTE, &req.0).await? {
+        return Ok(response);
+    }
+
+    let created = record_transaction(&mut db_tx, db.get_ref(), &transaction_policy, &req).await?;
+    let transaction = created.transaction.clone();
+    if dry_run.0 {
+        let wallet_ids = [req.wallet_id];
+        return dry_run::preview(db.get_ref(), db_tx, &req.user_id, StatusCode::CREATED, Some(created), &wallet_ids)
+            .await;
+    }
+    let response = idempotency::record(
+        &mut db_tx,
+        &key,
+        &req.user_id,
+        TRANSACTIONS_ROUTE,
+        StatusCode::CREATED,
+        &ApiResponse::success(created),
+    )
+    .await?;
+
+    // Commit database transaction
+    db_tx.commit().await.map_err(AppError::database("Failed to save changes"))?;
 
-    // Fetch wallet to validate and check balance
-    let wallet: Option<Wallet> = match sqlx::query_as::<_, Wallet>(
-        "SELECT id, user_id, name, balance, credit_limit, wallet_type, created_at, updated_at FROM wallets WHERE id = $1 AND user_id = $2"
+    // Invalidate caches (wallets + transactions live in each member's namespace)
+    wallet_members::invalidate_members(db.get_ref(), cache.get_ref(), &req.user_id, &[req.wallet_id]).await;
+
+    let cx = HookContext { pool: db.get_ref(), cache: cache.get_ref() };
+    hooks::transaction_created(&cx, TransactionCreated { transaction: &transaction }).await;
+
+    Ok(response)
+}
+
+/// Validate a new transaction and apply it inside the caller's database transaction
+///
+/// Inserts the transaction and its split lines, moves its wallet's balance,
+/// draws from its bucket, settles a matching reimbursement and records the
+/// budget thresholds it crossed, and returns the wallet's resulting balance. On `Err` the caller rolls back. The caller also invalidates the
+/// cache and runs top-ups after committing (see `create_transaction`).
+///
+/// A wallet shared with `req.user_id` takes the transaction if they are an
+/// editor; it is recorded under the wallet owner's `user_id`.
+pub(crate) async fn record_transaction(
+    conn: &mut PgConnection,
+    pool: &PgPool,
+    transaction_policy: &TransactionPolicy,
+    req: &CreateTransactionRequest,
+) -> Result<CreatedTransaction, AppError> {
+    // Fetch and lock the wallet, so no concurrent change moves its balance
+    // between the funds check and the update
+    let wallet = sqlx::query_as::<_, Wallet>(
+        "SELECT id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, created_at, updated_at, version FROM wallets WHERE id = $1 AND has_wallet_role(id, $2, 'viewer') AND deleted_at IS NULL FOR UPDATE"
     )
-    .bind(&req.wallet_id)
+    .bind(req.wallet_id)
     .bind(&req.user_id)
-    .fetch_optional(db.get_ref())
-    .await {
-        Ok(w) => w,
-        Err(e) => {
-            log::error!("Error fetching wallet: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<Transaction>::error("Failed to validate wallet".to_string()));
-        }
-    };
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(AppError::database("Failed to validate wallet"))?
+    .ok_or_else(|| AppError::Validation("Wallet not found or doesn't belong to user".to_string()))?;
+    wallet_members::authorize_wallet_access(&mut *conn, &req.user_id, &wallet.id.to_string(), Permission::Edit).await?;
 
-    let wallet = match wallet {
-        Some(w) => w,
-        None => {
-            return HttpResponse::BadRequest()
-                .json(ApiResponse::<Transaction>::error("Wallet not found or doesn't belong to user".to_string()));
-        }
-    };
+    let mut created = insert_transaction(&mut *conn, pool, transaction_policy, req, &wallet).await?;
+    move_balance(&mut *conn, req.wallet_id, &balance_delta(&req.transaction_type, &req.amount)?).await?;
+    created.wallet_balances = wallet_balances(conn, &[req.wallet_id]).await?;
+    Ok(created)
+}
+
+/// Validate a new transaction against `wallet` and insert it, leaving the
+/// wallet's balance to the caller
+///
+/// Everything `record_transaction` does except the balance update: a bulk
+/// create checks funds against its running balances and moves each wallet
+/// once (see bulk_transactions.rs). Income sources, fields and categories
+/// are the wallet owner's.
+pub(crate) async fn insert_transaction(
+    conn: &mut PgConnection,
+    pool: &PgPool,
+    transaction_policy: &TransactionPolicy,
+    req: &CreateTransactionRequest,
+    wallet: &Wallet,
+) -> Result<CreatedTransaction, AppError> {
+    let transaction_id = Uuid::new_v4();
+    let now = Utc::now();
+
+    validation::check(req)?;
 
-    // Validate transaction type
-    if req.transaction_type != "income" && req.transaction_type != "expense" {
-        return HttpResponse::BadRequest()
-            .json(ApiResponse::<Transaction>::error("Invalid transaction type. Must be 'income' or 'expense'".to_string()));
+    if let Some(violation) = policy::check(transaction_policy, &req.amount, &req.description).into_iter().next() {
+        return Err(violation.into());
     }
 
-    // Validate amount is positive
-    if req.amount <= BigDecimal::from_str("0").unwrap() {
-        return HttpResponse::BadRequest()
-            .json(ApiResponse::<Transaction>::error("Amount must be greater than 0".to_string()));
+    if !req.splits.is_empty() {
+        transaction_splits::check_lines(&req.amount, &req.splits).map_err(AppError::Validation)?;
+    }
+
+    if let Some(source_id) = req.income_source_id {
+        check_income_source(pool, &wallet.user_id, source_id).await?;
+    }
+
+    if let Some(metadata) = &req.metadata {
+        transaction_fields::validate_metadata(pool, &wallet.user_id, metadata).await?;
     }
 
     // Balance validation for expenses
     if req.transaction_type == "expense" {
-        let wallet_type = WalletType::from_str(&wallet.wallet_type).unwrap_or(WalletType::Other);
-        
-        match wallet_type {
-            WalletType::CreditCard => {
-                // For credit cards: check available credit (credit_limit - balance)
-                if let Some(limit) = &wallet.credit_limit {
-                    let available = limit - &wallet.balance;
-                    if req.amount > available {
-                        return HttpResponse::BadRequest()
-                            .json(ApiResponse::<Transaction>::error(
-                                format!("Insufficient credit. Available: {}, Required: {}", available, req.amount)
-                            ));
-                    }
-                } else {
-                    return HttpResponse::InternalServerError()
-                        .json(ApiResponse::<Transaction>::error("Credit card missing credit limit".to_string()));
-                }
-            }
-            _ => {
-                // For other wallets: balance cannot go negative
-                if req.amount > wallet.balance {
-                    return HttpResponse::BadRequest()
-                        .json(ApiResponse::<Transaction>::error(
-                            format!("Insufficient balance. Available: {}, Required: {}", wallet.balance, req.amount)
-                        ));
-                }
-            }
-        }
+        let template = wallet_types::template_for(&mut *conn, wallet)
+            .await
+            .map_err(AppError::database("Failed to validate wallet"))?;
+        check_expense_funds(&template, wallet, &req.amount)?;
     }
 
-    // Start database transaction (BEGIN/COMMIT)
-    let mut db_tx = match db.begin().await {
-        Ok(t) => t,
-        Err(e) => {
-            log::error!("Failed to begin database transaction: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<Transaction>::error("Database error".to_string()));
-        }
-    };
+    // Fat-finger guard: an amount far out of line needs an explicit confirm
+    let flagged = policy::check_sanity(
+        &mut *conn,
+        transaction_policy,
+        &wallet.user_id,
+        req.wallet_id,
+        None,
+        &req.amount,
+        &req.amount,
+    )
+    .await?;
+    if let Some(reason) = &flagged
+        && !req.confirm
+    {
+        return Err(policy::confirmation_required(reason));
+    }
+
+    // Store the listed spelling of the category, adding it if it's new
+    let category = categories::resolve(&mut *conn, &wallet.user_id, &req.category).await?;
 
     // Insert transaction record
-    let insert_result = sqlx::query_as::<_, Transaction>(
-        "INSERT INTO transactions (id, user_id, wallet_id, amount, transaction_type, category, description, created_at, updated_at) 
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) 
-         RETURNING id, user_id, wallet_id, amount, transaction_type, category, description, created_at, updated_at"
+    let transaction = sqlx::query_as::<_, Transaction>(
+        "INSERT INTO transactions (id, user_id, wallet_id, bucket_id, income_source_id, amount, transaction_type, category, description, notes, metadata, created_at, updated_at) 
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13) 
+         RETURNING id, sequence_number, user_id, wallet_id, bucket_id, income_source_id, amount, currency, transaction_type, category, description, notes, metadata, created_at, updated_at, version"
     )
-    .bind(&transaction_id)
-    .bind(&req.user_id)
-    .bind(&req.wallet_id)
+    .bind(transaction_id)
+    .bind(&wallet.user_id)
+    .bind(req.wallet_id)
+    .bind(req.bucket_id)
+    .bind(req.income_source_id)
     .bind(&req.amount)
     .bind(&req.transaction_type)
-    .bind(&req.category)
+    .bind(&category)
     .bind(&req.description)
+    .bind(&req.notes)
+    .bind(Value::Object(req.metadata.clone().unwrap_or_default()))
     .bind(now)
     .bind(now)
-    .fetch_one(&mut *db_tx)
-    .await;
-
-    let transaction = match insert_result {
-        Ok(tx) => tx,
-        Err(e) => {
-            log::error!("Error inserting transaction: {}", e);
-            let _ = db_tx.rollback().await;
-            return HttpResponse::BadRequest()
-                .json(ApiResponse::<Transaction>::error("Failed to create transaction".to_string()));
-        }
-    };
-
-    // Calculate balance delta
-    let balance_delta = match req.transaction_type.as_str() {
-        "income" => req.amount.clone(),
-        "expense" => -req.amount.clone(),
-        _ => {
-            let _ = db_tx.rollback().await;
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<Transaction>::error("Invalid transaction type".to_string()));
-        }
-    };
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(AppError::database("Failed to create transaction"))?;
+    if let Some(reason) = flagged {
+        log::info!(
+            target: "audit",
+            "Transaction {} of user {} confirmed past sanity limits: {}",
+            transaction.id,
+            req.user_id,
+            reason
+        );
+    }
 
-    // Update wallet balance atomically
-    let update_result = sqlx::query("UPDATE wallets SET balance = balance + $1 WHERE id = $2")
-        .bind(&balance_delta)
-        .bind(&req.wallet_id)
-        .execute(&mut *db_tx)
-        .await;
+    // Spread the amount over the given categories
+    let splits = transaction_splits::insert_lines(&mut *conn, &transaction, &req.splits).await?;
 
-    match update_result {
-        Ok(_) => {},
-        Err(e) => {
-            log::error!("Error updating wallet balance: {}", e);
-            let _ = db_tx.rollback().await;
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<Transaction>::error("Failed to update wallet balance".to_string()));
-        }
+    // Draw from the chosen bucket; unbucketed expenses may only spend the unallocated balance
+    if let Some(bucket_id) = req.bucket_id {
+        buckets::draw(&mut *conn, req.wallet_id, bucket_id, &req.amount).await?;
     }
 
-    // Commit database transaction
-    if let Err(e) = db_tx.commit().await {
-        log::error!("Failed to commit database transaction: {}", e);
-        return HttpResponse::InternalServerError()
-            .json(ApiResponse::<Transaction>::error("Failed to save changes".to_string()));
+    // Settle a matching open reimbursement with this income
+    if let Some(settled) = reimbursements::auto_settle(&mut *conn, &transaction)
+        .await
+        .map_err(AppError::database("Failed to save changes"))?
+    {
+        log::info!("Transaction {} settled reimbursement {}", transaction.id, settled.id);
     }
 
-    // Invalidate caches (specific wallet + all user transactions)
-    let mut cache_clone = cache.get_ref().clone();
-    let _ = invalidate_cache_pattern(&mut cache_clone, &format!("wallet:{}:{}*", req.user_id, req.wallet_id)).await;
-    let _ = invalidate_cache_pattern(&mut cache_clone, &format!("wallets:{}*", req.user_id)).await;
-    let _ = invalidate_cache_pattern(&mut cache_clone, &format!("transactions:{}*", req.user_id)).await;
+    // Raise the budget thresholds this expense crossed
+    let budget_warnings = budgets::record_crossings(&mut *conn, &transaction)
+        .await
+        .map_err(AppError::database("Failed to save changes"))?;
+
+    Ok(CreatedTransaction {
+        transaction,
+        over_budget: budget_warnings.iter().any(|w| w.over_limit),
+        budget_warnings,
+        splits,
+        wallet_balances: Vec::new(),
+    })
+}
 
-    HttpResponse::Created().json(ApiResponse::success(transaction))
+/// The error message `record_transaction` refused with
+pub(crate) fn rejection_reason(error: AppError) -> String {
+    error.log();
+    error.to_string()
 }
 
 /// Update a transaction with balance adjustments
 pub async fn update_transaction(
-    path: web::Path<(String, String)>,
+    path: web::Path<(String, Uuid)>,
     req: web::Json<UpdateTransactionRequest>,
+    dry_run: DryRun,
+    context: AuditContext,
     db: web::Data<PgPool>,
-    cache: web::Data<ConnectionManager>,
-) -> HttpResponse {
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
     let (user_id, transaction_id) = path.into_inner();
     let now = Utc::now();
+    validation::check(&req.0)?;
 
-    // Fetch current transaction
-    let current_tx: Option<Transaction> = match sqlx::query_as::<_, Transaction>(
-        "SELECT id, user_id, wallet_id, amount, transaction_type, category, description, created_at, updated_at FROM transactions WHERE id = $1 AND user_id = $2"
+    // Start database transaction
+    let mut db_tx = db.begin().await?;
+    audit::attach(&mut db_tx, &context).await?;
+
+    // Fetch and lock the current transaction, so concurrent edits apply one after the other
+    let current_tx = sqlx::query_as::<_, Transaction>(
+        "SELECT id, sequence_number, user_id, wallet_id, bucket_id, income_source_id, amount, currency, transaction_type, category, description, notes, metadata, created_at, updated_at, version FROM transactions WHERE id = $1 AND has_wallet_role(wallet_id, $2, 'viewer') AND deleted_at IS NULL FOR UPDATE"
     )
-    .bind(&transaction_id)
+    .bind(transaction_id)
     .bind(&user_id)
-    .fetch_optional(db.get_ref())
-    .await {
-        Ok(tx) => tx,
-        Err(e) => {
-            log::error!("Error fetching transaction: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<Transaction>::error("Database error".to_string()));
-        }
-    };
-
-    let current_tx = match current_tx {
-        Some(tx) => tx,
-        None => {
-            return HttpResponse::NotFound()
-                .json(ApiResponse::<Transaction>::error("Transaction not found".to_string()));
-        }
-    };
+    .fetch_optional(&mut *db_tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))?;
+    wallet_members::authorize_wallet_access(&mut *db_tx, &user_id, &current_tx.wallet_id.to_string(), Permission::Edit)
+        .await?;
+    let owner_id = current_tx.user_id.clone();
 
     // Determine new wallet and amount
-    let new_wallet_id = req.wallet_id.clone().unwrap_or_else(|| current_tx.wallet_id.clone());
+    let new_wallet_id = req.wallet_id.unwrap_or(current_tx.wallet_id);
     let new_amount = req.amount.clone().unwrap_or_else(|| current_tx.amount.clone());
 
-    // Validate new amount if changed
-    if req.amount.is_some() && new_amount <= BigDecimal::from_str("0").unwrap() {
-        return HttpResponse::BadRequest()
-            .json(ApiResponse::<Transaction>::error("Amount must be greater than 0".to_string()));
+    // A transaction can only move to another live wallet of the same owner
+    // the user can edit
+    if new_wallet_id != current_tx.wallet_id {
+        sqlx::query_as::<_, (Uuid,)>(
+            "SELECT id FROM wallets WHERE id = $1 AND user_id = $2 AND has_wallet_role(id, $3, 'editor') AND deleted_at IS NULL",
+        )
+        .bind(new_wallet_id)
+        .bind(&owner_id)
+        .bind(&user_id)
+        .fetch_optional(&mut *db_tx)
+        .await
+        .map_err(AppError::database("Failed to validate wallet"))?
+        .ok_or_else(|| AppError::Validation("Wallet not found or doesn't belong to user".to_string()))?;
     }
 
-    // Start database transaction
-    let mut db_tx = match db.begin().await {
-        Ok(t) => t,
-        Err(e) => {
-            log::error!("Failed to begin transaction: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<Transaction>::error("Database error".to_string()));
+    // Validate against the deployment's policy if the amount or description changed
+    let mut flagged = None;
+    if req.amount.is_some() || req.description.is_some() {
+        let transaction_policy = policy::transaction_policy(db.get_ref(), cache.get_ref()).await?;
+        let description = req.description.as_deref().or(current_tx.description.as_deref()).unwrap_or("");
+        if let Some(violation) = policy::check(&transaction_policy, &new_amount, description).into_iter().next() {
+            return Err(violation.into());
         }
-    };
 
-    // If wallet or amount changed, reverse old balance and validate new balance
-    if new_wallet_id != current_tx.wallet_id.clone() || req.amount.is_some() {
-        // Reverse old wallet balance
-        let old_wallet_id = current_tx.wallet_id.clone();
-        let reverse_delta = match current_tx.transaction_type.as_str() {
-            "income" => -current_tx.amount.clone(),
-            "expense" => current_tx.amount.clone(),
-            _ => {
-                let _ = db_tx.rollback().await;
-                return HttpResponse::InternalServerError()
-                    .json(ApiResponse::<Transaction>::error("Invalid transaction type".to_string()));
+        if req.amount.is_some() {
+            let change = &new_amount - &current_tx.amount;
+            flagged = policy::check_sanity(
+                &mut *db_tx,
+                &transaction_policy,
+                &owner_id,
+                new_wallet_id,
+                Some(current_tx.id),
+                &new_amount,
+                &change,
+            )
+            .await?;
+            if let Some(reason) = &flagged
+                && !req.confirm
+            {
+                return Err(policy::confirmation_required(reason));
             }
-        };
+        }
+    }
 
-        if let Err(e) = sqlx::query("UPDATE wallets SET balance = balance + $1 WHERE id = $2")
+    if let Some(lines) = &req.splits
+        && !lines.is_empty()
+    {
+        transaction_splits::check_lines(&new_amount, lines).map_err(AppError::Validation)?;
+    }
+
+    if let Some(source_id) = req.income_source_id {
+        if current_tx.transaction_type != "income" {
+            return Err(AppError::Validation("Only income can have an income source".to_string()));
+        }
+        check_income_source(db.get_ref(), &owner_id, source_id).await?;
+    }
+
+    if let Some(metadata) = &req.metadata {
+        transaction_fields::validate_metadata(db.get_ref(), &owner_id, metadata).await?;
+    }
+
+    let category = match &req.category {
+        Some(category) => Some(categories::resolve(&mut db_tx, &owner_id, category).await?),
+        None => None,
+    };
+
+    // Split lines must keep adding up to the amount
+    if req.splits.is_none()
+        && new_amount != current_tx.amount
+        && transaction_splits::is_split(&mut db_tx, current_tx.id).await?
+    {
+        return Err(AppError::Validation(
+            "This transaction is split; send new splits (or []) with the new amount".to_string(),
+        ));
+    }
+
+    // A bucket belongs to one wallet: moving the expense elsewhere detaches it
+    let new_bucket_id = if new_wallet_id == current_tx.wallet_id { current_tx.bucket_id } else { None };
+
+    // If wallet or amount changed, reverse old balance and apply the new one
+    if new_wallet_id != current_tx.wallet_id || req.amount.is_some() {
+        let old_wallet_id = current_tx.wallet_id;
+        let reverse_delta = -balance_delta(&current_tx.transaction_type, &current_tx.amount)?;
+        let new_delta = balance_delta(&current_tx.transaction_type, &new_amount)?;
+        lock_and_check_funds(&mut db_tx, &[(old_wallet_id, reverse_delta.clone()), (new_wallet_id, new_delta.clone())])
+            .await?;
+
+        // Reverse old wallet balance
+        sqlx::query("UPDATE wallets SET balance = balance + $1 WHERE id = $2")
             .bind(&reverse_delta)
-            .bind(&old_wallet_id)
+            .bind(old_wallet_id)
             .execute(&mut *db_tx)
             .await
-        {
-            log::error!("Error reversing old wallet balance: {}", e);
-            let _ = db_tx.rollback().await;
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<Transaction>::error("Failed to reverse old balance".to_string()));
-        }
+            .map_err(AppError::database("Failed to reverse old balance"))?;
 
-        // Check new wallet balance if amount is changing and it's an expense
-        if current_tx.transaction_type == "expense" && req.amount.is_some() {
-            let new_wallet: Option<Wallet> = match sqlx::query_as::<_, Wallet>(
-                "SELECT id, user_id, name, balance, credit_limit, wallet_type, created_at, updated_at FROM wallets WHERE id = $1"
-            )
-            .bind(&new_wallet_id)
-            .fetch_optional(&mut *db_tx)
-            .await {
-                Ok(w) => w,
-                Err(e) => {
-                    log::error!("Error fetching new wallet: {}", e);
-                    let _ = db_tx.rollback().await;
-                    return HttpResponse::InternalServerError()
-                        .json(ApiResponse::<Transaction>::error("Failed to validate wallet".to_string()));
-                }
-            };
-
-            if let Some(wallet) = new_wallet {
-                let wallet_type = WalletType::from_str(&wallet.wallet_type).unwrap_or(WalletType::Other);
-                match wallet_type {
-                    WalletType::CreditCard => {
-                        if let Some(limit) = &wallet.credit_limit {
-                            let available = limit - &wallet.balance;
-                            if new_amount > available {
-                                let _ = db_tx.rollback().await;
-                                return HttpResponse::BadRequest()
-                                    .json(ApiResponse::<Transaction>::error(
-                                        format!("Insufficient credit. Available: {}", available)
-                                    ));
-                            }
-                        }
-                    }
-                    _ => {
-                        if new_amount > wallet.balance {
-                            let _ = db_tx.rollback().await;
-                            return HttpResponse::BadRequest()
-                                .json(ApiResponse::<Transaction>::error(
-                                    format!("Insufficient balance. Available: {}", wallet.balance)
-                                ));
-                        }
-                    }
-                }
-            }
+        if let Some(bucket_id) = current_tx.bucket_id {
+            buckets::refund(&mut db_tx, bucket_id, &current_tx.amount).await?;
         }
 
         // Apply new wallet balance
-        let new_delta = match current_tx.transaction_type.as_str() {
-            "income" => new_amount.clone(),
-            "expense" => -new_amount.clone(),
-            _ => {
-                let _ = db_tx.rollback().await;
-                return HttpResponse::InternalServerError()
-                    .json(ApiResponse::<Transaction>::error("Invalid transaction type".to_string()));
-            }
-        };
-
-        if let Err(e) = sqlx::query("UPDATE wallets SET balance = balance + $1 WHERE id = $2")
+        sqlx::query("UPDATE wallets SET balance = balance + $1 WHERE id = $2")
             .bind(&new_delta)
-            .bind(&new_wallet_id)
+            .bind(new_wallet_id)
             .execute(&mut *db_tx)
             .await
-        {
-            log::error!("Error applying new wallet balance: {}", e);
-            let _ = db_tx.rollback().await;
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<Transaction>::error("Failed to apply new balance".to_string()));
+            .map_err(AppError::database("Failed to apply new balance"))?;
+
+        if let Some(bucket_id) = new_bucket_id {
+            buckets::draw(&mut db_tx, new_wallet_id, bucket_id, &new_amount).await?;
+        }
+        for wallet_id in [current_tx.wallet_id, new_wallet_id] {
+            freezes::ensure_not_frozen(&mut db_tx, wallet_id).await?;
+            buckets::ensure_covered(&mut db_tx, wallet_id).await?;
         }
     }
 
     // Update transaction
-    let update_result = sqlx::query_as::<_, Transaction>(
+    let updated_tx = sqlx::query_as::<_, Transaction>(
         "UPDATE transactions 
-         SET amount = $1, category = COALESCE($2, category), description = COALESCE($3, description), wallet_id = $4, updated_at = $5
-         WHERE id = $6 AND user_id = $7
-         RETURNING id, user_id, wallet_id, amount, transaction_type, category, description, created_at, updated_at"
+         SET amount = $1, category = COALESCE($2, category), description = COALESCE($3, description),
+             notes = COALESCE($4, notes), wallet_id = $5, updated_at = $6,
+             metadata = COALESCE($9, metadata), bucket_id = $10,
+             income_source_id = COALESCE($11, income_source_id)
+         WHERE id = $7 AND user_id = $8 AND deleted_at IS NULL
+         RETURNING id, sequence_number, user_id, wallet_id, bucket_id, income_source_id, amount, currency, transaction_type, category, description, notes, metadata, created_at, updated_at, version"
     )
     .bind(&new_amount)
-    .bind(&req.category)
+    .bind(&category)
     .bind(&req.description)
-    .bind(&new_wallet_id)
+    .bind(&req.notes)
+    .bind(new_wallet_id)
     .bind(now)
-    .bind(&transaction_id)
-    .bind(&user_id)
+    .bind(transaction_id)
+    .bind(&owner_id)
+    .bind(req.metadata.clone().map(Value::Object))
+    .bind(new_bucket_id)
+    .bind(req.income_source_id)
     .fetch_one(&mut *db_tx)
-    .await;
+    .await
+    .map_err(AppError::database("Failed to update transaction"))?;
 
-    let updated_tx = match update_result {
-        Ok(tx) => tx,
-        Err(e) => {
-            log::error!("Error updating transaction: {}", e);
-            let _ = db_tx.rollback().await;
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<Transaction>::error("Failed to update transaction".to_string()));
-        }
+    if let Some(lines) = &req.splits {
+        transaction_splits::replace_lines(&mut db_tx, &updated_tx, lines).await?;
+    }
+
+    let updated = UpdatedTransaction {
+        wallet_balances: wallet_balances(&mut db_tx, &[new_wallet_id, current_tx.wallet_id]).await?,
+        transaction: updated_tx,
     };
 
+    if dry_run.0 {
+        let wallet_ids = [current_tx.wallet_id, new_wallet_id];
+        return dry_run::preview(db.get_ref(), db_tx, &user_id, StatusCode::OK, Some(updated), &wallet_ids).await;
+    }
+
     // Commit transaction
-    if let Err(e) = db_tx.commit().await {
-        log::error!("Failed to commit transaction: {}", e);
-        return HttpResponse::InternalServerError()
-            .json(ApiResponse::<Transaction>::error("Failed to save changes".to_string()));
+    db_tx.commit().await.map_err(AppError::database("Failed to save changes"))?;
+
+    if let Some(reason) = flagged {
+        log::info!(
+            target: "audit",
+            "Transaction {} of user {} edited past sanity limits: {}",
+            updated.transaction.id,
+            user_id,
+            reason
+        );
     }
 
     // Invalidate caches
-    let mut cache_clone = cache.get_ref().clone();
-    let _ = invalidate_cache_pattern(&mut cache_clone, &format!("wallet*{}*", user_id)).await;
-    let _ = invalidate_cache_pattern(&mut cache_clone, &format!("wallets:{}*", user_id)).await;
-    let _ = invalidate_cache_pattern(&mut cache_clone, &format!("transactions:{}*", user_id)).await;
-    let _ = invalidate_cache_pattern(&mut cache_clone, &format!("transaction:{}*", user_id)).await;
+    let wallet_ids = [current_tx.wallet_id, new_wallet_id];
+    wallet_members::invalidate_members(db.get_ref(), cache.get_ref(), &user_id, &wallet_ids).await;
 
-    HttpResponse::Ok().json(ApiResponse::success(updated_tx))
+    let cx = HookContext { pool: db.get_ref(), cache: cache.get_ref() };
+    hooks::transaction_updated(&cx, TransactionUpdated { before: &current_tx, after: &updated.transaction }).await;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(updated)))
 }
 
 /// Delete a transaction and reverse wallet balance
+///
+/// The transaction is soft-deleted and can be restored, unless `?hard=true`
+/// removes it. Hard deletion also purges an already soft-deleted transaction,
+/// whose balance effect is already reversed. Either way the response carries
+/// the wallet's balance afterwards.
 pub async fn delete_transaction(
-    path: web::Path<(String, String)>,
+    path: web::Path<(String, Uuid)>,
+    query: web::Query<DeleteQuery>,
+    dry_run: DryRun,
+    context: AuditContext,
     db: web::Data<PgPool>,
-    cache: web::Data<ConnectionManager>,
-) -> HttpResponse {
+    cache: web::Data<dyn CacheBackend>,
+    storage: web::Data<dyn StorageBackend>,
+) -> Result<HttpResponse, AppError> {
     let (user_id, transaction_id) = path.into_inner();
 
-    // Fetch transaction to reverse balance
-    let transaction: Option<Transaction> = match sqlx::query_as::<_, Transaction>(
-        "SELECT id, user_id, wallet_id, amount, transaction_type, category, description, created_at, updated_at FROM transactions WHERE id = $1 AND user_id = $2"
+    // Start database transaction
+    let mut db_tx = db.begin().await?;
+    audit::attach(&mut db_tx, &context).await?;
+
+    // Fetch and lock the transaction to reverse balance
+    let transaction = sqlx::query_as::<_, Transaction>(
+        "SELECT id, sequence_number, user_id, wallet_id, bucket_id, income_source_id, amount, currency, transaction_type, category, description, notes, metadata, created_at, updated_at, version FROM transactions WHERE id = $1 AND has_wallet_role(wallet_id, $2, 'viewer') AND deleted_at IS NULL FOR UPDATE"
     )
-    .bind(&transaction_id)
+    .bind(transaction_id)
     .bind(&user_id)
-    .fetch_optional(db.get_ref())
-    .await {
-        Ok(tx) => tx,
-        Err(e) => {
-            log::error!("Error fetching transaction: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<String>::error("Database error".to_string()));
-        }
-    };
+    .fetch_optional(&mut *db_tx)
+    .await?;
 
-    let transaction = match transaction {
-        Some(tx) => tx,
-        None => {
-            return HttpResponse::NotFound()
-                .json(ApiResponse::<String>::error("Transaction not found".to_string()));
+    let Some(transaction) = transaction else {
+        if !query.hard {
+            return Err(AppError::NotFound("Transaction not found".to_string()));
         }
-    };
-
-    // Start database transaction
-    let mut db_tx = match db.begin().await {
-        Ok(t) => t,
-        Err(e) => {
-            log::error!("Failed to begin transaction: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<String>::error("Database error".to_string()));
+        let purged: Option<(Uuid, String, Uuid)> = sqlx::query_as(
+            "DELETE FROM transactions
+             WHERE id = $1 AND has_wallet_role(wallet_id, $2, 'editor') AND deleted_at IS NOT NULL
+             RETURNING id, user_id, wallet_id",
+        )
+        .bind(transaction_id)
+        .bind(&user_id)
+        .fetch_optional(&mut *db_tx)
+        .await
+        .map_err(AppError::database("Failed to delete transaction"))?;
+        let (id, owner_id, wallet_id) = purged.ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))?;
+        let deleted = DeletedTransaction {
+            id,
+            wallet_balances: wallet_balances(&mut db_tx, &[wallet_id]).await?,
+        };
+        if dry_run.0 {
+            return dry_run::preview(db.get_ref(), db_tx, &user_id, StatusCode::OK, Some(deleted), &[]).await;
         }
+        db_tx.commit().await.map_err(AppError::database("Failed to save changes"))?;
+        attachments::delete_all(db.get_ref(), storage.get_ref(), &owner_id, TRANSACTION_ENTITY, id).await;
+        wallet_members::invalidate_members(db.get_ref(), cache.get_ref(), &user_id, &[wallet_id]).await;
+        return Ok(HttpResponse::Ok().json(ApiResponse::success(deleted)));
     };
+    wallet_members::authorize_wallet_access(&mut *db_tx, &user_id, &transaction.wallet_id.to_string(), Permission::Edit)
+        .await?;
 
-    // Reverse wallet balance (wallet_id is now required, not Option)
-    let delta = match transaction.transaction_type.as_str() {
-        "income" => -transaction.amount.clone(),
-        "expense" => transaction.amount.clone(),
-        _ => {
-            let _ = db_tx.rollback().await;
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<String>::error("Invalid transaction type".to_string()));
-        }
-    };
+    // Reverse wallet balance (wallet_id is now required, not Option); taking
+    // back income must leave the wallet funded
+    let delta = -balance_delta(&transaction.transaction_type, &transaction.amount)?;
+    lock_and_check_funds(&mut db_tx, &[(transaction.wallet_id, delta.clone())]).await?;
 
-    let reverse_result = sqlx::query(
+    sqlx::query(
         "UPDATE wallets SET balance = balance + $1, updated_at = CURRENT_TIMESTAMP 
          WHERE id = $2"
     )
     .bind(delta)
-    .bind(&transaction.wallet_id)
+    .bind(transaction.wallet_id)
     .execute(&mut *db_tx)
-    .await;
+    .await?;
+
+    freezes::ensure_not_frozen(&mut db_tx, transaction.wallet_id).await?;
 
-    if let Err(e) = reverse_result {
-        log::error!("Error reversing wallet balance: {}", e);
-        let _ = db_tx.rollback().await;
-        return HttpResponse::InternalServerError()
-            .json(ApiResponse::<String>::error("Database error".to_string()));
+    // Put a bucketed expense back into its bucket; removing income must leave buckets covered
+    if let Some(bucket_id) = transaction.bucket_id {
+        buckets::refund(&mut db_tx, bucket_id, &transaction.amount).await?;
     }
+    buckets::ensure_covered(&mut db_tx, transaction.wallet_id).await?;
 
-    // Delete transaction
-    let delete_result = sqlx::query("DELETE FROM transactions WHERE id = $1 AND user_id = $2")
-        .bind(&transaction_id)
-        .bind(&user_id)
+    // Delete transaction, or hide it until restored
+    let sql = if query.hard {
+        "DELETE FROM transactions WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"
+    } else {
+        "UPDATE transactions SET deleted_at = CURRENT_TIMESTAMP WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"
+    };
+    let result = sqlx::query(sql)
+        .bind(transaction_id)
+        .bind(&transaction.user_id)
         .execute(&mut *db_tx)
-        .await;
+        .await
+        .map_err(AppError::database("Failed to delete transaction"))?;
 
-    match delete_result {
-        Ok(result) => {
-            if result.rows_affected() > 0 {
-                if let Err(e) = db_tx.commit().await {
-                    log::error!("Failed to commit transaction: {}", e);
-                    return HttpResponse::InternalServerError()
-                        .json(ApiResponse::<String>::error("Failed to save changes".to_string()));
-                }
-
-                // Invalidate caches
-                let mut cache_clone = cache.get_ref().clone();
-                let _ = invalidate_cache_pattern(&mut cache_clone, &format!("wallet*{}*", user_id)).await;
-                let _ = invalidate_cache_pattern(&mut cache_clone, &format!("wallets:{}*", user_id)).await;
-                let _ = invalidate_cache_pattern(&mut cache_clone, &format!("transactions:{}*", user_id)).await;
-                let _ = invalidate_cache_pattern(&mut cache_clone, &format!("transaction:{}*", user_id)).await;
-
-                HttpResponse::NoContent().finish()
-            } else {
-                let _ = db_tx.rollback().await;
-                HttpResponse::NotFound()
-                    .json(ApiResponse::<String>::error("Transaction not found".to_string()))
-            }
-        }
-        Err(e) => {
-            let _ = db_tx.rollback().await;
-            log::error!("Error deleting transaction: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<String>::error("Failed to delete transaction".to_string()))
-        }
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Transaction not found".to_string()));
+    }
+    let deleted = DeletedTransaction {
+        id: transaction.id,
+        wallet_balances: wallet_balances(&mut db_tx, &[transaction.wallet_id]).await?,
+    };
+    if dry_run.0 {
+        let wallet_ids = [transaction.wallet_id];
+        return dry_run::preview(db.get_ref(), db_tx, &user_id, StatusCode::OK, Some(deleted), &wallet_ids).await;
+    }
+    db_tx.commit().await.map_err(AppError::database("Failed to save changes"))?;
+    if query.hard {
+        attachments::delete_all(db.get_ref(), storage.get_ref(), &transaction.user_id, TRANSACTION_ENTITY, transaction.id)
+            .await;
     }
+
+    // Invalidate caches
+    wallet_members::invalidate_members(db.get_ref(), cache.get_ref(), &user_id, &[transaction.wallet_id]).await;
+
+    let cx = HookContext { pool: db.get_ref(), cache: cache.get_ref() };
+    hooks::transaction_deleted(&cx, TransactionDeleted { transaction: &transaction }).await;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(deleted)))
+}
+
+/// Restore a soft-deleted transaction and re-apply it to its wallet
+///
+/// The wallet must still be live and, for an expense, able to fund it again,
+/// as when it was created. A transaction deleted along with its wallet comes
+/// back by restoring the wallet.
+pub async fn restore_transaction(
+    path: web::Path<(String, Uuid)>,
+    context: AuditContext,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, transaction_id) = path.into_inner();
+
+    let mut db_tx = db.begin().await?;
+    audit::attach(&mut db_tx, &context).await?;
+
+    let transaction = sqlx::query_as::<_, Transaction>(
+        "SELECT id, sequence_number, user_id, wallet_id, bucket_id, income_source_id, amount, currency, transaction_type, category, description, notes, metadata, created_at, updated_at, version FROM transactions WHERE id = $1 AND has_wallet_role(wallet_id, $2, 'viewer') AND deleted_at IS NOT NULL FOR UPDATE"
+    )
+    .bind(transaction_id)
+    .bind(&user_id)
+    .fetch_optional(&mut *db_tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Deleted transaction not found".to_string()))?;
+    wallet_members::authorize_wallet_access(&mut *db_tx, &user_id, &transaction.wallet_id.to_string(), Permission::Edit)
+        .await?;
+
+    let wallet = sqlx::query_as::<_, Wallet>(
+        "SELECT id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, created_at, updated_at, version FROM wallets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE"
+    )
+    .bind(transaction.wallet_id)
+    .bind(&transaction.user_id)
+    .fetch_optional(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to validate wallet"))?
+    .ok_or_else(|| AppError::Conflict("The transaction's wallet is deleted; restore the wallet instead".to_string()))?;
+
+    if transaction.transaction_type == "expense" {
+        let template = wallet_types::template_for(&mut *db_tx, &wallet)
+            .await
+            .map_err(AppError::database("Failed to validate wallet"))?;
+        check_expense_funds(&template, &wallet, &transaction.amount)?;
+    }
+
+    sqlx::query("UPDATE wallets SET balance = balance + $1, updated_at = CURRENT_TIMESTAMP WHERE id = $2")
+        .bind(balance_delta(&transaction.transaction_type, &transaction.amount)?)
+        .bind(wallet.id)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(AppError::database("Failed to update wallet balance"))?;
+
+    freezes::ensure_not_frozen(&mut db_tx, wallet.id).await?;
+
+    if let Some(bucket_id) = transaction.bucket_id {
+        buckets::draw(&mut db_tx, wallet.id, bucket_id, &transaction.amount).await?;
+    }
+    buckets::ensure_covered(&mut db_tx, wallet.id).await?;
+
+    let restored = sqlx::query_as::<_, Transaction>(
+        "UPDATE transactions SET deleted_at = NULL WHERE id = $1
+         RETURNING id, sequence_number, user_id, wallet_id, bucket_id, income_source_id, amount, currency, transaction_type, category, description, notes, metadata, created_at, updated_at, version"
+    )
+    .bind(transaction.id)
+    .fetch_one(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to restore transaction"))?;
+
+    db_tx.commit().await.map_err(AppError::database("Failed to save changes"))?;
+
+    // Invalidate caches
+    wallet_members::invalidate_members(db.get_ref(), cache.get_ref(), &user_id, &[wallet.id]).await;
+
+    let cx = HookContext { pool: db.get_ref(), cache: cache.get_ref() };
+    hooks::transaction_restored(&cx, TransactionRestored { transaction: &restored }).await;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(restored)))
 }
 
 // ==================== ATOMIC TRANSACTION EXAMPLE ====================
@@ -553,7 +867,7 @@ pub async fn delete_transaction(
 // pub async fn create_transaction_atomic_example(
 //     req: web::Json<CreateTransactionRequest>,
 //     db: web::Data<PgPool>,
-//     cache: web::Data<ConnectionManager>,
+//     cache: web::Data<dyn CacheBackend>,
 // ) -> HttpResponse {
 //     let transaction_id = Uuid::new_v4().to_string();
 //     let now = Utc::now();
@@ -566,7 +880,7 @@ pub async fn delete_transaction(
 //
 //     // STEP 1: Fetch wallet to validate balance
 //     let wallet: Option<Wallet> = match sqlx::query_as::<_, Wallet>(
-//         "SELECT id, user_id, name, balance, credit_limit, wallet_type, created_at, updated_at 
+//         "SELECT id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, created_at, updated_at 
 //          FROM wallets WHERE id = $1 AND user_id = $2"
 //     )
 //     .bind(&req.wallet_id)
@@ -671,9 +985,8 @@ pub async fn delete_transaction(
 //     }
 //
 //     // STEP 6: INVALIDATE CACHE
-//     // Clear cached wallet and transaction data for this user
-//     let _ = invalidate_cache_pattern(&cache.get_ref(), &format!("wallet*{}*", req.user_id)).await;
-//     let _ = invalidate_cache_pattern(&cache.get_ref(), &format!("transactions:{}*", req.user_id)).await;
+//     // Bump the user's cache namespace version (wallets + transactions)
+//     let _ = invalidate_user_cache(cache.get_ref(), &req.user_id).await;
 //
 //     HttpResponse::Created().json(ApiResponse::success(transaction))
 // }
@@ -690,43 +1003,683 @@ pub async fn delete_transaction(
 //
 // ============================================================================
 
+// ==================== Attachment Handlers ====================
+
+/// Upload receipts or invoices for a transaction (multipart/form-data)
+pub async fn upload_transaction_attachment(
+    path: web::Path<(String, Uuid)>,
+    payload: Multipart,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    storage: web::Data<dyn StorageBackend>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, transaction_id) = path.into_inner();
+    let transaction = authorized_transaction(db.get_ref(), transaction_id, &user_id, Permission::Edit).await?;
+
+    // Attachments belong to the transaction's owner like the transaction
+    let response = attachments::upload(
+        db.get_ref(),
+        storage.get_ref(),
+        config.attachments_max_bytes,
+        &transaction.user_id,
+        TRANSACTION_ENTITY,
+        transaction.id,
+        payload,
+    )
+    .await;
+
+    wallet_members::invalidate_members(db.get_ref(), cache.get_ref(), &user_id, &[transaction.wallet_id]).await;
+    Ok(response)
+}
+
+/// List documents attached to a transaction
+pub async fn list_transaction_attachments(
+    path: web::Path<(String, Uuid)>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, transaction_id) = path.into_inner();
+    let transaction = authorized_transaction(db.get_ref(), transaction_id, &user_id, Permission::View).await?;
+    Ok(attachments::list(db.get_ref(), &transaction.user_id, TRANSACTION_ENTITY, transaction.id).await)
+}
+
+/// Download a document attached to a transaction
+pub async fn download_transaction_attachment(
+    path: web::Path<(String, Uuid, Uuid)>,
+    db: web::Data<PgPool>,
+    storage: web::Data<dyn StorageBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, transaction_id, attachment_id) = path.into_inner();
+    let transaction = authorized_transaction(db.get_ref(), transaction_id, &user_id, Permission::View).await?;
+    Ok(attachments::download(
+        db.get_ref(),
+        storage.get_ref(),
+        &transaction.user_id,
+        TRANSACTION_ENTITY,
+        transaction.id,
+        attachment_id,
+    )
+    .await)
+}
+
+/// Delete a document attached to a transaction
+pub async fn delete_transaction_attachment(
+    path: web::Path<(String, Uuid, Uuid)>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+    storage: web::Data<dyn StorageBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, transaction_id, attachment_id) = path.into_inner();
+    let transaction = authorized_transaction(db.get_ref(), transaction_id, &user_id, Permission::Edit).await?;
+
+    let response = attachments::remove(
+        db.get_ref(),
+        storage.get_ref(),
+        &transaction.user_id,
+        TRANSACTION_ENTITY,
+        transaction.id,
+        attachment_id,
+    )
+    .await;
+    wallet_members::invalidate_members(db.get_ref(), cache.get_ref(), &user_id, &[transaction.wallet_id]).await;
+    Ok(response)
+}
+
+// ==================== Notes ====================
+
+/// Render a transaction's Markdown notes as sanitized HTML
+pub async fn get_transaction_notes(
+    path: web::Path<(String, Uuid)>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, transaction_id) = path.into_inner();
+
+    let transaction = authorized_transaction(db.get_ref(), transaction_id, &user_id, Permission::View).await?;
+    let html = transaction.notes.as_deref().map(markdown::render_sanitized_html);
+    Ok(HttpResponse::Ok().json(ApiResponse::success(TransactionNotes {
+        transaction_id: transaction.id,
+        markdown: transaction.notes,
+        html,
+    })))
+}
+
+/// Add `delta` to a wallet's balance, which must not be frozen and must still
+/// cover its buckets afterwards
+pub(crate) async fn move_balance(conn: &mut PgConnection, wallet_id: Uuid, delta: &BigDecimal) -> Result<(), AppError> {
+    sqlx::query("UPDATE wallets SET balance = balance + $1 WHERE id = $2")
+        .bind(delta)
+        .bind(wallet_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::database("Failed to update wallet balance"))?;
+
+    freezes::ensure_not_frozen(&mut *conn, wallet_id).await?;
+    buckets::ensure_covered(&mut *conn, wallet_id).await?;
+    Ok(())
+}
+
+/// Balances of `wallet_ids` as the caller's database transaction leaves them
+///
+/// Read after the balance moves and before the commit, so a response reports
+/// exactly what its change left. Repeated ids are reported once.
+pub(crate) async fn wallet_balances(conn: &mut PgConnection, wallet_ids: &[Uuid]) -> Result<Vec<WalletBalance>, AppError> {
+    let mut balances: Vec<WalletBalance> = Vec::with_capacity(wallet_ids.len());
+    for wallet_id in wallet_ids {
+        if balances.iter().any(|b| b.wallet_id == *wallet_id) {
+            continue;
+        }
+        let wallet = sqlx::query_as::<_, Wallet>(
+            "SELECT id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, created_at, updated_at, version FROM wallets WHERE id = $1"
+        )
+        .bind(wallet_id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(AppError::database("Failed to read wallet balance"))?;
+        let template = wallet_types::template_for(&mut *conn, &wallet)
+            .await
+            .map_err(AppError::database("Failed to read wallet balance"))?;
+        let available_credit = match &wallet.credit_limit {
+            Some(limit) if template.supports_credit_limit => Some(limit - &wallet.balance),
+            _ => None,
+        };
+        balances.push(WalletBalance {
+            wallet_id: wallet.id,
+            balance: wallet.balance,
+            available_credit,
+        });
+    }
+    Ok(balances)
+}
+
+/// Lock the wallets a change moves and check that each can fund what the
+/// change takes out of it
+///
+/// `moves` are (wallet, signed balance change) pairs, netted per wallet. The
+/// wallets are locked in id order, so concurrent changes to the same wallets
+/// queue instead of deadlocking, and checked before the balances move: no
+/// other request can spend the same funds until the caller commits.
+async fn lock_and_check_funds(conn: &mut PgConnection, moves: &[(Uuid, BigDecimal)]) -> Result<(), AppError> {
+    let mut net: BTreeMap<Uuid, BigDecimal> = BTreeMap::new();
+    for (wallet_id, delta) in moves {
+        *net.entry(*wallet_id).or_insert_with(|| BigDecimal::from(0)) += delta;
+    }
+    let wallet_ids: Vec<Uuid> = net.keys().copied().collect();
+    let wallets = sqlx::query_as::<_, Wallet>(
+        "SELECT id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, created_at, updated_at, version FROM wallets WHERE id = ANY($1) ORDER BY id FOR UPDATE"
+    )
+    .bind(&wallet_ids)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(AppError::database("Failed to validate wallet"))?;
+
+    for wallet in &wallets {
+        let delta = &net[&wallet.id];
+        if *delta < BigDecimal::from(0) {
+            let template = wallet_types::template_for(&mut *conn, wallet)
+                .await
+                .map_err(AppError::database("Failed to validate wallet"))?;
+            check_expense_funds(&template, wallet, &-delta)?;
+        }
+    }
+    Ok(())
+}
+
+/// Signed change to the wallet balance of a transaction of `transaction_type`
+pub(crate) fn balance_delta(transaction_type: &str, amount: &BigDecimal) -> Result<BigDecimal, AppError> {
+    match transaction_type {
+        "income" => Ok(amount.clone()),
+        "expense" => Ok(-amount.clone()),
+        _ => Err(AppError::Internal("Invalid transaction type".to_string())),
+    }
+}
+
+/// Check that `wallet` can fund an expense of `amount` under its template
+///
+/// Credit-limited wallets spend against `credit_limit - balance`, other
+/// liabilities are uncapped, and assets can't go below zero.
+fn check_expense_funds(template: &WalletTemplate, wallet: &Wallet, amount: &BigDecimal) -> Result<(), AppError> {
+    if template.supports_credit_limit {
+        let Some(limit) = &wallet.credit_limit else {
+            return Err(AppError::Internal("Wallet missing credit limit".to_string()));
+        };
+        let available = limit - &wallet.balance;
+        if *amount > available {
+            return Err(AppError::Validation(format!(
+                "Insufficient credit. Available: {}, Required: {}",
+                available, amount
+            )));
+        }
+    } else if !template.is_liability() && *amount > wallet.balance {
+        return Err(AppError::Validation(format!(
+            "Insufficient balance. Available: {}, Required: {}",
+            wallet.balance, amount
+        )));
+    }
+    Ok(())
+}
+
+/// An income transaction may only link to an active source of its owner
+async fn check_income_source(pool: &PgPool, user_id: &str, source_id: Uuid) -> Result<(), AppError> {
+    let assignable = income_sources::is_assignable(pool, user_id, source_id)
+        .await
+        .map_err(AppError::database("Failed to validate income source"))?;
+    if !assignable {
+        return Err(AppError::Validation("Income source not found or inactive".to_string()));
+    }
+    Ok(())
+}
+
+// ==================== Validation ====================
+
+impl Validate for CreateTransactionRequest {
+    fn validate(&self, fields: &mut Fields) {
+        fields.one_of("transaction_type", Some(&self.transaction_type), &["income", "expense"]);
+        // Zero and the maximum are up to the transaction policy
+        fields.not_negative("amount", Some(&self.amount));
+        fields.length("category", &self.category, 1, MAX_CATEGORY_CHARS);
+        fields.length("description", &self.description, 0, MAX_DESCRIPTION_CHARS);
+        if let Err(msg) = validate_notes(self.notes.as_deref()) {
+            fields.invalid("notes", msg);
+        }
+        fields.require(
+            "bucket_id",
+            self.bucket_id.is_none() || self.transaction_type == "expense",
+            "Only expenses can draw from a bucket",
+        );
+        fields.require(
+            "income_source_id",
+            self.income_source_id.is_none() || self.transaction_type == "income",
+            "Only income can have an income source",
+        );
+    }
+}
+
+impl Validate for UpdateTransactionRequest {
+    fn validate(&self, fields: &mut Fields) {
+        fields.not_negative("amount", self.amount.as_ref());
+        fields.optional_length("category", self.category.as_deref(), 1, MAX_CATEGORY_CHARS);
+        fields.optional_length("description", self.description.as_deref(), 0, MAX_DESCRIPTION_CHARS);
+        if let Err(msg) = validate_notes(self.notes.as_deref()) {
+            fields.invalid("notes", msg);
+        }
+    }
+}
+
+/// Notes are limited to 64KB of UTF-8 (mirrors the DB CHECK constraint)
+pub(crate) fn validate_notes(notes: Option<&str>) -> Result<(), String> {
+    match notes {
+        Some(n) if n.len() > MAX_NOTES_BYTES => {
+            Err(format!("Notes exceed maximum size of {} bytes", MAX_NOTES_BYTES))
+        }
+        _ => Ok(()),
+    }
+}
+
 // ==================== Database Functions ====================
 
 async fn fetch_transactions_from_db(
     pool: &PgPool,
     user_id: &str,
-) -> Result<Vec<Transaction>, sqlx::Error> {
-    sqlx::query_as::<_, Transaction>(
-        "SELECT id, user_id, wallet_id, amount, transaction_type, category, description, created_at, updated_at FROM transactions WHERE user_id = $1 ORDER BY created_at DESC"
+    filter: TransactionFilter,
+    sort: TransactionSort,
+    cursor: Option<Cursor>,
+    page: PageParams,
+) -> Result<TransactionPage, sqlx::Error> {
+    let (total_count,): (i64,) = telemetry::sql_one(
+        "transactions",
+        "count",
+        filter
+            .clone()
+            .apply_shared(user_id, FilterQuery::new("SELECT COUNT(*) FROM transactions"))
+            .fetch_one(pool),
     )
-        .bind(user_id)
-        .fetch_all(pool)
+    .await?;
+
+    let mut query = filter.apply_shared(
+        user_id,
+        FilterQuery::new(
+            "SELECT id, sequence_number, user_id, wallet_id, bucket_id, income_source_id, amount, currency, transaction_type, category, description, notes, metadata, created_at, updated_at, version FROM transactions",
+        ),
+    );
+    if let Some(cursor) = cursor {
+        query = cursor.apply(query);
+    }
+    // One row past the page tells whether there is a next one
+    let mut transactions: Vec<Transaction> = telemetry::sql(
+        "transactions",
+        "select",
+        query
+            .order_by(sort.order_by())
+            .limit_offset(page.limit() + 1, page.offset())
+            .fetch_all(pool),
+    )
+    .await?;
+
+    let next_cursor = if transactions.len() as i64 > page.limit() {
+        transactions.truncate(page.limit() as usize);
+        transactions.last().map(|t| Cursor::after(sort, t).encode())
+    } else {
+        None
+    };
+    Ok(TransactionPage {
+        transactions,
+        total_count,
+        next_cursor,
+    })
+}
+
+/// A live transaction `user_id` may `permission` through its wallet, for the
+/// endpoints hanging off a transaction (notes, attachments)
+async fn authorized_transaction(
+    pool: &PgPool,
+    transaction_id: Uuid,
+    user_id: &str,
+    permission: Permission,
+) -> Result<Transaction, AppError> {
+    let transaction = telemetry::sql_one(
+        "transactions",
+        "select",
+        sqlx::query_as::<_, Transaction>(
+            "SELECT id, sequence_number, user_id, wallet_id, bucket_id, income_source_id, amount, currency, transaction_type, category, description, notes, metadata, created_at, updated_at, version FROM transactions WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(transaction_id)
+        .fetch_one(pool),
+    )
+    .await
+    .map_err(AppError::or_not_found("Transaction not found"))?;
+
+    wallet_members::authorize_wallet_access(pool, user_id, &transaction.wallet_id.to_string(), permission)
         .await
+        .map_err(|e| match e {
+            AppError::NotFound(_) => AppError::NotFound("Transaction not found".to_string()),
+            e => e,
+        })?;
+    Ok(transaction)
 }
 
+/// A live transaction of a wallet the user owns or shares
 async fn fetch_transaction_by_id(
     pool: &PgPool,
-    transaction_id: &str,
+    transaction_id: Uuid,
     user_id: &str,
 ) -> Result<Transaction, sqlx::Error> {
-    sqlx::query_as::<_, Transaction>(
-        "SELECT id, user_id, wallet_id, amount, transaction_type, category, description, created_at, updated_at FROM transactions WHERE id = $1 AND user_id = $2"
-    )
+    telemetry::sql_one(
+        "transactions",
+        "select",
+        sqlx::query_as::<_, Transaction>(
+            "SELECT id, sequence_number, user_id, wallet_id, bucket_id, income_source_id, amount, currency, transaction_type, category, description, notes, metadata, created_at, updated_at, version FROM transactions WHERE id = $1 AND has_wallet_role(wallet_id, $2, 'viewer') AND deleted_at IS NULL",
+        )
         .bind(transaction_id)
         .bind(user_id)
-        .fetch_one(pool)
-        .await
+        .fetch_one(pool),
+    )
+    .await
 }
 
 // ==================== Route Configuration ====================
 
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/transactions")
+        .user(Method::GET, "/user/{user_id}", get_user_transactions)
+        .user(Method::GET, "/user/{user_id}/export", data_export::export_transactions)
+        .extended()
+        .user(Method::GET, "/user/{user_id}/search", transaction_search::search_transactions)
+        .user(Method::GET, "/{user_id}/{transaction_id}", get_transaction)
+        .create("", create_transaction)
+        .dry_run()
+        .create("/from-template/{template_id}", templates::create_from_template)
+        .create("/batch-validate", batch_entry::validate_batch)
+        .create("/bulk", bulk_transactions::create_transactions_bulk)
+        .extended()
+        .dry_run()
+        .create("/bulk-delete", bulk_transactions::delete_transactions_bulk)
+        .extended()
+        .dry_run()
+        .create("/bulk-update", bulk_updates::apply_bulk_update)
+        .extended()
+        .create("/bulk-update/preview", bulk_updates::preview_bulk_update)
+        .user(Method::POST, "/{user_id}/import", statement_imports::import_statement)
+        .extended()
+        .user(Method::PUT, "/{user_id}/{transaction_id}", update_transaction)
+        .dry_run()
+        .user(Method::DELETE, "/{user_id}/{transaction_id}", delete_transaction)
+        .dry_run()
+        .user(Method::POST, "/{user_id}/{transaction_id}/restore", restore_transaction)
+        .user(Method::GET, "/{user_id}/{transaction_id}/notes", get_transaction_notes)
+        .user(Method::GET, "/{user_id}/{transaction_id}/splits", transaction_splits::get_transaction_splits)
+        .user(Method::GET, "/{user_id}/{transaction_id}/attachments", list_transaction_attachments)
+        .user(Method::POST, "/{user_id}/{transaction_id}/attachments", upload_transaction_attachment)
+        .extended()
+        .user(Method::GET, "/{user_id}/{transaction_id}/attachments/{attachment_id}", download_transaction_attachment)
+        .extended()
+        .user(Method::DELETE, "/{user_id}/{transaction_id}/attachments/{attachment_id}", delete_transaction_attachment)
+}
+
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(
-        web::scope("/api/transactions")
-            .route("/user/{user_id}", web::get().to(get_user_transactions))
-            .route("/{user_id}/{transaction_id}", web::get().to(get_transaction))
-            .route("", web::post().to(create_transaction))
-            .route("/{user_id}/{transaction_id}", web::put().to(update_transaction))
-            .route("/{user_id}/{transaction_id}", web::delete().to(delete_transaction)),
-    );
+    routes().register(cfg);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::attachments::LocalStorage;
+    use crate::cache::MemoryCache;
+    use crate::test_support;
+
+    fn policy() -> TransactionPolicy {
+        TransactionPolicy {
+            allow_zero_amount: false,
+            max_amount: None,
+            description_required_above: None,
+            outlier_multiplier: None,
+            max_net_worth_change_percent: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn request(user_id: &str, wallet_id: Uuid, transaction_type: &str, amount: &str) -> CreateTransactionRequest {
+        CreateTransactionRequest {
+            user_id: user_id.to_string(),
+            wallet_id,
+            amount: BigDecimal::from_str(amount).unwrap(),
+            transaction_type: transaction_type.to_string(),
+            category: "test".to_string(),
+            description: format!("Test {}", transaction_type),
+            notes: None,
+            metadata: None,
+            bucket_id: None,
+            income_source_id: None,
+            splits: Vec::new(),
+            confirm: true,
+        }
+    }
+
+    async fn record(pool: &PgPool, req: &CreateTransactionRequest) -> Transaction {
+        let mut db_tx = pool.begin().await.unwrap();
+        let created = record_transaction(&mut db_tx, pool, &policy(), req).await.expect("record transaction");
+        db_tx.commit().await.unwrap();
+        created.transaction
+    }
+
+    async fn balance_of(pool: &PgPool, wallet_id: Uuid) -> BigDecimal {
+        let (balance,): (BigDecimal,) = sqlx::query_as("SELECT balance FROM wallets WHERE id = $1")
+            .bind(wallet_id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        balance
+    }
+
+    fn memory_cache() -> web::Data<dyn CacheBackend> {
+        web::Data::from(Arc::new(MemoryCache::new()) as Arc<dyn CacheBackend>)
+    }
+
+    fn temp_storage() -> web::Data<dyn StorageBackend> {
+        web::Data::from(Arc::new(LocalStorage::new(std::env::temp_dir())) as Arc<dyn StorageBackend>)
+    }
+
+    fn amount_update(amount: &str) -> UpdateTransactionRequest {
+        UpdateTransactionRequest {
+            wallet_id: None,
+            amount: Some(BigDecimal::from_str(amount).unwrap()),
+            category: None,
+            description: None,
+            notes: None,
+            metadata: None,
+            income_source_id: None,
+            splits: None,
+            confirm: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_expenses_never_overdraw_an_asset_wallet() {
+        let Some(pool) = test_support::database("concurrent_expenses_never_overdraw_an_asset_wallet").await else {
+            return;
+        };
+        let user_id = format!("test-overdraft-{}", Uuid::new_v4());
+        let wallet_id = test_support::create_wallet(&pool, &user_id, "100.00").await;
+
+        // 25 expenses of 10.00 and 5 incomes of 7.50 race for 100.00
+        let requests: Vec<CreateTransactionRequest> = (0..30)
+            .map(|i| match i % 6 {
+                5 => request(&user_id, wallet_id, "income", "7.50"),
+                _ => request(&user_id, wallet_id, "expense", "10.00"),
+            })
+            .collect();
+        let tasks: Vec<_> = requests
+            .into_iter()
+            .map(|req| {
+                let pool = pool.clone();
+                tokio::spawn(async move {
+                    let mut db_tx = pool.begin().await?;
+                    record_transaction(&mut db_tx, &pool, &policy(), &req).await?;
+                    db_tx.commit().await?;
+                    Ok::<_, AppError>(())
+                })
+            })
+            .collect();
+        for task in tasks {
+            match task.await.expect("task panicked") {
+                Ok(()) | Err(AppError::Validation(_)) => {}
+                Err(e) => panic!("unexpected error: {:?}", e),
+            }
+        }
+
+        let (balance, opening_balance): (BigDecimal, BigDecimal) =
+            sqlx::query_as("SELECT balance, opening_balance FROM wallets WHERE id = $1")
+                .bind(wallet_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let (income, expense, expenses): (BigDecimal, BigDecimal, i64) = sqlx::query_as(
+            "SELECT COALESCE(SUM(amount) FILTER (WHERE transaction_type = 'income'), 0),
+                    COALESCE(SUM(amount) FILTER (WHERE transaction_type = 'expense'), 0),
+                    COUNT(*) FILTER (WHERE transaction_type = 'expense')
+             FROM transactions WHERE wallet_id = $1 AND deleted_at IS NULL",
+        )
+        .bind(wallet_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        test_support::remove_wallet(&pool, wallet_id).await;
+
+        assert!(balance >= BigDecimal::from(0), "overdrawn: {}", balance);
+        assert_eq!(balance, &opening_balance + &income - &expense);
+        // At most (100.00 + 5 x 7.50) / 10.00 expenses fit
+        assert!(expenses <= 13, "{} expenses went through", expenses);
+        assert!(expenses >= 10, "only {} expenses went through", expenses);
+    }
+
+    #[tokio::test]
+    async fn database_refuses_a_negative_asset_balance() {
+        let Some(pool) = test_support::database("database_refuses_a_negative_asset_balance").await else { return };
+        let user_id = format!("test-overdraft-{}", Uuid::new_v4());
+        let wallet_id = test_support::create_wallet(&pool, &user_id, "5.00").await;
+
+        let mut db_tx = pool.begin().await.unwrap();
+        let result = move_balance(&mut db_tx, wallet_id, &BigDecimal::from(-6)).await;
+        drop(db_tx);
+        test_support::remove_wallet(&pool, wallet_id).await;
+
+        assert!(
+            matches!(&result, Err(AppError::Validation(msg)) if msg == "Insufficient balance"),
+            "{:?}",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn updating_an_amount_moves_the_balance_by_the_difference() {
+        let Some(pool) = test_support::database("updating_an_amount_moves_the_balance_by_the_difference").await else {
+            return;
+        };
+        let user_id = format!("test-update-{}", Uuid::new_v4());
+        let wallet_id = test_support::create_wallet(&pool, &user_id, "100.00").await;
+        let transaction = record(&pool, &request(&user_id, wallet_id, "expense", "10.00")).await;
+
+        let response = update_transaction(
+            web::Path::from((user_id.clone(), transaction.id)),
+            web::Json(amount_update("25.00")),
+            DryRun(false),
+            AuditContext::default(),
+            web::Data::new(pool.clone()),
+            memory_cache(),
+        )
+        .await;
+        let balance = balance_of(&pool, wallet_id).await;
+        test_support::remove_wallet(&pool, wallet_id).await;
+
+        assert_eq!(response.expect("update transaction").status(), StatusCode::OK);
+        assert_eq!(balance, BigDecimal::from_str("75.00").unwrap());
+    }
+
+    #[tokio::test]
+    async fn updating_another_users_transaction_is_not_found() {
+        let Some(pool) = test_support::database("updating_another_users_transaction_is_not_found").await else {
+            return;
+        };
+        let user_id = format!("test-update-{}", Uuid::new_v4());
+        let wallet_id = test_support::create_wallet(&pool, &user_id, "100.00").await;
+        let transaction = record(&pool, &request(&user_id, wallet_id, "expense", "10.00")).await;
+
+        let response = update_transaction(
+            web::Path::from((format!("test-stranger-{}", Uuid::new_v4()), transaction.id)),
+            web::Json(amount_update("25.00")),
+            DryRun(false),
+            AuditContext::default(),
+            web::Data::new(pool.clone()),
+            memory_cache(),
+        )
+        .await;
+        let balance = balance_of(&pool, wallet_id).await;
+        test_support::remove_wallet(&pool, wallet_id).await;
+
+        assert!(matches!(response, Err(AppError::NotFound(_))), "{:?}", response.map(|r| r.status()));
+        assert_eq!(balance, BigDecimal::from_str("90.00").unwrap());
+    }
+
+    #[tokio::test]
+    async fn deleting_an_expense_gives_its_amount_back() {
+        let Some(pool) = test_support::database("deleting_an_expense_gives_its_amount_back").await else { return };
+        let user_id = format!("test-delete-{}", Uuid::new_v4());
+        let wallet_id = test_support::create_wallet(&pool, &user_id, "100.00").await;
+        let transaction = record(&pool, &request(&user_id, wallet_id, "expense", "10.00")).await;
+
+        let response = delete_transaction(
+            web::Path::from((user_id.clone(), transaction.id)),
+            web::Query(DeleteQuery::default()),
+            DryRun(false),
+            AuditContext::default(),
+            web::Data::new(pool.clone()),
+            memory_cache(),
+            temp_storage(),
+        )
+        .await;
+        let balance = balance_of(&pool, wallet_id).await;
+        let (soft_deleted,): (bool,) = sqlx::query_as("SELECT deleted_at IS NOT NULL FROM transactions WHERE id = $1")
+            .bind(transaction.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        test_support::remove_wallet(&pool, wallet_id).await;
+
+        assert_eq!(response.expect("delete transaction").status(), StatusCode::OK);
+        assert_eq!(balance, BigDecimal::from_str("100.00").unwrap());
+        assert!(soft_deleted);
+    }
+
+    #[tokio::test]
+    async fn notes_come_back_as_markdown_and_sanitized_html() {
+        let Some(pool) = test_support::database("notes_come_back_as_markdown_and_sanitized_html").await else {
+            return;
+        };
+        let user_id = format!("test-notes-{}", Uuid::new_v4());
+        let wallet_id = test_support::create_wallet(&pool, &user_id, "100.00").await;
+        let notes = "Paid **cash** <script>alert(1)</script>";
+        let transaction = record(
+            &pool,
+            &CreateTransactionRequest {
+                notes: Some(notes.to_string()),
+                ..request(&user_id, wallet_id, "expense", "10.00")
+            },
+        )
+        .await;
+
+        let path = web::Path::from((user_id.clone(), transaction.id));
+        let response = get_transaction_notes(path, web::Data::new(pool.clone())).await.expect("fetch notes");
+        test_support::remove_wallet(&pool, wallet_id).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = actix_web::body::to_bytes(response.into_body()).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["data"]["transaction_id"], transaction.id.to_string());
+        assert_eq!(body["data"]["markdown"], notes);
+        let html = body["data"]["html"].as_str().expect("html");
+        assert!(html.contains("<strong>cash</strong>"), "{}", html);
+        assert!(!html.contains("<script>"), "{}", html);
+    }
 }
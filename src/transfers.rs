@@ -0,0 +1,651 @@
+use actix_web::http::{Method, StatusCode};
+use actix_web::{web, HttpResponse, ResponseError};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::audit::{self, AuditContext};
+use crate::cache::{invalidate_user_cache, CacheBackend};
+use crate::currencies;
+use crate::dry_run::{self, DryRun};
+use crate::error::AppError;
+use crate::freezes;
+use crate::hooks::{self, HookContext, WalletsDebited};
+use crate::models::{
+    AgedTransfer, ApiResponse, CreatePendingTransferRequest, PendingTransfer, PendingTransferListQuery,
+    TransferAgingBucket, TransferAgingReport, Wallet, WalletTransfer, WalletTransferRequest,
+};
+use crate::routes::ScopedRoutes;
+use crate::standing_orders;
+
+// ==================== TRANSFERS ====================
+//
+// `POST /api/wallets/transfer` moves money between two of a user's wallets at
+// once: an expense on the source and an income on the destination (category
+// "transfer") are posted and both balances moved in one database
+// transaction, so either both legs land or neither does. The source must
+// cover the amount as it would an expense, and neither wallet may be frozen.
+// Between wallets of different currencies, the destination is credited the
+// amount converted at the current rate (see currencies.rs), recorded on the
+// transfer with the rate.
+// The transfer record links the two legs; deleting either leg reverses just
+// that leg and drops the link.
+//
+// The legs are ordinary `expense`/`income` transactions rather than
+// dedicated `transfer_out`/`transfer_in` types: balance updates, replay,
+// sanity limits and every summary only know those two types, and reports
+// already leave category "transfer" out of spending and income (see
+// `TRANSFER_CATEGORY`). The `wallet_transfers` row is what marks the pair as
+// one transfer.
+//
+// Bank transfers take days to clear, so a transfer between two of a user's
+// wallets can be made in two phases:
+//
+// 1. Initiate: an expense is posted on the source wallet right away and the
+//    transfer is `in_transit`. The source must cover it as it would an
+//    expense.
+// 2. Settle: an income is posted on the destination (`settled`), converted
+//    at the rate of the day it settles, or cancel: an income refunds the
+//    source (`cancelled`).
+//
+// All legs use category "transfer", so balance replay and reports see them
+// like any other transactions; the money in transit is in neither wallet.
+// Deleting the debit transaction refunds the source like any expense
+// deletion and drops the transfer with it.
+//
+// Transfers still in transit past `expected_by` are pushed to the user's
+// devices once a day by the alert job (see alerts.rs) until they are settled
+// or cancelled. The aging report groups the unsettled ones by age.
+//
+// ============================================================================
+
+/// Default time for a transfer to clear
+const DEFAULT_EXPECTED_DAYS: i64 = 3;
+
+/// Latest `expected_by` accepted, in days from now
+const MAX_EXPECTED_DAYS: i64 = 90;
+
+/// Number of transfers returned by the list endpoint
+const LIST_LIMIT: i64 = 200;
+
+/// Aging report buckets: label, minimum and maximum whole days in transit
+const AGING_BUCKETS: [(&str, i64, Option<i64>); 4] = [
+    ("0-2 days", 0, Some(2)),
+    ("3-5 days", 3, Some(5)),
+    ("6-10 days", 6, Some(10)),
+    ("over 10 days", 11, None),
+];
+
+const TRANSFER_COLUMNS: &str = "id, user_id, from_wallet_id, to_wallet_id, amount, reference, status, expected_by, \
+     debit_transaction_id, resolution_transaction_id, resolution_amount, initiated_at, resolved_at";
+
+const WALLET_TRANSFER_COLUMNS: &str = "id, user_id, from_wallet_id, to_wallet_id, amount, to_amount, exchange_rate, \
+     description, out_transaction_id, in_transaction_id, created_at";
+
+const WALLET_COLUMNS: &str = "id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, created_at, updated_at, version";
+
+// ==================== Handlers ====================
+
+/// Move money between two wallets at once: debit the source and credit the destination
+pub async fn transfer_between_wallets(
+    req: web::Json<WalletTransferRequest>,
+    dry_run: DryRun,
+    context: AuditContext,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    if req.from_wallet_id == req.to_wallet_id {
+        return Err(AppError::Validation("Source and destination wallets must differ".to_string()));
+    }
+    if req.amount <= BigDecimal::from(0) {
+        return Err(AppError::Validation("Amount must be greater than 0".to_string()));
+    }
+    let note = req.description.trim();
+    if note.chars().count() > 200 {
+        return Err(AppError::Validation("description must be at most 200 characters".to_string()));
+    }
+
+    let mut db_tx = db.begin().await.map_err(AppError::database("Database error"))?;
+    audit::attach(&mut db_tx, &context)
+        .await
+        .map_err(AppError::database("Database error"))?;
+
+    let wallets = lock_wallets(&mut db_tx, &req.user_id, req.from_wallet_id, req.to_wallet_id)
+        .await
+        .map_err(AppError::database("Failed to fetch wallets"))?;
+    let (Some(from), Some(to)) = (
+        wallets.iter().find(|w| w.id == req.from_wallet_id),
+        wallets.iter().find(|w| w.id == req.to_wallet_id),
+    ) else {
+        let found_source = wallets.iter().any(|w| w.id == req.from_wallet_id);
+        let missing = if found_source { req.to_wallet_id } else { req.from_wallet_id };
+        return Err(AppError::Validation(format!("Wallet {} not found", missing)));
+    };
+
+    let available = standing_orders::sendable(&mut db_tx, from)
+        .await
+        .map_err(AppError::database("Failed to check available funds"))?;
+    if let Some(available) = available
+        && req.amount > available
+    {
+        return Err(AppError::Validation(format!(
+            "Insufficient balance. Available: {}, Required: {}",
+            available, req.amount
+        )));
+    }
+
+    let conversion = currencies::convert(&mut *db_tx, &req.amount, &from.currency, &to.currency)
+        .await
+        .map_err(AppError::database("Failed to convert transfer amount"))?
+        .ok_or_else(|| AppError::Conflict(currencies::no_rate(&from.currency, &to.currency)))?;
+
+    let with_note = |description: String| match note {
+        "" => description,
+        note => format!("{}: {}", description, note),
+    };
+    let now = Utc::now();
+    let out = standing_orders::post_transfer_leg(
+        &mut db_tx,
+        &req.user_id,
+        from.id,
+        "expense",
+        &req.amount,
+        &with_note(format!("Transfer to {}", to.name)),
+        now,
+    )
+    .await
+    .map_err(AppError::database("Failed to transfer"))?;
+    let incoming = standing_orders::post_transfer_leg(
+        &mut db_tx,
+        &req.user_id,
+        to.id,
+        "income",
+        &conversion.amount,
+        &with_note(format!("Transfer from {}", from.name)),
+        now,
+    )
+    .await
+    .map_err(AppError::database("Failed to transfer"))?;
+    for wallet_id in [from.id, to.id] {
+        freezes::ensure_not_frozen(&mut db_tx, wallet_id).await?;
+    }
+
+    let transfer = sqlx::query_as::<_, WalletTransfer>(&format!(
+        "INSERT INTO wallet_transfers
+             (id, user_id, from_wallet_id, to_wallet_id, amount, to_amount, exchange_rate, description,
+              out_transaction_id, in_transaction_id, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+         RETURNING {}",
+        WALLET_TRANSFER_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(from.id)
+    .bind(to.id)
+    .bind(&req.amount)
+    .bind(&conversion.amount)
+    .bind(&conversion.rate)
+    .bind(note)
+    .bind(out)
+    .bind(incoming)
+    .bind(now)
+    .fetch_one(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to transfer"))?;
+
+    if dry_run.0 {
+        let wallet_ids = [transfer.from_wallet_id, transfer.to_wallet_id];
+        return dry_run::preview(db.get_ref(), db_tx, &req.user_id, StatusCode::CREATED, Some(transfer), &wallet_ids)
+            .await;
+    }
+
+    db_tx.commit().await.map_err(AppError::database("Failed to save changes"))?;
+
+    let _ = invalidate_user_cache(cache.get_ref(), &req.user_id).await;
+    let cx = HookContext { pool: db.get_ref(), cache: cache.get_ref() };
+    let event = WalletsDebited { owner_id: &req.user_id, wallet_ids: &[transfer.from_wallet_id] };
+    hooks::wallets_debited(&cx, event).await;
+
+    Ok(HttpResponse::Created().json(ApiResponse::success(transfer)))
+}
+
+/// Initiate a transfer: debit the source now, credit the destination on settlement
+pub async fn create_transfer(
+    req: web::Json<CreatePendingTransferRequest>,
+    dry_run: DryRun,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    if req.from_wallet_id == req.to_wallet_id {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<PendingTransfer>::error("Source and destination wallets must differ".to_string()));
+    }
+    if req.amount <= BigDecimal::from(0) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<PendingTransfer>::error("Amount must be greater than 0".to_string()));
+    }
+    let reference = req.reference.trim();
+    if reference.chars().count() > 200 {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<PendingTransfer>::error("reference must be at most 200 characters".to_string()));
+    }
+    let now = Utc::now();
+    let expected_by = req.expected_by.unwrap_or(now + Duration::days(DEFAULT_EXPECTED_DAYS));
+    if expected_by <= now || expected_by > now + Duration::days(MAX_EXPECTED_DAYS) {
+        return HttpResponse::BadRequest().json(ApiResponse::<PendingTransfer>::error(format!(
+            "expected_by must be in the next {} days",
+            MAX_EXPECTED_DAYS
+        )));
+    }
+
+    let mut db_tx = match db.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            log::error!("Failed to begin database transaction: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<PendingTransfer>::error("Database error".to_string()));
+        }
+    };
+
+    let wallets = match lock_wallets(&mut db_tx, &req.user_id, req.from_wallet_id, req.to_wallet_id).await {
+        Ok(wallets) => wallets,
+        Err(e) => {
+            log::error!("Error fetching wallets: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<PendingTransfer>::error("Database error".to_string()));
+        }
+    };
+    let (Some(from), Some(to)) = (
+        wallets.iter().find(|w| w.id == req.from_wallet_id),
+        wallets.iter().find(|w| w.id == req.to_wallet_id),
+    ) else {
+        let found_source = wallets.iter().any(|w| w.id == req.from_wallet_id);
+        let missing = if found_source { req.to_wallet_id } else { req.from_wallet_id };
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<PendingTransfer>::error(format!("Wallet {} not found", missing)));
+    };
+
+    match standing_orders::sendable(&mut db_tx, from).await {
+        Ok(Some(available)) if req.amount > available => {
+            return HttpResponse::BadRequest().json(ApiResponse::<PendingTransfer>::error(format!(
+                "Insufficient balance. Available: {}, Required: {}",
+                available, req.amount
+            )));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Error checking available funds: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<PendingTransfer>::error("Database error".to_string()));
+        }
+    }
+
+    // The credit is converted at settlement; refuse a transfer that couldn't settle
+    match currencies::convert(&mut *db_tx, &req.amount, &from.currency, &to.currency).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::Conflict()
+                .json(ApiResponse::<PendingTransfer>::error(currencies::no_rate(&from.currency, &to.currency)));
+        }
+        Err(e) => {
+            log::error!("Error converting transfer amount: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<PendingTransfer>::error("Database error".to_string()));
+        }
+    }
+
+    let description = match reference {
+        "" => format!("Transfer to {}", to.name),
+        reference => format!("Transfer to {}: {}", to.name, reference),
+    };
+    let debit =
+        standing_orders::post_transfer_leg(&mut db_tx, &req.user_id, from.id, "expense", &req.amount, &description, now)
+            .await;
+    let debit = match debit {
+        Ok(id) => id,
+        Err(e) => {
+            log::error!("Error posting transfer debit: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<PendingTransfer>::error("Failed to initiate transfer".to_string()));
+        }
+    };
+    if let Err(e) = freezes::ensure_not_frozen(&mut db_tx, from.id).await {
+        return e.to_response::<PendingTransfer>();
+    }
+
+    let result = sqlx::query_as::<_, PendingTransfer>(&format!(
+        "INSERT INTO pending_transfers
+             (id, user_id, from_wallet_id, to_wallet_id, amount, reference, status, expected_by, debit_transaction_id, initiated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, 'in_transit', $7, $8, $9)
+         RETURNING {}",
+        TRANSFER_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(from.id)
+    .bind(to.id)
+    .bind(&req.amount)
+    .bind(reference)
+    .bind(expected_by)
+    .bind(debit)
+    .bind(now)
+    .fetch_one(&mut *db_tx)
+    .await;
+    let transfer = match result {
+        Ok(transfer) => transfer,
+        Err(e) => {
+            log::error!("Error creating pending transfer: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<PendingTransfer>::error("Failed to initiate transfer".to_string()));
+        }
+    };
+
+    if dry_run.0 {
+        let wallet_ids = [transfer.from_wallet_id];
+        return dry_run::preview(db.get_ref(), db_tx, &req.user_id, StatusCode::CREATED, Some(transfer), &wallet_ids)
+            .await
+            .unwrap_or_else(|e| e.error_response());
+    }
+
+    if let Err(e) = db_tx.commit().await {
+        log::error!("Failed to commit database transaction: {}", e);
+        return HttpResponse::InternalServerError()
+            .json(ApiResponse::<PendingTransfer>::error("Failed to save changes".to_string()));
+    }
+
+    let _ = invalidate_user_cache(cache.get_ref(), &req.user_id).await;
+    let cx = HookContext { pool: db.get_ref(), cache: cache.get_ref() };
+    let event = WalletsDebited { owner_id: &req.user_id, wallet_ids: &[transfer.from_wallet_id] };
+    hooks::wallets_debited(&cx, event).await;
+
+    HttpResponse::Created().json(ApiResponse::success(transfer))
+}
+
+/// A user's transfers, newest first, optionally by status
+pub async fn get_user_transfers(
+    user_id: web::Path<String>,
+    query: web::Query<PendingTransferListQuery>,
+    db: web::Data<PgPool>,
+) -> HttpResponse {
+    if let Some(status) = &query.status
+        && !["in_transit", "settled", "cancelled"].contains(&status.as_str())
+    {
+        return HttpResponse::BadRequest().json(ApiResponse::<Vec<PendingTransfer>>::error(
+            "status must be in_transit, settled or cancelled".to_string(),
+        ));
+    }
+
+    let result = sqlx::query_as::<_, PendingTransfer>(&format!(
+        "SELECT {} FROM pending_transfers
+         WHERE user_id = $1 AND ($2::text IS NULL OR status = $2)
+         ORDER BY initiated_at DESC
+         LIMIT $3",
+        TRANSFER_COLUMNS
+    ))
+    .bind(user_id.into_inner())
+    .bind(&query.status)
+    .bind(LIST_LIMIT)
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(transfers) => HttpResponse::Ok().json(ApiResponse::success(transfers)),
+        Err(e) => {
+            log::error!("Error fetching transfers: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<Vec<PendingTransfer>>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Settle a transfer: credit the destination
+pub async fn settle_transfer(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, transfer_id) = path.into_inner();
+    resolve(db.get_ref(), cache.get_ref(), &user_id, &transfer_id, Resolution::Settle).await
+}
+
+/// Cancel a transfer: refund the source
+pub async fn cancel_transfer(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, transfer_id) = path.into_inner();
+    resolve(db.get_ref(), cache.get_ref(), &user_id, &transfer_id, Resolution::Cancel).await
+}
+
+/// Transfers still in transit, by age
+pub async fn get_transfer_aging(user_id: web::Path<String>, db: web::Data<PgPool>) -> HttpResponse {
+    let result = sqlx::query_as::<_, PendingTransfer>(&format!(
+        "SELECT {} FROM pending_transfers WHERE user_id = $1 AND status = 'in_transit' ORDER BY initiated_at",
+        TRANSFER_COLUMNS
+    ))
+    .bind(user_id.into_inner())
+    .fetch_all(db.get_ref())
+    .await;
+
+    match result {
+        Ok(transfers) => HttpResponse::Ok().json(ApiResponse::success(aging_report(transfers, Utc::now()))),
+        Err(e) => {
+            log::error!("Error fetching transfers in transit: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<TransferAgingReport>::error("Database error".to_string()))
+        }
+    }
+}
+
+/// Lock a user's wallets `a` and `b` in id order, so concurrent transfers can't deadlock
+///
+/// Wallets the user doesn't own are left out.
+async fn lock_wallets(conn: &mut PgConnection, user_id: &str, a: Uuid, b: Uuid) -> Result<Vec<Wallet>, sqlx::Error> {
+    sqlx::query_as::<_, Wallet>(&format!(
+        "SELECT {} FROM wallets WHERE id = ANY($1) AND user_id = $2 AND deleted_at IS NULL ORDER BY id FOR UPDATE",
+        WALLET_COLUMNS
+    ))
+    .bind(vec![a, b])
+    .bind(user_id)
+    .fetch_all(conn)
+    .await
+}
+
+// ==================== Settlement ====================
+
+#[derive(Clone, Copy, PartialEq)]
+enum Resolution {
+    Settle,
+    Cancel,
+}
+
+/// Post the credit or refund of an in-transit transfer and close it
+async fn resolve(
+    pool: &PgPool,
+    cache: &dyn CacheBackend,
+    user_id: &str,
+    transfer_id: &str,
+    resolution: Resolution,
+) -> HttpResponse {
+    let mut db_tx = match pool.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            log::error!("Failed to begin database transaction: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<PendingTransfer>::error("Database error".to_string()));
+        }
+    };
+
+    let transfer = sqlx::query_as::<_, PendingTransfer>(&format!(
+        "SELECT {} FROM pending_transfers WHERE id::text = $1 AND user_id = $2 FOR UPDATE",
+        TRANSFER_COLUMNS
+    ))
+    .bind(transfer_id)
+    .bind(user_id)
+    .fetch_optional(&mut *db_tx)
+    .await;
+    let transfer = match transfer {
+        Ok(Some(transfer)) if transfer.status == "in_transit" => transfer,
+        Ok(Some(transfer)) => {
+            return HttpResponse::Conflict()
+                .json(ApiResponse::<PendingTransfer>::error(format!("Transfer is already {}", transfer.status)));
+        }
+        Ok(None) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<PendingTransfer>::error("Transfer not found".to_string()));
+        }
+        Err(e) => {
+            log::error!("Error fetching transfer: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<PendingTransfer>::error("Database error".to_string()));
+        }
+    };
+
+    let (wallet_id, status) = match resolution {
+        Resolution::Settle => (transfer.to_wallet_id, "settled"),
+        Resolution::Cancel => (transfer.from_wallet_id, "cancelled"),
+    };
+    let wallets: Vec<(Uuid, String, String)> = match sqlx::query_as("SELECT id, name, currency FROM wallets WHERE id = ANY($1)")
+        .bind(vec![transfer.from_wallet_id, transfer.to_wallet_id])
+        .fetch_all(&mut *db_tx)
+        .await
+    {
+        Ok(wallets) => wallets,
+        Err(e) => {
+            log::error!("Error fetching wallets: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<PendingTransfer>::error("Database error".to_string()));
+        }
+    };
+    let wallet_of = |id: Uuid| wallets.iter().find(|(wallet_id, _, _)| *wallet_id == id);
+    let name_of = |id: Uuid| wallet_of(id).map_or("", |(_, name, _)| name.as_str());
+    let currency_of = |id: Uuid| wallet_of(id).map_or("", |(_, _, currency)| currency.as_str());
+    let description = match resolution {
+        Resolution::Settle => format!("Transfer from {}", name_of(transfer.from_wallet_id)),
+        Resolution::Cancel => format!("Refund of cancelled transfer to {}", name_of(transfer.to_wallet_id)),
+    };
+
+    // A settlement credits the amount converted at today's rate; a refund returns it as debited
+    let (from_currency, to_currency) = (currency_of(transfer.from_wallet_id), currency_of(wallet_id));
+    let amount = match currencies::convert(&mut *db_tx, &transfer.amount, from_currency, to_currency).await {
+        Ok(Some(conversion)) => conversion.amount,
+        Ok(None) => {
+            return HttpResponse::Conflict()
+                .json(ApiResponse::<PendingTransfer>::error(currencies::no_rate(from_currency, to_currency)));
+        }
+        Err(e) => {
+            log::error!("Error converting transfer amount: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<PendingTransfer>::error("Database error".to_string()));
+        }
+    };
+
+    let now = Utc::now();
+    let credit =
+        standing_orders::post_transfer_leg(&mut db_tx, user_id, wallet_id, "income", &amount, &description, now).await;
+    let credit = match credit {
+        Ok(id) => id,
+        Err(e) => {
+            log::error!("Error posting transfer credit: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<PendingTransfer>::error("Failed to update transfer".to_string()));
+        }
+    };
+    if let Err(e) = freezes::ensure_not_frozen(&mut db_tx, wallet_id).await {
+        return e.to_response::<PendingTransfer>();
+    }
+
+    let result = sqlx::query_as::<_, PendingTransfer>(&format!(
+        "UPDATE pending_transfers SET status = $1, resolution_transaction_id = $2, resolution_amount = $3, resolved_at = $4
+         WHERE id = $5
+         RETURNING {}",
+        TRANSFER_COLUMNS
+    ))
+    .bind(status)
+    .bind(credit)
+    .bind(&amount)
+    .bind(now)
+    .bind(transfer.id)
+    .fetch_one(&mut *db_tx)
+    .await;
+    let transfer = match result {
+        Ok(transfer) => transfer,
+        Err(e) => {
+            log::error!("Error updating transfer: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<PendingTransfer>::error("Failed to update transfer".to_string()));
+        }
+    };
+
+    if let Err(e) = db_tx.commit().await {
+        log::error!("Failed to commit database transaction: {}", e);
+        return HttpResponse::InternalServerError()
+            .json(ApiResponse::<PendingTransfer>::error("Failed to save changes".to_string()));
+    }
+
+    let _ = invalidate_user_cache(cache, user_id).await;
+    HttpResponse::Ok().json(ApiResponse::success(transfer))
+}
+
+// ==================== Aging Report ====================
+
+fn aging_report(transfers: Vec<PendingTransfer>, now: DateTime<Utc>) -> TransferAgingReport {
+    let mut buckets: Vec<TransferAgingBucket> = AGING_BUCKETS
+        .iter()
+        .map(|(label, min_days, max_days)| TransferAgingBucket {
+            label: label.to_string(),
+            min_days: *min_days,
+            max_days: *max_days,
+            count: 0,
+            total: BigDecimal::from(0),
+        })
+        .collect();
+
+    let mut total = BigDecimal::from(0);
+    let transfers: Vec<AgedTransfer> = transfers
+        .into_iter()
+        .map(|transfer| {
+            let age_days = (now - transfer.initiated_at).num_days().max(0);
+            if let Some(bucket) = buckets
+                .iter_mut()
+                .find(|b| age_days >= b.min_days && b.max_days.is_none_or(|max| age_days <= max))
+            {
+                bucket.count += 1;
+                bucket.total += &transfer.amount;
+            }
+            total += &transfer.amount;
+            AgedTransfer {
+                overdue: transfer.expected_by < now,
+                age_days,
+                transfer,
+            }
+        })
+        .collect();
+
+    TransferAgingReport {
+        as_of: now,
+        count: transfers.len() as i64,
+        total,
+        overdue_count: transfers.iter().filter(|t| t.overdue).count() as i64,
+        buckets,
+        transfers,
+    }
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/transfers")
+        .create("", create_transfer)
+        .dry_run()
+        .user(Method::GET, "/{user_id}", get_user_transfers)
+        .user(Method::GET, "/{user_id}/aging", get_transfer_aging)
+        .user(Method::POST, "/{user_id}/{transfer_id}/settle", settle_transfer)
+        .user(Method::POST, "/{user_id}/{transfer_id}/cancel", cancel_transfer)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
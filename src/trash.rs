@@ -0,0 +1,225 @@
+use std::collections::BTreeSet;
+
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+
+use crate::attachments::{self, StorageBackend};
+use crate::auth::AuthUser;
+use crate::cache::{invalidate_user_cache, CacheBackend};
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::limits;
+use crate::models::{ApiResponse, PageQuery, TrashItem, TrashPage, TrashQuery};
+use crate::routes::ScopedRoutes;
+
+// ==================== TRASH ====================
+//
+// Soft-deleted wallets and transactions (see the soft delete migration) stay
+// restorable for a retention window per entity type, then the purge job
+// removes them for good:
+//
+// - Transactions: `TRASH_TRANSACTION_RETENTION_DAYS` (30) after deletion.
+//   Transactions deleted along with their wallet follow the wallet instead,
+//   so restoring the wallet always brings them back.
+// - Wallets: `TRASH_WALLET_RETENTION_DAYS` (90), with their transactions
+//   (by cascade).
+//
+// A window of 0 keeps that type forever. The job deletes in batches of
+// `PURGE_BATCH_SIZE` rows, one short statement each, skipping rows locked by
+// a restore in progress. Afterwards it removes the purged transactions'
+// attachments and invalidates the cache of every user it touched.
+//
+// `GET /api/trash/{user_id}` lists both types together, most recently deleted
+// first, with the date each will be purged. Items are restored through the
+// wallet and transaction restore endpoints.
+//
+// ============================================================================
+
+pub const PURGE_JOB: &str = "trash_purge";
+
+/// Rows deleted per statement
+const PURGE_BATCH_SIZE: i64 = 500;
+
+/// Batches per entity type and run, so a run ends and the next one picks up
+const PURGE_BATCHES_PER_RUN: usize = 100;
+
+const ENTITY_TYPES: [&str; 2] = ["wallet", "transaction"];
+
+/// How long each entity type stays in the trash; `None` keeps it forever
+#[derive(Debug, Clone, Copy)]
+pub struct Retention {
+    pub transaction: Option<Duration>,
+    pub wallet: Option<Duration>,
+}
+
+impl Retention {
+    pub fn from_config(config: &AppConfig) -> Self {
+        let window = |days: u32| (days > 0).then(|| Duration::days(i64::from(days)));
+        Self {
+            transaction: window(config.trash_transaction_retention_days),
+            wallet: window(config.trash_wallet_retention_days),
+        }
+    }
+
+    fn for_entity(&self, entity_type: &str) -> Option<Duration> {
+        if entity_type == "wallet" { self.wallet } else { self.transaction }
+    }
+}
+
+// ==================== Handlers ====================
+
+/// Get a page of a user's trash, most recently deleted first
+pub async fn get_user_trash(
+    user: AuthUser,
+    query: web::Query<TrashQuery>,
+    db: web::Data<PgPool>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, AppError> {
+    if let Some(entity_type) = &query.entity_type
+        && !ENTITY_TYPES.contains(&entity_type.as_str())
+    {
+        return Err(AppError::Validation("entity_type must be 'wallet' or 'transaction'".to_string()));
+    }
+    let page = limits::page_params(
+        &config,
+        &PageQuery {
+            page: query.page,
+            per_page: query.per_page,
+        },
+    )
+    .map_err(AppError::Validation)?;
+
+    let (total_count,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM ({}) trash", TRASH_ITEMS))
+        .bind(&user.user_id)
+        .bind(query.entity_type.as_deref())
+        .fetch_one(db.get_ref())
+        .await
+        .map_err(AppError::database("Failed to fetch trash"))?;
+
+    let mut items = sqlx::query_as::<_, TrashItem>(&format!(
+        "SELECT * FROM ({}) trash ORDER BY deleted_at DESC, id LIMIT $3 OFFSET $4",
+        TRASH_ITEMS
+    ))
+    .bind(&user.user_id)
+    .bind(query.entity_type.as_deref())
+    .bind(page.limit())
+    .bind(page.offset())
+    .fetch_all(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to fetch trash"))?;
+
+    let retention = Retention::from_config(&config);
+    for item in &mut items {
+        item.purge_at = retention.for_entity(&item.entity_type).map(|window| item.deleted_at + window);
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(TrashPage { items, total_count })))
+}
+
+/// Deleted wallets, and transactions deleted on their own, of user `$1`;
+/// `$2` optionally narrows to one entity type
+const TRASH_ITEMS: &str = "
+    SELECT 'wallet' AS entity_type, w.id, w.id AS wallet_id, w.name, NULL::varchar AS category,
+           w.balance AS amount, w.currency,
+           (SELECT COUNT(*) FROM transactions t WHERE t.wallet_id = w.id AND t.deleted_at = w.deleted_at) AS transaction_count,
+           w.deleted_at
+    FROM wallets w
+    WHERE w.user_id = $1 AND w.deleted_at IS NOT NULL AND ($2::text IS NULL OR $2 = 'wallet')
+    UNION ALL
+    SELECT 'transaction', t.id, t.wallet_id, t.description, t.category, t.amount, t.currency, 0, t.deleted_at
+    FROM transactions t
+    JOIN wallets w ON w.id = t.wallet_id
+    WHERE t.user_id = $1 AND t.deleted_at IS NOT NULL AND w.deleted_at IS DISTINCT FROM t.deleted_at
+      AND ($2::text IS NULL OR $2 = 'transaction')";
+
+// ==================== Purge Job ====================
+
+/// Permanently delete trash past its retention window; returns how many
+/// wallets and transactions were removed
+pub async fn purge_expired(
+    pool: &PgPool,
+    cache: &dyn CacheBackend,
+    storage: &dyn StorageBackend,
+    retention: Retention,
+) -> Result<u64, sqlx::Error> {
+    let now = Utc::now();
+    let mut users = BTreeSet::new();
+    let mut purged = 0;
+
+    // Transactions deleted along with their wallet wait for the wallet
+    if let Some(window) = retention.transaction {
+        purged += purge_in_batches(
+            pool,
+            "DELETE FROM transactions WHERE id IN (
+                 SELECT t.id FROM transactions t
+                 WHERE t.deleted_at < $1
+                   AND NOT EXISTS (SELECT 1 FROM wallets w WHERE w.id = t.wallet_id AND w.deleted_at = t.deleted_at)
+                 LIMIT $2
+                 FOR UPDATE SKIP LOCKED
+             )
+             RETURNING user_id",
+            now - window,
+            &mut users,
+        )
+        .await?;
+    }
+    if let Some(window) = retention.wallet {
+        purged += purge_in_batches(
+            pool,
+            "DELETE FROM wallets WHERE id IN (
+                 SELECT id FROM wallets
+                 WHERE deleted_at < $1
+                 LIMIT $2
+                 FOR UPDATE SKIP LOCKED
+             )
+             RETURNING user_id",
+            now - window,
+            &mut users,
+        )
+        .await?;
+    }
+
+    // Receipts of the purged transactions, then everything cached about them
+    for user_id in &users {
+        attachments::delete_orphaned(pool, storage, user_id).await;
+        let _ = invalidate_user_cache(cache, user_id).await;
+    }
+    Ok(purged)
+}
+
+/// Run `delete` (binding the cutoff and batch size, returning `user_id`)
+/// until a batch comes back short or the run's batches are used up
+async fn purge_in_batches(
+    pool: &PgPool,
+    delete: &'static str,
+    cutoff: DateTime<Utc>,
+    users: &mut BTreeSet<String>,
+) -> Result<u64, sqlx::Error> {
+    let mut purged = 0;
+    for _ in 0..PURGE_BATCHES_PER_RUN {
+        let rows: Vec<(String,)> = sqlx::query_as(delete)
+            .bind(cutoff)
+            .bind(PURGE_BATCH_SIZE)
+            .fetch_all(pool)
+            .await?;
+        purged += rows.len() as u64;
+        let done = (rows.len() as i64) < PURGE_BATCH_SIZE;
+        users.extend(rows.into_iter().map(|(user_id,)| user_id));
+        if done {
+            break;
+        }
+    }
+    Ok(purged)
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/trash").user(Method::GET, "/{user_id}", get_user_trash)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
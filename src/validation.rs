@@ -0,0 +1,107 @@
+use sqlx::types::BigDecimal;
+
+use crate::error::AppError;
+use crate::models::FieldError;
+
+// ==================== REQUEST VALIDATION ====================
+//
+// Request bodies declare the rules of their fields by implementing
+// `Validate`: string lengths, the sign of amounts, the values an enum-like
+// string may take, and checks between fields that need no stored data.
+// `validation::check(&req)?` runs every rule and fails with one
+// `422 invalid_fields` naming each broken field, so a form can mark them all
+// at once:
+//
+//   {"success": false, "error": "amount: must be greater than 0",
+//    "code": "invalid_fields",
+//    "fields": [{"field": "amount", "message": "must be greater than 0"}]}
+//
+// Rules that depend on stored data (balances, the deployment's transaction
+// policy, whether a wallet or income source exists) stay in the handlers and
+// fail with `400 validation_failed`.
+//
+// ============================================================================
+
+/// A request body with declared field rules
+pub trait Validate {
+    fn validate(&self, fields: &mut Fields);
+}
+
+/// The broken rules of one request body
+#[derive(Debug, Default)]
+pub struct Fields {
+    errors: Vec<FieldError>,
+}
+
+impl Fields {
+    /// Record that `field` breaks a rule
+    pub fn invalid(&mut self, field: &str, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    /// `message` unless `ok`
+    pub fn require(&mut self, field: &str, ok: bool, message: &str) {
+        if !ok {
+            self.invalid(field, message);
+        }
+    }
+
+    /// Between `min` and `max` characters, not counting surrounding whitespace
+    pub fn length(&mut self, field: &str, value: &str, min: usize, max: usize) {
+        let count = value.trim().chars().count();
+        if count < min {
+            let message = match min {
+                1 => "can't be empty".to_string(),
+                _ => format!("must be at least {} characters", min),
+            };
+            self.invalid(field, message);
+        } else if count > max {
+            self.invalid(field, format!("must be at most {} characters", max));
+        }
+    }
+
+    /// `length`, if given
+    pub fn optional_length(&mut self, field: &str, value: Option<&str>, min: usize, max: usize) {
+        if let Some(value) = value {
+            self.length(field, value, min, max);
+        }
+    }
+
+    /// Greater than zero, if given
+    pub fn positive(&mut self, field: &str, value: Option<&BigDecimal>) {
+        if value.is_some_and(|value| *value <= BigDecimal::from(0)) {
+            self.invalid(field, "must be greater than 0");
+        }
+    }
+
+    /// Zero or more, if given
+    pub fn not_negative(&mut self, field: &str, value: Option<&BigDecimal>) {
+        if value.is_some_and(|value| *value < BigDecimal::from(0)) {
+            self.invalid(field, "can't be negative");
+        }
+    }
+
+    /// One of `allowed`, if given
+    pub fn one_of(&mut self, field: &str, value: Option<&str>, allowed: &[&str]) {
+        if let Some(value) = value
+            && !allowed.contains(&value)
+        {
+            let allowed: Vec<String> = allowed.iter().map(|a| format!("'{}'", a)).collect();
+            self.invalid(field, format!("must be one of {}", allowed.join(", ")));
+        }
+    }
+}
+
+/// Run `value`'s rules; every broken one is reported
+pub fn check<T: Validate>(value: &T) -> Result<(), AppError> {
+    let mut fields = Fields::default();
+    value.validate(&mut fields);
+    if fields.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::InvalidFields(fields.errors))
+    }
+}
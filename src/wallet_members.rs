@@ -0,0 +1,447 @@
+use std::collections::BTreeSet;
+
+use actix_web::{web, HttpResponse};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::cache::{invalidate_user_cache, CacheBackend};
+use crate::error::AppError;
+use crate::models::{AddWalletMemberRequest, ApiResponse, ReportVisibility, UpdateWalletMemberRequest, WalletMember};
+
+// ==================== SHARED WALLETS ====================
+//
+// A wallet can be shared with other users (a household, a couple's joint
+// account), each member with a role:
+//
+// - viewer: reads the wallet, its summary and its transactions
+// - editor: also records, edits, deletes and restores its transactions
+// - owner: also edits, deletes and restores the wallet and manages members
+//
+// The owner is the wallet's `user_id` and has a row in `wallet_members` like
+// everyone else, kept in step by triggers (see the migration). Members are
+// added as editors or viewers; ownership is not transferable here.
+//
+// Every endpoint under a wallet or one of its transactions authorizes with
+// `authorize_wallet_access` (or, inside a query, the `has_wallet_role` SQL
+// function) instead of comparing `user_id`, asking for the `Permission` the
+// action needs:
+//
+// - View (viewer): the wallet, its summary, transactions, splits, notes,
+//   attachments, buckets, freeze, statements, balance history, explanation
+//   and interest projection
+// - Edit (editor): creating, editing, deleting and restoring transactions,
+//   and uploading or deleting their attachments
+// - Manage (owner): the wallet itself, its members, buckets, freezes and
+//   card terms
+//
+// A transaction keeps the wallet owner's `user_id` whoever records it, so the
+// owner's categories, budgets, reports and exports include it; who made each
+// change is in the audit log. Rows hanging off a shared wallet (attachments,
+// buckets, statements) are the owner's too, so views read them with
+// `WalletAccess::owner_id`.
+//
+// Cached wallet and transaction reads live in each member's own namespace,
+// so writes invalidate every member's with `invalidate_members`.
+//
+// Reports stay private to their owner, but a report subscription or debt
+// statement of one of the owner's wallets can be shared with that wallet's
+// household or with some of its members (`check_report_sharing`); readers
+// check it with the `report_visible_to` SQL function, so a member who leaves
+// the wallet loses the report too.
+//
+// ============================================================================
+
+const MEMBER_COLUMNS: &str = "wallet_id, user_id, role, added_by, created_at, updated_at";
+
+/// What a member may do with a wallet, in increasing order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Viewer,
+    Editor,
+    Owner,
+}
+
+impl Role {
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "viewer" => Some(Role::Viewer),
+            "editor" => Some(Role::Editor),
+            "owner" => Some(Role::Owner),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Editor => "editor",
+            Role::Owner => "owner",
+        }
+    }
+
+    /// A role members can be given (the owner is the wallet's user)
+    fn assignable(role: &str) -> Result<Self, AppError> {
+        match Role::parse(role) {
+            Some(role) if role != Role::Owner => Ok(role),
+            _ => Err(AppError::Validation("role must be 'editor' or 'viewer'".to_string())),
+        }
+    }
+}
+
+/// What a caller wants to do with a wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    View,
+    Edit,
+    Manage,
+}
+
+impl Permission {
+    /// The least role allowed to do it
+    fn role(self) -> Role {
+        match self {
+            Permission::View => Role::Viewer,
+            Permission::Edit => Role::Editor,
+            Permission::Manage => Role::Owner,
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Permission::View => "view this wallet",
+            Permission::Edit => "change this wallet's transactions",
+            Permission::Manage => "manage this wallet",
+        }
+    }
+}
+
+/// A caller's access to a wallet
+#[derive(Debug, Clone)]
+pub struct WalletAccess {
+    pub wallet_id: Uuid,
+    /// The wallet's `user_id`
+    pub owner_id: String,
+}
+
+// ==================== Authorization ====================
+
+/// Check that `user_id` may do what `permission` stands for on `wallet_id`
+///
+/// 404 if the wallet doesn't exist or isn't shared with the user, 403 if
+/// their role is too low. Deleted wallets are found too; callers filter them.
+pub(crate) async fn authorize_wallet_access<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: &str,
+    wallet_id: &str,
+    permission: Permission,
+) -> Result<WalletAccess, AppError> {
+    let not_found = || AppError::NotFound("Wallet not found".to_string());
+    let wallet_id = Uuid::parse_str(wallet_id).map_err(|_| not_found())?;
+
+    let (owner_id, role): (String, String) = sqlx::query_as(
+        "SELECT w.user_id, m.role
+         FROM wallets w
+         JOIN wallet_members m ON m.wallet_id = w.id AND m.user_id = $2
+         WHERE w.id = $1",
+    )
+    .bind(wallet_id)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+    .map_err(AppError::database("Failed to check wallet access"))?
+    .ok_or_else(not_found)?;
+
+    let role = Role::parse(&role).ok_or_else(|| AppError::Internal(format!("Unknown wallet role {}", role)))?;
+    if role < permission.role() {
+        return Err(AppError::Forbidden(format!(
+            "As {} of this wallet you can't {}",
+            role.as_str(),
+            permission.describe()
+        )));
+    }
+    Ok(WalletAccess { wallet_id, owner_id })
+}
+
+/// Every member (owner included) of any of `wallet_ids`
+pub(crate) async fn members_of<'e, E: PgExecutor<'e>>(
+    executor: E,
+    wallet_ids: &[Uuid],
+) -> Result<Vec<String>, sqlx::Error> {
+    let rows: Vec<(String,)> = sqlx::query_as("SELECT DISTINCT user_id FROM wallet_members WHERE wallet_id = ANY($1)")
+        .bind(wallet_ids)
+        .fetch_all(executor)
+        .await?;
+    Ok(rows.into_iter().map(|(user_id,)| user_id).collect())
+}
+
+/// Invalidate the cache of `user_id` and of every member of `wallet_ids`
+pub(crate) async fn invalidate_members(pool: &PgPool, cache: &dyn CacheBackend, user_id: &str, wallet_ids: &[Uuid]) {
+    let mut users = members_of(pool, wallet_ids).await.unwrap_or_else(|e| {
+        log::error!("Failed to list wallet members: {}", e);
+        Vec::new()
+    });
+    users.push(user_id.to_string());
+    invalidate_users(cache, &users).await;
+}
+
+/// Invalidate the cache of each of `users` once
+pub(crate) async fn invalidate_users(cache: &dyn CacheBackend, users: &[String]) {
+    for user_id in users.iter().collect::<BTreeSet<_>>() {
+        let _ = invalidate_user_cache(cache, user_id).await;
+    }
+}
+
+// ==================== Report Sharing ====================
+
+/// Validate sharing a report of `owner_id` on `wallet_id`; returns the members
+/// to store in `shared_with` (none unless `visibility` is members)
+///
+/// A shared report must cover one wallet the owner owns, and listed members
+/// must be members of it.
+pub(crate) async fn check_report_sharing(
+    pool: &PgPool,
+    owner_id: &str,
+    wallet_id: Option<Uuid>,
+    visibility: ReportVisibility,
+    shared_with: Option<&[String]>,
+) -> Result<Vec<String>, AppError> {
+    if visibility == ReportVisibility::Private {
+        return Ok(Vec::new());
+    }
+    let wallet_id = wallet_id.ok_or_else(|| {
+        AppError::Validation("Only a report of one wallet can be shared; set its wallet_id".to_string())
+    })?;
+    let access = authorize_wallet_access(pool, owner_id, &wallet_id.to_string(), Permission::Manage).await?;
+    if visibility == ReportVisibility::Household {
+        return Ok(Vec::new());
+    }
+
+    let shared_with: Vec<String> = shared_with
+        .unwrap_or_default()
+        .iter()
+        .filter(|user_id| **user_id != access.owner_id)
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    if shared_with.is_empty() {
+        return Err(AppError::Validation("shared_with must list at least one member of the wallet".to_string()));
+    }
+    let members = members_of(pool, &[access.wallet_id])
+        .await
+        .map_err(AppError::database("Failed to check wallet members"))?;
+    if let Some(outsider) = shared_with.iter().find(|user_id| !members.contains(user_id)) {
+        return Err(AppError::Validation(format!("{} is not a member of the wallet", outsider)));
+    }
+    Ok(shared_with)
+}
+
+// ==================== Handlers ====================
+
+/// List a wallet's members, owner first
+pub async fn get_wallet_members(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, wallet_id) = path.into_inner();
+    let access = authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::View).await?;
+
+    let members = sqlx::query_as::<_, WalletMember>(&format!(
+        "SELECT {} FROM wallet_members
+         WHERE wallet_id = $1
+         ORDER BY CASE role WHEN 'owner' THEN 0 WHEN 'editor' THEN 1 ELSE 2 END, created_at, user_id",
+        MEMBER_COLUMNS
+    ))
+    .bind(access.wallet_id)
+    .fetch_all(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to fetch wallet members"))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(members)))
+}
+
+/// Share a wallet with a user (owner only)
+pub async fn add_wallet_member(
+    path: web::Path<(String, String)>,
+    req: web::Json<AddWalletMemberRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, wallet_id) = path.into_inner();
+    let role = Role::assignable(&req.role)?;
+    let access = authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::Manage).await?;
+
+    let (exists,): (bool,) = sqlx::query_as("SELECT EXISTS (SELECT 1 FROM users WHERE id = $1)")
+        .bind(&req.user_id)
+        .fetch_one(db.get_ref())
+        .await
+        .map_err(AppError::database("Failed to add wallet member"))?;
+    if !exists {
+        return Err(AppError::NotFound("User not found".to_string()));
+    }
+
+    let member = sqlx::query_as::<_, WalletMember>(&format!(
+        "INSERT INTO wallet_members (wallet_id, user_id, role, added_by)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (wallet_id, user_id) DO NOTHING
+         RETURNING {}",
+        MEMBER_COLUMNS
+    ))
+    .bind(access.wallet_id)
+    .bind(&req.user_id)
+    .bind(role.as_str())
+    .bind(&user_id)
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to add wallet member"))?
+    .ok_or_else(|| AppError::Conflict("The user is already a member of this wallet".to_string()))?;
+
+    log::info!(
+        target: "audit",
+        "wallet shared wallet_id={} user_id={} member={} role={}",
+        member.wallet_id,
+        user_id,
+        member.user_id,
+        member.role
+    );
+    invalidate_members(db.get_ref(), cache.get_ref(), &user_id, &[access.wallet_id]).await;
+
+    Ok(HttpResponse::Created().json(ApiResponse::success(member)))
+}
+
+/// Change a member's role (owner only)
+pub async fn update_wallet_member(
+    path: web::Path<(String, String, String)>,
+    req: web::Json<UpdateWalletMemberRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, wallet_id, member_id) = path.into_inner();
+    let role = Role::assignable(&req.role)?;
+    let access = authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::Manage).await?;
+    if member_id == access.owner_id {
+        return Err(AppError::Validation("The owner's role can't be changed".to_string()));
+    }
+
+    let member = sqlx::query_as::<_, WalletMember>(&format!(
+        "UPDATE wallet_members SET role = $3, updated_at = CURRENT_TIMESTAMP
+         WHERE wallet_id = $1 AND user_id = $2 AND role <> 'owner'
+         RETURNING {}",
+        MEMBER_COLUMNS
+    ))
+    .bind(access.wallet_id)
+    .bind(&member_id)
+    .bind(role.as_str())
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to update wallet member"))?
+    .ok_or_else(|| AppError::NotFound("Member not found".to_string()))?;
+
+    log::info!(
+        target: "audit",
+        "wallet member role changed wallet_id={} user_id={} member={} role={}",
+        member.wallet_id,
+        user_id,
+        member.user_id,
+        member.role
+    );
+    invalidate_members(db.get_ref(), cache.get_ref(), &user_id, &[access.wallet_id]).await;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(member)))
+}
+
+/// Remove a member (owner only), or leave a wallet shared with you
+pub async fn remove_wallet_member(
+    path: web::Path<(String, String, String)>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, wallet_id, member_id) = path.into_inner();
+    let permission = if member_id == user_id { Permission::View } else { Permission::Manage };
+    let access = authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, permission).await?;
+    if member_id == access.owner_id {
+        return Err(AppError::Validation(
+            "The owner can't be removed from their wallet; delete the wallet instead".to_string(),
+        ));
+    }
+
+    // Read the members first so the one removed loses its cached copies too
+    let members = members_of(db.get_ref(), &[access.wallet_id])
+        .await
+        .map_err(AppError::database("Failed to remove wallet member"))?;
+    let result = sqlx::query("DELETE FROM wallet_members WHERE wallet_id = $1 AND user_id = $2 AND role <> 'owner'")
+        .bind(access.wallet_id)
+        .bind(&member_id)
+        .execute(db.get_ref())
+        .await
+        .map_err(AppError::database("Failed to remove wallet member"))?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Member not found".to_string()));
+    }
+
+    log::info!(
+        target: "audit",
+        "wallet member removed wallet_id={} user_id={} member={}",
+        access.wallet_id,
+        user_id,
+        member_id
+    );
+    invalidate_users(cache.get_ref(), &members).await;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    #[test]
+    fn members_are_added_as_editors_or_viewers_only() {
+        assert_eq!(Role::assignable("editor").unwrap(), Role::Editor);
+        assert_eq!(Role::assignable("viewer").unwrap(), Role::Viewer);
+        assert!(Role::assignable("owner").is_err());
+        assert!(Role::assignable("admin").is_err());
+    }
+
+    #[test]
+    fn each_permission_needs_at_least_its_role() {
+        assert!(Role::Viewer >= Permission::View.role());
+        assert!(Role::Viewer < Permission::Edit.role());
+        assert!(Role::Editor >= Permission::Edit.role());
+        assert!(Role::Editor < Permission::Manage.role());
+        assert!(Role::Owner >= Permission::Manage.role());
+    }
+
+    #[tokio::test]
+    async fn access_follows_the_role_of_each_member() {
+        let Some(pool) = test_support::database("access_follows_the_role_of_each_member").await else { return };
+        let owner = format!("test-owner-{}", Uuid::new_v4());
+        let viewer = format!("test-viewer-{}", Uuid::new_v4());
+        let stranger = format!("test-stranger-{}", Uuid::new_v4());
+        let wallet_id = test_support::create_wallet(&pool, &owner, "10.00").await;
+        sqlx::query("INSERT INTO wallet_members (wallet_id, user_id, role, added_by) VALUES ($1, $2, 'viewer', $3)")
+            .bind(wallet_id)
+            .bind(&viewer)
+            .bind(&owner)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let id = wallet_id.to_string();
+        let owner_manages = authorize_wallet_access(&pool, &owner, &id, Permission::Manage).await;
+        let viewer_views = authorize_wallet_access(&pool, &viewer, &id, Permission::View).await;
+        let viewer_edits = authorize_wallet_access(&pool, &viewer, &id, Permission::Edit).await;
+        let stranger_views = authorize_wallet_access(&pool, &stranger, &id, Permission::View).await;
+        let bad_id = authorize_wallet_access(&pool, &owner, "not-a-uuid", Permission::View).await;
+        let _ = sqlx::query("DELETE FROM wallet_members WHERE wallet_id = $1").bind(wallet_id).execute(&pool).await;
+        test_support::remove_wallet(&pool, wallet_id).await;
+
+        assert_eq!(owner_manages.unwrap().owner_id, owner);
+        let access = viewer_views.unwrap();
+        assert_eq!((access.wallet_id, access.owner_id), (wallet_id, owner));
+        assert!(matches!(viewer_edits, Err(AppError::Forbidden(_))));
+        assert!(matches!(stranger_views, Err(AppError::NotFound(_))));
+        assert!(matches!(bad_id, Err(AppError::NotFound(_))));
+    }
+}
@@ -0,0 +1,221 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::cache::{get_or_set_cache, invalidate_user_cache, user_cache_key, CacheBackend};
+use crate::routes::ScopedRoutes;
+use crate::models::{
+    ApiResponse, CreateCustomWalletTypeRequest, CustomWalletType, Wallet, WalletBehavior, WalletTemplate, WalletType,
+};
+
+// ==================== CUSTOM WALLET TYPES ====================
+//
+// Besides the built-in types (Cash, BankAccount, CreditCard, Other), users
+// define their own ("Loan", "Gift Card", ...). Each type follows a behavior
+// template (`WalletTemplate`): asset or liability, and whether it has a
+// credit limit. Balance checks and available balance are driven by the
+// template, never by the type name:
+//
+// - Built-in types map to fixed templates (`WalletType::template`).
+// - Wallets of a custom type store `wallet_type = 'Other'` plus
+//   `custom_type_id`, whose template takes precedence.
+//
+// A type can't be deleted while wallets use it, and its template can't be
+// changed, so existing balances never switch meaning.
+//
+// ============================================================================
+
+/// Maximum number of custom wallet types per user
+const MAX_TYPES_PER_USER: i64 = 20;
+
+const WALLET_TYPE_COLUMNS: &str = "id, user_id, name, behavior, supports_credit_limit, created_at";
+
+// ==================== Handlers ====================
+
+/// List a user's custom wallet types (with caching)
+pub async fn get_user_wallet_types(
+    user_id: web::Path<String>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, "wallet_types").await;
+
+    let result = get_or_set_cache(
+        cache.get_ref(),
+        &cache_key,
+        fetch_wallet_types(db.get_ref(), &user_id),
+    )
+    .await;
+
+    match result {
+        Ok(types) => HttpResponse::Ok().json(ApiResponse::success(types)),
+        Err(e) => HttpResponse::InternalServerError()
+            .json(ApiResponse::<Vec<CustomWalletType>>::error(e.to_string())),
+    }
+}
+
+/// Define a custom wallet type
+pub async fn create_wallet_type(
+    req: web::Json<CreateCustomWalletTypeRequest>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let name = req.name.trim();
+    if name.is_empty() || name.chars().count() > 50 {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<CustomWalletType>::error("Name must be 1-50 characters".to_string()));
+    }
+    if WalletType::from_str(name).is_some() {
+        return HttpResponse::Conflict()
+            .json(ApiResponse::<CustomWalletType>::error(format!("'{}' is a built-in wallet type", name)));
+    }
+    if req.supports_credit_limit && req.behavior != WalletBehavior::Liability {
+        return HttpResponse::BadRequest().json(ApiResponse::<CustomWalletType>::error(
+            "Only liability types can have a credit limit".to_string(),
+        ));
+    }
+
+    let count: Result<(i64,), sqlx::Error> =
+        sqlx::query_as("SELECT COUNT(*) FROM custom_wallet_types WHERE user_id = $1")
+            .bind(&req.user_id)
+            .fetch_one(db.get_ref())
+            .await;
+    match count {
+        Ok((count,)) if count >= MAX_TYPES_PER_USER => {
+            return HttpResponse::BadRequest().json(ApiResponse::<CustomWalletType>::error(format!(
+                "A user can define at most {} wallet types",
+                MAX_TYPES_PER_USER
+            )));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Error counting wallet types: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<CustomWalletType>::error("Database error".to_string()));
+        }
+    }
+
+    let result = sqlx::query_as::<_, CustomWalletType>(&format!(
+        "INSERT INTO custom_wallet_types (id, user_id, name, behavior, supports_credit_limit, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (user_id, name) DO NOTHING
+         RETURNING {}",
+        WALLET_TYPE_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&req.user_id)
+    .bind(name)
+    .bind(req.behavior.as_str())
+    .bind(req.supports_credit_limit)
+    .bind(Utc::now())
+    .fetch_optional(db.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(wallet_type)) => {
+            let _ = invalidate_user_cache(cache.get_ref(), &req.user_id).await;
+            HttpResponse::Created().json(ApiResponse::success(wallet_type))
+        }
+        Ok(None) => HttpResponse::Conflict().json(ApiResponse::<CustomWalletType>::error(format!(
+            "Wallet type '{}' already exists",
+            name
+        ))),
+        Err(e) => {
+            log::error!("Error creating wallet type: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<CustomWalletType>::error("Failed to create wallet type".to_string()))
+        }
+    }
+}
+
+/// Delete a custom wallet type that no wallet uses
+pub async fn delete_wallet_type(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> HttpResponse {
+    let (user_id, type_id) = path.into_inner();
+
+    let result = sqlx::query("DELETE FROM custom_wallet_types WHERE id::text = $1 AND user_id = $2")
+        .bind(&type_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await;
+
+    match result {
+        Ok(query_result) if query_result.rows_affected() > 0 => {
+            let _ = invalidate_user_cache(cache.get_ref(), &user_id).await;
+            HttpResponse::NoContent().finish()
+        }
+        Ok(_) => HttpResponse::NotFound().json(ApiResponse::<String>::error("Wallet type not found".to_string())),
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => HttpResponse::Conflict()
+            .json(ApiResponse::<String>::error("Wallet type is used by a wallet".to_string())),
+        Err(e) => {
+            log::error!("Error deleting wallet type: {}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<String>::error("Failed to delete wallet type".to_string()))
+        }
+    }
+}
+
+// ==================== Templates ====================
+
+/// Template of a built-in type, or of `user_id`'s custom type `custom_type_id`
+///
+/// `Ok(None)` if the custom type doesn't exist for this user.
+pub async fn resolve_template(
+    executor: impl PgExecutor<'_>,
+    user_id: &str,
+    wallet_type: &WalletType,
+    custom_type_id: Option<Uuid>,
+) -> Result<Option<WalletTemplate>, sqlx::Error> {
+    let Some(custom_type_id) = custom_type_id else {
+        return Ok(Some(wallet_type.template()));
+    };
+
+    let custom = sqlx::query_as::<_, CustomWalletType>(&format!(
+        "SELECT {} FROM custom_wallet_types WHERE id = $1 AND user_id = $2",
+        WALLET_TYPE_COLUMNS
+    ))
+    .bind(custom_type_id)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(custom.map(|t| t.template()))
+}
+
+/// Template `wallet` follows
+pub async fn template_for(executor: impl PgExecutor<'_>, wallet: &Wallet) -> Result<WalletTemplate, sqlx::Error> {
+    let wallet_type = wallet.wallet_type_enum().unwrap_or(WalletType::Other);
+    let template = resolve_template(executor, &wallet.user_id, &wallet_type, wallet.custom_type_id).await?;
+    Ok(template.unwrap_or_else(|| wallet_type.template()))
+}
+
+// ==================== Database Functions ====================
+
+async fn fetch_wallet_types(pool: &PgPool, user_id: &str) -> Result<Vec<CustomWalletType>, sqlx::Error> {
+    sqlx::query_as::<_, CustomWalletType>(&format!(
+        "SELECT {} FROM custom_wallet_types WHERE user_id = $1 ORDER BY name",
+        WALLET_TYPE_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/wallet-types")
+        .create("", create_wallet_type)
+        .user(Method::GET, "/{user_id}", get_user_wallet_types)
+        .user(Method::DELETE, "/{user_id}/{type_id}", delete_wallet_type)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
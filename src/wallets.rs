@@ -1,208 +1,626 @@
-use actix_web::{web, HttpResponse};
-use redis::aio::ConnectionManager;
+use actix_web::http::{Method, StatusCode};
+use actix_web::{web, HttpRequest, HttpResponse};
 use sqlx::PgPool;
 use uuid::Uuid;
+use sqlx::types::BigDecimal;
+use chrono::{DateTime, Datelike, Months, NaiveTime, Utc};
 
-use crate::models::{ApiResponse, CreateWalletRequest, Wallet, UpdateWalletRequest};
-use crate::cache::{get_or_set_cache, invalidate_cache_pattern};
+use crate::attachments::{self, StorageBackend};
+use crate::audit::{self, AuditContext};
+use crate::auth::AuthUser;
+use crate::routes::ScopedRoutes;
+use crate::models::{
+    ApiResponse, AsOfQuery, CreateWalletRequest, DeleteQuery, FieldsQuery, Transaction, Wallet, WalletSummary, WalletListQuery, WalletTemplate, WalletType, UpdateWalletRequest,
+};
+use crate::api_keys;
+use crate::cache::{get_or_set_cache, get_or_set_cache_swr, invalidate_user_cache, user_cache_key, CacheBackend};
+use crate::balance_history;
+use crate::buckets;
+use crate::credit_cards;
+use crate::currencies;
+use crate::dry_run::{self, DryRun};
+use crate::error::AppError;
+use crate::explain;
+use crate::fieldsets;
+use crate::freezes;
+use crate::history;
+use crate::idempotency::{self, IdempotencyKey};
+use crate::interest;
+use crate::list_versions;
+use crate::money;
+use crate::opening_balances;
+use crate::summary_snapshots;
+use crate::telemetry;
+use crate::transfers;
+use crate::validation::{self, Fields, Validate};
+use crate::wallet_members::{self, Permission};
+use crate::wallet_types;
+
+/// Idempotency key scope of `create_wallet`
+const WALLETS_ROUTE: &str = "wallets";
 
 // ==================== CRUD Handlers ====================
 
-/// Get all wallets for a user (with caching)
+/// Get all wallets of a user and shared with them (with caching), or as they
+/// were at `?as_of=`
+///
+/// `?group_id=` keeps the wallets of one portfolio group; `?fields=` the named
+/// fields of each wallet.
 pub async fn get_user_wallets(
-    user_id: web::Path<String>,
+    user: AuthUser,
+    query: web::Query<WalletListQuery>,
+    fields: web::Query<FieldsQuery>,
     db: web::Data<PgPool>,
-    cache: web::Data<ConnectionManager>,
-) -> HttpResponse {
-    let user_id = user_id.into_inner();
-    let cache_key = format!("wallets:{}", user_id);
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = user.user_id;
+    let fields = fieldsets::parse(fields.fields.as_deref(), Wallet::FIELDS).map_err(AppError::Validation)?;
 
-    let result = get_or_set_cache(
-        &cache.get_ref(),
-        &cache_key,
-        fetch_wallets_from_db(db.get_ref(), &user_id),
-    )
-    .await;
+    let wallets = match query.as_of {
+        Some(as_of) => {
+            validate_as_of(as_of).map_err(AppError::Validation)?;
+            history::wallets_as_of(db.get_ref(), &user_id, None, as_of).await?
+        }
+        None => {
+            let cache_key = user_cache_key(cache.get_ref(), &user_id, "wallets").await;
+            get_or_set_cache(
+                cache.get_ref(),
+                &cache_key,
+                fetch_accessible_wallets(db.get_ref(), &user_id),
+            )
+            .await?
+        }
+    };
 
-    match result {
-        Ok(wallets) => HttpResponse::Ok().json(ApiResponse::success(wallets)),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<Vec<Wallet>>::error(e.to_string())),
+    let wallets = in_group(wallets, query.group_id);
+    let version = list_versions::list_version(&wallets, None);
+    let wallets = fieldsets::project(&wallets, fields.as_deref())?;
+    Ok(list_versions::list_response(version, wallets))
+}
+
+/// Keep the wallets of `group_id`, if given
+fn in_group(wallets: Vec<Wallet>, group_id: Option<Uuid>) -> Vec<Wallet> {
+    match group_id {
+        Some(group_id) => wallets.into_iter().filter(|w| w.group_id == Some(group_id)).collect(),
+        None => wallets,
     }
 }
 
-/// Get a single wallet by ID
+/// Get a single wallet by ID, or as it was at `?as_of=`
 pub async fn get_wallet(
     path: web::Path<(String, String)>,
+    query: web::Query<AsOfQuery>,
     db: web::Data<PgPool>,
-    cache: web::Data<ConnectionManager>,
-) -> HttpResponse {
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
     let (user_id, wallet_id) = path.into_inner();
-    let cache_key = format!("wallet:{}:{}", user_id, wallet_id);
 
-    let result = get_or_set_cache(
-        &cache.get_ref(),
+    if let Some(as_of) = query.as_of {
+        validate_as_of(as_of).map_err(AppError::Validation)?;
+        let wallet = history::wallet_as_of(db.get_ref(), &wallet_id, &user_id, as_of)
+            .await
+            .map_err(AppError::or_not_found("Wallet not found"))?;
+        return Ok(HttpResponse::Ok().json(ApiResponse::success(wallet)));
+    }
+
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, &format!("wallet:{}", wallet_id)).await;
+
+    let wallet = get_or_set_cache(
+        cache.get_ref(),
         &cache_key,
-        fetch_wallet_by_id(db.get_ref(), &wallet_id, &user_id),
+        fetch_accessible_wallet(db.get_ref(), &wallet_id, &user_id),
     )
-    .await;
+    .await
+    .map_err(AppError::or_not_found("Wallet not found"))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(wallet)))
+}
+
+/// Get month-to-date quick stats for a wallet (with caching), or as of `?as_of=`
+pub async fn get_wallet_summary(
+    path: web::Path<(String, String)>,
+    query: web::Query<AsOfQuery>,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, wallet_id) = path.into_inner();
 
-    match result {
-        Ok(wallet) => HttpResponse::Ok().json(ApiResponse::success(wallet)),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<Wallet>::error(e.to_string())),
+    if let Some(as_of) = query.as_of {
+        validate_as_of(as_of).map_err(AppError::Validation)?;
+        let summary = fetch_wallet_summary(db.get_ref(), &wallet_id, &user_id, Some(as_of))
+            .await
+            .map_err(AppError::or_not_found("Wallet not found"))?;
+        return Ok(HttpResponse::Ok().json(ApiResponse::success(summary)));
     }
+
+    let cache_key = user_cache_key(cache.get_ref(), &user_id, &format!("wallet_summary:{}", wallet_id)).await;
+
+    let summary = get_or_set_cache_swr(&cache, &cache_key, async move {
+        fetch_wallet_summary(db.get_ref(), &wallet_id, &user_id, None).await
+    })
+    .await
+    .map_err(AppError::or_not_found("Wallet not found"))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(summary)))
 }
 
 /// Create a new wallet
 pub async fn create_wallet(
+    http_req: HttpRequest,
     req: web::Json<CreateWalletRequest>,
+    key: IdempotencyKey,
+    dry_run: DryRun,
+    context: AuditContext,
     db: web::Data<PgPool>,
-    cache: web::Data<ConnectionManager>,
-) -> HttpResponse {
-    let wallet_id = Uuid::new_v4().to_string();
-    let wallet_type_str = req.wallet_type.as_str();
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let wallet_id = Uuid::new_v4();
+
+    let mut db_tx = db.begin().await?;
+    audit::attach(&mut db_tx, &context).await?;
+
+    // A retry with the same Idempotency-Key gets the first response back
+    if let Some(response) = idempotency::replay(&mut db_tx, &key, &req.user_id, WALLETS_ROUTE, &req.0).await? {
+        return Ok(response);
+    }
+    validation::check(&req.0)?;
+
+    // Custom-typed wallets are stored as 'Other' and follow their type's template
+    let wallet_type = match req.custom_type_id {
+        Some(_) => WalletType::Other,
+        None => req.wallet_type.clone().unwrap_or(WalletType::Other),
+    };
 
-    let query_result = sqlx::query_as::<_, Wallet>(
+    let template = wallet_types::resolve_template(db.get_ref(), &req.user_id, &wallet_type, req.custom_type_id)
+        .await
+        .map_err(AppError::database("Failed to create wallet"))?
+        .ok_or_else(|| AppError::Validation("Wallet type not found".to_string()))?;
+
+    if let Some(limit) = &req.credit_limit {
+        validate_credit_limit(limit, &template).map_err(AppError::Validation)?;
+    }
+
+    if req.apy.is_some() {
+        validate_apy(&wallet_type).map_err(AppError::Validation)?;
+    }
+
+    let currency = req.currency.as_deref().map(currencies::normalize_code);
+    if let Some(currency) = &currency
+        && !currencies::is_supported(db.get_ref(), currency)
+            .await
+            .map_err(AppError::database("Failed to create wallet"))?
+    {
+        return Err(AppError::Validation(format!("Unsupported currency {}", currency)));
+    }
+
+    // Interest accrues from creation, so the first posting is prorated (see interest.rs)
+    let wallet = sqlx::query_as::<_, Wallet>(
         r#"
-        INSERT INTO wallets (id, user_id, name, balance, credit_limit, wallet_type)
-        VALUES ($1, $2, $3, $4, $5, $6)
-        RETURNING id, user_id, name, balance, credit_limit, wallet_type, created_at, updated_at
+        INSERT INTO wallets (id, user_id, name, balance, opening_balance, currency, credit_limit, wallet_type, custom_type_id, apy, last_interest_posted_at, sandbox)
+        VALUES ($1, $2, $3, $4, $4, COALESCE($9, base_currency($2)), $5, $6, $7, $8, CASE WHEN $8::DECIMAL IS NULL THEN NULL ELSE CURRENT_TIMESTAMP END, $10)
+        RETURNING id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, sandbox, created_at, updated_at, version
         "#,
     )
-    .bind(&wallet_id)
+    .bind(wallet_id)
     .bind(&req.user_id)
     .bind(&req.name)
     .bind(&req.balance)
     .bind(&req.credit_limit)
-    .bind(wallet_type_str)
-    .fetch_one(db.get_ref())
-    .await;
-
-    match query_result {
-        Ok(wallet) => {
-            // Invalidate user's wallets cache
-            let mut cache_clone = cache.get_ref().clone();
-            let pattern = format!("wallets:{}", req.user_id);
-            let _ = invalidate_cache_pattern(&mut cache_clone, &pattern).await;
-
-            HttpResponse::Created().json(ApiResponse::success(wallet))
-        }
-        Err(e) => {
-            log::error!("Failed to create wallet: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<Wallet>::error("Failed to create wallet".to_string()))
-        }
+    .bind(wallet_type.as_str())
+    .bind(req.custom_type_id)
+    .bind(&req.apy)
+    .bind(&currency)
+    .bind(req.sandbox || api_keys::is_sandbox(&http_req))
+    .fetch_one(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to create wallet"))?;
+
+    if dry_run.0 {
+        let wallet_ids = [wallet.id];
+        return dry_run::preview(db.get_ref(), db_tx, &req.user_id, StatusCode::CREATED, Some(wallet), &wallet_ids)
+            .await;
     }
+
+    let response = idempotency::record(
+        &mut db_tx,
+        &key,
+        &req.user_id,
+        WALLETS_ROUTE,
+        StatusCode::CREATED,
+        &ApiResponse::success(wallet),
+    )
+    .await?;
+    db_tx.commit().await.map_err(AppError::database("Failed to create wallet"))?;
+
+    // Invalidate user's cache namespace
+    let _ = invalidate_user_cache(cache.get_ref(), &req.user_id).await;
+
+    Ok(response)
 }
 
 /// Update a wallet
 pub async fn update_wallet(
     path: web::Path<(String, String)>,
     req: web::Json<UpdateWalletRequest>,
+    dry_run: DryRun,
+    context: AuditContext,
     db: web::Data<PgPool>,
-    cache: web::Data<ConnectionManager>,
-) -> HttpResponse {
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
     let (user_id, wallet_id) = path.into_inner();
+    validation::check(&req.0)?;
+    let access = wallet_members::authorize_wallet_access(db.get_ref(), &user_id, &wallet_id, Permission::Manage).await?;
 
-    let query_result = sqlx::query_as::<_, Wallet>(
+    // APY and credit limit depend on the wallet's type
+    if req.apy.is_some() || req.credit_limit.is_some() {
+        let wallet = fetch_wallet_by_id(db.get_ref(), &wallet_id, &user_id)
+            .await
+            .map_err(AppError::or_not_found("Wallet not found"))?;
+        let wallet_type = wallet.wallet_type_enum().unwrap_or(WalletType::Other);
+        if req.apy.is_some() {
+            validate_apy(&wallet_type).map_err(AppError::Validation)?;
+        }
+
+        if let Some(limit) = &req.credit_limit {
+            let template = wallet_types::template_for(db.get_ref(), &wallet)
+                .await
+                .map_err(AppError::database("Failed to update wallet"))?;
+            validate_credit_limit(limit, &template).map_err(AppError::Validation)?;
+        }
+    }
+
+    let mut db_tx = db.begin().await.map_err(AppError::database("Failed to update wallet"))?;
+    audit::attach(&mut db_tx, &context).await?;
+
+    // A direct balance edit shifts opening_balance by the same delta so that
+    // balance = opening_balance + net transactions keeps holding for replay
+    let wallet = sqlx::query_as::<_, Wallet>(
         r#"
         UPDATE wallets
-        SET name = COALESCE($1, name), balance = COALESCE($2, balance), credit_limit = COALESCE($3, credit_limit)
-        WHERE id = $4 AND user_id = $5
-        RETURNING id, user_id, name, balance, credit_limit, wallet_type, created_at, updated_at
+        SET name = COALESCE($1, name), balance = COALESCE($2, balance), credit_limit = COALESCE($3, credit_limit),
+            opening_balance = opening_balance + (COALESCE($2, balance) - balance),
+            apy = COALESCE($4, apy),
+            last_interest_posted_at = CASE WHEN $4::DECIMAL IS NULL THEN last_interest_posted_at
+                                           ELSE COALESCE(last_interest_posted_at, CURRENT_TIMESTAMP) END
+        WHERE id = $5 AND user_id = $6 AND deleted_at IS NULL
+        RETURNING id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, sandbox, created_at, updated_at, version
         "#,
     )
     .bind(&req.name)
     .bind(&req.balance)
     .bind(&req.credit_limit)
+    .bind(&req.apy)
     .bind(&wallet_id)
     .bind(&user_id)
-    .fetch_optional(db.get_ref())
-    .await;
-
-    match query_result {
-        Ok(Some(wallet)) => {
-            // Invalidate relevant caches
-            let mut cache_clone = cache.get_ref().clone();
-            let pattern = format!("wallet{}:*", user_id);
-            let _ = invalidate_cache_pattern(&mut cache_clone, &pattern).await;
+    .fetch_optional(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to update wallet"))?
+    .ok_or_else(|| AppError::NotFound("Wallet not found".to_string()))?;
 
-            HttpResponse::Ok().json(ApiResponse::success(wallet))
-        }
-        Ok(None) => {
-            HttpResponse::NotFound()
-                .json(ApiResponse::<Wallet>::error("Wallet not found".to_string()))
-        }
-        Err(e) => {
-            log::error!("Failed to update wallet: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<Wallet>::error("Failed to update wallet".to_string()))
-        }
+    // A lower balance must still cover what is allocated to buckets
+    if req.balance.is_some() {
+        buckets::ensure_covered(&mut db_tx, wallet.id).await?;
+    }
+    if dry_run.0 {
+        let wallet_ids = [wallet.id];
+        return dry_run::preview(db.get_ref(), db_tx, &user_id, StatusCode::OK, Some(wallet), &wallet_ids).await;
     }
+    db_tx.commit().await.map_err(AppError::database("Failed to update wallet"))?;
+
+    // Invalidate every member's cache namespace
+    wallet_members::invalidate_members(db.get_ref(), cache.get_ref(), &user_id, &[access.wallet_id]).await;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(wallet)))
 }
 
-/// Delete a wallet
+/// Delete a wallet: soft-delete it with its transactions, or remove it with `?hard=true`
+///
+/// A soft-deleted wallet keeps its balance and disappears from every read
+/// until restored. Hard deletion also works on a soft-deleted wallet.
 pub async fn delete_wallet(
     path: web::Path<(String, String)>,
+    query: web::Query<DeleteQuery>,
+    dry_run: DryRun,
+    context: AuditContext,
     db: web::Data<PgPool>,
-    cache: web::Data<ConnectionManager>,
-) -> HttpResponse {
+    cache: web::Data<dyn CacheBackend>,
+    storage: web::Data<dyn StorageBackend>,
+) -> Result<HttpResponse, AppError> {
     let (user_id, wallet_id) = path.into_inner();
 
-    let delete_result = sqlx::query("DELETE FROM wallets WHERE id = $1 AND user_id = $2")
+    let mut db_tx = db.begin().await.map_err(AppError::database("Failed to delete wallet"))?;
+    audit::attach(&mut db_tx, &context).await?;
+
+    // Members are read before a hard delete takes them with the wallet
+    let access = wallet_members::authorize_wallet_access(&mut *db_tx, &user_id, &wallet_id, Permission::Manage).await?;
+    let members = wallet_members::members_of(&mut *db_tx, &[access.wallet_id])
+        .await
+        .map_err(AppError::database("Failed to delete wallet"))?;
+
+    if query.hard {
+        let result = sqlx::query("DELETE FROM wallets WHERE id::text = $1 AND user_id = $2")
+            .bind(&wallet_id)
+            .bind(&user_id)
+            .execute(&mut *db_tx)
+            .await
+            .map_err(AppError::database("Failed to delete wallet"))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Wallet not found".to_string()));
+        }
+    } else {
+        let deleted: Option<(Uuid, DateTime<Utc>)> = sqlx::query_as(
+            "UPDATE wallets SET deleted_at = CURRENT_TIMESTAMP
+             WHERE id::text = $1 AND user_id = $2 AND deleted_at IS NULL
+             RETURNING id, deleted_at",
+        )
         .bind(&wallet_id)
         .bind(&user_id)
-        .execute(db.get_ref())
-        .await;
-
-    match delete_result {
-        Ok(result) => {
-            if result.rows_affected() > 0 {
-                // Invalidate relevant caches
-                let mut cache_clone = cache.get_ref().clone();
-                let pattern = format!("wallet{}:*", user_id);
-                let _ = invalidate_cache_pattern(&mut cache_clone, &pattern).await;
-
-                HttpResponse::NoContent().finish()
-            } else {
-                HttpResponse::NotFound()
-                    .json(ApiResponse::<String>::error("Wallet not found".to_string()))
+        .fetch_optional(&mut *db_tx)
+        .await
+        .map_err(AppError::database("Failed to delete wallet"))?;
+        let (id, deleted_at) = deleted.ok_or_else(|| AppError::NotFound("Wallet not found".to_string()))?;
+
+        // The wallet's transactions go with it, stamped so a restore brings back
+        // these and not the ones deleted on their own
+        sqlx::query("UPDATE transactions SET deleted_at = $2 WHERE wallet_id = $1 AND deleted_at IS NULL")
+            .bind(id)
+            .bind(deleted_at)
+            .execute(&mut *db_tx)
+            .await
+            .map_err(AppError::database("Failed to delete wallet"))?;
+    }
+    if dry_run.0 {
+        let wallet_ids = [access.wallet_id];
+        return dry_run::preview::<()>(db.get_ref(), db_tx, &user_id, StatusCode::NO_CONTENT, None, &wallet_ids).await;
+    }
+    db_tx.commit().await.map_err(AppError::database("Failed to delete wallet"))?;
+    if query.hard {
+        // The wallet's transactions went with it; so do their receipts
+        attachments::delete_orphaned(db.get_ref(), storage.get_ref(), &user_id).await;
+    }
+
+    // Invalidate every member's cache namespace
+    wallet_members::invalidate_users(cache.get_ref(), &members).await;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Restore a soft-deleted wallet with the transactions deleted along with it
+///
+/// The balance was kept, so restoring changes no balance.
+pub async fn restore_wallet(
+    path: web::Path<(String, Uuid)>,
+    context: AuditContext,
+    db: web::Data<PgPool>,
+    cache: web::Data<dyn CacheBackend>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, wallet_id) = path.into_inner();
+
+    let mut db_tx = db.begin().await.map_err(AppError::database("Failed to restore wallet"))?;
+    audit::attach(&mut db_tx, &context).await?;
+    wallet_members::authorize_wallet_access(&mut *db_tx, &user_id, &wallet_id.to_string(), Permission::Manage).await?;
+
+    let deleted: Option<(Uuid, DateTime<Utc>)> = sqlx::query_as(
+        "SELECT id, deleted_at FROM wallets
+         WHERE id = $1 AND user_id = $2 AND deleted_at IS NOT NULL
+         FOR UPDATE",
+    )
+    .bind(wallet_id)
+    .bind(&user_id)
+    .fetch_optional(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to restore wallet"))?;
+    let (id, deleted_at) = deleted.ok_or_else(|| AppError::NotFound("Deleted wallet not found".to_string()))?;
+
+    sqlx::query("UPDATE transactions SET deleted_at = NULL WHERE wallet_id = $1 AND deleted_at = $2")
+        .bind(id)
+        .bind(deleted_at)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(AppError::database("Failed to restore wallet"))?;
+
+    let wallet = sqlx::query_as::<_, Wallet>(
+        "UPDATE wallets SET deleted_at = NULL, updated_at = CURRENT_TIMESTAMP WHERE id = $1
+         RETURNING id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, sandbox, created_at, updated_at, version",
+    )
+    .bind(id)
+    .fetch_one(&mut *db_tx)
+    .await
+    .map_err(AppError::database("Failed to restore wallet"))?;
+
+    db_tx.commit().await.map_err(AppError::database("Failed to restore wallet"))?;
+
+    // Invalidate every member's cache namespace
+    wallet_members::invalidate_members(db.get_ref(), cache.get_ref(), &user_id, &[wallet.id]).await;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(wallet)))
+}
+
+// ==================== Validation ====================
+
+/// `as_of` must not be in the future (the ledger can't tell what will happen)
+fn validate_as_of(as_of: DateTime<Utc>) -> Result<(), String> {
+    if as_of > Utc::now() {
+        return Err("as_of must not be in the future".to_string());
+    }
+    Ok(())
+}
+
+/// An APY needs a savings account (its range is checked with the request)
+fn validate_apy(wallet_type: &WalletType) -> Result<(), String> {
+    if *wallet_type != WalletType::BankAccount {
+        return Err("APY can only be set on BankAccount wallets".to_string());
+    }
+    Ok(())
+}
+
+/// A positive credit limit needs a template that supports one
+fn validate_credit_limit(limit: &BigDecimal, template: &WalletTemplate) -> Result<(), String> {
+    if *limit > BigDecimal::from(0) && !template.supports_credit_limit {
+        return Err("This wallet type does not support a credit limit".to_string());
+    }
+    Ok(())
+}
+
+/// Longest wallet name, in characters
+const MAX_NAME_CHARS: usize = 255;
+
+/// APY is a percentage
+fn check_apy(fields: &mut Fields, apy: Option<&BigDecimal>) {
+    if apy.is_some_and(|apy| *apy < BigDecimal::from(0) || *apy > BigDecimal::from(100)) {
+        fields.invalid("apy", "must be between 0 and 100");
+    }
+}
+
+impl Validate for CreateWalletRequest {
+    fn validate(&self, fields: &mut Fields) {
+        fields.length("name", &self.name, 1, MAX_NAME_CHARS);
+        match (&self.wallet_type, self.custom_type_id) {
+            (None, None) => fields.invalid("wallet_type", "wallet_type or custom_type_id is required"),
+            (Some(wallet_type), Some(_)) if *wallet_type != WalletType::Other => {
+                fields.invalid("custom_type_id", "Give either a built-in wallet_type or a custom_type_id")
             }
+            _ => {}
         }
-        Err(e) => {
-            log::error!("Failed to delete wallet: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<String>::error("Failed to delete wallet".to_string()))
-        }
+        fields.not_negative("credit_limit", self.credit_limit.as_ref());
+        check_apy(fields, self.apy.as_ref());
+    }
+}
+
+impl Validate for UpdateWalletRequest {
+    fn validate(&self, fields: &mut Fields) {
+        fields.optional_length("name", self.name.as_deref(), 1, MAX_NAME_CHARS);
+        fields.not_negative("credit_limit", self.credit_limit.as_ref());
+        check_apy(fields, self.apy.as_ref());
     }
 }
 
 // ==================== Database Functions ====================
 
-async fn fetch_wallets_from_db(pool: &PgPool, user_id: &str) -> Result<Vec<Wallet>, sqlx::Error> {
-    sqlx::query_as::<_, Wallet>(
-        "SELECT id, user_id, name, balance, credit_limit, wallet_type, created_at, updated_at FROM wallets WHERE user_id = $1 ORDER BY created_at DESC",
+pub(crate) async fn fetch_wallets_from_db(pool: &PgPool, user_id: &str) -> Result<Vec<Wallet>, sqlx::Error> {
+    telemetry::sql(
+        "wallets",
+        "select",
+        sqlx::query_as::<_, Wallet>(
+            "SELECT id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, sandbox, created_at, updated_at, version FROM wallets WHERE user_id = $1 AND deleted_at IS NULL ORDER BY created_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(pool),
+    )
+    .await
+}
+
+/// Wallets of the user and wallets shared with them, newest first
+async fn fetch_accessible_wallets(pool: &PgPool, user_id: &str) -> Result<Vec<Wallet>, sqlx::Error> {
+    telemetry::sql(
+        "wallets",
+        "select",
+        sqlx::query_as::<_, Wallet>(
+            "SELECT id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, sandbox, created_at, updated_at, version FROM wallets WHERE id IN (SELECT wallet_id FROM wallet_members WHERE user_id = $1) AND deleted_at IS NULL ORDER BY created_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(pool),
     )
-    .bind(user_id)
-    .fetch_all(pool)
     .await
 }
 
-async fn fetch_wallet_by_id(
+pub(crate) async fn fetch_wallet_by_id(
     pool: &PgPool,
     wallet_id: &str,
     user_id: &str,
 ) -> Result<Wallet, sqlx::Error> {
-    sqlx::query_as::<_, Wallet>(
-        "SELECT id, user_id, name, balance, credit_limit, wallet_type, created_at, updated_at FROM wallets WHERE id = $1 AND user_id = $2",
+    telemetry::sql_one(
+        "wallets",
+        "select",
+        sqlx::query_as::<_, Wallet>(
+            "SELECT id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, sandbox, created_at, updated_at, version FROM wallets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        )
+        .bind(wallet_id)
+        .bind(user_id)
+        .fetch_one(pool),
+    )
+    .await
+}
+
+/// A live wallet of the user or shared with them
+async fn fetch_accessible_wallet(pool: &PgPool, wallet_id: &str, user_id: &str) -> Result<Wallet, sqlx::Error> {
+    telemetry::sql_one(
+        "wallets",
+        "select",
+        sqlx::query_as::<_, Wallet>(
+            "SELECT id, user_id, name, balance, currency, credit_limit, wallet_type, custom_type_id, group_id, apy, last_interest_posted_at, sandbox, created_at, updated_at, version FROM wallets WHERE id::text = $1 AND has_wallet_role(id, $2, 'viewer') AND deleted_at IS NULL",
+        )
+        .bind(wallet_id)
+        .bind(user_id)
+        .fetch_one(pool),
     )
-    .bind(wallet_id)
-    .bind(user_id)
-    .fetch_one(pool)
     .await
 }
 
+/// Month-to-date stats as of now, or as of `as_of` rebuilt from history
+///
+/// Bucket allocations have no history, so a summary as of a past moment lists
+/// no buckets.
+async fn fetch_wallet_summary(
+    pool: &PgPool,
+    wallet_id: &str,
+    user_id: &str,
+    as_of: Option<DateTime<Utc>>,
+) -> Result<WalletSummary, sqlx::Error> {
+    let (wallet, now) = match as_of {
+        Some(as_of) => (history::wallet_as_of(pool, wallet_id, user_id, as_of).await?, as_of),
+        None => (fetch_accessible_wallet(pool, wallet_id, user_id).await?, Utc::now()),
+    };
+
+    let today = now.date_naive();
+    let month_start_date = today.with_day(1).unwrap_or(today);
+    let month_start = month_start_date.and_time(NaiveTime::MIN).and_utc();
+    let days_in_month = month_start_date
+        .checked_add_months(Months::new(1))
+        .map(|next| (next - month_start_date).num_days())
+        .unwrap_or(30);
+    let days_elapsed = i64::from(today.day());
+    let days_remaining = days_in_month - days_elapsed;
+
+    // A shared wallet's transactions carry its owner's user_id
+    let mtd = summary_snapshots::month_to_date(pool, wallet.id, &wallet.user_id, month_start, now).await?;
+
+    let last_transaction = sqlx::query_as::<_, Transaction>(
+        "SELECT id, sequence_number, user_id, wallet_id, bucket_id, income_source_id, amount, currency, transaction_type, category, description, notes, metadata, created_at, updated_at, version
+         FROM transactions WHERE wallet_id = $1 AND user_id = $2 AND deleted_at IS NULL AND created_at <= $3
+         ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(wallet.id)
+    .bind(&wallet.user_id)
+    .bind(now)
+    .fetch_optional(pool)
+    .await?;
+
+    let buckets = match as_of {
+        Some(_) => Vec::new(),
+        None => buckets::fetch_buckets(pool, wallet.id).await?,
+    };
+    let template = wallet_types::template_for(pool, &wallet).await?;
+
+    let average_daily_spend = (&mtd.expense / BigDecimal::from(days_elapsed)).round(2);
+    let projected_month_end_balance = &wallet.balance - money::prorate(&mtd.expense, days_remaining, days_elapsed);
+
+    Ok(WalletSummary {
+        available_balance: wallet.available_balance(&template),
+        wallet,
+        month_start,
+        mtd_income: mtd.income,
+        mtd_expense: mtd.expense,
+        mtd_transaction_count: mtd.transaction_count,
+        mtd_categories: mtd.categories,
+        average_daily_spend,
+        projected_month_end_balance,
+        last_transaction,
+        buckets,
+    })
+}
+
 // Update wallet balance (internal helper)
 pub async fn update_wallet_balance(
     pool: &PgPool,
@@ -219,13 +637,46 @@ pub async fn update_wallet_balance(
 
 // ==================== Route Configuration ====================
 
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/wallets")
+        .user(Method::GET, "/user/{user_id}", get_user_wallets)
+        .user(Method::GET, "/{user_id}/{wallet_id}", get_wallet)
+        .user(Method::GET, "/{user_id}/{wallet_id}/summary", get_wallet_summary)
+        .create("", create_wallet)
+        .dry_run()
+        .create("/transfer", transfers::transfer_between_wallets)
+        .dry_run()
+        .user(Method::PUT, "/{user_id}/{wallet_id}", update_wallet)
+        .dry_run()
+        .user(Method::DELETE, "/{user_id}/{wallet_id}", delete_wallet)
+        .dry_run()
+        .user(Method::POST, "/{user_id}/{wallet_id}/restore", restore_wallet)
+        .user(Method::GET, "/{user_id}/{wallet_id}/members", wallet_members::get_wallet_members)
+        .user(Method::POST, "/{user_id}/{wallet_id}/members", wallet_members::add_wallet_member)
+        .user(Method::PUT, "/{user_id}/{wallet_id}/members/{member_id}", wallet_members::update_wallet_member)
+        .user(Method::DELETE, "/{user_id}/{wallet_id}/members/{member_id}", wallet_members::remove_wallet_member)
+        .user(Method::GET, "/{user_id}/{wallet_id}/interest-projection", interest::get_interest_projection)
+        .user(Method::GET, "/{user_id}/{wallet_id}/statements", credit_cards::get_card_statements)
+        .user(Method::PUT, "/{user_id}/{wallet_id}/card-terms", credit_cards::set_card_terms)
+        .user(Method::DELETE, "/{user_id}/{wallet_id}/card-terms", credit_cards::delete_card_terms)
+        .user(Method::GET, "/{user_id}/{wallet_id}/explain", explain::explain_balance)
+        .user(Method::GET, "/{user_id}/{wallet_id}/history", balance_history::get_balance_history)
+        .user(Method::GET, "/{user_id}/{wallet_id}/opening-balance", opening_balances::get_opening_balances)
+        .user(Method::POST, "/{user_id}/{wallet_id}/opening-balance", opening_balances::set_opening_balance)
+        .dry_run()
+        .user(Method::GET, "/{user_id}/{wallet_id}/freeze", freezes::get_wallet_freeze)
+        .user(Method::GET, "/{user_id}/{wallet_id}/freeze/history", freezes::get_freeze_history)
+        .user(Method::POST, "/{user_id}/{wallet_id}/freeze", freezes::freeze_wallet)
+        .user(Method::POST, "/{user_id}/{wallet_id}/unfreeze", freezes::unfreeze_wallet)
+        .user(Method::GET, "/{user_id}/{wallet_id}/buckets", buckets::get_wallet_buckets)
+        .user(Method::POST, "/{user_id}/{wallet_id}/buckets", buckets::create_bucket)
+        .user(Method::POST, "/{user_id}/{wallet_id}/buckets/transfer", buckets::transfer_between_buckets)
+        .user(Method::PUT, "/{user_id}/{wallet_id}/buckets/{bucket_id}", buckets::update_bucket)
+        .user(Method::DELETE, "/{user_id}/{wallet_id}/buckets/{bucket_id}", buckets::delete_bucket)
+        .user(Method::POST, "/{user_id}/{wallet_id}/buckets/{bucket_id}/allocate", buckets::allocate_to_bucket)
+        .user(Method::POST, "/{user_id}/{wallet_id}/buckets/{bucket_id}/release", buckets::release_from_bucket)
+}
+
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(
-        web::scope("/api/wallets")
-            .route("/user/{user_id}", web::get().to(get_user_wallets))
-            .route("/{user_id}/{wallet_id}", web::get().to(get_wallet))
-            .route("", web::post().to(create_wallet))
-            .route("/{user_id}/{wallet_id}", web::put().to(update_wallet))
-            .route("/{user_id}/{wallet_id}", web::delete().to(delete_wallet)),
-    );
+    routes().register(cfg);
 }
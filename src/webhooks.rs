@@ -0,0 +1,536 @@
+use std::time::Duration;
+
+use actix_web::http::Method;
+use actix_web::{web, HttpResponse};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Url};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::limits;
+use crate::models::{
+    ApiResponse, CreateWebhookEndpointRequest, PageQuery, UpdateWebhookEndpointRequest, WebhookDelivery,
+    WebhookDeliveryQuery, WebhookEndpoint,
+};
+use crate::routes::ScopedRoutes;
+
+// ==================== WEBHOOKS ====================
+//
+// Users register endpoints that receive change events of their wallets,
+// transactions and debts, e.g. to sync a data warehouse:
+//
+// 1. Outbox: a database trigger writes every change to `event_outbox` in the
+//    same DB transaction as the change itself (the same rules as the audit
+//    log, see audit.rs), so an event exists exactly when its change
+//    committed, whatever the write path. Users without an active endpoint
+//    get no events.
+// 2. Fan-out: `WEBHOOK_JOB` queues each new event for the user's active
+//    endpoints subscribed to its type (`webhook_deliveries`).
+// 3. Delivery: the job POSTs the event as JSON, signed with the endpoint's
+//    secret in `X-KetoBook-Signature`. Anything but a 2xx answer is retried
+//    with exponential backoff (`RETRY_BASE` doubling up to `RETRY_MAX`) for
+//    `MAX_ATTEMPTS` attempts, after which the delivery is failed; it can be
+//    retried by hand from the delivery log.
+//
+// Delivery is at least once and not strictly ordered: receivers should
+// dedupe and order by the event `id`, which increases with every event.
+// Events and their deliveries are kept for `EVENT_RETENTION_DAYS`.
+//
+// Event types are `{wallet,transaction,debt}.{created,updated,deleted,restored}`
+// and `debt.paid` (a debt's status became `paid`). Setting `deleted_at` is a
+// delete. The event's `data` is the row after the change (before it, for
+// deletes).
+//
+// ============================================================================
+
+pub const WEBHOOK_JOB: &str = "webhooks";
+
+const EVENT_TYPES: &[&str] = &[
+    "wallet.created",
+    "wallet.updated",
+    "wallet.deleted",
+    "wallet.restored",
+    "transaction.created",
+    "transaction.updated",
+    "transaction.deleted",
+    "transaction.restored",
+    "debt.created",
+    "debt.updated",
+    "debt.deleted",
+    "debt.restored",
+    "debt.paid",
+];
+
+/// Header carrying `sha256=<hex HMAC-SHA256 of the body>`
+pub const SIGNATURE_HEADER: &str = "X-KetoBook-Signature";
+
+/// Maximum number of endpoints per user
+const MAX_ENDPOINTS_PER_USER: i64 = 10;
+
+const MAX_URL_LEN: usize = 500;
+
+/// Delivery attempts before a delivery is failed
+const MAX_ATTEMPTS: i16 = 12;
+
+/// Wait after the first failed attempt, doubled after each one
+const RETRY_BASE: Duration = Duration::from_secs(30);
+
+/// Longest wait between attempts
+const RETRY_MAX: Duration = Duration::from_secs(6 * 3600);
+
+/// How long a delivery has to be answered
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Events queued and deliveries attempted per batch
+const BATCH_SIZE: i64 = 200;
+
+const EVENT_RETENTION_DAYS: i32 = 30;
+
+const ENDPOINT_COLUMNS: &str = "id, user_id, url, secret, event_types, active, created_at, updated_at";
+
+const DELIVERY_COLUMNS: &str = "d.id, d.endpoint_id, d.event_id, o.event_type, o.entity_id, d.status, d.attempts,
+     d.next_attempt_at, d.response_status, d.last_error, d.created_at, d.delivered_at";
+
+type HmacSha256 = Hmac<Sha256>;
+
+// ==================== Handlers ====================
+
+/// Register an endpoint; its signing secret is generated
+pub async fn create_endpoint(
+    req: web::Json<CreateWebhookEndpointRequest>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let url = req.url.trim();
+    validate_url(url).map_err(AppError::Validation)?;
+    let event_types = normalize_event_types(req.event_types.as_deref()).map_err(AppError::Validation)?;
+
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM webhook_endpoints WHERE user_id = $1")
+        .bind(&req.user_id)
+        .fetch_one(db.get_ref())
+        .await
+        .map_err(AppError::database("Failed to create webhook endpoint"))?;
+    if count >= MAX_ENDPOINTS_PER_USER {
+        return Err(AppError::Validation(format!(
+            "A user can have at most {} webhook endpoints",
+            MAX_ENDPOINTS_PER_USER
+        )));
+    }
+
+    let endpoint = sqlx::query_as::<_, WebhookEndpoint>(&format!(
+        "INSERT INTO webhook_endpoints (user_id, url, secret, event_types)
+         VALUES ($1, $2, $3, $4)
+         RETURNING {}",
+        ENDPOINT_COLUMNS
+    ))
+    .bind(&req.user_id)
+    .bind(url)
+    .bind(generate_secret())
+    .bind(event_types)
+    .fetch_one(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to create webhook endpoint"))?;
+
+    Ok(HttpResponse::Created().json(ApiResponse::success(endpoint)))
+}
+
+/// A user's endpoints, oldest first
+pub async fn get_user_endpoints(user_id: web::Path<String>, db: web::Data<PgPool>) -> Result<HttpResponse, AppError> {
+    let endpoints = sqlx::query_as::<_, WebhookEndpoint>(&format!(
+        "SELECT {} FROM webhook_endpoints WHERE user_id = $1 ORDER BY created_at",
+        ENDPOINT_COLUMNS
+    ))
+    .bind(user_id.into_inner())
+    .fetch_all(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to fetch webhook endpoints"))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(endpoints)))
+}
+
+/// Change an endpoint's URL, event types or pause it (`active: false`)
+///
+/// Events raised while an endpoint is paused are not sent to it; deliveries
+/// already queued wait until it is resumed.
+pub async fn update_endpoint(
+    path: web::Path<(String, Uuid)>,
+    req: web::Json<UpdateWebhookEndpointRequest>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, endpoint_id) = path.into_inner();
+    let url = req.url.as_deref().map(str::trim);
+    if let Some(url) = url {
+        validate_url(url).map_err(AppError::Validation)?;
+    }
+    let event_types = match &req.event_types {
+        Some(types) => Some(normalize_event_types(Some(types)).map_err(AppError::Validation)?),
+        None => None,
+    };
+
+    let endpoint = sqlx::query_as::<_, WebhookEndpoint>(&format!(
+        "UPDATE webhook_endpoints
+         SET url = COALESCE($3, url),
+             event_types = CASE WHEN $4 THEN $5 ELSE event_types END,
+             active = COALESCE($6, active),
+             updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND user_id = $2
+         RETURNING {}",
+        ENDPOINT_COLUMNS
+    ))
+    .bind(endpoint_id)
+    .bind(&user_id)
+    .bind(url)
+    .bind(event_types.is_some())
+    .bind(event_types.flatten())
+    .bind(req.active)
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to update webhook endpoint"))?
+    .ok_or_else(|| AppError::NotFound("Webhook endpoint not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(endpoint)))
+}
+
+/// Replace an endpoint's signing secret; deliveries from now on use the new one
+pub async fn rotate_endpoint_secret(
+    path: web::Path<(String, Uuid)>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, endpoint_id) = path.into_inner();
+    let endpoint = sqlx::query_as::<_, WebhookEndpoint>(&format!(
+        "UPDATE webhook_endpoints SET secret = $3, updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND user_id = $2
+         RETURNING {}",
+        ENDPOINT_COLUMNS
+    ))
+    .bind(endpoint_id)
+    .bind(&user_id)
+    .bind(generate_secret())
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to update webhook endpoint"))?
+    .ok_or_else(|| AppError::NotFound("Webhook endpoint not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(endpoint)))
+}
+
+/// Remove an endpoint with its delivery log
+pub async fn delete_endpoint(path: web::Path<(String, Uuid)>, db: web::Data<PgPool>) -> Result<HttpResponse, AppError> {
+    let (user_id, endpoint_id) = path.into_inner();
+    let result = sqlx::query("DELETE FROM webhook_endpoints WHERE id = $1 AND user_id = $2")
+        .bind(endpoint_id)
+        .bind(&user_id)
+        .execute(db.get_ref())
+        .await
+        .map_err(AppError::database("Failed to delete webhook endpoint"))?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Webhook endpoint not found".to_string()));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// An endpoint's deliveries, newest event first
+///
+/// Supports `?page=&per_page=` and `?status=pending|sent|failed`.
+pub async fn get_endpoint_deliveries(
+    path: web::Path<(String, Uuid)>,
+    page: web::Query<PageQuery>,
+    query: web::Query<WebhookDeliveryQuery>,
+    db: web::Data<PgPool>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, endpoint_id) = path.into_inner();
+    let page = limits::page_params(&config, &page).map_err(AppError::Validation)?;
+    if let Some(status) = query.status.as_deref()
+        && !matches!(status, "pending" | "sent" | "failed")
+    {
+        return Err(AppError::Validation(
+            "Invalid status. Must be 'pending', 'sent' or 'failed'".to_string(),
+        ));
+    }
+
+    fetch_endpoint(db.get_ref(), &user_id, endpoint_id).await?;
+    let deliveries = sqlx::query_as::<_, WebhookDelivery>(&format!(
+        "SELECT {} FROM webhook_deliveries d
+         JOIN event_outbox o ON o.id = d.event_id
+         WHERE d.endpoint_id = $1 AND ($2::text IS NULL OR d.status = $2)
+         ORDER BY d.event_id DESC
+         LIMIT $3 OFFSET $4",
+        DELIVERY_COLUMNS
+    ))
+    .bind(endpoint_id)
+    .bind(query.status.as_deref())
+    .bind(page.limit())
+    .bind(page.offset())
+    .fetch_all(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to fetch webhook deliveries"))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(deliveries)))
+}
+
+/// Queue a failed delivery again, with a fresh set of attempts
+pub async fn retry_delivery(
+    path: web::Path<(String, Uuid, Uuid)>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, endpoint_id, delivery_id) = path.into_inner();
+    fetch_endpoint(db.get_ref(), &user_id, endpoint_id).await?;
+
+    let delivery = sqlx::query_as::<_, WebhookDelivery>(&format!(
+        "WITH retried AS (
+             UPDATE webhook_deliveries
+             SET status = 'pending', attempts = 0, next_attempt_at = CURRENT_TIMESTAMP
+             WHERE id = $1 AND endpoint_id = $2 AND status = 'failed'
+             RETURNING *
+         )
+         SELECT {} FROM retried d JOIN event_outbox o ON o.id = d.event_id",
+        DELIVERY_COLUMNS
+    ))
+    .bind(delivery_id)
+    .bind(endpoint_id)
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(AppError::database("Failed to retry webhook delivery"))?
+    .ok_or_else(|| AppError::NotFound("Failed webhook delivery not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(delivery)))
+}
+
+async fn fetch_endpoint(pool: &PgPool, user_id: &str, endpoint_id: Uuid) -> Result<WebhookEndpoint, AppError> {
+    sqlx::query_as::<_, WebhookEndpoint>(&format!(
+        "SELECT {} FROM webhook_endpoints WHERE id = $1 AND user_id = $2",
+        ENDPOINT_COLUMNS
+    ))
+    .bind(endpoint_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::or_not_found("Webhook endpoint not found"))
+}
+
+// ==================== Validation ====================
+
+/// Check that a webhook URL is an absolute http(s) URL of at most `MAX_URL_LEN`
+pub fn validate_url(url: &str) -> Result<(), String> {
+    if url.len() > MAX_URL_LEN {
+        return Err(format!("Webhook URL must be at most {} characters", MAX_URL_LEN));
+    }
+    let parsed = Url::parse(url).map_err(|_| "Webhook URL is not a valid URL".to_string())?;
+    if !matches!(parsed.scheme(), "https" | "http") || parsed.host_str().is_none() {
+        return Err("Webhook URL must be an http(s) URL".to_string());
+    }
+    Ok(())
+}
+
+/// Known, deduplicated event types; none or `[]` means all of them
+fn normalize_event_types(types: Option<&[String]>) -> Result<Option<Vec<String>>, String> {
+    let Some(types) = types.filter(|types| !types.is_empty()) else {
+        return Ok(None);
+    };
+    let mut normalized: Vec<String> = Vec::with_capacity(types.len());
+    for event_type in types {
+        let event_type = event_type.trim();
+        if !EVENT_TYPES.contains(&event_type) {
+            return Err(format!("Unknown event type '{}'", event_type));
+        }
+        if !normalized.iter().any(|t| t == event_type) {
+            normalized.push(event_type.to_string());
+        }
+    }
+    Ok(Some(normalized))
+}
+
+// ==================== Signing ====================
+
+/// A random signing secret (64 hex characters)
+pub fn generate_secret() -> String {
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+    hex::encode(secret)
+}
+
+/// The `SIGNATURE_HEADER` value of `body` under `secret`
+pub fn signature(secret: &str, body: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+// ==================== Webhook Job ====================
+
+/// A due delivery with its event and endpoint
+#[derive(sqlx::FromRow)]
+struct DueDelivery {
+    id: Uuid,
+    attempts: i16,
+    url: String,
+    secret: String,
+    event_id: i64,
+    event_type: String,
+    user_id: String,
+    data: Value,
+    created_at: DateTime<Utc>,
+}
+
+/// Queue new events, send due deliveries and drop expired events; returns
+/// the number of deliveries sent
+pub async fn run(pool: &PgPool, client: &Client) -> Result<u64, sqlx::Error> {
+    while queue_events(pool).await? == BATCH_SIZE as u64 {}
+    let sent = deliver_due(pool, client).await?;
+
+    // Deliveries go with their event
+    sqlx::query(
+        "DELETE FROM event_outbox o
+         WHERE o.created_at < CURRENT_TIMESTAMP - make_interval(days => $1)
+           AND o.dispatched_at IS NOT NULL
+           AND NOT EXISTS (SELECT 1 FROM webhook_deliveries d WHERE d.event_id = o.id AND d.status = 'pending')",
+    )
+    .bind(EVENT_RETENTION_DAYS)
+    .execute(pool)
+    .await?;
+
+    Ok(sent)
+}
+
+/// Client sending the deliveries
+pub fn client() -> Client {
+    Client::builder().timeout(DELIVERY_TIMEOUT).build().unwrap_or_default()
+}
+
+/// Queue a batch of new events for their endpoints; returns the number of events
+async fn queue_events(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "WITH batch AS (
+             SELECT id, user_id, event_type FROM event_outbox
+             WHERE dispatched_at IS NULL
+             ORDER BY id
+             LIMIT $1
+             FOR UPDATE SKIP LOCKED
+         ), queued AS (
+             INSERT INTO webhook_deliveries (endpoint_id, event_id, user_id)
+             SELECT e.id, b.id, b.user_id
+             FROM batch b
+             JOIN webhook_endpoints e ON e.user_id = b.user_id
+             WHERE e.active AND (e.event_types IS NULL OR b.event_type = ANY(e.event_types))
+             ON CONFLICT (endpoint_id, event_id) DO NOTHING
+         )
+         UPDATE event_outbox SET dispatched_at = CURRENT_TIMESTAMP WHERE id IN (SELECT id FROM batch)",
+    )
+    .bind(BATCH_SIZE)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+async fn deliver_due(pool: &PgPool, client: &Client) -> Result<u64, sqlx::Error> {
+    let due = sqlx::query_as::<_, DueDelivery>(
+        "SELECT d.id, d.attempts, e.url, e.secret, o.id AS event_id, o.event_type, o.user_id, o.data, o.created_at
+         FROM webhook_deliveries d
+         JOIN webhook_endpoints e ON e.id = d.endpoint_id
+         JOIN event_outbox o ON o.id = d.event_id
+         WHERE d.status = 'pending' AND d.next_attempt_at <= CURRENT_TIMESTAMP AND e.active
+         ORDER BY d.event_id
+         LIMIT $1",
+    )
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    let mut sent = 0;
+    for delivery in due {
+        let body = json!({
+            "id": delivery.event_id,
+            "type": delivery.event_type,
+            "user_id": delivery.user_id,
+            "created_at": delivery.created_at,
+            "data": delivery.data,
+        })
+        .to_string();
+
+        let response = client
+            .post(&delivery.url)
+            .header("Content-Type", "application/json")
+            .header("X-KetoBook-Event", &delivery.event_type)
+            .header("X-KetoBook-Delivery", delivery.id.to_string())
+            .header(SIGNATURE_HEADER, signature(&delivery.secret, &body))
+            .body(body)
+            .send()
+            .await;
+        let (response_status, error) = match response {
+            Ok(response) if response.status().is_success() => (Some(response.status().as_u16() as i16), None),
+            Ok(response) => (
+                Some(response.status().as_u16() as i16),
+                Some(format!("Endpoint answered {}", response.status())),
+            ),
+            Err(e) => (None, Some(e.to_string())),
+        };
+
+        let attempts = delivery.attempts + 1;
+        match error {
+            None => {
+                sent += 1;
+                sqlx::query(
+                    "UPDATE webhook_deliveries
+                     SET status = 'sent', attempts = $2, response_status = $3, last_error = NULL,
+                         delivered_at = CURRENT_TIMESTAMP
+                     WHERE id = $1",
+                )
+                .bind(delivery.id)
+                .bind(attempts)
+                .bind(response_status)
+                .execute(pool)
+                .await?;
+            }
+            Some(error) => {
+                let status = if attempts >= MAX_ATTEMPTS { "failed" } else { "pending" };
+                sqlx::query(
+                    "UPDATE webhook_deliveries
+                     SET status = $2, attempts = $3, response_status = $4, last_error = $5,
+                         next_attempt_at = CURRENT_TIMESTAMP + make_interval(secs => $6)
+                     WHERE id = $1",
+                )
+                .bind(delivery.id)
+                .bind(status)
+                .bind(attempts)
+                .bind(response_status)
+                .bind(&error)
+                .bind(retry_delay(attempts).as_secs_f64())
+                .execute(pool)
+                .await?;
+            }
+        }
+    }
+
+    Ok(sent)
+}
+
+/// Wait after the `attempts`-th failed attempt
+fn retry_delay(attempts: i16) -> Duration {
+    let doublings = u32::try_from(attempts - 1).unwrap_or(0).min(16);
+    (RETRY_BASE * 2u32.pow(doublings)).min(RETRY_MAX)
+}
+
+// ==================== Route Configuration ====================
+
+pub fn routes() -> ScopedRoutes {
+    ScopedRoutes::new("/api/webhooks")
+        .create("", create_endpoint)
+        .user(Method::GET, "/{user_id}", get_user_endpoints)
+        .user(Method::PUT, "/{user_id}/{endpoint_id}", update_endpoint)
+        .user(Method::DELETE, "/{user_id}/{endpoint_id}", delete_endpoint)
+        .user(Method::POST, "/{user_id}/{endpoint_id}/rotate-secret", rotate_endpoint_secret)
+        .user(Method::GET, "/{user_id}/{endpoint_id}/deliveries", get_endpoint_deliveries)
+        .user(Method::POST, "/{user_id}/{endpoint_id}/deliveries/{delivery_id}/retry", retry_delivery)
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    routes().register(cfg);
+}
@@ -199,4 +199,17 @@ $finalDebtResponse = Invoke-WebRequest "$apiBase/api/debts/user/$userId" -UseBas
 $finalDebtResponse.Content | Format-Json
 Write-Host ""
 
+# 16. Route Manifest Ownership Audit
+Write-Host "16. Auditing Route Manifest (every mutating route must be owned)" -ForegroundColor Yellow
+$manifest = (Invoke-WebRequest "$apiBase/api/routes" -UseBasicParsing).Content | ConvertFrom-Json
+$unowned = @($manifest.data | Where-Object { $_.mutating -and $_.ownership -eq "public" })
+if ($unowned.Count -eq 0) {
+    Write-Host "All mutating routes establish an owner" -ForegroundColor Green
+} else {
+    Write-Host "$($unowned.Count) mutating route(s) without ownership:" -ForegroundColor Red
+    $unowned | Format-Table method, path
+    exit 1
+}
+Write-Host ""
+
 Write-Host "=== Test Complete ===" -ForegroundColor Cyan
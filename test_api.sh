@@ -10,6 +10,7 @@ USER_ID="user_123"
 GREEN='\033[0;32m'
 BLUE='\033[0;34m'
 YELLOW='\033[1;33m'
+RED='\033[0;31m'
 NC='\033[0m' # No Color
 
 echo -e "${BLUE}=== KetoBook API Test Script ===${NC}\n"
@@ -142,4 +143,16 @@ echo -e "${YELLOW}15. Final State - Remaining Debts${NC}"
 curl -s "$API_BASE/api/debts/user/$USER_ID" | jq .
 echo -e "\n"
 
+# 16. Route Manifest Ownership Audit
+echo -e "${YELLOW}16. Auditing Route Manifest (every mutating route must be owned)${NC}"
+UNOWNED=$(curl -s "$API_BASE/api/routes" | jq '[.data[] | select(.mutating and .ownership == "public" and (.signed | not))] | length')
+if [ "$UNOWNED" = "0" ]; then
+  echo -e "${GREEN}All mutating routes establish an owner${NC}"
+else
+  echo -e "${RED}$UNOWNED mutating route(s) without ownership:${NC}"
+  curl -s "$API_BASE/api/routes" | jq '.data[] | select(.mutating and .ownership == "public" and (.signed | not))'
+  exit 1
+fi
+echo -e "\n"
+
 echo -e "${GREEN}=== Test Complete ===${NC}"